@@ -0,0 +1,107 @@
+// Save and load time and file size of a 50k object scene in each format.
+// Run with `cargo bench --bench scene_formats`
+use bevy::prelude::*;
+use navi::engine::objects::{MetaValue, ShapeType};
+use navi::engine::scene::{
+    BodyState, SCENE_FORMAT_VERSION, SceneFile, SceneFormat, SceneObject, SceneShape,
+};
+use navi::engine::world::WorldSettings;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::time::{Duration, Instant};
+
+const OBJECTS: u32 = 50_000;
+// Best of a few, so a slow first read of a cold file doesn't count
+const RUNS: usize = 3;
+
+fn large_scene() -> SceneFile {
+    let shapes = ShapeType::all();
+    let objects = (0..OBJECTS)
+        .map(|id| {
+            let shape_type = shapes[id as usize % shapes.len()];
+            let f = id as f32;
+            SceneObject {
+                id,
+                name: format!("{} {}", shape_type.display_name(), id),
+                shape_type: SceneShape::Known(shape_type),
+                dimensions: shape_type.default_dimensions(),
+                translation: Vec3::new(f * 0.37 % 200.0, f * 0.011 % 40.0, f * 0.73 % 200.0),
+                rotation: Quat::from_rotation_y(f * 0.1),
+                scale: Vec3::ONE,
+                color: Color::srgb(f * 0.13 % 1.0, f * 0.29 % 1.0, f * 0.71 % 1.0),
+                physics: default(),
+                tags: if id % 10 == 0 {
+                    BTreeSet::from(["tenth".to_string()])
+                } else {
+                    BTreeSet::new()
+                },
+                parent: (id % 50 != 0).then_some(id - id % 50),
+                locked: false,
+                physics_locked: false,
+                hidden: false,
+                collider_disabled: false,
+                prefab: None,
+                custom_mesh: None,
+                metadata: if id % 25 == 0 {
+                    BTreeMap::from([("score".to_string(), MetaValue::Number(f64::from(id)))])
+                } else {
+                    BTreeMap::new()
+                },
+                body_state: (id % 3 == 0).then_some(BodyState {
+                    linvel: Vec3::new(0.0, -f * 0.001 % 5.0, 0.0),
+                    angvel: Vec3::ZERO,
+                    sleeping: id % 2 == 0,
+                }),
+            }
+        })
+        .collect();
+    SceneFile {
+        version: SCENE_FORMAT_VERSION,
+        world: WorldSettings::default(),
+        objects,
+        joints: Vec::new(),
+    }
+}
+
+fn best(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let scene = large_scene();
+    let dir = std::env::temp_dir().join(format!("navi-scene-bench-{}", std::process::id()));
+    println!("{} objects, best of {} runs", OBJECTS, RUNS);
+    println!(
+        "{:>14} {:>10} {:>10} {:>12}",
+        "format", "save", "load", "size"
+    );
+    let mut ron_size = 0;
+    for format in SceneFormat::all() {
+        let path = dir.join(format!("scene.{}", format.extension()));
+        let save = best(|| scene.write(&path).unwrap());
+        let load = best(|| {
+            let read = SceneFile::read(&path).unwrap();
+            assert_eq!(read.objects.len(), OBJECTS as usize);
+        });
+        let size = fs::metadata(&path).unwrap().len();
+        if format == SceneFormat::Ron {
+            ron_size = size;
+        }
+        println!(
+            "{:>14} {:>10.1?} {:>10.1?} {:>9} KiB ({:.0}% of RON)",
+            format.name(),
+            save,
+            load,
+            size / 1024,
+            size as f64 * 100.0 / ron_size as f64
+        );
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
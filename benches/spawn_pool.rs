@@ -0,0 +1,102 @@
+// Spawning 1,000 objects a second, each living one second, with and without
+// the entity pool. Run with `cargo bench --bench spawn_pool`
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use bevy::prelude::*;
+use common::{headless_app, spawn_event};
+use navi::engine::objects::{DespawnEntityEvent, DespawnTarget, GameObjectManager, ShapeType};
+use navi::engine::pool::EntityPool;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const SPAWNS_PER_SECOND: usize = 1_000;
+const FRAMES_PER_SECOND: usize = 60;
+const LIFETIME_FRAMES: usize = FRAMES_PER_SECOND;
+// Long enough for the pool to fill and reach a steady state
+const FRAMES: usize = FRAMES_PER_SECOND * 10;
+
+struct Run {
+    frames: Vec<Duration>,
+    spawned: usize,
+}
+
+impl Run {
+    fn report(&mut self, label: &str) {
+        self.frames.sort_unstable();
+        let total: Duration = self.frames.iter().sum();
+        let percentile = |p: f64| self.frames[((self.frames.len() - 1) as f64 * p) as usize];
+        println!(
+            "{:>9}: {:>8.0} spawns/s of CPU time, frame mean {:?}, p50 {:?}, p99 {:?}, max {:?}",
+            label,
+            self.spawned as f64 / total.as_secs_f64(),
+            total / self.frames.len() as u32,
+            percentile(0.5),
+            percentile(0.99),
+            self.frames.last().unwrap(),
+        );
+    }
+}
+
+fn run(pooled: bool) -> Run {
+    let mut app = headless_app();
+    app.world_mut().resource_mut::<EntityPool>().enabled = pooled;
+    app.world_mut()
+        .resource_mut::<EntityPool>()
+        .set_size(ShapeType::Cube, SPAWNS_PER_SECOND);
+
+    let mut alive: VecDeque<(usize, Vec<u32>)> = VecDeque::new();
+    let mut frames = Vec::with_capacity(FRAMES);
+    let mut spawned = 0;
+    for frame in 0..FRAMES {
+        // Spread evenly, 16 or 17 a frame
+        let due = (frame + 1) * SPAWNS_PER_SECOND / FRAMES_PER_SECOND
+            - frame * SPAWNS_PER_SECOND / FRAMES_PER_SECOND;
+        for index in 0..due {
+            let x = (spawned + index) % 100;
+            app.world_mut().send_event(spawn_event(
+                ShapeType::Cube,
+                Vec3::new(x as f32, 1.0, (frame % 100) as f32),
+            ));
+        }
+        if alive
+            .front()
+            .is_some_and(|(born, _)| frame - born >= LIFETIME_FRAMES)
+        {
+            let (_, ids) = alive.pop_front().unwrap();
+            app.world_mut().send_event(DespawnEntityEvent {
+                target: DespawnTarget::Expired(ids),
+            });
+        }
+
+        let before: Vec<u32> = ids(&app);
+        let start = Instant::now();
+        app.update();
+        frames.push(start.elapsed());
+        spawned += due;
+
+        let born: Vec<u32> = ids(&app)
+            .into_iter()
+            .filter(|id| before.binary_search(id).is_err())
+            .collect();
+        alive.push_back((frame, born));
+    }
+    Run { frames, spawned }
+}
+
+fn ids(app: &App) -> Vec<u32> {
+    app.world()
+        .resource::<GameObjectManager>()
+        .iter()
+        .map(|obj| obj.id)
+        .collect()
+}
+
+fn main() {
+    println!(
+        "{} frames, {} cubes spawned a second, each despawned after a second",
+        FRAMES, SPAWNS_PER_SECOND
+    );
+    run(false).report("unpooled");
+    run(true).report("pooled");
+}
@@ -0,0 +1,84 @@
+// A plain Bevy app, no editor, showing a scene exported from Navi with
+// "Export Bevy scene...". Run with
+//   cargo run --example load_navi_scene -- path/to/scene.scn.ron
+use bevy::prelude::*;
+use bevy::scene::serde::SceneDeserializer;
+use bevy_rapier3d::prelude::*;
+use navi::engine::dynamic_scene::NaviScenePlugin;
+use serde::de::DeserializeSeed;
+use std::path::PathBuf;
+
+#[derive(Resource)]
+struct ScenePath(PathBuf);
+
+fn main() {
+    let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("usage: load_navi_scene <scene.scn.ron>");
+        std::process::exit(2);
+    };
+
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            RapierPhysicsPlugin::<NoUserData>::default(),
+            NaviScenePlugin,
+        ))
+        .insert_resource(ScenePath(path))
+        .add_systems(Startup, (setup_view_system, load_scene_system))
+        .run();
+}
+
+fn setup_view_system(mut commands: Commands) {
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 12.0, 24.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(4.0, 10.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+// Read through the type registry the same way Bevy's scene loader does, so
+// a file with a type the app hasn't registered fails here with its name.
+// The asset server only reads from the assets folder, which the exported
+// file usually isn't in
+fn load_scene_system(
+    mut commands: Commands,
+    path: Res<ScenePath>,
+    registry: Res<AppTypeRegistry>,
+    mut scenes: ResMut<Assets<DynamicScene>>,
+) {
+    let text = match std::fs::read_to_string(&path.0) {
+        Ok(text) => text,
+        Err(err) => {
+            error!("Cannot read {}: {}", path.0.display(), err);
+            return;
+        }
+    };
+    let mut deserializer = match ron::Deserializer::from_str(&text) {
+        Ok(deserializer) => deserializer,
+        Err(err) => {
+            error!("Cannot read {}: {}", path.0.display(), err);
+            return;
+        }
+    };
+    let scene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut deserializer);
+    match scene {
+        Ok(scene) => {
+            info!(
+                "Loaded {} objects from {}",
+                scene.entities.len(),
+                path.0.display()
+            );
+            commands.spawn(DynamicSceneRoot(scenes.add(scene)));
+        }
+        Err(err) => error!("Cannot load {}: {}", path.0.display(), err),
+    }
+}
@@ -0,0 +1,244 @@
+use crate::engine::core::UiSet;
+use crate::engine::gizmo::GizmoAxis;
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::{GameObjectManager, wake_body};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::selection::Selection;
+use bevy::prelude::*;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+
+// How far below an object drop to ground looks for something to land on
+const DROP_RAY_LENGTH: f32 = 1000.0;
+
+// Layout tools applied to every selected object as one undo step
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrangeEvent {
+    // Rest each object on whatever is below it
+    DropToGround,
+    // Match the first selected object's coordinate on the axis
+    Align(GizmoAxis),
+    // Even spacing between the outermost objects on the axis
+    Distribute(GizmoAxis),
+}
+
+impl ArrangeEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            ArrangeEvent::DropToGround => "Drop to ground",
+            ArrangeEvent::Align(_) => "Align",
+            ArrangeEvent::Distribute(_) => "Distribute",
+        }
+    }
+}
+
+fn axis_value(position: Vec3, axis: GizmoAxis) -> f32 {
+    position.dot(axis.direction())
+}
+
+fn with_axis_value(position: Vec3, axis: GizmoAxis, value: f32) -> Vec3 {
+    position + axis.direction() * (value - axis_value(position, axis))
+}
+
+pub fn arrange_system(
+    mut commands: Commands,
+    mut arrange_events: EventReader<ArrangeEvent>,
+    selection: Res<Selection>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut transforms: Query<&mut Transform>,
+    rapier_context: ReadRapierContext,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+) {
+    for event in arrange_events.read() {
+        // Selection order matters, align follows the first object picked
+        let targets: Vec<(u32, Entity, Transform)> = selection
+            .entities
+            .iter()
+            .filter_map(|&entity| {
+                let obj = game_manager.get_object_by_entity(entity)?;
+                let transform = transforms.get(entity).ok()?;
+                (!obj.locked).then_some((obj.id, entity, *transform))
+            })
+            .collect();
+        if targets.is_empty() {
+            notifications.warn(format!("{}: nothing selected", event.name()));
+            continue;
+        }
+
+        let mut moves = Vec::new();
+        match *event {
+            ArrangeEvent::DropToGround => {
+                let Ok(context) = rapier_context.single() else {
+                    continue;
+                };
+                for &(id, entity, before) in &targets {
+                    let Some(obj) = game_manager.get_object_by_entity(entity) else {
+                        continue;
+                    };
+                    let filter = QueryFilter::default().exclude_collider(entity);
+                    let Some((_, toi)) = context.cast_ray(
+                        before.translation,
+                        Vec3::NEG_Y,
+                        DROP_RAY_LENGTH,
+                        true,
+                        filter,
+                    ) else {
+                        continue;
+                    };
+                    // Bottom of the rotated collider, not just the unrotated half height
+                    let aabb = obj.aabb_at(&before);
+                    let bottom_offset = before.translation.y - aabb.min.y;
+                    let ground = before.translation.y - toi;
+                    let after = before.with_translation(Vec3::new(
+                        before.translation.x,
+                        ground + bottom_offset,
+                        before.translation.z,
+                    ));
+                    moves.push((id, before, after));
+                }
+                let missed = targets.len() - moves.len();
+                if missed > 0 {
+                    notifications.warn(format!("{} object(s) had nothing below them", missed));
+                }
+            }
+            ArrangeEvent::Align(axis) => {
+                let value = axis_value(targets[0].2.translation, axis);
+                for &(id, _, before) in &targets[1..] {
+                    let after =
+                        before.with_translation(with_axis_value(before.translation, axis, value));
+                    moves.push((id, before, after));
+                }
+            }
+            ArrangeEvent::Distribute(axis) => {
+                if targets.len() < 3 {
+                    notifications.warn("Distribute needs at least 3 selected objects");
+                    continue;
+                }
+                let mut sorted = targets.clone();
+                sorted.sort_by(|(_, _, a), (_, _, b)| {
+                    axis_value(a.translation, axis).total_cmp(&axis_value(b.translation, axis))
+                });
+                let start = axis_value(sorted[0].2.translation, axis);
+                let end = axis_value(sorted[sorted.len() - 1].2.translation, axis);
+                let step = (end - start) / (sorted.len() - 1) as f32;
+                for (i, &(id, _, before)) in sorted.iter().enumerate() {
+                    let value = start + step * i as f32;
+                    let after =
+                        before.with_translation(with_axis_value(before.translation, axis, value));
+                    moves.push((id, before, after));
+                }
+            }
+        }
+
+        moves.retain(|(_, before, after)| before != after);
+        if moves.is_empty() {
+            continue;
+        }
+        for &(id, _, after) in &moves {
+            let Some(entity) = game_manager.get_object_by_id(id).map(|obj| obj.entity()) else {
+                continue;
+            };
+            game_manager.set_position(entity, after.translation);
+            if let Ok(mut transform) = transforms.get_mut(entity) {
+                *transform = after;
+            }
+            wake_body(&mut commands, entity);
+        }
+        info!("{}: moved {} objects", event.name(), moves.len());
+        history.push(
+            EditorCommand::Arrange {
+                name: event.name(),
+                moves,
+            },
+            time.elapsed_secs_f64(),
+        );
+    }
+}
+
+pub fn arrange_panel_system(
+    mut contexts: EguiContexts,
+    selection: Res<Selection>,
+    mut arrange_events: EventWriter<ArrangeEvent>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Arrange",
+        egui::Window::new("Arrange").default_width(220.0),
+        |ui| {
+            ui.label(format!("{} selected", selection.len()));
+            ui.separator();
+
+            let any = !selection.is_empty();
+            if ui
+                .add_enabled(any, egui::Button::new("Drop to ground"))
+                .clicked()
+            {
+                arrange_events.write(ArrangeEvent::DropToGround);
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Align");
+                for axis in GizmoAxis::all() {
+                    let button = ui
+                        .add_enabled(selection.len() > 1, egui::Button::new(axis.label()))
+                        .on_hover_text(format!(
+                            "Match the first selected object's {}",
+                            axis.label()
+                        ));
+                    if button.clicked() {
+                        arrange_events.write(ArrangeEvent::Align(axis));
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Distribute");
+                for axis in GizmoAxis::all() {
+                    let button = ui
+                        .add_enabled(selection.len() > 2, egui::Button::new(axis.label()))
+                        .on_hover_text("Needs 3 or more objects");
+                    if button.clicked() {
+                        arrange_events.write(ArrangeEvent::Distribute(axis));
+                    }
+                }
+            });
+        },
+    );
+}
+
+pub fn register_arrange_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register_event("Drop selection to ground", None, ArrangeEvent::DropToGround);
+    for axis in GizmoAxis::all() {
+        registry.register_event(
+            format!("Align selection on {}", axis.label()),
+            None,
+            ArrangeEvent::Align(axis),
+        );
+        registry.register_event(
+            format!("Distribute selection along {}", axis.label()),
+            None,
+            ArrangeEvent::Distribute(axis),
+        );
+    }
+}
+
+// Align, distribute and drop to ground, from the panel and the palette
+pub struct ArrangePlugin;
+
+impl Plugin for ArrangePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ArrangeEvent>()
+            .add_systems(Startup, register_arrange_commands)
+            .add_systems(EguiContextPass, arrange_panel_system.in_set(UiSet::Panels));
+    }
+}
@@ -0,0 +1,413 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::oplog::OperationLog;
+use crate::engine::scene::{
+    LoadSceneEvent, SaveState, SceneCapture, SceneFile, SceneFileDialog, SceneFormat, SceneLoadMode,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on};
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const MIN_AUTOSAVE_INTERVAL_SECS: f32 = 10.0;
+pub const MAX_AUTOSAVE_INTERVAL_SECS: f32 = 600.0;
+pub const MAX_AUTOSAVE_COPIES: usize = 10;
+
+// Saved with the editor prefs
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    pub enabled: bool,
+    pub interval_secs: f32,
+    // Autosaves kept per scene, the newest plus numbered older ones
+    pub copies: usize,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 120.0,
+            copies: 3,
+        }
+    }
+}
+
+impl AutosaveSettings {
+    pub fn clamped(&self) -> Self {
+        let interval_secs = if self.interval_secs.is_finite() {
+            self.interval_secs
+                .clamp(MIN_AUTOSAVE_INTERVAL_SECS, MAX_AUTOSAVE_INTERVAL_SECS)
+        } else {
+            Self::default().interval_secs
+        };
+        Self {
+            interval_secs,
+            copies: self.copies.clamp(1, MAX_AUTOSAVE_COPIES),
+            ..*self
+        }
+    }
+}
+
+// scene.ron autosaves to scene.autosave.ron next to it, JSON scenes to
+// scene.autosave.json
+pub fn autosave_path(scene: &Path) -> PathBuf {
+    backup_path(scene, 0)
+}
+
+// Older autosaves are scene.autosave.1.ron, scene.autosave.2.ron, ...
+fn backup_path(scene: &Path, index: usize) -> PathBuf {
+    let stem = scene
+        .file_stem()
+        .map_or("scene".into(), |stem| stem.to_string_lossy());
+    let extension = SceneFormat::of(scene).extension();
+    let name = if index == 0 {
+        format!("{}.autosave.{}", stem, extension)
+    } else {
+        format!("{}.autosave.{}.{}", stem, index, extension)
+    };
+    scene.with_file_name(name)
+}
+
+// Shift every autosave one slot older, dropping the oldest, then write the new
+// one. Runs on the IO pool
+fn rotate_and_write(
+    scene: &SceneFile,
+    scene_path: &Path,
+    copies: usize,
+) -> Result<PathBuf, String> {
+    let oldest = backup_path(scene_path, copies.saturating_sub(1));
+    if oldest.exists() {
+        fs::remove_file(&oldest).map_err(|err| err.to_string())?;
+    }
+    for index in (0..copies.saturating_sub(1)).rev() {
+        let from = backup_path(scene_path, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(scene_path, index + 1)).map_err(|err| err.to_string())?;
+        }
+    }
+    let path = autosave_path(scene_path);
+    scene.write(&path)?;
+    Ok(path)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// An autosave left newer than its scene, usually by a crash
+#[derive(Debug, Clone)]
+pub struct AutosaveRecovery {
+    pub scene: PathBuf,
+    pub autosave: PathBuf,
+}
+
+#[derive(Resource, Default)]
+pub struct Autosave {
+    // Scene the previous session worked on, kept in the prefs file
+    pub last_scene: Option<PathBuf>,
+    pub recovery: Option<AutosaveRecovery>,
+    // Operation log revision and time of the last manual or automatic save
+    saved_revision: u64,
+    saved_at: f64,
+    // Set by a replacing load, the scene matches its file once the load's
+    // spawns and removals are in the operation log
+    clean_after_load: bool,
+    // Write in flight and the revision it captured
+    task: Option<(Task<Result<PathBuf, String>>, u64)>,
+}
+
+impl Autosave {
+    // Offers recovery when last session's scene has an autosave newer than itself
+    pub fn new(last_scene: Option<PathBuf>) -> Self {
+        let recovery = last_scene.as_deref().and_then(|scene| {
+            let autosave = autosave_path(scene);
+            let autosaved = modified(&autosave)?;
+            modified(scene)
+                .is_none_or(|saved| autosaved > saved)
+                .then(|| AutosaveRecovery {
+                    scene: scene.to_path_buf(),
+                    autosave,
+                })
+        });
+        Self {
+            last_scene,
+            recovery,
+            ..default()
+        }
+    }
+
+    pub fn mark_saved(&mut self, revision: u64, now: f64) {
+        self.saved_revision = revision;
+        self.saved_at = now;
+    }
+
+    pub fn mark_loaded(&mut self) {
+        self.clean_after_load = true;
+    }
+
+    // Whether anything changed since the last save or replacing load
+    pub fn is_dirty(&self, log: &OperationLog) -> bool {
+        log.revision() != self.saved_revision
+    }
+}
+
+// Whether the open scene has changes a new scene or load would throw away
+#[derive(SystemParam)]
+pub struct UnsavedChanges<'w> {
+    dialog: Res<'w, SceneFileDialog>,
+    autosave: Res<'w, Autosave>,
+    log: Res<'w, OperationLog>,
+}
+
+impl UnsavedChanges<'_> {
+    pub fn is_dirty(&self) -> bool {
+        self.autosave.is_dirty(&self.log)
+    }
+
+    // Where the open scene was loaded from or last saved to
+    pub fn scene(&self) -> Option<&Path> {
+        self.dialog.current.as_deref()
+    }
+}
+
+// Write the current scene's autosave every interval, only if the operation log
+// moved since the last save. The scene is captured here and written off-thread
+pub fn autosave_system(
+    settings: Res<AutosaveSettings>,
+    SaveState {
+        dialog,
+        mut autosave,
+    }: SaveState,
+    log: Res<OperationLog>,
+    capture: SceneCapture,
+    mut notifications: ResMut<EditorNotifications>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    if let Some((task, _)) = &autosave.task
+        && task.is_finished()
+        && let Some((task, revision)) = autosave.task.take()
+    {
+        match block_on(task) {
+            Ok(path) => {
+                info!("Autosaved to {}", path.display());
+                notifications.info("Autosaved");
+                autosave.mark_saved(revision, now);
+            }
+            Err(err) => {
+                warn!("Autosave failed: {}", err);
+                notifications.error(format!("Autosave failed: {}", err));
+                // Wait a full interval before trying again
+                autosave.saved_at = now;
+            }
+        }
+    }
+
+    if autosave.clean_after_load {
+        autosave.clean_after_load = false;
+        autosave.mark_saved(log.revision(), now);
+    }
+
+    let Some(scene_path) = dialog.current.clone() else {
+        return;
+    };
+    if !settings.enabled
+        || autosave.task.is_some()
+        || log.revision() == autosave.saved_revision
+        || now - autosave.saved_at < settings.interval_secs as f64
+    {
+        return;
+    }
+    autosave.last_scene = Some(scene_path.clone());
+
+    let scene = capture.capture(dialog.include_physics);
+    let copies = settings.copies.max(1);
+    let task =
+        IoTaskPool::get().spawn(async move { rotate_and_write(&scene, &scene_path, copies) });
+    autosave.task = Some((task, log.revision()));
+}
+
+pub fn autosave_recovery_window_system(
+    mut contexts: EguiContexts,
+    mut autosave: ResMut<Autosave>,
+    mut load_events: EventWriter<LoadSceneEvent>,
+) {
+    let Some(recovery) = autosave.recovery.clone() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let mut closed = false;
+    egui::Window::new("Recover autosave")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{} has an autosave newer than the last save",
+                recovery.scene.display()
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Recover").clicked() {
+                    load_events.write(LoadSceneEvent {
+                        path: recovery.autosave.clone(),
+                        mode: SceneLoadMode::Replace,
+                        save_to: Some(recovery.scene.clone()),
+                    });
+                    closed = true;
+                }
+                if ui
+                    .add_enabled(
+                        recovery.scene.exists(),
+                        egui::Button::new("Open saved scene"),
+                    )
+                    .clicked()
+                {
+                    load_events.write(LoadSceneEvent {
+                        path: recovery.scene.clone(),
+                        mode: SceneLoadMode::Replace,
+                        save_to: None,
+                    });
+                    closed = true;
+                }
+                if ui.button("Ignore").clicked() {
+                    closed = true;
+                }
+            });
+        });
+    if closed {
+        autosave.recovery = None;
+    }
+}
+
+// Autosave section of the Preferences panel
+pub fn autosave_settings_ui(ui: &mut egui::Ui, settings: &mut AutosaveSettings) {
+    ui.checkbox(&mut settings.enabled, "Autosave")
+        .on_hover_text("Only once the scene has been saved to or opened from a file");
+    ui.add_enabled(
+        settings.enabled,
+        egui::Slider::new(
+            &mut settings.interval_secs,
+            MIN_AUTOSAVE_INTERVAL_SECS..=MAX_AUTOSAVE_INTERVAL_SECS,
+        )
+        .suffix(" s")
+        .text("Interval"),
+    );
+    ui.add_enabled(
+        settings.enabled,
+        egui::Slider::new(&mut settings.copies, 1..=MAX_AUTOSAVE_COPIES).text("Copies kept"),
+    );
+}
+
+// Periodic autosaves and the recovery window shown when one is newer than its
+// scene
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiContextPass,
+            autosave_recovery_window_system.in_set(UiSet::Popups),
+        )
+        .add_systems(Update, autosave_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::SCENE_FORMAT_VERSION;
+    use crate::engine::world::WorldSettings;
+
+    // An empty scene told apart by its gravity
+    fn numbered_scene(number: usize) -> SceneFile {
+        SceneFile {
+            version: SCENE_FORMAT_VERSION,
+            world: WorldSettings {
+                gravity: Vec3::new(0.0, -(number as f32), 0.0),
+                ground: true,
+            },
+            objects: Vec::new(),
+            joints: Vec::new(),
+        }
+    }
+
+    // Autosave `writes` times, then the file names left and the scene number
+    // in each, newest first
+    fn autosave_repeatedly(
+        file_name: &str,
+        copies: usize,
+        writes: usize,
+    ) -> (Vec<String>, Vec<usize>) {
+        let dir = std::env::temp_dir().join(format!(
+            "navi-autosave-test-{}-{}-{}",
+            std::process::id(),
+            copies,
+            file_name
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let scene_path = dir.join(file_name);
+        for number in 1..=writes {
+            let written = rotate_and_write(&numbered_scene(number), &scene_path, copies).unwrap();
+            assert_eq!(written, autosave_path(&scene_path));
+        }
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let numbers = (0..names.len())
+            .map(|index| {
+                let scene = SceneFile::read(&backup_path(&scene_path, index)).unwrap();
+                -scene.world.gravity.y as usize
+            })
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        (names, numbers)
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_copies() {
+        let (names, numbers) = autosave_repeatedly("level.ron", 3, 5);
+        assert_eq!(
+            names,
+            [
+                "level.autosave.1.ron",
+                "level.autosave.2.ron",
+                "level.autosave.ron"
+            ]
+        );
+        assert_eq!(numbers, [5, 4, 3]);
+    }
+
+    #[test]
+    fn a_single_copy_is_overwritten_in_place() {
+        let (names, numbers) = autosave_repeatedly("level.ron", 1, 3);
+        assert_eq!(names, ["level.autosave.ron"]);
+        assert_eq!(numbers, [3]);
+    }
+
+    #[test]
+    fn autosaves_keep_the_scene_format() {
+        for extension in ["json", "nvscene"] {
+            let (names, numbers) = autosave_repeatedly(&format!("level.{}", extension), 2, 4);
+            assert_eq!(
+                names,
+                [
+                    format!("level.autosave.1.{}", extension),
+                    format!("level.autosave.{}", extension)
+                ]
+            );
+            assert_eq!(numbers, [4, 3]);
+        }
+    }
+}
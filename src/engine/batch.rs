@@ -0,0 +1,452 @@
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectId, ObjectSpawner, PhysicsProps, ShapeDimensions,
+    ShapeType, SharedMaterial, SpawnRules, SpawnSource, object_material,
+};
+use bevy::prelude::*;
+use bevy_egui::egui;
+use std::collections::HashSet;
+use std::f32::consts::PI;
+
+// Upper bound for a single batch, larger requests are clamped
+pub const MAX_BATCH_SPAWN: usize = 10_000;
+
+// Colours handed out in turn to batch spawned objects, one material each
+const BATCH_PALETTE: [Color; 8] = [
+    Color::srgb(0.9, 0.35, 0.3),
+    Color::srgb(0.95, 0.6, 0.25),
+    Color::srgb(0.95, 0.85, 0.3),
+    Color::srgb(0.45, 0.8, 0.35),
+    Color::srgb(0.3, 0.75, 0.75),
+    Color::srgb(0.3, 0.5, 0.9),
+    Color::srgb(0.6, 0.4, 0.9),
+    Color::srgb(0.9, 0.45, 0.7),
+];
+
+// Where a batch is spawned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpawnRegion {
+    Point(Vec3),
+    Box { center: Vec3, half_extents: Vec3 },
+    Sphere { center: Vec3, radius: f32 },
+}
+
+impl SpawnRegion {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpawnRegion::Point(_) => "Point",
+            SpawnRegion::Box { .. } => "Box",
+            SpawnRegion::Sphere { .. } => "Sphere",
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        match *self {
+            SpawnRegion::Point(center)
+            | SpawnRegion::Box { center, .. }
+            | SpawnRegion::Sphere { center, .. } => center,
+        }
+    }
+
+    // Box around the region, a point has no size
+    fn half_extents(&self) -> Vec3 {
+        match *self {
+            SpawnRegion::Point(_) => Vec3::ZERO,
+            SpawnRegion::Box { half_extents, .. } => half_extents.abs(),
+            SpawnRegion::Sphere { radius, .. } => Vec3::splat(radius.abs()),
+        }
+    }
+
+    fn contains(&self, position: Vec3) -> bool {
+        match *self {
+            SpawnRegion::Sphere { center, radius } => position.distance(center) <= radius.abs(),
+            _ => true,
+        }
+    }
+
+    // A uniformly random point inside the region
+    fn random_point(&self) -> Vec3 {
+        let half = self.half_extents();
+        loop {
+            let unit = Vec3::new(
+                rand::random::<f32>(),
+                rand::random::<f32>(),
+                rand::random::<f32>(),
+            ) * 2.0
+                - Vec3::ONE;
+            let position = self.center() + unit * half;
+            // Rejection sampling keeps the sphere uniform, about half the tries land
+            if self.contains(position) {
+                return position;
+            }
+        }
+    }
+
+    // Evenly spaced points filling the region, row by row from the bottom
+    fn grid_points(&self, count: usize) -> Vec<Vec3> {
+        let half = self.half_extents();
+        // A sphere only holds about half of its bounding grid
+        let fill = match self {
+            SpawnRegion::Sphere { .. } => PI / 6.0,
+            _ => 1.0,
+        };
+        let mut side = ((count as f32 / fill).cbrt().ceil() as usize).max(1);
+        loop {
+            let step = if side > 1 {
+                half * 2.0 / (side - 1) as f32
+            } else {
+                Vec3::ZERO
+            };
+            let origin = if side > 1 {
+                self.center() - half
+            } else {
+                self.center()
+            };
+            let mut points = Vec::with_capacity(count);
+            'fill: for y in 0..side {
+                for z in 0..side {
+                    for x in 0..side {
+                        let position = origin + step * Vec3::new(x as f32, y as f32, z as f32);
+                        if self.contains(position) {
+                            points.push(position);
+                            if points.len() == count {
+                                break 'fill;
+                            }
+                        }
+                    }
+                }
+            }
+            if points.len() == count {
+                return points;
+            }
+            side += 1;
+        }
+    }
+
+    pub fn positions(&self, count: usize, layout: SpawnLayout, jitter: f32) -> Vec<Vec3> {
+        let mut positions = match layout {
+            SpawnLayout::Random => (0..count).map(|_| self.random_point()).collect(),
+            SpawnLayout::Grid => self.grid_points(count),
+        };
+        if jitter > 0.0 {
+            for position in &mut positions {
+                *position += (Vec3::new(
+                    rand::random::<f32>(),
+                    rand::random::<f32>(),
+                    rand::random::<f32>(),
+                ) * 2.0
+                    - Vec3::ONE)
+                    * jitter;
+            }
+        }
+        positions
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpawnLayout {
+    #[default]
+    Random,
+    Grid,
+}
+
+impl SpawnLayout {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpawnLayout::Random => "Random",
+            SpawnLayout::Grid => "Grid",
+        }
+    }
+}
+
+// Spawn many objects of one shape as a single undo step
+#[derive(Event, Debug, Clone)]
+pub struct BatchSpawnEvent {
+    pub source: SpawnSource,
+    pub shape_type: ShapeType,
+    pub count: usize,
+    pub region: SpawnRegion,
+    // Numbered like duplicate names, None uses the shape's default names
+    pub base_name: Option<String>,
+    pub layout: SpawnLayout,
+    // Random offset added to every position, up to this far on each axis
+    pub jitter: f32,
+    pub params: Option<ShapeDimensions>,
+}
+
+// Every object in the batch shares one mesh, one collider shape and a palette
+// of materials, and is registered with the manager in one go. Per object
+// spawn commands and logging are what made large batches hitch
+pub fn batch_spawn_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut batch_events: EventReader<BatchSpawnEvent>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    SpawnRules { naming, limits, .. }: SpawnRules,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
+) {
+    let mut evicting = HashSet::new();
+    for event in batch_events.read() {
+        let count = event.count.min(MAX_BATCH_SPAWN);
+        if count == 0 {
+            continue;
+        }
+        if event.count > count {
+            notifications.warn(format!("Batch spawn is limited to {} objects", count));
+        }
+        // The whole batch fits or none of it is spawned
+        match limits.make_room(&game_manager, event.shape_type, count, &evicting) {
+            Ok(victims) if victims.is_empty() => {}
+            Ok(victims) => {
+                notifications.info(format!(
+                    "Evicting {} objects to make room for the batch",
+                    victims.len()
+                ));
+                evicting.extend(victims.iter().copied());
+                despawn_events.write(DespawnEntityEvent {
+                    target: DespawnTarget::Evicted(victims),
+                });
+            }
+            Err(err) => {
+                notifications.warn(format!("Batch of {} not spawned: {}", count, err));
+                continue;
+            }
+        }
+
+        let shape_type = event.shape_type;
+        let dimensions = event
+            .params
+            .unwrap_or_else(|| shape_type.default_dimensions())
+            .validated(shape_type);
+        let mesh = shape_type.create_mesh_with(&mut meshes, &dimensions);
+        let collider = shape_type.create_collider_with(&dimensions);
+        let palette: Vec<_> = BATCH_PALETTE
+            .iter()
+            .map(|&color| materials.add(object_material(color)))
+            .collect();
+
+        let names: Vec<Option<String>> = match event
+            .base_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            Some(name) if naming.unique_names => game_manager
+                .unique_names(name, count)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            name => vec![name.map(str::to_string); count],
+        };
+        let positions = event.region.positions(count, event.layout, event.jitter);
+        let entities: Vec<Entity> = (0..count).map(|_| commands.spawn_empty().id()).collect();
+
+        let now = time.elapsed_secs_f64();
+        let ids = game_manager.add_objects(
+            shape_type,
+            entities
+                .iter()
+                .zip(&positions)
+                .zip(names)
+                .map(|((&entity, &position), name)| (entity, position, name)),
+            now,
+        );
+        // Entities left over if the manager ran out of ids
+        for &entity in &entities[ids.len()..] {
+            commands.entity(entity).despawn();
+        }
+
+        let physics = PhysicsProps::default();
+        let mut bundles = Vec::with_capacity(count);
+        let mut snapshots = Vec::with_capacity(count);
+        for (i, &id) in ids.iter().enumerate() {
+            let Some(obj) = game_manager.get_object_by_id_mut(id) else {
+                continue;
+            };
+            obj.color = BATCH_PALETTE[i % BATCH_PALETTE.len()];
+            obj.dimensions = dimensions;
+            obj.physics = physics;
+            obj.source = event.source;
+            let transform = Transform::from_translation(obj.position());
+            bundles.push((
+                obj.entity(),
+                (
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(palette[i % palette.len()].clone()),
+                    SharedMaterial,
+                    transform,
+                    physics.rigid_body(false, false),
+                    collider.clone(),
+                    physics.components(),
+                    Visibility::Inherited,
+                    GameObjectId::from(&*obj),
+                ),
+            ));
+            snapshots.extend(game_manager.snapshot(id, transform));
+        }
+        commands.insert_batch(bundles);
+
+        if snapshots.len() < count {
+            notifications.warn(format!(
+                "Spawned {} of {} {} objects",
+                snapshots.len(),
+                count,
+                shape_type.display_name()
+            ));
+        } else {
+            notifications.info(format!(
+                "Spawned {} {} objects",
+                snapshots.len(),
+                shape_type.display_name()
+            ));
+        }
+        history.push(EditorCommand::SpawnBatch(snapshots), now);
+    }
+}
+
+// Batch spawn options in the Spawn panel
+#[derive(Resource, Debug, Clone)]
+pub struct BatchSpawnSettings {
+    pub count: usize,
+    pub region: SpawnRegion,
+    pub layout: SpawnLayout,
+    pub jitter: f32,
+    pub base_name: String,
+}
+
+impl Default for BatchSpawnSettings {
+    fn default() -> Self {
+        Self {
+            count: 100,
+            region: SpawnRegion::Box {
+                center: Vec3::new(0.0, 6.0, 0.0),
+                half_extents: Vec3::splat(5.0),
+            },
+            layout: SpawnLayout::Random,
+            jitter: 0.0,
+            base_name: String::new(),
+        }
+    }
+}
+
+impl BatchSpawnSettings {
+    pub fn event(&self, shape_type: ShapeType, params: ShapeDimensions) -> BatchSpawnEvent {
+        BatchSpawnEvent {
+            source: SpawnSource::ToolbarButton,
+            shape_type,
+            count: self.count,
+            region: self.region,
+            base_name: Some(self.base_name.clone()),
+            layout: self.layout,
+            jitter: self.jitter,
+            params: Some(params),
+        }
+    }
+}
+
+// Region, layout and count controls. Returns true when "Spawn N" was clicked
+pub fn batch_spawn_ui(ui: &mut egui::Ui, settings: &mut BatchSpawnSettings) -> bool {
+    ui.horizontal(|ui| {
+        ui.label("Count");
+        ui.add(egui::DragValue::new(&mut settings.count).range(1..=MAX_BATCH_SPAWN));
+    });
+
+    let center = settings.region.center();
+    let half_extents = settings.region.half_extents().max(Vec3::ONE);
+    let regions = [
+        SpawnRegion::Point(center),
+        SpawnRegion::Box {
+            center,
+            half_extents,
+        },
+        SpawnRegion::Sphere {
+            center,
+            radius: half_extents.max_element(),
+        },
+    ];
+    egui::ComboBox::from_label("Region")
+        .selected_text(settings.region.label())
+        .show_ui(ui, |ui| {
+            for region in regions {
+                if ui
+                    .selectable_label(settings.region.label() == region.label(), region.label())
+                    .clicked()
+                {
+                    settings.region = region;
+                }
+            }
+        });
+
+    let vec_ui = |ui: &mut egui::Ui, label: &str, value: &mut Vec3| {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            ui.add(egui::DragValue::new(&mut value.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut value.y).speed(0.1));
+            ui.add(egui::DragValue::new(&mut value.z).speed(0.1));
+        });
+    };
+    match &mut settings.region {
+        SpawnRegion::Point(center) => vec_ui(ui, "Center", center),
+        SpawnRegion::Box {
+            center,
+            half_extents,
+        } => {
+            vec_ui(ui, "Center", center);
+            vec_ui(ui, "Half size", half_extents);
+        }
+        SpawnRegion::Sphere { center, radius } => {
+            vec_ui(ui, "Center", center);
+            ui.horizontal(|ui| {
+                ui.label("Radius");
+                ui.add(
+                    egui::DragValue::new(radius)
+                        .speed(0.1)
+                        .range(0.0..=f32::MAX),
+                );
+            });
+        }
+    }
+
+    ui.horizontal(|ui| {
+        for layout in [SpawnLayout::Random, SpawnLayout::Grid] {
+            ui.radio_value(&mut settings.layout, layout, layout.label());
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Jitter");
+        ui.add(
+            egui::DragValue::new(&mut settings.jitter)
+                .speed(0.01)
+                .range(0.0..=f32::MAX),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.base_name)
+                .hint_text("Default names")
+                .desired_width(120.0),
+        );
+    });
+
+    ui.button(format!("Spawn {}", settings.count)).clicked()
+}
+
+// Settings for spawning many objects at once
+pub struct BatchSpawnPlugin;
+
+impl Plugin for BatchSpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BatchSpawnEvent>()
+            .init_resource::<BatchSpawnSettings>();
+    }
+}
@@ -0,0 +1,271 @@
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::serde_util;
+use crate::engine::toolbar::MainCamera;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+
+// Slot n is stored and recalled with digit key n, slot 10 uses 0
+pub const BOOKMARK_SLOTS: u8 = 10;
+// Seconds the camera takes to glide to a recalled bookmark
+const TRANSITION_SECS: f32 = 0.35;
+
+const SLOT_KEYS: [KeyCode; BOOKMARK_SLOTS as usize] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub slot: u8,
+    pub name: String,
+    #[serde(with = "serde_util::vec3")]
+    pub translation: Vec3,
+    #[serde(with = "serde_util::quat")]
+    pub rotation: Quat,
+}
+
+impl CameraBookmark {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.translation).with_rotation(self.rotation)
+    }
+}
+
+// Eased move from one camera transform to another
+#[derive(Debug, Clone, Copy)]
+struct CameraTransition {
+    from: Transform,
+    to: Transform,
+    elapsed: f32,
+}
+
+// Saved viewpoints, persisted in the editor prefs file
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraBookmarks {
+    bookmarks: Vec<CameraBookmark>,
+    #[serde(skip)]
+    transition: Option<CameraTransition>,
+}
+
+impl CameraBookmarks {
+    // Bookmarks ordered by slot
+    pub fn iter(&self) -> impl Iterator<Item = &CameraBookmark> {
+        self.bookmarks.iter()
+    }
+
+    pub fn get(&self, slot: u8) -> Option<&CameraBookmark> {
+        self.bookmarks.iter().find(|bookmark| bookmark.slot == slot)
+    }
+
+    // Store a view in a slot, keeping the existing name when overwriting
+    pub fn save(&mut self, slot: u8, transform: &Transform) {
+        if slot == 0 || slot > BOOKMARK_SLOTS {
+            return;
+        }
+        if let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.slot == slot) {
+            bookmark.translation = transform.translation;
+            bookmark.rotation = transform.rotation;
+            return;
+        }
+        self.bookmarks.push(CameraBookmark {
+            slot,
+            name: format!("View {}", slot),
+            translation: transform.translation,
+            rotation: transform.rotation,
+        });
+        self.bookmarks.sort_by_key(|bookmark| bookmark.slot);
+    }
+
+    pub fn first_free_slot(&self) -> Option<u8> {
+        (1..=BOOKMARK_SLOTS).find(|slot| self.get(*slot).is_none())
+    }
+
+    pub fn rename(&mut self, slot: u8, name: &str) {
+        if let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.slot == slot) {
+            bookmark.name = name.to_string();
+        }
+    }
+
+    pub fn remove(&mut self, slot: u8) {
+        self.bookmarks.retain(|bookmark| bookmark.slot != slot);
+    }
+
+    // Start gliding the camera from its current transform to the bookmark
+    pub fn recall(&mut self, slot: u8, current: &Transform) -> bool {
+        let Some(to) = self.get(slot).map(CameraBookmark::transform) else {
+            return false;
+        };
+        self.glide_to(current, to);
+        true
+    }
+
+    // Eased camera move that isn't tied to a bookmark slot
+    pub fn glide_to(&mut self, current: &Transform, to: Transform) {
+        self.transition = Some(CameraTransition {
+            from: *current,
+            to,
+            elapsed: 0.0,
+        });
+    }
+}
+
+// Ctrl+digit saves the current view, a plain digit recalls it. Ignored while
+// a text field has focus so typing numbers doesn't move the camera
+pub fn camera_bookmark_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    cameras: Query<&Transform, With<MainCamera>>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    let Some(index) = SLOT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+    else {
+        return;
+    };
+    let Ok(camera) = cameras.single() else {
+        return;
+    };
+
+    let slot = index as u8 + 1;
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        bookmarks.save(slot, camera);
+        notifications.info(format!("Saved camera bookmark {}", slot));
+    } else if !bookmarks.recall(slot, camera) {
+        notifications.info(format!("Camera bookmark {} is empty", slot));
+    }
+}
+
+pub fn camera_transition_system(
+    time: Res<Time>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    mut cameras: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Some(mut transition) = bookmarks.transition else {
+        return;
+    };
+    let Ok(mut camera) = cameras.single_mut() else {
+        return;
+    };
+
+    transition.elapsed += time.delta_secs();
+    let t = (transition.elapsed / TRANSITION_SECS).clamp(0.0, 1.0);
+    // Smoothstep so the move eases in and out
+    let eased = t * t * (3.0 - 2.0 * t);
+    camera.translation = transition
+        .from
+        .translation
+        .lerp(transition.to.translation, eased);
+    camera.rotation = transition
+        .from
+        .rotation
+        .slerp(transition.to.rotation, eased);
+
+    bookmarks.transition = (t < 1.0).then_some(transition);
+}
+
+pub fn bookmarks_panel_system(
+    mut contexts: EguiContexts,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    cameras: Query<&Transform, With<MainCamera>>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let camera = cameras.single().ok().copied().unwrap_or_default();
+
+    layout.show(
+        ctx,
+        "Bookmarks",
+        egui::Window::new("Bookmarks").default_width(260.0),
+        |ui| {
+            ui.label("Ctrl+1..0 saves the view, 1..0 recalls it");
+            ui.separator();
+
+            let mut rename: Option<(u8, String)> = None;
+            let mut recall = None;
+            let mut overwrite = None;
+            let mut remove = None;
+            for bookmark in bookmarks.iter() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}", bookmark.slot % BOOKMARK_SLOTS));
+                    let mut name = bookmark.name.clone();
+                    if ui
+                        .add(egui::TextEdit::singleline(&mut name).desired_width(110.0))
+                        .changed()
+                    {
+                        rename = Some((bookmark.slot, name));
+                    }
+                    if ui.small_button("Go").clicked() {
+                        recall = Some(bookmark.slot);
+                    }
+                    if ui
+                        .small_button("Set")
+                        .on_hover_text("Replace with the current view")
+                        .clicked()
+                    {
+                        overwrite = Some(bookmark.slot);
+                    }
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(bookmark.slot);
+                    }
+                });
+            }
+            if bookmarks.iter().next().is_none() {
+                ui.weak("No bookmarks yet");
+            }
+
+            if let Some((slot, name)) = rename {
+                bookmarks.rename(slot, &name);
+            }
+            if let Some(slot) = recall {
+                bookmarks.recall(slot, &camera);
+            }
+            if let Some(slot) = overwrite {
+                bookmarks.save(slot, &camera);
+            }
+            if let Some(slot) = remove {
+                bookmarks.remove(slot);
+            }
+
+            ui.separator();
+            let free = bookmarks.first_free_slot();
+            if ui
+                .add_enabled(free.is_some(), egui::Button::new("Bookmark current view"))
+                .clicked()
+                && let Some(slot) = free
+            {
+                bookmarks.save(slot, &camera);
+            }
+        },
+    );
+}
+
+// The Bookmarks panel. The bookmarks themselves are loaded with the prefs
+pub struct BookmarksPlugin;
+
+impl Plugin for BookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiContextPass,
+            bookmarks_panel_system.in_set(UiSet::Panels),
+        );
+    }
+}
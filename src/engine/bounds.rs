@@ -0,0 +1,141 @@
+use crate::engine::core::EditorSet;
+use crate::engine::objects::{GameObjectManager, SelectedShape, ShapeDimensions, ShapeType};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::selection::Selection;
+use bevy::math::bounding::BoundingVolume;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const DYNAMIC_COLOR: Color = Color::srgb(0.3, 0.9, 0.4);
+const FIXED_COLOR: Color = Color::srgb(1.0, 0.6, 0.2);
+// Bodies held by the transform gizmo while dragging
+const KINEMATIC_COLOR: Color = Color::srgb(0.4, 0.7, 1.0);
+// Boxes drawn for unselected objects are dimmed so the selection stands out
+const UNSELECTED_ALPHA: f32 = 0.35;
+
+// Debug outlines for object bounds, toggled from the View menu
+#[derive(Resource, Debug, Clone)]
+pub struct BoundsOverlay {
+    // Bounding box and collider of the selected objects
+    pub show_selected: bool,
+    // Bounding boxes of every object of the shape picked for spawning
+    pub show_shape_type: bool,
+}
+
+impl Default for BoundsOverlay {
+    fn default() -> Self {
+        Self {
+            show_selected: true,
+            show_shape_type: false,
+        }
+    }
+}
+
+fn body_color(body: Option<&RigidBody>) -> Color {
+    match body {
+        Some(RigidBody::Fixed) => FIXED_COLOR,
+        Some(RigidBody::KinematicPositionBased | RigidBody::KinematicVelocityBased) => {
+            KINEMATIC_COLOR
+        }
+        _ => DYNAMIC_COLOR,
+    }
+}
+
+// Wireframe of the collider as rapier sees it, scale included
+fn draw_collider_outline(
+    gizmos: &mut Gizmos,
+    shape_type: ShapeType,
+    dims: &ShapeDimensions,
+    transform: &Transform,
+    color: Color,
+) {
+    let isometry = Isometry3d::new(transform.translation, transform.rotation);
+    match shape_type {
+        ShapeType::Ball => {
+            gizmos.sphere(isometry, dims.radius, color);
+        }
+        ShapeType::Cube | ShapeType::CustomMesh => {
+            gizmos.primitive_3d(&Cuboid::from_size(dims.half_extents * 2.0), isometry, color);
+        }
+        ShapeType::Capsule => {
+            gizmos.primitive_3d(
+                &Capsule3d::new(dims.radius, dims.capsule_segment()),
+                isometry,
+                color,
+            );
+        }
+        ShapeType::Cylinder => {
+            gizmos.primitive_3d(&Cylinder::new(dims.radius, dims.height), isometry, color);
+        }
+        ShapeType::Cone => {
+            gizmos.primitive_3d(&Cone::new(dims.radius, dims.height), isometry, color);
+        }
+    }
+}
+
+pub fn draw_bounds_system(
+    mut gizmos: Gizmos,
+    overlay: Res<BoundsOverlay>,
+    selection: Res<Selection>,
+    selected_shape: Res<SelectedShape>,
+    game_manager: Res<GameObjectManager>,
+    objects: Query<(&GlobalTransform, Option<&RigidBody>)>,
+) {
+    if !overlay.show_selected && !overlay.show_shape_type {
+        return;
+    }
+
+    for obj in game_manager.iter() {
+        let selected = overlay.show_selected && selection.contains(obj.entity());
+        let of_shape =
+            overlay.show_shape_type && !obj.hidden && obj.shape_type == selected_shape.shape_type;
+        if !selected && !of_shape {
+            continue;
+        }
+        let Ok((global, body)) = objects.get(obj.entity()) else {
+            continue;
+        };
+        // World space, so children are boxed where they're drawn
+        let transform = &global.compute_transform();
+
+        let color = body_color(body);
+        let aabb = obj.aabb_at(transform);
+        let box_color = if selected {
+            color
+        } else {
+            color.with_alpha(UNSELECTED_ALPHA)
+        };
+        gizmos.cuboid(
+            Transform::from_translation(aabb.center().into())
+                .with_scale(Vec3::from(aabb.half_size()) * 2.0),
+            box_color,
+        );
+
+        if selected && !obj.collider_disabled {
+            let dims = obj.dimensions.scaled(obj.shape_type, transform.scale);
+            draw_collider_outline(&mut gizmos, obj.shape_type, &dims, transform, color);
+        }
+    }
+}
+
+pub fn register_bounds_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Toggle selection bounds", None, |world| {
+        let mut overlay = world.resource_mut::<BoundsOverlay>();
+        overlay.show_selected = !overlay.show_selected;
+    });
+    registry.register("Toggle shape type bounds", None, |world| {
+        let mut overlay = world.resource_mut::<BoundsOverlay>();
+        overlay.show_shape_type = !overlay.show_shape_type;
+    });
+}
+
+// Bounding boxes drawn around the selection and the spawn shape
+pub struct BoundsPlugin;
+
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoundsOverlay>()
+            .add_systems(Startup, register_bounds_commands)
+            .add_systems(Update, draw_bounds_system.in_set(EditorSet::Bookkeeping));
+    }
+}
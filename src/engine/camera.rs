@@ -0,0 +1,216 @@
+use crate::engine::bookmarks::CameraBookmarks;
+use crate::engine::core::UiSet;
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::{GameObjectManager, PlacedObjects};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::selection::Selection;
+use crate::engine::toolbar::MainCamera;
+use bevy::ecs::system::SystemState;
+use bevy::math::bounding::{Aabb3d, BoundingVolume};
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_FOV_DEGREES: f32 = 1.0;
+pub const MAX_FOV_DEGREES: f32 = 179.0;
+pub const MIN_NEAR_CLIP: f32 = 0.001;
+// Far plane is always kept at least this far beyond the near plane
+const MIN_CLIP_RANGE: f32 = 0.1;
+// Camera distance kept when focusing on an object
+const FOCUS_DISTANCE: f32 = 8.0;
+// Room left around the scene when framing it, as a fraction of its size
+const FRAME_MARGIN: f32 = 0.1;
+// Smallest radius framed, so a single small object isn't filled edge to edge
+const MIN_FRAME_RADIUS: f32 = 1.0;
+
+// Editor camera parameters, saved with the editor prefs
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    pub fov_degrees: f32,
+    pub near: f32,
+    pub far: f32,
+    // Units per second when flying the camera
+    pub move_speed: f32,
+    // Degrees per pixel of mouse movement when orbiting
+    pub orbit_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            fov_degrees: 45.0,
+            near: 0.1,
+            far: 1000.0,
+            move_speed: 10.0,
+            orbit_speed: 0.3,
+        }
+    }
+}
+
+impl CameraSettings {
+    // Keep the projection valid, values from a hand-edited prefs file included
+    pub fn clamped(&self) -> Self {
+        let defaults = Self::default();
+        let finite_or = |value: f32, fallback: f32| {
+            if value.is_finite() { value } else { fallback }
+        };
+
+        let near = finite_or(self.near, defaults.near).max(MIN_NEAR_CLIP);
+        Self {
+            fov_degrees: finite_or(self.fov_degrees, defaults.fov_degrees)
+                .clamp(MIN_FOV_DEGREES, MAX_FOV_DEGREES),
+            near,
+            far: finite_or(self.far, defaults.far).max(near + MIN_CLIP_RANGE),
+            move_speed: finite_or(self.move_speed, defaults.move_speed).max(0.0),
+            orbit_speed: finite_or(self.orbit_speed, defaults.orbit_speed).max(0.0),
+        }
+    }
+}
+
+// Push changed settings into the main camera's projection
+pub fn apply_camera_settings_system(
+    settings: Res<CameraSettings>,
+    mut cameras: Query<&mut Projection, With<MainCamera>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let settings = settings.clamped();
+    for mut projection in &mut cameras {
+        *projection = Projection::Perspective(PerspectiveProjection {
+            fov: settings.fov_degrees.to_radians(),
+            near: settings.near,
+            far: settings.far,
+            ..default()
+        });
+    }
+}
+
+pub fn camera_panel_system(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<CameraSettings>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Camera",
+        egui::Window::new("Camera").default_width(240.0),
+        |ui| {
+            // Edit a copy so the resource is only marked changed on real edits
+            let mut edited = settings.clone();
+
+            ui.add(
+                egui::Slider::new(&mut edited.fov_degrees, MIN_FOV_DEGREES..=MAX_FOV_DEGREES)
+                    .text("FOV"),
+            );
+            ui.horizontal(|ui| {
+                ui.label("Clip");
+                ui.add(
+                    egui::DragValue::new(&mut edited.near)
+                        .speed(0.01)
+                        .range(MIN_NEAR_CLIP..=f32::MAX)
+                        .prefix("near: "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut edited.far)
+                        .speed(1.0)
+                        .range(MIN_NEAR_CLIP..=f32::MAX)
+                        .prefix("far: "),
+                );
+            });
+            ui.add(egui::Slider::new(&mut edited.move_speed, 0.5..=100.0).text("Move speed"));
+            ui.add(egui::Slider::new(&mut edited.orbit_speed, 0.05..=2.0).text("Orbit speed"));
+
+            if ui.button("Reset").clicked() {
+                edited = CameraSettings::default();
+            }
+
+            let edited = edited.clamped();
+            if edited != *settings {
+                *settings = edited;
+            }
+        },
+    );
+}
+
+// Move the camera so the primary selection sits in the middle of the view
+pub fn focus_selection(world: &mut World) {
+    let Some(entity) = world.resource::<Selection>().primary() else {
+        return;
+    };
+    let Some(target) = world.get::<Transform>(entity).map(|t| t.translation) else {
+        return;
+    };
+    let mut cameras = world.query_filtered::<&mut Transform, With<MainCamera>>();
+    if let Ok(mut camera) = cameras.single_mut(world) {
+        let back = *camera.back();
+        *camera =
+            Transform::from_translation(target + back * FOCUS_DISTANCE).looking_at(target, Vec3::Y);
+    }
+}
+
+// Camera that sees all of bounds from the current viewing direction, with the
+// bounding sphere fitted to the vertical field of view
+pub fn frame_bounds(camera: &Transform, bounds: &Aabb3d, fov_degrees: f32) -> Transform {
+    let center = Vec3::from(bounds.center());
+    let radius =
+        (Vec3::from(bounds.half_size()).length() * (1.0 + FRAME_MARGIN)).max(MIN_FRAME_RADIUS);
+    let half_fov = (fov_degrees.to_radians() / 2.0).max(f32::EPSILON);
+    let distance = radius / half_fov.sin();
+    Transform::from_translation(center + *camera.back() * distance).looking_at(center, Vec3::Y)
+}
+
+// Glide the camera back far enough to see every object
+pub fn frame_all(world: &mut World) {
+    if world.resource::<GameObjectManager>().is_empty() {
+        info!("Nothing to frame, the scene is empty");
+        return;
+    }
+    let bounds = SystemState::<PlacedObjects>::new(world)
+        .get(world)
+        .scene_bounds();
+    let fov = world.resource::<CameraSettings>().fov_degrees;
+    let mut cameras = world.query_filtered::<&Transform, With<MainCamera>>();
+    let Ok(camera) = cameras.single(world).copied() else {
+        return;
+    };
+    world
+        .resource_mut::<CameraBookmarks>()
+        .glide_to(&camera, frame_bounds(&camera, &bounds, fov));
+}
+
+pub fn frame_all_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    mut commands: Commands,
+) {
+    if !egui_input.wants_keyboard_input()
+        && bindings.just_pressed(InputAction::FrameAll, &keyboard_input)
+    {
+        commands.queue(frame_all);
+    }
+}
+
+pub fn register_camera_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Focus selected", None, focus_selection);
+    registry.register("Frame all", None, frame_all);
+}
+
+// Camera panel and palette entries, the camera settings come from the prefs
+pub struct EditorCameraPlugin;
+
+impl Plugin for EditorCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, register_camera_commands)
+            .add_systems(EguiContextPass, camera_panel_system.in_set(UiSet::Panels));
+    }
+}
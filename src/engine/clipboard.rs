@@ -0,0 +1,337 @@
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::input::ViewportInput;
+use crate::engine::labels::CursorPick;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DUPLICATE_OFFSET, GameObjectManager, ObjectError, ObjectSpawner, SpawnSource, restore_object,
+};
+use crate::engine::scene::SceneObject;
+use crate::engine::selection::Selection;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+
+// Version of the clipboard snippet, bumped when it stops being readable
+const CLIPBOARD_FORMAT_VERSION: u32 = 1;
+// How far the cursor ray looks for a surface to paste onto
+const PASTE_RAY_LENGTH: f32 = 500.0;
+
+// What Ctrl+C puts on the system clipboard, as one line of RON. The named
+// version field keeps random text from parsing as objects
+#[derive(Serialize, Deserialize)]
+struct ClipboardObjects {
+    navi_objects: u32,
+    objects: Vec<SceneObject>,
+}
+
+// Paste objects from another editor or this one. They are placed around
+// `anchor` when set, otherwise next to where they were copied from
+#[derive(Event, Debug, Clone)]
+pub struct PasteObjectsEvent {
+    pub objects: Vec<SceneObject>,
+    pub anchor: Option<Vec3>,
+}
+
+// The selection and where its objects are in the world
+#[derive(SystemParam)]
+pub struct SelectedObjects<'w, 's> {
+    selection: Res<'w, Selection>,
+    game_manager: Res<'w, GameObjectManager>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+impl SelectedObjects<'_, '_> {
+    // Selected objects in world space. Pasted objects come back without parents
+    fn copy(&self) -> Vec<SceneObject> {
+        self.selection
+            .ids(&self.game_manager)
+            .into_iter()
+            .filter_map(|id| {
+                let obj = self.game_manager.get_object_by_id(id)?;
+                let transform = self
+                    .transforms
+                    .get(obj.entity())
+                    .map(|global| global.compute_transform())
+                    .unwrap_or_default();
+                let mut object = SceneObject::from(&self.game_manager.snapshot(id, transform)?);
+                object.parent = None;
+                Some(object)
+            })
+            .collect()
+    }
+}
+
+fn parse_clipboard(text: &str) -> Result<Vec<SceneObject>, String> {
+    let snippet: ClipboardObjects =
+        ron::from_str(text.trim()).map_err(|_| "the clipboard doesn't hold Navi objects")?;
+    if snippet.navi_objects > CLIPBOARD_FORMAT_VERSION {
+        return Err("the objects were copied from a newer Navi".to_string());
+    }
+    if snippet.objects.is_empty() {
+        return Err("the clipboard holds no objects".to_string());
+    }
+    Ok(snippet.objects)
+}
+
+// Ctrl+C copies the selection, Ctrl+V pastes at the cursor when it's over a
+// surface in the viewport. Ctrl+Shift+C/V stay with the property clipboard
+pub fn clipboard_input_system(
+    ViewportInput {
+        keyboard_input,
+        egui_input,
+        ..
+    }: ViewportInput,
+    mut clipboard: ResMut<EguiClipboard>,
+    selected: SelectedObjects,
+    picker: CursorPick,
+    mut paste_events: EventWriter<PasteObjectsEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        let objects = selected.copy();
+        if objects.is_empty() {
+            return;
+        }
+        let count = objects.len();
+        let snippet = ClipboardObjects {
+            navi_objects: CLIPBOARD_FORMAT_VERSION,
+            objects,
+        };
+        match ron::to_string(&snippet) {
+            Ok(text) => {
+                clipboard.set_text(&text);
+                notifications.info(format!("Copied {} objects", count));
+            }
+            Err(err) => notifications.error(format!("Cannot copy objects: {}", err)),
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyV) {
+        let Some(text) = clipboard.get_text() else {
+            notifications.warn("Cannot paste: the clipboard is empty");
+            return;
+        };
+        let objects = match parse_clipboard(&text) {
+            Ok(objects) => objects,
+            Err(err) => {
+                notifications.warn(format!("Cannot paste: {}", err));
+                return;
+            }
+        };
+        // Only a surface under the cursor counts, not empty sky or a panel
+        let anchor = (!egui_input.wants_any_pointer_input())
+            .then(|| {
+                let ray = picker.ray()?;
+                let (_, toi) = picker.cast(ray, PASTE_RAY_LENGTH)?;
+                Some(ray.get_point(toi))
+            })
+            .flatten();
+        paste_events.write(PasteObjectsEvent { objects, anchor });
+    }
+}
+
+// Spawn pasted objects with fresh ids and select them, like duplication
+pub fn paste_objects_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut paste_events: EventReader<PasteObjectsEvent>,
+    mut selection: ResMut<Selection>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+) {
+    for event in paste_events.read() {
+        // Move the group over the anchor with its lowest object half a unit above it
+        let offset = match event.anchor {
+            Some(anchor) => {
+                let center = event
+                    .objects
+                    .iter()
+                    .map(|object| object.translation)
+                    .sum::<Vec3>()
+                    / event.objects.len().max(1) as f32;
+                let lowest = event
+                    .objects
+                    .iter()
+                    .map(|object| object.translation.y)
+                    .fold(f32::INFINITY, f32::min);
+                anchor - Vec3::new(center.x, lowest, center.z) + Vec3::Y * 0.5
+            }
+            None => DUPLICATE_OFFSET,
+        };
+
+        let mut pasted = Vec::new();
+        let mut snapshots = Vec::new();
+        let mut failed = Vec::new();
+        for object in &event.objects {
+            let mut snapshot = match object.to_snapshot() {
+                Ok(snapshot) => snapshot,
+                Err(err) => {
+                    failed.push(err);
+                    continue;
+                }
+            };
+            let Some(id) = game_manager.ids().peek() else {
+                failed.push(ObjectError::IdsExhausted.to_string());
+                break;
+            };
+            snapshot.id = id;
+            snapshot.name = format!("{} (pasted)", snapshot.name);
+            snapshot.transform.translation += offset;
+            snapshot.parent = None;
+            snapshot.locked = false;
+            snapshot.physics_locked = false;
+            snapshot.source = SpawnSource::Duplicate;
+
+            match restore_object(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut game_manager,
+                &snapshot,
+                time.elapsed_secs_f64(),
+            ) {
+                Ok(entity) => {
+                    pasted.push(entity);
+                    snapshots.push(snapshot);
+                }
+                Err(err) => failed.push(format!("{}: {}", snapshot.name, err)),
+            }
+        }
+
+        // One paste is undone as a whole
+        if !snapshots.is_empty() {
+            history.push(
+                EditorCommand::SpawnBatch(snapshots),
+                time.elapsed_secs_f64(),
+            );
+        }
+        for err in &failed {
+            warn!("Cannot paste object: {}", err);
+        }
+        if !failed.is_empty() {
+            notifications.error(format!(
+                "Cannot paste {} objects: {}",
+                failed.len(),
+                failed.join(", ")
+            ));
+        }
+        if !pasted.is_empty() {
+            notifications.info(format!("Pasted {} objects", pasted.len()));
+            selection.select_range(pasted);
+        }
+    }
+}
+
+// Copy and paste of whole objects
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PasteObjectsEvent>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::custom_mesh::CustomMeshLibrary;
+    use crate::engine::fixtures::{object, sample_scene};
+    use crate::engine::history::EditorHistory;
+    use crate::engine::objects::ShapeType;
+
+    fn snippet(version: u32, objects: Vec<SceneObject>) -> String {
+        ron::to_string(&ClipboardObjects {
+            navi_objects: version,
+            objects,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn copied_objects_paste_back() {
+        let objects = sample_scene().objects;
+        let text = format!(
+            "\n  {}  \n",
+            snippet(CLIPBOARD_FORMAT_VERSION, objects.clone())
+        );
+        assert_eq!(parse_clipboard(&text), Ok(objects));
+    }
+
+    #[test]
+    fn other_text_is_not_pasted() {
+        for text in ["", "hello", "(objects: [])", "{\"navi_objects\": 1}"] {
+            assert_eq!(
+                parse_clipboard(text),
+                Err("the clipboard doesn't hold Navi objects".to_string()),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn an_empty_snippet_is_refused() {
+        assert_eq!(
+            parse_clipboard(&snippet(CLIPBOARD_FORMAT_VERSION, Vec::new())),
+            Err("the clipboard holds no objects".to_string())
+        );
+    }
+
+    #[test]
+    fn objects_from_a_newer_navi_are_refused() {
+        let text = snippet(CLIPBOARD_FORMAT_VERSION + 1, sample_scene().objects);
+        assert_eq!(
+            parse_clipboard(&text),
+            Err("the objects were copied from a newer Navi".to_string())
+        );
+    }
+
+    #[test]
+    fn a_paste_is_undone_in_one_step() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .add_event::<PasteObjectsEvent>()
+            .init_resource::<GameObjectManager>()
+            .init_resource::<CustomMeshLibrary>()
+            .init_resource::<Selection>()
+            .init_resource::<EditorHistory>()
+            .init_resource::<EditorNotifications>()
+            .add_systems(Update, paste_objects_system);
+        let objects = ShapeType::all()
+            .into_iter()
+            .take(3)
+            .enumerate()
+            .map(|(id, shape_type)| object(id as u32, shape_type))
+            .collect();
+        app.world_mut().send_event(PasteObjectsEvent {
+            objects,
+            anchor: None,
+        });
+        app.update();
+
+        assert_eq!(app.world().resource::<GameObjectManager>().len(), 3);
+        assert_eq!(app.world().resource::<Selection>().len(), 3);
+        assert_eq!(
+            app.world().resource::<EditorHistory>().undo_description(),
+            Some("Spawn 3 objects".to_string())
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::{GameObjectId, GameObjectManager};
+use crate::engine::selection::Selection;
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, Enum, ReflectMut, TypeInfo, VariantInfo};
+use bevy::window::PrimaryWindow;
+use bevy_egui::*;
+use std::any::TypeId;
+
+// Nested structs deeper than this are shown as text
+const MAX_DEPTH: usize = 6;
+
+// Last path segment of a type name, keeping any generics
+fn short_name(full: &str) -> &str {
+    let path_end = full.find('<').unwrap_or(full.len());
+    let start = full[..path_end].rfind("::").map_or(0, |i| i + 2);
+    &full[start..]
+}
+
+// Generic view of every component on the primary selection. Reflected
+// components get editable fields, the rest are listed by name
+pub fn component_inspector_system(world: &mut World) {
+    let Ok(mut egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .single_mut(world)
+    else {
+        return;
+    };
+    let ctx = egui_context.get_mut().clone();
+
+    let entity = world.resource::<Selection>().primary();
+    let locked = entity
+        .and_then(|entity| {
+            world
+                .resource::<GameObjectManager>()
+                .get_object_by_entity(entity)
+        })
+        .is_some_and(|obj| obj.locked);
+
+    world.resource_scope(|world, mut layout: Mut<EditorLayout>| {
+        layout.show(
+            &ctx,
+            "Components",
+            egui::Window::new("Components")
+                .default_width(300.0)
+                .default_height(400.0),
+            |ui| {
+                let Some(entity) = entity else {
+                    ui.label("No object selected");
+                    return;
+                };
+                if locked {
+                    ui.label("🔒 Locked, fields are read-only");
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    components_ui(ui, world, entity, !locked);
+                });
+            },
+        );
+    });
+}
+
+fn components_ui(ui: &mut egui::Ui, world: &mut World, entity: Entity, editable: bool) {
+    let Ok(infos) = world.inspect_entity(entity) else {
+        ui.label("Selected object no longer exists");
+        return;
+    };
+    let mut components: Vec<(String, Option<TypeId>)> = infos
+        .map(|info| (info.name().to_string(), info.type_id()))
+        .collect();
+    components.sort_by(|(a, _), (b, _)| short_name(a).cmp(short_name(b)));
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let mut unreflected = Vec::new();
+
+    for (name, type_id) in components {
+        let Some(reflect_component) = type_id
+            .and_then(|id| registry.get(id))
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        else {
+            unreflected.push(name);
+            continue;
+        };
+        let Some(current) = reflect_component.reflect(world.entity(entity)) else {
+            continue;
+        };
+        // Edit a copy and apply it back, so the component is only marked
+        // changed when a field actually is
+        let Ok(mut value) = current.reflect_clone() else {
+            unreflected.push(name);
+            continue;
+        };
+        // The id component mirrors the GameObjectManager, rename from the inspector instead
+        let field_editable = editable && type_id != Some(TypeId::of::<GameObjectId>());
+
+        let changed = egui::CollapsingHeader::new(short_name(&name))
+            .id_salt(("component", &name))
+            .show(ui, |ui| {
+                ui.add_enabled_ui(field_editable, |ui| {
+                    reflect_ui(ui, value.as_partial_reflect_mut(), 0)
+                })
+                .inner
+            })
+            .body_returned
+            .unwrap_or(false);
+        if changed {
+            reflect_component.apply(world.entity_mut(entity), value.as_partial_reflect());
+        }
+    }
+
+    if !unreflected.is_empty() {
+        ui.separator();
+        ui.label("Not reflected");
+        for name in unreflected {
+            ui.weak(short_name(&name)).on_hover_text(&name);
+        }
+    }
+}
+
+// Widgets for the value types we know how to edit directly, None for anything else
+fn leaf_ui(ui: &mut egui::Ui, value: &mut dyn PartialReflect) -> Option<bool> {
+    if let Some(v) = value.try_downcast_mut::<f32>() {
+        return Some(ui.add(egui::DragValue::new(v).speed(0.01)).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<f64>() {
+        return Some(ui.add(egui::DragValue::new(v).speed(0.01)).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<u32>() {
+        return Some(ui.add(egui::DragValue::new(v)).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<i32>() {
+        return Some(ui.add(egui::DragValue::new(v)).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<usize>() {
+        return Some(ui.add(egui::DragValue::new(v)).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<bool>() {
+        return Some(ui.checkbox(v, "").changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<String>() {
+        return Some(ui.text_edit_singleline(v).changed());
+    }
+    if let Some(v) = value.try_downcast_mut::<Vec3>() {
+        let mut changed = false;
+        for axis in [&mut v.x, &mut v.y, &mut v.z] {
+            changed |= ui.add(egui::DragValue::new(axis).speed(0.01)).changed();
+        }
+        return Some(changed);
+    }
+    if let Some(v) = value.try_downcast_mut::<Quat>() {
+        let (x, y, z) = v.to_euler(EulerRot::XYZ);
+        let mut degrees = [x.to_degrees(), y.to_degrees(), z.to_degrees()];
+        let mut changed = false;
+        for angle in degrees.iter_mut() {
+            changed |= ui
+                .add(egui::DragValue::new(angle).speed(1.0).suffix("°"))
+                .changed();
+        }
+        if changed {
+            *v = Quat::from_euler(
+                EulerRot::XYZ,
+                degrees[0].to_radians(),
+                degrees[1].to_radians(),
+                degrees[2].to_radians(),
+            );
+        }
+        return Some(changed);
+    }
+    if let Some(v) = value.try_downcast_mut::<Color>() {
+        let mut rgba = v.to_srgba().to_f32_array();
+        let changed = ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed();
+        if changed {
+            *v = Color::srgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+        }
+        return Some(changed);
+    }
+    if let Some(v) = value.try_downcast_ref::<Entity>() {
+        ui.label(format!("{}", v));
+        return Some(false);
+    }
+    None
+}
+
+// Types drawn inline by leaf_ui
+fn is_leaf(value: &dyn PartialReflect) -> bool {
+    let Some(value) = value.try_as_reflect() else {
+        return false;
+    };
+    let type_id = value.as_any().type_id();
+    [
+        TypeId::of::<f32>(),
+        TypeId::of::<f64>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<usize>(),
+        TypeId::of::<bool>(),
+        TypeId::of::<String>(),
+        TypeId::of::<Vec3>(),
+        TypeId::of::<Quat>(),
+        TypeId::of::<Color>(),
+        TypeId::of::<Entity>(),
+    ]
+    .contains(&type_id)
+}
+
+// Labelled row for a simple field, collapsible section for a compound one
+fn field_ui(ui: &mut egui::Ui, label: &str, value: &mut dyn PartialReflect, depth: usize) -> bool {
+    if is_leaf(value) {
+        return ui
+            .horizontal(|ui| {
+                ui.label(label);
+                leaf_ui(ui, value).unwrap_or(false)
+            })
+            .inner;
+    }
+    egui::CollapsingHeader::new(label)
+        .id_salt((label, depth))
+        .show(ui, |ui| reflect_ui(ui, value, depth + 1))
+        .body_returned
+        .unwrap_or(false)
+}
+
+// Walk a reflected value and draw widgets for its fields, returns true if any were edited
+fn reflect_ui(ui: &mut egui::Ui, value: &mut dyn PartialReflect, depth: usize) -> bool {
+    if let Some(changed) = leaf_ui(ui, value) {
+        return changed;
+    }
+    if depth > MAX_DEPTH {
+        ui.weak(format!("{:?}", value));
+        return false;
+    }
+
+    let mut changed = false;
+    match value.reflect_mut() {
+        ReflectMut::Struct(s) => {
+            for i in 0..s.field_len() {
+                let name = s.name_at(i).unwrap_or_default().to_string();
+                if let Some(field) = s.field_at_mut(i) {
+                    changed |= field_ui(ui, &name, field, depth);
+                }
+            }
+        }
+        ReflectMut::TupleStruct(t) => {
+            for i in 0..t.field_len() {
+                if let Some(field) = t.field_mut(i) {
+                    changed |= field_ui(ui, &i.to_string(), field, depth);
+                }
+            }
+        }
+        ReflectMut::Tuple(t) => {
+            for i in 0..t.field_len() {
+                if let Some(field) = t.field_mut(i) {
+                    changed |= field_ui(ui, &i.to_string(), field, depth);
+                }
+            }
+        }
+        ReflectMut::List(list) => {
+            for i in 0..list.len() {
+                if let Some(item) = list.get_mut(i) {
+                    changed |= field_ui(ui, &format!("[{}]", i), item, depth);
+                }
+            }
+        }
+        ReflectMut::Array(array) => {
+            for i in 0..array.len() {
+                if let Some(item) = array.get_mut(i) {
+                    changed |= field_ui(ui, &format!("[{}]", i), item, depth);
+                }
+            }
+        }
+        ReflectMut::Enum(e) => changed |= enum_ui(ui, e, depth),
+        _ => {
+            ui.weak(format!("{:?}", value));
+        }
+    }
+    changed
+}
+
+// Variant picker plus the fields of the current variant. Only unit variants can
+// be switched to, the others would need values for their fields
+fn enum_ui(ui: &mut egui::Ui, e: &mut dyn Enum, depth: usize) -> bool {
+    let mut changed = false;
+    let current = e.variant_name().to_string();
+    if let Some(TypeInfo::Enum(info)) = e.get_represented_type_info() {
+        let mut picked = None;
+        egui::ComboBox::from_id_salt(("enum", info.type_path(), depth))
+            .selected_text(&current)
+            .show_ui(ui, |ui| {
+                for variant in info.iter() {
+                    let unit = matches!(variant, VariantInfo::Unit(_));
+                    let selected = variant.name() == current;
+                    if ui
+                        .add_enabled(unit, egui::SelectableLabel::new(selected, variant.name()))
+                        .clicked()
+                        && !selected
+                    {
+                        picked = Some(variant.name());
+                    }
+                }
+            });
+        if let Some(name) = picked {
+            e.apply(&DynamicEnum::new(name, DynamicVariant::Unit));
+            return true;
+        }
+    } else {
+        ui.label(&current);
+    }
+
+    for i in 0..e.field_len() {
+        let name = e
+            .name_at(i)
+            .map_or_else(|| i.to_string(), |name| name.to_string());
+        if let Some(field) = e.field_at_mut(i) {
+            changed |= field_ui(ui, &name, field, depth);
+        }
+    }
+    changed
+}
+
+// The reflection-based Components panel
+pub struct ComponentsPlugin;
+
+impl Plugin for ComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiContextPass,
+            component_inspector_system.in_set(UiSet::Panels),
+        );
+    }
+}
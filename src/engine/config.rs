@@ -0,0 +1,405 @@
+use crate::engine::notify::EditorNotifications;
+use crate::engine::world::EARTH_GRAVITY;
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PresentMode, WindowMode, WindowResolution};
+use bevy_rapier3d::prelude::TimestepMode;
+use directories::ProjectDirs;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+pub const CONFIG_FILE_NAME: &str = "navi.toml";
+
+const MIN_WINDOW_SIZE: f32 = 320.0;
+const MSAA_SAMPLES: [u32; 4] = [1, 2, 4, 8];
+const MIN_TIMESTEP_HZ: f64 = 10.0;
+const MAX_TIMESTEP_HZ: f64 = 1000.0;
+
+// Every key navi.toml understands, as "section.key"
+const CONFIG_KEYS: [&str; 9] = [
+    "window.title",
+    "window.width",
+    "window.height",
+    "window.vsync",
+    "window.fullscreen",
+    "render.msaa",
+    "render.physics_debug",
+    "physics.timestep_hz",
+    "physics.gravity",
+];
+
+// Engine settings read once before the app is built. Editor preferences
+// that change at runtime live in the prefs file instead
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct NaviConfig {
+    // File the values came from, None when there was none
+    pub path: Option<PathBuf>,
+    pub title: String,
+    pub width: f32,
+    pub height: f32,
+    pub vsync: bool,
+    pub fullscreen: bool,
+    // Samples per pixel, 1 turns MSAA off
+    pub msaa: u32,
+    // Rapier's wireframe overlay, the plugin isn't added at all when off
+    pub physics_debug: bool,
+    // None lets rapier step with the frame time
+    pub timestep_hz: Option<f64>,
+    // Gravity of new scenes
+    pub gravity: Vec3,
+    // Keys set by the file, the rest are defaults
+    pub from_file: BTreeSet<String>,
+    // Keys that were ignored and why, reported once the app is running. The
+    // whole file is "navi.toml"
+    pub warnings: Vec<(String, String)>,
+}
+
+impl Default for NaviConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            title: "Navi".to_string(),
+            width: 1280.0,
+            height: 720.0,
+            vsync: true,
+            fullscreen: false,
+            msaa: 4,
+            physics_debug: true,
+            timestep_hz: None,
+            gravity: EARTH_GRAVITY,
+            from_file: BTreeSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+// navi.toml in the working directory, otherwise next to the editor prefs
+pub fn config_path() -> Option<PathBuf> {
+    let local = PathBuf::from(CONFIG_FILE_NAME);
+    if local.exists() {
+        return Some(local);
+    }
+    ProjectDirs::from("", "", "Navi")
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+        .filter(|path| path.exists())
+}
+
+// Looks up "section.key" and records where the value came from
+struct ConfigReader<'a> {
+    table: &'a toml::Table,
+    config: &'a mut NaviConfig,
+}
+
+impl ConfigReader<'_> {
+    fn value(&self, key: &str) -> Option<&toml::Value> {
+        let (section, name) = key.split_once('.')?;
+        self.table.get(section)?.as_table()?.get(name)
+    }
+
+    // `parse` returns the reason a value can't be used
+    fn read<T>(
+        &mut self,
+        key: &str,
+        parse: impl FnOnce(&toml::Value) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = self.value(key)?;
+        match parse(value) {
+            Ok(parsed) => {
+                self.config.from_file.insert(key.to_string());
+                Some(parsed)
+            }
+            Err(reason) => {
+                self.config
+                    .warnings
+                    .push((key.to_string(), format!("{}, got {}", reason, value)));
+                None
+            }
+        }
+    }
+}
+
+fn as_bool(value: &toml::Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| "expected true or false".to_string())
+}
+
+fn as_number(value: &toml::Value) -> Result<f64, String> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|int| int as f64))
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| "expected a number".to_string())
+}
+
+fn window_size(value: &toml::Value) -> Result<f32, String> {
+    let size = as_number(value)? as f32;
+    if size < MIN_WINDOW_SIZE {
+        return Err(format!("must be at least {}", MIN_WINDOW_SIZE));
+    }
+    Ok(size)
+}
+
+impl NaviConfig {
+    // A missing file gives the defaults. Problems are collected in
+    // `warnings` since logging isn't set up yet
+    pub fn load() -> Self {
+        let Some(path) = config_path() else {
+            return Self::default();
+        };
+        let mut config = match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) => Self {
+                warnings: vec![(
+                    CONFIG_FILE_NAME.to_string(),
+                    format!("cannot be read: {}", err),
+                )],
+                ..default()
+            },
+        };
+        config.path = Some(path);
+        config
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut config = Self::default();
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(err) => {
+                config.warnings.push((
+                    CONFIG_FILE_NAME.to_string(),
+                    format!("isn't valid TOML: {}", err.message()),
+                ));
+                return config;
+            }
+        };
+
+        for (section, value) in &table {
+            match value.as_table() {
+                Some(keys) => {
+                    for key in keys.keys() {
+                        let full = format!("{}.{}", section, key);
+                        if !CONFIG_KEYS.contains(&full.as_str()) {
+                            config
+                                .warnings
+                                .push((full, "is an unknown key".to_string()));
+                        }
+                    }
+                }
+                None => config
+                    .warnings
+                    .push((section.clone(), "is an unknown key".to_string())),
+            }
+        }
+
+        let mut reader = ConfigReader {
+            table: &table,
+            config: &mut config,
+        };
+        let title = reader.read("window.title", |value| {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "expected a string".to_string())
+        });
+        let width = reader.read("window.width", window_size);
+        let height = reader.read("window.height", window_size);
+        let vsync = reader.read("window.vsync", as_bool);
+        let fullscreen = reader.read("window.fullscreen", as_bool);
+        let msaa = reader.read("render.msaa", |value| {
+            value
+                .as_integer()
+                .and_then(|samples| u32::try_from(samples).ok())
+                .filter(|samples| MSAA_SAMPLES.contains(samples))
+                .ok_or_else(|| "expected 1, 2, 4 or 8".to_string())
+        });
+        let physics_debug = reader.read("render.physics_debug", as_bool);
+        let timestep_hz = reader.read("physics.timestep_hz", |value| {
+            let hz = as_number(value)?;
+            if !(MIN_TIMESTEP_HZ..=MAX_TIMESTEP_HZ).contains(&hz) {
+                return Err(format!(
+                    "must be between {} and {}",
+                    MIN_TIMESTEP_HZ, MAX_TIMESTEP_HZ
+                ));
+            }
+            Ok(hz)
+        });
+        let gravity = reader.read("physics.gravity", |value| {
+            let numbers = value
+                .as_array()
+                .filter(|array| array.len() == 3)
+                .ok_or_else(|| "expected [x, y, z]".to_string())?
+                .iter()
+                .map(as_number)
+                .collect::<Result<Vec<f64>, String>>()?;
+            Ok(Vec3::new(
+                numbers[0] as f32,
+                numbers[1] as f32,
+                numbers[2] as f32,
+            ))
+        });
+
+        if let Some(title) = title {
+            config.title = title;
+        }
+        if let Some(width) = width {
+            config.width = width;
+        }
+        if let Some(height) = height {
+            config.height = height;
+        }
+        if let Some(vsync) = vsync {
+            config.vsync = vsync;
+        }
+        if let Some(fullscreen) = fullscreen {
+            config.fullscreen = fullscreen;
+        }
+        if let Some(msaa) = msaa {
+            config.msaa = msaa;
+        }
+        if let Some(physics_debug) = physics_debug {
+            config.physics_debug = physics_debug;
+        }
+        if timestep_hz.is_some() {
+            config.timestep_hz = timestep_hz;
+        }
+        if let Some(gravity) = gravity {
+            config.gravity = gravity;
+        }
+        config
+    }
+
+    // The file couldn't be read or parsed at all
+    pub fn is_unreadable(&self) -> bool {
+        self.warnings.iter().any(|(key, _)| key == CONFIG_FILE_NAME)
+    }
+
+    // Keys that were rejected keep their value from `previous` rather than
+    // falling back to the default, so a typo doesn't undo a working setting
+    pub fn keep_previous(&mut self, previous: &NaviConfig) {
+        let rejected: Vec<String> = self.warnings.iter().map(|(key, _)| key.clone()).collect();
+        for key in rejected {
+            if previous.is_from_file(&key) {
+                self.from_file.insert(key.clone());
+            }
+            match key.as_str() {
+                "window.title" => self.title = previous.title.clone(),
+                "window.width" => self.width = previous.width,
+                "window.height" => self.height = previous.height,
+                "window.vsync" => self.vsync = previous.vsync,
+                "window.fullscreen" => self.fullscreen = previous.fullscreen,
+                "render.msaa" => self.msaa = previous.msaa,
+                "render.physics_debug" => self.physics_debug = previous.physics_debug,
+                "physics.timestep_hz" => self.timestep_hz = previous.timestep_hz,
+                "physics.gravity" => self.gravity = previous.gravity,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn is_from_file(&self, key: &str) -> bool {
+        self.from_file.contains(key)
+    }
+
+    pub fn window(&self) -> Window {
+        Window {
+            title: self.title.clone(),
+            resolution: WindowResolution::new(self.width, self.height),
+            present_mode: if self.vsync {
+                PresentMode::AutoVsync
+            } else {
+                PresentMode::AutoNoVsync
+            },
+            mode: if self.fullscreen {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            } else {
+                WindowMode::Windowed
+            },
+            ..default()
+        }
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        match self.msaa {
+            1 => Msaa::Off,
+            2 => Msaa::Sample2,
+            8 => Msaa::Sample8,
+            _ => Msaa::Sample4,
+        }
+    }
+
+    pub fn timestep_mode(&self) -> TimestepMode {
+        match self.timestep_hz {
+            Some(hz) => TimestepMode::Fixed {
+                dt: (1.0 / hz) as f32,
+                substeps: 1,
+            },
+            None => TimestepMode::default(),
+        }
+    }
+
+    // Keys with their values as shown in the World panel
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("window.title", self.title.clone()),
+            ("window.width", self.width.to_string()),
+            ("window.height", self.height.to_string()),
+            ("window.vsync", self.vsync.to_string()),
+            ("window.fullscreen", self.fullscreen.to_string()),
+            ("render.msaa", self.msaa.to_string()),
+            ("render.physics_debug", self.physics_debug.to_string()),
+            (
+                "physics.timestep_hz",
+                self.timestep_hz
+                    .map_or("frame rate".to_string(), |hz| hz.to_string()),
+            ),
+            (
+                "physics.gravity",
+                format!(
+                    "[{}, {}, {}]",
+                    self.gravity.x, self.gravity.y, self.gravity.z
+                ),
+            ),
+        ]
+    }
+}
+
+// Log what was wrong with navi.toml now that logging is up, with a toast
+pub fn report_config_system(
+    config: Res<NaviConfig>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    let Some(path) = &config.path else {
+        return;
+    };
+    info!(
+        "Engine config {} sets {} keys",
+        path.display(),
+        config.from_file.len()
+    );
+    if config.warnings.is_empty() {
+        return;
+    }
+    for (key, reason) in &config.warnings {
+        warn!("Ignoring {} in {}: {}", key, path.display(), reason);
+    }
+    let keys: Vec<&str> = config
+        .warnings
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .collect();
+    notifications.warn(format!(
+        "{} has invalid settings, using defaults for: {}",
+        CONFIG_FILE_NAME,
+        keys.join(", ")
+    ));
+}
+
+// Logs the settings navi.toml was read with
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, report_config_system);
+    }
+}
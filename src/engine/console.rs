@@ -0,0 +1,182 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::layout::EditorLayout;
+use bevy::log::tracing::{Event, Level, Subscriber, field::Field, field::Visit};
+use bevy::log::tracing_subscriber::{Layer, layer::Context};
+use bevy::log::{BoxedLayer, LogPlugin};
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+const CONSOLE_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub level: Level,
+    pub text: String,
+}
+
+// Ring buffer of captured log lines shown in the console panel
+#[derive(Resource)]
+pub struct ConsoleLog {
+    pub lines: VecDeque<ConsoleLine>,
+    pub show_info: bool,
+    pub show_warn: bool,
+    pub show_error: bool,
+    receiver: Mutex<Receiver<ConsoleLine>>,
+}
+
+impl ConsoleLog {
+    fn new(receiver: Receiver<ConsoleLine>) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(CONSOLE_CAPACITY),
+            show_info: true,
+            show_warn: true,
+            show_error: true,
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    pub fn push(&mut self, line: ConsoleLine) {
+        if self.lines.len() == CONSOLE_CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    fn is_visible(&self, level: &Level) -> bool {
+        match *level {
+            Level::ERROR => self.show_error,
+            Level::WARN => self.show_warn,
+            _ => self.show_info,
+        }
+    }
+}
+
+// Tracing layer forwarding formatted events over a channel, so logging never
+// waits on the console and UI systems can log freely
+struct ConsoleLayer {
+    sender: Sender<ConsoleLine>,
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let _ = self.sender.send(ConsoleLine {
+            level: *metadata.level(),
+            text: format!(
+                "[{}] {}: {}",
+                metadata.level(),
+                metadata.target(),
+                visitor.message
+            ),
+        });
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+// Hooked into LogPlugin::custom_layer
+pub fn console_log_layer(app: &mut App) -> Option<BoxedLayer> {
+    let (sender, receiver) = mpsc::channel();
+    app.insert_resource(ConsoleLog::new(receiver));
+    Some(Box::new(ConsoleLayer { sender }))
+}
+
+pub fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        custom_layer: console_log_layer,
+        ..default()
+    }
+}
+
+// Move pending lines from the channel into the ring buffer
+pub fn drain_console_system(mut console: ResMut<ConsoleLog>) {
+    let pending: Vec<ConsoleLine> = match console.receiver.lock() {
+        Ok(receiver) => receiver.try_iter().collect(),
+        Err(_) => return,
+    };
+    for line in pending {
+        console.push(line);
+    }
+}
+
+pub fn console_panel_system(
+    mut contexts: EguiContexts,
+    mut console: ResMut<ConsoleLog>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Console",
+        egui::Window::new("Console")
+            .default_width(520.0)
+            .default_height(220.0),
+        |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut console.show_info, "Info");
+                ui.checkbox(&mut console.show_warn, "Warn");
+                ui.checkbox(&mut console.show_error, "Error");
+                if ui.button("Clear").clicked() {
+                    console.clear();
+                }
+            });
+            ui.separator();
+
+            // stick_to_bottom stops following once the user scrolls up
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in console
+                        .lines
+                        .iter()
+                        .filter(|l| console.is_visible(&l.level))
+                    {
+                        let color = match line.level {
+                            Level::ERROR => egui::Color32::LIGHT_RED,
+                            Level::WARN => egui::Color32::YELLOW,
+                            _ => ui.visuals().text_color(),
+                        };
+                        ui.label(egui::RichText::new(&line.text).monospace().color(color));
+                    }
+                });
+        },
+    );
+}
+
+// Log lines collected for the Console panel
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(EguiContextPass, console_panel_system.in_set(UiSet::Panels))
+            .add_systems(Update, drain_console_system.in_set(EditorSet::Bookkeeping));
+    }
+}
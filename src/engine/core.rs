@@ -1,83 +1,348 @@
+use crate::engine::arrange::*;
+use crate::engine::autosave::*;
+use crate::engine::batch::*;
+use crate::engine::bookmarks::*;
+use crate::engine::bounds::*;
+use crate::engine::camera::*;
+use crate::engine::clipboard::*;
+use crate::engine::components::*;
+use crate::engine::config::*;
+use crate::engine::console::*;
+use crate::engine::csv_export::*;
+use crate::engine::custom_mesh::*;
+use crate::engine::dynamic_scene::*;
 use crate::engine::editor::*;
-use crate::engine::input::handle_input;
+use crate::engine::export::*;
+use crate::engine::gizmo::*;
+use crate::engine::groups::*;
+use crate::engine::history::*;
+use crate::engine::hot_reload::*;
+use crate::engine::impulse::*;
+use crate::engine::input::*;
+use crate::engine::joints::*;
+use crate::engine::labels::*;
+use crate::engine::lifetime::*;
+use crate::engine::measure::*;
+use crate::engine::minimap::*;
+use crate::engine::notify::*;
 use crate::engine::objects::*;
+use crate::engine::oplog::*;
+use crate::engine::palette::*;
+use crate::engine::placement::*;
+use crate::engine::playback::*;
+use crate::engine::pool::*;
+use crate::engine::prefab::*;
+use crate::engine::prefab_file::*;
+use crate::engine::prefs::*;
+use crate::engine::profiler::*;
+use crate::engine::properties::*;
+use crate::engine::recorder::*;
+use crate::engine::scene::*;
+use crate::engine::screenshot::*;
+use crate::engine::selection::*;
+use crate::engine::session::*;
+use crate::engine::simulation::*;
+use crate::engine::snap::*;
+use crate::engine::stats::*;
+use crate::engine::templates::*;
+use crate::engine::thumbnails::*;
+use crate::engine::toolbar::*;
+use crate::engine::trash::*;
+use crate::engine::world::*;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
 use bevy_egui::*;
 use bevy_rapier3d::prelude::*;
+use std::time::Duration;
 
-pub fn run() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugins(RapierDebugRenderPlugin::default())
-        .add_plugins(EguiPlugin {
-            enable_multipass_for_primary_context: true,
-        })
-        // Add custom events
-        .add_event::<SpawnEntityEvent>()
-        // Initialize resources
-        .init_resource::<SelectedShape>()
-        .init_resource::<GameObjectManager>()
-        // Startup systems
-        .add_systems(Startup, (setup_graphics, setup_physics))
-        .add_systems(EguiContextPass, ui_example_system)
-        // Update systems with proper ordering
+// The static ground slab, spawned while WorldSettings::ground is on
+pub const GROUND_SIZE: Vec3 = Vec3::new(200.0, 0.2, 200.0);
+pub const GROUND_POSITION: Vec3 = Vec3::new(0.0, -2.0, 0.0);
+pub const GROUND_COLOR: Color = Color::srgb(0.3, 0.5, 0.3);
+
+// Phases of an Update, in order. Input is read first, then requests are
+// carried out, then the edits the UI sent last frame are committed, then the
+// manager is synced with the world. Bookkeeping sees the finished frame
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EditorSet {
+    Input,
+    Logic,
+    Commit,
+    Sync,
+    Bookkeeping,
+}
+
+// Order of the egui pass. Theme and scale first so everything is drawn with
+// them, panels claim screen edges before floating windows, and popups go
+// last so they draw over the panels
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UiSet {
+    Prefs,
+    Toolbar,
+    Panels,
+    Popups,
+}
+
+pub fn run(startup: StartupScene) {
+    // Window and plugin choices have to be known before the app is built
+    let config = NaviConfig::load();
+
+    // Restore saved editor state before the first egui pass
+    let (prefs, prefs_problem) = EditorPrefs::load();
+    let prefs_saver = PrefsSaver::new(&prefs);
+    let mut notifications = EditorNotifications::new(prefs.notifications);
+    if let Some(problem) = prefs_problem {
+        notifications.warn(problem);
+    }
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(log_plugin())
+            .set(asset_plugin())
+            .set(WindowPlugin {
+                primary_window: Some(config.window()),
+                ..default()
+            }),
+    )
+    .add_plugins(FrameTimeDiagnosticsPlugin::default())
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+    .insert_resource(config.timestep_mode());
+    if config.physics_debug {
+        app.add_plugins(RapierDebugRenderPlugin::default());
+    }
+    app.add_plugins(EguiPlugin {
+        enable_multipass_for_primary_context: true,
+    })
+    .add_plugins(EditorPlugin)
+    .insert_resource(startup)
+    .insert_resource(prefs.layout)
+    .insert_resource(prefs.camera.clamped())
+    .insert_resource(prefs.bindings)
+    .insert_resource(prefs.ui.clamped())
+    .insert_resource(notifications)
+    .insert_resource(prefs.bookmarks)
+    .insert_resource(prefs.autosave.clamped())
+    .insert_resource(Autosave::new(prefs.last_scene))
+    .insert_resource(prefs.screenshots)
+    .insert_resource(prefs.snap.clamped())
+    .insert_resource(WorldSettings {
+        gravity: config.gravity,
+        ..default()
+    })
+    .insert_resource(config)
+    .insert_resource(prefs.recent_scenes)
+    .insert_resource(prefs_saver);
+    app.run();
+}
+
+// Every editor module's plugin, and the systems whose order runs across
+// modules. A module's own panels, popups and bookkeeping are added by its
+// plugin into one of the sets above
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            Update,
+            (
+                EditorSet::Input,
+                EditorSet::Logic,
+                EditorSet::Commit,
+                EditorSet::Sync,
+                EditorSet::Bookkeeping,
+            )
+                .chain(),
+        )
+        .configure_sets(
+            EguiContextPass,
+            (UiSet::Prefs, UiSet::Toolbar, UiSet::Panels, UiSet::Popups).chain(),
+        )
+        .add_plugins((
+            ObjectsPlugin,
+            SelectionPlugin,
+            HistoryPlugin,
+            SimulationPlugin,
+            WorldPlugin,
+            EntityPoolPlugin,
+            LifetimePlugin,
+            BatchSpawnPlugin,
+            ClipboardPlugin,
+            PropertiesPlugin,
+            GroupsPlugin,
+            TrashPlugin,
+            JointsPlugin,
+            ImpulsePlugin,
+            ArrangePlugin,
+        ))
+        .add_plugins((
+            EditorPanelsPlugin,
+            ComponentsPlugin,
+            ToolbarPlugin,
+            CommandPalettePlugin,
+            NotificationsPlugin,
+            KeybindingsPlugin,
+            EditorCameraPlugin,
+            BookmarksPlugin,
+            MinimapPlugin,
+            TransformGizmoPlugin,
+            LabelsPlugin,
+            BoundsPlugin,
+            MeasurePlugin,
+            PlacementPlugin,
+            SnapPlugin,
+        ))
+        .add_plugins((
+            SceneFilePlugin,
+            TemplatesPlugin,
+            SessionPlugin,
+            AutosavePlugin,
+            ThumbnailsPlugin,
+            ScreenshotsPlugin,
+            PrefabPlugin,
+            PrefabFilePlugin,
+            CustomMeshPlugin,
+            GltfExportPlugin,
+            CsvExportPlugin,
+            DynamicSceneExportPlugin,
+            RecorderPlugin,
+            PlaybackPlugin,
+            OperationLogPlugin,
+        ))
+        .add_plugins((
+            PrefsPlugin,
+            ConfigPlugin,
+            HotReloadPlugin,
+            ConsolePlugin,
+            StatsPlugin,
+            ProfilerPlugin,
+        ))
+        .add_systems(Startup, setup_graphics)
         .add_systems(
             Update,
             (
-                // Input handling first
+                capture_binding_system,
+                command_palette_input_system,
                 handle_input,
-                // Then UI systems (egui context is automatically managed by the plugin)
-                (shape_selection_ui).chain(), // Ensure UI systems run in order
-                // Finally, game logic systems
+                shape_selection_ui,
+                history_input_system,
+                duplicate_input_system,
+                clipboard_input_system,
+                properties_input_system,
+                scene_input_system,
+                simulation_input_system,
+                toggle_stats_overlay_system,
+                gizmo_mode_input_system,
+                gizmo_interaction_system,
+                camera_bookmark_input_system,
+                measure_input_system,
+                placement_input_system,
+                select_nearby_input_system,
+                select_nearest_input_system,
+                (
+                    frame_all_input_system,
+                    screenshot_input_system,
+                    flip_gravity_input_system,
+                    impulse_input_system,
+                    hinge_pick_system,
+                ),
+            )
+                .chain()
+                .in_set(EditorSet::Input),
+        )
+        .add_systems(
+            Update,
+            (
+                // Replayed events are carried out the frame they're sent
+                playback_system,
+                // Sees this frame's requests before they're carried out
+                record_session_system,
+                import_mesh_system,
+                track_mesh_loads_system,
                 spawn_entity_system,
+                batch_spawn_system.run_if(playback_inactive),
+                spawn_prefab_system.run_if(playback_inactive),
+                duplicate_object_system.run_if(playback_inactive),
+                paste_objects_system.run_if(playback_inactive),
+                load_scene_system,
+                place_template_camera_system,
+                apply_history_system,
+                set_gravity_system,
+                apply_world_settings_system,
+                apply_camera_settings_system,
+                camera_transition_system,
+                apply_simulation_state_system,
             )
-                .chain(), // Ensure proper execution order
+                .chain()
+                .in_set(EditorSet::Logic),
         )
-        .run();
+        .add_systems(
+            Update,
+            (
+                update_prefab_instances_system,
+                lifetime_system,
+                apply_transform_edits_system,
+                (
+                    arrange_system,
+                    apply_impulse_system.run_if(playback_inactive),
+                    joint_system,
+                ),
+                apply_group_events_system,
+                apply_material_edits_system,
+                apply_physics_edits_system,
+                apply_bulk_edits_system,
+                apply_properties_system,
+                apply_dimension_edits_system,
+                rename_object_system,
+                tag_object_system,
+                meta_edit_system,
+                lock_object_system,
+                set_visibility_system,
+                reparent_object_system,
+                despawn_entity_system,
+                trim_pool_system,
+                apply_trash_events_system,
+                clear_scene_system,
+            )
+                .chain()
+                .in_set(EditorSet::Commit),
+        )
+        .add_systems(
+            Update,
+            (
+                cleanup_destroyed_entities_system,
+                announce_spawned_objects_system,
+                validate_manager_system
+                    .run_if(on_timer(Duration::from_secs_f32(MANAGER_VALIDATION_SECS))),
+                update_object_positions_system,
+                update_object_velocities_system,
+                apply_custom_meshes_system,
+                update_measurements_system,
+                prune_selection_system,
+                prune_groups_system,
+                prune_joints_system,
+                selection_outline_system,
+                record_operations_system,
+            )
+                .chain()
+                .in_set(EditorSet::Sync),
+        );
+    }
 }
 
-fn setup_graphics(mut commands: Commands) {
+fn setup_graphics(mut commands: Commands, ui_prefs: Res<UiPrefs>, config: Res<NaviConfig>) {
     // Add a camera so we can see the debug-render.
+    // It hosts the egui context, so start it at the saved UI scale. Templates
+    // move it to their own view when they're created
     commands.spawn((
         Camera3d::default(),
-        Transform::from_xyz(-3.0, 3.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
-    ));
-}
-
-fn setup_physics(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    // Create the ground
-    let ground_mesh = meshes.add(Cuboid::new(200.0, 0.2, 200.0));
-    let ground_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.3, 0.5, 0.3),
-        ..default()
-    });
-
-    commands.spawn((
-        Mesh3d(ground_mesh),
-        MeshMaterial3d(ground_material),
-        Transform::from_xyz(0.0, -2.0, 0.0),
-        Collider::cuboid(100.0, 0.1, 100.0),
-        RigidBody::Fixed,
-    ));
-
-    // Create the initial bouncing ball
-    let initial_mesh = meshes.add(Sphere::new(0.5));
-    let initial_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.8, 0.7, 0.6),
-        ..default()
-    });
-
-    commands.spawn((
-        Mesh3d(initial_mesh),
-        MeshMaterial3d(initial_material),
-        Transform::from_translation(Vec3::new(0.0, 4.0, 0.0)),
-        RigidBody::Dynamic,
-        Collider::ball(0.5),
-        Restitution::coefficient(0.7),
+        MainCamera,
+        EguiContextSettings {
+            scale_factor: ui_prefs.scale,
+            ..default()
+        },
+        default_camera(),
+        config.msaa(),
     ));
 }
@@ -0,0 +1,439 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{GameObject, GameObjectManager};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::default_scene_path;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::fs;
+use std::path::PathBuf;
+
+// Groups of columns picked in the export dialog, some span several columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CsvColumn {
+    Id,
+    Name,
+    Shape,
+    Position,
+    Rotation,
+    CreatedAt,
+    Tags,
+    Velocity,
+    Color,
+}
+
+impl CsvColumn {
+    pub fn all() -> [CsvColumn; 9] {
+        [
+            CsvColumn::Id,
+            CsvColumn::Name,
+            CsvColumn::Shape,
+            CsvColumn::Position,
+            CsvColumn::Rotation,
+            CsvColumn::CreatedAt,
+            CsvColumn::Tags,
+            CsvColumn::Velocity,
+            CsvColumn::Color,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CsvColumn::Id => "ID",
+            CsvColumn::Name => "Name",
+            CsvColumn::Shape => "Shape",
+            CsvColumn::Position => "Position",
+            CsvColumn::Rotation => "Rotation",
+            CsvColumn::CreatedAt => "Created at",
+            CsvColumn::Tags => "Tags",
+            CsvColumn::Velocity => "Velocity",
+            CsvColumn::Color => "Color",
+        }
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        match self {
+            CsvColumn::Id => &["id"],
+            CsvColumn::Name => &["name"],
+            CsvColumn::Shape => &["shape"],
+            CsvColumn::Position => &["x", "y", "z"],
+            CsvColumn::Rotation => &["rot_x_deg", "rot_y_deg", "rot_z_deg"],
+            CsvColumn::CreatedAt => &["created_at"],
+            CsvColumn::Tags => &["tags"],
+            CsvColumn::Velocity => &["vel_x", "vel_y", "vel_z"],
+            CsvColumn::Color => &["color"],
+        }
+    }
+
+    fn values(&self, obj: &GameObject) -> Vec<String> {
+        let vec3 = |v: Vec3| vec![v.x.to_string(), v.y.to_string(), v.z.to_string()];
+        match self {
+            CsvColumn::Id => vec![obj.id.to_string()],
+            CsvColumn::Name => vec![obj.name.clone()],
+            CsvColumn::Shape => vec![obj.shape_type.display_name().to_string()],
+            CsvColumn::Position => vec3(obj.position()),
+            CsvColumn::Rotation => {
+                let (x, y, z) = obj.rotation.to_euler(EulerRot::XYZ);
+                vec3(Vec3::new(x, y, z) * 180.0 / std::f32::consts::PI)
+            }
+            CsvColumn::CreatedAt => vec![obj.created_at.to_string()],
+            CsvColumn::Tags => {
+                let mut tags: Vec<&str> = obj.tags.iter().map(String::as_str).collect();
+                tags.sort_unstable();
+                vec![tags.join(";")]
+            }
+            CsvColumn::Velocity => vec3(obj.linvel),
+            CsvColumn::Color => vec![obj.color.to_srgba().to_hex()],
+        }
+    }
+}
+
+// Quoted when it holds a separator, quote or line break, quotes doubled
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_row(out: &mut String, fields: impl IntoIterator<Item = String>) {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| escape_field(&field))
+        .collect();
+    out.push_str(&fields.join(","));
+    out.push('\n');
+}
+
+// A header row then one row per object in id order
+pub fn objects_to_csv<'a>(
+    objects: impl IntoIterator<Item = &'a GameObject>,
+    columns: &[CsvColumn],
+) -> String {
+    let mut out = String::new();
+    write_row(
+        &mut out,
+        columns
+            .iter()
+            .flat_map(|column| column.headers().iter().map(|header| header.to_string())),
+    );
+    for obj in objects {
+        write_row(
+            &mut out,
+            columns.iter().flat_map(|column| column.values(obj)),
+        );
+    }
+    out
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ExportCsvEvent {
+    pub path: PathBuf,
+    pub columns: Vec<CsvColumn>,
+}
+
+#[derive(Resource)]
+pub struct CsvExport {
+    pub open: bool,
+    pub path: String,
+    // Ticked columns, kept between exports
+    pub columns: Vec<CsvColumn>,
+}
+
+impl Default for CsvExport {
+    fn default() -> Self {
+        Self {
+            open: false,
+            path: String::new(),
+            columns: CsvColumn::all().to_vec(),
+        }
+    }
+}
+
+impl CsvExport {
+    pub fn open_dialog(&mut self) {
+        if self.path.is_empty() {
+            self.path = default_scene_path()
+                .with_extension("csv")
+                .display()
+                .to_string();
+        }
+        self.open = true;
+    }
+
+    // In dialog order no matter the order they were ticked in
+    fn selected(&self) -> Vec<CsvColumn> {
+        CsvColumn::all()
+            .into_iter()
+            .filter(|column| self.columns.contains(column))
+            .collect()
+    }
+}
+
+pub fn export_csv_system(
+    mut export_events: EventReader<ExportCsvEvent>,
+    game_manager: Res<GameObjectManager>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in export_events.read() {
+        let csv = objects_to_csv(game_manager.iter(), &event.columns);
+        let result = event
+            .path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&event.path, csv));
+        match result {
+            Ok(()) => {
+                info!(
+                    "Exported {} objects to {}",
+                    game_manager.iter().len(),
+                    event.path.display()
+                );
+                notifications.info(format!("Exported CSV to {}", event.path.display()));
+            }
+            Err(err) => {
+                warn!("CSV export failed: {}", err);
+                notifications.error(format!("CSV export failed: {}", err));
+            }
+        }
+    }
+}
+
+pub fn csv_export_dialog_system(
+    mut contexts: EguiContexts,
+    mut export: ResMut<CsvExport>,
+    mut export_events: EventWriter<ExportCsvEvent>,
+    game_manager: Res<GameObjectManager>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if !export.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Export CSV")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("Columns");
+            ui.horizontal_wrapped(|ui| {
+                for column in CsvColumn::all() {
+                    let mut checked = export.columns.contains(&column);
+                    if ui.checkbox(&mut checked, column.display_name()).changed() {
+                        if checked {
+                            export.columns.push(column);
+                        } else {
+                            export.columns.retain(|other| *other != column);
+                        }
+                    }
+                }
+            });
+            ui.separator();
+            ui.label("File (.csv)");
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut export.path).desired_width(360.0));
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                let columns = export.selected();
+                let path = export.path.trim().to_string();
+                let ready = !path.is_empty() && !columns.is_empty();
+                if (ui.add_enabled(ready, egui::Button::new("Export")).clicked() || entered)
+                    && ready
+                {
+                    export_events.write(ExportCsvEvent {
+                        path: PathBuf::from(path),
+                        columns: columns.clone(),
+                    });
+                    export.open = false;
+                }
+                if ui
+                    .add_enabled(!columns.is_empty(), egui::Button::new("Copy to clipboard"))
+                    .clicked()
+                {
+                    ui.ctx()
+                        .copy_text(objects_to_csv(game_manager.iter(), &columns));
+                    notifications.info(format!(
+                        "Copied {} objects as CSV",
+                        game_manager.iter().len()
+                    ));
+                }
+                if ui.button("Cancel").clicked() {
+                    export.open = false;
+                }
+            });
+        });
+}
+
+pub fn register_csv_export_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Export CSV...", None, |world| {
+        world.resource_mut::<CsvExport>().open_dialog();
+    });
+}
+
+// CSV export of the object list
+pub struct CsvExportPlugin;
+
+impl Plugin for CsvExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportCsvEvent>()
+            .init_resource::<CsvExport>()
+            .add_systems(Startup, register_csv_export_commands)
+            .add_systems(
+                EguiContextPass,
+                csv_export_dialog_system.in_set(UiSet::Popups),
+            )
+            .add_systems(Update, export_csv_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::ShapeType;
+
+    // RFC 4180 as spreadsheets read it: quoted fields may hold separators,
+    // doubled quotes and line breaks
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.is_empty() => quoted = true,
+                ',' if !quoted => row.push(std::mem::take(&mut field)),
+                '\n' if !quoted => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                c => field.push(c),
+            }
+        }
+        assert!(!quoted, "unterminated quote in {:?}", text);
+        assert!(field.is_empty() && row.is_empty(), "no final newline");
+        rows
+    }
+
+    const AWKWARD_NAMES: [&str; 6] = [
+        "plain",
+        "comma, separated",
+        "say \"hi\"",
+        "two\nlines",
+        "windows\r\nline",
+        "\"",
+    ];
+
+    fn manager() -> GameObjectManager {
+        let mut game_manager = GameObjectManager::default();
+        for (index, name) in AWKWARD_NAMES.iter().enumerate() {
+            let id = game_manager
+                .add_object(
+                    Entity::from_raw(index as u32 + 1),
+                    ShapeType::Ball,
+                    Vec3::new(index as f32, -1.5, 0.25),
+                    Some(name.to_string()),
+                    false,
+                    index as f64,
+                )
+                .unwrap();
+            game_manager.add_tag(id, "b,\"quoted\"").unwrap();
+            game_manager.add_tag(id, "a").unwrap();
+        }
+        game_manager
+    }
+
+    #[test]
+    fn fields_are_quoted_only_when_they_need_it() {
+        assert_eq!(escape_field("plain text"), "plain text");
+        assert_eq!(escape_field(""), "");
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("a\nb"), "\"a\nb\"");
+        assert_eq!(escape_field("a\rb"), "\"a\rb\"");
+    }
+
+    #[test]
+    fn awkward_fields_parse_back_unchanged() {
+        let game_manager = manager();
+        let csv = objects_to_csv(
+            game_manager.iter(),
+            &[CsvColumn::Id, CsvColumn::Name, CsvColumn::Tags],
+        );
+        let rows = parse_csv(&csv);
+
+        assert_eq!(rows[0], ["id", "name", "tags"]);
+        assert_eq!(rows.len(), AWKWARD_NAMES.len() + 1);
+        for (row, obj) in rows[1..].iter().zip(game_manager.iter()) {
+            assert_eq!(row[0], obj.id.to_string());
+            assert_eq!(row[1], obj.name);
+            assert_eq!(row[2], "a;b,\"quoted\"");
+        }
+        let names: Vec<&str> = rows[1..].iter().map(|row| row[1].as_str()).collect();
+        assert_eq!(names, AWKWARD_NAMES);
+    }
+
+    #[test]
+    fn every_row_has_a_field_per_header() {
+        let game_manager = manager();
+        let rows = parse_csv(&objects_to_csv(game_manager.iter(), &CsvColumn::all()));
+        let headers: Vec<&str> = CsvColumn::all()
+            .iter()
+            .flat_map(|column| column.headers().iter().copied())
+            .collect();
+        assert_eq!(rows[0], headers);
+        for row in &rows[1..] {
+            assert_eq!(row.len(), headers.len(), "{:?}", row);
+        }
+
+        let x = headers.iter().position(|&header| header == "x").unwrap();
+        let positions: Vec<[f32; 3]> = rows[1..]
+            .iter()
+            .map(|row| [0, 1, 2].map(|axis| row[x + axis].parse().unwrap()))
+            .collect();
+        let expected: Vec<[f32; 3]> = game_manager
+            .iter()
+            .map(|obj| obj.position().to_array())
+            .collect();
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn columns_come_out_in_the_order_given() {
+        let game_manager = manager();
+        let csv = objects_to_csv(
+            game_manager.iter().take(1),
+            &[CsvColumn::Color, CsvColumn::Shape, CsvColumn::Id],
+        );
+        let rows = parse_csv(&csv);
+        assert_eq!(rows[0], ["color", "shape", "id"]);
+        let obj = game_manager.iter().next().unwrap();
+        assert_eq!(
+            rows[1],
+            [
+                obj.color.to_srgba().to_hex(),
+                obj.shape_type.display_name().to_string(),
+                obj.id.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_objects_is_just_the_header() {
+        assert_eq!(
+            objects_to_csv([], &[CsvColumn::Id, CsvColumn::Position]),
+            "id,x,y,z\n"
+        );
+        assert_eq!(objects_to_csv([], &[]), "\n");
+    }
+}
@@ -0,0 +1,523 @@
+use crate::engine::core::UiSet;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    GameObjectManager, ShapeDimensions, ShapeType, SpawnEntityEvent, SpawnSource, unique_name,
+};
+use crate::engine::palette::CommandRegistry;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadState, RenderAssetUsages, UnapprovedPathMode};
+use bevy::gltf::GltfAssetLabel;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshAabb, PrimitiveTopology};
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Formats the import dialog accepts
+const MESH_EXTENSIONS: [&str; 3] = ["glb", "gltf", "obj"];
+
+// Imported files live anywhere on disk, so loads outside the assets folder
+// are allowed through load_override only
+pub fn asset_plugin() -> AssetPlugin {
+    AssetPlugin {
+        unapproved_path_mode: UnapprovedPathMode::Deny,
+        ..default()
+    }
+}
+
+// Collider generated for an imported mesh. Convex hulls are cheap and work on
+// dynamic bodies, a trimesh follows the surface exactly but is hollow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshColliderKind {
+    #[default]
+    ConvexHull,
+    TriMesh,
+}
+
+impl MeshColliderKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MeshColliderKind::ConvexHull => "Convex hull",
+            MeshColliderKind::TriMesh => "Triangle mesh",
+        }
+    }
+
+    fn build(&self, mesh: &Mesh) -> Option<Collider> {
+        let shape = match self {
+            MeshColliderKind::ConvexHull => ComputedColliderShape::ConvexHull,
+            MeshColliderKind::TriMesh => ComputedColliderShape::TriMesh(TriMeshFlags::default()),
+        };
+        Collider::from_bevy_mesh(mesh, &shape)
+    }
+}
+
+// One imported file. The collider and bounding box are filled in once the
+// asset server has loaded the mesh
+#[derive(Debug, Clone)]
+pub struct CustomMeshEntry {
+    pub path: PathBuf,
+    pub collider_kind: MeshColliderKind,
+    pub mesh: Handle<Mesh>,
+    collider: Option<Collider>,
+    dimensions: Option<ShapeDimensions>,
+}
+
+impl CustomMeshEntry {
+    pub fn is_ready(&self) -> bool {
+        self.collider.is_some()
+    }
+}
+
+// Meshes imported this session by name, spawned as ShapeType::CustomMesh
+#[derive(Resource, Debug, Default)]
+pub struct CustomMeshLibrary {
+    entries: BTreeMap<String, CustomMeshEntry>,
+}
+
+impl CustomMeshLibrary {
+    pub fn get(&self, name: &str) -> Option<&CustomMeshEntry> {
+        self.entries.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &CustomMeshEntry)> {
+        self.entries.iter()
+    }
+
+    // Loaded meshes only, in name order
+    pub fn ready(&self) -> impl Iterator<Item = &String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.is_ready())
+            .map(|(name, _)| name)
+    }
+
+    // Bounding box of a loaded mesh, None while it's loading or if unknown
+    pub fn dimensions(&self, name: &str) -> Option<ShapeDimensions> {
+        self.entries.get(name)?.dimensions
+    }
+}
+
+// Marks an object drawn with an imported mesh, by library name
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct CustomMeshInstance(pub String);
+
+#[derive(Event, Debug, Clone)]
+pub struct ImportMeshEvent {
+    pub path: PathBuf,
+    // Library name, the file name when unset
+    pub name: Option<String>,
+    pub collider: MeshColliderKind,
+}
+
+fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MESH_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn is_gltf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "glb" | "gltf"))
+}
+
+// Start loading each requested file. Problems found later by the asset server
+// are reported by track_mesh_loads_system
+pub fn import_mesh_system(
+    mut import_events: EventReader<ImportMeshEvent>,
+    asset_server: Res<AssetServer>,
+    mut library: ResMut<CustomMeshLibrary>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in import_events.read() {
+        if !is_supported(&event.path) {
+            notifications.error(format!(
+                "Cannot import {}: only {} files are supported",
+                event.path.display(),
+                MESH_EXTENSIONS.join(", ")
+            ));
+            continue;
+        }
+
+        let base = event
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or_else(|| {
+                event
+                    .path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "Mesh".to_string());
+        let name = unique_name(&base, |candidate| library.contains(candidate));
+
+        // glTF files hold whole scenes, the first primitive of the first mesh is used
+        let mesh = if is_gltf(&event.path) {
+            asset_server.load_override(
+                GltfAssetLabel::Primitive {
+                    mesh: 0,
+                    primitive: 0,
+                }
+                .from_asset(event.path.clone()),
+            )
+        } else {
+            asset_server.load_override(event.path.clone())
+        };
+        info!("Importing {} as {}", event.path.display(), name);
+        library.entries.insert(
+            name,
+            CustomMeshEntry {
+                path: event.path.clone(),
+                collider_kind: event.collider,
+                mesh,
+                collider: None,
+                dimensions: None,
+            },
+        );
+    }
+}
+
+// Finish imports the asset server is done with: build the collider of loaded
+// meshes, drop failed ones with an error toast
+pub fn track_mesh_loads_system(
+    asset_server: Res<AssetServer>,
+    meshes: Res<Assets<Mesh>>,
+    mut library: ResMut<CustomMeshLibrary>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    let pending: Vec<String> = library
+        .entries
+        .iter()
+        .filter(|(_, entry)| !entry.is_ready())
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    for name in pending {
+        let Some(entry) = library.entries.get_mut(&name) else {
+            continue;
+        };
+        let failure = match asset_server.get_load_state(&entry.mesh) {
+            Some(LoadState::Failed(err)) => Some(err.to_string()),
+            Some(LoadState::Loaded) => match meshes.get(&entry.mesh) {
+                Some(mesh) => match (entry.collider_kind.build(mesh), mesh.compute_aabb()) {
+                    (Some(collider), Some(aabb)) => {
+                        entry.collider = Some(collider);
+                        entry.dimensions = Some(ShapeDimensions {
+                            half_extents: Vec3::from(aabb.half_extents),
+                            ..default()
+                        });
+                        info!("Imported mesh {} from {}", name, entry.path.display());
+                        notifications.info(format!("Imported mesh {}", name));
+                        None
+                    }
+                    _ => Some("the mesh has no usable triangles".to_string()),
+                },
+                None => Some("the mesh isn't kept in main world memory".to_string()),
+            },
+            _ => None,
+        };
+        if let Some(err) = failure {
+            warn!("Cannot import {}: {}", entry.path.display(), err);
+            notifications.error(format!("Cannot import {}: {}", entry.path.display(), err));
+            library.entries.remove(&name);
+        }
+    }
+}
+
+// Give custom mesh objects their imported mesh and collider. Spawns, undo and
+// dimension edits insert the bounding box, so anything not showing its mesh
+// yet is fixed up here, including objects waiting on an import
+pub fn apply_custom_meshes_system(
+    mut commands: Commands,
+    library: Res<CustomMeshLibrary>,
+    instances: Query<(Entity, &CustomMeshInstance, &Mesh3d)>,
+) {
+    for (entity, instance, mesh) in &instances {
+        let Some(entry) = library.get(&instance.0) else {
+            continue;
+        };
+        if let Some(collider) = &entry.collider
+            && mesh.0 != entry.mesh
+        {
+            commands
+                .entity(entity)
+                .insert((Mesh3d(entry.mesh.clone()), collider.clone()));
+        }
+    }
+}
+
+// Loaded meshes by name, for the glTF export
+pub fn loaded_custom_meshes(
+    library: &CustomMeshLibrary,
+    meshes: &Assets<Mesh>,
+) -> Vec<(String, Mesh)> {
+    library
+        .iter()
+        .filter_map(|(name, entry)| Some((name.clone(), meshes.get(&entry.mesh)?.clone())))
+        .collect()
+}
+
+// Spawn event for an imported mesh, the same path as the primitive shapes
+pub fn custom_mesh_spawn(name: &str, position: Vec3) -> SpawnEntityEvent {
+    SpawnEntityEvent {
+        source: SpawnSource::ToolbarButton,
+        position,
+        shape_type: ShapeType::CustomMesh,
+        custom_mesh: Some(name.to_string()),
+        ..default()
+    }
+}
+
+// Count of scene objects using each mesh, shown in the import dialog
+fn instance_counts(game_manager: &GameObjectManager) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for obj in game_manager.iter() {
+        if let Some(name) = &obj.custom_mesh {
+            *counts.entry(name.as_str()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+// Wavefront OBJ files as meshes. Only positions, normals and polygon faces are
+// read, faces are fanned into triangles. Normals are computed when missing
+#[derive(Default, TypePath)]
+pub struct ObjMeshLoader;
+
+impl AssetLoader for ObjMeshLoader {
+    type Asset = Mesh;
+    type Settings = ();
+    type Error = io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Mesh, io::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_obj(&String::from_utf8_lossy(&bytes))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+}
+
+// OBJ indices start at 1, negative ones count back from the latest element
+fn obj_index(token: &str, len: usize) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("bad index \"{}\"", token))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!("index {} out of range", index));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_vec3(parts: &mut std::str::SplitWhitespace, line: usize) -> Result<[f32; 3], String> {
+    let mut value = [0.0; 3];
+    for component in &mut value {
+        *component = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(|| format!("line {}: expected three numbers", line))?;
+    }
+    Ok(value)
+}
+
+fn parse_obj(source: &str) -> Result<Mesh, String> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    // Each distinct position/normal pair becomes one vertex
+    let mut vertices: HashMap<(usize, Option<usize>), u32> = HashMap::new();
+    let mut out_positions: Vec<[f32; 3]> = Vec::new();
+    let mut out_normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let mut missing_normals = false;
+
+    for (number, line) in source.lines().enumerate() {
+        let number = number + 1;
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("v") => positions.push(parse_vec3(&mut parts, number)?),
+            Some("vn") => normals.push(parse_vec3(&mut parts, number)?),
+            Some("f") => {
+                let mut corners = Vec::new();
+                for corner in parts {
+                    let mut fields = corner.split('/');
+                    let position = obj_index(fields.next().unwrap_or(""), positions.len())
+                        .map_err(|err| format!("line {}: {}", number, err))?;
+                    let normal = match fields.nth(1).filter(|field| !field.is_empty()) {
+                        Some(field) => Some(
+                            obj_index(field, normals.len())
+                                .map_err(|err| format!("line {}: {}", number, err))?,
+                        ),
+                        None => None,
+                    };
+                    missing_normals |= normal.is_none();
+                    let vertex = *vertices.entry((position, normal)).or_insert_with(|| {
+                        out_positions.push(positions[position]);
+                        out_normals.push(normal.map_or([0.0; 3], |normal| normals[normal]));
+                        out_positions.len() as u32 - 1
+                    });
+                    corners.push(vertex);
+                }
+                if corners.len() < 3 {
+                    return Err(format!("line {}: face with fewer than 3 corners", number));
+                }
+                for pair in corners[1..].windows(2) {
+                    indices.extend([corners[0], pair[0], pair[1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    if indices.is_empty() {
+        return Err("no faces".to_string());
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, out_positions)
+    .with_inserted_indices(Indices::U32(indices));
+    if missing_normals {
+        mesh.compute_normals();
+    } else {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, out_normals);
+    }
+    Ok(mesh)
+}
+
+// Path prompt for Import mesh, opened from the Scene menu and the palette
+#[derive(Resource, Debug, Default)]
+pub struct MeshImportDialog {
+    pub open: bool,
+    pub path: String,
+    pub name: String,
+    pub collider: MeshColliderKind,
+}
+
+pub fn mesh_import_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<MeshImportDialog>,
+    library: Res<CustomMeshLibrary>,
+    game_manager: Res<GameObjectManager>,
+    mut import_events: EventWriter<ImportMeshEvent>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Import mesh")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("File (.glb, .gltf or .obj)");
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(360.0));
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.add(
+                    egui::TextEdit::singleline(&mut dialog.name)
+                        .hint_text("File name")
+                        .desired_width(200.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Collider");
+                for kind in [MeshColliderKind::ConvexHull, MeshColliderKind::TriMesh] {
+                    ui.selectable_value(&mut dialog.collider, kind, kind.label());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let path = dialog.path.trim().to_string();
+                if (ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Import"))
+                    .clicked()
+                    || entered)
+                    && !path.is_empty()
+                {
+                    import_events.write(ImportMeshEvent {
+                        path: PathBuf::from(path),
+                        name: Some(dialog.name.clone()),
+                        collider: dialog.collider,
+                    });
+                    dialog.name.clear();
+                    dialog.open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.open = false;
+                }
+            });
+
+            if library.iter().next().is_some() {
+                ui.separator();
+                let counts = instance_counts(&game_manager);
+                for (name, entry) in library.iter() {
+                    let status = if entry.is_ready() {
+                        format!(
+                            "{} instances",
+                            counts.get(name.as_str()).copied().unwrap_or(0)
+                        )
+                    } else {
+                        "loading...".to_string()
+                    };
+                    ui.label(format!(
+                        "{} ({}), {}",
+                        name,
+                        entry.collider_kind.label(),
+                        status
+                    ))
+                    .on_hover_text(entry.path.display().to_string());
+                }
+            }
+        });
+}
+
+pub fn register_custom_mesh_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Import mesh...", None, |world| {
+        world.resource_mut::<MeshImportDialog>().open = true;
+    });
+}
+
+// OBJ import, the loader and the library of imported meshes
+pub struct CustomMeshPlugin;
+
+impl Plugin for CustomMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset_loader::<ObjMeshLoader>()
+            .add_event::<ImportMeshEvent>()
+            .init_resource::<CustomMeshLibrary>()
+            .init_resource::<MeshImportDialog>()
+            .add_systems(Startup, register_custom_mesh_commands)
+            .add_systems(
+                EguiContextPass,
+                mesh_import_dialog_system.in_set(UiSet::Popups),
+            );
+    }
+}
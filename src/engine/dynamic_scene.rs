@@ -0,0 +1,349 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{GameObjectManager, ShapeDimensions, ShapeType, object_material};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::default_scene_path;
+use bevy::prelude::*;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+// What an exported object is made of, since meshes, materials and colliders
+// can't be written into a scene file. NaviScenePlugin builds them back
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq)]
+#[reflect(Component, Default)]
+pub struct NaviShape {
+    pub shape_type: ShapeType,
+    pub dimensions: ShapeDimensions,
+    pub color: Color,
+    // Library name of an imported mesh, drawn as its bounding box elsewhere
+    pub custom_mesh: Option<String>,
+    pub tags: Vec<String>,
+}
+
+// For Bevy apps loading scenes exported from Navi. Registers NaviShape and
+// gives each one a mesh, material and collider. The app needs the rapier
+// plugin too, for the body components in the scene
+pub struct NaviScenePlugin;
+
+impl Plugin for NaviScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<NaviShape>()
+            .add_systems(Update, build_navi_shapes_system);
+    }
+}
+
+pub fn build_navi_shapes_system(
+    mut commands: Commands,
+    shapes: Query<(Entity, &NaviShape), Added<NaviShape>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, shape) in &shapes {
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(shape.shape_type.mesh_with(&shape.dimensions))),
+            MeshMaterial3d(materials.add(object_material(shape.color))),
+            shape.shape_type.create_collider_with(&shape.dimensions),
+        ));
+    }
+}
+
+// Write every object as a Bevy DynamicScene (.scn.ron) for other Bevy apps.
+// Objects are flattened into world space
+#[derive(Event, Debug, Clone)]
+pub struct ExportDynamicSceneEvent {
+    pub path: PathBuf,
+}
+
+#[derive(Resource, Default)]
+pub struct DynamicSceneExport {
+    pub open: bool,
+    pub path: String,
+}
+
+impl DynamicSceneExport {
+    pub fn open_dialog(&mut self) {
+        if self.path.is_empty() {
+            self.path = default_scene_path()
+                .with_extension("scn.ron")
+                .display()
+                .to_string();
+        }
+        self.open = true;
+    }
+}
+
+// The scene is built in a world of its own holding only what gets exported,
+// so editor components never leak into the file
+fn build_dynamic_scene(
+    game_manager: &GameObjectManager,
+    transforms: &Query<&GlobalTransform>,
+    registry: &AppTypeRegistry,
+) -> Result<String, String> {
+    let mut scratch = World::new();
+    scratch.insert_resource(registry.clone());
+    let mut entities = Vec::new();
+    for obj in game_manager.iter() {
+        let transform = transforms
+            .get(obj.entity())
+            .map(|global| global.compute_transform())
+            .unwrap_or(Transform::from_translation(obj.position()).with_rotation(obj.rotation));
+        let mut tags: Vec<String> = obj.tags.iter().cloned().collect();
+        tags.sort_unstable();
+        let entity = scratch.spawn((
+            transform,
+            Name::new(obj.name.clone()),
+            NaviShape {
+                shape_type: obj.shape_type,
+                dimensions: obj.dimensions,
+                color: obj.color,
+                custom_mesh: obj.custom_mesh.clone(),
+                tags,
+            },
+            obj.physics.rigid_body(obj.physics_locked, false),
+            obj.physics.components(),
+        ));
+        entities.push(entity.id());
+    }
+
+    let scene = DynamicSceneBuilder::from_world(&scratch)
+        .deny_all()
+        .allow_component::<Transform>()
+        .allow_component::<Name>()
+        .allow_component::<NaviShape>()
+        .allow_component::<RigidBody>()
+        .allow_component::<Restitution>()
+        .allow_component::<Friction>()
+        .allow_component::<Damping>()
+        .allow_component::<GravityScale>()
+        .extract_entities(entities.into_iter())
+        .build();
+    scene
+        .serialize(&registry.read())
+        .map_err(|err| err.to_string())
+}
+
+pub fn export_dynamic_scene_system(
+    mut export_events: EventReader<ExportDynamicSceneEvent>,
+    game_manager: Res<GameObjectManager>,
+    transforms: Query<&GlobalTransform>,
+    registry: Res<AppTypeRegistry>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in export_events.read() {
+        let result = build_dynamic_scene(&game_manager, &transforms, &registry).and_then(|text| {
+            if let Some(dir) = event
+                .path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+            {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(&event.path, text).map_err(|err| err.to_string())
+        });
+        match result {
+            Ok(()) => {
+                info!(
+                    "Exported {} objects as a Bevy scene to {}",
+                    game_manager.iter().len(),
+                    event.path.display()
+                );
+                notifications.info(format!("Exported Bevy scene to {}", event.path.display()));
+            }
+            Err(err) => {
+                warn!("Bevy scene export failed: {}", err);
+                notifications.error(format!("Bevy scene export failed: {}", err));
+            }
+        }
+    }
+}
+
+pub fn dynamic_scene_export_dialog_system(
+    mut contexts: EguiContexts,
+    mut export: ResMut<DynamicSceneExport>,
+    mut export_events: EventWriter<ExportDynamicSceneEvent>,
+) {
+    if !export.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Export Bevy scene")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("File (.scn.ron)");
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut export.path).desired_width(360.0));
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                let path = export.path.trim().to_string();
+                if (ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Export"))
+                    .clicked()
+                    || entered)
+                    && !path.is_empty()
+                {
+                    export_events.write(ExportDynamicSceneEvent {
+                        path: PathBuf::from(path),
+                    });
+                    export.open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    export.open = false;
+                }
+            });
+        });
+}
+
+pub fn register_dynamic_scene_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Export Bevy scene...", None, |world| {
+        world.resource_mut::<DynamicSceneExport>().open_dialog();
+    });
+}
+
+// Export to a Bevy scene file. NaviShape is registered so it shows up in the
+// Components panel
+pub struct DynamicSceneExportPlugin;
+
+impl Plugin for DynamicSceneExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<NaviShape>()
+            .add_event::<ExportDynamicSceneEvent>()
+            .init_resource::<DynamicSceneExport>()
+            .add_systems(Startup, register_dynamic_scene_commands)
+            .add_systems(
+                EguiContextPass,
+                dynamic_scene_export_dialog_system.in_set(UiSet::Popups),
+            )
+            .add_systems(
+                Update,
+                export_dynamic_scene_system.in_set(EditorSet::Bookkeeping),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::{BodyKind, PhysicsProps};
+    use bevy::ecs::entity::EntityHashMap;
+    use bevy::ecs::system::SystemState;
+    use bevy::scene::serde::SceneDeserializer;
+    use serde::de::DeserializeSeed;
+
+    // What a Bevy app has registered after adding the rapier plugin and
+    // NaviScenePlugin
+    fn registry() -> AppTypeRegistry {
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Transform>();
+            registry.register::<Name>();
+            registry.register::<NaviShape>();
+            registry.register::<RigidBody>();
+            registry.register::<Restitution>();
+            registry.register::<Friction>();
+            registry.register::<Damping>();
+            registry.register::<GravityScale>();
+        }
+        registry
+    }
+
+    fn export(world: &mut World) -> String {
+        let mut state: SystemState<(Res<GameObjectManager>, Query<&GlobalTransform>)> =
+            SystemState::new(world);
+        let (game_manager, transforms) = state.get(world);
+        build_dynamic_scene(&game_manager, &transforms, &registry()).unwrap()
+    }
+
+    fn editor_world() -> World {
+        let mut world = World::new();
+        let mut game_manager = GameObjectManager::default();
+        let spots = [
+            (ShapeType::Cube, Vec3::new(1.0, 2.0, 3.0)),
+            (ShapeType::Ball, Vec3::new(-4.0, 0.5, 0.0)),
+        ];
+        for (shape_type, position) in spots {
+            let transform = Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_y(0.5))
+                .with_scale(Vec3::new(1.0, 2.0, 1.0));
+            let entity = world.spawn(GlobalTransform::from(transform)).id();
+            let id = game_manager
+                .add_object(entity, shape_type, position, None, false, 0.0)
+                .unwrap();
+            game_manager.add_tag(id, "exported").unwrap();
+            let obj = game_manager.get_object_by_id_mut(id).unwrap();
+            obj.color = Color::srgba(0.25, 0.5, 1.0, 1.0);
+        }
+        let crate_id = game_manager.iter().next().unwrap().id;
+        game_manager.get_object_by_id_mut(crate_id).unwrap().physics = PhysicsProps {
+            body: BodyKind::Fixed,
+            restitution: 0.25,
+            ..default()
+        };
+        world.insert_resource(game_manager);
+        world
+    }
+
+    #[test]
+    fn exported_scene_loads_in_a_plain_world() {
+        let mut editor = editor_world();
+        let text = export(&mut editor);
+
+        let registry = registry();
+        let mut deserializer = ron::Deserializer::from_str(&text).unwrap();
+        let scene = SceneDeserializer {
+            type_registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(scene.entities.len(), 2);
+
+        let mut world = World::new();
+        world.insert_resource(registry);
+        scene
+            .write_to_world(&mut world, &mut EntityHashMap::default())
+            .unwrap();
+
+        let game_manager = editor.resource::<GameObjectManager>();
+        let mut loaded: Vec<(&Name, &Transform, &NaviShape, &RigidBody, &Restitution)> = world
+            .query::<(&Name, &Transform, &NaviShape, &RigidBody, &Restitution)>()
+            .iter(&world)
+            .collect();
+        loaded.sort_by_key(|(name, ..)| name.as_str().to_string());
+        let mut objects: Vec<_> = game_manager.iter().collect();
+        objects.sort_by_key(|obj| obj.name.clone());
+        assert_eq!(loaded.len(), objects.len());
+
+        for ((name, transform, shape, body, restitution), obj) in loaded.into_iter().zip(objects) {
+            let expected = editor
+                .get::<GlobalTransform>(obj.entity())
+                .unwrap()
+                .compute_transform();
+            assert_eq!(name.as_str(), obj.name);
+            assert!(transform.translation.distance(expected.translation) < 1e-5);
+            assert!(transform.rotation.angle_between(expected.rotation) < 1e-5);
+            assert!(transform.scale.distance(expected.scale) < 1e-5);
+            assert_eq!(shape.shape_type, obj.shape_type);
+            assert_eq!(shape.dimensions, obj.dimensions);
+            assert_eq!(shape.color, obj.color);
+            assert_eq!(shape.tags, ["exported"]);
+            assert_eq!(*body, obj.physics.rigid_body(false, false));
+            assert_eq!(restitution.coefficient, obj.physics.restitution);
+        }
+    }
+
+    #[test]
+    fn editor_components_stay_out_of_the_file() {
+        let mut editor = editor_world();
+        let text = export(&mut editor);
+        assert!(text.contains("NaviShape"));
+        assert!(!text.contains("GameObjectId"));
+        assert!(!text.contains("GlobalTransform"));
+    }
+}
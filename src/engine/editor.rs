@@ -1,12 +1,45 @@
+use crate::engine::batch::{BatchSpawnEvent, BatchSpawnSettings, batch_spawn_ui};
+use crate::engine::core::UiSet;
+use crate::engine::history::{EditContext, EditorCommand, EditorHistory};
+use crate::engine::impulse::{ApplyImpulseEvent, ImpulseTool, impulse_ui};
+use crate::engine::input::{InputAction, InputBindings, key_name};
+use crate::engine::joints::{HingeDialog, JointEvent, JointRegistry, SpringTool, joints_ui};
+use crate::engine::layout::{EditorLayout, PanelContext};
+use crate::engine::lifetime::{Lifetime, LifetimeSettings, MIN_LIFETIME_SECS};
+use crate::engine::objects::{
+    BodyKind, ClearSceneEvent, DUPLICATE_OFFSET, DespawnEntityEvent, DespawnTarget,
+    DuplicateObjectEvent, GameObject, GameObjectManager, HierarchySettings, LockObjectEvent,
+    MAX_META_KEY_LEN, MIN_DENSITY, MIN_DIMENSION, MaterialHandles, MetaEditEvent, MetaValue,
+    NamingSettings, PhysicsProps, RECENT_SPAWN_SECS, RenameObjectEvent, ReparentEvent,
+    SelectedShape, SetVisibilityEvent, ShapeDimensions, ShapeParams, ShapeType, SharedMaterial,
+    SortKey, SpawnPhysics, SpawnSource, TagObjectEvent, format_age, set_material_color,
+    set_rigid_body, sorted_tags, wake_body,
+};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::placement::PlacementMode;
+use crate::engine::properties::PropertyClipboard;
+use crate::engine::scene::RestoreSceneEvent;
+use crate::engine::selection::Selection;
+use crate::engine::snap::SnapSettings;
+use crate::engine::templates::{spawn_door_event, spawn_rope_bridge_event};
+use crate::engine::toolbar::MainCamera;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_egui::*;
+use std::collections::{HashMap, HashSet};
 
-pub fn ui_example_system(mut contexts: EguiContexts) {
+pub fn ui_example_system(mut contexts: EguiContexts, mut layout: ResMut<EditorLayout>) {
     // Use the safer approach with proper error handling
-    egui::Window::new("Sanity Check")
-        .default_width(200.0)
-        .default_height(100.0)
-        .show(contexts.ctx_mut(), |ui| {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    layout.show(
+        ctx,
+        "Sanity Check",
+        egui::Window::new("Sanity Check")
+            .default_width(200.0)
+            .default_height(100.0),
+        |ui| {
             ui.label("If you see this, egui is fine");
 
             // Safe way to get available space
@@ -15,5 +48,1742 @@ pub fn ui_example_system(mut contexts: EguiContexts) {
                 "Available: {:.1} x {:.1}",
                 available_space.x, available_space.y
             ));
+        },
+    );
+}
+
+// Filter state for the hierarchy panel
+#[derive(Default)]
+pub struct HierarchyFilter {
+    pub name: String,
+    pub shape: Option<ShapeType>,
+    pub tag: Option<String>,
+    pub source: Option<SpawnSource>,
+}
+
+// Hierarchy toggles applied when locking or hiding objects
+#[derive(Default)]
+pub struct HierarchyOptions {
+    pub lock_physics: bool,
+    pub disable_hidden_colliders: bool,
+}
+
+// Hierarchy panel state kept between frames
+#[derive(Default)]
+pub struct HierarchyPanelState {
+    filter: HierarchyFilter,
+    options: HierarchyOptions,
+    // Objects waiting for the delete prompt to be answered
+    pending_delete: Vec<u32>,
+}
+
+// What the hierarchy rows send when clicked, dragged or picked from a menu
+#[derive(SystemParam)]
+pub struct HierarchyRequests<'w> {
+    pub despawn_events: EventWriter<'w, DespawnEntityEvent>,
+    pub lock_events: EventWriter<'w, LockObjectEvent>,
+    pub visibility_events: EventWriter<'w, SetVisibilityEvent>,
+    pub reparent_events: EventWriter<'w, ReparentEvent>,
+    pub duplicate_events: EventWriter<'w, DuplicateObjectEvent>,
+    pub clipboard: ResMut<'w, PropertyClipboard>,
+}
+
+// How the hierarchy rows are sorted and what they show besides the name
+#[derive(SystemParam)]
+pub struct HierarchyView<'w, 's> {
+    pub settings: ResMut<'w, HierarchySettings>,
+    pub camera: Query<'w, 's, &'static GlobalTransform, With<MainCamera>>,
+    pub joints: Res<'w, JointRegistry>,
+    pub time: Res<'w, Time>,
+}
+
+// Indent per level of the hierarchy tree
+const HIERARCHY_INDENT: f32 = 16.0;
+
+// Small rounded button showing one tag
+fn tag_chip(ui: &mut egui::Ui, text: &str) -> egui::Response {
+    ui.add(
+        egui::Button::new(egui::RichText::new(text).small())
+            .small()
+            .corner_radius(8.0),
+    )
+}
+
+// Drag and drop payload for reparenting in the hierarchy, the dragged object's id
+struct HierarchyDrag(u32);
+
+// Rows of the hierarchy tree as (object, depth), each parent followed by its
+// children. Siblings keep their order in `sorted`
+fn hierarchy_rows<'a>(
+    game_manager: &'a GameObjectManager,
+    sorted: &[&'a GameObject],
+) -> Vec<(&'a GameObject, usize)> {
+    let rank: HashMap<u32, usize> = sorted
+        .iter()
+        .enumerate()
+        .map(|(rank, obj)| (obj.id, rank))
+        .collect();
+    let mut rows = Vec::with_capacity(sorted.len());
+    let mut stack: Vec<(u32, usize)> = sorted
+        .iter()
+        .filter(|obj| obj.parent.is_none())
+        .map(|obj| (obj.id, 0))
+        .collect();
+    stack.reverse();
+    while let Some((id, depth)) = stack.pop() {
+        let Some(obj) = game_manager.get_object_by_id(id) else {
+            continue;
+        };
+        rows.push((obj, depth));
+        let mut children = obj.children.clone();
+        children.sort_by_key(|child| rank.get(child).copied().unwrap_or(usize::MAX));
+        stack.extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+    }
+    rows
+}
+
+// Column-style sort buttons, clicking the active one flips the direction
+fn sort_header_ui(ui: &mut egui::Ui, settings: &mut HierarchySettings, camera: Vec3) {
+    ui.horizontal(|ui| {
+        ui.label("Sort");
+        for key in [
+            SortKey::Id,
+            SortKey::Name,
+            SortKey::ShapeType,
+            SortKey::CreatedAt,
+            SortKey::DistanceToCamera(camera),
+        ] {
+            let active = settings.sort.same_kind(&key);
+            let text = match (active, settings.ascending) {
+                (true, true) => format!("{} ⏶", key.label()),
+                (true, false) => format!("{} ⏷", key.label()),
+                (false, _) => key.label().to_string(),
+            };
+            if ui.selectable_label(active, text).clicked() {
+                if active {
+                    settings.ascending = !settings.ascending;
+                } else {
+                    settings.ascending = true;
+                }
+                settings.sort = key;
+            }
+        }
+    });
+}
+
+// Hierarchy panel listing every object tracked by the GameObjectManager. Shown
+// as a tree unless filtered, dragging a row onto another reparents it
+pub fn hierarchy_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    game_manager: Res<GameObjectManager>,
+    mut selection: ResMut<Selection>,
+    HierarchyRequests {
+        mut despawn_events,
+        mut lock_events,
+        mut visibility_events,
+        mut reparent_events,
+        mut duplicate_events,
+        mut clipboard,
+    }: HierarchyRequests,
+    HierarchyView {
+        mut settings,
+        camera,
+        joints,
+        time,
+    }: HierarchyView,
+    mut state: Local<HierarchyPanelState>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let HierarchyPanelState {
+        filter,
+        options,
+        pending_delete,
+    } = &mut *state;
+
+    let now = time.elapsed_secs_f64();
+    let mut delete_request: Vec<u32> = Vec::new();
+
+    layout.show(
+        ctx,
+        "Hierarchy",
+        egui::Window::new("Hierarchy")
+            .default_width(260.0)
+            .default_height(320.0),
+        |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter");
+                ui.text_edit_singleline(&mut filter.name);
+            });
+            egui::ComboBox::from_label("Type")
+                .selected_text(filter.shape.map_or("All", |shape| shape.display_name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.shape, None, "All");
+                    for shape in ShapeType::all() {
+                        ui.selectable_value(&mut filter.shape, Some(shape), shape.display_name());
+                    }
+                });
+            egui::ComboBox::from_label("Tag")
+                .selected_text(filter.tag.as_deref().unwrap_or("All"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.tag, None, "All");
+                    for tag in game_manager.all_tags() {
+                        ui.selectable_value(&mut filter.tag, Some(tag.to_string()), tag);
+                    }
+                });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Source")
+                    .selected_text(filter.source.map_or("All", |source| source.label()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut filter.source, None, "All");
+                        for source in SpawnSource::all() {
+                            ui.selectable_value(&mut filter.source, Some(source), source.label());
+                        }
+                    });
+                let mut show_icons = settings.show_source_icons;
+                if ui.checkbox(&mut show_icons, "Icons").changed() {
+                    settings.show_source_icons = show_icons;
+                }
+                let mut show_ages = settings.show_ages;
+                if ui.checkbox(&mut show_ages, "Ages").changed() {
+                    settings.show_ages = show_ages;
+                }
+            });
+
+            let mut visible = game_manager.filter_objects(&filter.name, filter.shape);
+            if let Some(tag) = &filter.tag {
+                visible.retain(|obj| obj.tags.contains(tag));
+            }
+            if let Some(source) = filter.source {
+                visible.retain(|obj| obj.source == source);
+            }
+            ui.label(format!(
+                "{} of {} objects shown",
+                visible.len(),
+                game_manager.len()
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Select all").clicked() {
+                    selection.select_all(&game_manager);
+                }
+                if ui.button("Clear").clicked() {
+                    selection.clear();
+                }
+                let selected_ids = selection.ids(&game_manager);
+                if ui
+                    .add_enabled(
+                        !selected_ids.is_empty(),
+                        egui::Button::new("Delete selected"),
+                    )
+                    .clicked()
+                {
+                    delete_request = selected_ids;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.menu_button("Visibility", |ui| {
+                    for shape in ShapeType::all() {
+                        if ui
+                            .button(format!("Hide all {}s", shape.display_name().to_lowercase()))
+                            .clicked()
+                        {
+                            for obj in game_manager.get_objects_by_type(shape) {
+                                visibility_events.write(SetVisibilityEvent {
+                                    id: obj.id,
+                                    visible: false,
+                                    disable_collider: options.disable_hidden_colliders,
+                                });
+                            }
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Show all").clicked() {
+                        for obj in game_manager.iter().filter(|obj| obj.hidden) {
+                            visibility_events.write(SetVisibilityEvent {
+                                id: obj.id,
+                                visible: true,
+                                disable_collider: false,
+                            });
+                        }
+                        ui.close_menu();
+                    }
+                });
+                ui.checkbox(&mut options.lock_physics, "Lock physics")
+                    .on_hover_text("Newly locked objects are also frozen in place");
+                ui.checkbox(&mut options.disable_hidden_colliders, "Hide colliders")
+                    .on_hover_text("Newly hidden objects also stop colliding");
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .button(format!("Select last {:.0}s", RECENT_SPAWN_SECS))
+                    .on_hover_text("Select objects spawned recently")
+                    .clicked()
+                {
+                    selection.select_range(
+                        game_manager
+                            .objects_created_between(now - RECENT_SPAWN_SECS, now)
+                            .into_iter()
+                            .filter(|obj| !obj.locked)
+                            .map(|obj| obj.entity()),
+                    );
+                }
+                let mut max_age = settings.max_age_secs;
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut max_age)
+                            .range(0.0..=f64::MAX)
+                            .suffix("s"),
+                    )
+                    .changed()
+                {
+                    settings.max_age_secs = max_age;
+                }
+                if ui.button("Delete older").clicked() {
+                    delete_request = game_manager
+                        .objects_older_than(settings.max_age_secs, now)
+                        .into_iter()
+                        .filter(|obj| !obj.locked)
+                        .map(|obj| obj.id)
+                        .collect();
+                }
+            });
+            let mut delete_children = settings.delete_children;
+            if ui
+                .checkbox(&mut delete_children, "Delete children")
+                .on_hover_text("Deleting a parent also deletes everything under it")
+                .changed()
+            {
+                settings.delete_children = delete_children;
+            }
+            let camera_position = camera
+                .single()
+                .map_or(Vec3::ZERO, |camera| camera.translation());
+            // Distance sorting follows the camera as it moves
+            if let SortKey::DistanceToCamera(position) = settings.sort
+                && position != camera_position
+            {
+                settings.sort = SortKey::DistanceToCamera(camera_position);
+            }
+            sort_header_ui(ui, &mut settings, camera_position);
+            ui.separator();
+
+            let modifiers = ui.input(|i| i.modifiers);
+            // The tree only makes sense unfiltered, matches are listed flat
+            let filtering = !filter.name.trim().is_empty()
+                || filter.shape.is_some()
+                || filter.tag.is_some()
+                || filter.source.is_some();
+            let mut picked_tag = None;
+            let sorted = game_manager.sorted_objects(settings.sort, settings.ascending);
+            let rows = if filtering {
+                let visible: HashSet<u32> = visible.iter().map(|obj| obj.id).collect();
+                sorted
+                    .iter()
+                    .filter(|obj| visible.contains(&obj.id))
+                    .map(|obj| (*obj, 0))
+                    .collect()
+            } else {
+                hierarchy_rows(&game_manager, &sorted)
+            };
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for (row, &(object, depth)) in rows.iter().enumerate() {
+                        let is_selected = selection.contains(object.entity());
+                        let label = format!(
+                            "{}  (ID: {}, {})",
+                            object.name,
+                            object.id,
+                            object.shape_type.display_name()
+                        );
+
+                        ui.horizontal(|ui| {
+                            if ui
+                                .small_button("🗑")
+                                .on_hover_text("Delete (Shift-click skips confirmation)")
+                                .clicked()
+                            {
+                                delete_request = vec![object.id];
+                            }
+
+                            let (icon, hover) = if object.locked {
+                                ("🔒", "Unlock")
+                            } else {
+                                ("🔓", "Lock")
+                            };
+                            if ui.small_button(icon).on_hover_text(hover).clicked() {
+                                lock_events.write(LockObjectEvent {
+                                    id: object.id,
+                                    locked: !object.locked,
+                                    lock_physics: options.lock_physics,
+                                });
+                            }
+
+                            let eye = if object.hidden { "◌" } else { "👁" };
+                            let eye_hover = if object.hidden { "Show" } else { "Hide" };
+                            if ui.small_button(eye).on_hover_text(eye_hover).clicked() {
+                                visibility_events.write(SetVisibilityEvent {
+                                    id: object.id,
+                                    visible: object.hidden,
+                                    disable_collider: options.disable_hidden_colliders,
+                                });
+                            }
+                            ui.add_space(depth as f32 * HIERARCHY_INDENT);
+                            if settings.show_source_icons {
+                                ui.label(object.source.icon())
+                                    .on_hover_text(object.source.label());
+                            }
+                            if let Some(icon) = object.physics.body.icon() {
+                                ui.label(icon).on_hover_text(object.physics.body.label());
+                            }
+                            let partners: Vec<&str> = joints
+                                .of(object.id)
+                                .filter_map(|joint| {
+                                    game_manager.get_object_by_id(joint.partner(object.id))
+                                })
+                                .map(|partner| partner.name.as_str())
+                                .collect();
+                            if !partners.is_empty() {
+                                ui.label("🔗")
+                                    .on_hover_text(format!("Attached to {}", partners.join(", ")));
+                            }
+                            if settings.show_ages {
+                                ui.weak(format_age(object.age(now)));
+                            }
+
+                            // Locked rows stay visible but can't be picked
+                            let response = ui
+                                .add_enabled(
+                                    !object.locked,
+                                    egui::SelectableLabel::new(is_selected, label),
+                                )
+                                .interact(egui::Sense::drag());
+                            response.dnd_set_drag_payload(HierarchyDrag(object.id));
+                            if let Some(dragged) = response.dnd_hover_payload::<HierarchyDrag>()
+                                && dragged.0 != object.id
+                            {
+                                ui.painter().rect_stroke(
+                                    response.rect,
+                                    2.0,
+                                    ui.visuals().selection.stroke,
+                                    egui::StrokeKind::Outside,
+                                );
+                            }
+                            if let Some(dragged) = response.dnd_release_payload::<HierarchyDrag>()
+                                && dragged.0 != object.id
+                            {
+                                reparent_events.write(ReparentEvent {
+                                    id: dragged.0,
+                                    parent: Some(object.id),
+                                });
+                            }
+                            for tag in sorted_tags(&object.tags) {
+                                if tag_chip(ui, tag)
+                                    .on_hover_text("Show only this tag")
+                                    .clicked()
+                                {
+                                    picked_tag = Some(tag.to_string());
+                                }
+                            }
+                            if response.clicked() {
+                                if modifiers.ctrl {
+                                    selection.toggle(object.entity());
+                                } else if modifiers.shift {
+                                    // Range over the rows currently shown, from the anchor to here
+                                    let anchor_row = selection
+                                        .anchor
+                                        .and_then(|anchor| {
+                                            rows.iter().position(|(obj, _)| obj.entity() == anchor)
+                                        })
+                                        .unwrap_or(row);
+                                    let (start, end) = (anchor_row.min(row), anchor_row.max(row));
+                                    selection.select_range(
+                                        rows[start..=end]
+                                            .iter()
+                                            .filter(|(obj, _)| !obj.locked)
+                                            .map(|(obj, _)| obj.entity()),
+                                    );
+                                } else {
+                                    selection.select(object.entity());
+                                }
+                            }
+                            response.context_menu(|ui| {
+                                if ui.button("Duplicate").clicked() {
+                                    duplicate_events.write(DuplicateObjectEvent {
+                                        source_id: object.id,
+                                        offset: DUPLICATE_OFFSET,
+                                    });
+                                    ui.close_menu();
+                                }
+                                if ui.button("Copy properties").clicked() {
+                                    clipboard.source_id = Some(object.id);
+                                    ui.close_menu();
+                                }
+                                if object.parent.is_some() && ui.button("Unparent").clicked() {
+                                    reparent_events.write(ReparentEvent {
+                                        id: object.id,
+                                        parent: None,
+                                    });
+                                    ui.close_menu();
+                                }
+                                if ui.button("Delete").clicked() {
+                                    delete_request = vec![object.id];
+                                    ui.close_menu();
+                                }
+                            });
+                        });
+                    }
+
+                    if let Some(tag) = picked_tag {
+                        filter.tag = Some(tag);
+                    }
+                    if !filtering {
+                        let (_, dropped) = ui.dnd_drop_zone::<HierarchyDrag, ()>(
+                            egui::Frame::default().inner_margin(4.0),
+                            |ui| {
+                                ui.weak("Drop here to unparent");
+                            },
+                        );
+                        if let Some(dragged) = dropped {
+                            reparent_events.write(ReparentEvent {
+                                id: dragged.0,
+                                parent: None,
+                            });
+                        }
+                    }
+                });
+        },
+    );
+
+    // Shift skips the confirmation dialog for fast cleanup
+    if !delete_request.is_empty() {
+        if ctx.input(|i| i.modifiers.shift) {
+            for id in delete_request {
+                despawn_events.write(DespawnEntityEvent {
+                    target: DespawnTarget::Id(id),
+                });
+            }
+        } else {
+            *pending_delete = delete_request;
+        }
+    }
+
+    pending_delete.retain(|&id| game_manager.get_object_by_id(id).is_some());
+    if pending_delete.is_empty() {
+        return;
+    }
+
+    let prompt = match pending_delete.as_slice() {
+        [id] => {
+            let name = game_manager
+                .get_object_by_id(*id)
+                .map_or("", |obj| obj.name.as_str());
+            let children = if settings.delete_children {
+                game_manager.descendants(*id).len()
+            } else {
+                0
+            };
+            match children {
+                0 => format!("Delete \"{}\" (ID: {})?", name, id),
+                n => format!("Delete \"{}\" (ID: {}) and its {} children?", name, id, n),
+            }
+        }
+        ids => format!("Delete {} objects?", ids.len()),
+    };
+
+    egui::Window::new("Confirm delete")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(prompt);
+            ui.horizontal(|ui| {
+                if ui.button("Delete").clicked() {
+                    for id in pending_delete.drain(..) {
+                        despawn_events.write(DespawnEntityEvent {
+                            target: DespawnTarget::Id(id),
+                        });
+                    }
+                }
+                if ui
+                    .button("Move to trash")
+                    .on_hover_text("Restore it later from the Trash panel")
+                    .clicked()
+                {
+                    despawn_events.write(DespawnEntityEvent {
+                        target: DespawnTarget::Trash(std::mem::take(&mut *pending_delete)),
+                    });
+                }
+                if ui.button("Cancel").clicked() {
+                    pending_delete.clear();
+                }
+            });
+        });
+}
+
+// Transform edit made in the inspector, applied outside of the egui pass
+#[derive(Event)]
+pub struct TransformEditEvent {
+    pub entity: Entity,
+    pub transform: Transform,
+}
+
+// Material color edit made in the inspector
+#[derive(Event)]
+pub struct MaterialEditEvent {
+    pub entity: Entity,
+    pub color: Color,
+}
+
+// Physics property edit made in the inspector
+#[derive(Event)]
+pub struct PhysicsEditEvent {
+    pub entity: Entity,
+    pub props: PhysicsProps,
+}
+
+// Shape size edit from the inspector or the scale gizmo, rebuilds mesh and collider
+#[derive(Event)]
+pub struct DimensionEditEvent {
+    pub entity: Entity,
+    pub dimensions: ShapeDimensions,
+}
+
+// Edit applied to every selected object in one go. Only the fields the user
+// touched are set, the rest keep each object's own value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkEdit {
+    pub body: Option<BodyKind>,
+    pub restitution: Option<f32>,
+    pub friction: Option<f32>,
+    pub linear_damping: Option<f32>,
+    pub angular_damping: Option<f32>,
+    pub gravity_scale: Option<f32>,
+    pub color: Option<Color>,
+    pub offset: Option<Vec3>,
+}
+
+impl BulkEdit {
+    fn touches_physics(&self) -> bool {
+        self.body.is_some()
+            || self.restitution.is_some()
+            || self.friction.is_some()
+            || self.linear_damping.is_some()
+            || self.angular_damping.is_some()
+            || self.gravity_scale.is_some()
+    }
+
+    fn apply_physics(&self, props: &mut PhysicsProps) {
+        if let Some(body) = self.body {
+            props.body = body;
+        }
+        if let Some(value) = self.restitution {
+            props.restitution = value;
+        }
+        if let Some(value) = self.friction {
+            props.friction = value;
+        }
+        if let Some(value) = self.linear_damping {
+            props.linear_damping = value;
+        }
+        if let Some(value) = self.angular_damping {
+            props.angular_damping = value;
+        }
+        if let Some(value) = self.gravity_scale {
+            props.gravity_scale = value;
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct BulkEditEvent {
+    pub entities: Vec<Entity>,
+    pub edit: BulkEdit,
+}
+
+// Text typed into the inspector's add fields
+#[derive(Default)]
+pub struct InspectorBuffers {
+    tag: String,
+    meta_key: String,
+    meta_value: String,
+}
+
+// Editable key/value table. Values keep their type, new entries are parsed
+// with MetaValue::parse
+fn metadata_ui(
+    ui: &mut egui::Ui,
+    object: &GameObject,
+    buffers: &mut InspectorBuffers,
+    meta_events: &mut EventWriter<MetaEditEvent>,
+) {
+    egui::CollapsingHeader::new(format!("Metadata ({})", object.metadata.len()))
+        .id_salt("inspector_metadata")
+        .show(ui, |ui| {
+            let mut keys: Vec<&String> = object.metadata.keys().collect();
+            keys.sort_unstable();
+            egui::Grid::new("metadata_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for key in keys {
+                        let value = &object.metadata[key];
+                        ui.label(key).on_hover_text(value.type_name());
+                        let mut edited = value.clone();
+                        let changed = match &mut edited {
+                            MetaValue::Bool(value) => ui.checkbox(value, "").changed(),
+                            MetaValue::Number(value) => {
+                                ui.add(egui::DragValue::new(value).speed(0.1)).changed()
+                            }
+                            MetaValue::String(value) => ui
+                                .add(egui::TextEdit::singleline(value).desired_width(100.0))
+                                .changed(),
+                        };
+                        if changed {
+                            meta_events.write(MetaEditEvent {
+                                id: object.id,
+                                key: key.clone(),
+                                value: Some(edited),
+                            });
+                        }
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            meta_events.write(MetaEditEvent {
+                                id: object.id,
+                                key: key.clone(),
+                                value: None,
+                            });
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut buffers.meta_key)
+                        .hint_text("Key")
+                        .char_limit(MAX_META_KEY_LEN)
+                        .desired_width(70.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut buffers.meta_value)
+                        .hint_text("Value")
+                        .desired_width(90.0),
+                );
+                if ui
+                    .add_enabled(
+                        !buffers.meta_key.trim().is_empty(),
+                        egui::Button::new("Set"),
+                    )
+                    .on_hover_text("true/false and numbers are stored as such")
+                    .clicked()
+                {
+                    meta_events.write(MetaEditEvent {
+                        id: object.id,
+                        key: std::mem::take(&mut buffers.meta_key),
+                        value: Some(MetaValue::parse(&std::mem::take(&mut buffers.meta_value))),
+                    });
+                }
+            });
+        });
+}
+
+// Values being dragged in the multi-selection inspector, sent once released
+#[derive(Default)]
+pub struct BulkEditState {
+    pending: BulkEdit,
+    offset: Vec3,
+}
+
+// Inspector state kept between frames
+#[derive(Default)]
+pub struct InspectorState {
+    // The selected object's name as typed, committed on Enter or focus loss
+    rename_buffer: Option<(u32, String)>,
+    buffers: InspectorBuffers,
+    bulk_state: BulkEditState,
+}
+
+// Edits the inspector sends for the objects it shows
+#[derive(SystemParam)]
+pub struct InspectorEdits<'w> {
+    pub edit_events: EventWriter<'w, TransformEditEvent>,
+    pub material_events: EventWriter<'w, MaterialEditEvent>,
+    pub physics_events: EventWriter<'w, PhysicsEditEvent>,
+    pub dimension_events: EventWriter<'w, DimensionEditEvent>,
+    pub rename_events: EventWriter<'w, RenameObjectEvent>,
+    pub tag_events: EventWriter<'w, TagObjectEvent>,
+    pub meta_events: EventWriter<'w, MetaEditEvent>,
+    pub bulk_events: EventWriter<'w, BulkEditEvent>,
+}
+
+// Impulse and joint tools, shown when several objects are selected
+#[derive(SystemParam)]
+pub struct InspectorTools<'w, 's> {
+    pub impulse_tool: ResMut<'w, ImpulseTool>,
+    pub impulse_events: EventWriter<'w, ApplyImpulseEvent>,
+    pub camera: Query<'w, 's, &'static Transform, With<MainCamera>>,
+    pub joints: Res<'w, JointRegistry>,
+    pub joint_events: EventWriter<'w, JointEvent>,
+    pub hinge_dialog: ResMut<'w, HingeDialog>,
+    pub spring_tool: ResMut<'w, SpringTool>,
+}
+
+// Inspector panel for the selected object's transform, material and physics
+pub fn inspector_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    transforms: Query<(&Transform, Option<&Lifetime>)>,
+    InspectorEdits {
+        mut edit_events,
+        mut material_events,
+        mut physics_events,
+        mut dimension_events,
+        mut rename_events,
+        mut tag_events,
+        mut meta_events,
+        mut bulk_events,
+    }: InspectorEdits,
+    InspectorTools {
+        mut impulse_tool,
+        mut impulse_events,
+        camera,
+        joints,
+        mut joint_events,
+        mut hinge_dialog,
+        mut spring_tool,
+    }: InspectorTools,
+    mut state: Local<InspectorState>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let InspectorState {
+        rename_buffer,
+        buffers,
+        bulk_state,
+    } = &mut *state;
+
+    layout.show(
+        ctx,
+        "Inspector",
+        egui::Window::new("Inspector").default_width(260.0),
+        |ui| {
+            if selection.len() > 1 {
+                multi_selection_ui(ui, &selection, &game_manager, bulk_state, &mut bulk_events);
+                ui.separator();
+                if let Some(event) = impulse_ui(ui, &mut impulse_tool, camera.single().ok()) {
+                    impulse_events.write(event);
+                }
+                if let Some(event) = joints_ui(
+                    ui,
+                    &selection,
+                    &game_manager,
+                    &joints,
+                    &mut hinge_dialog,
+                    &mut spring_tool,
+                ) {
+                    joint_events.write(event);
+                }
+                return;
+            }
+            // Drop anything left pending from a multi-selection
+            *bulk_state = BulkEditState::default();
+            let Some(entity) = selection.primary() else {
+                ui.label("No object selected");
+                return;
+            };
+            let (Some(object), Ok((transform, lifetime))) = (
+                game_manager.get_object_by_entity(entity),
+                transforms.get(entity),
+            ) else {
+                ui.label("Selected object no longer exists");
+                return;
+            };
+
+            ui.label(format!(
+                "{} (ID: {}, {})",
+                object.name,
+                object.id,
+                object.shape_type.display_name()
+            ));
+            if let Some(prefab) = &object.prefab {
+                ui.weak(format!("Prefab: {}", prefab));
+            }
+            ui.weak(format!("Created by: {}", object.source.label()));
+            ui.weak(format!(
+                "Velocity: {:.2} m/s, spin {:.2} rad/s, energy ~{:.2} J",
+                object.speed(),
+                object.angvel.length(),
+                object.kinetic_energy()
+            ))
+            .on_hover_text(format!(
+                "Linear {:.2?}\nAngular {:.2?}\nEnergy from an estimated mass of {:.2} kg",
+                object.linvel,
+                object.angvel,
+                object.estimated_mass()
+            ));
+            if let Some(lifetime) = lifetime {
+                ui.add(
+                    egui::ProgressBar::new(1.0 - lifetime.fraction_elapsed())
+                        .text(format!("Expires in {:.1}s", lifetime.remaining.max(0.0))),
+                );
+            }
+            if object.locked {
+                ui.label("🔒 Locked, unlock it in the hierarchy to edit");
+                return;
+            }
+
+            // Rename buffer follows the selection and is committed on Enter or focus loss
+            if rename_buffer
+                .as_ref()
+                .is_none_or(|(id, _)| *id != object.id)
+            {
+                *rename_buffer = Some((object.id, object.name.clone()));
+            }
+            if let Some((_, buffer)) = rename_buffer.as_mut() {
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    let response = ui.text_edit_singleline(buffer);
+                    if response.lost_focus() && *buffer != object.name {
+                        rename_events.write(RenameObjectEvent {
+                            id: object.id,
+                            new_name: buffer.clone(),
+                        });
+                    } else if !response.has_focus() && *buffer != object.name {
+                        *buffer = object.name.clone();
+                    }
+                });
+            }
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Tags");
+                for tag in sorted_tags(&object.tags) {
+                    if tag_chip(ui, &format!("{} ✖", tag))
+                        .on_hover_text("Remove tag")
+                        .clicked()
+                    {
+                        tag_events.write(TagObjectEvent {
+                            id: object.id,
+                            tag: tag.to_string(),
+                            add: false,
+                        });
+                    }
+                }
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut buffers.tag)
+                        .hint_text("Add tag")
+                        .desired_width(80.0),
+                );
+                if response.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    && !buffers.tag.trim().is_empty()
+                {
+                    tag_events.write(TagObjectEvent {
+                        id: object.id,
+                        tag: std::mem::take(&mut buffers.tag),
+                        add: true,
+                    });
+                }
+            });
+            metadata_ui(ui, object, buffers, &mut meta_events);
+            ui.separator();
+
+            // Work on a copy of the live transform and only send it back if edited
+            let mut position = transform.translation;
+            let (rx, ry, rz) = transform.rotation.to_euler(EulerRot::XYZ);
+            let mut rotation = Vec3::new(rx.to_degrees(), ry.to_degrees(), rz.to_degrees());
+            let mut scale = transform.scale;
+
+            let mut changed = false;
+            changed |= vec3_row(ui, "Position", &mut position, 0.05);
+            changed |= vec3_row(ui, "Rotation", &mut rotation, 1.0);
+            changed |= vec3_row(ui, "Scale", &mut scale, 0.01);
+
+            if changed {
+                edit_events.write(TransformEditEvent {
+                    entity,
+                    transform: Transform {
+                        translation: position,
+                        rotation: Quat::from_euler(
+                            EulerRot::XYZ,
+                            rotation.x.to_radians(),
+                            rotation.y.to_radians(),
+                            rotation.z.to_radians(),
+                        ),
+                        scale: scale.max(Vec3::splat(0.01)),
+                    },
+                });
+            }
+
+            ui.separator();
+            let mut dimensions = object.dimensions;
+            if dimensions_ui(ui, object.shape_type, &mut dimensions) {
+                dimension_events.write(DimensionEditEvent { entity, dimensions });
+            }
+
+            ui.separator();
+            let mut rgba = object.color.to_srgba().to_f32_array();
+            let mut color_changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                color_changed |= ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed();
+            });
+            color_changed |= ui
+                .add(egui::Slider::new(&mut rgba[3], 0.0..=1.0).text("Alpha"))
+                .changed();
+
+            if color_changed {
+                material_events.write(MaterialEditEvent {
+                    entity,
+                    color: Color::srgba(rgba[0], rgba[1], rgba[2], rgba[3]),
+                });
+            }
+
+            ui.separator();
+            let mut props = object.physics;
+            if physics_props_ui(ui, &mut props) {
+                physics_events.write(PhysicsEditEvent { entity, props });
+            }
+            if let Some(event) = impulse_ui(ui, &mut impulse_tool, camera.single().ok()) {
+                impulse_events.write(event);
+            }
+            if let Some(event) = joints_ui(
+                ui,
+                &selection,
+                &game_manager,
+                &joints,
+                &mut hinge_dialog,
+                &mut spring_tool,
+            ) {
+                joint_events.write(event);
+            }
+        },
+    );
+}
+
+// Dimension fields relevant to the given shape, returns true if any changed
+fn dimensions_ui(ui: &mut egui::Ui, shape_type: ShapeType, dims: &mut ShapeDimensions) -> bool {
+    let mut changed = false;
+    let mut field = |ui: &mut egui::Ui, label: &str, value: &mut f32| {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            changed |= ui
+                .add(
+                    egui::DragValue::new(value)
+                        .speed(0.01)
+                        .range(MIN_DIMENSION..=100.0),
+                )
+                .changed();
+        });
+    };
+
+    match shape_type {
+        ShapeType::Ball => field(ui, "Radius", &mut dims.radius),
+        ShapeType::Cube => {
+            field(ui, "Half X", &mut dims.half_extents.x);
+            field(ui, "Half Y", &mut dims.half_extents.y);
+            field(ui, "Half Z", &mut dims.half_extents.z);
+        }
+        ShapeType::Capsule | ShapeType::Cylinder | ShapeType::Cone => {
+            field(ui, "Radius", &mut dims.radius);
+            field(ui, "Height", &mut dims.height);
+        }
+        ShapeType::CustomMesh => {
+            ui.weak("Imported meshes keep their modelled size");
+        }
+    }
+    changed
+}
+
+// Value shared by every object, None when they differ
+fn shared_value<T: PartialEq + Copy>(
+    objects: &[&GameObject],
+    value: impl Fn(&GameObject) -> T,
+) -> Option<T> {
+    let first = value(objects.first()?);
+    objects
+        .iter()
+        .all(|obj| value(obj) == first)
+        .then_some(first)
+}
+
+// Drag value that reads "mixed" until touched. Edits are held in `pending`
+// while dragging and returned once the drag ends or the text box loses focus
+fn bulk_value_row(
+    ui: &mut egui::Ui,
+    label: &str,
+    shared: Option<f32>,
+    fallback: f32,
+    pending: &mut Option<f32>,
+    range: std::ops::RangeInclusive<f32>,
+) -> Option<f32> {
+    let mixed = shared.is_none() && pending.is_none();
+    let mut value = pending.or(shared).unwrap_or(fallback);
+    let response = ui
+        .horizontal(|ui| {
+            ui.label(label);
+            let mut drag = egui::DragValue::new(&mut value).speed(0.01).range(range);
+            if mixed {
+                drag = drag.custom_formatter(|_, _| "mixed".to_string());
+            }
+            ui.add(drag)
+        })
+        .inner;
+    if response.changed() {
+        *pending = Some(value);
+    }
+    if response.dragged() || response.has_focus() {
+        return None;
+    }
+    pending.take()
+}
+
+// Shared properties of every selected object, edits go to all of them
+fn multi_selection_ui(
+    ui: &mut egui::Ui,
+    selection: &Selection,
+    game_manager: &GameObjectManager,
+    state: &mut BulkEditState,
+    bulk_events: &mut EventWriter<BulkEditEvent>,
+) {
+    let objects: Vec<&GameObject> = selection
+        .entities
+        .iter()
+        .filter_map(|&entity| game_manager.get_object_by_entity(entity))
+        .filter(|obj| !obj.locked)
+        .collect();
+    ui.label(format!("{} objects selected", objects.len()));
+    ui.separator();
+
+    let Some(first) = objects.first() else {
+        return;
+    };
+    let shared_shape = shared_value(&objects, |obj| obj.shape_type);
+    ui.label(format!(
+        "Shape: {}",
+        shared_shape.map_or("(mixed)", |shape| shape.display_name())
+    ));
+
+    let mut edit = BulkEdit::default();
+
+    ui.horizontal(|ui| {
+        ui.label("Offset");
+        for value in [
+            &mut state.offset.x,
+            &mut state.offset.y,
+            &mut state.offset.z,
+        ] {
+            ui.add(egui::DragValue::new(value).speed(0.05));
+        }
+        if ui
+            .add_enabled(state.offset != Vec3::ZERO, egui::Button::new("Apply"))
+            .on_hover_text("Move every selected object by this amount")
+            .clicked()
+        {
+            edit.offset = Some(state.offset);
+            state.offset = Vec3::ZERO;
+        }
+    });
+    ui.separator();
+
+    // Color commits when the picker closes, not on every hue change
+    let shared_color = shared_value(&objects, |obj| obj.color);
+    let mut rgba = state
+        .pending
+        .color
+        .or(shared_color)
+        .unwrap_or(first.color)
+        .to_srgba()
+        .to_f32_array();
+    ui.horizontal(|ui| {
+        ui.label("Color");
+        if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+            state.pending.color = Some(Color::srgba(rgba[0], rgba[1], rgba[2], rgba[3]));
+        }
+        if shared_color.is_none() && state.pending.color.is_none() {
+            ui.weak("mixed");
+        }
+    });
+    if state.pending.color.is_some() && !ui.memory(|m| m.any_popup_open()) {
+        edit.color = state.pending.color.take();
+    }
+    ui.separator();
+
+    ui.label("Physics");
+    let shared_body = shared_value(&objects, |obj| obj.physics.body);
+    ui.horizontal(|ui| {
+        ui.label("Body");
+        egui::ComboBox::from_id_salt("bulk_body")
+            .selected_text(shared_body.map_or("mixed", |body| body.label()))
+            .show_ui(ui, |ui| {
+                for body in BodyKind::all() {
+                    if ui
+                        .selectable_label(shared_body == Some(body), body.label())
+                        .clicked()
+                    {
+                        edit.body = Some(body);
+                    }
+                }
+            });
+    });
+
+    let props = first.physics;
+    edit.restitution = bulk_value_row(
+        ui,
+        "Restitution",
+        shared_value(&objects, |obj| obj.physics.restitution),
+        props.restitution,
+        &mut state.pending.restitution,
+        0.0..=1.0,
+    );
+    edit.friction = bulk_value_row(
+        ui,
+        "Friction",
+        shared_value(&objects, |obj| obj.physics.friction),
+        props.friction,
+        &mut state.pending.friction,
+        0.0..=2.0,
+    );
+    edit.linear_damping = bulk_value_row(
+        ui,
+        "Linear damping",
+        shared_value(&objects, |obj| obj.physics.linear_damping),
+        props.linear_damping,
+        &mut state.pending.linear_damping,
+        0.0..=10.0,
+    );
+    edit.angular_damping = bulk_value_row(
+        ui,
+        "Angular damping",
+        shared_value(&objects, |obj| obj.physics.angular_damping),
+        props.angular_damping,
+        &mut state.pending.angular_damping,
+        0.0..=10.0,
+    );
+    edit.gravity_scale = bulk_value_row(
+        ui,
+        "Gravity scale",
+        shared_value(&objects, |obj| obj.physics.gravity_scale),
+        props.gravity_scale,
+        &mut state.pending.gravity_scale,
+        -2.0..=2.0,
+    );
+
+    if edit != BulkEdit::default() {
+        bulk_events.write(BulkEditEvent {
+            entities: objects.iter().map(|obj| obj.entity()).collect(),
+            edit,
         });
+    }
+}
+
+// Sliders for physics properties, the value boxes accept typed exact values
+fn physics_props_ui(ui: &mut egui::Ui, props: &mut PhysicsProps) -> bool {
+    let mut changed = false;
+    ui.label("Physics");
+    changed |= ui
+        .add(egui::Slider::new(&mut props.restitution, 0.0..=1.0).text("Restitution"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut props.friction, 0.0..=2.0).text("Friction"))
+        .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut props.density, MIN_DENSITY..=100.0)
+                .logarithmic(true)
+                .text("Density"),
+        )
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut props.linear_damping, 0.0..=10.0).text("Linear damping"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut props.angular_damping, 0.0..=10.0).text("Angular damping"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut props.gravity_scale, -2.0..=2.0).text("Gravity scale"))
+        .changed();
+    ui.horizontal(|ui| {
+        ui.label("Body");
+        for body in BodyKind::all() {
+            changed |= ui
+                .selectable_value(&mut props.body, body, body.label())
+                .changed();
+        }
+    });
+    changed
+}
+
+// Labelled row of x/y/z drag values, returns true if any were changed
+fn vec3_row(ui: &mut egui::Ui, label: &str, value: &mut Vec3, speed: f32) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut value.x)
+                    .speed(speed)
+                    .prefix("x: "),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut value.y)
+                    .speed(speed)
+                    .prefix("y: "),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut value.z)
+                    .speed(speed)
+                    .prefix("z: "),
+            )
+            .changed();
+    });
+    changed
+}
+
+// Commit inspector transform edits to the entity and the manager
+pub fn apply_transform_edits_system(
+    mut commands: Commands,
+    mut edit_events: EventReader<TransformEditEvent>,
+    mut transforms: Query<&mut Transform>,
+    game_manager: Res<GameObjectManager>,
+    mut history: ResMut<EditorHistory>,
+    snap: Res<SnapSettings>,
+    time: Res<Time>,
+) {
+    for event in edit_events.read() {
+        if game_manager
+            .get_object_by_entity(event.entity)
+            .is_some_and(|obj| obj.locked)
+        {
+            continue;
+        }
+        let Ok(mut transform) = transforms.get_mut(event.entity) else {
+            continue;
+        };
+        let before = *transform;
+        let after = Transform {
+            translation: snap.snap_position(event.transform.translation),
+            rotation: snap.snap_rotation(event.transform.rotation),
+            scale: event.transform.scale,
+        };
+        *transform = after;
+
+        // The translation is local to any parent, update_object_positions_system
+        // works out the world position the manager and its index hold
+        if let Some(obj) = game_manager.get_object_by_entity(event.entity) {
+            history.push_transform(obj.id, before, after, time.elapsed_secs_f64());
+        }
+
+        // Sleeping bodies ignore teleports until woken
+        wake_body(&mut commands, event.entity);
+    }
+}
+
+// Settings the spawn panel edits
+#[derive(SystemParam)]
+pub struct SpawnPanelSettings<'w> {
+    pub selected_shape: ResMut<'w, SelectedShape>,
+    pub shape_params: ResMut<'w, ShapeParams>,
+    pub spawn_physics: ResMut<'w, SpawnPhysics>,
+    pub placement: ResMut<'w, PlacementMode>,
+    pub naming: ResMut<'w, NamingSettings>,
+    pub lifetime: ResMut<'w, LifetimeSettings>,
+    pub batch: ResMut<'w, BatchSpawnSettings>,
+}
+
+// Spawn settings panel with a dropdown mirroring SelectedShape
+pub fn spawn_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    SpawnPanelSettings {
+        mut selected_shape,
+        mut shape_params,
+        mut spawn_physics,
+        mut placement,
+        mut naming,
+        mut lifetime,
+        mut batch,
+    }: SpawnPanelSettings,
+    mut batch_events: EventWriter<BatchSpawnEvent>,
+    bindings: Res<InputBindings>,
+    mut restore_events: EventWriter<RestoreSceneEvent>,
+    camera: Query<&Transform, With<MainCamera>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    // Tab also moves egui focus, so lock the dropdown while a text field has it
+    let text_has_focus = ctx.wants_keyboard_input();
+
+    layout.show(
+        ctx,
+        "Spawn",
+        egui::Window::new("Spawn").default_width(220.0),
+        |ui| {
+            ui.label(format!(
+                "Selected shape: {}",
+                selected_shape.shape_type.display_name()
+            ));
+
+            ui.add_enabled_ui(!text_has_focus, |ui| {
+                let mut shape_type = selected_shape.shape_type;
+                egui::ComboBox::from_label("Shape")
+                    .selected_text(shape_type.display_name())
+                    .show_ui(ui, |ui| {
+                        for shape in ShapeType::all() {
+                            ui.selectable_value(&mut shape_type, shape, shape.display_name());
+                        }
+                    });
+
+                // Only write on change so Tab cycling isn't clobbered
+                if shape_type != selected_shape.shape_type {
+                    selected_shape.shape_type = shape_type;
+                    info!("Selected shape: {}", shape_type.display_name());
+                }
+            });
+
+            let mut placing = placement.active;
+            let toggle = ui
+                .checkbox(&mut placing, "Click to place")
+                .on_hover_text(format!(
+                    "Preview the shape under the cursor, click or press Space to spawn it ({})",
+                    key_name(bindings.key(InputAction::PlacementMode))
+                ));
+            if toggle.changed() {
+                placement.toggle();
+            }
+            let mut unique_names = naming.unique_names;
+            if ui
+                .checkbox(&mut unique_names, "Unique names")
+                .on_hover_text("Number repeated names like bob.001, bob.002")
+                .changed()
+            {
+                naming.unique_names = unique_names;
+            }
+            let mut settings = lifetime.clone();
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.keyboard_spawns, "Lifetime")
+                    .on_hover_text("Objects spawned from the keyboard despawn after this long");
+                ui.add_enabled(
+                    settings.keyboard_spawns,
+                    egui::DragValue::new(&mut settings.default_secs)
+                        .speed(0.1)
+                        .range(MIN_LIFETIME_SECS..=f32::MAX)
+                        .suffix(" s"),
+                );
+            });
+            if settings.keyboard_spawns != lifetime.keyboard_spawns
+                || settings.default_secs != lifetime.default_secs
+            {
+                *lifetime = settings;
+            }
+
+            ui.separator();
+            let shape_type = selected_shape.shape_type;
+            let mut dims = shape_params.get(shape_type);
+            if dimensions_ui(ui, shape_type, &mut dims) {
+                shape_params.set(shape_type, dims);
+            }
+            if ui.button("Reset size").clicked() {
+                shape_params.set(shape_type, shape_type.default_dimensions());
+            }
+
+            egui::CollapsingHeader::new("Spawn physics").show(ui, |ui| {
+                let mut props = spawn_physics.props;
+                if physics_props_ui(ui, &mut props) {
+                    spawn_physics.props = props.clamped().0;
+                }
+                if ui.button("Reset physics").clicked() {
+                    spawn_physics.props = PhysicsProps::default();
+                }
+            });
+
+            egui::CollapsingHeader::new("Batch spawn").show(ui, |ui| {
+                if batch_spawn_ui(ui, &mut batch) {
+                    batch_events.write(batch.event(shape_type, shape_params.get(shape_type)));
+                }
+            });
+
+            egui::CollapsingHeader::new("Presets").show(ui, |ui| {
+                if let Ok(camera) = camera.single()
+                    && ui
+                        .button("Door")
+                        .on_hover_text("A panel hinged to a fixed post, in front of the camera")
+                        .clicked()
+                {
+                    restore_events.write(spawn_door_event(camera));
+                }
+                if let Ok(camera) = camera.single()
+                    && ui
+                        .button("Rope bridge")
+                        .on_hover_text("Planks chained with springs between two fixed anchors")
+                        .clicked()
+                {
+                    restore_events.write(spawn_rope_bridge_event(camera));
+                }
+            });
+
+            ui.separator();
+            ui.label("Tab cycles shapes, Space spawns");
+        },
+    );
+}
+
+// Write inspector color edits into the entity's own material
+pub fn apply_material_edits_system(
+    mut commands: Commands,
+    mut edit_events: EventReader<MaterialEditEvent>,
+    material_handles: Query<(&MeshMaterial3d<StandardMaterial>, Has<SharedMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut game_manager: ResMut<GameObjectManager>,
+) {
+    for event in edit_events.read() {
+        // Single spawns create their own material, batch spawned objects get
+        // one split off here, so the edit only affects this object
+        let Ok((handle, shared)) = material_handles.get(event.entity) else {
+            continue;
+        };
+        set_material_color(
+            &mut commands,
+            &mut materials,
+            event.entity,
+            handle,
+            shared,
+            event.color,
+        );
+
+        if let Some(obj) = game_manager.get_object_by_entity_mut(event.entity) {
+            obj.color = event.color;
+        }
+    }
+}
+
+// Insert the edited rapier components and mirror them on the GameObject
+pub fn apply_physics_edits_system(
+    mut commands: Commands,
+    mut edit_events: EventReader<PhysicsEditEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+) {
+    for event in edit_events.read() {
+        let Some(obj) = game_manager.get_object_by_entity_mut(event.entity) else {
+            continue;
+        };
+        if obj.physics.body != event.props.body {
+            set_rigid_body(
+                &mut commands,
+                event.entity,
+                event
+                    .props
+                    .rigid_body(obj.physics_locked, obj.parent.is_some()),
+            );
+        }
+        obj.physics = event.props;
+
+        commands
+            .entity(event.entity)
+            .insert(event.props.components());
+        wake_body(&mut commands, event.entity);
+    }
+}
+
+// Apply a multi-selection edit to every object at once. Offsets become a single
+// undo step, the other fields only touch the components they change
+pub fn apply_bulk_edits_system(
+    mut commands: Commands,
+    mut edit_events: EventReader<BulkEditEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut transforms: Query<&mut Transform>,
+    material_handles: MaterialHandles,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    EditContext {
+        mut history, time, ..
+    }: EditContext,
+) {
+    for event in edit_events.read() {
+        let edit = &event.edit;
+        let mut moves = Vec::new();
+        for &entity in &event.entities {
+            let Some(obj) = game_manager.get_object_by_entity_mut(entity) else {
+                continue;
+            };
+            if obj.locked {
+                continue;
+            }
+
+            if edit.touches_physics() {
+                let mut props = obj.physics;
+                edit.apply_physics(&mut props);
+                if props.body != obj.physics.body {
+                    set_rigid_body(
+                        &mut commands,
+                        entity,
+                        props.rigid_body(obj.physics_locked, obj.parent.is_some()),
+                    );
+                }
+                obj.physics = props;
+                commands.entity(entity).insert(props.components());
+            }
+
+            if let Some(color) = edit.color {
+                obj.color = color;
+                if let Ok((handle, shared)) = material_handles.get(entity) {
+                    set_material_color(
+                        &mut commands,
+                        &mut materials,
+                        entity,
+                        handle,
+                        shared,
+                        color,
+                    );
+                }
+            }
+
+            if let Some(offset) = edit.offset
+                && let Ok(mut transform) = transforms.get_mut(entity)
+            {
+                let before = *transform;
+                transform.translation += offset;
+                moves.push((obj.id, before, *transform));
+            }
+
+            if edit.touches_physics() || edit.offset.is_some() {
+                wake_body(&mut commands, entity);
+            }
+        }
+
+        for &(id, _, after) in &moves {
+            game_manager.set_position_by_id(id, after.translation);
+        }
+        if !moves.is_empty() {
+            history.push(
+                EditorCommand::Arrange {
+                    name: "Offset",
+                    moves,
+                },
+                time.elapsed_secs_f64(),
+            );
+        }
+    }
+}
+
+pub fn apply_dimension_edits_system(
+    mut commands: Commands,
+    mut edit_events: EventReader<DimensionEditEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for event in edit_events.read() {
+        let Some(obj) = game_manager.get_object_by_entity_mut(event.entity) else {
+            continue;
+        };
+        obj.dimensions = event.dimensions.validated(obj.shape_type);
+
+        // A scaled Transform alone would leave the collider at the old size
+        commands.entity(event.entity).insert((
+            Mesh3d(
+                obj.shape_type
+                    .create_mesh_with(&mut meshes, &obj.dimensions),
+            ),
+            obj.shape_type.create_collider_with(&obj.dimensions),
+        ));
+        wake_body(&mut commands, event.entity);
+    }
+}
+
+// Open state of the clear scene confirmation, set from the toolbar or the palette
+#[derive(Resource, Default)]
+pub struct ClearSceneDialog {
+    pub open: bool,
+}
+
+pub fn clear_scene_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<ClearSceneDialog>,
+    game_manager: Res<GameObjectManager>,
+    mut clear_events: EventWriter<ClearSceneEvent>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let count = game_manager.len();
+    egui::Window::new("Clear scene")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            if count == 0 {
+                ui.label("The scene is already empty");
+                if ui.button("Close").clicked() {
+                    dialog.open = false;
+                }
+                return;
+            }
+
+            ui.label(format!("Delete all {} objects?", count));
+            ui.weak("Ctrl+Z brings them back");
+            ui.horizontal(|ui| {
+                if ui.button("Clear").clicked() {
+                    clear_events.write(ClearSceneEvent);
+                    dialog.open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.open = false;
+                }
+            });
+        });
+}
+
+pub fn register_editor_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Clear scene", None, |world| {
+        world.resource_mut::<ClearSceneDialog>().open = true;
+    });
+}
+
+// Hierarchy, Inspector and Spawn panels and the edits they send
+pub struct EditorPanelsPlugin;
+
+impl Plugin for EditorPanelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransformEditEvent>()
+            .add_event::<MaterialEditEvent>()
+            .add_event::<PhysicsEditEvent>()
+            .add_event::<DimensionEditEvent>()
+            .add_event::<BulkEditEvent>()
+            .init_resource::<ClearSceneDialog>()
+            .add_systems(Startup, register_editor_commands)
+            .add_systems(
+                EguiContextPass,
+                (
+                    ui_example_system,
+                    hierarchy_panel_system,
+                    inspector_panel_system,
+                    spawn_panel_system,
+                )
+                    .in_set(UiSet::Panels),
+            )
+            .add_systems(
+                EguiContextPass,
+                clear_scene_dialog_system.in_set(UiSet::Popups),
+            );
+    }
 }
@@ -0,0 +1,450 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::core::{GROUND_COLOR, GROUND_POSITION, GROUND_SIZE};
+use crate::engine::custom_mesh::{CustomMeshLibrary, loaded_custom_meshes};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{GameObjectManager, ShapeDimensions, ShapeType};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::default_scene_path;
+use crate::engine::world::WorldSettings;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on};
+use bevy_egui::*;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+// glTF accessor component types and buffer view targets
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+// What one node of the export needs, captured on the main thread so the
+// meshes can be built and written on a task
+#[derive(Debug, Clone)]
+struct ExportNode {
+    name: String,
+    shape: ExportShape,
+    transform: Transform,
+    color: Color,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExportShape {
+    Object(ShapeType, ShapeDimensions),
+    // Index into the imported meshes captured with the nodes
+    Imported(usize),
+    Ground,
+}
+
+impl ExportShape {
+    fn mesh(&self, imported: &[(String, Mesh)]) -> Mesh {
+        match self {
+            ExportShape::Object(shape, dims) => shape.mesh_with(dims),
+            ExportShape::Imported(index) => imported[*index].1.clone(),
+            ExportShape::Ground => Cuboid::from_size(GROUND_SIZE).into(),
+        }
+    }
+
+    fn name<'a>(&self, imported: &'a [(String, Mesh)]) -> &'a str {
+        match self {
+            ExportShape::Object(shape, _) => shape.display_name(),
+            ExportShape::Imported(index) => &imported[*index].0,
+            ExportShape::Ground => "Ground",
+        }
+    }
+
+    // Objects with the same shape and dimensions share one glTF mesh
+    fn key(&self) -> Vec<u32> {
+        match self {
+            ExportShape::Object(shape, dims) => {
+                let dims = dims.validated(*shape);
+                vec![
+                    *shape as u32,
+                    dims.radius.to_bits(),
+                    dims.height.to_bits(),
+                    dims.half_extents.x.to_bits(),
+                    dims.half_extents.y.to_bits(),
+                    dims.half_extents.z.to_bits(),
+                ]
+            }
+            ExportShape::Imported(index) => vec![u32::MAX - 1, *index as u32],
+            ExportShape::Ground => vec![u32::MAX],
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ExportGltfEvent {
+    pub path: PathBuf,
+    pub include_ground: bool,
+}
+
+// Path prompt for Export glTF, and the export running in the background
+#[derive(Resource, Default)]
+pub struct GltfExport {
+    pub open: bool,
+    pub path: String,
+    pub include_ground: bool,
+    task: Option<Task<Result<(PathBuf, usize), String>>>,
+}
+
+impl GltfExport {
+    pub fn open_dialog(&mut self) {
+        if self.path.is_empty() {
+            self.path = default_scene_path()
+                .with_extension("glb")
+                .display()
+                .to_string();
+        }
+        self.open = true;
+    }
+
+    pub fn running(&self) -> bool {
+        self.task.is_some()
+    }
+}
+
+// GLB chunks and buffer views start on 4 byte boundaries
+fn pad_to_4(bytes: &mut Vec<u8>, fill: u8) {
+    bytes.resize(bytes.len().next_multiple_of(4), fill);
+}
+
+// Binary chunk being filled, every view starts 4 byte aligned
+#[derive(Default)]
+struct GlbBuilder {
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GlbBuilder {
+    fn push_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        pad_to_4(&mut self.bin, 0);
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.bin.extend_from_slice(bytes);
+        self.views.len() - 1
+    }
+
+    fn push_vec3s(&mut self, values: &[[f32; 3]], with_bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let view = self.push_view(&bytes, ARRAY_BUFFER);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": "VEC3",
+        });
+        // Positions must carry their bounds
+        if with_bounds {
+            let (min, max) = values.iter().fold(
+                (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                |(min, max), value| {
+                    let value = Vec3::from_array(*value);
+                    (min.min(value), max.max(value))
+                },
+            );
+            accessor["min"] = json!(min.to_array());
+            accessor["max"] = json!(max.to_array());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+        let view = self.push_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    // Triangle list primitive with positions, normals and indices
+    fn push_mesh(&mut self, mesh: &Mesh, name: &str, material: usize) -> Result<Value, String> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return Err(format!("{} mesh has no positions", name));
+        };
+        let mut attributes = json!({ "POSITION": self.push_vec3s(positions, true) });
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            attributes["NORMAL"] = json!(self.push_vec3s(normals, false));
+        }
+        let indices: Vec<u32> = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.iter().map(|&index| index as u32).collect(),
+            Some(Indices::U32(indices)) => indices.clone(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        Ok(json!({
+            "name": name,
+            "primitives": [{
+                "attributes": attributes,
+                "indices": self.push_indices(&indices),
+                "material": material,
+            }],
+        }))
+    }
+}
+
+// glTF materials hold linear colors
+fn material_json(color: Color) -> Value {
+    let color = color.to_linear();
+    let mut material = json!({
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [color.red, color.green, color.blue, color.alpha],
+            "metallicFactor": 0.0,
+            "roughnessFactor": 0.5,
+        },
+    });
+    if color.alpha < 1.0 {
+        material["alphaMode"] = json!("BLEND");
+    }
+    material
+}
+
+fn node_json(node: &ExportNode, mesh: usize) -> Value {
+    let transform = node.transform;
+    json!({
+        "name": node.name,
+        "mesh": mesh,
+        "translation": transform.translation.to_array(),
+        "rotation": transform.rotation.to_array(),
+        "scale": transform.scale.to_array(),
+    })
+}
+
+// Build the whole .glb. A mesh is made per distinct shape and dimensions and a
+// material per distinct color, so a thousand copies of one object stay small
+fn build_glb(nodes: &[ExportNode], imported: &[(String, Mesh)]) -> Result<Vec<u8>, String> {
+    let mut builder = GlbBuilder::default();
+    let mut meshes = Vec::new();
+    let mut mesh_ids: HashMap<(Vec<u32>, usize), usize> = HashMap::new();
+    let mut materials = Vec::new();
+    let mut material_ids: HashMap<[u32; 4], usize> = HashMap::new();
+    let mut node_values = Vec::new();
+
+    for node in nodes {
+        let color = node.color.to_linear();
+        let material_key = [color.red, color.green, color.blue, color.alpha].map(f32::to_bits);
+        let material = *material_ids.entry(material_key).or_insert_with(|| {
+            materials.push(material_json(node.color));
+            materials.len() - 1
+        });
+
+        // Materials live on the primitive, so meshes are shared per material too
+        let mesh_key = (node.shape.key(), material);
+        let mesh = match mesh_ids.get(&mesh_key) {
+            Some(&mesh) => mesh,
+            None => {
+                meshes.push(builder.push_mesh(
+                    &node.shape.mesh(imported),
+                    node.shape.name(imported),
+                    material,
+                )?);
+                mesh_ids.insert(mesh_key, meshes.len() - 1);
+                meshes.len() - 1
+            }
+        };
+        node_values.push(node_json(node, mesh));
+    }
+
+    pad_to_4(&mut builder.bin, 0);
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "Navi" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..node_values.len()).collect::<Vec<_>>() }],
+        "nodes": node_values,
+        "meshes": meshes,
+        "materials": materials,
+        "accessors": builder.accessors,
+        "bufferViews": builder.views,
+        "buffers": [{ "byteLength": builder.bin.len() }],
+    });
+    let mut json_bytes = serde_json::to_vec(&document).map_err(|err| err.to_string())?;
+    pad_to_4(&mut json_bytes, b' ');
+
+    let total = 12 + 8 + json_bytes.len() + 8 + builder.bin.len();
+    let mut glb = Vec::with_capacity(total);
+    for word in [GLB_MAGIC, GLB_VERSION, total as u32] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    glb.extend_from_slice(&json_bytes);
+    glb.extend_from_slice(&(builder.bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+    glb.extend_from_slice(&builder.bin);
+    Ok(glb)
+}
+
+fn write_glb(nodes: &[ExportNode], imported: &[(String, Mesh)], path: &Path) -> Result<(), String> {
+    let glb = build_glb(nodes, imported)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(path, glb).map_err(|err| err.to_string())
+}
+
+// Capture every object with its world transform, then build and write the
+// file on the compute pool. The outcome is reported when the task finishes
+pub fn export_gltf_system(
+    mut export_events: EventReader<ExportGltfEvent>,
+    mut export: ResMut<GltfExport>,
+    game_manager: Res<GameObjectManager>,
+    transforms: Query<&GlobalTransform>,
+    mut notifications: ResMut<EditorNotifications>,
+    library: Res<CustomMeshLibrary>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    if let Some(task) = &export.task
+        && task.is_finished()
+        && let Some(task) = export.task.take()
+    {
+        match block_on(task) {
+            Ok((path, count)) => {
+                info!("Exported {} nodes to {}", count, path.display());
+                notifications.info(format!("Exported glTF to {}", path.display()));
+            }
+            Err(err) => {
+                warn!("glTF export failed: {}", err);
+                notifications.error(format!("glTF export failed: {}", err));
+            }
+        }
+    }
+
+    for event in export_events.read() {
+        if export.task.is_some() {
+            notifications.warn("An export is already running");
+            continue;
+        }
+
+        let imported = loaded_custom_meshes(&library, &meshes);
+        let mut nodes: Vec<ExportNode> = game_manager
+            .iter()
+            .map(|obj| ExportNode {
+                name: obj.name.clone(),
+                // Imported meshes that aren't loaded export as their bounding box
+                shape: obj
+                    .custom_mesh
+                    .as_ref()
+                    .and_then(|name| imported.iter().position(|(mesh, _)| mesh == name))
+                    .map_or(
+                        ExportShape::Object(obj.shape_type, obj.dimensions),
+                        ExportShape::Imported,
+                    ),
+                transform: transforms
+                    .get(obj.entity())
+                    .map(|global| global.compute_transform())
+                    .unwrap_or(
+                        Transform::from_translation(obj.position()).with_rotation(obj.rotation),
+                    ),
+                color: obj.color,
+            })
+            .collect();
+        if event.include_ground {
+            nodes.push(ExportNode {
+                name: "Ground".to_string(),
+                shape: ExportShape::Ground,
+                transform: Transform::from_translation(GROUND_POSITION),
+                color: GROUND_COLOR,
+            });
+        }
+
+        let path = event.path.clone();
+        export.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            write_glb(&nodes, &imported, &path).map(|()| (path, nodes.len()))
+        }));
+    }
+}
+
+pub fn gltf_export_dialog_system(
+    mut contexts: EguiContexts,
+    mut export: ResMut<GltfExport>,
+    mut export_events: EventWriter<ExportGltfEvent>,
+    world: Res<WorldSettings>,
+) {
+    if !export.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Export glTF")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("File (.glb)");
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut export.path).desired_width(360.0));
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            // Only offered while the scene has a ground
+            ui.add_enabled(
+                world.ground,
+                egui::Checkbox::new(&mut export.include_ground, "Include ground plane"),
+            );
+            ui.horizontal(|ui| {
+                let path = export.path.trim().to_string();
+                let ready = !path.is_empty() && !export.running();
+                if (ui.add_enabled(ready, egui::Button::new("Export")).clicked() || entered)
+                    && ready
+                {
+                    export_events.write(ExportGltfEvent {
+                        path: PathBuf::from(path),
+                        include_ground: export.include_ground && world.ground,
+                    });
+                    export.open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    export.open = false;
+                }
+            });
+        });
+}
+
+pub fn register_export_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Export glTF...", None, |world| {
+        world.resource_mut::<GltfExport>().open_dialog();
+    });
+}
+
+// glTF export of the scene
+pub struct GltfExportPlugin;
+
+impl Plugin for GltfExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportGltfEvent>()
+            .init_resource::<GltfExport>()
+            .add_systems(Startup, register_export_commands)
+            .add_systems(
+                EguiContextPass,
+                gltf_export_dialog_system.in_set(UiSet::Popups),
+            )
+            .add_systems(Update, export_gltf_system.in_set(EditorSet::Bookkeeping));
+    }
+}
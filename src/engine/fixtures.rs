@@ -0,0 +1,124 @@
+// Scenes shared by the unit tests of the modules that read and write them
+use crate::engine::joints::{JointKind, ObjectJoint, SpringParams};
+use crate::engine::objects::{BodyKind, MetaValue, PhysicsProps, ShapeType};
+use crate::engine::scene::{BodyState, SCENE_FORMAT_VERSION, SceneFile, SceneObject, SceneShape};
+use crate::engine::world::WorldSettings;
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+// A plain object of this shape with everything else left at its default
+pub(crate) fn object(id: u32, shape_type: ShapeType) -> SceneObject {
+    SceneObject {
+        id,
+        name: format!("object {}", id),
+        shape_type: SceneShape::Known(shape_type),
+        dimensions: shape_type.default_dimensions(),
+        translation: Vec3::new(id as f32, 0.5, -1.25),
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+        color: Color::srgba(0.25, 0.5, 0.75, 1.0),
+        physics: PhysicsProps::default(),
+        tags: BTreeSet::new(),
+        parent: None,
+        locked: false,
+        physics_locked: false,
+        hidden: false,
+        collider_disabled: false,
+        prefab: None,
+        custom_mesh: None,
+        metadata: BTreeMap::new(),
+        body_state: None,
+    }
+}
+
+// Something in every field that has a serde attribute or an odd type, and
+// every kind of joint
+pub(crate) fn sample_scene() -> SceneFile {
+    let mut crate_box = object(0, ShapeType::Cube);
+    crate_box.name = "Crate \"A\", // not a comment\n(second line)".to_string();
+    crate_box.tags = BTreeSet::from(["heavy".to_string(), "wooden".to_string()]);
+    crate_box.metadata = BTreeMap::from([
+        (
+            "label".to_string(),
+            MetaValue::String("a, \"b\"\nc".to_string()),
+        ),
+        (
+            "looks_like".to_string(),
+            MetaValue::String("true".to_string()),
+        ),
+        ("score".to_string(), MetaValue::Number(-2.75)),
+        ("solid".to_string(), MetaValue::Bool(true)),
+    ]);
+    crate_box.locked = true;
+    crate_box.physics = PhysicsProps {
+        body: BodyKind::Kinematic,
+        friction: 0.875,
+        ..default()
+    };
+
+    let mut ball = object(3, ShapeType::Ball);
+    ball.parent = Some(0);
+    ball.rotation = Quat::from_xyzw(0.0, 1.0, 0.0, 0.0);
+    ball.scale = Vec3::new(1.0, 2.0, 0.5);
+    ball.prefab = Some("Bouncy ball".to_string());
+    ball.hidden = true;
+    ball.collider_disabled = true;
+    ball.body_state = Some(BodyState {
+        linvel: Vec3::new(0.0, -3.5, 1.25),
+        angvel: Vec3::X,
+        sleeping: true,
+    });
+
+    let mut mesh = object(7, ShapeType::CustomMesh);
+    mesh.custom_mesh = Some("teapot".to_string());
+
+    SceneFile {
+        version: SCENE_FORMAT_VERSION,
+        world: WorldSettings {
+            gravity: Vec3::new(0.0, -3.75, 0.0),
+            ground: false,
+        },
+        objects: vec![crate_box, ball, mesh],
+        joints: vec![
+            ObjectJoint {
+                parent: 0,
+                child: 7,
+                kind: JointKind::Fixed,
+                anchor: Vec3::Z,
+                rotation: Quat::IDENTITY,
+            },
+            ObjectJoint {
+                parent: 0,
+                child: 3,
+                kind: JointKind::Revolute {
+                    pivot: Vec3::new(0.5, 0.0, 0.0),
+                    axis: Vec3::Y,
+                    limits: Some([-1.5, 0.25]),
+                },
+                anchor: Vec3::new(1.0, 0.0, 0.0),
+                rotation: Quat::IDENTITY,
+            },
+            ObjectJoint {
+                parent: 3,
+                child: 7,
+                kind: JointKind::Revolute {
+                    pivot: Vec3::ZERO,
+                    axis: Vec3::X,
+                    limits: None,
+                },
+                anchor: Vec3::ZERO,
+                rotation: Quat::IDENTITY,
+            },
+            ObjectJoint {
+                parent: 3,
+                child: 7,
+                kind: JointKind::Spring(SpringParams {
+                    rest_length: 2.5,
+                    ..default()
+                }),
+                anchor: Vec3::ZERO,
+                rotation: Quat::IDENTITY,
+            },
+        ],
+    }
+}
@@ -0,0 +1,482 @@
+use crate::engine::core::EditorSet;
+use crate::engine::editor::DimensionEditEvent;
+use crate::engine::history::EditContext;
+use crate::engine::input::{InputAction, InputBindings, ViewportInput};
+use crate::engine::labels::CursorPick;
+use crate::engine::objects::{GameObjectManager, wake_body};
+use crate::engine::selection::Selection;
+use crate::engine::snap::SnapSettings;
+use crate::engine::toolbar::MainCamera;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_rapier3d::prelude::*;
+
+// Handle length as a fraction of the camera distance, keeps the gizmo a constant size on screen
+const GIZMO_SCALE: f32 = 0.15;
+// How close in pixels the cursor has to be to grab a handle
+const PICK_RADIUS_PX: f32 = 8.0;
+const HOVER_COLOR: Color = Color::srgb(1.0, 0.9, 0.1);
+// Rotation increment used while Ctrl is held
+const ROTATION_SNAP_DEGREES: f32 = 15.0;
+// Segments used to hit-test the rotation rings
+const RING_SEGMENTS: usize = 32;
+const MIN_SCALE: f32 = 0.01;
+
+// Which handles the gizmo shows, switched with W/E/R by default
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub fn all() -> [GizmoMode; 3] {
+        [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GizmoMode::Translate => "Move",
+            GizmoMode::Rotate => "Rotate",
+            GizmoMode::Scale => "Scale",
+        }
+    }
+
+    pub fn action(&self) -> InputAction {
+        match self {
+            GizmoMode::Translate => InputAction::GizmoMove,
+            GizmoMode::Rotate => InputAction::GizmoRotate,
+            GizmoMode::Scale => InputAction::GizmoScale,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub fn all() -> [GizmoAxis; 3] {
+        [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z]
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            GizmoAxis::X => Vec3::X,
+            GizmoAxis::Y => Vec3::Y,
+            GizmoAxis::Z => Vec3::Z,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GizmoAxis::X => "X",
+            GizmoAxis::Y => "Y",
+            GizmoAxis::Z => "Z",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            GizmoAxis::X => Color::srgb(0.9, 0.2, 0.2),
+            GizmoAxis::Y => Color::srgb(0.2, 0.9, 0.2),
+            GizmoAxis::Z => Color::srgb(0.2, 0.4, 0.9),
+        }
+    }
+}
+
+// Where the cursor grabbed a handle, per mode
+#[derive(Debug, Clone, Copy)]
+enum DragGrab {
+    // Position along the axis line
+    Translate(f32),
+    // Direction from the centre to the grab point on the ring plane
+    Rotate(Vec3),
+    // Distance along the local axis, relative to the centre
+    Scale(f32),
+}
+
+// An in-progress handle drag
+#[derive(Debug, Clone, Copy)]
+pub struct GizmoDrag {
+    pub entity: Entity,
+    pub axis: GizmoAxis,
+    // Transform when the drag started, used for the undo step
+    pub start: Transform,
+    // The same in world space, handles are dragged in world space
+    start_world: Transform,
+    // Parent of a child object, the result is converted back relative to it
+    parent: Option<GlobalTransform>,
+    grab: DragGrab,
+    // Body type to put back when the drag ends, None for kinematic bodies
+    // that could be moved as they were
+    body: Option<RigidBody>,
+}
+
+// Transform gizmo state for the primary selected object
+#[derive(Resource, Debug, Default)]
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    pub hovered: Option<GizmoAxis>,
+    pub drag: Option<GizmoDrag>,
+}
+
+impl TransformGizmo {
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+}
+
+// Mode keys are ignored while a text field has focus or a drag is running
+// World-space transform of an object. Roots use their Transform directly, it's
+// current while GlobalTransform lags a frame behind a drag
+fn world_transform(transform: &Transform, global: &GlobalTransform, parented: bool) -> Transform {
+    if parented {
+        global.compute_transform()
+    } else {
+        *transform
+    }
+}
+
+pub fn gizmo_mode_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    mut gizmo: ResMut<TransformGizmo>,
+) {
+    if egui_input.wants_keyboard_input() || gizmo.is_dragging() {
+        return;
+    }
+    if let Some(mode) = GizmoMode::all()
+        .into_iter()
+        .find(|mode| bindings.just_pressed(mode.action(), &keyboard_input))
+    {
+        gizmo.mode = mode;
+    }
+}
+
+// The selected object the gizmo is drawn on, and what a drag changes
+#[derive(SystemParam)]
+pub struct GizmoTarget<'w, 's> {
+    pub selection: Res<'w, Selection>,
+    pub game_manager: ResMut<'w, GameObjectManager>,
+    pub bodies: Query<
+        'w,
+        's,
+        (
+            &'static mut Transform,
+            &'static GlobalTransform,
+            Option<&'static ChildOf>,
+            Option<&'static RigidBody>,
+        ),
+    >,
+    pub globals: Query<'w, 's, &'static GlobalTransform>,
+    pub dimension_events: EventWriter<'w, DimensionEditEvent>,
+}
+
+// Hover, grab and drag the handles. The body is kinematic while dragged so
+// physics doesn't pull it away, releasing restores it and records the edit
+pub fn gizmo_interaction_system(
+    mut commands: Commands,
+    mut gizmo: ResMut<TransformGizmo>,
+    ViewportInput {
+        keyboard_input,
+        mouse_input,
+        egui_input,
+        ..
+    }: ViewportInput,
+    picker: CursorPick,
+    GizmoTarget {
+        selection,
+        mut game_manager,
+        mut bodies,
+        globals,
+        mut dimension_events,
+    }: GizmoTarget,
+    snap: Res<SnapSettings>,
+    EditContext {
+        mut history, time, ..
+    }: EditContext,
+) {
+    let cursor = picker.cursor();
+    let Some((camera, camera_transform)) = picker.camera() else {
+        return;
+    };
+
+    if let Some(drag) = gizmo.drag {
+        let Ok((mut transform, ..)) = bodies.get_mut(drag.entity) else {
+            // Object went away mid-drag
+            gizmo.drag = None;
+            return;
+        };
+
+        if !mouse_input.pressed(MouseButton::Left) {
+            gizmo.drag = None;
+            if let Some(body) = drag.body {
+                commands
+                    .entity(drag.entity)
+                    .insert((body, Velocity::zero()));
+                wake_body(&mut commands, drag.entity);
+            }
+            let Some(obj) = game_manager.get_object_by_entity_mut(drag.entity) else {
+                return;
+            };
+
+            if let DragGrab::Scale(_) = drag.grab {
+                // Bake the stretch into the shape so mesh and collider are rebuilt at the new size
+                let factor = transform.scale / drag.start.scale;
+                transform.scale = drag.start.scale;
+                dimension_events.write(DimensionEditEvent {
+                    entity: drag.entity,
+                    dimensions: obj.dimensions.scaled(obj.shape_type, factor),
+                });
+            } else {
+                let id = obj.id;
+                game_manager.set_position(drag.entity, transform.translation);
+                history.push_transform(id, drag.start, *transform, time.elapsed_secs_f64());
+            }
+            return;
+        }
+
+        let Some(ray) = cursor.and_then(|c| camera.viewport_to_world(camera_transform, c).ok())
+        else {
+            return;
+        };
+        let start = drag.start_world;
+        let origin = start.translation;
+        // Current pose in world space, only the dragged component is replaced
+        let mut world = match drag.parent {
+            Some(parent) => parent.mul_transform(*transform).compute_transform(),
+            None => *transform,
+        };
+        match drag.grab {
+            DragGrab::Translate(grab_param) => {
+                let axis = drag.axis.direction();
+                if let Some(param) = axis_param(origin, axis, ray) {
+                    let moved = origin + axis * (param - grab_param);
+                    // Only the dragged component snaps, the others stay where they were
+                    let snapped = snap.snap_position(moved);
+                    world.translation = moved + axis * axis.dot(snapped - moved);
+                }
+            }
+            DragGrab::Rotate(grab_dir) => {
+                let axis = drag.axis.direction();
+                if let Some(dir) = ring_direction(origin, axis, ray) {
+                    let mut angle = axis.dot(grab_dir.cross(dir)).atan2(grab_dir.dot(dir));
+                    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+                        let step = ROTATION_SNAP_DEGREES.to_radians();
+                        angle = (angle / step).round() * step;
+                    }
+                    world.rotation = Quat::from_axis_angle(axis, angle) * start.rotation;
+                }
+            }
+            DragGrab::Scale(grab_param) => {
+                let axis = start.rotation * drag.axis.direction();
+                if let Some(param) = axis_param(origin, axis, ray) {
+                    let factor = (param / grab_param).max(MIN_SCALE);
+                    let mut scale = start.scale;
+                    scale[drag.axis as usize] *= factor;
+                    world.scale = scale;
+                }
+            }
+        }
+        *transform = match drag.parent {
+            Some(parent) => GlobalTransform::from(world).reparented_to(&parent),
+            None => world,
+        };
+        return;
+    }
+
+    gizmo.hovered = None;
+    let Some(entity) = selection.primary() else {
+        return;
+    };
+    let Ok((transform, global, child_of, body)) = bodies.get(entity) else {
+        return;
+    };
+    let parent = child_of.and_then(|child_of| globals.get(child_of.parent()).ok().copied());
+    let world = world_transform(transform, global, child_of.is_some());
+    if egui_input.wants_any_pointer_input()
+        || game_manager
+            .get_object_by_entity(entity)
+            .is_some_and(|obj| obj.locked)
+    {
+        return;
+    }
+    let Some(cursor) = cursor else {
+        return;
+    };
+
+    let origin = world.translation;
+    let length = handle_length(origin, camera_transform);
+    let mode = gizmo.mode;
+    gizmo.hovered = GizmoAxis::all().into_iter().find(|axis| {
+        handle_points(mode, &world, *axis, length)
+            .iter()
+            .filter_map(|p| camera.world_to_viewport(camera_transform, *p).ok())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .any(|seg| distance_to_segment(cursor, seg[0], seg[1]) <= PICK_RADIUS_PX)
+    });
+
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(axis) = gizmo.hovered else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    let grab = match mode {
+        GizmoMode::Translate => axis_param(origin, axis.direction(), ray).map(DragGrab::Translate),
+        GizmoMode::Rotate => ring_direction(origin, axis.direction(), ray).map(DragGrab::Rotate),
+        GizmoMode::Scale => axis_param(origin, world.rotation * axis.direction(), ray)
+            .filter(|param| param.abs() > f32::EPSILON)
+            .map(DragGrab::Scale),
+    };
+    let Some(grab) = grab else {
+        return;
+    };
+
+    gizmo.drag = Some(GizmoDrag {
+        entity,
+        axis,
+        start: *transform,
+        start_world: world,
+        parent,
+        grab,
+        body: match body.copied().unwrap_or(RigidBody::Dynamic) {
+            RigidBody::KinematicPositionBased => None,
+            body => Some(body),
+        },
+    });
+    // Other bodies are made kinematic for the drag so physics doesn't fight it
+    if gizmo.drag.is_some_and(|drag| drag.body.is_some()) {
+        commands
+            .entity(entity)
+            .insert(RigidBody::KinematicPositionBased);
+    }
+}
+
+pub fn draw_gizmo_system(
+    mut gizmos: Gizmos,
+    gizmo: Res<TransformGizmo>,
+    selection: Res<Selection>,
+    transforms: Query<(&Transform, &GlobalTransform, Option<&ChildOf>)>,
+    cameras: Query<&GlobalTransform, With<MainCamera>>,
+) {
+    let entity = gizmo.drag.map(|drag| drag.entity).or(selection.primary());
+    let (Some(entity), Ok(camera_transform)) = (entity, cameras.single()) else {
+        return;
+    };
+    let Ok((transform, global, child_of)) = transforms.get(entity) else {
+        return;
+    };
+    let transform = &world_transform(transform, global, child_of.is_some());
+
+    let origin = transform.translation;
+    let length = handle_length(origin, camera_transform);
+    let active = gizmo.drag.map(|drag| drag.axis).or(gizmo.hovered);
+    for axis in GizmoAxis::all() {
+        let color = if active == Some(axis) {
+            HOVER_COLOR
+        } else {
+            axis.color()
+        };
+        match gizmo.mode {
+            GizmoMode::Translate => {
+                gizmos.arrow(origin, origin + axis.direction() * length, color);
+            }
+            GizmoMode::Rotate => {
+                let rotation = Quat::from_rotation_arc(Vec3::Z, axis.direction());
+                gizmos.circle(Isometry3d::new(origin, rotation), length, color);
+            }
+            GizmoMode::Scale => {
+                let end = origin + transform.rotation * axis.direction() * length;
+                gizmos.line(origin, end, color);
+                gizmos.cuboid(
+                    Transform::from_translation(end)
+                        .with_rotation(transform.rotation)
+                        .with_scale(Vec3::splat(length * 0.1)),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+// World-space polyline used to hit-test one handle
+fn handle_points(
+    mode: GizmoMode,
+    transform: &Transform,
+    axis: GizmoAxis,
+    length: f32,
+) -> Vec<Vec3> {
+    let origin = transform.translation;
+    match mode {
+        GizmoMode::Translate => vec![origin, origin + axis.direction() * length],
+        GizmoMode::Scale => vec![
+            origin,
+            origin + transform.rotation * axis.direction() * length,
+        ],
+        GizmoMode::Rotate => {
+            let rotation = Quat::from_rotation_arc(Vec3::Z, axis.direction());
+            (0..=RING_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                    origin + rotation * Vec3::new(angle.cos(), angle.sin(), 0.0) * length
+                })
+                .collect()
+        }
+    }
+}
+
+// Direction from the centre to where the ray crosses the ring's plane
+fn ring_direction(origin: Vec3, axis: Vec3, ray: Ray3d) -> Option<Vec3> {
+    let distance = ray.intersect_plane(origin, InfinitePlane3d::new(axis))?;
+    (ray.get_point(distance) - origin).try_normalize()
+}
+
+fn handle_length(origin: Vec3, camera_transform: &GlobalTransform) -> f32 {
+    origin.distance(camera_transform.translation()) * GIZMO_SCALE
+}
+
+// Parameter along the axis line closest to the ray, None when the axis points
+// straight at the camera and the drag direction is undefined
+fn axis_param(origin: Vec3, axis: Vec3, ray: Ray3d) -> Option<f32> {
+    let ray_dir = *ray.direction;
+    let b = axis.dot(ray_dir);
+    let denom = 1.0 - b * b;
+    if denom < 1e-4 {
+        return None;
+    }
+    let w = origin - ray.origin;
+    Some((b * ray_dir.dot(w) - axis.dot(w)) / denom)
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
+// The move, rotate and scale handles on the selected object
+pub struct TransformGizmoPlugin;
+
+impl Plugin for TransformGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformGizmo>()
+            .add_systems(Update, draw_gizmo_system.in_set(EditorSet::Bookkeeping));
+    }
+}
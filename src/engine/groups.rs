@@ -0,0 +1,444 @@
+use crate::engine::core::UiSet;
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::layout::PanelContext;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectManager, ObjectRemovedEvent, SetVisibilityEvent,
+    wake_body,
+};
+use crate::engine::selection::Selection;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Named sets of objects, an object can be in any number of groups
+#[derive(Resource, Default, Debug, Clone)]
+pub struct Groups {
+    groups: BTreeMap<String, BTreeSet<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupError {
+    InvalidName(String),
+    AlreadyExists(String),
+    NotFound(String),
+    NoObject(u32),
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupError::InvalidName(name) => write!(f, "\"{}\" is not a valid group name", name),
+            GroupError::AlreadyExists(name) => write!(f, "group \"{}\" already exists", name),
+            GroupError::NotFound(name) => write!(f, "no group named \"{}\"", name),
+            GroupError::NoObject(id) => write!(f, "no game object with ID {}", id),
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+impl Groups {
+    // Group names are trimmed, everything else is kept as typed
+    pub fn create(&mut self, name: &str) -> Result<String, GroupError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(GroupError::InvalidName(name.to_string()));
+        }
+        if self.groups.contains_key(name) {
+            return Err(GroupError::AlreadyExists(name.to_string()));
+        }
+        self.groups.insert(name.to_string(), BTreeSet::new());
+        Ok(name.to_string())
+    }
+
+    // Drops the group only, its members stay in the world
+    pub fn delete(&mut self, name: &str) -> Result<BTreeSet<u32>, GroupError> {
+        self.groups
+            .remove(name)
+            .ok_or_else(|| GroupError::NotFound(name.to_string()))
+    }
+
+    // Ok(false) if the object was already a member
+    pub fn add_member(
+        &mut self,
+        game_manager: &GameObjectManager,
+        name: &str,
+        id: u32,
+    ) -> Result<bool, GroupError> {
+        if game_manager.get_object_by_id(id).is_none() {
+            return Err(GroupError::NoObject(id));
+        }
+        let members = self
+            .groups
+            .get_mut(name)
+            .ok_or_else(|| GroupError::NotFound(name.to_string()))?;
+        Ok(members.insert(id))
+    }
+
+    pub fn remove_member(&mut self, name: &str, id: u32) -> Result<bool, GroupError> {
+        let members = self
+            .groups
+            .get_mut(name)
+            .ok_or_else(|| GroupError::NotFound(name.to_string()))?;
+        Ok(members.remove(&id))
+    }
+
+    pub fn members(&self, name: &str) -> Option<&BTreeSet<u32>> {
+        self.groups.get(name)
+    }
+
+    // Names of the groups an object belongs to
+    pub fn groups_of(&self, id: u32) -> Vec<&str> {
+        self.groups
+            .iter()
+            .filter(|(_, members)| members.contains(&id))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    // Groups by name with their members
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BTreeSet<u32>)> {
+        self.groups
+            .iter()
+            .map(|(name, members)| (name.as_str(), members))
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // Forget an object in every group, empty groups are kept
+    pub fn prune(&mut self, id: u32) {
+        for members in self.groups.values_mut() {
+            members.remove(&id);
+        }
+    }
+}
+
+// Drop removed objects from every group
+pub fn prune_groups_system(
+    mut removed_events: EventReader<ObjectRemovedEvent>,
+    mut groups: ResMut<Groups>,
+) {
+    for event in removed_events.read() {
+        groups.prune(event.id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupOp {
+    Show,
+    Hide,
+    // Despawn every member as one undo step
+    Delete,
+    // Replace the selection with the unlocked members
+    Select,
+    // Offset every unlocked member in world space
+    Move(Vec3),
+}
+
+// An operation on every member of a group
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct GroupEvent {
+    pub name: String,
+    pub op: GroupOp,
+}
+
+// How group operations reach the members: moves are applied here, showing,
+// hiding and deleting are passed on to the systems that do them for one object
+#[derive(SystemParam)]
+pub struct GroupEdits<'w, 's> {
+    pub transforms: Query<'w, 's, (&'static mut Transform, Option<&'static ChildOf>)>,
+    pub globals: Query<'w, 's, &'static GlobalTransform>,
+    pub visibility_events: EventWriter<'w, SetVisibilityEvent>,
+    pub despawn_events: EventWriter<'w, DespawnEntityEvent>,
+}
+
+pub fn apply_group_events_system(
+    mut commands: Commands,
+    mut group_events: EventReader<GroupEvent>,
+    groups: Res<Groups>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut selection: ResMut<Selection>,
+    GroupEdits {
+        mut transforms,
+        globals,
+        mut visibility_events,
+        mut despawn_events,
+    }: GroupEdits,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+) {
+    for event in group_events.read() {
+        let Some(members) = groups.members(&event.name) else {
+            notifications.warn(format!("No group named \"{}\"", event.name));
+            continue;
+        };
+        if members.is_empty() {
+            notifications.warn(format!("Group \"{}\" is empty", event.name));
+            continue;
+        }
+
+        match event.op {
+            GroupOp::Show | GroupOp::Hide => {
+                for &id in members {
+                    visibility_events.write(SetVisibilityEvent {
+                        id,
+                        visible: event.op == GroupOp::Show,
+                        disable_collider: false,
+                    });
+                }
+            }
+            GroupOp::Delete => {
+                despawn_events.write(DespawnEntityEvent {
+                    target: DespawnTarget::Ids(members.iter().copied().collect()),
+                });
+            }
+            GroupOp::Select => {
+                selection.select_range(
+                    members
+                        .iter()
+                        .filter_map(|&id| game_manager.get_object_by_id(id))
+                        .filter(|obj| !obj.locked)
+                        .map(|obj| obj.entity()),
+                );
+            }
+            GroupOp::Move(delta) => {
+                let mut moves = Vec::new();
+                for &id in members {
+                    let Some(obj) = game_manager.get_object_by_id(id) else {
+                        continue;
+                    };
+                    if obj.locked || has_ancestor_in(&game_manager, id, members) {
+                        // Children follow a parent that's moving with the group
+                        continue;
+                    }
+                    let Ok((transform, child_of)) = transforms.get(obj.entity()) else {
+                        continue;
+                    };
+                    // The delta is in world space, children store local transforms
+                    let local_delta = child_of
+                        .and_then(|child_of| globals.get(child_of.parent()).ok())
+                        .map_or(delta, |parent| {
+                            parent.affine().inverse().transform_vector3(delta)
+                        });
+                    let before = *transform;
+                    let after = before.with_translation(before.translation + local_delta);
+                    moves.push((id, before, after));
+                }
+                if moves.is_empty() {
+                    continue;
+                }
+                for &(id, _, after) in &moves {
+                    let Some(entity) = game_manager.get_object_by_id(id).map(|obj| obj.entity())
+                    else {
+                        continue;
+                    };
+                    game_manager.set_position(entity, after.translation);
+                    if let Ok((mut transform, _)) = transforms.get_mut(entity) {
+                        *transform = after;
+                    }
+                    wake_body(&mut commands, entity);
+                }
+                info!("Moved group {}: {} objects", event.name, moves.len());
+                history.push(
+                    EditorCommand::Arrange {
+                        name: "Move group",
+                        moves,
+                    },
+                    time.elapsed_secs_f64(),
+                );
+            }
+        }
+    }
+}
+
+fn has_ancestor_in(game_manager: &GameObjectManager, id: u32, members: &BTreeSet<u32>) -> bool {
+    let mut parent = game_manager.get_object_by_id(id).and_then(|obj| obj.parent);
+    while let Some(parent_id) = parent {
+        if members.contains(&parent_id) {
+            return true;
+        }
+        parent = game_manager
+            .get_object_by_id(parent_id)
+            .and_then(|obj| obj.parent);
+    }
+    false
+}
+
+// Text typed into the panel, kept between frames
+#[derive(Default)]
+pub struct GroupsPanelState {
+    new_name: String,
+    move_delta: Vec3,
+}
+
+pub fn groups_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    mut groups: ResMut<Groups>,
+    game_manager: Res<GameObjectManager>,
+    selection: Res<Selection>,
+    mut group_events: EventWriter<GroupEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+    mut state: Local<GroupsPanelState>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Groups",
+        egui::Window::new("Groups").default_width(260.0),
+        |ui| {
+            let selected = selection.ids(&game_manager);
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.new_name)
+                        .hint_text("Group name")
+                        .desired_width(120.0),
+                );
+                let create = ui.button("Create");
+                let from_selection = ui
+                    .add_enabled(!selected.is_empty(), egui::Button::new("From selection"))
+                    .on_hover_text("Create the group with the selected objects in it");
+                if create.clicked() || from_selection.clicked() {
+                    match groups.create(&state.new_name) {
+                        Ok(name) => {
+                            if from_selection.clicked() {
+                                for &id in &selected {
+                                    let _ = groups.add_member(&game_manager, &name, id);
+                                }
+                            }
+                            state.new_name.clear();
+                        }
+                        Err(err) => notifications.error(format!("Cannot create group: {}", err)),
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Move by");
+                ui.add(
+                    egui::DragValue::new(&mut state.move_delta.x)
+                        .speed(0.1)
+                        .prefix("x "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut state.move_delta.y)
+                        .speed(0.1)
+                        .prefix("y "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut state.move_delta.z)
+                        .speed(0.1)
+                        .prefix("z "),
+                );
+            });
+            ui.separator();
+
+            if groups.is_empty() {
+                ui.weak("No groups yet");
+                return;
+            }
+
+            let mut remove_group = None;
+            let mut membership = Vec::new();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (name, members) in groups.iter() {
+                    egui::CollapsingHeader::new(format!("{} ({})", name, members.len()))
+                        .id_salt(("group", name))
+                        .show(ui, |ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                let any = !members.is_empty();
+                                let mut op_button = |ui: &mut egui::Ui, label: &str, op| {
+                                    if ui.add_enabled(any, egui::Button::new(label)).clicked() {
+                                        group_events.write(GroupEvent {
+                                            name: name.to_string(),
+                                            op,
+                                        });
+                                    }
+                                };
+                                op_button(ui, "Select", GroupOp::Select);
+                                op_button(ui, "Hide", GroupOp::Hide);
+                                op_button(ui, "Show", GroupOp::Show);
+                                op_button(ui, "Move", GroupOp::Move(state.move_delta));
+                                op_button(ui, "Delete objects", GroupOp::Delete);
+                            });
+                            ui.horizontal(|ui| {
+                                let any_selected = !selected.is_empty();
+                                if ui
+                                    .add_enabled(any_selected, egui::Button::new("Add selection"))
+                                    .clicked()
+                                {
+                                    membership.push((name.to_string(), true));
+                                }
+                                if ui
+                                    .add_enabled(
+                                        any_selected,
+                                        egui::Button::new("Remove selection"),
+                                    )
+                                    .clicked()
+                                {
+                                    membership.push((name.to_string(), false));
+                                }
+                                if ui
+                                    .button("Remove group")
+                                    .on_hover_text("The objects stay in the scene")
+                                    .clicked()
+                                {
+                                    remove_group = Some(name.to_string());
+                                }
+                            });
+                            for &id in members {
+                                let label = game_manager
+                                    .get_object_by_id(id)
+                                    .map_or_else(|| format!("#{}", id), |obj| obj.name.clone());
+                                ui.label(format!("  {}", label));
+                            }
+                        });
+                }
+            });
+
+            for (name, add) in membership {
+                for &id in &selected {
+                    let result = if add {
+                        groups.add_member(&game_manager, &name, id)
+                    } else {
+                        groups.remove_member(&name, id)
+                    };
+                    if let Err(err) = result {
+                        notifications.error(format!("Group \"{}\": {}", name, err));
+                    }
+                }
+            }
+            if let Some(name) = remove_group {
+                let _ = groups.delete(&name);
+            }
+        },
+    );
+}
+
+// Named groups of objects and the Groups panel
+pub struct GroupsPlugin;
+
+impl Plugin for GroupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GroupEvent>()
+            .init_resource::<Groups>()
+            .add_systems(EguiContextPass, groups_panel_system.in_set(UiSet::Panels));
+    }
+}
@@ -0,0 +1,423 @@
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    GameObjectManager, MaterialHandles, ObjectRemovedEvent, ObjectSnapshot, ObjectSpawner,
+    orphan_children, reparent_object, restore_object, restore_objects, wake_body,
+};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::properties::{ObjectProperties, apply_object_properties};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use std::collections::VecDeque;
+
+const HISTORY_CAPACITY: usize = 100;
+
+// Consecutive transform edits on the same object within this window are merged,
+// so one inspector drag becomes a single undo step
+const TRANSFORM_MERGE_SECS: f64 = 0.5;
+
+// An undoable editor operation
+#[derive(Debug, Clone)]
+pub enum EditorCommand {
+    Spawn(ObjectSnapshot),
+    // Every object made by one batch spawn
+    SpawnBatch(Vec<ObjectSnapshot>),
+    Delete(ObjectSnapshot),
+    Transform {
+        id: u32,
+        before: Transform,
+        after: Transform,
+    },
+    ClearScene(Vec<ObjectSnapshot>),
+    // A parent deleted along with its children, parent first
+    DeleteGroup(Vec<ObjectSnapshot>),
+    Reparent {
+        id: u32,
+        before: Option<u32>,
+        after: Option<u32>,
+    },
+    // Several objects moved by one arrange tool, as (id, before, after)
+    Arrange {
+        name: &'static str,
+        moves: Vec<(u32, Transform, Transform)>,
+    },
+    // Properties pasted from one object onto others, as (id, before, after)
+    Properties {
+        changes: Vec<(u32, ObjectProperties, ObjectProperties)>,
+    },
+}
+
+impl EditorCommand {
+    pub fn description(&self) -> String {
+        match self {
+            EditorCommand::Spawn(snapshot) => format!("Spawn {}", snapshot.name),
+            EditorCommand::SpawnBatch(snapshots) => format!("Spawn {} objects", snapshots.len()),
+            EditorCommand::Delete(snapshot) => format!("Delete {}", snapshot.name),
+            EditorCommand::Transform { id, .. } => format!("Move object {}", id),
+            EditorCommand::ClearScene(snapshots) => {
+                format!("Clear scene ({} objects)", snapshots.len())
+            }
+            EditorCommand::DeleteGroup(snapshots) => format!(
+                "Delete {} and {} children",
+                snapshots
+                    .first()
+                    .map_or("", |snapshot| snapshot.name.as_str()),
+                snapshots.len().saturating_sub(1)
+            ),
+            EditorCommand::Reparent { id, .. } => format!("Reparent object {}", id),
+            EditorCommand::Arrange { name, moves } => {
+                format!("{} ({} objects)", name, moves.len())
+            }
+            EditorCommand::Properties { changes } => {
+                format!("Paste properties ({} objects)", changes.len())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    command: EditorCommand,
+    time: f64,
+}
+
+// Undo/redo stacks for editor operations
+#[derive(Resource, Default)]
+pub struct EditorHistory {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+}
+
+impl EditorHistory {
+    // Record a new operation, dropping the oldest once the cap is reached
+    pub fn push(&mut self, command: EditorCommand, time: f64) {
+        self.redo.clear();
+        self.push_undo(HistoryEntry { command, time });
+    }
+
+    pub fn push_transform(&mut self, id: u32, before: Transform, after: Transform, time: f64) {
+        if let Some(HistoryEntry {
+            command:
+                EditorCommand::Transform {
+                    id: last_id,
+                    after: last_after,
+                    ..
+                },
+            time: last_time,
+        }) = self.undo.back_mut()
+            && *last_id == id
+            && time - *last_time < TRANSFORM_MERGE_SECS
+        {
+            *last_after = after;
+            *last_time = time;
+            self.redo.clear();
+            return;
+        }
+
+        self.push(EditorCommand::Transform { id, before, after }, time);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo_description(&self) -> Option<String> {
+        self.undo.back().map(|entry| entry.command.description())
+    }
+
+    pub fn redo_description(&self) -> Option<String> {
+        self.redo.last().map(|entry| entry.command.description())
+    }
+
+    fn push_undo(&mut self, entry: HistoryEntry) {
+        if self.undo.len() == HISTORY_CAPACITY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(entry);
+    }
+}
+
+// What a system that changes the scene reports to: the undo stack, the
+// notifications and the clock both are stamped with
+#[derive(SystemParam)]
+pub struct EditContext<'w> {
+    pub history: ResMut<'w, EditorHistory>,
+    pub notifications: ResMut<'w, EditorNotifications>,
+    pub time: Res<'w, Time>,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEvent {
+    Undo,
+    Redo,
+}
+
+// Ctrl+Z undoes, Ctrl+Shift+Z redoes; text fields keep their own undo
+pub fn history_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut history_events: EventWriter<HistoryEvent>,
+) {
+    if egui_input.wants_keyboard_input() || !keyboard_input.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        history_events.write(HistoryEvent::Redo);
+    } else {
+        history_events.write(HistoryEvent::Undo);
+    }
+}
+
+pub fn apply_history_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut history_events: EventReader<HistoryEvent>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    mut transforms: Query<&mut Transform>,
+    material_handles: MaterialHandles,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
+) {
+    for event in history_events.read() {
+        let entry = match event {
+            HistoryEvent::Undo => history.undo.pop_back(),
+            HistoryEvent::Redo => history.redo.pop(),
+        };
+        let Some(mut entry) = entry else {
+            continue;
+        };
+        info!("{:?}: {}", event, entry.command.description());
+
+        let now = time.elapsed_secs_f64();
+        match (event, &mut entry.command) {
+            // Removing the object again, keep its latest state for the way back
+            (HistoryEvent::Undo, EditorCommand::Spawn(snapshot))
+            | (HistoryEvent::Redo, EditorCommand::Delete(snapshot)) => {
+                let Some(obj) = game_manager.get_object_by_id(snapshot.id) else {
+                    warn!("Cannot remove object {}: not found", snapshot.id);
+                    continue;
+                };
+                let entity = obj.entity();
+                if let Ok(transform) = transforms.get(entity) {
+                    snapshot.transform = *transform;
+                }
+                if let Some(current) = game_manager.snapshot(snapshot.id, snapshot.transform) {
+                    *snapshot = current;
+                }
+                orphan_children(&mut commands, &mut game_manager, snapshot.id);
+                commands.entity(entity).despawn();
+                if let Some(obj) = game_manager.remove_object(entity) {
+                    removed_events.write(ObjectRemovedEvent::from(&obj));
+                }
+            }
+            // Bringing the object back under its original id
+            (HistoryEvent::Redo, EditorCommand::Spawn(snapshot))
+            | (HistoryEvent::Undo, EditorCommand::Delete(snapshot)) => {
+                if let Err(err) = restore_object(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &mut game_manager,
+                    snapshot,
+                    now,
+                ) {
+                    warn!("Cannot restore object {}: {}", snapshot.id, err);
+                    notifications.error(format!("Cannot restore {}: {}", snapshot.name, err));
+                    continue;
+                }
+            }
+            (HistoryEvent::Undo, EditorCommand::ClearScene(snapshots))
+            | (HistoryEvent::Undo, EditorCommand::DeleteGroup(snapshots))
+            | (HistoryEvent::Redo, EditorCommand::SpawnBatch(snapshots)) => {
+                restore_objects(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &mut game_manager,
+                    snapshots,
+                    now,
+                );
+            }
+            (HistoryEvent::Redo, EditorCommand::ClearScene(snapshots)) => {
+                remove_objects(
+                    &mut commands,
+                    &mut game_manager,
+                    &transforms,
+                    &mut removed_events,
+                    snapshots,
+                );
+                if game_manager.is_empty() {
+                    game_manager.clear_all();
+                }
+            }
+            (HistoryEvent::Redo, EditorCommand::DeleteGroup(snapshots))
+            | (HistoryEvent::Undo, EditorCommand::SpawnBatch(snapshots)) => {
+                remove_objects(
+                    &mut commands,
+                    &mut game_manager,
+                    &transforms,
+                    &mut removed_events,
+                    snapshots,
+                );
+            }
+            (_, EditorCommand::Reparent { id, before, after }) => {
+                let parent = if *event == HistoryEvent::Undo {
+                    *before
+                } else {
+                    *after
+                };
+                if let Err(err) = reparent_object(&mut commands, &mut game_manager, *id, parent) {
+                    warn!("Cannot reparent object {}: {}", id, err);
+                    notifications.error(format!("Cannot reparent object {}: {}", id, err));
+                }
+            }
+            (_, EditorCommand::Transform { id, before, after }) => {
+                let target = if *event == HistoryEvent::Undo {
+                    *before
+                } else {
+                    *after
+                };
+                move_object(
+                    &mut commands,
+                    &mut game_manager,
+                    &mut transforms,
+                    *id,
+                    target,
+                );
+            }
+            (_, EditorCommand::Arrange { moves, .. }) => {
+                for (id, before, after) in moves.iter() {
+                    let target = if *event == HistoryEvent::Undo {
+                        *before
+                    } else {
+                        *after
+                    };
+                    move_object(
+                        &mut commands,
+                        &mut game_manager,
+                        &mut transforms,
+                        *id,
+                        target,
+                    );
+                }
+            }
+            (_, EditorCommand::Properties { changes }) => {
+                for (id, before, after) in changes.iter() {
+                    let target = if *event == HistoryEvent::Undo {
+                        before
+                    } else {
+                        after
+                    };
+                    apply_object_properties(
+                        &mut commands,
+                        &mut game_manager,
+                        &mut meshes,
+                        &mut materials,
+                        &material_handles,
+                        *id,
+                        target,
+                    );
+                }
+            }
+        }
+
+        entry.time = now;
+        match event {
+            HistoryEvent::Undo => history.redo.push(entry),
+            HistoryEvent::Redo => history.push_undo(entry),
+        }
+    }
+}
+
+// Despawn every object in a batch, refreshing the snapshots first so the way back
+// restores their latest state
+fn remove_objects(
+    commands: &mut Commands,
+    game_manager: &mut GameObjectManager,
+    transforms: &Query<&mut Transform>,
+    removed_events: &mut EventWriter<ObjectRemovedEvent>,
+    snapshots: &mut [ObjectSnapshot],
+) {
+    for snapshot in snapshots.iter_mut() {
+        let Some(obj) = game_manager.get_object_by_id(snapshot.id) else {
+            continue;
+        };
+        let entity = obj.entity();
+        let transform = transforms
+            .get(entity)
+            .copied()
+            .unwrap_or(snapshot.transform);
+        if let Some(current) = game_manager.snapshot(snapshot.id, transform) {
+            *snapshot = current;
+        }
+        // A parent earlier in the batch may have taken this one down already
+        commands.entity(entity).try_despawn();
+        if let Some(obj) = game_manager.remove_object(entity) {
+            removed_events.write(ObjectRemovedEvent::from(&obj));
+        }
+    }
+}
+
+// Teleport an object to a stored transform and wake it so physics picks it up
+fn move_object(
+    commands: &mut Commands,
+    game_manager: &mut GameObjectManager,
+    transforms: &mut Query<&mut Transform>,
+    id: u32,
+    target: Transform,
+) {
+    let Some(entity) = game_manager.get_object_by_id(id).map(|obj| obj.entity()) else {
+        warn!("Cannot move object {}: not found", id);
+        return;
+    };
+    game_manager.set_position(entity, target.translation);
+    if let Ok(mut transform) = transforms.get_mut(entity) {
+        *transform = target;
+    }
+    wake_body(commands, entity);
+}
+
+pub fn register_history_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register_event(
+        "Undo",
+        Some(CommandHint::Keys("Ctrl+Z")),
+        HistoryEvent::Undo,
+    );
+    registry.register_event(
+        "Redo",
+        Some(CommandHint::Keys("Ctrl+Shift+Z")),
+        HistoryEvent::Redo,
+    );
+}
+
+// Undo and redo
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HistoryEvent>()
+            .init_resource::<EditorHistory>()
+            .add_systems(Startup, register_history_commands);
+    }
+}
@@ -0,0 +1,501 @@
+use crate::engine::autosave::AutosaveSettings;
+use crate::engine::camera::CameraSettings;
+use crate::engine::config::{CONFIG_FILE_NAME, NaviConfig};
+use crate::engine::core::EditorSet;
+use crate::engine::input::InputBindings;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::prefs::{EditorPrefs, PrefsSaver, prefs_path};
+use crate::engine::toolbar::MainCamera;
+use crate::engine::world::WorldSettings;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow};
+use bevy_rapier3d::prelude::*;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver};
+
+// Quiet time after the last change before a file is read. Editors that
+// write a file twice in a row only cause one reload
+const RELOAD_DELAY_SECS: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum WatchedFile {
+    Config,
+    Prefs,
+}
+
+// Watches navi.toml and the editor prefs, which hold the keybindings. The
+// directories are watched rather than the files so editors that save by
+// replacing the file are seen too
+#[derive(Resource)]
+pub struct ConfigWatcher {
+    // Dropping it stops the watch
+    _watcher: RecommendedWatcher,
+    receiver: Mutex<Receiver<notify::Result<notify::Event>>>,
+    config_path: PathBuf,
+    prefs_path: Option<PathBuf>,
+    // Time of the last change seen per file
+    pending: HashMap<WatchedFile, f64>,
+}
+
+impl ConfigWatcher {
+    fn watched(&self, path: &Path) -> Option<WatchedFile> {
+        if path == self.config_path {
+            Some(WatchedFile::Config)
+        } else if Some(path) == self.prefs_path.as_deref() {
+            Some(WatchedFile::Prefs)
+        } else {
+            None
+        }
+    }
+}
+
+// A navi.toml that doesn't exist yet is watched for where it would be read
+// from first, the working directory
+pub fn start_config_watcher_system(mut commands: Commands, config: Res<NaviConfig>) {
+    let config_path = config
+        .path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME));
+    let Ok(config_path) = std::path::absolute(&config_path) else {
+        return;
+    };
+    let prefs_path = prefs_path().and_then(|path| std::path::absolute(path).ok());
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            warn!("Cannot watch config files, changes need a restart: {}", err);
+            return;
+        }
+    };
+    for path in [Some(&config_path), prefs_path.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        let Some(dir) = path.parent() else {
+            continue;
+        };
+        let result = fs::create_dir_all(dir)
+            .map_err(|err| err.to_string())
+            .and_then(|()| {
+                watcher
+                    .watch(dir, RecursiveMode::NonRecursive)
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Cannot watch {}: {}", dir.display(), err);
+        }
+    }
+
+    commands.insert_resource(ConfigWatcher {
+        _watcher: watcher,
+        receiver: Mutex::new(receiver),
+        config_path,
+        prefs_path,
+        pending: HashMap::new(),
+    });
+}
+
+// A setting a reload can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Gravity,
+    WindowTitle,
+    Vsync,
+    Msaa,
+    PhysicsTimestep,
+    DebugRender,
+    WindowSize,
+    Fullscreen,
+    Keybindings,
+    Camera,
+    Autosave,
+}
+
+impl Setting {
+    fn name(self) -> &'static str {
+        match self {
+            Setting::Gravity => "gravity",
+            Setting::WindowTitle => "window title",
+            Setting::Vsync => "vsync",
+            Setting::Msaa => "MSAA",
+            Setting::PhysicsTimestep => "physics timestep",
+            Setting::DebugRender => "debug render",
+            Setting::WindowSize => "window size",
+            Setting::Fullscreen => "fullscreen",
+            Setting::Keybindings => "keybindings",
+            Setting::Camera => "camera",
+            Setting::Autosave => "autosave",
+        }
+    }
+}
+
+// What a reload changed, for the toast
+#[derive(Debug, Default, PartialEq)]
+struct ReloadOutcome {
+    applied: Vec<Setting>,
+    needs_restart: Vec<Setting>,
+}
+
+impl ReloadOutcome {
+    fn message(&self, file: &str) -> Option<String> {
+        if self.applied.is_empty() && self.needs_restart.is_empty() {
+            return None;
+        }
+        let names = |settings: &[Setting]| {
+            settings
+                .iter()
+                .map(|setting| setting.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut message = format!("{} reloaded", file);
+        if !self.applied.is_empty() {
+            message.push_str(&format!(", applied {}", names(&self.applied)));
+        }
+        if !self.needs_restart.is_empty() {
+            message.push_str(&format!(", restart for {}", names(&self.needs_restart)));
+        }
+        Some(message)
+    }
+}
+
+// The config a changed navi.toml holds, None for a deleted one. Keys it
+// rejects keep their previous value. A file that can't be parsed at all keeps
+// the whole previous config and gives the reasons instead
+fn reloaded_config(
+    previous: &NaviConfig,
+    path: &Path,
+    contents: Option<&str>,
+) -> Result<NaviConfig, Vec<String>> {
+    let mut new = match contents {
+        Some(contents) => {
+            let mut new = NaviConfig::parse(contents);
+            new.path = Some(path.to_path_buf());
+            new
+        }
+        // Deleted, back to the defaults
+        None => NaviConfig::default(),
+    };
+    if new.is_unreadable() {
+        return Err(new.warnings.into_iter().map(|(_, reason)| reason).collect());
+    }
+    new.keep_previous(previous);
+    Ok(new)
+}
+
+// Which settings differ between `old` and `new`, and whether each can change
+// while running. Debug render needs its plugin, which is left out on start
+// when it's off
+fn config_outcome(old: &NaviConfig, new: &NaviConfig, debug_render: bool) -> ReloadOutcome {
+    let mut outcome = ReloadOutcome::default();
+    let changes = [
+        (new.gravity != old.gravity, Setting::Gravity, true),
+        (new.title != old.title, Setting::WindowTitle, true),
+        (new.vsync != old.vsync, Setting::Vsync, true),
+        (new.msaa != old.msaa, Setting::Msaa, true),
+        (
+            new.timestep_hz != old.timestep_hz,
+            Setting::PhysicsTimestep,
+            true,
+        ),
+        (
+            new.physics_debug != old.physics_debug,
+            Setting::DebugRender,
+            debug_render,
+        ),
+        (
+            new.width != old.width || new.height != old.height,
+            Setting::WindowSize,
+            false,
+        ),
+        (new.fullscreen != old.fullscreen, Setting::Fullscreen, false),
+    ];
+    for (changed, setting, live) in changes {
+        match (changed, live) {
+            (false, _) => {}
+            (true, true) => outcome.applied.push(setting),
+            (true, false) => outcome.needs_restart.push(setting),
+        }
+    }
+    outcome
+}
+
+// What the config settings that apply without a restart are written to
+#[derive(SystemParam)]
+pub struct LiveConfig<'w, 's> {
+    pub world: ResMut<'w, WorldSettings>,
+    pub debug_render: Option<ResMut<'w, DebugRenderContext>>,
+    pub timestep: ResMut<'w, TimestepMode>,
+    pub cameras: Query<'w, 's, &'static mut Msaa, With<MainCamera>>,
+    pub windows: Query<'w, 's, &'static mut Window, With<PrimaryWindow>>,
+}
+
+// The prefs a reload of the prefs file replaces
+#[derive(SystemParam)]
+pub struct ReloadedPrefs<'w> {
+    pub bindings: ResMut<'w, InputBindings>,
+    pub camera: ResMut<'w, CameraSettings>,
+    pub autosave: ResMut<'w, AutosaveSettings>,
+    pub saver: Res<'w, PrefsSaver>,
+}
+
+pub fn hot_reload_system(
+    watcher: Option<ResMut<ConfigWatcher>>,
+    time: Res<Time>,
+    mut config: ResMut<NaviConfig>,
+    LiveConfig {
+        mut world,
+        mut debug_render,
+        mut timestep,
+        mut cameras,
+        mut windows,
+    }: LiveConfig,
+    ReloadedPrefs {
+        mut bindings,
+        mut camera,
+        mut autosave,
+        saver,
+    }: ReloadedPrefs,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    let Some(mut watcher) = watcher else {
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+
+    let events: Vec<notify::Event> = match watcher.receiver.lock() {
+        Ok(receiver) => receiver.try_iter().filter_map(Result::ok).collect(),
+        Err(_) => return,
+    };
+    for event in events {
+        if matches!(event.kind, EventKind::Access(_)) {
+            continue;
+        }
+        for path in &event.paths {
+            if let Some(file) = watcher.watched(path) {
+                watcher.pending.insert(file, now);
+            }
+        }
+    }
+
+    let due: Vec<WatchedFile> = watcher
+        .pending
+        .iter()
+        .filter(|(_, changed)| now - **changed >= RELOAD_DELAY_SECS)
+        .map(|(file, _)| *file)
+        .collect();
+    for file in due {
+        watcher.pending.remove(&file);
+        match file {
+            WatchedFile::Config => {
+                let path = watcher.config_path.clone();
+                let contents = fs::read_to_string(&path).ok();
+                let new = match reloaded_config(&config, &path, contents.as_deref()) {
+                    Ok(new) => new,
+                    Err(reasons) => {
+                        for reason in reasons {
+                            warn!(
+                                "{} {}, keeping the previous settings",
+                                CONFIG_FILE_NAME, reason
+                            );
+                        }
+                        notifications.error(format!(
+                            "{} couldn't be parsed, keeping the previous settings",
+                            CONFIG_FILE_NAME
+                        ));
+                        continue;
+                    }
+                };
+                for (key, reason) in &new.warnings {
+                    warn!("Ignoring {} in {}: {}", key, path.display(), reason);
+                }
+                if !new.warnings.is_empty() {
+                    let keys: Vec<&str> =
+                        new.warnings.iter().map(|(key, _)| key.as_str()).collect();
+                    notifications.warn(format!(
+                        "{} has invalid settings, keeping the previous: {}",
+                        CONFIG_FILE_NAME,
+                        keys.join(", ")
+                    ));
+                }
+
+                let outcome = config_outcome(&config, &new, debug_render.is_some());
+                for setting in &outcome.applied {
+                    match setting {
+                        Setting::Gravity => world.gravity = new.gravity,
+                        Setting::Vsync => {
+                            for mut window in &mut windows {
+                                window.present_mode = if new.vsync {
+                                    PresentMode::AutoVsync
+                                } else {
+                                    PresentMode::AutoNoVsync
+                                };
+                            }
+                        }
+                        Setting::Msaa => {
+                            for mut msaa in &mut cameras {
+                                *msaa = new.msaa();
+                            }
+                        }
+                        Setting::PhysicsTimestep => *timestep = new.timestep_mode(),
+                        Setting::DebugRender => {
+                            if let Some(debug_render) = debug_render.as_mut() {
+                                debug_render.enabled = new.physics_debug;
+                            }
+                        }
+                        // window_title_system picks the title up
+                        Setting::WindowTitle => {}
+                        // Not config settings, or not ones applied live
+                        _ => {}
+                    }
+                }
+                *config = new;
+                if let Some(message) = outcome.message(CONFIG_FILE_NAME) {
+                    info!("{}", message);
+                    notifications.info(message);
+                }
+            }
+            WatchedFile::Prefs => {
+                let Some(path) = watcher.prefs_path.clone() else {
+                    continue;
+                };
+                let Ok(contents) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                // The editor's own saves come through here too
+                if saver.wrote(&contents) {
+                    continue;
+                }
+                let prefs = match EditorPrefs::parse(&contents) {
+                    Ok(prefs) => prefs,
+                    Err(err) => {
+                        warn!("Cannot reload {}: {}", path.display(), err);
+                        notifications.error(
+                            "Preferences file couldn't be parsed, keeping the current preferences",
+                        );
+                        continue;
+                    }
+                };
+
+                let mut outcome = ReloadOutcome::default();
+                if prefs.bindings != *bindings {
+                    *bindings = prefs.bindings;
+                    outcome.applied.push(Setting::Keybindings);
+                }
+                let camera_settings = prefs.camera.clamped();
+                if camera_settings != *camera {
+                    *camera = camera_settings;
+                    outcome.applied.push(Setting::Camera);
+                }
+                let autosave_settings = prefs.autosave.clamped();
+                if autosave_settings != *autosave {
+                    *autosave = autosave_settings;
+                    outcome.applied.push(Setting::Autosave);
+                }
+                if let Some(message) = outcome.message("Preferences") {
+                    info!("{}", message);
+                    notifications.info(message);
+                }
+            }
+        }
+    }
+}
+
+// Watches navi.toml and the prefs file and applies edits made outside the
+// editor
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_config_watcher_system)
+            .add_systems(Update, hot_reload_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> PathBuf {
+        PathBuf::from("/projects/navi.toml")
+    }
+
+    fn previous() -> NaviConfig {
+        reloaded_config(
+            &NaviConfig::default(),
+            &path(),
+            Some("[physics]\ngravity = [0.0, -1.6, 0.0]\n[window]\ntitle = \"Moon\""),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn an_unparsable_file_keeps_the_previous_config() {
+        let reasons =
+            reloaded_config(&previous(), &path(), Some("[physics\ngravity = ")).unwrap_err();
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].starts_with("isn't valid TOML"), "{}", reasons[0]);
+    }
+
+    #[test]
+    fn rejected_keys_keep_their_previous_value() {
+        let previous = previous();
+        let new = reloaded_config(
+            &previous,
+            &path(),
+            Some("[physics]\ngravity = \"down\"\n[window]\ntitle = \"Mars\""),
+        )
+        .unwrap();
+        assert_eq!(new.gravity, previous.gravity);
+        assert_eq!(new.title, "Mars");
+        assert_eq!(new.warnings.len(), 1);
+        assert_eq!(new.path, Some(path()));
+
+        // A deleted file goes back to the defaults
+        assert_eq!(
+            reloaded_config(&previous, &path(), None),
+            Ok(NaviConfig::default())
+        );
+    }
+
+    #[test]
+    fn live_settings_are_applied_and_the_rest_wait_for_a_restart() {
+        let old = NaviConfig::default();
+        assert_eq!(config_outcome(&old, &old, true), ReloadOutcome::default());
+        assert_eq!(config_outcome(&old, &old, true).message("navi.toml"), None);
+
+        let new = NaviConfig {
+            gravity: Vec3::ZERO,
+            msaa: 1,
+            width: 640.0,
+            fullscreen: true,
+            physics_debug: !old.physics_debug,
+            ..old.clone()
+        };
+        let outcome = config_outcome(&old, &new, true);
+        assert_eq!(
+            outcome.applied,
+            [Setting::Gravity, Setting::Msaa, Setting::DebugRender]
+        );
+        assert_eq!(
+            outcome.needs_restart,
+            [Setting::WindowSize, Setting::Fullscreen]
+        );
+        assert_eq!(
+            outcome.message("navi.toml").unwrap(),
+            "navi.toml reloaded, applied gravity, MSAA, debug render, restart for window size, fullscreen"
+        );
+
+        // Without the debug render plugin turning it on needs a restart
+        let outcome = config_outcome(&old, &new, false);
+        assert_eq!(outcome.applied, [Setting::Gravity, Setting::Msaa]);
+        assert_eq!(outcome.needs_restart[0], Setting::DebugRender);
+    }
+}
@@ -0,0 +1,304 @@
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{GameObject, GameObjectManager, wake_body};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::selection::Selection;
+use crate::engine::toolbar::MainCamera;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_egui::input::EguiWantsInput;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashMap;
+
+const MAX_IMPULSE: f32 = 1000.0;
+
+// Objects an ApplyImpulseEvent pushes, like DespawnTarget
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImpulseTarget {
+    // Whatever is selected when the event is handled
+    Selection,
+    Id(u32),
+    Entity(Entity),
+    // Several objects at once, ids that no longer exist are skipped
+    Ids(Vec<u32>),
+    All,
+}
+
+impl ImpulseTarget {
+    fn resolve<'a>(
+        &self,
+        selection: &Selection,
+        game_manager: &'a GameObjectManager,
+    ) -> Vec<&'a GameObject> {
+        match self {
+            ImpulseTarget::Selection => selection
+                .entities
+                .iter()
+                .filter_map(|&entity| game_manager.get_object_by_entity(entity))
+                .collect(),
+            ImpulseTarget::Id(id) => game_manager.get_object_by_id(*id).into_iter().collect(),
+            ImpulseTarget::Entity(entity) => game_manager
+                .get_object_by_entity(*entity)
+                .into_iter()
+                .collect(),
+            ImpulseTarget::Ids(ids) => ids
+                .iter()
+                .filter_map(|id| game_manager.get_object_by_id(*id))
+                .collect(),
+            ImpulseTarget::All => game_manager.iter().collect(),
+        }
+    }
+}
+
+// Kick objects once with an impulse and an angular impulse, both in N·s
+#[derive(Event, Debug, Clone)]
+pub struct ApplyImpulseEvent {
+    pub target: ImpulseTarget,
+    pub impulse: Vec3,
+    pub torque: Vec3,
+}
+
+// Impulse section of the inspector, the jump and push hotkeys use its strength
+#[derive(Resource, Debug, Clone)]
+pub struct ImpulseTool {
+    // Normalized when applied
+    pub direction: Vec3,
+    pub magnitude: f32,
+    pub torque: Vec3,
+}
+
+impl Default for ImpulseTool {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::Y,
+            magnitude: 5.0,
+            torque: Vec3::ZERO,
+        }
+    }
+}
+
+impl ImpulseTool {
+    pub fn event(&self) -> ApplyImpulseEvent {
+        ApplyImpulseEvent {
+            target: ImpulseTarget::Selection,
+            impulse: self.direction.normalize_or_zero() * self.magnitude,
+            torque: self.torque,
+        }
+    }
+}
+
+// The camera's view direction along the ground, so a push doesn't drive
+// objects into it. Looking straight down pushes straight down
+pub fn push_direction(camera: &Transform) -> Vec3 {
+    let forward = camera.forward().as_vec3();
+    let flat = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
+    if flat == Vec3::ZERO { forward } else { flat }
+}
+
+// Why an object can't take an impulse, None when it can
+fn rejection(obj: &GameObject, body: Option<&RigidBody>) -> Option<&'static str> {
+    if obj.locked {
+        return Some("it's locked");
+    }
+    match body {
+        Some(RigidBody::Dynamic) => None,
+        Some(RigidBody::Fixed) => Some("Fixed bodies don't move"),
+        Some(RigidBody::KinematicPositionBased | RigidBody::KinematicVelocityBased) => {
+            Some("kinematic bodies only move when the editor moves them")
+        }
+        None => Some("it has no rigid body"),
+    }
+}
+
+pub fn apply_impulse_system(
+    mut commands: Commands,
+    mut impulse_events: EventReader<ApplyImpulseEvent>,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    bodies: Query<&RigidBody>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    // Impulses sent for the same object in one frame add up
+    let mut pending: HashMap<Entity, ExternalImpulse> = HashMap::new();
+    for event in impulse_events.read() {
+        if !event.impulse.is_finite() || !event.torque.is_finite() {
+            warn!(
+                "Ignoring impulse {:?}, torque {:?}",
+                event.impulse, event.torque
+            );
+            continue;
+        }
+        let objects = event.target.resolve(&selection, &game_manager);
+        if objects.is_empty() {
+            notifications.warn("Impulse: nothing to push");
+            continue;
+        }
+
+        let mut rejected = Vec::new();
+        for obj in &objects {
+            let entity = obj.entity();
+            if let Some(reason) = rejection(obj, bodies.get(entity).ok()) {
+                rejected.push((obj.name.as_str(), reason));
+                continue;
+            }
+            let impulse = pending.entry(entity).or_default();
+            impulse.impulse += event.impulse;
+            impulse.torque_impulse += event.torque;
+        }
+        match rejected.as_slice() {
+            [] => {}
+            [(name, reason)] => {
+                notifications.warn(format!("Cannot push {}: {}", name, reason));
+            }
+            _ => notifications.warn(format!(
+                "Skipped {} of {} objects, only unlocked dynamic bodies take impulses",
+                rejected.len(),
+                objects.len()
+            )),
+        }
+    }
+
+    for (entity, impulse) in pending {
+        // A sleeping body drops the impulse unless it's woken first
+        wake_body(&mut commands, entity);
+        commands.entity(entity).insert(impulse);
+    }
+}
+
+// Jump kicks the selection upward, push along the camera's view
+pub fn impulse_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    tool: Res<ImpulseTool>,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut impulse_events: EventWriter<ApplyImpulseEvent>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    let direction = if bindings.just_pressed(InputAction::JumpSelection, &keyboard_input) {
+        Vec3::Y
+    } else if bindings.just_pressed(InputAction::PushSelection, &keyboard_input) {
+        let Ok(camera) = camera.single() else {
+            return;
+        };
+        push_direction(camera)
+    } else {
+        return;
+    };
+    impulse_events.write(ApplyImpulseEvent {
+        target: ImpulseTarget::Selection,
+        impulse: direction * tool.magnitude,
+        torque: Vec3::ZERO,
+    });
+}
+
+// Impulse section for the inspector, returns the event to send when Apply is clicked
+pub fn impulse_ui(
+    ui: &mut egui::Ui,
+    tool: &mut ImpulseTool,
+    camera: Option<&Transform>,
+) -> Option<ApplyImpulseEvent> {
+    let mut apply = false;
+    egui::CollapsingHeader::new("Impulse").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Direction");
+            for (value, prefix) in [
+                (&mut tool.direction.x, "x: "),
+                (&mut tool.direction.y, "y: "),
+                (&mut tool.direction.z, "z: "),
+            ] {
+                ui.add(
+                    egui::DragValue::new(value)
+                        .speed(0.05)
+                        .range(-1.0..=1.0)
+                        .prefix(prefix),
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Up").clicked() {
+                tool.direction = Vec3::Y;
+            }
+            if let Some(camera) = camera
+                && ui
+                    .button("Camera forward")
+                    .on_hover_text("Along the ground in the view direction")
+                    .clicked()
+            {
+                tool.direction = push_direction(camera);
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut tool.magnitude, 0.0..=MAX_IMPULSE)
+                .logarithmic(true)
+                .text("Strength")
+                .suffix(" N·s"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Spin");
+            for (value, prefix) in [
+                (&mut tool.torque.x, "x: "),
+                (&mut tool.torque.y, "y: "),
+                (&mut tool.torque.z, "z: "),
+            ] {
+                ui.add(egui::DragValue::new(value).speed(0.1).prefix(prefix));
+            }
+        });
+        let valid = tool.direction != Vec3::ZERO || tool.torque != Vec3::ZERO;
+        apply = ui
+            .add_enabled(valid, egui::Button::new("Apply to selection"))
+            .clicked();
+    });
+    apply.then(|| tool.event())
+}
+
+pub fn register_impulse_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register(
+        "Jump selection",
+        Some(CommandHint::Binding(InputAction::JumpSelection)),
+        |world| {
+            let magnitude = world.resource::<ImpulseTool>().magnitude;
+            world.send_event(ApplyImpulseEvent {
+                target: ImpulseTarget::Selection,
+                impulse: Vec3::Y * magnitude,
+                torque: Vec3::ZERO,
+            });
+        },
+    );
+    registry.register(
+        "Push selection",
+        Some(CommandHint::Binding(InputAction::PushSelection)),
+        |world| {
+            let magnitude = world.resource::<ImpulseTool>().magnitude;
+            let Ok(camera) = world
+                .query_filtered::<&Transform, With<MainCamera>>()
+                .single(world)
+                .copied()
+            else {
+                return;
+            };
+            world.send_event(ApplyImpulseEvent {
+                target: ImpulseTarget::Selection,
+                impulse: push_direction(&camera) * magnitude,
+                torque: Vec3::ZERO,
+            });
+        },
+    );
+    registry.register("Apply impulse to selection", None, |world| {
+        let event = world.resource::<ImpulseTool>().event();
+        world.send_event(event);
+    });
+}
+
+// The impulse tool
+pub struct ImpulsePlugin;
+
+impl Plugin for ImpulsePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyImpulseEvent>()
+            .init_resource::<ImpulseTool>()
+            .add_systems(Startup, register_impulse_commands);
+    }
+}
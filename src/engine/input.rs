@@ -1,18 +1,425 @@
-use crate::engine::objects::{SelectedShape, ShapeType, SpawnEntityEvent};
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectManager, NextSpawn, SpawnEntityEvent, SpawnSource,
+    random_spawn_position,
+};
+use crate::engine::placement::PlacementMode;
+use crate::engine::selection::Selection;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// Editor actions that can be bound to a single key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    SpawnObject,
+    CycleShape,
+    ListObjects,
+    DeleteSelected,
+    TogglePause,
+    StepSimulation,
+    ToggleStats,
+    GizmoMove,
+    GizmoRotate,
+    GizmoScale,
+    MeasureMode,
+    PlacementMode,
+    SelectNearby,
+    SelectNearestToCursor,
+    FrameAll,
+    Screenshot,
+    FlipGravity,
+    JumpSelection,
+    PushSelection,
+}
+
+impl InputAction {
+    pub fn all() -> [InputAction; 19] {
+        [
+            InputAction::SpawnObject,
+            InputAction::CycleShape,
+            InputAction::ListObjects,
+            InputAction::DeleteSelected,
+            InputAction::TogglePause,
+            InputAction::StepSimulation,
+            InputAction::ToggleStats,
+            InputAction::GizmoMove,
+            InputAction::GizmoRotate,
+            InputAction::GizmoScale,
+            InputAction::MeasureMode,
+            InputAction::PlacementMode,
+            InputAction::SelectNearby,
+            InputAction::SelectNearestToCursor,
+            InputAction::FrameAll,
+            InputAction::Screenshot,
+            InputAction::FlipGravity,
+            InputAction::JumpSelection,
+            InputAction::PushSelection,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            InputAction::SpawnObject => "Spawn object",
+            InputAction::CycleShape => "Cycle shape",
+            InputAction::ListObjects => "List objects",
+            InputAction::DeleteSelected => "Delete selected",
+            InputAction::TogglePause => "Play / pause",
+            InputAction::StepSimulation => "Step simulation",
+            InputAction::ToggleStats => "Toggle stats",
+            InputAction::GizmoMove => "Gizmo: move",
+            InputAction::GizmoRotate => "Gizmo: rotate",
+            InputAction::GizmoScale => "Gizmo: scale",
+            InputAction::MeasureMode => "Measure mode",
+            InputAction::PlacementMode => "Placement mode",
+            InputAction::SelectNearby => "Select nearby",
+            InputAction::SelectNearestToCursor => "Select nearest to cursor",
+            InputAction::FrameAll => "Frame all",
+            InputAction::Screenshot => "Screenshot",
+            InputAction::FlipGravity => "Flip gravity",
+            InputAction::JumpSelection => "Jump selection",
+            InputAction::PushSelection => "Push selection",
+        }
+    }
+
+    pub fn default_key(&self) -> KeyCode {
+        match self {
+            InputAction::SpawnObject => KeyCode::Space,
+            InputAction::CycleShape => KeyCode::Tab,
+            InputAction::ListObjects => KeyCode::KeyL,
+            InputAction::DeleteSelected => KeyCode::Delete,
+            InputAction::TogglePause => KeyCode::KeyP,
+            InputAction::StepSimulation => KeyCode::Period,
+            InputAction::ToggleStats => KeyCode::F3,
+            InputAction::GizmoMove => KeyCode::KeyW,
+            InputAction::GizmoRotate => KeyCode::KeyE,
+            InputAction::GizmoScale => KeyCode::KeyR,
+            InputAction::MeasureMode => KeyCode::KeyM,
+            InputAction::PlacementMode => KeyCode::KeyG,
+            InputAction::SelectNearby => KeyCode::KeyN,
+            InputAction::SelectNearestToCursor => KeyCode::KeyC,
+            InputAction::FrameAll => KeyCode::Home,
+            InputAction::Screenshot => KeyCode::F12,
+            InputAction::FlipGravity => KeyCode::KeyF,
+            InputAction::JumpSelection => KeyCode::KeyJ,
+            InputAction::PushSelection => KeyCode::KeyK,
+        }
+    }
+}
+
+// Keys that can be bound, stored in the prefs file by their KeyCode name
+const BINDABLE_KEYS: [KeyCode; 62] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Period,
+    KeyCode::Comma,
+    KeyCode::Slash,
+    KeyCode::Backquote,
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| key_name(*key) == name)
+}
+
+// Action to key mapping, saved with the editor prefs
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct InputBindings {
+    keys: BTreeMap<InputAction, KeyCode>,
+    // Action waiting for its new key, set from the keybindings panel
+    capturing: Option<InputAction>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            keys: InputAction::all()
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+            capturing: None,
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn key(&self, action: InputAction) -> KeyCode {
+        self.keys
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_key())
+    }
+
+    // Presses with Ctrl held belong to the Ctrl shortcuts (undo, duplicate,
+    // copy properties), not to the plain bindings
+    pub fn just_pressed(&self, action: InputAction, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        keyboard_input.just_pressed(self.key(action))
+            && !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    }
+
+    pub fn bind(&mut self, action: InputAction, key: KeyCode) {
+        self.keys.insert(action, key);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn capturing(&self) -> Option<InputAction> {
+        self.capturing
+    }
+
+    pub fn start_capture(&mut self, action: InputAction) {
+        self.capturing = Some(action);
+    }
+
+    // Other actions sharing this action's key
+    pub fn conflicts(&self, action: InputAction) -> Vec<InputAction> {
+        let key = self.key(action);
+        InputAction::all()
+            .into_iter()
+            .filter(|other| *other != action && self.key(*other) == key)
+            .collect()
+    }
+}
+
+// Saved as action name -> key name, unknown names keep the default binding
+impl Serialize for InputBindings {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        InputAction::all()
+            .into_iter()
+            .map(|action| (action, key_name(self.key(action))))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InputBindings {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = BTreeMap::<InputAction, String>::deserialize(deserializer)?;
+        let mut bindings = Self::default();
+        for (action, name) in names {
+            match key_from_name(&name) {
+                Some(key) => bindings.bind(action, key),
+                None => warn!("Unknown key {:?} for {:?}, using default", name, action),
+            }
+        }
+        Ok(bindings)
+    }
+}
+
+// Take the next key press for the action being rebound. Runs before every
+// other input system and swallows the press so it doesn't also trigger an action
+pub fn capture_binding_system(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    let Some(action) = bindings.capturing else {
+        return;
+    };
+    let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+        return;
+    };
+    keyboard_input.clear_just_pressed(key);
+
+    if key == KeyCode::Escape {
+        bindings.capturing = None;
+        return;
+    }
+    if !BINDABLE_KEYS.contains(&key) {
+        warn!("{:?} can't be bound", key);
+        return;
+    }
+    bindings.bind(action, key);
+    bindings.capturing = None;
+    info!("Bound {} to {}", action.display_name(), key_name(key));
+}
+
+// Keys and mouse buttons as the viewport sees them, with the bindings and
+// whether egui has claimed them this frame
+#[derive(SystemParam)]
+pub struct ViewportInput<'w> {
+    pub keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    pub mouse_input: Res<'w, ButtonInput<MouseButton>>,
+    pub egui_input: Res<'w, EguiWantsInput>,
+    pub bindings: Res<'w, InputBindings>,
+}
 
 pub fn handle_input(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    selected_shape: Res<SelectedShape>,
+    ViewportInput {
+        keyboard_input,
+        egui_input,
+        bindings,
+        ..
+    }: ViewportInput,
+    NextSpawn {
+        selected_shape,
+        shape_params,
+        spawn_physics,
+        lifetime,
+        ..
+    }: NextSpawn,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    placement: Res<PlacementMode>,
     mut spawn_events: EventWriter<SpawnEntityEvent>,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        let x = (rand::random::<f32>() - 0.5) * 10.0;
-        let z = (rand::random::<f32>() - 0.5) * 10.0;
-        spawn_events.send(SpawnEntityEvent {
-            position: Vec3::new(x, 4.0, z),
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+
+    // Placement mode spawns at the ghost instead
+    if !placement.active && bindings.just_pressed(InputAction::SpawnObject, &keyboard_input) {
+        spawn_events.write(SpawnEntityEvent {
+            source: SpawnSource::Keyboard,
+            position: random_spawn_position(),
             shape_type: selected_shape.shape_type,
             custom_name: Some("bob".to_string()),
+            params: Some(shape_params.get(selected_shape.shape_type)),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+            lifetime_secs: lifetime.keyboard_lifetime(),
+            custom_mesh: None,
+            physics: Some(spawn_physics.props),
+            body_type: None,
         });
     }
+
+    if bindings.just_pressed(InputAction::DeleteSelected, &keyboard_input) {
+        for id in selection.ids(&game_manager) {
+            despawn_events.write(DespawnEntityEvent {
+                target: DespawnTarget::Id(id),
+            });
+        }
+    }
+}
+
+pub fn keybindings_panel_system(
+    mut contexts: EguiContexts,
+    mut bindings: ResMut<InputBindings>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Keybindings",
+        egui::Window::new("Keybindings").default_width(280.0),
+        |ui| {
+            ui.label("Click a key, then press the new one (Esc cancels)");
+            ui.separator();
+
+            egui::Grid::new("keybindings_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for action in InputAction::all() {
+                        ui.label(action.display_name());
+
+                        let text = if bindings.capturing() == Some(action) {
+                            "Press a key...".to_string()
+                        } else {
+                            key_name(bindings.key(action))
+                        };
+                        if ui.button(text).clicked() {
+                            bindings.start_capture(action);
+                        }
+
+                        let conflicts = bindings.conflicts(action);
+                        if conflicts.is_empty() {
+                            ui.label("");
+                        } else {
+                            let names: Vec<&str> =
+                                conflicts.iter().map(|a| a.display_name()).collect();
+                            ui.colored_label(egui::Color32::RED, "⚠ conflict")
+                                .on_hover_text(format!("Also bound to {}", names.join(", ")));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            if ui.button("Reset to defaults").clicked() {
+                bindings.reset();
+            }
+        },
+    );
+}
+
+// The Keybindings panel, the bindings are loaded with the prefs
+pub struct KeybindingsPlugin;
+
+impl Plugin for KeybindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiContextPass,
+            keybindings_panel_system.in_set(UiSet::Panels),
+        );
+    }
 }
@@ -0,0 +1,1193 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::gizmo::TransformGizmo;
+use crate::engine::input::ViewportInput;
+use crate::engine::labels::CursorPick;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{GameObjectManager, wake_body};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::selection::Selection;
+use crate::engine::serde_util;
+use bevy::prelude::*;
+use bevy_egui::EguiContextPass;
+use bevy_egui::{EguiContexts, egui};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const PICK_RAY_LENGTH: f32 = 1000.0;
+// Stiffness and damping are per unit of mass, so light and heavy objects
+// spring alike
+const DEFAULT_STIFFNESS: f32 = 50.0;
+const DEFAULT_DAMPING: f32 = 2.0;
+// Stretch, as a fraction of the rest length, drawn at full color
+const FULL_STRETCH: f32 = 0.25;
+const SPRING_REST_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const SPRING_STRETCHED_COLOR: Color = Color::srgb(0.95, 0.25, 0.2);
+const SPRING_COMPRESSED_COLOR: Color = Color::srgb(0.25, 0.45, 0.95);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum JointKind {
+    // Welds the two objects together
+    #[default]
+    Fixed,
+    // Swings about `axis` through `pivot`, both in the parent's frame
+    Revolute {
+        #[serde(with = "serde_util::vec3")]
+        pivot: Vec3,
+        #[serde(with = "serde_util::vec3")]
+        axis: Vec3,
+        // Radians from the pose it was created in, None swings freely
+        limits: Option<[f32; 2]>,
+    },
+    // Pulls the two centers toward a distance, like a spring
+    Spring(SpringParams),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpringParams {
+    pub rest_length: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl Default for SpringParams {
+    fn default() -> Self {
+        Self {
+            rest_length: 1.0,
+            stiffness: DEFAULT_STIFFNESS,
+            damping: DEFAULT_DAMPING,
+        }
+    }
+}
+
+impl SpringParams {
+    // Negative or broken values are reset so rapier never sees them
+    pub fn clamped(&self) -> Self {
+        let valid = |value: f32, fallback: f32| {
+            if value.is_finite() {
+                value.max(0.0)
+            } else {
+                fallback
+            }
+        };
+        let defaults = Self::default();
+        Self {
+            rest_length: valid(self.rest_length, defaults.rest_length),
+            stiffness: valid(self.stiffness, defaults.stiffness),
+            damping: valid(self.damping, defaults.damping),
+        }
+    }
+
+    // How far `length` is past the rest length as a fraction of it, negative
+    // when compressed
+    pub fn stretch(&self, length: f32) -> f32 {
+        (length - self.rest_length) / self.rest_length.max(0.01)
+    }
+}
+
+// Line color for a spring, white at rest
+fn spring_color(stretch: f32) -> Color {
+    let amount = (stretch / FULL_STRETCH).clamp(-1.0, 1.0);
+    if amount >= 0.0 {
+        SPRING_REST_COLOR.mix(&SPRING_STRETCHED_COLOR, amount)
+    } else {
+        SPRING_REST_COLOR.mix(&SPRING_COMPRESSED_COLOR, -amount)
+    }
+}
+
+impl JointKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JointKind::Fixed => "Fixed",
+            JointKind::Revolute { .. } => "Hinge",
+            JointKind::Spring(_) => "Spring",
+        }
+    }
+}
+
+// Angle limits are edited in degrees and stored in radians
+pub fn limits_from_degrees(degrees: [f32; 2]) -> [f32; 2] {
+    let [min, max] = degrees.map(f32::to_radians);
+    [min.min(max), min.max(max)]
+}
+
+pub fn limits_to_degrees(radians: [f32; 2]) -> [f32; 2] {
+    radians.map(f32::to_degrees)
+}
+
+// A joint between two objects, saved with the scene. Rapier's ImpulseJoint
+// sits on the child and points at the parent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ObjectJoint {
+    pub parent: u32,
+    pub child: u32,
+    pub kind: JointKind,
+    // The child's pose in the parent's frame when they were attached
+    #[serde(with = "serde_util::vec3")]
+    pub anchor: Vec3,
+    #[serde(with = "serde_util::quat")]
+    pub rotation: Quat,
+}
+
+impl ObjectJoint {
+    // Holds the child where it is now relative to the parent
+    pub fn between(
+        kind: JointKind,
+        parent: (u32, &GlobalTransform),
+        child: (u32, &GlobalTransform),
+    ) -> Self {
+        let (_, parent_rotation, parent_position) = parent.1.to_scale_rotation_translation();
+        let (_, child_rotation, child_position) = child.1.to_scale_rotation_translation();
+        let inverse = parent_rotation.inverse();
+        Self {
+            parent: parent.0,
+            child: child.0,
+            kind,
+            anchor: inverse * (child_position - parent_position),
+            rotation: inverse * child_rotation,
+        }
+    }
+
+    // Hinge through a world space pivot and axis, converted into the parent's frame
+    pub fn hinge(
+        parent: (u32, &GlobalTransform),
+        child: (u32, &GlobalTransform),
+        pivot: Vec3,
+        axis: Vec3,
+        limits: Option<[f32; 2]>,
+    ) -> Self {
+        let (_, parent_rotation, parent_position) = parent.1.to_scale_rotation_translation();
+        let inverse = parent_rotation.inverse();
+        let kind = JointKind::Revolute {
+            pivot: inverse * (pivot - parent_position),
+            axis: (inverse * axis).normalize_or(Vec3::X),
+            limits,
+        };
+        Self::between(kind, parent, child)
+    }
+
+    // Registry key, the same whichever object comes first
+    pub fn key(&self) -> (u32, u32) {
+        pair(self.parent, self.child)
+    }
+
+    pub fn involves(&self, id: u32) -> bool {
+        self.parent == id || self.child == id
+    }
+
+    // The other object of the joint
+    pub fn partner(&self, id: u32) -> u32 {
+        if self.parent == id {
+            self.child
+        } else {
+            self.parent
+        }
+    }
+
+    // Jointed objects don't collide with each other, a weld or hinge usually
+    // has them touching
+    fn rapier_joint(&self) -> TypedJoint {
+        match self.kind {
+            JointKind::Fixed => {
+                let mut joint = FixedJointBuilder::new()
+                    .local_anchor1(self.anchor)
+                    .local_basis1(self.rotation)
+                    .build();
+                joint.set_contacts_enabled(false);
+                joint.into()
+            }
+            JointKind::Revolute {
+                pivot,
+                axis,
+                limits,
+            } => {
+                // Both frames line up in the pose the hinge was made in, so
+                // the limits count from there
+                let basis = Quat::from_rotation_arc(Vec3::X, axis);
+                let to_child = self.rotation.inverse();
+                let mut joint = RevoluteJointBuilder::new(axis)
+                    .local_anchor1(pivot)
+                    .local_anchor2(to_child * (pivot - self.anchor))
+                    .build();
+                joint
+                    .data
+                    .set_local_basis1(basis)
+                    .set_local_basis2(to_child * basis);
+                if let Some(limits) = limits {
+                    joint.set_limits(limits);
+                }
+                joint.set_contacts_enabled(false);
+                joint.into()
+            }
+            // Between the centers, the objects keep colliding with each other
+            JointKind::Spring(spring) => {
+                SpringJointBuilder::new(spring.rest_length, spring.stiffness, spring.damping)
+                    .spring_model(MotorModel::AccelerationBased)
+                    .build()
+                    .into()
+            }
+        }
+    }
+}
+
+fn pair(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+struct JointEntry {
+    joint: ObjectJoint,
+    // Entity carrying the ImpulseJoint, kept so it can be removed after the
+    // child is gone from the manager
+    entity: Entity,
+}
+
+// Joints between objects, keyed by both ids
+#[derive(Resource, Default)]
+pub struct JointRegistry {
+    entries: BTreeMap<(u32, u32), JointEntry>,
+}
+
+impl JointRegistry {
+    pub fn get(&self, a: u32, b: u32) -> Option<&ObjectJoint> {
+        self.entries.get(&pair(a, b)).map(|entry| &entry.joint)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ObjectJoint> {
+        self.entries.values().map(|entry| &entry.joint)
+    }
+
+    // Joints the object is part of, as parent or child
+    pub fn of(&self, id: u32) -> impl Iterator<Item = &ObjectJoint> {
+        self.iter().filter(move |joint| joint.involves(id))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Rapier allows one ImpulseJoint per entity, so an object can be the
+    // child of one joint only
+    fn held_by(&self, child: u32) -> Option<&ObjectJoint> {
+        self.iter().find(|joint| joint.child == child)
+    }
+
+    // Insert the rapier joint and record it, replacing a joint between the same pair
+    pub fn attach(
+        &mut self,
+        commands: &mut Commands,
+        game_manager: &GameObjectManager,
+        joint: ObjectJoint,
+    ) -> Result<(), String> {
+        if joint.parent == joint.child {
+            return Err("an object can't be attached to itself".to_string());
+        }
+        let find = |id: u32| {
+            game_manager
+                .get_object_by_id(id)
+                .ok_or_else(|| format!("object {} doesn't exist", id))
+        };
+        let (parent, child) = (find(joint.parent)?, find(joint.child)?);
+        if let Some(held) = self.held_by(joint.child)
+            && held.key() != joint.key()
+        {
+            return Err(format!(
+                "{} is already attached to another object",
+                child.name
+            ));
+        }
+        if let Some(old) = self.entries.remove(&joint.key()) {
+            commands.entity(old.entity).try_remove::<ImpulseJoint>();
+        }
+
+        commands
+            .entity(child.entity())
+            .insert(ImpulseJoint::new(parent.entity(), joint.rapier_joint()));
+        wake_body(commands, parent.entity());
+        wake_body(commands, child.entity());
+        self.entries.insert(
+            joint.key(),
+            JointEntry {
+                joint,
+                entity: child.entity(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn detach(
+        &mut self,
+        commands: &mut Commands,
+        game_manager: &GameObjectManager,
+        a: u32,
+        b: u32,
+    ) -> Option<ObjectJoint> {
+        let entry = self.entries.remove(&pair(a, b))?;
+        commands.entity(entry.entity).try_remove::<ImpulseJoint>();
+        for id in [a, b] {
+            if let Some(obj) = game_manager.get_object_by_id(id) {
+                wake_body(commands, obj.entity());
+            }
+        }
+        Some(entry.joint)
+    }
+
+    // Forget every joint, used when a scene replaces the current one
+    pub fn clear(&mut self, commands: &mut Commands) {
+        for entry in std::mem::take(&mut self.entries).into_values() {
+            commands.entity(entry.entity).try_remove::<ImpulseJoint>();
+        }
+    }
+
+    pub fn saved(&self) -> Vec<ObjectJoint> {
+        self.iter().copied().collect()
+    }
+}
+
+// Joint requests from the inspector, the palette and scripts
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum JointEvent {
+    // Weld the child to the parent where they are now
+    Attach {
+        parent: u32,
+        child: u32,
+    },
+    // Hinge the two objects about a world space axis through `pivot`, None
+    // for the point halfway between them. Limits are in radians
+    Hinge {
+        parent: u32,
+        child: u32,
+        pivot: Option<Vec3>,
+        axis: Vec3,
+        limits: Option<[f32; 2]>,
+    },
+    // Connect the two centers with a spring, resting at their current
+    // distance when `rest_length` is None
+    Spring {
+        parent: u32,
+        child: u32,
+        rest_length: Option<f32>,
+        stiffness: f32,
+        damping: f32,
+    },
+    // New angle limits for the hinge between a and b, None frees it
+    SetLimits {
+        a: u32,
+        b: u32,
+        limits: Option<[f32; 2]>,
+    },
+    // New settings for the spring between a and b
+    SetSpring {
+        a: u32,
+        b: u32,
+        spring: SpringParams,
+    },
+    Detach {
+        a: u32,
+        b: u32,
+    },
+    // Every joint the object is part of
+    DetachAll(u32),
+}
+
+pub fn joint_system(
+    mut commands: Commands,
+    mut joint_events: EventReader<JointEvent>,
+    mut joints: ResMut<JointRegistry>,
+    game_manager: Res<GameObjectManager>,
+    transforms: Query<&GlobalTransform>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    let name = |id: u32| {
+        game_manager
+            .get_object_by_id(id)
+            .map_or_else(|| format!("object {}", id), |obj| obj.name.clone())
+    };
+    let pose = |id: u32| {
+        let obj = game_manager.get_object_by_id(id)?;
+        transforms.get(obj.entity()).ok()
+    };
+    for event in joint_events.read() {
+        let (parent, child, joint, swapped) = match *event {
+            JointEvent::Attach { parent, child } => {
+                let (Some(parent_pose), Some(child_pose)) = (pose(parent), pose(child)) else {
+                    notifications.warn("Cannot attach: object no longer exists");
+                    continue;
+                };
+                (
+                    parent,
+                    child,
+                    ObjectJoint::between(
+                        JointKind::Fixed,
+                        (parent, parent_pose),
+                        (child, child_pose),
+                    ),
+                    ObjectJoint::between(
+                        JointKind::Fixed,
+                        (child, child_pose),
+                        (parent, parent_pose),
+                    ),
+                )
+            }
+            JointEvent::Hinge {
+                parent,
+                child,
+                pivot,
+                axis,
+                limits,
+            } => {
+                let (Some(parent_pose), Some(child_pose)) = (pose(parent), pose(child)) else {
+                    notifications.warn("Cannot hinge: object no longer exists");
+                    continue;
+                };
+                if axis.normalize_or_zero() == Vec3::ZERO || !axis.is_finite() {
+                    notifications.warn("Cannot hinge: the axis has no direction");
+                    continue;
+                }
+                let pivot = pivot.unwrap_or_else(|| {
+                    parent_pose.translation().midpoint(child_pose.translation())
+                });
+                let limits = limits.map(|[min, max]| [min.min(max), min.max(max)]);
+                (
+                    parent,
+                    child,
+                    ObjectJoint::hinge(
+                        (parent, parent_pose),
+                        (child, child_pose),
+                        pivot,
+                        axis,
+                        limits,
+                    ),
+                    ObjectJoint::hinge(
+                        (child, child_pose),
+                        (parent, parent_pose),
+                        pivot,
+                        axis,
+                        limits,
+                    ),
+                )
+            }
+            JointEvent::Spring {
+                parent,
+                child,
+                rest_length,
+                stiffness,
+                damping,
+            } => {
+                let (Some(parent_pose), Some(child_pose)) = (pose(parent), pose(child)) else {
+                    notifications.warn("Cannot connect: object no longer exists");
+                    continue;
+                };
+                let spring = SpringParams {
+                    rest_length: rest_length.unwrap_or_else(|| {
+                        parent_pose.translation().distance(child_pose.translation())
+                    }),
+                    stiffness,
+                    damping,
+                }
+                .clamped();
+                (
+                    parent,
+                    child,
+                    ObjectJoint::between(
+                        JointKind::Spring(spring),
+                        (parent, parent_pose),
+                        (child, child_pose),
+                    ),
+                    ObjectJoint::between(
+                        JointKind::Spring(spring),
+                        (child, child_pose),
+                        (parent, parent_pose),
+                    ),
+                )
+            }
+            JointEvent::SetSpring { a, b, spring } => {
+                let Some(mut joint) = joints.get(a, b).copied() else {
+                    notifications.warn(format!("{} and {} aren't attached", name(a), name(b)));
+                    continue;
+                };
+                let JointKind::Spring(current) = &mut joint.kind else {
+                    notifications.warn("Only springs have a rest length");
+                    continue;
+                };
+                *current = spring.clamped();
+                if let Err(err) = joints.attach(&mut commands, &game_manager, joint) {
+                    notifications.warn(format!("Cannot change the spring: {}", err));
+                }
+                continue;
+            }
+            JointEvent::SetLimits { a, b, limits } => {
+                let Some(mut joint) = joints.get(a, b).copied() else {
+                    notifications.warn(format!("{} and {} aren't attached", name(a), name(b)));
+                    continue;
+                };
+                let JointKind::Revolute {
+                    limits: current, ..
+                } = &mut joint.kind
+                else {
+                    notifications.warn("Only hinges have angle limits");
+                    continue;
+                };
+                *current = limits.map(|[min, max]| [min.min(max), min.max(max)]);
+                if let Err(err) = joints.attach(&mut commands, &game_manager, joint) {
+                    notifications.warn(format!("Cannot change the limits: {}", err));
+                }
+                continue;
+            }
+            JointEvent::Detach { a, b } => {
+                if joints.detach(&mut commands, &game_manager, a, b).is_some() {
+                    notifications.info(format!("Detached {} from {}", name(b), name(a)));
+                } else {
+                    notifications.warn(format!("{} and {} aren't attached", name(a), name(b)));
+                }
+                continue;
+            }
+            JointEvent::DetachAll(id) => {
+                let partners: Vec<u32> = joints.of(id).map(|joint| joint.partner(id)).collect();
+                for partner in &partners {
+                    joints.detach(&mut commands, &game_manager, id, *partner);
+                }
+                if !partners.is_empty() {
+                    notifications.info(format!(
+                        "Detached {} from {} object(s)",
+                        name(id),
+                        partners.len()
+                    ));
+                }
+                continue;
+            }
+        };
+
+        // Try the other way around before giving up on an object that already
+        // hangs from a joint
+        let kind = joint.kind.label();
+        let result = joints
+            .attach(&mut commands, &game_manager, joint)
+            .or_else(|err| {
+                joints
+                    .attach(&mut commands, &game_manager, swapped)
+                    .map_err(|_| err)
+            });
+        match result {
+            Ok(()) => {
+                let message = format!(
+                    "Attached {} to {} ({})",
+                    name(child),
+                    name(parent),
+                    kind.to_lowercase()
+                );
+                info!("{}", message);
+                notifications.info(message);
+            }
+            Err(err) => notifications.warn(format!("Cannot attach: {}", err)),
+        }
+    }
+}
+
+// Drop joints whose objects were deleted. A reused id belongs to a new object,
+// so the child's entity has to match as well
+pub fn prune_joints_system(
+    mut commands: Commands,
+    mut joints: ResMut<JointRegistry>,
+    game_manager: Res<GameObjectManager>,
+) {
+    if joints.is_empty() {
+        return;
+    }
+    joints.entries.retain(|_, entry| {
+        let alive = game_manager.get_object_by_id(entry.joint.parent).is_some()
+            && game_manager
+                .get_object_by_id(entry.joint.child)
+                .is_some_and(|obj| obj.entity() == entry.entity);
+        if !alive {
+            commands.entity(entry.entity).try_remove::<ImpulseJoint>();
+            info!(
+                "Removed joint between objects {} and {}",
+                entry.joint.parent, entry.joint.child
+            );
+        }
+        alive
+    });
+}
+
+// Stiffness and damping new springs get, edited in the Joints section
+#[derive(Resource, Debug, Clone)]
+pub struct SpringTool {
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl Default for SpringTool {
+    fn default() -> Self {
+        Self {
+            stiffness: DEFAULT_STIFFNESS,
+            damping: DEFAULT_DAMPING,
+        }
+    }
+}
+
+impl SpringTool {
+    // A spring resting at the objects' current distance
+    pub fn event(&self, parent: u32, child: u32) -> JointEvent {
+        JointEvent::Spring {
+            parent,
+            child,
+            rest_length: None,
+            stiffness: self.stiffness,
+            damping: self.damping,
+        }
+    }
+}
+
+// Springs as lines between the centers, red when stretched and blue when
+// compressed
+pub fn draw_springs_system(
+    mut gizmos: Gizmos,
+    joints: Res<JointRegistry>,
+    game_manager: Res<GameObjectManager>,
+) {
+    for joint in joints.iter() {
+        let JointKind::Spring(spring) = joint.kind else {
+            continue;
+        };
+        let (Some(parent), Some(child)) = (
+            game_manager.get_object_by_id(joint.parent),
+            game_manager.get_object_by_id(joint.child),
+        ) else {
+            continue;
+        };
+        let stretch = spring.stretch(parent.position().distance(child.position()));
+        gizmos.line(parent.position(), child.position(), spring_color(stretch));
+    }
+}
+
+// Axis choices in the hinge dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HingeAxis {
+    X,
+    #[default]
+    Y,
+    Z,
+    Custom,
+}
+
+impl HingeAxis {
+    pub fn all() -> [HingeAxis; 4] {
+        [HingeAxis::X, HingeAxis::Y, HingeAxis::Z, HingeAxis::Custom]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HingeAxis::X => "X",
+            HingeAxis::Y => "Y",
+            HingeAxis::Z => "Z",
+            HingeAxis::Custom => "Custom",
+        }
+    }
+}
+
+// Where the hinge's pivot goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HingeAnchor {
+    // Halfway between the two objects
+    #[default]
+    Midpoint,
+    // A point clicked in the viewport
+    Clicked,
+}
+
+// Settings for a hinge between two selected objects, limits in degrees
+#[derive(Resource, Debug, Clone)]
+pub struct HingeDialog {
+    pub open: bool,
+    // Parent and child
+    pub pair: Option<(u32, u32)>,
+    pub axis: HingeAxis,
+    // World space, used when `axis` is Custom
+    pub custom_axis: Vec3,
+    pub limited: bool,
+    pub limits: [f32; 2],
+    pub anchor: HingeAnchor,
+    // Last clicked pivot, world space
+    pub point: Option<Vec3>,
+    // Waiting for a click in the viewport, the dialog hides meanwhile
+    pub picking: bool,
+}
+
+impl Default for HingeDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            pair: None,
+            axis: HingeAxis::default(),
+            custom_axis: Vec3::Y,
+            limited: false,
+            limits: [-90.0, 90.0],
+            anchor: HingeAnchor::default(),
+            point: None,
+            picking: false,
+        }
+    }
+}
+
+impl HingeDialog {
+    pub fn open_for(&mut self, parent: u32, child: u32) {
+        self.pair = Some((parent, child));
+        self.point = None;
+        self.picking = false;
+        self.open = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.picking = false;
+    }
+
+    pub fn axis_vector(&self) -> Vec3 {
+        match self.axis {
+            HingeAxis::X => Vec3::X,
+            HingeAxis::Y => Vec3::Y,
+            HingeAxis::Z => Vec3::Z,
+            HingeAxis::Custom => self.custom_axis.normalize_or_zero(),
+        }
+    }
+
+    // The request to send, None until the settings are usable
+    pub fn event(&self) -> Option<JointEvent> {
+        let (parent, child) = self.pair?;
+        let axis = self.axis_vector();
+        if axis == Vec3::ZERO {
+            return None;
+        }
+        let pivot = match self.anchor {
+            HingeAnchor::Midpoint => None,
+            HingeAnchor::Clicked => Some(self.point?),
+        };
+        Some(JointEvent::Hinge {
+            parent,
+            child,
+            pivot,
+            axis,
+            limits: self.limited.then(|| limits_from_degrees(self.limits)),
+        })
+    }
+}
+
+pub fn hinge_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<HingeDialog>,
+    game_manager: Res<GameObjectManager>,
+    mut joint_events: EventWriter<JointEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if !dialog.open || dialog.picking {
+        return;
+    }
+    let Some((parent, child)) = dialog.pair else {
+        dialog.close();
+        return;
+    };
+    let name = |id: u32| {
+        game_manager
+            .get_object_by_id(id)
+            .map(|obj| obj.name.clone())
+    };
+    let (Some(parent_name), Some(child_name)) = (name(parent), name(child)) else {
+        dialog.close();
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Hinge")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!("Hinge {} to {}", child_name, parent_name));
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Axis");
+                for axis in HingeAxis::all() {
+                    ui.selectable_value(&mut dialog.axis, axis, axis.label());
+                }
+            });
+            if dialog.axis == HingeAxis::Custom {
+                ui.horizontal(|ui| {
+                    let custom = &mut dialog.custom_axis;
+                    for (value, prefix) in [
+                        (&mut custom.x, "x: "),
+                        (&mut custom.y, "y: "),
+                        (&mut custom.z, "z: "),
+                    ] {
+                        ui.add(
+                            egui::DragValue::new(value)
+                                .speed(0.05)
+                                .range(-1.0..=1.0)
+                                .prefix(prefix),
+                        );
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut dialog.limited, "Limits");
+                let limited = dialog.limited;
+                ui.add_enabled_ui(limited, |ui| limits_ui(ui, &mut dialog.limits));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Pivot");
+                ui.radio_value(&mut dialog.anchor, HingeAnchor::Midpoint, "Midpoint");
+                ui.radio_value(&mut dialog.anchor, HingeAnchor::Clicked, "Clicked point");
+            });
+            if dialog.anchor == HingeAnchor::Clicked {
+                ui.horizontal(|ui| {
+                    match dialog.point {
+                        Some(point) => {
+                            ui.label(format!("({:.2}, {:.2}, {:.2})", point.x, point.y, point.z))
+                        }
+                        None => ui.weak("No point yet"),
+                    };
+                    if ui.button("Pick in viewport").clicked() {
+                        dialog.picking = true;
+                        notifications.info("Click where the hinge goes, Escape cancels");
+                    }
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                let event = dialog.event();
+                if ui
+                    .add_enabled(event.is_some(), egui::Button::new("Create"))
+                    .clicked()
+                    && let Some(event) = event
+                {
+                    joint_events.write(event);
+                    dialog.close();
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.close();
+                }
+            });
+        });
+}
+
+// Pick the hinge dialog's pivot with a click, on the surface under the cursor
+// or the y = 0 plane past it
+pub fn hinge_pick_system(
+    ViewportInput {
+        keyboard_input,
+        mouse_input,
+        egui_input,
+        ..
+    }: ViewportInput,
+    gizmo: Res<TransformGizmo>,
+    mut dialog: ResMut<HingeDialog>,
+    picker: CursorPick,
+) {
+    if !dialog.picking {
+        return;
+    }
+    if !egui_input.wants_keyboard_input() && keyboard_input.just_pressed(KeyCode::Escape) {
+        dialog.picking = false;
+        return;
+    }
+    if !mouse_input.just_pressed(MouseButton::Left)
+        || egui_input.wants_any_pointer_input()
+        || gizmo.hovered.is_some()
+    {
+        return;
+    }
+    let Some(ray) = picker.ray() else {
+        return;
+    };
+    let hit = picker.cast(ray, PICK_RAY_LENGTH).map(|(_, toi)| toi);
+    let Some(distance) =
+        hit.or_else(|| ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)))
+    else {
+        return;
+    };
+    dialog.point = Some(ray.get_point(distance));
+    dialog.picking = false;
+}
+
+// Min and max drag values in degrees
+fn limits_ui(ui: &mut egui::Ui, degrees: &mut [f32; 2]) -> bool {
+    let mut changed = false;
+    for (value, prefix) in degrees.iter_mut().zip(["min: ", "max: "]) {
+        changed |= ui
+            .add(
+                egui::DragValue::new(value)
+                    .speed(1.0)
+                    .range(-180.0..=180.0)
+                    .prefix(prefix)
+                    .suffix("°"),
+            )
+            .changed();
+    }
+    changed
+}
+
+// Rest length, stiffness and damping drag values
+fn spring_ui(ui: &mut egui::Ui, spring: &mut SpringParams) -> bool {
+    let mut changed = false;
+    for (value, prefix, suffix) in [
+        (&mut spring.rest_length, "rest: ", " m"),
+        (&mut spring.stiffness, "k: ", ""),
+        (&mut spring.damping, "damping: ", ""),
+    ] {
+        changed |= ui
+            .add(
+                egui::DragValue::new(value)
+                    .speed(0.05)
+                    .range(0.0..=f32::MAX)
+                    .prefix(prefix)
+                    .suffix(suffix),
+            )
+            .changed();
+    }
+    changed
+}
+
+// Joints of the selected object with a Detach button each, plus Attach,
+// Hinge and Connect with spring when exactly two objects are selected
+pub fn joints_ui(
+    ui: &mut egui::Ui,
+    selection: &Selection,
+    game_manager: &GameObjectManager,
+    joints: &JointRegistry,
+    hinge: &mut HingeDialog,
+    spring_tool: &mut SpringTool,
+) -> Option<JointEvent> {
+    let ids: Vec<u32> = selection
+        .entities
+        .iter()
+        .filter_map(|&entity| game_manager.get_object_by_entity(entity))
+        .map(|obj| obj.id)
+        .collect();
+    let mut event = None;
+
+    egui::CollapsingHeader::new("Joints").show(ui, |ui| {
+        if let [parent, child] = ids[..] {
+            if joints.get(parent, child).is_some() {
+                if ui.button("Detach").clicked() {
+                    event = Some(JointEvent::Detach {
+                        a: parent,
+                        b: child,
+                    });
+                }
+            } else {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Attach (fixed joint)")
+                        .on_hover_text(
+                            "Weld the second selected object to the first where they are now",
+                        )
+                        .clicked()
+                    {
+                        event = Some(JointEvent::Attach { parent, child });
+                    }
+                    if ui
+                        .button("Hinge...")
+                        .on_hover_text("Let the second selected object swing about an axis")
+                        .clicked()
+                    {
+                        hinge.open_for(parent, child);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Connect with spring")
+                        .on_hover_text("A spring resting at their current distance")
+                        .clicked()
+                    {
+                        event = Some(spring_tool.event(parent, child));
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut spring_tool.stiffness)
+                            .speed(0.5)
+                            .range(0.0..=f32::MAX)
+                            .prefix("k: "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut spring_tool.damping)
+                            .speed(0.05)
+                            .range(0.0..=f32::MAX)
+                            .prefix("damping: "),
+                    );
+                });
+            }
+        }
+
+        let Some(&id) = ids.first().filter(|_| ids.len() == 1) else {
+            if ids.len() != 2 {
+                ui.weak("Select two objects to attach them");
+            }
+            return;
+        };
+        let mut any = false;
+        for joint in joints.of(id) {
+            any = true;
+            let partner = joint.partner(id);
+            let partner_name = game_manager
+                .get_object_by_id(partner)
+                .map_or("?", |obj| obj.name.as_str());
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} joint with {}",
+                    joint.kind.label(),
+                    partner_name
+                ));
+                if ui.small_button("Detach").clicked() {
+                    event = Some(JointEvent::Detach { a: id, b: partner });
+                }
+            });
+            if let JointKind::Revolute { limits, .. } = joint.kind {
+                let mut limited = limits.is_some();
+                let mut degrees = limits.map_or([-90.0, 90.0], limits_to_degrees);
+                ui.horizontal(|ui| {
+                    let mut changed = ui.checkbox(&mut limited, "Limits").changed();
+                    ui.add_enabled_ui(limited, |ui| changed |= limits_ui(ui, &mut degrees));
+                    if changed {
+                        event = Some(JointEvent::SetLimits {
+                            a: id,
+                            b: partner,
+                            limits: limited.then(|| limits_from_degrees(degrees)),
+                        });
+                    }
+                });
+            }
+            if let JointKind::Spring(mut spring) = joint.kind {
+                if ui.horizontal(|ui| spring_ui(ui, &mut spring)).inner {
+                    event = Some(JointEvent::SetSpring {
+                        a: id,
+                        b: partner,
+                        spring,
+                    });
+                }
+                let positions = (
+                    game_manager.get_object_by_id(id),
+                    game_manager.get_object_by_id(partner),
+                );
+                if let (Some(obj), Some(other)) = positions {
+                    let length = obj.position().distance(other.position());
+                    ui.weak(format!(
+                        "Length now {:.2} m, {:+.0}% of rest",
+                        length,
+                        spring.stretch(length) * 100.0
+                    ));
+                }
+            }
+        }
+        if !any {
+            ui.weak("No joints, select two objects to attach them");
+        }
+    });
+    event
+}
+
+// Selected objects in selection order
+fn selected_ids(world: &World) -> Vec<u32> {
+    let game_manager = world.resource::<GameObjectManager>();
+    world
+        .resource::<Selection>()
+        .entities
+        .iter()
+        .filter_map(|&entity| game_manager.get_object_by_entity(entity))
+        .map(|obj| obj.id)
+        .collect()
+}
+
+pub fn register_joint_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Attach selection (fixed joint)", None, |world| {
+        if let [parent, child] = selected_ids(world)[..] {
+            world.send_event(JointEvent::Attach { parent, child });
+        } else if let Some(mut notifications) = world.get_resource_mut::<EditorNotifications>() {
+            notifications.warn("Select exactly two objects to attach them");
+        }
+    });
+    registry.register("Hinge selection...", None, |world| {
+        if let [parent, child] = selected_ids(world)[..] {
+            world.resource_mut::<HingeDialog>().open_for(parent, child);
+        } else if let Some(mut notifications) = world.get_resource_mut::<EditorNotifications>() {
+            notifications.warn("Select exactly two objects to hinge them");
+        }
+    });
+    registry.register("Connect selection with spring", None, |world| {
+        if let [parent, child] = selected_ids(world)[..] {
+            let event = world.resource::<SpringTool>().event(parent, child);
+            world.send_event(event);
+        } else if let Some(mut notifications) = world.get_resource_mut::<EditorNotifications>() {
+            notifications.warn("Select exactly two objects to connect them");
+        }
+    });
+    registry.register("Detach selection", None, |world| {
+        let ids = selected_ids(world);
+        if let [a, b] = ids[..] {
+            world.send_event(JointEvent::Detach { a, b });
+        } else {
+            for id in ids {
+                world.send_event(JointEvent::DetachAll(id));
+            }
+        }
+    });
+}
+
+// Joints between objects, the hinge dialog and the spring tool
+pub struct JointsPlugin;
+
+impl Plugin for JointsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<JointEvent>()
+            .init_resource::<JointRegistry>()
+            .init_resource::<HingeDialog>()
+            .init_resource::<SpringTool>()
+            .add_systems(Startup, register_joint_commands)
+            .add_systems(EguiContextPass, hinge_dialog_system.in_set(UiSet::Popups))
+            .add_systems(Update, draw_springs_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 2], expected: [f32; 2]) {
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-4, "{:?}", actual);
+        }
+    }
+
+    #[test]
+    fn limits_convert_to_radians() {
+        assert_close(
+            limits_from_degrees([-90.0, 45.0]),
+            [-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_4],
+        );
+        assert_eq!(limits_from_degrees([0.0, 0.0]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn swapped_limits_are_put_in_order() {
+        assert_eq!(
+            limits_from_degrees([45.0, -90.0]),
+            limits_from_degrees([-90.0, 45.0])
+        );
+        let [min, max] = limits_from_degrees([170.0, 10.0]);
+        assert!(min < max);
+    }
+
+    #[test]
+    fn limits_read_back_in_degrees() {
+        for degrees in [[-90.0, 45.0], [0.0, 180.0], [-360.0, 360.0], [12.5, 12.5]] {
+            assert_close(limits_to_degrees(limits_from_degrees(degrees)), degrees);
+        }
+        assert_close(
+            limits_to_degrees(limits_from_degrees([30.0, -30.0])),
+            [-30.0, 30.0],
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::engine::core::UiSet;
+use crate::engine::objects::{GameObjectId, GameObjectManager, format_age};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::prefs::UiPrefs;
+use crate::engine::toolbar::MainCamera;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+
+// Labels start fading at this camera distance and are gone by the second
+const LABEL_FADE_START: f32 = 15.0;
+const LABEL_FADE_END: f32 = 40.0;
+// Gap between the top of an object and its label
+const LABEL_GAP: f32 = 0.3;
+const HOVER_RAY_LENGTH: f32 = 500.0;
+
+// Viewport overlays for telling objects apart
+#[derive(Resource, Debug, Clone)]
+pub struct ViewportLabels {
+    pub show_names: bool,
+    pub show_tooltips: bool,
+}
+
+impl Default for ViewportLabels {
+    fn default() -> Self {
+        Self {
+            show_names: false,
+            show_tooltips: true,
+        }
+    }
+}
+
+// Ray from the camera through the cursor, None when the cursor is outside the window
+pub fn cursor_ray(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Ray3d> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world(camera_transform, cursor).ok()
+}
+
+// The window, main camera and physics world, for finding what's under the cursor
+#[derive(SystemParam)]
+pub struct CursorPick<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+    rapier_context: ReadRapierContext<'w, 's>,
+}
+
+impl CursorPick<'_, '_> {
+    pub fn camera(&self) -> Option<(&Camera, &GlobalTransform)> {
+        self.cameras.single().ok()
+    }
+
+    // Cursor position in the window, None while it's outside
+    pub fn cursor(&self) -> Option<Vec2> {
+        self.windows.single().ok()?.cursor_position()
+    }
+
+    // Ray from the main camera through the cursor
+    pub fn ray(&self) -> Option<Ray3d> {
+        let (camera, camera_transform) = self.camera()?;
+        cursor_ray(self.windows.single().ok()?, camera, camera_transform)
+    }
+
+    // First collider along the ray within max_distance, and how far along it is
+    pub fn cast(&self, ray: Ray3d, max_distance: f32) -> Option<(Entity, f32)> {
+        self.rapier_context.single().ok()?.cast_ray(
+            ray.origin,
+            *ray.direction,
+            max_distance,
+            true,
+            QueryFilter::default(),
+        )
+    }
+}
+
+// Names drawn above objects and a tooltip for the object under the cursor
+pub fn viewport_labels_ui(
+    mut contexts: EguiContexts,
+    labels: Res<ViewportLabels>,
+    ui_prefs: Res<UiPrefs>,
+    game_manager: Res<GameObjectManager>,
+    objects: Query<(Entity, &GlobalTransform, &GameObjectId)>,
+    picker: CursorPick,
+    time: Res<Time>,
+) {
+    if !labels.show_names && !labels.show_tooltips {
+        return;
+    }
+    let Some((camera, camera_transform)) = picker.camera() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    // Viewport coordinates are logical pixels, egui points also include the UI scale
+    let to_egui = |pos: Vec2| egui::pos2(pos.x, pos.y) / ui_prefs.scale;
+
+    if labels.show_names {
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        let camera_pos = camera_transform.translation();
+        let forward = camera_transform.forward();
+        for (entity, transform, object_id) in &objects {
+            let Some(obj) = game_manager.get_object_by_entity(entity) else {
+                continue;
+            };
+            if obj.hidden {
+                continue;
+            }
+            let anchor = transform.translation()
+                + Vec3::Y * (obj.dimensions.half_height(obj.shape_type) + LABEL_GAP);
+            // Points behind the camera still project, so check the side explicitly
+            if forward.dot(anchor - camera_pos) <= 0.0 {
+                continue;
+            }
+            let Ok(screen) = camera.world_to_viewport(camera_transform, anchor) else {
+                continue;
+            };
+
+            let distance = camera_pos.distance(anchor);
+            let fade = 1.0
+                - ((distance - LABEL_FADE_START) / (LABEL_FADE_END - LABEL_FADE_START))
+                    .clamp(0.0, 1.0);
+            if fade <= 0.0 {
+                continue;
+            }
+            painter.text(
+                to_egui(screen),
+                egui::Align2::CENTER_BOTTOM,
+                &object_id.name,
+                egui::FontId::proportional(13.0),
+                egui::Color32::WHITE.gamma_multiply(fade),
+            );
+        }
+    }
+
+    if !labels.show_tooltips || ctx.is_pointer_over_area() {
+        return;
+    }
+    let Some(obj) = picker
+        .ray()
+        .and_then(|ray| picker.cast(ray, HOVER_RAY_LENGTH))
+        .and_then(|(entity, _)| game_manager.get_object_by_entity(entity))
+    else {
+        return;
+    };
+
+    let age = obj.age(time.elapsed_secs_f64());
+    let position = obj.position();
+    egui::show_tooltip_at_pointer(
+        ctx,
+        egui::LayerId::background(),
+        egui::Id::new("viewport_object_tooltip"),
+        |ui| {
+            ui.strong(&obj.name);
+            ui.label(format!("ID: {}, {}", obj.id, obj.shape_type.display_name()));
+            ui.label(format!(
+                "Position: ({:.2}, {:.2}, {:.2})",
+                position.x, position.y, position.z
+            ));
+            ui.label(format!("Age: {}", format_age(age)));
+        },
+    );
+}
+
+pub fn register_label_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Toggle object labels", None, |world| {
+        let mut labels = world.resource_mut::<ViewportLabels>();
+        labels.show_names = !labels.show_names;
+    });
+    registry.register("Toggle hover tooltips", None, |world| {
+        let mut labels = world.resource_mut::<ViewportLabels>();
+        labels.show_tooltips = !labels.show_tooltips;
+    });
+}
+
+// Object names and hover tooltips drawn over the viewport
+pub struct LabelsPlugin;
+
+impl Plugin for LabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewportLabels>()
+            .add_systems(Startup, register_label_commands)
+            .add_systems(EguiContextPass, viewport_labels_ui.in_set(UiSet::Popups));
+    }
+}
@@ -0,0 +1,99 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Saved state of a single editor window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub open: bool,
+    pub pos: Option<[f32; 2]>,
+    pub size: Option<[f32; 2]>,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            open: true,
+            pos: None,
+            size: None,
+        }
+    }
+}
+
+// Open state, position and size of each editor window, keyed by title
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorLayout {
+    pub panels: BTreeMap<String, PanelLayout>,
+    // Bumped on reset so egui forgets the old window state
+    #[serde(skip)]
+    pub generation: u32,
+}
+
+// The egui context and the saved layout an editor window is drawn with
+#[derive(SystemParam)]
+pub struct PanelContext<'w, 's> {
+    pub contexts: EguiContexts<'w, 's>,
+    pub layout: ResMut<'w, EditorLayout>,
+}
+
+impl EditorLayout {
+    pub fn is_open(&self, title: &str) -> bool {
+        self.panels.get(title).is_none_or(|panel| panel.open)
+    }
+
+    pub fn set_open(&mut self, title: &str, open: bool) {
+        self.panels.entry(title.to_string()).or_default().open = open;
+    }
+
+    pub fn toggle(&mut self, title: &str) {
+        let open = self.is_open(title);
+        self.set_open(title, !open);
+    }
+
+    pub fn reset(&mut self) {
+        self.panels.clear();
+        self.generation += 1;
+    }
+
+    // Show a window with its saved layout applied and record where it ends up
+    pub fn show<R>(
+        &mut self,
+        ctx: &egui::Context,
+        title: &str,
+        window: egui::Window<'_>,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) -> Option<R> {
+        let generation = self.generation;
+        let panel = self.panels.entry(title.to_string()).or_default();
+        if !panel.open {
+            return None;
+        }
+
+        let mut open = true;
+        let mut window = window
+            .id(egui::Id::new(("editor_panel", title, generation)))
+            .open(&mut open);
+        if let Some(pos) = panel.pos {
+            window = window.default_pos(pos);
+        }
+        if let Some(size) = panel.size {
+            window = window.default_size(size);
+        }
+
+        let response = window.show(ctx, |ui| {
+            let size = ui.max_rect().size();
+            (add_contents(ui), size)
+        });
+
+        panel.open = open;
+        let response = response?;
+        panel.pos = Some(response.response.rect.min.into());
+        let (inner, size) = response.inner?;
+        panel.size = Some(size.into());
+        Some(inner)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::engine::objects::{DespawnEntityEvent, DespawnTarget, GameObjectManager};
+use crate::engine::simulation::SimulationState;
+use bevy::prelude::*;
+
+// Shortest lifetime the spawn settings accept, in seconds
+pub const MIN_LIFETIME_SECS: f32 = 0.1;
+
+// Seconds left before the object removes itself
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Lifetime {
+    pub remaining: f32,
+    pub total: f32,
+}
+
+impl Lifetime {
+    pub fn new(secs: f32) -> Self {
+        let secs = secs.max(MIN_LIFETIME_SECS);
+        Self {
+            remaining: secs,
+            total: secs,
+        }
+    }
+
+    // Share of the lifetime already used, 0 when spawned and 1 when expired
+    pub fn fraction_elapsed(&self) -> f32 {
+        (1.0 - self.remaining / self.total).clamp(0.0, 1.0)
+    }
+}
+
+// Default lifetime for objects spawned from the keyboard
+#[derive(Resource, Debug, Clone)]
+pub struct LifetimeSettings {
+    pub keyboard_spawns: bool,
+    pub default_secs: f32,
+}
+
+impl Default for LifetimeSettings {
+    fn default() -> Self {
+        Self {
+            keyboard_spawns: false,
+            default_secs: 5.0,
+        }
+    }
+}
+
+impl LifetimeSettings {
+    // Lifetime for a keyboard spawn, None when the setting is off
+    pub fn keyboard_lifetime(&self) -> Option<f32> {
+        self.keyboard_spawns.then_some(self.default_secs)
+    }
+}
+
+// Count down while the simulation runs and hand expired objects to the usual
+// despawn path in one event. Locked objects hold their remaining time
+pub fn lifetime_system(
+    time: Res<Time>,
+    simulation: Res<SimulationState>,
+    game_manager: Res<GameObjectManager>,
+    mut lifetimes: Query<(Entity, &mut Lifetime)>,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
+) {
+    if simulation.is_paused() {
+        return;
+    }
+
+    let delta = time.delta_secs();
+    let mut expired = Vec::new();
+    for (entity, mut lifetime) in lifetimes.iter_mut() {
+        let Some(obj) = game_manager.get_object_by_entity(entity) else {
+            continue;
+        };
+        if obj.locked || lifetime.remaining <= 0.0 {
+            continue;
+        }
+        lifetime.remaining -= delta;
+        if lifetime.remaining <= 0.0 {
+            expired.push(obj.id);
+        }
+    }
+
+    if !expired.is_empty() {
+        despawn_events.write(DespawnEntityEvent {
+            target: DespawnTarget::Expired(expired),
+        });
+    }
+}
+
+// Default lifetime given to new objects
+pub struct LifetimePlugin;
+
+impl Plugin for LifetimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LifetimeSettings>();
+    }
+}
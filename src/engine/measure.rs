@@ -0,0 +1,360 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::gizmo::TransformGizmo;
+use crate::engine::input::{InputAction, InputBindings, ViewportInput, key_name};
+use crate::engine::labels::CursorPick;
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::GameObjectManager;
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::prefs::UiPrefs;
+use crate::engine::toolbar::MainCamera;
+use bevy::prelude::*;
+use bevy_egui::*;
+
+const MEASURE_COLOR: Color = Color::srgb(1.0, 0.9, 0.2);
+const PENDING_COLOR: Color = Color::srgb(1.0, 0.5, 0.2);
+const ENDPOINT_RADIUS: f32 = 0.08;
+const MEASURE_RAY_LENGTH: f32 = 500.0;
+
+// One end of a measurement, pinned to an object so it follows it around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeasureEndpoint {
+    // Object id, None for a point picked on the ground or once the object is gone
+    pub object: Option<u32>,
+    pub position: Vec3,
+}
+
+impl MeasureEndpoint {
+    fn label(&self) -> String {
+        match self.object {
+            Some(id) => format!("object {}", id),
+            None => format!(
+                "({:.2}, {:.2}, {:.2})",
+                self.position.x, self.position.y, self.position.z
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub id: u32,
+    pub start: MeasureEndpoint,
+    pub end: MeasureEndpoint,
+}
+
+impl Measurement {
+    pub fn delta(&self) -> Vec3 {
+        self.end.position - self.start.position
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.delta().length()
+    }
+
+    fn midpoint(&self) -> Vec3 {
+        self.start.position.lerp(self.end.position, 0.5)
+    }
+}
+
+// Distances measured in the viewport, several can be kept at once
+#[derive(Resource, Default)]
+pub struct Measurements {
+    pub measurements: Vec<Measurement>,
+    // Clicks pick endpoints while this is on
+    pub active: bool,
+    // First endpoint of a measurement still waiting for its second click
+    pub pending: Option<MeasureEndpoint>,
+    next_id: u32,
+}
+
+impl Measurements {
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+        if !active {
+            self.pending = None;
+        }
+    }
+
+    // First click starts a measurement, the second one finishes it
+    pub fn pick(&mut self, endpoint: MeasureEndpoint) {
+        let Some(start) = self.pending.take() else {
+            self.pending = Some(endpoint);
+            return;
+        };
+        self.measurements.push(Measurement {
+            id: self.next_id,
+            start,
+            end: endpoint,
+        });
+        self.next_id += 1;
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.measurements.retain(|m| m.id != id);
+    }
+
+    pub fn clear(&mut self) {
+        self.measurements.clear();
+        self.pending = None;
+    }
+}
+
+// Toggle measure mode, pick endpoints with left click and cancel with Escape
+pub fn measure_input_system(
+    ViewportInput {
+        keyboard_input,
+        mouse_input,
+        egui_input,
+        bindings,
+    }: ViewportInput,
+    gizmo: Res<TransformGizmo>,
+    mut measurements: ResMut<Measurements>,
+    game_manager: Res<GameObjectManager>,
+    picker: CursorPick,
+) {
+    if !egui_input.wants_keyboard_input() {
+        if bindings.just_pressed(InputAction::MeasureMode, &keyboard_input) {
+            let active = !measurements.active;
+            measurements.set_active(active);
+        }
+        if keyboard_input.just_pressed(KeyCode::Escape) && measurements.pending.is_some() {
+            measurements.pending = None;
+        }
+    }
+
+    if !measurements.active
+        || !mouse_input.just_pressed(MouseButton::Left)
+        || egui_input.wants_any_pointer_input()
+    {
+        return;
+    }
+    // Gizmo handles keep priority over measuring
+    if gizmo.hovered.is_some() || gizmo.is_dragging() {
+        return;
+    }
+    let Some(ray) = picker.ray() else {
+        return;
+    };
+
+    let hit = picker.cast(ray, MEASURE_RAY_LENGTH);
+    let endpoint = match hit {
+        Some((entity, toi)) => match game_manager.get_object_by_entity(entity) {
+            Some(obj) => MeasureEndpoint {
+                object: Some(obj.id),
+                position: obj.position(),
+            },
+            None => MeasureEndpoint {
+                object: None,
+                position: ray.get_point(toi),
+            },
+        },
+        // Nothing under the cursor, fall back to the y = 0 plane
+        None => {
+            let Some(distance) = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))
+            else {
+                return;
+            };
+            MeasureEndpoint {
+                object: None,
+                position: ray.get_point(distance),
+            }
+        }
+    };
+    measurements.pick(endpoint);
+}
+
+// Follow endpoint objects after update_object_positions_system has copied
+// their transforms into the manager. A deleted object leaves its last position
+pub fn update_measurements_system(
+    game_manager: Res<GameObjectManager>,
+    mut measurements: ResMut<Measurements>,
+) {
+    if !game_manager.is_changed()
+        || (measurements.measurements.is_empty() && measurements.pending.is_none())
+    {
+        return;
+    }
+
+    let refresh = |endpoint: &mut MeasureEndpoint| {
+        let Some(id) = endpoint.object else {
+            return;
+        };
+        match game_manager.get_object_by_id(id) {
+            Some(obj) => endpoint.position = obj.position(),
+            None => endpoint.object = None,
+        }
+    };
+    if let Some(pending) = measurements.pending.as_mut() {
+        refresh(pending);
+    }
+    for measurement in measurements.measurements.iter_mut() {
+        refresh(&mut measurement.start);
+        refresh(&mut measurement.end);
+    }
+}
+
+pub fn draw_measurements_system(mut gizmos: Gizmos, measurements: Res<Measurements>) {
+    for measurement in &measurements.measurements {
+        gizmos.line(
+            measurement.start.position,
+            measurement.end.position,
+            MEASURE_COLOR,
+        );
+        for point in [measurement.start.position, measurement.end.position] {
+            gizmos.sphere(
+                Isometry3d::from_translation(point),
+                ENDPOINT_RADIUS,
+                MEASURE_COLOR,
+            );
+        }
+    }
+    if let Some(pending) = measurements.pending {
+        gizmos.sphere(
+            Isometry3d::from_translation(pending.position),
+            ENDPOINT_RADIUS,
+            PENDING_COLOR,
+        );
+    }
+}
+
+// Distance label at the middle of each measurement line
+pub fn measurement_labels_ui(
+    mut contexts: EguiContexts,
+    measurements: Res<Measurements>,
+    ui_prefs: Res<UiPrefs>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if measurements.measurements.is_empty() {
+        return;
+    }
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let forward = camera_transform.forward();
+    for measurement in &measurements.measurements {
+        let midpoint = measurement.midpoint();
+        if forward.dot(midpoint - camera_transform.translation()) <= 0.0 {
+            continue;
+        }
+        let Ok(screen) = camera.world_to_viewport(camera_transform, midpoint) else {
+            continue;
+        };
+        painter.text(
+            egui::pos2(screen.x, screen.y) / ui_prefs.scale,
+            egui::Align2::CENTER_BOTTOM,
+            format!("{:.2} m", measurement.distance()),
+            egui::FontId::proportional(14.0),
+            egui::Color32::YELLOW,
+        );
+    }
+}
+
+pub fn measure_panel_system(
+    mut contexts: EguiContexts,
+    mut measurements: ResMut<Measurements>,
+    bindings: Res<InputBindings>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Measure",
+        egui::Window::new("Measure").default_width(280.0),
+        |ui| {
+            let mut active = measurements.active;
+            if ui
+                .checkbox(&mut active, "Measure mode")
+                .on_hover_text(format!(
+                    "Click two objects or ground points ({})",
+                    key_name(bindings.key(InputAction::MeasureMode))
+                ))
+                .changed()
+            {
+                measurements.set_active(active);
+            }
+            if let Some(pending) = measurements.pending {
+                ui.label(format!(
+                    "From {}, click the second point (Esc cancels)",
+                    pending.label()
+                ));
+            }
+            ui.separator();
+
+            if measurements.measurements.is_empty() {
+                ui.label("No measurements");
+                return;
+            }
+
+            let mut removed = None;
+            egui::Grid::new("measurements_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for measurement in &measurements.measurements {
+                        ui.label(format!("{:.3}", measurement.distance()))
+                            .on_hover_text(format!(
+                                "{} to {}",
+                                measurement.start.label(),
+                                measurement.end.label()
+                            ));
+                        let delta = measurement.delta();
+                        ui.label(format!(
+                            "Δx {:.2}  Δy {:.2}  Δz {:.2}",
+                            delta.x, delta.y, delta.z
+                        ));
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            removed = Some(measurement.id);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(id) = removed {
+                measurements.remove(id);
+            }
+
+            ui.separator();
+            if ui.button("Clear all").clicked() {
+                measurements.clear();
+            }
+        },
+    );
+}
+
+pub fn register_measure_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register(
+        "Toggle measure mode",
+        Some(CommandHint::Binding(InputAction::MeasureMode)),
+        |world| {
+            let mut measurements = world.resource_mut::<Measurements>();
+            let active = !measurements.active;
+            measurements.set_active(active);
+        },
+    );
+    registry.register("Clear measurements", None, |world| {
+        world.resource_mut::<Measurements>().clear();
+    });
+}
+
+// Distance measurements between points and objects
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Measurements>()
+            .add_systems(Startup, register_measure_commands)
+            .add_systems(EguiContextPass, measure_panel_system.in_set(UiSet::Panels))
+            .add_systems(EguiContextPass, measurement_labels_ui.in_set(UiSet::Popups))
+            .add_systems(
+                Update,
+                draw_measurements_system.in_set(EditorSet::Bookkeeping),
+            );
+    }
+}
@@ -0,0 +1,619 @@
+use crate::engine::scene::{SCENE_FORMAT_VERSION, SceneFile, SceneFormat, binary_payload};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A step from the version it's keyed by to the next one, run on the raw file
+// before it's read as a SceneFile
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+// Registered steps, oldest first. Version 1 is the first scene format, so
+// there's nothing to upgrade yet. When the format changes, bump
+// SCENE_FORMAT_VERSION and add (old version, step) here
+pub const MIGRATIONS: &[(u32, Migration)] = &[];
+
+// Only the version, so a file can be checked before the rest is understood
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
+}
+
+pub fn probe_version(format: SceneFormat, contents: &[u8]) -> Result<u32, String> {
+    let probe: Result<VersionProbe, String> = match format {
+        SceneFormat::Ron => {
+            ron::from_str(SceneFormat::text(contents)?).map_err(|err| err.to_string())
+        }
+        SceneFormat::Json => serde_json::from_slice(contents).map_err(|err| err.to_string()),
+        SceneFormat::Binary => {
+            rmp_serde::from_slice(binary_payload(contents)?).map_err(|err| err.to_string())
+        }
+    };
+    probe
+        .map(|probe| probe.version)
+        .map_err(|err| format!("cannot read the format version: {}", err))
+}
+
+// Refuse files written by a later editor
+pub fn check_version(version: u32) -> Result<(), String> {
+    if version > SCENE_FORMAT_VERSION {
+        return Err(format!(
+            "scene is format version {}, it requires a newer Navi (this one reads up to version {})",
+            version, SCENE_FORMAT_VERSION
+        ));
+    }
+    Ok(())
+}
+
+// Run every step in `steps` from `version` up to `latest`. Files go through
+// MIGRATIONS up to SCENE_FORMAT_VERSION
+pub fn migrate_value(
+    value: &mut Value,
+    version: u32,
+    latest: u32,
+    steps: &[(u32, Migration)],
+) -> Result<(), String> {
+    for from in version..latest {
+        let Some((_, step)) = steps.iter().find(|(step_from, _)| *step_from == from) else {
+            return Err(format!("no migration from scene format version {}", from));
+        };
+        step(value).map_err(|err| format!("migrating from version {}: {}", from, err))?;
+        value["version"] = Value::from(from + 1);
+    }
+    Ok(())
+}
+
+// Read an older file through the migrations
+pub fn read_migrated(
+    format: SceneFormat,
+    contents: &[u8],
+    version: u32,
+) -> Result<SceneFile, String> {
+    let mut value = match format {
+        SceneFormat::Ron => ron_to_value(SceneFormat::text(contents)?)?,
+        SceneFormat::Json => serde_json::from_slice(contents).map_err(|err| err.to_string())?,
+        SceneFormat::Binary => {
+            rmp_serde::from_slice(binary_payload(contents)?).map_err(|err| err.to_string())?
+        }
+    };
+    migrate_value(&mut value, version, SCENE_FORMAT_VERSION, MIGRATIONS)?;
+    serde_json::from_value(value)
+        .map_err(|err| format!("after migrating from version {}: {}", version, err))
+}
+
+// Upgrade a scene file to the current format in place, keeping the original
+// next to it as <file>.bak. Returns a line for the terminal
+pub fn migrate_file(path: &Path) -> Result<String, String> {
+    let contents = fs::read(path).map_err(|err| err.to_string())?;
+    let version = probe_version(SceneFormat::of(path), &contents)?;
+    check_version(version)?;
+    if version == SCENE_FORMAT_VERSION {
+        return Ok(format!(
+            "{} is already format version {}",
+            path.display(),
+            version
+        ));
+    }
+
+    let scene = SceneFile::read(path)?;
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup).map_err(|err| format!("cannot write backup: {}", err))?;
+    scene.write(path)?;
+    Ok(format!(
+        "Migrated {} from version {} to {}, original kept as {}",
+        path.display(),
+        version,
+        SCENE_FORMAT_VERSION,
+        backup.display()
+    ))
+}
+
+// Not ron::Value: ron 0.8's deserialize_any reads an identifier and throws it
+// away, so `Revolute(pivot: ..)` comes back as a bare Map and `Fixed` as Unit,
+// and no migration could tell which joint kind it's looking at. RON files are
+// read into JSON values here instead. Covers what Save scene writes: structs,
+// tuples, lists, maps, strings, numbers, booleans, options and enum variants,
+// which are tagged the same way serde_json writes them
+pub fn ron_to_value(source: &str) -> Result<Value, String> {
+    let mut reader = RonReader {
+        source: source.as_bytes(),
+        pos: 0,
+    };
+    let value = reader.value()?;
+    reader.skip_ws();
+    if reader.pos < reader.source.len() {
+        return Err(reader.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct RonReader<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl RonReader<'_> {
+    fn error(&self, message: &str) -> String {
+        let before = &self.source[..self.pos.min(self.source.len())];
+        let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+        let column = before
+            .iter()
+            .rev()
+            .take_while(|&&byte| byte != b'\n')
+            .count()
+            + 1;
+        format!(
+            "invalid RON at line {}, column {}: {}",
+            line, column, message
+        )
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.pos).copied()
+    }
+
+    // Whitespace, comments and the #![enable(..)] header
+    fn skip_ws(&mut self) {
+        loop {
+            let rest = &self.source[self.pos..];
+            if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                self.pos += 1;
+            } else if rest.starts_with(b"//") || rest.starts_with(b"#!") {
+                let end = rest
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .unwrap_or(rest.len());
+                self.pos += end;
+            } else if rest.starts_with(b"/*") {
+                let end = rest
+                    .windows(2)
+                    .position(|pair| pair == b"*/")
+                    .map_or(rest.len(), |end| end + 2);
+                self.pos += end;
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+
+    // Consume a comma if there is one, true at the closing bracket
+    fn list_end(&mut self, close: u8) -> Result<bool, String> {
+        self.skip_ws();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_ws();
+        } else if self.peek() != Some(close) {
+            return Err(self.error(&format!("expected `,` or `{}`", close as char)));
+        }
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn identifier(&mut self) -> Option<&str> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+        {
+            self.pos += 1;
+        }
+        (self.pos > start)
+            .then(|| std::str::from_utf8(&self.source[start..self.pos]).ok())
+            .flatten()
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => {
+                self.pos += 1;
+                self.sequence(b']').map(Value::Array)
+            }
+            Some(b'{') => self.map(),
+            Some(b'(') => {
+                self.pos += 1;
+                self.parenthesized()
+            }
+            Some(byte) if byte == b'-' || byte == b'+' || byte == b'.' || byte.is_ascii_digit() => {
+                self.number()
+            }
+            Some(byte) if byte.is_ascii_alphabetic() || byte == b'_' => {
+                let start = self.pos;
+                let Some(ident) = self.identifier().map(str::to_string) else {
+                    return Err(self.error("expected a value"));
+                };
+                self.skip_ws();
+                let called = self.peek() == Some(b'(');
+                match ident.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "None" => Ok(Value::Null),
+                    "Some" if called => {
+                        self.pos += 1;
+                        let value = self.value()?;
+                        self.expect(b')')?;
+                        Ok(value)
+                    }
+                    "inf" | "NaN" => {
+                        self.pos = start;
+                        Err(self.error("non-finite numbers can't be migrated"))
+                    }
+                    // Save scene writes no struct names, so a name with
+                    // brackets is an enum variant. Tagged the way serde_json
+                    // writes it, with a newtype variant's one field unwrapped
+                    _ if called => {
+                        self.pos += 1;
+                        let value = match self.parenthesized()? {
+                            Value::Array(mut fields) if fields.len() == 1 => fields.remove(0),
+                            value => value,
+                        };
+                        Ok(Value::Object(Map::from_iter([(ident, value)])))
+                    }
+                    _ => Ok(Value::String(ident)),
+                }
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    // After `(`: a struct when it starts with `name:`, a tuple otherwise,
+    // `()` is unit
+    fn parenthesized(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        if self.peek() == Some(b')') {
+            self.pos += 1;
+            return Ok(Value::Null);
+        }
+        let start = self.pos;
+        let is_struct = self.identifier().is_some() && {
+            self.skip_ws();
+            self.peek() == Some(b':')
+        };
+        self.pos = start;
+        if !is_struct {
+            return self.sequence(b')').map(Value::Array);
+        }
+
+        let mut fields = Map::new();
+        loop {
+            self.skip_ws();
+            let Some(name) = self.identifier().map(str::to_string) else {
+                return Err(self.error("expected a field name"));
+            };
+            self.expect(b':')?;
+            fields.insert(name, self.value()?);
+            if self.list_end(b')')? {
+                return Ok(Value::Object(fields));
+            }
+        }
+    }
+
+    fn sequence(&mut self, close: u8) -> Result<Vec<Value>, String> {
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.value()?);
+            if self.list_end(close)? {
+                return Ok(items);
+            }
+        }
+    }
+
+    fn map(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut entries = Map::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            let key = match self.value()? {
+                Value::String(key) => key,
+                other => other.to_string(),
+            };
+            self.expect(b':')?;
+            entries.insert(key, self.value()?);
+            if self.list_end(b'}')? {
+                return Ok(Value::Object(entries));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|byte| {
+            byte.is_ascii_digit() || matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'_')
+        }) {
+            self.pos += 1;
+        }
+        let text: String = String::from_utf8_lossy(&self.source[start..self.pos]).replace('_', "");
+        let number = if text.contains(['.', 'e', 'E']) {
+            text.parse::<f64>().ok().and_then(Number::from_f64)
+        } else if let Ok(int) = text.parse::<i64>() {
+            Some(Number::from(int))
+        } else {
+            text.parse::<u64>().ok().map(Number::from)
+        };
+        number.map(Value::Number).ok_or_else(|| {
+            self.pos = start;
+            self.error(&format!("bad number `{}`", text))
+        })
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    match escape {
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'0' => bytes.push(0),
+                        b'u' => {
+                            let rest = &self.source[self.pos..];
+                            let end = rest.iter().position(|&byte| byte == b'}');
+                            let code = end
+                                .filter(|_| rest.first() == Some(&b'{'))
+                                .and_then(|end| std::str::from_utf8(&rest[1..end]).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("bad unicode escape"))?;
+                            self.pos += end.unwrap_or(0) + 1;
+                            bytes.extend_from_slice(code.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        other => bytes.push(other),
+                    }
+                }
+                other => bytes.push(other),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("string is not UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::sample_scene;
+    use crate::engine::joints::JointKind;
+
+    fn saved_ron(scene: &SceneFile) -> String {
+        ron::ser::to_string_pretty(scene, ron::ser::PrettyConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn saved_ron_reads_back_through_a_value() {
+        let scene = sample_scene();
+        let value = ron_to_value(&saved_ron(&scene)).unwrap();
+        assert_eq!(value["objects"][1]["parent"], Value::from(0));
+        assert_eq!(value["joints"][0]["kind"], Value::from("Fixed"));
+        assert_eq!(serde_json::from_value::<SceneFile>(value).unwrap(), scene);
+    }
+
+    #[test]
+    fn variants_with_fields_keep_their_name() {
+        let scene = sample_scene();
+        let value = ron_to_value(&saved_ron(&scene)).unwrap();
+        let kinds: Vec<&Value> = value["joints"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|joint| &joint["kind"])
+            .collect();
+        assert_eq!(
+            kinds[1]["Revolute"]["limits"],
+            serde_json::json!([-1.5, 0.25])
+        );
+        assert_eq!(kinds[2]["Revolute"]["limits"], Value::Null);
+        assert_eq!(kinds[3]["Spring"]["rest_length"], Value::from(2.5));
+
+        // Exactly what serde_json makes of the same scene
+        let json = serde_json::to_value(&scene).unwrap();
+        for (kind, written) in kinds.iter().zip(json["joints"].as_array().unwrap()) {
+            let keys = |value: &Value| {
+                value
+                    .as_object()
+                    .map(|map| map.keys().cloned().collect::<Vec<_>>())
+            };
+            assert_eq!(keys(kind), keys(&written["kind"]));
+        }
+        let joints: Vec<JointKind> = serde_json::from_value::<SceneFile>(value)
+            .unwrap()
+            .joints
+            .iter()
+            .map(|joint| joint.kind)
+            .collect();
+        let expected: Vec<JointKind> = scene.joints.iter().map(|joint| joint.kind).collect();
+        assert_eq!(joints, expected);
+    }
+
+    // Why RON isn't read through ron::Value
+    #[test]
+    fn ron_values_lose_variant_names() {
+        let value: ron::Value = ron::from_str("Revolute(axis: 1)").unwrap();
+        let ron::Value::Map(fields) = value else {
+            panic!("{:?}", value);
+        };
+        assert_eq!(fields.keys().count(), 1);
+        assert_eq!(
+            ron::from_str::<ron::Value>("Fixed").unwrap(),
+            ron::Value::Unit
+        );
+    }
+
+    #[test]
+    fn newtype_and_tuple_variants() {
+        assert_eq!(
+            ron_to_value("Spring((rest_length: 1.0))").unwrap(),
+            serde_json::json!({ "Spring": { "rest_length": 1.0 } })
+        );
+        assert_eq!(
+            ron_to_value("Pair(1, \"two\")").unwrap(),
+            serde_json::json!({ "Pair": [1, "two"] })
+        );
+        assert_eq!(
+            ron_to_value("[Fixed, Some(Fixed), None]").unwrap(),
+            serde_json::json!(["Fixed", "Fixed", null])
+        );
+    }
+
+    #[test]
+    fn every_format_reads_back_through_the_migration_path() {
+        let dir = std::env::temp_dir().join(format!("navi-migrate-test-{}", std::process::id()));
+        let scene = sample_scene();
+        let mut read_back = Vec::new();
+        for (format, name) in SceneFormat::all()
+            .into_iter()
+            .zip(["ron", "json", "nvscene"])
+        {
+            let path = dir.join(format!("scene.{}", name));
+            scene.write(&path).unwrap();
+            let contents = fs::read(&path).unwrap();
+            let version = probe_version(format, &contents);
+            read_back.push((
+                version,
+                read_migrated(format, &contents, SCENE_FORMAT_VERSION),
+            ));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        for (version, read) in read_back {
+            assert_eq!(version, Ok(SCENE_FORMAT_VERSION));
+            assert_eq!(read.unwrap(), scene);
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        assert_eq!(check_version(1), Ok(()));
+        assert_eq!(check_version(SCENE_FORMAT_VERSION), Ok(()));
+        let err = check_version(SCENE_FORMAT_VERSION + 1).unwrap_err();
+        assert!(err.contains("requires a newer Navi"), "{}", err);
+        assert!(check_version(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn the_version_is_probed_before_the_rest_is_understood() {
+        let future = format!(
+            "(version: {}, objects: [], gravity_wells: [(strength: 2.0)])",
+            SCENE_FORMAT_VERSION + 1
+        );
+        assert_eq!(
+            probe_version(SceneFormat::Ron, future.as_bytes()),
+            Ok(SCENE_FORMAT_VERSION + 1)
+        );
+        let json = r#"{ "objects": {}, "version": 4 }"#;
+        assert_eq!(probe_version(SceneFormat::Json, json.as_bytes()), Ok(4));
+        let err = probe_version(SceneFormat::Json, b"{}").unwrap_err();
+        assert!(err.starts_with("cannot read the format version"), "{}", err);
+    }
+
+    #[test]
+    fn current_files_need_no_steps() {
+        let mut value = serde_json::to_value(sample_scene()).unwrap();
+        let before = value.clone();
+        migrate_value(
+            &mut value,
+            SCENE_FORMAT_VERSION,
+            SCENE_FORMAT_VERSION,
+            MIGRATIONS,
+        )
+        .unwrap();
+        assert_eq!(value, before);
+    }
+
+    // A made-up format history: version 1 called the objects `things`,
+    // version 2 stored the gravity as a single number
+    const TEST_MIGRATIONS: &[(u32, Migration)] = &[
+        (1, |value| {
+            let things = value
+                .as_object_mut()
+                .and_then(|scene| scene.remove("things"))
+                .ok_or("no things")?;
+            value["objects"] = things;
+            Ok(())
+        }),
+        (2, |value| {
+            let gravity = value["gravity"].as_f64().ok_or("gravity is not a number")?;
+            value["gravity"] = serde_json::json!([0.0, -gravity, 0.0]);
+            Ok(())
+        }),
+    ];
+
+    #[test]
+    fn steps_chain_up_to_the_latest_version() {
+        let mut value = serde_json::json!({ "version": 1, "things": [1, 2], "gravity": 9.5 });
+        migrate_value(&mut value, 1, 3, TEST_MIGRATIONS).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "version": 3, "objects": [1, 2], "gravity": [0.0, -9.5, 0.0] })
+        );
+
+        // Starting partway only runs the later steps
+        let mut value = serde_json::json!({ "version": 2, "objects": [], "gravity": 1.0 });
+        migrate_value(&mut value, 2, 3, TEST_MIGRATIONS).unwrap();
+        assert_eq!(value["gravity"], serde_json::json!([0.0, -1.0, 0.0]));
+    }
+
+    #[test]
+    fn a_missing_or_failing_step_stops_the_chain() {
+        let mut value = serde_json::json!({ "version": 1, "things": [] , "gravity": 1.0 });
+        let err = migrate_value(&mut value, 1, 4, TEST_MIGRATIONS).unwrap_err();
+        assert_eq!(err, "no migration from scene format version 3");
+
+        let mut value = serde_json::json!({ "version": 0 });
+        let err = migrate_value(&mut value, 0, 3, TEST_MIGRATIONS).unwrap_err();
+        assert_eq!(err, "no migration from scene format version 0");
+
+        let mut value = serde_json::json!({ "version": 2, "gravity": "down" });
+        let err = migrate_value(&mut value, 2, 3, TEST_MIGRATIONS).unwrap_err();
+        assert_eq!(err, "migrating from version 2: gravity is not a number");
+        assert_eq!(value["version"], Value::from(2));
+    }
+
+    #[test]
+    fn ron_errors_give_the_position() {
+        let err = ron_to_value("(\n    version: 1,\n    objects: [inf],\n)").unwrap_err();
+        assert!(
+            err.starts_with("invalid RON at line 3, column 15"),
+            "{}",
+            err
+        );
+        let err = ron_to_value("(version: 1) trailing").unwrap_err();
+        assert!(err.ends_with("trailing characters"), "{}", err);
+        let err = ron_to_value("(name: \"unterminated)").unwrap_err();
+        assert!(err.ends_with("unterminated string"), "{}", err);
+    }
+}
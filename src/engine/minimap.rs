@@ -0,0 +1,237 @@
+use crate::engine::bookmarks::CameraBookmarks;
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::PlacedObjects;
+use crate::engine::toolbar::MainCamera;
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy_egui::*;
+
+// Height the minimap camera looks down from
+const MINIMAP_HEIGHT: f32 = 200.0;
+const MINIMAP_DEFAULT_ZOOM: f32 = 40.0;
+const MINIMAP_INITIAL_SIZE: u32 = 256;
+const MINIMAP_MIN_ZOOM: f32 = 5.0;
+const MINIMAP_MAX_ZOOM: f32 = 200.0;
+// Main camera distance used when its view doesn't point down at the ground
+const FALLBACK_VIEW_DISTANCE: f32 = 8.0;
+
+// Marker for the top-down camera that renders into the minimap image
+#[derive(Component)]
+pub struct MinimapCamera;
+
+// Render target and view size of the minimap window
+#[derive(Resource)]
+pub struct Minimap {
+    pub image: Handle<Image>,
+    // World units visible from the top to the bottom of the minimap
+    pub zoom: f32,
+}
+
+fn minimap_image(width: u32, height: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+pub fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(minimap_image(MINIMAP_INITIAL_SIZE, MINIMAP_INITIAL_SIZE));
+    commands.spawn((
+        MinimapCamera,
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(image.clone().into()),
+            // Drawn before the main camera so the image is ready for egui
+            order: -1,
+            clear_color: ClearColorConfig::Custom(Color::srgb(0.08, 0.08, 0.1)),
+            ..default()
+        },
+        Projection::from(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical {
+                viewport_height: MINIMAP_DEFAULT_ZOOM,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        // -Z is up on the minimap, +X to the right
+        Transform::from_xyz(0.0, MINIMAP_HEIGHT, 0.0).looking_at(Vec3::ZERO, Vec3::NEG_Z),
+    ));
+    commands.insert_resource(Minimap {
+        image,
+        zoom: MINIMAP_DEFAULT_ZOOM,
+    });
+}
+
+// Keep the minimap centred over the main camera, and only render it while the window is open
+pub fn minimap_follow_system(
+    minimap: Res<Minimap>,
+    layout: Res<EditorLayout>,
+    main_camera: Query<&Transform, (With<MainCamera>, Without<MinimapCamera>)>,
+    mut minimap_camera: Query<(&mut Camera, &mut Transform, &mut Projection), With<MinimapCamera>>,
+) {
+    let Ok((mut camera, mut transform, mut projection)) = minimap_camera.single_mut() else {
+        return;
+    };
+    let open = layout.is_open("Minimap");
+    if camera.is_active != open {
+        camera.is_active = open;
+    }
+    if !open {
+        return;
+    }
+
+    if let Ok(main) = main_camera.single() {
+        let target = Vec3::new(main.translation.x, MINIMAP_HEIGHT, main.translation.z);
+        if transform.translation != target {
+            transform.translation = target;
+        }
+    }
+    if minimap.is_changed()
+        && let Projection::Orthographic(ortho) = projection.as_mut()
+    {
+        ortho.scaling_mode = ScalingMode::FixedVertical {
+            viewport_height: minimap.zoom,
+        };
+    }
+}
+
+pub fn minimap_panel_system(
+    mut contexts: EguiContexts,
+    mut minimap: ResMut<Minimap>,
+    mut images: ResMut<Assets<Image>>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+    objects: PlacedObjects,
+    mut layout: ResMut<EditorLayout>,
+) {
+    // Weak handle, the resource owns the image
+    let texture = contexts.add_image(minimap.image.clone_weak());
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let pixels_per_point = ctx.pixels_per_point();
+    let Ok(camera) = main_camera.single() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Minimap",
+        egui::Window::new("Minimap")
+            .default_size([220.0, 240.0])
+            .resizable(true),
+        |ui| {
+            let mut zoom = minimap.zoom;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut zoom, MINIMAP_MIN_ZOOM..=MINIMAP_MAX_ZOOM)
+                        .logarithmic(true)
+                        .text("Zoom"),
+                );
+                if ui
+                    .add_enabled(
+                        !objects.game_manager.is_empty(),
+                        egui::Button::new("Fit scene"),
+                    )
+                    .on_hover_text("Zoom out until every object is on the map")
+                    .clicked()
+                {
+                    zoom = zoom_to_fit(&objects, camera.translation);
+                }
+            });
+            if zoom != minimap.zoom {
+                minimap.zoom = zoom;
+            }
+
+            let size = ui.available_size().max(egui::vec2(32.0, 32.0));
+            // Resize the existing image in place so no old render targets pile up
+            let pixels = UVec2::new(
+                (size.x * pixels_per_point).round() as u32,
+                (size.y * pixels_per_point).round() as u32,
+            );
+            // get_mut flags the asset as modified, so only take it when the size is off
+            let resize = images
+                .get(&minimap.image)
+                .is_some_and(|image| image.size() != pixels);
+            if resize && let Some(image) = images.get_mut(&minimap.image) {
+                image.resize(Extent3d {
+                    width: pixels.x,
+                    height: pixels.y,
+                    depth_or_array_layers: 1,
+                });
+            }
+
+            let response = ui.add(
+                egui::Image::new(egui::load::SizedTexture::new(texture, size))
+                    .sense(egui::Sense::click()),
+            );
+            // Main camera sits in the middle of the map
+            ui.painter().circle_filled(
+                response.rect.center(),
+                4.0,
+                egui::Color32::from_rgb(255, 200, 50),
+            );
+
+            let Some(pointer) = response
+                .interact_pointer_pos()
+                .filter(|_| response.clicked())
+            else {
+                return;
+            };
+            let uv = (pointer - response.rect.min) / response.rect.size();
+            let visible = Vec2::new(minimap.zoom * size.x / size.y, minimap.zoom);
+            let target = Vec3::new(
+                camera.translation.x + (uv.x - 0.5) * visible.x,
+                0.0,
+                camera.translation.z + (uv.y - 0.5) * visible.y,
+            );
+            bookmarks.glide_to(camera, look_at_from_same_angle(camera, target));
+        },
+    );
+}
+
+// Zoom showing the whole scene with the map centred on the camera, which the
+// minimap always is. Sized for a square map, wider ones see a little more
+fn zoom_to_fit(objects: &PlacedObjects, center: Vec3) -> f32 {
+    let bounds = objects.scene_bounds();
+    let center = center.xz();
+    let reach = (bounds.min.xz() - center)
+        .abs()
+        .max((bounds.max.xz() - center).abs())
+        .max_element();
+    (reach * 2.0 * 1.1).clamp(MINIMAP_MIN_ZOOM, MINIMAP_MAX_ZOOM)
+}
+
+// Move the camera, keeping its rotation, so its view centre lands on the target
+fn look_at_from_same_angle(camera: &Transform, target: Vec3) -> Transform {
+    let forward = camera.forward();
+    let distance = if forward.y < -0.1 {
+        (camera.translation.y - target.y) / -forward.y
+    } else {
+        FALLBACK_VIEW_DISTANCE
+    };
+    camera.with_translation(target - *forward * distance)
+}
+
+// Top-down minimap camera and its panel
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_minimap)
+            .add_systems(EguiContextPass, minimap_panel_system.in_set(UiSet::Panels))
+            .add_systems(Update, minimap_follow_system.in_set(EditorSet::Bookkeeping));
+    }
+}
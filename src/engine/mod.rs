@@ -1,4 +1,58 @@
+pub mod arrange;
+pub mod autosave;
+pub mod batch;
+pub mod bookmarks;
+pub mod bounds;
+pub mod camera;
+pub mod clipboard;
+pub mod components;
+pub mod config;
+pub mod console;
 pub mod core;
+pub mod csv_export;
+pub mod custom_mesh;
+pub mod dynamic_scene;
 pub mod editor;
+pub mod export;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod gizmo;
+pub mod groups;
+pub mod history;
+pub mod hot_reload;
+pub mod impulse;
 pub mod input;
+pub mod joints;
+pub mod labels;
+pub mod layout;
+pub mod lifetime;
+pub mod measure;
+pub mod migrate;
+pub mod minimap;
+pub mod notify;
 pub mod objects;
+pub mod oplog;
+pub mod palette;
+pub mod placement;
+pub mod playback;
+pub mod pool;
+pub mod prefab;
+pub mod prefab_file;
+pub mod prefs;
+pub mod profiler;
+pub mod properties;
+pub mod recorder;
+pub mod scene;
+pub mod screenshot;
+pub mod selection;
+pub mod serde_util;
+pub mod session;
+pub mod simulation;
+pub mod snap;
+pub mod spatial;
+pub mod stats;
+pub mod templates;
+pub mod thumbnails;
+pub mod toolbar;
+pub mod trash;
+pub mod world;
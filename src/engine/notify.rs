@@ -0,0 +1,209 @@
+use crate::engine::core::{EditorSet, UiSet};
+use bevy::prelude::*;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+// Toasts kept in memory, older ones are dropped even if not yet shown
+const MAX_QUEUED: usize = 50;
+// Seconds a toast takes to fade out at the end of its life
+const FADE_SECS: f32 = 0.5;
+const TOAST_WIDTH: f32 = 260.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotifyLevel {
+    fn color(&self) -> egui::Color32 {
+        match self {
+            NotifyLevel::Info => egui::Color32::LIGHT_BLUE,
+            NotifyLevel::Warning => egui::Color32::YELLOW,
+            NotifyLevel::Error => egui::Color32::RED,
+        }
+    }
+}
+
+// How long toasts last and how many are stacked at once, saved with the editor prefs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub duration_secs: f32,
+    pub max_visible: usize,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            duration_secs: 3.0,
+            max_visible: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: u64,
+    pub level: NotifyLevel,
+    pub message: String,
+    // File the toast offers to open, like a saved screenshot
+    pub link: Option<PathBuf>,
+    // Seconds left before it disappears, errors stay until clicked
+    remaining: f32,
+}
+
+impl Toast {
+    fn is_sticky(&self) -> bool {
+        self.level == NotifyLevel::Error
+    }
+}
+
+// Short-lived messages shown in the bottom right corner
+#[derive(Resource, Default)]
+pub struct EditorNotifications {
+    pub settings: NotificationSettings,
+    toasts: VecDeque<Toast>,
+    next_id: u64,
+}
+
+impl EditorNotifications {
+    pub fn new(settings: NotificationSettings) -> Self {
+        Self {
+            settings,
+            ..default()
+        }
+    }
+
+    pub fn push(&mut self, level: NotifyLevel, message: impl Into<String>) {
+        self.push_toast(level, message.into(), None);
+    }
+
+    fn push_toast(&mut self, level: NotifyLevel, message: String, link: Option<PathBuf>) {
+        if self.toasts.len() == MAX_QUEUED {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            id: self.next_id,
+            level,
+            message,
+            link,
+            remaining: self.settings.duration_secs,
+        });
+        self.next_id += 1;
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(NotifyLevel::Info, message);
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(NotifyLevel::Warning, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(NotifyLevel::Error, message);
+    }
+
+    // Info toast with an Open link to a file
+    pub fn info_with_link(&mut self, message: impl Into<String>, path: &Path) {
+        self.push_toast(NotifyLevel::Info, message.into(), Some(path.to_path_buf()));
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|toast| toast.id != id);
+    }
+
+    // Newest toasts first, capped at the configured count
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter().rev().take(self.settings.max_visible)
+    }
+}
+
+pub fn tick_notifications_system(time: Res<Time>, mut notifications: ResMut<EditorNotifications>) {
+    if notifications.toasts.is_empty() {
+        return;
+    }
+
+    let delta = time.delta_secs();
+    for toast in notifications.toasts.iter_mut() {
+        if !toast.is_sticky() {
+            toast.remaining -= delta;
+        }
+    }
+    notifications
+        .toasts
+        .retain(|toast| toast.is_sticky() || toast.remaining > 0.0);
+}
+
+pub fn notifications_ui(
+    mut contexts: EguiContexts,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if notifications.toasts.is_empty() {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let mut dismissed = Vec::new();
+    egui::Area::new(egui::Id::new("editor_notifications"))
+        .order(egui::Order::Tooltip)
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+        .show(ctx, |ui| {
+            ui.set_width(TOAST_WIDTH);
+            // Oldest at the top so new toasts appear in the corner
+            let toasts: Vec<&Toast> = notifications.visible().collect();
+            for toast in toasts.into_iter().rev() {
+                let opacity = if toast.is_sticky() {
+                    1.0
+                } else {
+                    (toast.remaining / FADE_SECS).clamp(0.0, 1.0)
+                };
+                let response = ui
+                    .scope(|ui| {
+                        ui.multiply_opacity(opacity);
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            ui.colored_label(toast.level.color(), &toast.message);
+                            if let Some(path) = &toast.link
+                                && ui.link("Open").clicked()
+                            {
+                                let path = std::path::absolute(path).unwrap_or(path.clone());
+                                ui.ctx().open_url(egui::OpenUrl::new_tab(format!(
+                                    "file://{}",
+                                    path.display()
+                                )));
+                            }
+                        })
+                    })
+                    .inner
+                    .response
+                    .interact(egui::Sense::click());
+                if response.on_hover_text("Click to dismiss").clicked() {
+                    dismissed.push(toast.id);
+                }
+            }
+        });
+
+    for id in dismissed {
+        notifications.dismiss(id);
+    }
+}
+
+// Toasts. The notification settings come from the prefs
+pub struct NotificationsPlugin;
+
+impl Plugin for NotificationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(EguiContextPass, notifications_ui.in_set(UiSet::Popups))
+            .add_systems(
+                Update,
+                tick_notifications_system.in_set(EditorSet::Bookkeeping),
+            );
+    }
+}
@@ -1,17 +1,55 @@
+use crate::engine::custom_mesh::{CustomMeshInstance, CustomMeshLibrary};
+use crate::engine::history::{EditContext, EditorCommand, EditorHistory};
+use crate::engine::input::{InputAction, ViewportInput};
+use crate::engine::lifetime::{Lifetime, LifetimeSettings};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::playback::Playback;
+use crate::engine::pool::{EntityPool, PooledEntities, reactivate_pooled};
+use crate::engine::selection::Selection;
+use crate::engine::serde_util;
+use crate::engine::snap::SnapSettings;
+use crate::engine::spatial::SpatialGrid;
+use crate::engine::toolbar::MainCamera;
+use crate::engine::trash::Trash;
+use bevy::ecs::entity::Entities;
+use bevy::ecs::system::SystemParam;
+use bevy::math::bounding::{Aabb3d, Bounded3d, BoundingVolume};
 use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
 use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Smallest dimension a shape may have, keeps meshes and colliders valid
+pub const MIN_DIMENSION: f32 = 0.01;
+
+// Spawn rate in ObjectStats is averaged over this window
+pub const SPAWN_RATE_WINDOW_SECS: f64 = 60.0;
+// Spawn timestamps kept for the rate, objects spawned the same frame share one
+const SPAWN_HISTORY_CAPACITY: usize = 256;
+
+// Longest metadata key accepted, in characters
+pub const MAX_META_KEY_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect, Serialize, Deserialize)]
 pub enum ShapeType {
+    #[default]
     Ball,
     Cube,
     Capsule,
     Cylinder,
     Cone,
+    // Imported mesh from the CustomMeshLibrary, named by the object's
+    // custom_mesh. Its dimensions hold the mesh's bounding box
+    CustomMesh,
 }
 
 impl ShapeType {
-    // Get all available shapes for dropdown
+    // Get all available shapes for dropdown. CustomMesh needs a library entry
+    // to spawn, so it's listed with the imported meshes instead
     pub fn all() -> Vec<ShapeType> {
         vec![
             ShapeType::Ball,
@@ -30,34 +68,250 @@ impl ShapeType {
             ShapeType::Capsule => "Capsule",
             ShapeType::Cylinder => "Cylinder",
             ShapeType::Cone => "Cone",
+            ShapeType::CustomMesh => "Mesh",
         }
     }
 
-    // Create collider with default parameters
-    pub fn create_collider(&self) -> Collider {
+    // Fixed colour identifying the shape in toolbars and overlays
+    pub fn swatch_color(&self) -> Color {
+        match self {
+            ShapeType::Ball => Color::srgb(0.9, 0.4, 0.3),
+            ShapeType::Cube => Color::srgb(0.3, 0.6, 0.9),
+            ShapeType::Capsule => Color::srgb(0.4, 0.8, 0.4),
+            ShapeType::Cylinder => Color::srgb(0.9, 0.8, 0.3),
+            ShapeType::Cone => Color::srgb(0.7, 0.4, 0.9),
+            ShapeType::CustomMesh => Color::srgb(0.6, 0.6, 0.6),
+        }
+    }
+
+    // Auto-generated name used when no custom name is given
+    pub fn default_name(&self, id: u32) -> String {
+        format!("{} {}", self.display_name(), id)
+    }
+
+    // Dimensions used when a spawn doesn't specify any
+    pub fn default_dimensions(&self) -> ShapeDimensions {
         match self {
-            ShapeType::Ball => Collider::ball(0.5),
-            ShapeType::Cube => Collider::cuboid(0.5, 0.5, 0.5),
-            ShapeType::Capsule => Collider::capsule_y(1.0, 0.3),
-            ShapeType::Cylinder => Collider::cylinder(1.0, 0.5),
-            ShapeType::Cone => Collider::cone(1.0, 0.5),
+            ShapeType::Ball => ShapeDimensions {
+                radius: 0.5,
+                ..default()
+            },
+            ShapeType::Cube | ShapeType::CustomMesh => ShapeDimensions {
+                half_extents: Vec3::splat(0.5),
+                ..default()
+            },
+            ShapeType::Capsule => ShapeDimensions {
+                radius: 0.3,
+                height: 2.6,
+                ..default()
+            },
+            ShapeType::Cylinder | ShapeType::Cone => ShapeDimensions {
+                radius: 0.5,
+                height: 2.0,
+                ..default()
+            },
         }
     }
 
+    // Create collider with default parameters
+    pub fn create_collider(&self) -> Collider {
+        self.create_collider_with(&self.default_dimensions())
+    }
+
     // Create visual mesh
     pub fn create_mesh(&self, meshes: &mut Assets<Mesh>) -> Handle<Mesh> {
+        self.create_mesh_with(meshes, &self.default_dimensions())
+    }
+
+    // Collider and mesh below both take `height` as the full height of the shape,
+    // rapier wants half heights and bevy's capsule wants the length between the caps.
+    // A custom mesh gets its bounding box until apply_custom_meshes_system swaps
+    // in the imported mesh and its collider
+    pub fn create_collider_with(&self, dims: &ShapeDimensions) -> Collider {
+        let dims = dims.validated(*self);
+        match self {
+            ShapeType::Ball => Collider::ball(dims.radius),
+            ShapeType::Cube | ShapeType::CustomMesh => Collider::cuboid(
+                dims.half_extents.x,
+                dims.half_extents.y,
+                dims.half_extents.z,
+            ),
+            ShapeType::Capsule => Collider::capsule_y(dims.capsule_segment() / 2.0, dims.radius),
+            ShapeType::Cylinder => Collider::cylinder(dims.height / 2.0, dims.radius),
+            ShapeType::Cone => Collider::cone(dims.height / 2.0, dims.radius),
+        }
+    }
+
+    pub fn create_mesh_with(
+        &self,
+        meshes: &mut Assets<Mesh>,
+        dims: &ShapeDimensions,
+    ) -> Handle<Mesh> {
+        meshes.add(self.mesh_with(dims))
+    }
+
+    // The mesh itself, for when it isn't going into the asset store
+    pub fn mesh_with(&self, dims: &ShapeDimensions) -> Mesh {
+        let dims = dims.validated(*self);
         match self {
-            ShapeType::Ball => meshes.add(Sphere::new(0.5)),
-            ShapeType::Cube => meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
-            ShapeType::Capsule => meshes.add(Capsule3d::new(0.3, 2.0)),
-            ShapeType::Cylinder => meshes.add(Cylinder::new(0.5, 2.0)),
-            ShapeType::Cone => meshes.add(Cone::new(0.5, 2.0)),
+            ShapeType::Ball => Sphere::new(dims.radius).into(),
+            ShapeType::Cube | ShapeType::CustomMesh => {
+                Cuboid::from_size(dims.half_extents * 2.0).into()
+            }
+            ShapeType::Capsule => Capsule3d::new(dims.radius, dims.capsule_segment()).into(),
+            ShapeType::Cylinder => Cylinder::new(dims.radius, dims.height).into(),
+            ShapeType::Cone => Cone::new(dims.radius, dims.height).into(),
+        }
+    }
+
+    // World space box around the shape placed at the isometry
+    pub fn aabb_with(&self, dims: &ShapeDimensions, isometry: Isometry3d) -> Aabb3d {
+        let dims = dims.validated(*self);
+        match self {
+            ShapeType::Ball => Sphere::new(dims.radius).aabb_3d(isometry),
+            ShapeType::Cube | ShapeType::CustomMesh => {
+                Cuboid::from_size(dims.half_extents * 2.0).aabb_3d(isometry)
+            }
+            ShapeType::Capsule => {
+                Capsule3d::new(dims.radius, dims.capsule_segment()).aabb_3d(isometry)
+            }
+            ShapeType::Cylinder => Cylinder::new(dims.radius, dims.height).aabb_3d(isometry),
+            ShapeType::Cone => Cone::new(dims.radius, dims.height).aabb_3d(isometry),
+        }
+    }
+}
+
+// Size of a shape, which fields apply depends on the ShapeType:
+// ball uses radius, cube uses half_extents, capsule/cylinder/cone use radius and height
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct ShapeDimensions {
+    pub radius: f32,
+    #[serde(with = "serde_util::vec3")]
+    pub half_extents: Vec3,
+    // Full height including a capsule's end caps
+    pub height: f32,
+}
+
+impl Default for ShapeDimensions {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            half_extents: Vec3::splat(0.5),
+            height: 2.0,
+        }
+    }
+}
+
+impl ShapeDimensions {
+    // Clamp to the minimum size, a capsule is never shorter than its two caps
+    pub fn validated(&self, shape_type: ShapeType) -> Self {
+        let radius = self.radius.max(MIN_DIMENSION);
+        let half_extents = self.half_extents.max(Vec3::splat(MIN_DIMENSION));
+        let min_height = match shape_type {
+            ShapeType::Capsule => radius * 2.0,
+            _ => MIN_DIMENSION,
+        };
+        Self {
+            radius,
+            half_extents,
+            height: self.height.max(min_height),
+        }
+    }
+
+    // Bake a scale factor into the dimensions. Round shapes can't stretch
+    // unevenly, so their radius follows the larger horizontal factor
+    pub fn scaled(&self, shape_type: ShapeType, scale: Vec3) -> Self {
+        let scale = scale.abs();
+        let dims = match shape_type {
+            // Imported meshes keep the size they were modelled at
+            ShapeType::CustomMesh => *self,
+            ShapeType::Ball => Self {
+                radius: self.radius * scale.max_element(),
+                ..*self
+            },
+            ShapeType::Cube => Self {
+                half_extents: self.half_extents * scale,
+                ..*self
+            },
+            ShapeType::Capsule | ShapeType::Cylinder | ShapeType::Cone => Self {
+                radius: self.radius * scale.x.max(scale.z),
+                height: self.height * scale.y,
+                ..*self
+            },
+        };
+        dims.validated(shape_type)
+    }
+
+    // Distance from the centre to the top of the unrotated shape
+    pub fn half_height(&self, shape_type: ShapeType) -> f32 {
+        match shape_type {
+            ShapeType::Ball => self.radius,
+            ShapeType::Cube | ShapeType::CustomMesh => self.half_extents.y,
+            ShapeType::Capsule | ShapeType::Cylinder | ShapeType::Cone => self.height / 2.0,
         }
     }
+
+    // Length of the straight section between a capsule's caps
+    pub fn capsule_segment(&self) -> f32 {
+        (self.height - self.radius * 2.0).max(0.0)
+    }
+
+    // Volume of the collider, which is also its mass at rapier's default density
+    pub fn volume(&self, shape_type: ShapeType) -> f32 {
+        let dims = self.validated(shape_type);
+        let sphere = 4.0 / 3.0 * std::f32::consts::PI * dims.radius.powi(3);
+        let disc = std::f32::consts::PI * dims.radius * dims.radius;
+        match shape_type {
+            ShapeType::Ball => sphere,
+            ShapeType::Cube | ShapeType::CustomMesh => (dims.half_extents * 2.0).element_product(),
+            ShapeType::Capsule => sphere + disc * dims.capsule_segment(),
+            ShapeType::Cylinder => disc * dims.height,
+            ShapeType::Cone => disc * dims.height / 3.0,
+        }
+    }
+}
+
+// Pre-spawn dimensions for each shape, edited in the spawn panel
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapeParams {
+    pub dimensions: HashMap<ShapeType, ShapeDimensions>,
+}
+
+impl Default for ShapeParams {
+    fn default() -> Self {
+        Self {
+            dimensions: ShapeType::all()
+                .into_iter()
+                .map(|shape| (shape, shape.default_dimensions()))
+                .collect(),
+        }
+    }
+}
+
+impl ShapeParams {
+    pub fn get(&self, shape_type: ShapeType) -> ShapeDimensions {
+        self.dimensions
+            .get(&shape_type)
+            .copied()
+            .unwrap_or_else(|| shape_type.default_dimensions())
+    }
+
+    pub fn set(&mut self, shape_type: ShapeType, dims: ShapeDimensions) {
+        self.dimensions
+            .insert(shape_type, dims.validated(shape_type));
+    }
+}
+
+// Physics given to objects spawned from the editor, edited in the spawn panel
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SpawnPhysics {
+    pub props: PhysicsProps,
 }
 
 // Component to identify game objects
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct GameObjectId {
     pub id: u32,
     pub name: String,
@@ -65,6 +319,17 @@ pub struct GameObjectId {
     pub created_at: f64, // timestamp
 }
 
+impl From<&GameObject> for GameObjectId {
+    fn from(obj: &GameObject) -> Self {
+        Self {
+            id: obj.id,
+            name: obj.name.clone(),
+            shape_type: obj.shape_type,
+            created_at: obj.created_at,
+        }
+    }
+}
+
 // Resource to store currently selected shape
 #[derive(Resource)]
 pub struct SelectedShape {
@@ -79,204 +344,3482 @@ impl Default for SelectedShape {
     }
 }
 
-#[derive(Event)]
+// What the spawn key and placement mode make: the shape, its size and
+// physics, how long it lasts and where it snaps to
+#[derive(SystemParam)]
+pub struct NextSpawn<'w> {
+    pub selected_shape: Res<'w, SelectedShape>,
+    pub shape_params: Res<'w, ShapeParams>,
+    pub spawn_physics: Res<'w, SpawnPhysics>,
+    pub lifetime: Res<'w, LifetimeSettings>,
+    pub snap: Res<'w, SnapSettings>,
+}
+
+// Serializable so scripts and remote tools can send spawns as data
+#[derive(Event, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SpawnEntityEvent {
+    pub source: SpawnSource,
+    #[serde(with = "serde_util::vec3")]
     pub position: Vec3,
     pub shape_type: ShapeType,
-    pub custom_name: Option<String>, // Allow custom naming
+    pub custom_name: Option<String>,          // Allow custom naming
+    pub params: Option<ShapeDimensions>,      // Defaults to the shape's default dimensions
+    pub tags: Vec<String>,                    // Normalized on spawn, invalid ones are dropped
+    pub metadata: HashMap<String, MetaValue>, // Invalid keys are dropped
+    pub lifetime_secs: Option<f32>,           // Despawn after this long, see Lifetime
+    pub custom_mesh: Option<String>,          // Library mesh, needed for ShapeType::CustomMesh
+    pub physics: Option<PhysicsProps>,        // Clamped on spawn, defaults when absent
+    pub body_type: Option<BodyKind>,          // Overrides the body in physics
 }
 
-// Improved GameObject struct
-#[derive(Debug, Clone)]
+// Where an object came from. Undo and trash restores keep the original source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SpawnSource {
+    Keyboard,
+    ToolbarButton,
+    // Code that didn't say otherwise
+    #[default]
+    Script,
+    SceneLoad,
+    Duplicate,
+    Network,
+    // Spawns sent by recording playback
+    Playback,
+}
+
+impl SpawnSource {
+    pub fn all() -> [SpawnSource; 7] {
+        [
+            SpawnSource::Keyboard,
+            SpawnSource::ToolbarButton,
+            SpawnSource::Script,
+            SpawnSource::SceneLoad,
+            SpawnSource::Duplicate,
+            SpawnSource::Network,
+            SpawnSource::Playback,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpawnSource::Keyboard => "Keyboard",
+            SpawnSource::ToolbarButton => "Editor UI",
+            SpawnSource::Script => "Script",
+            SpawnSource::SceneLoad => "Scene load",
+            SpawnSource::Duplicate => "Duplicate",
+            SpawnSource::Network => "Network",
+            SpawnSource::Playback => "Playback",
+        }
+    }
+
+    // Shown before hierarchy rows when source icons are on
+    pub fn icon(&self) -> &'static str {
+        match self {
+            SpawnSource::Keyboard => "⌨",
+            SpawnSource::ToolbarButton => "🖱",
+            SpawnSource::Script => "📜",
+            SpawnSource::SceneLoad => "📂",
+            SpawnSource::Duplicate => "⎘",
+            SpawnSource::Network => "🌐",
+            SpawnSource::Playback => "▶",
+        }
+    }
+}
+
+// Rigid body type picked at spawn or in the inspector, locking can still hold
+// a body Fixed. Kinematic bodies only move when the editor moves them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BodyKind {
+    #[default]
+    Dynamic,
+    Fixed,
+    Kinematic,
+}
+
+impl BodyKind {
+    pub fn all() -> [BodyKind; 3] {
+        [BodyKind::Dynamic, BodyKind::Fixed, BodyKind::Kinematic]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BodyKind::Dynamic => "Dynamic",
+            BodyKind::Fixed => "Fixed",
+            BodyKind::Kinematic => "Kinematic",
+        }
+    }
+
+    // Shown in the hierarchy, dynamic bodies are the usual case and get none
+    pub fn icon(&self) -> Option<&'static str> {
+        match self {
+            BodyKind::Dynamic => None,
+            BodyKind::Fixed => Some("⚓"),
+            BodyKind::Kinematic => Some("⇄"),
+        }
+    }
+}
+
+// Lowest collider density, a massless dynamic body can't be simulated
+pub const MIN_DENSITY: f32 = 0.01;
+
+// Physics material and damping settings mirrored from the rapier components
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsProps {
+    pub body: BodyKind,
+    pub restitution: f32,
+    pub friction: f32,
+    // Collider density, the body's mass follows from it and the shape
+    pub density: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub gravity_scale: f32,
+}
+
+impl Default for PhysicsProps {
+    fn default() -> Self {
+        Self {
+            body: BodyKind::Dynamic,
+            restitution: 0.7,
+            friction: 0.5,
+            density: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            gravity_scale: 1.0,
+        }
+    }
+}
+
+impl PhysicsProps {
+    // Body to insert, a physics lock overrides the chosen kind. Children are
+    // kinematic so they're carried along by their parent instead of simulated
+    pub fn rigid_body(&self, physics_locked: bool, parented: bool) -> RigidBody {
+        match (physics_locked, parented, self.body) {
+            (true, _, _) | (false, _, BodyKind::Fixed) => RigidBody::Fixed,
+            (false, true, BodyKind::Dynamic) | (false, _, BodyKind::Kinematic) => {
+                RigidBody::KinematicPositionBased
+            }
+            (false, false, BodyKind::Dynamic) => RigidBody::Dynamic,
+        }
+    }
+
+    // Values rapier can't use pulled back into range, with the names of the
+    // fields that were changed. Non-finite values fall back to the default
+    pub fn clamped(&self) -> (Self, Vec<&'static str>) {
+        let defaults = Self::default();
+        let mut clamped = *self;
+        let mut changed = Vec::new();
+        let fields: [(&'static str, &mut f32, f32, f32); 6] = [
+            ("restitution", &mut clamped.restitution, 0.0, f32::MAX),
+            ("friction", &mut clamped.friction, 0.0, f32::MAX),
+            ("density", &mut clamped.density, MIN_DENSITY, f32::MAX),
+            ("linear damping", &mut clamped.linear_damping, 0.0, f32::MAX),
+            (
+                "angular damping",
+                &mut clamped.angular_damping,
+                0.0,
+                f32::MAX,
+            ),
+            (
+                "gravity scale",
+                &mut clamped.gravity_scale,
+                f32::MIN,
+                f32::MAX,
+            ),
+        ];
+        let default_values = [
+            defaults.restitution,
+            defaults.friction,
+            defaults.density,
+            defaults.linear_damping,
+            defaults.angular_damping,
+            defaults.gravity_scale,
+        ];
+        for ((name, value, min, max), default) in fields.into_iter().zip(default_values) {
+            let fixed = if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                default
+            };
+            if fixed != *value {
+                *value = fixed;
+                changed.push(name);
+            }
+        }
+        (clamped, changed)
+    }
+
+    // Rapier components matching these settings, inserting replaces any existing ones
+    pub fn components(
+        &self,
+    ) -> (
+        Restitution,
+        Friction,
+        ColliderMassProperties,
+        Damping,
+        GravityScale,
+    ) {
+        (
+            Restitution::coefficient(self.restitution),
+            Friction::coefficient(self.friction),
+            ColliderMassProperties::Density(self.density),
+            Damping {
+                linear_damping: self.linear_damping,
+                angular_damping: self.angular_damping,
+            },
+            GravityScale(self.gravity_scale),
+        )
+    }
+}
+
+// Improved GameObject struct. Serialized without its entity, which only means
+// something in this run; deserialized objects get a placeholder until the
+// manager registers them
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameObject {
     pub id: u32,
     pub name: String,
-    pub entity: Entity,
+    // Read through entity(), only the manager may change it
+    #[serde(skip, default = "placeholder_entity")]
+    entity: Entity,
     pub shape_type: ShapeType,
-    pub position: Vec3,
+    // Read through position(), the manager moves it with set_position so the
+    // spatial index follows
+    #[serde(with = "serde_util::vec3")]
+    position: Vec3,
+    #[serde(with = "serde_util::quat")]
+    pub rotation: Quat,
+    // Mirrored from rapier's Velocity, zero for bodies that don't have one
+    #[serde(with = "serde_util::vec3")]
+    pub linvel: Vec3,
+    #[serde(with = "serde_util::vec3")]
+    pub angvel: Vec3,
     pub created_at: f64,
+    #[serde(with = "serde_util::color")]
+    pub color: Color,
+    pub physics: PhysicsProps,
+    pub dimensions: ShapeDimensions,
+    // Locked objects can't be selected, moved or deleted
+    pub locked: bool,
+    // Body held Fixed while locked, put back to Dynamic on unlock
+    pub physics_locked: bool,
+    pub hidden: bool,
+    // Collider switched off while hidden, hidden objects still collide otherwise
+    pub collider_disabled: bool,
+    // Ids in the object hierarchy, mirrored by ChildOf on the entities
+    pub parent: Option<u32>,
+    pub children: Vec<u32>,
+    // Normalized with normalize_tag
+    pub tags: HashSet<String>,
+    // Prefab the object was spawned from, kept after the prefab is deleted
+    pub prefab: Option<String>,
+    // Library name of the imported mesh, set for ShapeType::CustomMesh
+    #[serde(default)]
+    pub custom_mesh: Option<String>,
+    // Free-form data attached by scripts and external tools
+    pub metadata: HashMap<String, MetaValue>,
+    pub source: SpawnSource,
 }
 
-#[derive(Resource, Default)]
-pub struct GameObjectManager {
-    pub objects: Vec<GameObject>,
-    pub next_id: u32,
+fn placeholder_entity() -> Entity {
+    Entity::PLACEHOLDER
 }
 
-impl GameObjectManager {
-    pub fn add_object(
-        &mut self,
-        entity: Entity,
-        shape_type: ShapeType,
-        position: Vec3,
-        custom_name: Option<String>,
-        timestamp: f64,
-    ) -> u32 {
-        let id = self.next_id;
-        let name = custom_name.unwrap_or_else(|| format!("{} {}", shape_type.display_name(), id));
-
-        let game_object = GameObject {
-            id,
-            name: name.clone(),
-            entity,
-            shape_type,
-            position,
-            created_at: timestamp,
-        };
+// Everything needed to recreate an object, used by undo/redo
+#[derive(Debug, Clone)]
+pub struct ObjectSnapshot {
+    pub id: u32,
+    pub name: String,
+    pub shape_type: ShapeType,
+    pub transform: Transform,
+    pub color: Color,
+    pub physics: PhysicsProps,
+    pub dimensions: ShapeDimensions,
+    // Locked objects can't be selected, moved or deleted
+    pub locked: bool,
+    // Body held Fixed while locked, put back to Dynamic on unlock
+    pub physics_locked: bool,
+    pub hidden: bool,
+    // Collider switched off while hidden, hidden objects still collide otherwise
+    pub collider_disabled: bool,
+    // Reattached on restore if the parent still exists, the transform is relative to it
+    pub parent: Option<u32>,
+    pub tags: HashSet<String>,
+    pub prefab: Option<String>,
+    pub custom_mesh: Option<String>,
+    pub metadata: HashMap<String, MetaValue>,
+    pub source: SpawnSource,
+}
 
-        self.objects.push(game_object);
-        self.next_id += 1;
+// Errors returned by GameObjectManager operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectError {
+    NotFound(u32),
+    DuplicateId(u32),
+    // Parenting would make an object its own ancestor, as (child, parent)
+    ParentCycle(u32, u32),
+    // Tag that's empty once trimmed
+    InvalidTag(String),
+    // Metadata key that's empty once trimmed or longer than MAX_META_KEY_LEN
+    InvalidMetaKey(String),
+    // The object's entity was despawned behind the manager's back
+    StaleEntity(u32, Entity),
+    // Every u32 is taken by a live object
+    IdsExhausted,
+    // SpawnLimits won't let more objects in
+    ObjectLimit(usize),
+}
 
-        info!("Added game object: {} (ID: {}) at {:?}", name, id, position);
-        id
+impl fmt::Display for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectError::NotFound(id) => write!(f, "no game object with ID {}", id),
+            ObjectError::DuplicateId(id) => write!(f, "game object ID {} is already in use", id),
+            ObjectError::ParentCycle(child, parent) => write!(
+                f,
+                "game object {} can't be parented to {}, it's one of its descendants",
+                child, parent
+            ),
+            ObjectError::InvalidTag(tag) => write!(f, "\"{}\" is not a valid tag", tag),
+            ObjectError::IdsExhausted => write!(f, "no free game object IDs left"),
+            ObjectError::ObjectLimit(max) => {
+                write!(f, "the scene is limited to {} objects", max)
+            }
+            ObjectError::StaleEntity(id, entity) => write!(
+                f,
+                "game object {} points at entity {}, which no longer exists",
+                id, entity
+            ),
+            ObjectError::InvalidMetaKey(key) => write!(
+                f,
+                "\"{}\" is not a valid metadata key, keys are 1 to {} characters",
+                key, MAX_META_KEY_LEN
+            ),
+        }
     }
+}
 
-    pub fn remove_object(&mut self, entity: Entity) -> Option<GameObject> {
-        if let Some(index) = self.objects.iter().position(|obj| obj.entity == entity) {
-            let removed = self.objects.remove(index);
-            info!("Removed game object: {} (ID: {})", removed.name, removed.id);
-            Some(removed)
+impl std::error::Error for ObjectError {}
+
+// A metadata value, stored untagged so saved files read as plain values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetaValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl MetaValue {
+    // Text typed in the inspector: true/false and numbers keep their type,
+    // anything else is a string
+    pub fn parse(text: &str) -> Self {
+        let trimmed = text.trim();
+        if let Ok(value) = trimmed.parse::<bool>() {
+            MetaValue::Bool(value)
+        } else if let Ok(value) = trimmed.parse::<f64>()
+            && value.is_finite()
+        {
+            MetaValue::Number(value)
         } else {
-            None
+            MetaValue::String(text.to_string())
         }
     }
 
-    pub fn get_object_by_entity(&self, entity: Entity) -> Option<&GameObject> {
-        self.objects.iter().find(|obj| obj.entity == entity)
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MetaValue::Bool(_) => "bool",
+            MetaValue::Number(_) => "number",
+            MetaValue::String(_) => "string",
+        }
     }
+}
 
-    pub fn get_object_by_id(&self, id: u32) -> Option<&GameObject> {
-        self.objects.iter().find(|obj| obj.id == id)
+impl fmt::Display for MetaValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetaValue::Bool(value) => write!(f, "{}", value),
+            MetaValue::Number(value) => write!(f, "{}", value),
+            MetaValue::String(value) => write!(f, "{}", value),
+        }
     }
+}
 
-    pub fn get_objects_by_type(&self, shape_type: ShapeType) -> Vec<&GameObject> {
-        self.objects
-            .iter()
-            .filter(|obj| obj.shape_type == shape_type)
-            .collect()
+// Metadata keys are trimmed but otherwise kept as given
+pub fn normalize_meta_key(key: &str) -> Result<String, ObjectError> {
+    let trimmed = key.trim();
+    if trimmed.is_empty() || trimmed.chars().count() > MAX_META_KEY_LEN {
+        return Err(ObjectError::InvalidMetaKey(key.to_string()));
     }
+    Ok(trimmed.to_string())
+}
 
-    pub fn list_objects(&self) -> Vec<String> {
-        self.objects
-            .iter()
-            .map(|obj| {
-                format!(
-                    "{} (ID: {}, Type: {})",
-                    obj.name,
-                    obj.id,
-                    obj.shape_type.display_name()
-                )
-            })
-            .collect()
-    }
+// Orderings offered by sorted_objects. Distance is measured from the camera
+// position passed in, children use their position relative to the parent
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortKey {
+    #[default]
+    Id,
+    Name,
+    ShapeType,
+    CreatedAt,
+    DistanceToCamera(Vec3),
 }
 
-pub fn spawn_entity_system(
-    mut commands: Commands,
-    mut spawn_events: EventReader<SpawnEntityEvent>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut game_manager: ResMut<GameObjectManager>,
-    time: Res<Time>,
-) {
-    for event in spawn_events.read() {
-        let collider = event.shape_type.create_collider();
-        let mesh = event.shape_type.create_mesh(&mut meshes);
-        let material = materials.add(StandardMaterial {
-            base_color: Color::srgb(
-                rand::random::<f32>(),
-                rand::random::<f32>(),
-                rand::random::<f32>(),
-            ),
-            ..default()
-        });
+impl SortKey {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Id => "ID",
+            SortKey::Name => "Name",
+            SortKey::ShapeType => "Type",
+            SortKey::CreatedAt => "Created",
+            SortKey::DistanceToCamera(_) => "Distance",
+        }
+    }
 
-        let entity = commands
-            .spawn((
-                Mesh3d(mesh),
-                MeshMaterial3d(material),
-                Transform::from_translation(event.position),
-                RigidBody::Dynamic,
-                collider,
-                Restitution::coefficient(0.7),
-            ))
-            .id();
-
-        // Add the GameObject ID component and register with manager
-        let object_id = game_manager.add_object(
-            entity,
-            event.shape_type,
-            event.position,
-            event.custom_name.clone(),
-            time.elapsed_secs_f64(),
-        );
+    // Same key, ignoring the camera position a distance sort was made with
+    pub fn same_kind(&self, other: &SortKey) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
 
-        // Add the GameObjectId component to the entity
-        commands.entity(entity).insert(GameObjectId {
-            id: object_id,
-            name: event
-                .custom_name
-                .clone()
-                .unwrap_or_else(|| format!("{} {}", event.shape_type.display_name(), object_id)),
-            shape_type: event.shape_type,
-            created_at: time.elapsed_secs_f64(),
-        });
+// Compare names the way people read them: runs of digits by value, so
+// "Cube 2" comes before "Cube 10", and text case-insensitively
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        let ordering = if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let a_len = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let b_len = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (a_digits, b_digits) = (&a[..a_len], &b[..b_len]);
+            // Compare without leading zeros so long runs can't overflow
+            let a_value = a_digits.trim_start_matches('0');
+            let b_value = b_digits.trim_start_matches('0');
+            let ordering = a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+                .then_with(|| a_digits.len().cmp(&b_digits.len()));
+            a = &a[a_len..];
+            b = &b[b_len..];
+            ordering
+        } else {
+            a = &a[ca.len_utf8()..];
+            b = &b[cb.len_utf8()..];
+            ca.to_lowercase().cmp(cb.to_lowercase())
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
     }
 }
 
-// System to handle entity removal and cleanup
-pub fn cleanup_destroyed_entities_system(
-    mut removed: RemovedComponents<GameObjectId>,
-    mut game_manager: ResMut<GameObjectManager>,
-) {
-    for entity in removed.read() {
-        game_manager.remove_object(entity);
+// The name itself when free, otherwise the first free "name 2", "name 3", ...
+// Library names are numbered this way, object names by the manager's own
+// ".001" scheme
+pub fn unique_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut candidate = name.to_string();
+    let mut n = 1;
+    while taken(&candidate) {
+        n += 1;
+        candidate = format!("{} {}", name, n);
     }
+    candidate
 }
 
-// UI system for shape selection
-pub fn shape_selection_ui(
-    mut selected_shape: ResMut<SelectedShape>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    game_manager: Res<GameObjectManager>,
-) {
-    // Cycle through shapes with Tab key
-    if keyboard_input.just_pressed(KeyCode::Tab) {
-        let shapes = ShapeType::all();
-        let current_index = shapes
-            .iter()
-            .position(|&s| s == selected_shape.shape_type)
-            .unwrap_or(0);
-        let next_index = (current_index + 1) % shapes.len();
-        selected_shape.shape_type = shapes[next_index];
-        info!(
-            "Selected shape: {}",
-            selected_shape.shape_type.display_name()
-        );
+// Tags are compared case-insensitively: trimmed, lowercased and with inner
+// whitespace collapsed to a single dash. None if nothing is left
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let normalized = tag
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+// Splits "bob.004" into ("bob", Some(4)). Only a dot followed by at least three
+// digits counts as a suffix, so "v1.5" keeps its name
+pub fn split_name_suffix(name: &str) -> (&str, Option<u32>) {
+    if let Some((base, digits)) = name.rsplit_once('.')
+        && digits.len() >= 3
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && let Ok(number) = digits.parse()
+    {
+        return (base, Some(number));
+    }
+    (name, None)
+}
+
+// Tags in display order
+pub fn sorted_tags(tags: &HashSet<String>) -> Vec<&str> {
+    let mut sorted: Vec<&str> = tags.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted
+}
+
+impl GameObject {
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.position
     }
 
-    // Debug: Print all objects with 'L' key
-    if keyboard_input.just_pressed(KeyCode::KeyL) {
-        info!("Current game objects:");
-        for object_info in game_manager.list_objects() {
-            info!("  {}", object_info);
+    // Name, id, shape and tags on one line, as list_objects prints them
+    pub fn summary(&self) -> String {
+        let tags = if self.tags.is_empty() {
+            String::new()
+        } else {
+            format!(", Tags: {}", sorted_tags(&self.tags).join(", "))
+        };
+        format!(
+            "{} (ID: {}, Type: {}{})",
+            self.name,
+            self.id,
+            self.shape_type.display_name(),
+            tags
+        )
+    }
+
+    // Seconds since spawning, on the Time::elapsed_secs_f64 clock created_at uses
+    pub fn age(&self, now: f64) -> f64 {
+        (now - self.created_at).max(0.0)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.linvel.length()
+    }
+
+    // Mass assuming rapier's default density of 1
+    pub fn estimated_mass(&self) -> f32 {
+        self.dimensions.volume(self.shape_type)
+    }
+
+    // Translational kinetic energy from the estimated mass, spin is left out
+    pub fn kinetic_energy(&self) -> f32 {
+        0.5 * self.estimated_mass() * self.linvel.length_squared()
+    }
+
+    // The entity, or StaleEntity if it has been despawned without the manager
+    // hearing about it yet
+    pub fn live_entity(&self, entities: &Entities) -> Result<Entity, ObjectError> {
+        if entities.contains(self.entity) {
+            Ok(self.entity)
+        } else {
+            Err(ObjectError::StaleEntity(self.id, self.entity))
         }
-        info!("Total objects: {}", game_manager.objects.len());
     }
-}
 
-// System to update object positions (useful for tracking moving objects)
-pub fn update_object_positions_system(
-    mut game_manager: ResMut<GameObjectManager>,
-    query: Query<(Entity, &Transform), (With<GameObjectId>, Changed<Transform>)>,
+    // Axis aligned bounds at the last synced position and rotation. The
+    // manager doesn't track scale: the gizmo bakes it into the dimensions, but
+    // inspector edits and loaded scenes leave it on the transform, so use
+    // aabb_at with the entity's transform where that matters
+    pub fn aabb(&self) -> Aabb3d {
+        self.shape_type.aabb_with(
+            &self.dimensions,
+            Isometry3d::new(self.position, self.rotation),
+        )
+    }
+
+    // Axis aligned bounds at the entity's transform, any leftover scale is
+    // applied the same way the gizmo bakes it into the dimensions
+    pub fn aabb_at(&self, transform: &Transform) -> Aabb3d {
+        let dims = self.dimensions.scaled(self.shape_type, transform.scale);
+        self.shape_type.aabb_with(
+            &dims,
+            Isometry3d::new(transform.translation, transform.rotation),
+        )
+    }
+}
+
+// Hands out object ids. Fresh ids count up and wrap past u32::MAX, skipping
+// any still in use. Freed ids are only handed out again with recycling on,
+// off by default so undo snapshots keep their ids to themselves
+#[derive(Debug, Default)]
+pub struct IdAllocator {
+    next: u32,
+    live: HashSet<u32>,
+    // Freed ids waiting to be reused, lowest first
+    free: BTreeSet<u32>,
+    recycle: bool,
+}
+
+impl IdAllocator {
+    // The id allocate would return, without taking it
+    pub fn peek(&self) -> Option<u32> {
+        if self.recycle
+            && let Some(&id) = self.free.first()
+        {
+            return Some(id);
+        }
+        self.next_unused(self.next)
+    }
+
+    pub fn allocate(&mut self) -> Result<u32, ObjectError> {
+        let id = self.peek().ok_or(ObjectError::IdsExhausted)?;
+        self.take(id);
+        Ok(id)
+    }
+
+    // Claim a specific id, for objects restored or loaded with their own
+    pub fn reserve(&mut self, id: u32) -> Result<(), ObjectError> {
+        if self.live.contains(&id) {
+            return Err(ObjectError::DuplicateId(id));
+        }
+        self.take(id);
+        Ok(())
+    }
+
+    pub fn release(&mut self, id: u32) {
+        if self.live.remove(&id) && self.recycle {
+            self.free.insert(id);
+        }
+    }
+
+    pub fn is_live(&self, id: u32) -> bool {
+        self.live.contains(&id)
+    }
+
+    pub fn set_recycling(&mut self, recycle: bool) {
+        self.recycle = recycle;
+        if !recycle {
+            self.free.clear();
+        }
+    }
+
+    // Rebuild from the ids actually in use, new ids continue after the
+    // highest. Run after loading a scene
+    pub fn reconcile(&mut self, ids: impl IntoIterator<Item = u32>) {
+        self.live = ids.into_iter().collect();
+        self.free.clear();
+        self.next = self.live.iter().max().map_or(0, |&max| max.wrapping_add(1));
+    }
+
+    // Count on from next, never backwards. Playback uses it so spawns get the
+    // ids they had when the session was recorded
+    pub fn advance_to(&mut self, next: u32) {
+        self.next = self.next.max(next);
+    }
+
+    // Forget every id and count from zero again
+    pub fn reset(&mut self) {
+        self.reconcile(std::iter::empty());
+    }
+
+    fn take(&mut self, id: u32) {
+        self.live.insert(id);
+        self.free.remove(&id);
+        // Only move forward, reserving an old id mustn't rewind the counter.
+        // Taking u32::MAX wraps it to 0
+        if id >= self.next {
+            self.next = id.wrapping_add(1);
+        }
+    }
+
+    // First id from start on that isn't in use, wrapping around once
+    fn next_unused(&self, start: u32) -> Option<u32> {
+        if self.live.len() > u32::MAX as usize {
+            return None;
+        }
+        (start..=u32::MAX)
+            .chain(0..start)
+            .find(|id| !self.live.contains(id))
+    }
+}
+
+// Registry of every object in the scene. Iteration through iter(), iter_mut()
+// and the queries built on them is always in ascending id order, so the
+// hierarchy, saved scenes and anything synced elsewhere see a stable order
+#[derive(Resource, Default)]
+pub struct GameObjectManager {
+    // Keyed by id so iteration is always in id order
+    objects: BTreeMap<u32, GameObject>,
+    // Entity to id index, kept in step with objects. GameObject::entity is
+    // private so the _mut getters can't change it behind the index
+    ids_by_entity: HashMap<Entity, u32>,
+    ids: IdAllocator,
+    // Positions by id, moved along by update_object_positions_system
+    spatial: SpatialGrid,
+    // Ring buffer of (timestamp, objects spawned then), oldest first
+    spawn_times: VecDeque<(f64, usize)>,
+}
+
+// Summary of the scene returned by GameObjectManager::stats
+#[derive(Debug, Clone, Default)]
+pub struct ObjectStats {
+    pub total: usize,
+    pub counts_by_type: HashMap<ShapeType, usize>,
+    pub counts_by_source: HashMap<SpawnSource, usize>,
+    pub oldest_created_at: Option<f64>,
+    pub newest_created_at: Option<f64>,
+    // Spawns per second over the last SPAWN_RATE_WINDOW_SECS, or since
+    // startup if that's shorter
+    pub spawn_rate: f64,
+    // Box around every object's tracked position
+    pub bounds: Option<Aabb3d>,
+}
+
+// Window used by "Select recently spawned"
+pub const RECENT_SPAWN_SECS: f64 = 10.0;
+
+// Age as "3m 12s", "1h 5m" or "42s"
+pub fn format_age(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+// Select every unlocked object spawned in the last RECENT_SPAWN_SECS
+pub fn select_recent_spawns(world: &mut World) {
+    let now = world.resource::<Time>().elapsed_secs_f64();
+    world.resource_scope(|world, mut selection: Mut<Selection>| {
+        let entities: Vec<Entity> = world
+            .resource::<GameObjectManager>()
+            .objects_created_between(now - RECENT_SPAWN_SECS, now)
+            .into_iter()
+            .filter(|obj| !obj.locked)
+            .map(|obj| obj.entity())
+            .collect();
+        selection.select_range(entities);
+    });
+}
+
+// Delete every unlocked object older than HierarchySettings::max_age_secs
+pub fn delete_old_objects(world: &mut World) {
+    let now = world.resource::<Time>().elapsed_secs_f64();
+    let max_age = world.resource::<HierarchySettings>().max_age_secs;
+    let ids: Vec<u32> = world
+        .resource::<GameObjectManager>()
+        .objects_older_than(max_age, now)
+        .into_iter()
+        .filter(|obj| !obj.locked)
+        .map(|obj| obj.id)
+        .collect();
+    if !ids.is_empty() {
+        world.send_event(DespawnEntityEvent {
+            target: DespawnTarget::Ids(ids),
+        });
+    }
+}
+
+impl fmt::Display for ObjectStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} objects, {:.2} spawns/s over the last minute",
+            self.total, self.spawn_rate
+        )?;
+        if let (Some(oldest), Some(newest)) = (self.oldest_created_at, self.newest_created_at) {
+            write!(f, ", created {:.1}s to {:.1}s", oldest, newest)?;
+        }
+        if let Some(bounds) = self.bounds {
+            write!(
+                f,
+                ", bounds {:.1?} to {:.1?}",
+                Vec3::from(bounds.min).to_array(),
+                Vec3::from(bounds.max).to_array()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl GameObjectManager {
+    pub fn add_object(
+        &mut self,
+        entity: Entity,
+        shape_type: ShapeType,
+        position: Vec3,
+        custom_name: Option<String>,
+        unique_name: bool,
+        timestamp: f64,
+    ) -> Result<u32, ObjectError> {
+        let id = self.ids.peek().ok_or(ObjectError::IdsExhausted)?;
+        let mut name = custom_name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| shape_type.default_name(id));
+        if unique_name {
+            name = self.unique_name(&name);
+        }
+
+        let id = self.register_object(entity, shape_type, position, name.clone(), timestamp)?;
+        info!("Added game object: {} (ID: {}) at {:?}", name, id, position);
+        Ok(id)
+    }
+
+    // Register a whole batch of new objects, logging once instead of per object.
+    // Names are used as given, None falls back to the shape's default name.
+    // Stops early if ids run out, so fewer ids than objects may come back
+    pub fn add_objects(
+        &mut self,
+        shape_type: ShapeType,
+        objects: impl IntoIterator<Item = (Entity, Vec3, Option<String>)>,
+        timestamp: f64,
+    ) -> Vec<u32> {
+        let mut ids = Vec::new();
+        for (entity, position, name) in objects {
+            let Some(id) = self.ids.peek() else {
+                warn!("Out of game object IDs after {} objects", ids.len());
+                break;
+            };
+            let name = name.unwrap_or_else(|| shape_type.default_name(id));
+            match self.register_object(entity, shape_type, position, name, timestamp) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    warn!("Cannot register game object: {}", err);
+                    break;
+                }
+            }
+        }
+        info!(
+            "Added {} game objects of type {}",
+            ids.len(),
+            shape_type.display_name()
+        );
+        ids
+    }
+
+    fn register_object(
+        &mut self,
+        entity: Entity,
+        shape_type: ShapeType,
+        position: Vec3,
+        name: String,
+        timestamp: f64,
+    ) -> Result<u32, ObjectError> {
+        let id = self.ids.allocate()?;
+        self.record_spawn(timestamp);
+        let game_object = GameObject {
+            id,
+            name,
+            entity,
+            shape_type,
+            position,
+            rotation: Quat::IDENTITY,
+            linvel: Vec3::ZERO,
+            angvel: Vec3::ZERO,
+            created_at: timestamp,
+            color: Color::WHITE,
+            physics: PhysicsProps::default(),
+            dimensions: shape_type.default_dimensions(),
+            locked: false,
+            physics_locked: false,
+            hidden: false,
+            collider_disabled: false,
+            parent: None,
+            children: Vec::new(),
+            tags: HashSet::new(),
+            prefab: None,
+            custom_mesh: None,
+            metadata: HashMap::new(),
+            source: SpawnSource::default(),
+        };
+
+        self.ids_by_entity.insert(entity, id);
+        self.objects.insert(id, game_object);
+        self.spatial.insert(id, position);
+        Ok(id)
+    }
+
+    // Register a recreated object under its original id
+    pub fn insert_object(
+        &mut self,
+        entity: Entity,
+        snapshot: &ObjectSnapshot,
+        timestamp: f64,
+    ) -> Result<(), ObjectError> {
+        self.ids.reserve(snapshot.id)?;
+
+        self.ids_by_entity.insert(entity, snapshot.id);
+        self.spatial
+            .insert(snapshot.id, snapshot.transform.translation);
+        self.objects.insert(
+            snapshot.id,
+            GameObject {
+                id: snapshot.id,
+                name: snapshot.name.clone(),
+                entity,
+                shape_type: snapshot.shape_type,
+                position: snapshot.transform.translation,
+                rotation: snapshot.transform.rotation,
+                linvel: Vec3::ZERO,
+                angvel: Vec3::ZERO,
+                created_at: timestamp,
+                color: snapshot.color,
+                physics: snapshot.physics,
+                dimensions: snapshot.dimensions,
+                locked: snapshot.locked,
+                physics_locked: snapshot.physics_locked,
+                hidden: snapshot.hidden,
+                collider_disabled: snapshot.collider_disabled,
+                parent: None,
+                children: Vec::new(),
+                tags: snapshot.tags.clone(),
+                prefab: snapshot.prefab.clone(),
+                custom_mesh: snapshot.custom_mesh.clone(),
+                metadata: snapshot.metadata.clone(),
+                source: snapshot.source,
+            },
+        );
+        if let Some(parent) = snapshot.parent
+            && self.objects.contains_key(&parent)
+        {
+            self.set_parent(snapshot.id, parent)?;
+        }
+
+        info!(
+            "Restored game object: {} (ID: {})",
+            snapshot.name, snapshot.id
+        );
+        Ok(())
+    }
+
+    // Capture an object's current state, the transform comes from its entity
+    pub fn snapshot(&self, id: u32, transform: Transform) -> Option<ObjectSnapshot> {
+        self.get_object_by_id(id).map(|obj| ObjectSnapshot {
+            id,
+            name: obj.name.clone(),
+            shape_type: obj.shape_type,
+            transform,
+            color: obj.color,
+            physics: obj.physics,
+            dimensions: obj.dimensions,
+            locked: obj.locked,
+            physics_locked: obj.physics_locked,
+            hidden: obj.hidden,
+            collider_disabled: obj.collider_disabled,
+            parent: obj.parent,
+            tags: obj.tags.clone(),
+            prefab: obj.prefab.clone(),
+            custom_mesh: obj.custom_mesh.clone(),
+            metadata: obj.metadata.clone(),
+            source: obj.source,
+        })
+    }
+
+    // Forget every object and start ids from zero again, entities are left to the caller
+    pub fn clear_all(&mut self) -> Vec<GameObject> {
+        self.ids.reset();
+        self.spatial.clear();
+        self.ids_by_entity.clear();
+        std::mem::take(&mut self.objects).into_values().collect()
+    }
+
+    pub fn ids(&self) -> &IdAllocator {
+        &self.ids
+    }
+
+    pub fn ids_mut(&mut self) -> &mut IdAllocator {
+        &mut self.ids
+    }
+
+    // Resync the allocator with the objects actually registered, so loaded
+    // objects never share an id with the next spawn
+    pub fn reconcile_ids(&mut self) {
+        self.ids.reconcile(self.objects.keys().copied());
+    }
+
+    // Drop every object whose entity fails the check, unlinking it from the
+    // hierarchy like remove_object does
+    pub fn evict_stale(&mut self, is_alive: impl Fn(Entity) -> bool) -> Vec<GameObject> {
+        let stale: Vec<Entity> = self
+            .ids_by_entity
+            .keys()
+            .copied()
+            .filter(|&entity| !is_alive(entity))
+            .collect();
+        stale
+            .into_iter()
+            .filter_map(|entity| self.remove_object(entity))
+            .collect()
+    }
+
+    // Remove an object, its children stay behind as root objects
+    pub fn remove_object(&mut self, entity: Entity) -> Option<GameObject> {
+        let id = self.ids_by_entity.remove(&entity)?;
+        let removed = self.objects.remove(&id)?;
+        self.ids.release(id);
+        self.spatial.remove(id);
+        if let Some(parent) = removed
+            .parent
+            .and_then(|parent| self.objects.get_mut(&parent))
+        {
+            parent.children.retain(|&child| child != id);
+        }
+        for child in &removed.children {
+            if let Some(child) = self.objects.get_mut(child) {
+                child.parent = None;
+            }
+        }
+        info!("Removed game object: {} (ID: {})", removed.name, removed.id);
+        Some(removed)
+    }
+
+    // Make one object the child of another, moving it out of any previous parent
+    pub fn set_parent(&mut self, child_id: u32, parent_id: u32) -> Result<(), ObjectError> {
+        if !self.objects.contains_key(&child_id) {
+            return Err(ObjectError::NotFound(child_id));
+        }
+        if !self.objects.contains_key(&parent_id) {
+            return Err(ObjectError::NotFound(parent_id));
+        }
+        // Walk up from the new parent, finding the child there means a loop
+        let mut ancestor = Some(parent_id);
+        while let Some(id) = ancestor {
+            if id == child_id {
+                return Err(ObjectError::ParentCycle(child_id, parent_id));
+            }
+            ancestor = self.objects.get(&id).and_then(|obj| obj.parent);
+        }
+
+        self.detach(child_id)?;
+        if let Some(parent) = self.objects.get_mut(&parent_id) {
+            parent.children.push(child_id);
+        }
+        if let Some(child) = self.objects.get_mut(&child_id) {
+            child.parent = Some(parent_id);
+        }
+        Ok(())
+    }
+
+    // Turn an object back into a root, returns the parent it had
+    pub fn detach(&mut self, child_id: u32) -> Result<Option<u32>, ObjectError> {
+        let child = self
+            .objects
+            .get_mut(&child_id)
+            .ok_or(ObjectError::NotFound(child_id))?;
+        let Some(parent_id) = child.parent.take() else {
+            return Ok(None);
+        };
+        if let Some(parent) = self.objects.get_mut(&parent_id) {
+            parent.children.retain(|&id| id != child_id);
+        }
+        Ok(Some(parent_id))
+    }
+
+    // Every object below this one, parents before their children
+    pub fn descendants(&self, id: u32) -> Vec<u32> {
+        let mut descendants = Vec::new();
+        let mut stack: Vec<u32> = self
+            .get_object_by_id(id)
+            .map(|obj| obj.children.iter().rev().copied().collect())
+            .unwrap_or_default();
+        while let Some(child) = stack.pop() {
+            descendants.push(child);
+            if let Some(obj) = self.get_object_by_id(child) {
+                stack.extend(obj.children.iter().rev());
+            }
+        }
+        descendants
+    }
+
+    // Rename an object, an empty name reverts to the auto-generated default
+    pub fn rename_object(&mut self, id: u32, new_name: &str) -> Result<String, ObjectError> {
+        let obj = self.objects.get_mut(&id).ok_or(ObjectError::NotFound(id))?;
+
+        let trimmed = new_name.trim();
+        let name = if trimmed.is_empty() {
+            obj.shape_type.default_name(id)
+        } else {
+            trimmed.to_string()
+        };
+
+        info!("Renamed game object {} -> {} (ID: {})", obj.name, name, id);
+        obj.name = name.clone();
+        Ok(name)
+    }
+
+    // The name itself if it's free, otherwise the base name with the lowest
+    // unused ".NNN" suffix, the way Blender numbers duplicates
+    pub fn unique_name(&self, name: &str) -> String {
+        self.unique_names(name, 1).remove(0)
+    }
+
+    // Names for several new objects at once, scanning the scene a single time.
+    // The first keeps the name as given if it's free
+    pub fn unique_names(&self, name: &str, count: usize) -> Vec<String> {
+        let (base, own_number) = split_name_suffix(name);
+        let mut taken = false;
+        let mut used = HashSet::new();
+        for obj in self.objects.values() {
+            taken |= obj.name == name;
+            if let (obj_base, Some(number)) = split_name_suffix(&obj.name)
+                && obj_base == base
+            {
+                used.insert(number);
+            }
+        }
+
+        let mut names = Vec::with_capacity(count);
+        if !taken && count > 0 {
+            names.push(name.to_string());
+            used.extend(own_number);
+        }
+        let mut number = 1;
+        while names.len() < count {
+            if used.insert(number) {
+                names.push(format!("{}.{:03}", base, number));
+            }
+            number += 1;
+        }
+        names
+    }
+
+    // Exact matches first, then names starting with the text, each in id order
+    pub fn find_by_name(&self, name: &str) -> Vec<&GameObject> {
+        let (exact, prefix): (Vec<&GameObject>, Vec<&GameObject>) = self
+            .objects
+            .values()
+            .filter(|obj| obj.name.starts_with(name))
+            .partition(|obj| obj.name == name);
+        exact.into_iter().chain(prefix).collect()
+    }
+
+    // Every object ordered by the key, ties broken by id
+    pub fn sorted_objects(&self, key: SortKey, ascending: bool) -> Vec<&GameObject> {
+        let mut objects: Vec<&GameObject> = self.iter().collect();
+        objects.sort_by(|a, b| {
+            let ordering = match key {
+                SortKey::Id => a.id.cmp(&b.id),
+                SortKey::Name => natural_cmp(&a.name, &b.name),
+                SortKey::ShapeType => a.shape_type.display_name().cmp(b.shape_type.display_name()),
+                SortKey::CreatedAt => a.created_at.total_cmp(&b.created_at),
+                SortKey::DistanceToCamera(camera) => a
+                    .position
+                    .distance_squared(camera)
+                    .total_cmp(&b.position.distance_squared(camera)),
+            }
+            .then(a.id.cmp(&b.id));
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+        objects
+    }
+
+    // Every object in ascending id order. The order only depends on which ids
+    // exist, never on the order objects were added or removed in
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &GameObject> + ExactSizeIterator {
+        self.objects.values()
+    }
+
+    // Same order as iter(). Positions can't be changed this way, use
+    // set_position for that
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut GameObject> + ExactSizeIterator {
+        self.objects.values_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn get_object_by_entity(&self, entity: Entity) -> Option<&GameObject> {
+        self.ids_by_entity
+            .get(&entity)
+            .and_then(|id| self.objects.get(id))
+    }
+
+    pub fn get_object_by_id(&self, id: u32) -> Option<&GameObject> {
+        self.objects.get(&id)
+    }
+
+    pub fn get_object_by_entity_mut(&mut self, entity: Entity) -> Option<&mut GameObject> {
+        self.ids_by_entity
+            .get(&entity)
+            .and_then(|id| self.objects.get_mut(id))
+    }
+
+    pub fn get_object_by_id_mut(&mut self, id: u32) -> Option<&mut GameObject> {
+        self.objects.get_mut(&id)
+    }
+
+    // Record where an object's entity is now, keeping the spatial index in step
+    pub fn set_position(&mut self, entity: Entity, position: Vec3) {
+        if let Some(&id) = self.ids_by_entity.get(&entity) {
+            self.set_position_by_id(id, position);
+        }
+    }
+
+    pub fn set_position_by_id(&mut self, id: u32, position: Vec3) {
+        if let Some(obj) = self.objects.get_mut(&id) {
+            obj.position = position;
+            self.spatial.insert(id, position);
+        }
+    }
+
+    // Objects whose position is within radius of center, in id order. Uses the
+    // positions from the last update_object_positions_system run
+    pub fn objects_within_radius(&self, center: Vec3, radius: f32) -> Vec<&GameObject> {
+        self.spatial
+            .within_radius(center, radius)
+            .into_iter()
+            .filter_map(|id| self.objects.get(&id))
+            .collect()
+    }
+
+    // Object closest to point, optionally only of one shape. Equal distances go
+    // to the lowest id
+    pub fn nearest_object(&self, point: Vec3, filter: Option<ShapeType>) -> Option<&GameObject> {
+        let id = self.spatial.nearest(point, |id| {
+            filter.is_none_or(|shape_type| {
+                self.objects
+                    .get(&id)
+                    .is_some_and(|obj| obj.shape_type == shape_type)
+            })
+        })?;
+        self.objects.get(&id)
+    }
+
+    // Objects created from start to end inclusive, oldest first
+    pub fn objects_created_between(&self, start: f64, end: f64) -> Vec<&GameObject> {
+        let mut objects: Vec<&GameObject> = self
+            .iter()
+            .filter(|obj| obj.created_at >= start && obj.created_at <= end)
+            .collect();
+        objects.sort_by(|a, b| a.created_at.total_cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        objects
+    }
+
+    // Objects that have existed for more than secs at time now, oldest first
+    pub fn objects_older_than(&self, secs: f64, now: f64) -> Vec<&GameObject> {
+        let mut objects: Vec<&GameObject> = self.iter().filter(|obj| obj.age(now) > secs).collect();
+        objects.sort_by(|a, b| a.created_at.total_cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        objects
+    }
+
+    // Objects whose position lies inside the box, in id order
+    pub fn objects_in_aabb(&self, min: Vec3, max: Vec3) -> Vec<&GameObject> {
+        self.spatial
+            .in_aabb(min, max)
+            .into_iter()
+            .filter_map(|id| self.objects.get(&id))
+            .collect()
+    }
+
+    // Tag an object, returns false if it already had the tag
+    pub fn add_tag(&mut self, id: u32, tag: &str) -> Result<bool, ObjectError> {
+        let tag = normalize_tag(tag).ok_or_else(|| ObjectError::InvalidTag(tag.to_string()))?;
+        let obj = self.objects.get_mut(&id).ok_or(ObjectError::NotFound(id))?;
+        Ok(obj.tags.insert(tag))
+    }
+
+    // Set a metadata value, returning the one it replaced
+    pub fn set_meta(
+        &mut self,
+        id: u32,
+        key: &str,
+        value: MetaValue,
+    ) -> Result<Option<MetaValue>, ObjectError> {
+        let key = normalize_meta_key(key)?;
+        let obj = self.objects.get_mut(&id).ok_or(ObjectError::NotFound(id))?;
+        Ok(obj.metadata.insert(key, value))
+    }
+
+    pub fn get_meta(&self, id: u32, key: &str) -> Option<&MetaValue> {
+        self.get_object_by_id(id)?.metadata.get(key.trim())
+    }
+
+    pub fn remove_meta(&mut self, id: u32, key: &str) -> Result<Option<MetaValue>, ObjectError> {
+        let obj = self.objects.get_mut(&id).ok_or(ObjectError::NotFound(id))?;
+        Ok(obj.metadata.remove(key.trim()))
+    }
+
+    // Untag an object, returns false if it didn't have the tag
+    pub fn remove_tag(&mut self, id: u32, tag: &str) -> Result<bool, ObjectError> {
+        let obj = self.objects.get_mut(&id).ok_or(ObjectError::NotFound(id))?;
+        Ok(normalize_tag(tag).is_some_and(|tag| obj.tags.remove(&tag)))
+    }
+
+    // Objects carrying the tag, matched after normalizing it
+    pub fn get_objects_by_tag(&self, tag: &str) -> Vec<&GameObject> {
+        let Some(tag) = normalize_tag(tag) else {
+            return Vec::new();
+        };
+        self.iter().filter(|obj| obj.tags.contains(&tag)).collect()
+    }
+
+    // Every tag in use, sorted
+    pub fn all_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self
+            .iter()
+            .flat_map(|obj| obj.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    pub fn get_objects_by_type(&self, shape_type: ShapeType) -> Vec<&GameObject> {
+        self.iter()
+            .filter(|obj| obj.shape_type == shape_type)
+            .collect()
+    }
+
+    // Case-insensitive name substring match, optionally narrowed to one shape type
+    pub fn filter_objects(&self, name_query: &str, shape: Option<ShapeType>) -> Vec<&GameObject> {
+        let query = name_query.trim().to_lowercase();
+        let candidates = match shape {
+            Some(shape_type) => self.get_objects_by_type(shape_type),
+            None => self.iter().collect(),
+        };
+
+        candidates
+            .into_iter()
+            .filter(|obj| query.is_empty() || obj.name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    // Note a spawn for the rate, folded into the last entry when it's the
+    // same frame
+    fn record_spawn(&mut self, timestamp: f64) {
+        if let Some((last, count)) = self.spawn_times.back_mut()
+            && *last == timestamp
+        {
+            *count += 1;
+            return;
+        }
+        if self.spawn_times.len() == SPAWN_HISTORY_CAPACITY {
+            self.spawn_times.pop_front();
+        }
+        self.spawn_times.push_back((timestamp, 1));
+    }
+
+    // Spawns per second over the rate window ending at `now`
+    pub fn spawn_rate(&self, now: f64) -> f64 {
+        let window = SPAWN_RATE_WINDOW_SECS.min(now);
+        if window <= 0.0 {
+            return 0.0;
+        }
+        let spawned: usize = self
+            .spawn_times
+            .iter()
+            .rev()
+            .take_while(|(timestamp, _)| now - timestamp <= window)
+            .map(|(_, count)| count)
+            .sum();
+        spawned as f64 / window
+    }
+
+    // Counts, ages, spawn rate and extent of the scene at time `now`, the
+    // extent as scene_bounds works it out
+    pub fn stats(
+        &self,
+        now: f64,
+        world_transform: impl Fn(Entity) -> Option<Transform>,
+    ) -> ObjectStats {
+        let mut stats = ObjectStats {
+            total: self.len(),
+            counts_by_type: self.counts_by_type(),
+            counts_by_source: self.counts_by_source(),
+            spawn_rate: self.spawn_rate(now),
+            ..default()
+        };
+        for obj in self.iter() {
+            stats.oldest_created_at = Some(
+                stats
+                    .oldest_created_at
+                    .map_or(obj.created_at, |oldest| oldest.min(obj.created_at)),
+            );
+            stats.newest_created_at = Some(
+                stats
+                    .newest_created_at
+                    .map_or(obj.created_at, |newest| newest.max(obj.created_at)),
+            );
+        }
+        stats.bounds = (!self.is_empty()).then(|| self.scene_bounds(world_transform));
+        stats
+    }
+
+    // Union of every object's bounds at the world transform of its entity, so
+    // scale left on the transform counts. Objects without one fall back to
+    // their synced placement. An empty scene gives a zero sized box at the
+    // origin
+    pub fn scene_bounds(&self, world_transform: impl Fn(Entity) -> Option<Transform>) -> Aabb3d {
+        self.iter()
+            .map(|obj| {
+                world_transform(obj.entity)
+                    .map_or_else(|| obj.aabb(), |transform| obj.aabb_at(&transform))
+            })
+            .reduce(|a, b| a.merge(&b))
+            .unwrap_or(Aabb3d {
+                min: Vec3A::ZERO,
+                max: Vec3A::ZERO,
+            })
+    }
+
+    // Number of objects per shape type, shapes with no objects are omitted
+    pub fn counts_by_type(&self) -> HashMap<ShapeType, usize> {
+        let mut counts = HashMap::new();
+        for obj in self.iter() {
+            *counts.entry(obj.shape_type).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn counts_by_source(&self) -> HashMap<SpawnSource, usize> {
+        let mut counts = HashMap::new();
+        for obj in self.iter() {
+            *counts.entry(obj.source).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // Most recently created object of the given shape
+    pub fn latest_of_type(&self, shape_type: ShapeType) -> Option<&GameObject> {
+        self.iter()
+            .filter(|obj| obj.shape_type == shape_type)
+            .max_by(|a, b| a.created_at.total_cmp(&b.created_at).then(a.id.cmp(&b.id)))
+    }
+
+    // One line per object, sorted by id
+    pub fn list_objects(&self) -> Vec<String> {
+        self.iter().map(GameObject::summary).collect()
+    }
+}
+
+// Material for an object of this colour, blended once it's see-through
+pub fn object_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        alpha_mode: if color.alpha() < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        ..default()
+    }
+}
+
+// Objects from a batch spawn share one material per palette colour until
+// their colour is edited
+#[derive(Component)]
+pub struct SharedMaterial;
+
+// Each object's material and whether it's still shared
+pub type MaterialHandles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static MeshMaterial3d<StandardMaterial>,
+        Has<SharedMaterial>,
+    ),
+>;
+
+// Recolour an object's material in place, or give it its own first if the
+// material is shared with other objects
+pub fn set_material_color(
+    commands: &mut Commands,
+    materials: &mut Assets<StandardMaterial>,
+    entity: Entity,
+    handle: &MeshMaterial3d<StandardMaterial>,
+    shared: bool,
+    color: Color,
+) {
+    if shared {
+        commands
+            .entity(entity)
+            .insert(MeshMaterial3d(materials.add(object_material(color))))
+            .remove::<SharedMaterial>();
+    } else if let Some(material) = materials.get_mut(&handle.0) {
+        material.base_color = color;
+        material.alpha_mode = object_material(color).alpha_mode;
+    }
+}
+
+// Fill a reserved entity with the rendered physics body for an object,
+// shared by every spawn path so mesh and collider always agree
+pub fn insert_object_components(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    entity: Entity,
+    snapshot: &ObjectSnapshot,
+) {
+    let material = materials.add(object_material(snapshot.color));
+
+    commands.entity(entity).insert((
+        Mesh3d(
+            snapshot
+                .shape_type
+                .create_mesh_with(meshes, &snapshot.dimensions),
+        ),
+        MeshMaterial3d(material),
+        snapshot.transform,
+        snapshot
+            .physics
+            .rigid_body(snapshot.physics_locked, snapshot.parent.is_some()),
+        snapshot
+            .shape_type
+            .create_collider_with(&snapshot.dimensions),
+        snapshot.physics.components(),
+        if snapshot.hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        },
+    ));
+    if snapshot.collider_disabled {
+        commands.entity(entity).insert(ColliderDisabled);
+    }
+    match &snapshot.custom_mesh {
+        Some(name) => {
+            commands
+                .entity(entity)
+                .insert(CustomMeshInstance(name.clone()));
+        }
+        None => {
+            commands.entity(entity).remove::<CustomMeshInstance>();
+        }
+    }
+}
+
+// Recreate an object from a snapshot, keeping its original id. It goes back
+// under its parent if that still exists
+pub fn restore_object(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    game_manager: &mut GameObjectManager,
+    snapshot: &ObjectSnapshot,
+    timestamp: f64,
+) -> Result<Entity, ObjectError> {
+    if game_manager.get_object_by_id(snapshot.id).is_some() {
+        return Err(ObjectError::DuplicateId(snapshot.id));
+    }
+
+    let entity = commands.spawn_empty().id();
+    game_manager.insert_object(entity, snapshot, timestamp)?;
+    insert_object_components(commands, meshes, materials, entity, snapshot);
+    if let Some(obj) = game_manager.get_object_by_id(snapshot.id) {
+        commands.entity(entity).insert(GameObjectId::from(obj));
+    }
+    sync_parent_components(commands, game_manager, snapshot.id, false);
+    Ok(entity)
+}
+
+// Recreate a batch of objects. Parents are linked once everything is back, so
+// a child can come before its parent in the list. Returns the ids that
+// couldn't be restored and why
+pub fn restore_objects(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    game_manager: &mut GameObjectManager,
+    snapshots: &[ObjectSnapshot],
+    timestamp: f64,
+) -> Vec<(u32, ObjectError)> {
+    let mut failed = Vec::new();
+    for snapshot in snapshots {
+        if let Err(err) = restore_object(
+            commands,
+            meshes,
+            materials,
+            game_manager,
+            snapshot,
+            timestamp,
+        ) {
+            warn!("Cannot restore object {}: {}", snapshot.id, err);
+            failed.push((snapshot.id, err));
+        }
+    }
+    for snapshot in snapshots {
+        let Some(parent) = snapshot.parent else {
+            continue;
+        };
+        let linked = game_manager
+            .get_object_by_id(snapshot.id)
+            .is_none_or(|obj| obj.parent == Some(parent));
+        if !linked && game_manager.set_parent(snapshot.id, parent).is_ok() {
+            sync_parent_components(commands, game_manager, snapshot.id, false);
+        }
+    }
+    failed
+}
+
+// Mirror an object's parent onto its entity. Editor reparenting keeps the world
+// transform, restored snapshots already hold one relative to the parent
+fn sync_parent_components(
+    commands: &mut Commands,
+    game_manager: &GameObjectManager,
+    id: u32,
+    keep_world: bool,
+) {
+    let Some(obj) = game_manager.get_object_by_id(id) else {
+        return;
+    };
+    let parent = obj
+        .parent
+        .and_then(|parent| game_manager.get_object_by_id(parent))
+        .map(|parent| parent.entity);
+
+    let mut entity = commands.entity(obj.entity);
+    match (parent, keep_world) {
+        (Some(parent), true) => entity.set_parent_in_place(parent),
+        (Some(parent), false) => entity.insert(ChildOf(parent)),
+        (None, true) => entity.remove_parent_in_place(),
+        (None, false) => entity.remove::<ChildOf>(),
+    };
+    entity.insert(obj.physics.rigid_body(obj.physics_locked, parent.is_some()));
+    wake_body(commands, obj.entity);
+}
+
+// Move an object under a new parent, or to the root with None, leaving it
+// where it is in the world. Returns the parent it had before
+pub fn reparent_object(
+    commands: &mut Commands,
+    game_manager: &mut GameObjectManager,
+    id: u32,
+    parent: Option<u32>,
+) -> Result<Option<u32>, ObjectError> {
+    let previous = game_manager
+        .get_object_by_id(id)
+        .ok_or(ObjectError::NotFound(id))?
+        .parent;
+    match parent {
+        Some(parent) => game_manager.set_parent(id, parent)?,
+        None => {
+            game_manager.detach(id)?;
+        }
+    }
+    sync_parent_components(commands, game_manager, id, true);
+    Ok(previous)
+}
+
+// Turn an object's children into roots in place, so despawning it leaves them behind
+pub fn orphan_children(commands: &mut Commands, game_manager: &mut GameObjectManager, id: u32) {
+    let children = game_manager
+        .get_object_by_id(id)
+        .map(|obj| obj.children.clone())
+        .unwrap_or_default();
+    for child in children {
+        if let Err(err) = reparent_object(commands, game_manager, child, None) {
+            warn!("Cannot detach object {}: {}", child, err);
+        }
+    }
+}
+
+// Everything a system needs to build objects and register them with the manager
+#[derive(SystemParam)]
+pub struct ObjectSpawner<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub materials: ResMut<'w, Assets<StandardMaterial>>,
+    pub library: Res<'w, CustomMeshLibrary>,
+    pub game_manager: ResMut<'w, GameObjectManager>,
+}
+
+// Where new objects land, what they're called and how many may exist. Nothing
+// but a recording spawns during playback
+#[derive(SystemParam)]
+pub struct SpawnRules<'w> {
+    pub snap: Res<'w, SnapSettings>,
+    pub naming: Res<'w, NamingSettings>,
+    pub limits: Res<'w, SpawnLimits>,
+    pub playback: Res<'w, Playback>,
+}
+
+pub fn spawn_entity_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        library,
+        mut game_manager,
+    }: ObjectSpawner,
+    mut spawn_events: EventReader<SpawnEntityEvent>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    SpawnRules {
+        snap,
+        naming,
+        limits,
+        playback,
+    }: SpawnRules,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
+    mut pool: PooledEntities,
+    mut rng: ResMut<EditorRng>,
+) {
+    let mut evicting = HashSet::new();
+    let mut blocked = 0;
+    for event in spawn_events.read() {
+        // Only the recording spawns during playback, anything else would diverge
+        if playback.is_active() && event.source != SpawnSource::Playback {
+            blocked += 1;
+            continue;
+        }
+        // Imported meshes spawn at the size they were modelled at
+        let custom_dimensions = match (event.shape_type, &event.custom_mesh) {
+            (ShapeType::CustomMesh, Some(name)) => match library.dimensions(name) {
+                Some(dims) => Some(dims),
+                None => {
+                    notifications.warn(format!("Cannot spawn: mesh {} isn't loaded", name));
+                    continue;
+                }
+            },
+            (ShapeType::CustomMesh, None) => {
+                notifications.warn("Cannot spawn: no mesh given for a custom mesh object");
+                continue;
+            }
+            _ => None,
+        };
+
+        match limits.make_room(&game_manager, event.shape_type, 1, &evicting) {
+            Ok(victims) if victims.is_empty() => {}
+            Ok(victims) => {
+                evicting.extend(victims.iter().copied());
+                despawn_events.write(DespawnEntityEvent {
+                    target: DespawnTarget::Evicted(victims),
+                });
+            }
+            Err(err) => {
+                warn!("Spawn rejected: {}", err);
+                notifications.warn(format!("Cannot spawn: {}", err));
+                continue;
+            }
+        }
+
+        let (mut physics, clamped) = event.physics.unwrap_or_default().clamped();
+        if let Some(body) = event.body_type {
+            physics.body = body;
+        }
+        if !clamped.is_empty() {
+            warn!("Spawn physics out of range, clamped {}", clamped.join(", "));
+            notifications.warn(format!(
+                "Physics values out of range, clamped {}",
+                clamped.join(", ")
+            ));
+        }
+
+        let position = snap.snap_position(event.position);
+        let color = rng.random_color();
+        let dimensions = custom_dimensions
+            .or(event.params)
+            .unwrap_or_else(|| event.shape_type.default_dimensions())
+            .validated(event.shape_type);
+
+        // Add the GameObject ID component and register with manager. A parked
+        // entity of the same shape is reused when the pool has one
+        let reused = pool.take(&mut commands, event.shape_type);
+        let entity = reused.map_or_else(|| commands.spawn_empty().id(), |(entity, _)| entity);
+        let object_id = match game_manager.add_object(
+            entity,
+            event.shape_type,
+            position,
+            event.custom_name.clone(),
+            naming.unique_names,
+            time.elapsed_secs_f64(),
+        ) {
+            Ok(id) => id,
+            Err(err) => {
+                commands.entity(entity).despawn();
+                notifications.error(format!("Cannot spawn: {}", err));
+                continue;
+            }
+        };
+        let Some(obj) = game_manager.get_object_by_id_mut(object_id) else {
+            continue;
+        };
+        obj.color = color;
+        obj.dimensions = dimensions;
+        obj.physics = physics;
+        obj.source = event.source;
+        if event.shape_type == ShapeType::CustomMesh {
+            obj.custom_mesh = event.custom_mesh.clone();
+        }
+        obj.tags = event
+            .tags
+            .iter()
+            .filter_map(|tag| normalize_tag(tag))
+            .collect();
+        obj.metadata = event
+            .metadata
+            .iter()
+            .filter_map(|(key, value)| Some((normalize_meta_key(key).ok()?, value.clone())))
+            .collect();
+        let object_id_component = GameObjectId::from(&*obj);
+
+        let Some(snapshot) =
+            game_manager.snapshot(object_id, Transform::from_translation(position))
+        else {
+            continue;
+        };
+        match reused {
+            Some((_, parked)) => reactivate_pooled(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                entity,
+                parked,
+                &snapshot,
+            ),
+            None => insert_object_components(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                entity,
+                &snapshot,
+            ),
+        }
+        commands.entity(entity).insert(object_id_component);
+        if let Some(secs) = event.lifetime_secs {
+            commands.entity(entity).insert(Lifetime::new(secs));
+        }
+
+        notifications.info(format!("Spawned {}", snapshot.name));
+        history.push(EditorCommand::Spawn(snapshot), time.elapsed_secs_f64());
+    }
+    if blocked > 0 {
+        notifications.warn("Spawning is disabled during playback");
+    }
+}
+
+#[derive(Event)]
+pub struct RenameObjectEvent {
+    pub id: u32,
+    pub new_name: String,
+}
+
+// Apply renames to both the manager and the entity's GameObjectId
+pub fn rename_object_system(
+    mut rename_events: EventReader<RenameObjectEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut ids: Query<&mut GameObjectId>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in rename_events.read() {
+        match game_manager.rename_object(event.id, &event.new_name) {
+            Ok(name) => {
+                let entity = game_manager
+                    .get_object_by_id(event.id)
+                    .map(|obj| obj.entity);
+                if let Some(mut object_id) = entity.and_then(|entity| ids.get_mut(entity).ok()) {
+                    object_id.name = name;
+                }
+            }
+            Err(err) => {
+                warn!("Rename failed: {}", err);
+                notifications.error(format!("Rename failed: {}", err));
+            }
+        }
+    }
+}
+
+// Lock or unlock an object from the hierarchy
+#[derive(Event)]
+pub struct LockObjectEvent {
+    pub id: u32,
+    pub locked: bool,
+    // Also freeze the body while locked
+    pub lock_physics: bool,
+}
+
+pub fn lock_object_system(
+    mut commands: Commands,
+    mut lock_events: EventReader<LockObjectEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+) {
+    for event in lock_events.read() {
+        let Some(obj) = game_manager.get_object_by_id_mut(event.id) else {
+            continue;
+        };
+
+        let physics_locked = event.locked && event.lock_physics;
+        if physics_locked != obj.physics_locked {
+            set_rigid_body(
+                &mut commands,
+                obj.entity,
+                obj.physics.rigid_body(physics_locked, obj.parent.is_some()),
+            );
+        }
+        obj.locked = event.locked;
+        obj.physics_locked = physics_locked;
+        info!(
+            "{} {}",
+            if obj.locked { "Locked" } else { "Unlocked" },
+            obj.name
+        );
+    }
+}
+
+// Show or hide an object without despawning it
+#[derive(Event)]
+pub struct SetVisibilityEvent {
+    pub id: u32,
+    pub visible: bool,
+    // Also switch the collider off while hidden
+    pub disable_collider: bool,
+}
+
+pub fn set_visibility_system(
+    mut commands: Commands,
+    mut visibility_events: EventReader<SetVisibilityEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut visibilities: Query<&mut Visibility>,
+) {
+    for event in visibility_events.read() {
+        let Some(obj) = game_manager.get_object_by_id_mut(event.id) else {
+            continue;
+        };
+
+        obj.hidden = !event.visible;
+        if let Ok(mut visibility) = visibilities.get_mut(obj.entity) {
+            *visibility = if obj.hidden {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+
+        let collider_disabled = obj.hidden && event.disable_collider;
+        if collider_disabled != obj.collider_disabled {
+            if collider_disabled {
+                commands.entity(obj.entity).insert(ColliderDisabled);
+            } else {
+                commands.entity(obj.entity).remove::<ColliderDisabled>();
+            }
+            // Objects resting on it should fall once it stops colliding
+            wake_body(&mut commands, obj.entity);
+        }
+        obj.collider_disabled = collider_disabled;
+    }
+}
+
+// Randomness applied when a spawn is carried out. Seeded, so a recorded
+// session played back from its seed gets the same colors
+#[derive(Resource)]
+pub struct EditorRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for EditorRng {
+    fn default() -> Self {
+        Self::seeded(rand::random())
+    }
+}
+
+impl EditorRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn random_color(&mut self) -> Color {
+        Color::srgb(self.rng.r#gen(), self.rng.r#gen(), self.rng.r#gen())
+    }
+}
+
+// Random drop point above the middle of the ground
+pub fn random_spawn_position() -> Vec3 {
+    let x = (rand::random::<f32>() - 0.5) * 10.0;
+    let z = (rand::random::<f32>() - 0.5) * 10.0;
+    Vec3::new(x, 4.0, z)
+}
+
+// Which objects a DespawnEntityEvent removes
+#[derive(Debug, Clone, PartialEq)]
+pub enum DespawnTarget {
+    Id(u32),
+    Entity(Entity),
+    // Several objects at once, ids that no longer exist are skipped
+    Ids(Vec<u32>),
+    // Objects whose Lifetime ran out, removed without a toast or undo step
+    Expired(Vec<u32>),
+    // Oldest objects pushed out by SpawnLimits, also without a toast or undo step
+    Evicted(Vec<u32>),
+    // Objects moved to the Trash, restored from there rather than by undo
+    Trash(Vec<u32>),
+    Type(ShapeType),
+    All,
+}
+
+impl DespawnTarget {
+    // Targets naming one object, those warn when it's locked or missing
+    pub fn is_single(&self) -> bool {
+        matches!(self, DespawnTarget::Id(_) | DespawnTarget::Entity(_))
+    }
+
+    // Ids currently covered by the target, None if a single object doesn't exist
+    pub fn resolve(&self, game_manager: &GameObjectManager) -> Option<Vec<u32>> {
+        match self {
+            DespawnTarget::Id(id) => game_manager.get_object_by_id(*id).map(|obj| vec![obj.id]),
+            DespawnTarget::Entity(entity) => game_manager
+                .get_object_by_entity(*entity)
+                .map(|obj| vec![obj.id]),
+            DespawnTarget::Ids(ids)
+            | DespawnTarget::Expired(ids)
+            | DespawnTarget::Evicted(ids)
+            | DespawnTarget::Trash(ids) => Some(ids.clone()),
+            DespawnTarget::Type(shape_type) => Some(
+                game_manager
+                    .get_objects_by_type(*shape_type)
+                    .iter()
+                    .map(|obj| obj.id)
+                    .collect(),
+            ),
+            DespawnTarget::All => Some(game_manager.iter().map(|obj| obj.id).collect()),
+        }
+    }
+}
+
+impl fmt::Display for DespawnTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DespawnTarget::Id(id) => write!(f, "object {}", id),
+            DespawnTarget::Entity(entity) => write!(f, "entity {}", entity),
+            DespawnTarget::Ids(ids) => write!(f, "{} objects", ids.len()),
+            DespawnTarget::Expired(ids) => write!(f, "{} expired objects", ids.len()),
+            DespawnTarget::Evicted(ids) => write!(f, "{} evicted objects", ids.len()),
+            DespawnTarget::Trash(ids) => write!(f, "{} objects for the trash", ids.len()),
+            DespawnTarget::Type(shape_type) => write!(f, "all {}s", shape_type.display_name()),
+            DespawnTarget::All => write!(f, "all objects"),
+        }
+    }
+}
+
+// Request to remove objects, shared by the editor and scripted deletion
+#[derive(Event)]
+pub struct DespawnEntityEvent {
+    pub target: DespawnTarget,
+}
+
+// Sent once for every object that enters the scene, however it got there:
+// spawns, batches, prefabs, duplicates, undo and trash restores
+#[derive(Event, Debug, Clone)]
+pub struct ObjectSpawnedEvent {
+    pub id: u32,
+    pub entity: Entity,
+    pub shape_type: ShapeType,
+    pub position: Vec3,
+    pub name: String,
+}
+
+impl From<&GameObject> for ObjectSpawnedEvent {
+    fn from(obj: &GameObject) -> Self {
+        Self {
+            id: obj.id,
+            entity: obj.entity,
+            shape_type: obj.shape_type,
+            position: obj.position,
+            name: obj.name.clone(),
+        }
+    }
+}
+
+// Sent once an object has left the GameObjectManager, whatever removed it
+#[derive(Event, Debug, Clone)]
+pub struct ObjectRemovedEvent {
+    pub id: u32,
+    pub entity: Entity,
+    pub shape_type: ShapeType,
+    pub name: String,
+    // Position as of the last sync, the entity may already be gone
+    pub last_position: Vec3,
+}
+
+impl From<&GameObject> for ObjectRemovedEvent {
+    fn from(obj: &GameObject) -> Self {
+        Self {
+            id: obj.id,
+            entity: obj.entity,
+            shape_type: obj.shape_type,
+            name: obj.name.clone(),
+            last_position: obj.position,
+        }
+    }
+}
+
+// Where Ctrl+D puts copies, relative to the original
+pub const DUPLICATE_OFFSET: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+
+// Copy an object under a fresh id, shifted by the offset
+#[derive(Event)]
+pub struct DuplicateObjectEvent {
+    pub source_id: u32,
+    pub offset: Vec3,
+}
+
+// Ctrl+D duplicates every unlocked selected object
+pub fn duplicate_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    mut duplicate_events: EventWriter<DuplicateObjectEvent>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !keyboard_input.just_pressed(KeyCode::KeyD)
+        || !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    for id in selection.ids(&game_manager) {
+        if game_manager
+            .get_object_by_id(id)
+            .is_some_and(|obj| !obj.locked)
+        {
+            duplicate_events.write(DuplicateObjectEvent {
+                source_id: id,
+                offset: DUPLICATE_OFFSET,
+            });
+        }
+    }
+}
+
+// Spawn the copies and select them. Each copy gets a material of its own, so
+// recoloring it leaves the original alone
+pub fn duplicate_object_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut duplicate_events: EventReader<DuplicateObjectEvent>,
+    mut selection: ResMut<Selection>,
+    transforms: Query<&Transform>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+) {
+    let mut copies = Vec::new();
+    for event in duplicate_events.read() {
+        let Some(source) = game_manager.get_object_by_id(event.source_id) else {
+            warn!(
+                "Cannot duplicate game object {}: not found",
+                event.source_id
+            );
+            notifications.error(format!(
+                "Cannot duplicate object {}: not found",
+                event.source_id
+            ));
+            continue;
+        };
+        let transform = transforms.get(source.entity).copied().unwrap_or_default();
+        let Some(mut snapshot) = game_manager.snapshot(source.id, transform) else {
+            continue;
+        };
+        // Same place in the hierarchy, but a new identity and none of the editor locks
+        let Some(id) = game_manager.ids().peek() else {
+            notifications.error(format!("Cannot duplicate: {}", ObjectError::IdsExhausted));
+            continue;
+        };
+        snapshot.id = id;
+        snapshot.name = format!("{} (copy)", snapshot.name);
+        snapshot.transform.translation += event.offset;
+        snapshot.locked = false;
+        snapshot.physics_locked = false;
+        snapshot.source = SpawnSource::Duplicate;
+
+        match restore_object(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut game_manager,
+            &snapshot,
+            time.elapsed_secs_f64(),
+        ) {
+            Ok(entity) => {
+                copies.push(entity);
+                notifications.info(format!("Duplicated as {}", snapshot.name));
+                history.push(EditorCommand::Spawn(snapshot), time.elapsed_secs_f64());
+            }
+            Err(err) => {
+                warn!("Cannot duplicate object {}: {}", event.source_id, err);
+                notifications.error(format!("Cannot duplicate: {}", err));
+            }
+        }
+    }
+    if !copies.is_empty() {
+        selection.select_range(copies);
+    }
+}
+
+// Add or remove one tag on an object
+#[derive(Event)]
+pub struct TagObjectEvent {
+    pub id: u32,
+    pub tag: String,
+    pub add: bool,
+}
+
+pub fn tag_object_system(
+    mut tag_events: EventReader<TagObjectEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in tag_events.read() {
+        let result = if event.add {
+            game_manager.add_tag(event.id, &event.tag)
+        } else {
+            game_manager.remove_tag(event.id, &event.tag)
+        };
+        if let Err(err) = result {
+            warn!("Tag edit failed: {}", err);
+            notifications.error(format!("Tag edit failed: {}", err));
+        }
+    }
+}
+
+// Set or, with None, remove a metadata entry
+#[derive(Event)]
+pub struct MetaEditEvent {
+    pub id: u32,
+    pub key: String,
+    pub value: Option<MetaValue>,
+}
+
+pub fn meta_edit_system(
+    mut meta_events: EventReader<MetaEditEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in meta_events.read() {
+        let result = match &event.value {
+            Some(value) => game_manager
+                .set_meta(event.id, &event.key, value.clone())
+                .map(|_| ()),
+            None => game_manager.remove_meta(event.id, &event.key).map(|_| ()),
+        };
+        if let Err(err) = result {
+            warn!("Metadata edit failed: {}", err);
+            notifications.error(format!("Metadata edit failed: {}", err));
+        }
+    }
+}
+
+// Move an object under another one, or back to the root with None
+#[derive(Event)]
+pub struct ReparentEvent {
+    pub id: u32,
+    pub parent: Option<u32>,
+}
+
+// Spawned objects get a ".NNN" suffix when their name is taken
+#[derive(Resource)]
+pub struct NamingSettings {
+    pub unique_names: bool,
+}
+
+impl Default for NamingSettings {
+    fn default() -> Self {
+        Self { unique_names: true }
+    }
+}
+
+// What happens to a spawn that would go over SpawnLimits::max_objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    #[default]
+    RejectNew,
+    EvictOldest,
+    EvictOldestOfSameType,
+}
+
+impl EvictionPolicy {
+    pub fn all() -> [EvictionPolicy; 3] {
+        [
+            EvictionPolicy::RejectNew,
+            EvictionPolicy::EvictOldest,
+            EvictionPolicy::EvictOldestOfSameType,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EvictionPolicy::RejectNew => "Reject new",
+            EvictionPolicy::EvictOldest => "Evict oldest",
+            EvictionPolicy::EvictOldestOfSameType => "Evict oldest of same type",
+        }
+    }
+}
+
+// Cap on the number of objects in the scene, None for no cap
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SpawnLimits {
+    pub max_objects: Option<usize>,
+    pub policy: EvictionPolicy,
+}
+
+impl SpawnLimits {
+    // Objects to evict so count more objects of shape_type fit, oldest first.
+    // Ids in evicting are already on their way out. All or nothing: an error
+    // means none of the new objects should be spawned
+    pub fn make_room(
+        &self,
+        game_manager: &GameObjectManager,
+        shape_type: ShapeType,
+        count: usize,
+        evicting: &HashSet<u32>,
+    ) -> Result<Vec<u32>, ObjectError> {
+        let Some(max) = self.max_objects else {
+            return Ok(Vec::new());
+        };
+        let room = max.saturating_sub(game_manager.len().saturating_sub(evicting.len()));
+        if count <= room {
+            return Ok(Vec::new());
+        }
+        if self.policy == EvictionPolicy::RejectNew || count > max {
+            return Err(ObjectError::ObjectLimit(max));
+        }
+
+        // Locked objects are never evicted
+        let mut candidates: Vec<&GameObject> = game_manager
+            .iter()
+            .filter(|obj| !obj.locked && !evicting.contains(&obj.id))
+            .filter(|obj| {
+                self.policy != EvictionPolicy::EvictOldestOfSameType || obj.shape_type == shape_type
+            })
+            .collect();
+        let needed = count - room;
+        if candidates.len() < needed {
+            return Err(ObjectError::ObjectLimit(max));
+        }
+        candidates.sort_by(|a, b| a.created_at.total_cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(candidates[..needed].iter().map(|obj| obj.id).collect())
+    }
+}
+
+// How deleting a parent treats its children, and how the hierarchy is sorted
+#[derive(Resource)]
+pub struct HierarchySettings {
+    // Delete the whole subtree, otherwise the children become root objects
+    pub delete_children: bool,
+    // Also used by the L key listing
+    pub sort: SortKey,
+    pub ascending: bool,
+    pub show_source_icons: bool,
+    pub show_ages: bool,
+    // Used by "Delete older than" in the hierarchy and the command palette
+    pub max_age_secs: f64,
+}
+
+impl Default for HierarchySettings {
+    fn default() -> Self {
+        Self {
+            delete_children: false,
+            sort: SortKey::Id,
+            ascending: true,
+            show_source_icons: false,
+            show_ages: false,
+            max_age_secs: 60.0,
+        }
+    }
+}
+
+pub fn reparent_object_system(
+    mut commands: Commands,
+    mut reparent_events: EventReader<ReparentEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut history: ResMut<EditorHistory>,
+    mut notifications: ResMut<EditorNotifications>,
+    time: Res<Time>,
+) {
+    for event in reparent_events.read() {
+        match reparent_object(&mut commands, &mut game_manager, event.id, event.parent) {
+            Ok(before) if before != event.parent => history.push(
+                EditorCommand::Reparent {
+                    id: event.id,
+                    before,
+                    after: event.parent,
+                },
+                time.elapsed_secs_f64(),
+            ),
+            Ok(_) => {}
+            Err(err) => {
+                warn!("Reparent failed: {}", err);
+                notifications.error(format!("Reparent failed: {}", err));
+            }
+        }
+    }
+}
+
+// Where deleted objects go: with or without their children, into the trash,
+// or parked in the pool for the next spawn
+#[derive(SystemParam)]
+pub struct DeleteTargets<'w> {
+    pub settings: Res<'w, HierarchySettings>,
+    pub trash: ResMut<'w, Trash>,
+    pub pool: ResMut<'w, EntityPool>,
+}
+
+// Despawn requested objects, the manager is updated by cleanup_destroyed_entities_system.
+// Each event becomes one undo step however many objects it takes
+pub fn despawn_entity_system(
+    mut commands: Commands,
+    mut despawn_events: EventReader<DespawnEntityEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    transforms: Query<&Transform>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    DeleteTargets {
+        settings,
+        mut trash,
+        mut pool,
+    }: DeleteTargets,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
 ) {
-    for (entity, transform) in query.iter() {
-        if let Some(obj) = game_manager
-            .objects
-            .iter_mut()
-            .find(|obj| obj.entity == entity)
+    // Children already taken down with a parent deleted this frame
+    let mut deleted = HashSet::new();
+    for event in despawn_events.read() {
+        let single = event.target.is_single();
+        let expired = matches!(
+            event.target,
+            DespawnTarget::Expired(_) | DespawnTarget::Evicted(_)
+        );
+        let trashing = matches!(event.target, DespawnTarget::Trash(_));
+        let Some(ids) = event.target.resolve(&game_manager) else {
+            warn!("Cannot despawn {}: not found", event.target);
+            notifications.error(format!("Cannot delete {}: not found", event.target));
+            continue;
+        };
+
+        let mut snapshots: Vec<ObjectSnapshot> = Vec::new();
+        let mut kept_locked = 0;
+        for id in ids {
+            if deleted.contains(&id) {
+                continue;
+            }
+            let Some(obj) = game_manager.get_object_by_id(id) else {
+                continue;
+            };
+            if obj.locked {
+                if single {
+                    notifications.warn(format!("{} is locked, unlock it to delete", obj.name));
+                }
+                kept_locked += 1;
+                continue;
+            }
+            let (entity, name) = (obj.entity, obj.name.clone());
+
+            let descendants = if settings.delete_children {
+                game_manager.descendants(id)
+            } else {
+                Vec::new()
+            };
+            if let Some(locked) = descendants
+                .iter()
+                .filter_map(|&id| game_manager.get_object_by_id(id))
+                .find(|obj| obj.locked)
+            {
+                notifications.warn(format!(
+                    "{} has a locked child ({}), unlock it to delete",
+                    name, locked.name
+                ));
+                continue;
+            }
+
+            // Parent first, so undo can put each child back under it
+            let removed: Vec<ObjectSnapshot> = std::iter::once(id)
+                .chain(descendants.iter().copied())
+                .filter_map(|id| {
+                    let obj = game_manager.get_object_by_id(id)?;
+                    let transform = transforms.get(obj.entity).copied().unwrap_or_default();
+                    game_manager.snapshot(id, transform)
+                })
+                .collect();
+            if trashing {
+                // One entry per object so each comes back with its children
+                if let Some(evicted) = trash.push(removed.clone(), time.elapsed_secs_f64()) {
+                    info!("Trash full, dropped {} for good", evicted.name());
+                }
+            }
+            snapshots.extend(removed);
+            // Children left behind as root objects, so the object goes alone
+            let goes_alone = descendants.is_empty();
+            deleted.extend(std::iter::once(id).chain(descendants));
+            if !settings.delete_children {
+                orphan_children(&mut commands, &mut game_manager, id);
+            }
+            let shape_type = game_manager.get_object_by_id(id).map(|obj| obj.shape_type);
+            if goes_alone
+                && let Some(shape_type) = shape_type
+                && pool.has_room(shape_type)
+                && let Some(obj) = game_manager.remove_object(entity)
+            {
+                // Parked instead of despawned, so it's taken out of the manager here
+                removed_events.write(ObjectRemovedEvent::from(&obj));
+                pool.park(&mut commands, entity, shape_type, obj.dimensions);
+                continue;
+            }
+            // Despawning is recursive, so this also takes any remaining children
+            commands.entity(entity).despawn();
+        }
+
+        if !single && kept_locked > 0 {
+            notifications.warn(format!("Kept {} locked objects", kept_locked));
+        }
+        let Some(first) = snapshots.first() else {
+            continue;
+        };
+        if expired {
+            continue;
+        }
+        if trashing {
+            notifications.info(match snapshots.len() {
+                1 => format!("Moved {} to the trash", first.name),
+                n => format!("Moved {} objects to the trash", n),
+            });
+            continue;
+        }
+        notifications.info(match (single, snapshots.len()) {
+            (_, 1) => format!("Deleted {}", first.name),
+            (true, n) => format!("Deleted {} and {} children", first.name, n - 1),
+            (false, n) => format!("Deleted {} objects", n),
+        });
+        let command = if snapshots.len() == 1 {
+            EditorCommand::Delete(snapshots.remove(0))
+        } else {
+            EditorCommand::DeleteGroup(snapshots)
+        };
+        history.push(command, time.elapsed_secs_f64());
+    }
+}
+
+// Remove every managed object at once, the ground and camera aren't managed so they stay
+#[derive(Event)]
+pub struct ClearSceneEvent;
+
+pub fn clear_scene_system(
+    mut commands: Commands,
+    mut clear_events: EventReader<ClearSceneEvent>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut selection: ResMut<Selection>,
+    transforms: Query<&Transform>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
+) {
+    if clear_events.read().count() == 0 || game_manager.is_empty() {
+        return;
+    }
+
+    // Snapshot everything first so one undo brings the whole scene back
+    let snapshots: Vec<ObjectSnapshot> = game_manager
+        .iter()
+        .filter_map(|obj| {
+            let transform = transforms.get(obj.entity).copied().unwrap_or_default();
+            game_manager.snapshot(obj.id, transform)
+        })
+        .collect();
+
+    for obj in game_manager.clear_all() {
+        // Children go with their parent's despawn
+        commands.entity(obj.entity).try_despawn();
+        removed_events.write(ObjectRemovedEvent::from(&obj));
+    }
+    selection.clear();
+
+    info!("Cleared scene ({} objects)", snapshots.len());
+    notifications.info(format!("Cleared {} objects", snapshots.len()));
+    history.push(
+        EditorCommand::ClearScene(snapshots),
+        time.elapsed_secs_f64(),
+    );
+}
+
+// Drop despawned entities from the manager. Runs after the edit systems so
+// their despawns are reflected the same frame
+pub fn cleanup_destroyed_entities_system(
+    mut removed: RemovedComponents<GameObjectId>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
+) {
+    for entity in removed.read() {
+        if let Some(obj) = game_manager.remove_object(entity) {
+            removed_events.write(ObjectRemovedEvent::from(&obj));
+        }
+    }
+}
+
+// Every spawn path finishes by inserting GameObjectId, so watching for it
+// catches them all without each one sending the event itself
+pub fn announce_spawned_objects_system(
+    added: Query<Entity, Added<GameObjectId>>,
+    game_manager: Res<GameObjectManager>,
+    mut spawned_events: EventWriter<ObjectSpawnedEvent>,
+) {
+    spawned_events.write_batch(
+        added
+            .iter()
+            .filter_map(|entity| game_manager.get_object_by_entity(entity))
+            .map(ObjectSpawnedEvent::from),
+    );
+}
+
+// How often validate_manager_system checks tracked entities
+pub const MANAGER_VALIDATION_SECS: f32 = 1.0;
+
+// Safety net for cleanup_destroyed_entities_system. Entities despawned before
+// their GameObjectId was inserted, or taken down with a parent, never report a
+// removed component, so every tracked entity is checked against the world
+pub fn validate_manager_system(
+    mut game_manager: ResMut<GameObjectManager>,
+    live: Query<(), With<GameObjectId>>,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
+) {
+    let evicted = game_manager.evict_stale(|entity| live.contains(entity));
+    for obj in &evicted {
+        warn!(
+            "Evicted {} (ID: {}): entity {} was despawned without the manager noticing",
+            obj.name, obj.id, obj.entity
+        );
+        removed_events.write(ObjectRemovedEvent::from(obj));
+    }
+}
+
+// UI system for shape selection
+pub fn shape_selection_ui(
+    mut selected_shape: ResMut<SelectedShape>,
+    ViewportInput {
+        keyboard_input,
+        egui_input,
+        bindings,
+        ..
+    }: ViewportInput,
+    objects: PlacedObjects,
+    settings: Res<HierarchySettings>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+    time: Res<Time>,
+) {
+    // Cycle through shapes (Tab by default), unless egui is using the key to move focus
+    if bindings.just_pressed(InputAction::CycleShape, &keyboard_input)
+        && !egui_input.wants_keyboard_input()
+    {
+        let shapes = ShapeType::all();
+        let current_index = shapes
+            .iter()
+            .position(|&s| s == selected_shape.shape_type)
+            .unwrap_or(0);
+        let next_index = (current_index + 1) % shapes.len();
+        selected_shape.shape_type = shapes[next_index];
+        info!(
+            "Selected shape: {}",
+            selected_shape.shape_type.display_name()
+        );
+    }
+
+    // Debug: Print all objects ('L' by default)
+    if bindings.just_pressed(InputAction::ListObjects, &keyboard_input) {
+        // Listed in the hierarchy's order, distances from the current camera
+        let key = match settings.sort {
+            SortKey::DistanceToCamera(_) => SortKey::DistanceToCamera(
+                camera
+                    .single()
+                    .map_or(Vec3::ZERO, |camera| camera.translation()),
+            ),
+            key => key,
+        };
+        info!("Current game objects (by {}):", key.label());
+        for obj in objects.game_manager.sorted_objects(key, settings.ascending) {
+            info!("  {}", obj.summary());
+        }
+        info!("Total: {}", objects.stats(time.elapsed_secs_f64()));
+    }
+}
+
+// The manager and where its objects are in the world, for bounds and stats
+// that include the scale left on their transforms
+#[derive(SystemParam)]
+pub struct PlacedObjects<'w, 's> {
+    pub game_manager: Res<'w, GameObjectManager>,
+    pub transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+impl PlacedObjects<'_, '_> {
+    pub fn world_transform(&self, entity: Entity) -> Option<Transform> {
+        self.transforms
+            .get(entity)
+            .ok()
+            .map(GlobalTransform::compute_transform)
+    }
+
+    pub fn scene_bounds(&self) -> Aabb3d {
+        self.game_manager
+            .scene_bounds(|entity| self.world_transform(entity))
+    }
+
+    pub fn stats(&self, now: f64) -> ObjectStats {
+        self.game_manager
+            .stats(now, |entity| self.world_transform(entity))
+    }
+}
+
+// Movement smaller than this isn't written back to the manager
+pub const POSITION_EPSILON: f32 = 1e-4;
+// Same for turning, in radians
+const ROTATION_EPSILON: f32 = 1e-4;
+
+// System to update object positions and rotations (useful for tracking moving objects). The
+// manager holds world space, so a child's transform is composed with its
+// parents', and moving a parent moves its children's entries too. One map
+// lookup per changed transform, and the manager is only touched, and so only
+// marked changed, when something actually moved
+pub fn update_object_positions_system(
+    mut game_manager: ResMut<GameObjectManager>,
+    changed: Query<Entity, (With<GameObjectId>, Changed<Transform>)>,
+    transforms: Query<(&Transform, Option<&ChildOf>)>,
+) {
+    let mut moved = Vec::new();
+    for entity in &changed {
+        let Some(obj) = game_manager.get_object_by_entity(entity) else {
+            continue;
+        };
+        moved.push(entity);
+        if !obj.children.is_empty() {
+            let id = obj.id;
+            moved.extend(
+                game_manager
+                    .descendants(id)
+                    .into_iter()
+                    .filter_map(|child| Some(game_manager.get_object_by_id(child)?.entity)),
+            );
+        }
+    }
+    if moved.len() > 1 {
+        moved.sort_unstable();
+        moved.dedup();
+    }
+
+    for entity in moved {
+        let Some((translation, rotation)) = world_placement(&transforms, entity) else {
+            continue;
+        };
+        let Some(obj) = game_manager.get_object_by_entity_mut(entity) else {
+            continue;
+        };
+        if obj.position.distance_squared(translation) <= POSITION_EPSILON * POSITION_EPSILON
+            && obj.rotation.angle_between(rotation) <= ROTATION_EPSILON
         {
-            obj.position = transform.translation;
+            continue;
+        }
+        obj.rotation = rotation;
+        game_manager.set_position(entity, translation);
+    }
+}
+
+// World translation and rotation of an entity from its own and its parents'
+// transforms, without waiting for GlobalTransform to be propagated
+fn world_placement(
+    transforms: &Query<(&Transform, Option<&ChildOf>)>,
+    entity: Entity,
+) -> Option<(Vec3, Quat)> {
+    let (transform, mut parent) = transforms.get(entity).ok()?;
+    let mut global = GlobalTransform::from(*transform);
+    while let Some(&ChildOf(entity)) = parent {
+        let Ok((transform, grandparent)) = transforms.get(entity) else {
+            break;
+        };
+        global = GlobalTransform::from(*transform) * global;
+        parent = grandparent;
+    }
+    let (_, rotation, translation) = global.to_scale_rotation_translation();
+    Some((translation, rotation))
+}
+
+// Objects whose rapier velocity changed since the last run
+type ChangedVelocities<'w, 's> =
+    Query<'w, 's, (Entity, &'static Velocity), (With<GameObjectId>, Changed<Velocity>)>;
+
+// Copy rapier velocities onto the manager's objects. Bodies that lose their
+// Velocity, or never had one, read as standing still
+pub fn update_object_velocities_system(
+    mut game_manager: ResMut<GameObjectManager>,
+    query: ChangedVelocities,
+    mut removed: RemovedComponents<Velocity>,
+) {
+    for entity in removed.read() {
+        if let Some(obj) = game_manager.get_object_by_entity_mut(entity) {
+            obj.linvel = Vec3::ZERO;
+            obj.angvel = Vec3::ZERO;
+        }
+    }
+    for (entity, velocity) in query.iter() {
+        let changed = game_manager
+            .get_object_by_entity(entity)
+            .is_some_and(|obj| obj.linvel != velocity.linvel || obj.angvel != velocity.angvel);
+        if changed && let Some(obj) = game_manager.get_object_by_entity_mut(entity) {
+            obj.linvel = velocity.linvel;
+            obj.angvel = velocity.angvel;
+        }
+    }
+}
+
+// Wake a rigid body so rapier picks up changes made while it was asleep
+pub fn wake_body(commands: &mut Commands, entity: Entity) {
+    commands.entity(entity).insert(Sleeping::default());
+}
+
+// Replace an object's rigid body. A body made Fixed drops its velocity so it
+// doesn't fly off with it when switched back to Dynamic
+pub fn set_rigid_body(commands: &mut Commands, entity: Entity, body: RigidBody) {
+    if body == RigidBody::Fixed {
+        commands.entity(entity).insert((body, Velocity::zero()));
+    } else {
+        commands.entity(entity).insert(body);
+    }
+    wake_body(commands, entity);
+}
+
+// Game objects, the manager that tracks them and the events that change them
+pub struct ObjectsPlugin;
+
+impl Plugin for ObjectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GameObjectId>()
+            .add_event::<SpawnEntityEvent>()
+            .add_event::<DespawnEntityEvent>()
+            .add_event::<ObjectSpawnedEvent>()
+            .add_event::<ObjectRemovedEvent>()
+            .add_event::<RenameObjectEvent>()
+            .add_event::<ReparentEvent>()
+            .add_event::<TagObjectEvent>()
+            .add_event::<MetaEditEvent>()
+            .add_event::<DuplicateObjectEvent>()
+            .add_event::<ClearSceneEvent>()
+            .add_event::<LockObjectEvent>()
+            .add_event::<SetVisibilityEvent>()
+            .init_resource::<GameObjectManager>()
+            .init_resource::<SelectedShape>()
+            .init_resource::<ShapeParams>()
+            .init_resource::<SpawnPhysics>()
+            .init_resource::<HierarchySettings>()
+            .init_resource::<NamingSettings>()
+            .init_resource::<SpawnLimits>()
+            .init_resource::<EditorRng>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+    // Register an object named as given, on a made up entity numbered after
+    // its id so removals never lead to two objects sharing one
+    fn add_named(game_manager: &mut GameObjectManager, name: &str) -> u32 {
+        let entity = Entity::from_raw(game_manager.ids().peek().unwrap() + 1);
+        game_manager
+            .add_object(
+                entity,
+                ShapeType::Cube,
+                Vec3::ZERO,
+                Some(name.to_string()),
+                false,
+                0.0,
+            )
+            .unwrap()
+    }
+
+    fn add_at(
+        game_manager: &mut GameObjectManager,
+        shape_type: ShapeType,
+        position: Vec3,
+        timestamp: f64,
+    ) -> u32 {
+        let entity = Entity::from_raw(game_manager.ids().peek().unwrap() + 1);
+        game_manager
+            .add_object(entity, shape_type, position, None, false, timestamp)
+            .unwrap()
+    }
+
+    fn manager_with_names(names: &[&str]) -> GameObjectManager {
+        let mut game_manager = GameObjectManager::default();
+        for name in names {
+            add_named(&mut game_manager, name);
+        }
+        game_manager
+    }
+
+    #[test]
+    fn unique_name_keeps_free_names() {
+        let game_manager = manager_with_names(&["alice"]);
+        assert_eq!(game_manager.unique_name("bob"), "bob");
+        assert_eq!(game_manager.unique_name("bob.004"), "bob.004");
+    }
+
+    #[test]
+    fn unique_name_takes_the_lowest_free_number() {
+        let game_manager = manager_with_names(&["bob"]);
+        assert_eq!(game_manager.unique_name("bob"), "bob.001");
+
+        let game_manager = manager_with_names(&["bob", "bob.001", "bob.003"]);
+        assert_eq!(game_manager.unique_name("bob"), "bob.002");
+    }
+
+    #[test]
+    fn unique_name_numbers_from_the_base_of_suffixed_names() {
+        let game_manager = manager_with_names(&["bob", "bob.001"]);
+        assert_eq!(game_manager.unique_name("bob.001"), "bob.002");
+        // Not a suffix, so numbered on the whole name
+        let game_manager = manager_with_names(&["v1.5"]);
+        assert_eq!(game_manager.unique_name("v1.5"), "v1.5.001");
+    }
+
+    #[test]
+    fn library_names_count_up_from_two() {
+        let taken = ["Mesh", "Mesh 2", "Mesh 4"];
+        let taken = |name: &str| taken.contains(&name);
+        assert_eq!(unique_name("Crate", taken), "Crate");
+        assert_eq!(unique_name("Mesh", taken), "Mesh 3");
+    }
+
+    #[test]
+    fn unique_names_hands_out_distinct_names() {
+        let game_manager = manager_with_names(&["bob", "bob.002"]);
+        assert_eq!(
+            game_manager.unique_names("bob", 3),
+            ["bob.001", "bob.003", "bob.004"]
+        );
+
+        let game_manager = manager_with_names(&[]);
+        assert_eq!(
+            game_manager.unique_names("bob.002", 3),
+            ["bob.002", "bob.001", "bob.003"]
+        );
+        assert!(game_manager.unique_names("bob", 0).is_empty());
+    }
+
+    #[test]
+    fn add_object_numbers_names_when_asked() {
+        let mut game_manager = GameObjectManager::default();
+        for index in 0..3 {
+            game_manager
+                .add_object(
+                    Entity::from_raw(index + 1),
+                    ShapeType::Ball,
+                    Vec3::ZERO,
+                    Some("bob".to_string()),
+                    true,
+                    0.0,
+                )
+                .unwrap();
+        }
+        let names: Vec<&str> = game_manager.iter().map(|obj| obj.name.as_str()).collect();
+        assert_eq!(names, ["bob", "bob.001", "bob.002"]);
+    }
+
+    #[test]
+    fn list_objects_is_in_id_order_whatever_the_sort() {
+        let mut game_manager = manager_with_names(&["Cube 10", "Cube 2"]);
+        game_manager.add_tag(0, "Heavy").unwrap();
+        assert_eq!(
+            game_manager.list_objects(),
+            [
+                "Cube 10 (ID: 0, Type: Cube, Tags: heavy)",
+                "Cube 2 (ID: 1, Type: Cube)"
+            ]
+        );
+        let by_name: Vec<u32> = game_manager
+            .sorted_objects(SortKey::Name, true)
+            .iter()
+            .map(|obj| obj.id)
+            .collect();
+        assert_eq!(by_name, [1, 0]);
+    }
+
+    #[test]
+    fn stats_of_an_empty_manager() {
+        let stats = GameObjectManager::default().stats(30.0, |_| None);
+        assert_eq!(stats.total, 0);
+        assert!(stats.counts_by_type.is_empty());
+        assert_eq!(stats.oldest_created_at, None);
+        assert_eq!(stats.newest_created_at, None);
+        assert_eq!(stats.spawn_rate, 0.0);
+        assert!(stats.bounds.is_none());
+    }
+
+    #[test]
+    fn stats_of_a_single_object() {
+        let mut game_manager = GameObjectManager::default();
+        let position = Vec3::new(1.0, 2.0, -3.0);
+        add_at(&mut game_manager, ShapeType::Ball, position, 5.0);
+
+        let stats = game_manager.stats(5.0, |_| None);
+        assert_eq!(stats.total, 1);
+        assert_eq!(stats.counts_by_type.get(&ShapeType::Ball), Some(&1));
+        assert_eq!(stats.oldest_created_at, Some(5.0));
+        assert_eq!(stats.newest_created_at, Some(5.0));
+        // One spawn in the five seconds since startup
+        assert!((stats.spawn_rate - 0.2).abs() < 1e-9);
+        let bounds = stats.bounds.unwrap();
+        assert!(Vec3::from(bounds.min).cmple(position).all());
+        assert!(Vec3::from(bounds.max).cmpge(position).all());
+    }
+
+    #[test]
+    fn spawn_rate_only_counts_the_window() {
+        let mut game_manager = GameObjectManager::default();
+        assert_eq!(game_manager.spawn_rate(0.0), 0.0);
+
+        add_at(&mut game_manager, ShapeType::Cube, Vec3::ZERO, 10.0);
+        // Right on the window's edge, still counted
+        add_at(&mut game_manager, ShapeType::Cube, Vec3::ZERO, 40.0);
+        for _ in 0..3 {
+            add_at(&mut game_manager, ShapeType::Cube, Vec3::ZERO, 90.0);
+        }
+        let window = SPAWN_RATE_WINDOW_SECS;
+        assert!((game_manager.spawn_rate(100.0) - 4.0 / window).abs() < 1e-9);
+        // Long after the last spawn
+        assert_eq!(game_manager.spawn_rate(200.0), 0.0);
+    }
+
+    #[test]
+    fn spawn_history_is_capped() {
+        let mut game_manager = GameObjectManager::default();
+        for index in 0..SPAWN_HISTORY_CAPACITY + 10 {
+            add_at(
+                &mut game_manager,
+                ShapeType::Cube,
+                Vec3::ZERO,
+                index as f64 * 0.1,
+            );
+        }
+        assert_eq!(game_manager.spawn_times.len(), SPAWN_HISTORY_CAPACITY);
+        let now = (SPAWN_HISTORY_CAPACITY + 10) as f64 * 0.1;
+        assert!((game_manager.spawn_rate(now) - SPAWN_HISTORY_CAPACITY as f64 / now).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ids_wrap_past_u32_max_skipping_live_ones() {
+        let mut ids = IdAllocator::default();
+        ids.reserve(0).unwrap();
+        ids.reserve(1).unwrap();
+        ids.reserve(u32::MAX - 1).unwrap();
+        assert_eq!(ids.allocate().unwrap(), u32::MAX);
+        // 0 and 1 are still in use
+        assert_eq!(ids.allocate().unwrap(), 2);
+        assert_eq!(ids.allocate().unwrap(), 3);
+    }
+
+    #[test]
+    fn wrapping_reuses_ids_freed_since() {
+        let mut ids = IdAllocator::default();
+        ids.reserve(0).unwrap();
+        ids.reserve(u32::MAX).unwrap();
+        ids.release(0);
+        assert_eq!(ids.peek(), Some(0));
+        assert_eq!(ids.allocate().unwrap(), 0);
+        assert_eq!(ids.allocate().unwrap(), 1);
+    }
+
+    #[test]
+    fn reserving_a_live_id_is_an_error() {
+        let mut ids = IdAllocator::default();
+        let id = ids.allocate().unwrap();
+        assert_eq!(ids.reserve(id), Err(ObjectError::DuplicateId(id)));
+        // Reserving behind the counter doesn't rewind it
+        ids.reserve(10).unwrap();
+        ids.reserve(4).unwrap();
+        assert_eq!(ids.allocate().unwrap(), 11);
+
+        let mut game_manager = GameObjectManager::default();
+        let id = add_named(&mut game_manager, "bob");
+        let snapshot = game_manager.snapshot(id, Transform::default()).unwrap();
+        assert_eq!(
+            game_manager.insert_object(Entity::from_raw(99), &snapshot, 0.0),
+            Err(ObjectError::DuplicateId(id))
+        );
+        assert_eq!(game_manager.len(), 1);
+    }
+
+    #[test]
+    fn freed_ids_are_only_recycled_when_enabled() {
+        let mut ids = IdAllocator::default();
+        for _ in 0..4 {
+            ids.allocate().unwrap();
+        }
+        ids.release(2);
+        assert_eq!(ids.allocate().unwrap(), 4);
+
+        ids.set_recycling(true);
+        ids.release(3);
+        ids.release(1);
+        assert_eq!(ids.allocate().unwrap(), 1);
+        assert_eq!(ids.allocate().unwrap(), 3);
+        assert_eq!(ids.allocate().unwrap(), 5);
+
+        // Turning it off forgets what was waiting
+        ids.release(0);
+        ids.set_recycling(false);
+        assert_eq!(ids.allocate().unwrap(), 6);
+        assert!(!ids.is_live(0));
+    }
+
+    #[test]
+    fn reconcile_continues_after_the_highest_loaded_id() {
+        let mut ids = IdAllocator::default();
+        for _ in 0..20 {
+            ids.allocate().unwrap();
+        }
+        ids.reconcile([7, 3]);
+        assert!(ids.is_live(3) && ids.is_live(7));
+        assert!(!ids.is_live(12));
+        assert_eq!(ids.allocate().unwrap(), 8);
+
+        ids.reconcile([u32::MAX]);
+        assert_eq!(ids.allocate().unwrap(), 0);
+
+        ids.reset();
+        assert_eq!(ids.peek(), Some(0));
+    }
+
+    #[test]
+    fn advance_to_never_goes_back() {
+        let mut ids = IdAllocator::default();
+        ids.advance_to(10);
+        ids.advance_to(5);
+        assert_eq!(ids.allocate().unwrap(), 10);
+    }
+
+    #[test]
+    fn nearest_object_in_an_empty_manager_is_none() {
+        let game_manager = GameObjectManager::default();
+        assert!(game_manager.nearest_object(Vec3::ZERO, None).is_none());
+        assert!(
+            game_manager
+                .nearest_object(Vec3::ZERO, Some(ShapeType::Cube))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn nearest_object_ties_go_to_the_lowest_id() {
+        let mut game_manager = GameObjectManager::default();
+        let ball = add_at(&mut game_manager, ShapeType::Ball, Vec3::X * 10.0, 0.0);
+        let left = add_at(&mut game_manager, ShapeType::Cube, -Vec3::X, 0.0);
+        add_at(&mut game_manager, ShapeType::Cube, Vec3::X, 0.0);
+        // Same position as the first ball, so the same distance from anywhere
+        add_at(&mut game_manager, ShapeType::Ball, Vec3::X * 10.0, 0.0);
+
+        let nearest =
+            |point: Vec3, filter| game_manager.nearest_object(point, filter).map(|obj| obj.id);
+        assert_eq!(nearest(Vec3::ZERO, None), Some(left));
+        assert_eq!(nearest(Vec3::X * 12.0, Some(ShapeType::Ball)), Some(ball));
+        assert_eq!(nearest(Vec3::X * 12.0, None), Some(ball));
+    }
+
+    // Random scenes and points, with and without a shape filter, against a
+    // scan over every object
+    #[test]
+    fn nearest_object_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(59);
+        let shapes = [ShapeType::Cube, ShapeType::Ball, ShapeType::Cylinder];
+        let mut game_manager = GameObjectManager::default();
+        for step in 0..300 {
+            let position = Vec3::new(
+                rng.gen_range(-40..=40) as f32 * 0.5,
+                rng.gen_range(-4..=4) as f32 * 0.5,
+                rng.gen_range(-40..=40) as f32 * 0.5,
+            );
+            add_at(
+                &mut game_manager,
+                shapes[step % shapes.len()],
+                position,
+                0.0,
+            );
+            if step % 7 == 6 {
+                let entity = game_manager
+                    .iter()
+                    .nth(step % game_manager.len())
+                    .unwrap()
+                    .entity();
+                game_manager.remove_object(entity);
+            }
+
+            let point = Vec3::new(
+                rng.gen_range(-30.0..30.0),
+                rng.gen_range(-3.0..3.0),
+                rng.gen_range(-30.0..30.0),
+            );
+            for filter in [None, Some(ShapeType::Ball), Some(ShapeType::Capsule)] {
+                let expected = game_manager
+                    .iter()
+                    .filter(|obj| filter.is_none_or(|shape_type| obj.shape_type == shape_type))
+                    .min_by(|a, b| {
+                        a.position
+                            .distance_squared(point)
+                            .total_cmp(&b.position.distance_squared(point))
+                            .then(a.id.cmp(&b.id))
+                    })
+                    .map(|obj| obj.id);
+                assert_eq!(
+                    game_manager.nearest_object(point, filter).map(|obj| obj.id),
+                    expected,
+                    "nearest to {} with {:?} at step {}",
+                    point,
+                    filter,
+                    step
+                );
+            }
+        }
+    }
+
+    fn assert_aabb(aabb: Aabb3d, min: Vec3, max: Vec3) {
+        assert!(
+            Vec3::from(aabb.min).abs_diff_eq(min, 1e-5)
+                && Vec3::from(aabb.max).abs_diff_eq(max, 1e-5),
+            "got {:?} to {:?}, wanted {} to {}",
+            aabb.min,
+            aabb.max,
+            min,
+            max
+        );
+    }
+
+    #[test]
+    fn rotated_cuboid_bounds() {
+        let dims = ShapeDimensions {
+            half_extents: Vec3::new(1.0, 0.5, 0.25),
+            ..default()
+        };
+        let at = Vec3::new(2.0, 0.0, -1.0);
+        let upright = ShapeType::Cube.aabb_with(&dims, Isometry3d::from_translation(at));
+        assert_aabb(upright, at - dims.half_extents, at + dims.half_extents);
+
+        // A quarter turn about z swaps the x and y extents
+        let quarter = Isometry3d::new(at, Quat::from_rotation_z(FRAC_PI_2));
+        let extents = Vec3::new(0.5, 1.0, 0.25);
+        assert_aabb(
+            ShapeType::Cube.aabb_with(&dims, quarter),
+            at - extents,
+            at + extents,
+        );
+
+        // An eighth turn about y spreads a unit cube to its diagonal
+        let cube = ShapeType::Cube.default_dimensions();
+        let eighth = Isometry3d::from_rotation(Quat::from_rotation_y(FRAC_PI_4));
+        let extents = Vec3::new(FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2);
+        assert_aabb(ShapeType::Cube.aabb_with(&cube, eighth), -extents, extents);
+    }
+
+    #[test]
+    fn rotated_capsule_bounds() {
+        // Radius 0.3, 2.6 tall, so the caps' centres are 1.0 from the middle
+        let dims = ShapeType::Capsule.default_dimensions();
+        let upright = ShapeType::Capsule.aabb_with(&dims, Isometry3d::IDENTITY);
+        let extents = Vec3::new(0.3, 1.3, 0.3);
+        assert_aabb(upright, -extents, extents);
+
+        let lying = Isometry3d::from_rotation(Quat::from_rotation_x(FRAC_PI_2));
+        let extents = Vec3::new(0.3, 0.3, 1.3);
+        assert_aabb(
+            ShapeType::Capsule.aabb_with(&dims, lying),
+            -extents,
+            extents,
+        );
+
+        let tilted = Isometry3d::from_rotation(Quat::from_rotation_z(FRAC_PI_4));
+        let reach = FRAC_1_SQRT_2 + 0.3;
+        let extents = Vec3::new(reach, reach, 0.3);
+        assert_aabb(
+            ShapeType::Capsule.aabb_with(&dims, tilted),
+            -extents,
+            extents,
+        );
+    }
+
+    #[test]
+    fn object_aabb_follows_its_rotation() {
+        let mut game_manager = GameObjectManager::default();
+        let id = add_at(&mut game_manager, ShapeType::Capsule, Vec3::Y * 5.0, 0.0);
+        game_manager.get_object_by_id_mut(id).unwrap().rotation = Quat::from_rotation_x(FRAC_PI_2);
+        assert_aabb(
+            game_manager.get_object_by_id(id).unwrap().aabb(),
+            Vec3::new(-0.3, 4.7, -1.3),
+            Vec3::new(0.3, 5.3, 1.3),
+        );
+    }
+
+    #[test]
+    fn scene_bounds_cover_every_object() {
+        let mut game_manager = GameObjectManager::default();
+        // Empty scenes get a zero sized box at the origin
+        assert_aabb(game_manager.scene_bounds(|_| None), Vec3::ZERO, Vec3::ZERO);
+
+        add_at(
+            &mut game_manager,
+            ShapeType::Cube,
+            Vec3::new(-4.0, 0.0, 0.0),
+            0.0,
+        );
+        add_at(
+            &mut game_manager,
+            ShapeType::Ball,
+            Vec3::new(3.0, 2.0, 1.0),
+            0.0,
+        );
+        assert_aabb(
+            game_manager.scene_bounds(|_| None),
+            Vec3::new(-4.5, -0.5, -0.5),
+            Vec3::new(3.5, 2.5, 1.5),
+        );
+
+        // Scale left on the transform, as the inspector and loaded scenes do,
+        // widens the box. Objects without a transform keep their synced one
+        let id = add_at(
+            &mut game_manager,
+            ShapeType::Cube,
+            Vec3::new(0.0, -5.0, 0.0),
+            0.0,
+        );
+        let stretched = game_manager.get_object_by_id(id).unwrap().entity();
+        let transform = Transform::from_xyz(0.0, -5.0, 0.0).with_scale(Vec3::new(10.0, 1.0, 1.0));
+        assert_aabb(
+            game_manager.scene_bounds(|entity| (entity == stretched).then_some(transform)),
+            Vec3::new(-5.0, -5.5, -0.5),
+            Vec3::new(5.0, 2.5, 1.5),
+        );
+    }
+
+    fn ids_in_order(game_manager: &GameObjectManager) -> Vec<u32> {
+        game_manager.iter().map(|obj| obj.id).collect()
+    }
+
+    // Random adds and removes, with recycling on so freed low ids come back
+    // after higher ones. Iteration has to stay ascending throughout
+    #[test]
+    fn iteration_is_by_id_whatever_the_removal_history() {
+        let mut rng = StdRng::seed_from_u64(69);
+        let mut game_manager = GameObjectManager::default();
+        game_manager.ids_mut().set_recycling(true);
+        let mut next_entity = 1;
+        for step in 0..500 {
+            if game_manager.is_empty() || rng.gen_bool(0.6) {
+                game_manager
+                    .add_object(
+                        Entity::from_raw(next_entity),
+                        ShapeType::Cube,
+                        Vec3::ZERO,
+                        None,
+                        false,
+                        0.0,
+                    )
+                    .unwrap();
+                next_entity += 1;
+            } else {
+                let index = rng.gen_range(0..game_manager.len());
+                let entity = game_manager.iter().nth(index).unwrap().entity();
+                game_manager.remove_object(entity).unwrap();
+            }
+
+            let ids = ids_in_order(&game_manager);
+            assert!(ids.is_sorted(), "out of order at step {}: {:?}", step, ids);
+            assert_eq!(ids.len(), game_manager.len());
+            let mut_ids: Vec<u32> = game_manager.iter_mut().map(|obj| obj.id).collect();
+            assert_eq!(mut_ids, ids);
+            let reversed: Vec<u32> = game_manager.iter().rev().map(|obj| obj.id).collect();
+            assert!(reversed.iter().rev().eq(ids.iter()));
         }
     }
+
+    #[test]
+    fn reinserted_ids_take_their_place_in_order() {
+        let mut game_manager = manager_with_names(&["a", "b", "c", "d"]);
+        let snapshot = game_manager.snapshot(1, Transform::default()).unwrap();
+        game_manager.remove_object(Entity::from_raw(2)).unwrap();
+        game_manager.remove_object(Entity::from_raw(1)).unwrap();
+        add_named(&mut game_manager, "e");
+        assert_eq!(ids_in_order(&game_manager), [2, 3, 4]);
+
+        // Undo brings object 1 back under its own id, ahead of the rest
+        game_manager
+            .insert_object(Entity::from_raw(20), &snapshot, 0.0)
+            .unwrap();
+        assert_eq!(ids_in_order(&game_manager), [1, 2, 3, 4]);
+        assert_eq!(game_manager.list_objects().len(), 4);
+        assert!(!game_manager.is_empty());
+    }
+
+    #[test]
+    fn filtering_ignores_case_and_an_empty_query_keeps_everything() {
+        let mut game_manager = manager_with_names(&["Red Crate", "crate lid", "Ball"]);
+        add_at(&mut game_manager, ShapeType::Ball, Vec3::ZERO, 0.0);
+        let names = |query: &str, shape: Option<ShapeType>| -> Vec<String> {
+            game_manager
+                .filter_objects(query, shape)
+                .into_iter()
+                .map(|obj| obj.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("CRATE", None), ["Red Crate", "crate lid"]);
+        assert_eq!(names("  rEd ", None), ["Red Crate"]);
+        assert!(names("barrel", None).is_empty());
+        for query in ["", "   "] {
+            assert_eq!(names(query, None).len(), 4, "{:?}", query);
+        }
+
+        // The shape narrows the list before the name is matched
+        assert_eq!(names("", Some(ShapeType::Ball)).len(), 1);
+        assert!(names("crate", Some(ShapeType::Ball)).is_empty());
+        assert_eq!(names("ball", Some(ShapeType::Cube)), ["Ball"]);
+    }
+
+    #[test]
+    fn counts_by_type_leave_out_empty_shapes_and_follow_removals() {
+        let mut game_manager = GameObjectManager::default();
+        assert!(game_manager.counts_by_type().is_empty());
+
+        let first_ball = add_at(&mut game_manager, ShapeType::Ball, Vec3::ZERO, 0.0);
+        add_at(&mut game_manager, ShapeType::Ball, Vec3::X, 0.0);
+        let cube = add_at(&mut game_manager, ShapeType::Cube, Vec3::Y, 0.0);
+        assert_eq!(
+            game_manager.counts_by_type(),
+            HashMap::from([(ShapeType::Ball, 2), (ShapeType::Cube, 1)])
+        );
+
+        game_manager
+            .remove_object(Entity::from_raw(first_ball + 1))
+            .unwrap();
+        assert_eq!(
+            game_manager.counts_by_type(),
+            HashMap::from([(ShapeType::Ball, 1), (ShapeType::Cube, 1)])
+        );
+        game_manager
+            .remove_object(Entity::from_raw(cube + 1))
+            .unwrap();
+        assert_eq!(
+            game_manager.counts_by_type(),
+            HashMap::from([(ShapeType::Ball, 1)])
+        );
+    }
 }
@@ -0,0 +1,382 @@
+use crate::engine::core::UiSet;
+use crate::engine::editor::{
+    BulkEditEvent, DimensionEditEvent, MaterialEditEvent, PhysicsEditEvent, TransformEditEvent,
+};
+use crate::engine::layout::EditorLayout;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    ClearSceneEvent, GameObjectManager, MetaEditEvent, ObjectRemovedEvent, ObjectSpawnedEvent,
+    RenameObjectEvent, SpawnSource, TagObjectEvent,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use directories::ProjectDirs;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Entries kept in memory, the oldest are dropped past this
+pub const OPERATION_LOG_CAPACITY: usize = 10_000;
+
+// Repeats of the same edit on the same object this close together, like an
+// inspector drag, update one entry instead of adding more
+const COALESCE_SECS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Spawn,
+    Despawn,
+    Rename,
+    Edit,
+}
+
+impl OperationKind {
+    pub fn all() -> [OperationKind; 4] {
+        [
+            OperationKind::Spawn,
+            OperationKind::Despawn,
+            OperationKind::Rename,
+            OperationKind::Edit,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OperationKind::Spawn => "Spawn",
+            OperationKind::Despawn => "Despawn",
+            OperationKind::Rename => "Rename",
+            OperationKind::Edit => "Edit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Operation {
+    // Seconds since startup
+    pub time: f64,
+    pub kind: OperationKind,
+    // Who caused it. Spawns carry the object's source, everything else is
+    // done from the editor for now
+    pub actor: SpawnSource,
+    pub id: u32,
+    pub name: String,
+    pub detail: String,
+}
+
+// Every spawn, despawn, rename and property edit this session, oldest first.
+// Cleared when the scene is cleared
+#[derive(Resource, Debug, Default)]
+pub struct OperationLog {
+    entries: VecDeque<Operation>,
+    // Bumped on every record and clear, coalesced edits included, so
+    // autosave can tell whether anything happened since it last wrote
+    revision: u64,
+}
+
+impl OperationLog {
+    pub fn record(&mut self, operation: Operation) {
+        self.revision += 1;
+        if operation.kind == OperationKind::Edit
+            && let Some(last) = self.entries.back_mut()
+            && last.kind == operation.kind
+            && last.id == operation.id
+            && last.detail == operation.detail
+            && operation.time - last.time < COALESCE_SECS
+        {
+            last.time = operation.time;
+            return;
+        }
+        if self.entries.len() >= OPERATION_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(operation);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Operation> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Write every entry as one JSON object per line, returns where it went
+    pub fn export(&self) -> Result<PathBuf, String> {
+        let dirs = ProjectDirs::from("", "", "Navi").ok_or("no data directory available")?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = dirs.data_dir().join(format!("operations-{}.jsonl", stamp));
+
+        let mut contents = String::new();
+        for operation in &self.entries {
+            let line = serde_json::to_string(operation).map_err(|err| err.to_string())?;
+            let _ = writeln!(contents, "{}", line);
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, contents).map_err(|err| err.to_string())?;
+        info!(
+            "Exported {} operations to {}",
+            self.entries.len(),
+            path.display()
+        );
+        Ok(path)
+    }
+}
+
+// Every event that ends up as an entry in the log
+#[derive(SystemParam)]
+pub struct LoggedEvents<'w, 's> {
+    pub spawned: EventReader<'w, 's, ObjectSpawnedEvent>,
+    pub removed: EventReader<'w, 's, ObjectRemovedEvent>,
+    pub renames: EventReader<'w, 's, RenameObjectEvent>,
+    pub transforms: EventReader<'w, 's, TransformEditEvent>,
+    pub materials: EventReader<'w, 's, MaterialEditEvent>,
+    pub physics: EventReader<'w, 's, PhysicsEditEvent>,
+    pub dimensions: EventReader<'w, 's, DimensionEditEvent>,
+    pub tags: EventReader<'w, 's, TagObjectEvent>,
+    pub meta: EventReader<'w, 's, MetaEditEvent>,
+    pub bulk: EventReader<'w, 's, BulkEditEvent>,
+    pub clears: EventReader<'w, 's, ClearSceneEvent>,
+}
+
+// Log the edits applied this frame. Runs after the edit systems, so spawns and
+// renames are read back from the manager as they ended up
+pub fn record_operations_system(
+    mut log: ResMut<OperationLog>,
+    game_manager: Res<GameObjectManager>,
+    LoggedEvents {
+        mut spawned,
+        mut removed,
+        mut renames,
+        mut transforms,
+        mut materials,
+        mut physics,
+        mut dimensions,
+        mut tags,
+        mut meta,
+        mut bulk,
+        mut clears,
+    }: LoggedEvents,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+    let edit = |log: &mut OperationLog, id: Option<u32>, detail: String| {
+        let Some(obj) = id.and_then(|id| game_manager.get_object_by_id(id)) else {
+            return;
+        };
+        log.record(Operation {
+            time: now,
+            kind: OperationKind::Edit,
+            actor: SpawnSource::ToolbarButton,
+            id: obj.id,
+            name: obj.name.clone(),
+            detail,
+        });
+    };
+    let id_of = |entity: Entity| game_manager.get_object_by_entity(entity).map(|obj| obj.id);
+
+    for event in spawned.read() {
+        let actor = game_manager
+            .get_object_by_id(event.id)
+            .map_or(SpawnSource::default(), |obj| obj.source);
+        log.record(Operation {
+            time: now,
+            kind: OperationKind::Spawn,
+            actor,
+            id: event.id,
+            name: event.name.clone(),
+            detail: format!(
+                "{} at {:.2?}",
+                event.shape_type.display_name(),
+                event.position
+            ),
+        });
+    }
+    for event in removed.read() {
+        log.record(Operation {
+            time: now,
+            kind: OperationKind::Despawn,
+            actor: SpawnSource::ToolbarButton,
+            id: event.id,
+            name: event.name.clone(),
+            detail: format!(
+                "{} at {:.2?}",
+                event.shape_type.display_name(),
+                event.last_position
+            ),
+        });
+    }
+    for event in renames.read() {
+        // Skip renames that were rejected, unique names may have added a suffix
+        if let Some(obj) = game_manager.get_object_by_id(event.id)
+            && obj.name.starts_with(event.new_name.trim())
+        {
+            log.record(Operation {
+                time: now,
+                kind: OperationKind::Rename,
+                actor: SpawnSource::ToolbarButton,
+                id: obj.id,
+                name: obj.name.clone(),
+                detail: format!("Renamed to {}", obj.name),
+            });
+        }
+    }
+    for event in transforms.read() {
+        edit(&mut log, id_of(event.entity), "Transform".to_string());
+    }
+    for event in materials.read() {
+        edit(&mut log, id_of(event.entity), "Color".to_string());
+    }
+    for event in physics.read() {
+        edit(&mut log, id_of(event.entity), "Physics".to_string());
+    }
+    for event in dimensions.read() {
+        edit(&mut log, id_of(event.entity), "Dimensions".to_string());
+    }
+    for event in tags.read() {
+        let verb = if event.add { "Added" } else { "Removed" };
+        edit(
+            &mut log,
+            Some(event.id),
+            format!("{} tag {}", verb, event.tag),
+        );
+    }
+    for event in meta.read() {
+        let detail = match &event.value {
+            Some(value) => format!("Set {} = {}", event.key, value),
+            None => format!("Removed {}", event.key),
+        };
+        edit(&mut log, Some(event.id), detail);
+    }
+    for event in bulk.read() {
+        for &entity in &event.entities {
+            edit(&mut log, id_of(entity), "Bulk edit".to_string());
+        }
+    }
+
+    // A new scene starts a new log, after this frame's removals went in
+    if clears.read().count() > 0 {
+        log.clear();
+    }
+}
+
+// Filter toggles for the operation log panel
+pub struct OperationLogFilter {
+    pub kinds: [bool; 4],
+    pub hide_scene_loads: bool,
+}
+
+impl Default for OperationLogFilter {
+    fn default() -> Self {
+        Self {
+            kinds: [true; 4],
+            hide_scene_loads: false,
+        }
+    }
+}
+
+pub fn operation_log_panel_system(
+    mut contexts: EguiContexts,
+    log: Res<OperationLog>,
+    mut notifications: ResMut<EditorNotifications>,
+    mut layout: ResMut<EditorLayout>,
+    mut filter: Local<OperationLogFilter>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Operation Log",
+        egui::Window::new("Operation Log")
+            .default_width(420.0)
+            .default_height(300.0),
+        |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (kind, shown) in OperationKind::all().into_iter().zip(&mut filter.kinds) {
+                    ui.checkbox(shown, kind.label());
+                }
+                ui.checkbox(&mut filter.hide_scene_loads, "Hide scene loads");
+            });
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} / {} entries",
+                    log.len(),
+                    OPERATION_LOG_CAPACITY
+                ));
+                if ui
+                    .add_enabled(!log.is_empty(), egui::Button::new("Export JSON lines"))
+                    .clicked()
+                {
+                    match log.export() {
+                        Ok(path) => notifications.info(format!("Exported to {}", path.display())),
+                        Err(err) => notifications.error(format!("Export failed: {}", err)),
+                    }
+                }
+            });
+            ui.separator();
+
+            let rows: Vec<&Operation> = log
+                .iter()
+                .rev()
+                .filter(|op| {
+                    let index = OperationKind::all()
+                        .iter()
+                        .position(|kind| *kind == op.kind)
+                        .unwrap_or(0);
+                    filter.kinds[index]
+                        && !(filter.hide_scene_loads && op.actor == SpawnSource::SceneLoad)
+                })
+                .collect();
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            // Newest first, only the visible rows are laid out
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for op in &rows[range] {
+                        ui.horizontal(|ui| {
+                            ui.weak(format!("{:>8.2}s", op.time));
+                            ui.label(op.actor.icon()).on_hover_text(op.actor.label());
+                            ui.strong(op.kind.label());
+                            ui.label(format!("{} (ID: {})", op.name, op.id));
+                            ui.weak(&op.detail);
+                        });
+                    }
+                });
+        },
+    );
+}
+
+// The log of every edit, shown in the Operation Log panel
+pub struct OperationLogPlugin;
+
+impl Plugin for OperationLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OperationLog>().add_systems(
+            EguiContextPass,
+            operation_log_panel_system.in_set(UiSet::Panels),
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::engine::core::UiSet;
+use crate::engine::input::{InputAction, InputBindings, key_name};
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::sync::Arc;
+
+// Most matches listed at once
+const MAX_RESULTS: usize = 12;
+
+pub type CommandAction = Arc<dyn Fn(&mut World) + Send + Sync>;
+
+// Shortcut shown next to a command, bindings are looked up live so rebinding updates it
+#[derive(Debug, Clone, Copy)]
+pub enum CommandHint {
+    Binding(InputAction),
+    Keys(&'static str),
+}
+
+#[derive(Clone)]
+pub struct PaletteCommand {
+    pub name: String,
+    pub hint: Option<CommandHint>,
+    pub action: CommandAction,
+}
+
+// Commands listed in the palette, modules add theirs from a startup system
+#[derive(Resource, Default)]
+pub struct CommandRegistry {
+    commands: Vec<PaletteCommand>,
+}
+
+impl CommandRegistry {
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        hint: Option<CommandHint>,
+        action: impl Fn(&mut World) + Send + Sync + 'static,
+    ) {
+        self.commands.push(PaletteCommand {
+            name: name.into(),
+            hint,
+            action: Arc::new(action),
+        });
+    }
+
+    // Command that just sends a copy of the event
+    pub fn register_event<E: Event + Clone>(
+        &mut self,
+        name: impl Into<String>,
+        hint: Option<CommandHint>,
+        event: E,
+    ) {
+        self.register(name, hint, move |world| {
+            world.send_event(event.clone());
+        });
+    }
+
+    pub fn commands(&self) -> &[PaletteCommand] {
+        &self.commands
+    }
+
+    // Commands matching the query, best match first
+    pub fn search(&self, query: &str) -> Vec<&PaletteCommand> {
+        let mut matches: Vec<(i32, &PaletteCommand)> = self
+            .commands
+            .iter()
+            .filter_map(|command| fuzzy_score(query, &command.name).map(|score| (score, command)))
+            .collect();
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+}
+
+// Score a subsequence match, consecutive letters and word starts rank higher.
+// None when the query letters don't all appear in order
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|&i| text[i] == query_char)?;
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Small penalty for skipped letters so tighter matches win
+        score -= (found - next).min(5) as i32;
+        last_match = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+// Open state of the palette popup
+#[derive(Resource, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+}
+
+// Ctrl+P opens the palette. While it's open every key press belongs to it, so
+// the keyboard state is cleared before the other input systems see it
+pub fn command_palette_input_system(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut palette: ResMut<CommandPalette>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !palette.open && ctrl && keyboard_input.just_pressed(KeyCode::KeyP) {
+        palette.show();
+    }
+    if palette.open {
+        keyboard_input.reset_all();
+    }
+}
+
+pub fn command_palette_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut palette: ResMut<CommandPalette>,
+    registry: Res<CommandRegistry>,
+    bindings: Res<InputBindings>,
+) {
+    if !palette.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let (escape, enter, up, down) = ctx.input(|i| {
+        (
+            i.key_pressed(egui::Key::Escape),
+            i.key_pressed(egui::Key::Enter),
+            i.key_pressed(egui::Key::ArrowUp),
+            i.key_pressed(egui::Key::ArrowDown),
+        )
+    });
+    if escape {
+        palette.close();
+        return;
+    }
+
+    let mut run: Option<CommandAction> = None;
+    egui::Window::new("Command Palette")
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+        .fixed_size(egui::vec2(420.0, 0.0))
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .hint_text("Type a command...")
+                    .desired_width(f32::INFINITY),
+            );
+            response.request_focus();
+            if response.changed() {
+                palette.selected = 0;
+            }
+
+            let matches = registry.search(&palette.query);
+            if matches.is_empty() {
+                ui.label("No matching commands");
+                return;
+            }
+
+            let visible = matches.len().min(MAX_RESULTS);
+            if down {
+                palette.selected = (palette.selected + 1) % visible;
+            }
+            if up {
+                palette.selected = (palette.selected + visible - 1) % visible;
+            }
+            palette.selected = palette.selected.min(visible - 1);
+
+            ui.separator();
+            for (i, command) in matches.iter().take(MAX_RESULTS).enumerate() {
+                ui.horizontal(|ui| {
+                    let label = ui.selectable_label(i == palette.selected, &command.name);
+                    if label.clicked() {
+                        run = Some(command.action.clone());
+                    }
+                    if let Some(hint) = command.hint {
+                        let text = match hint {
+                            CommandHint::Binding(action) => key_name(bindings.key(action)),
+                            CommandHint::Keys(keys) => keys.to_string(),
+                        };
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.weak(text);
+                        });
+                    }
+                });
+            }
+            if enter {
+                run = Some(matches[palette.selected].action.clone());
+            }
+        });
+
+    if let Some(action) = run {
+        palette.close();
+        commands.queue(move |world: &mut World| action(world));
+    }
+}
+
+// The command palette. Each module adds its own entries at startup
+pub struct CommandPalettePlugin;
+
+impl Plugin for CommandPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandRegistry>()
+            .init_resource::<CommandPalette>()
+            .add_systems(EguiContextPass, command_palette_ui.in_set(UiSet::Popups));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::engine::core::EditorSet;
+use crate::engine::gizmo::TransformGizmo;
+use crate::engine::input::{InputAction, ViewportInput};
+use crate::engine::labels::CursorPick;
+use crate::engine::measure::Measurements;
+use crate::engine::objects::{
+    NextSpawn, SelectedShape, ShapeDimensions, ShapeParams, ShapeType, SpawnEntityEvent,
+    SpawnSource,
+};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const GHOST_COLOR: Color = Color::srgba(0.4, 0.8, 1.0, 0.35);
+const PLACEMENT_RAY_LENGTH: f32 = 500.0;
+
+// Translucent stand-in for the object about to be spawned. It has no collider
+// and is never registered with the GameObjectManager
+#[derive(Component)]
+pub struct PlacementGhost;
+
+// Click-to-place spawning, the ghost follows the cursor while this is active
+#[derive(Resource, Default)]
+pub struct PlacementMode {
+    pub active: bool,
+    // Where the next spawn lands, None while the cursor points at nothing
+    pub position: Option<Vec3>,
+    ghost: Option<Entity>,
+    // Shape and size the current ghost mesh was built for
+    ghost_shape: Option<(ShapeType, ShapeDimensions)>,
+}
+
+impl PlacementMode {
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        if !self.active {
+            self.position = None;
+        }
+    }
+}
+
+// Toggle placement mode, track the cursor and spawn on click or Space
+pub fn placement_input_system(
+    ViewportInput {
+        keyboard_input,
+        mouse_input,
+        egui_input,
+        bindings,
+    }: ViewportInput,
+    gizmo: Res<TransformGizmo>,
+    measurements: Res<Measurements>,
+    mut placement: ResMut<PlacementMode>,
+    NextSpawn {
+        selected_shape,
+        shape_params,
+        spawn_physics,
+        snap,
+        ..
+    }: NextSpawn,
+    picker: CursorPick,
+    mut spawn_events: EventWriter<SpawnEntityEvent>,
+) {
+    let keyboard_free = !egui_input.wants_keyboard_input();
+    if keyboard_free && bindings.just_pressed(InputAction::PlacementMode, &keyboard_input) {
+        placement.toggle();
+    }
+    if !placement.active {
+        return;
+    }
+    if keyboard_free && keyboard_input.just_pressed(KeyCode::Escape) {
+        placement.toggle();
+        return;
+    }
+
+    let shape = selected_shape.shape_type;
+    let dims = shape_params.get(shape);
+    let position = picker.ray().and_then(|ray| {
+        let hit = picker.cast(ray, PLACEMENT_RAY_LENGTH).map(|(_, toi)| toi);
+        // Past the colliders fall back to the y = 0 plane
+        let distance =
+            hit.or_else(|| ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)))?;
+        // Rest the shape on the surface instead of sinking it halfway in
+        let point = ray.get_point(distance) + Vec3::Y * dims.half_height(shape);
+        Some(snap.snap_position(point))
+    });
+    // Hold the last spot while the cursor is over a panel
+    if !egui_input.wants_any_pointer_input() {
+        placement.position = position;
+    }
+
+    let Some(position) = placement.position else {
+        return;
+    };
+    let clicked = mouse_input.just_pressed(MouseButton::Left)
+        && !egui_input.wants_any_pointer_input()
+        && gizmo.hovered.is_none()
+        && !gizmo.is_dragging()
+        && !measurements.active;
+    let space = keyboard_free && bindings.just_pressed(InputAction::SpawnObject, &keyboard_input);
+    if clicked || space {
+        spawn_events.write(SpawnEntityEvent {
+            source: SpawnSource::Keyboard,
+            position,
+            shape_type: shape,
+            custom_name: None,
+            params: Some(dims),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+            lifetime_secs: None,
+            custom_mesh: None,
+            physics: Some(spawn_physics.props),
+            body_type: None,
+        });
+    }
+}
+
+// Keep the ghost entity in step with the mode, the selected shape and its size
+pub fn placement_ghost_system(
+    mut commands: Commands,
+    mut placement: ResMut<PlacementMode>,
+    selected_shape: Res<SelectedShape>,
+    shape_params: Res<ShapeParams>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ghosts: Query<&mut Transform, With<PlacementGhost>>,
+) {
+    let shape = (
+        selected_shape.shape_type,
+        shape_params.get(selected_shape.shape_type),
+    );
+    let wanted = placement.active && placement.position.is_some();
+
+    // Rebuild from scratch when the shape changes rather than patching the mesh
+    if let Some(ghost) = placement.ghost
+        && (!wanted || placement.ghost_shape != Some(shape))
+    {
+        commands.entity(ghost).try_despawn();
+        placement.ghost = None;
+        placement.ghost_shape = None;
+    }
+    let Some(position) = placement.position.filter(|_| wanted) else {
+        return;
+    };
+
+    if let Some(mut transform) = placement.ghost.and_then(|ghost| ghosts.get_mut(ghost).ok()) {
+        transform.translation = position;
+        return;
+    }
+
+    let (shape_type, dims) = shape;
+    let ghost = commands
+        .spawn((
+            PlacementGhost,
+            Mesh3d(shape_type.create_mesh_with(&mut meshes, &dims)),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: GHOST_COLOR,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(position),
+            NotShadowCaster,
+        ))
+        .id();
+    placement.ghost = Some(ghost);
+    placement.ghost_shape = Some(shape);
+}
+
+pub fn register_placement_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register(
+        "Toggle placement mode",
+        Some(CommandHint::Binding(InputAction::PlacementMode)),
+        |world| {
+            world.resource_mut::<PlacementMode>().toggle();
+        },
+    );
+}
+
+// Click-to-place spawning and its ghost preview
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlacementMode>()
+            .add_systems(Startup, register_placement_commands)
+            .add_systems(
+                Update,
+                placement_ghost_system.in_set(EditorSet::Bookkeeping),
+            );
+    }
+}
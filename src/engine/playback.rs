@@ -0,0 +1,550 @@
+use crate::engine::core::UiSet;
+use crate::engine::editor::{
+    DimensionEditEvent, MaterialEditEvent, PhysicsEditEvent, TransformEditEvent,
+};
+use crate::engine::layout::EditorLayout;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, EditorRng, GameObjectManager, MetaEditEvent,
+    RenameObjectEvent, SpawnEntityEvent, SpawnSource, TagObjectEvent,
+};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::recorder::{RecordedAction, Recording, default_recording_dir};
+use crate::engine::scene::{RestoreSceneEvent, SceneLoadMode};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// Speeds offered in the Playback panel
+pub const PLAYBACK_SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+// Final positions further apart than this count as drift
+const DRIFT_TOLERANCE: f32 = 0.01;
+
+// Final positions after playback against those stored in the recording
+#[derive(Debug, Clone, Default)]
+pub struct DriftReport {
+    // Objects found in both
+    pub compared: usize,
+    pub max: f32,
+    pub mean: f32,
+    // Object that drifted furthest, with the distance
+    pub worst: Option<(u32, f32)>,
+    // Recorded objects missing after playback, and objects only playback has
+    pub missing: Vec<u32>,
+    pub extra: usize,
+    // Played at 1x without scrubbing, otherwise physics ran a different course
+    // and drift is expected
+    pub faithful: bool,
+}
+
+impl DriftReport {
+    pub fn deterministic(&self) -> bool {
+        self.max <= DRIFT_TOLERANCE && self.missing.is_empty() && self.extra == 0
+    }
+}
+
+fn drift_report(
+    recording: &Recording,
+    game_manager: &GameObjectManager,
+    faithful: bool,
+) -> DriftReport {
+    let mut report = DriftReport {
+        faithful,
+        ..default()
+    };
+    let mut total = 0.0;
+    for recorded in &recording.final_positions {
+        let Some(obj) = game_manager.get_object_by_id(recorded.id) else {
+            report.missing.push(recorded.id);
+            continue;
+        };
+        let drift = obj.position().distance(recorded.position);
+        total += drift;
+        report.compared += 1;
+        if drift > report.max {
+            report.max = drift;
+            report.worst = Some((recorded.id, drift));
+        }
+    }
+    report.mean = total / report.compared.max(1) as f32;
+    report.extra = game_manager
+        .iter()
+        .filter(|obj| {
+            recording
+                .final_positions
+                .iter()
+                .all(|recorded| recorded.id != obj.id)
+        })
+        .count();
+    report
+}
+
+// Plays a recording back by restoring its starting scene and sending its
+// events again when the clock passes them
+#[derive(Resource)]
+pub struct Playback {
+    // File field of the panel
+    pub path: String,
+    recording: Option<Arc<Recording>>,
+    // From Play until Stop, paused or not. Spawning is blocked meanwhile
+    active: bool,
+    pub paused: bool,
+    pub speed: f32,
+    // Seconds into the recording
+    clock: f64,
+    // Next event to send
+    next: usize,
+    // Set to start over from the starting scene on the next update
+    restart: bool,
+    // The starting scene is being put back, events wait a frame for it
+    restoring: bool,
+    faithful: bool,
+    pub report: Option<DriftReport>,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            recording: None,
+            active: false,
+            paused: false,
+            speed: 1.0,
+            clock: 0.0,
+            next: 0,
+            restart: false,
+            restoring: false,
+            faithful: true,
+            report: None,
+        }
+    }
+}
+
+impl Playback {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_deref()
+    }
+
+    pub fn clock(&self) -> f64 {
+        self.clock
+    }
+
+    pub fn load(&mut self, recording: Recording) {
+        *self = Self {
+            path: std::mem::take(&mut self.path),
+            speed: self.speed,
+            recording: Some(Arc::new(recording)),
+            ..default()
+        };
+    }
+
+    // From the start, the scene is replaced on the next update
+    pub fn play(&mut self) {
+        if self.recording.is_none() {
+            return;
+        }
+        self.active = true;
+        self.paused = false;
+        self.restart = true;
+        self.clock = 0.0;
+        self.faithful = self.speed == 1.0;
+        self.report = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.paused = false;
+    }
+
+    // Forwards sends the events in between at once, backwards starts over
+    // from the starting scene and does the same
+    pub fn seek(&mut self, time: f64) {
+        let Some(duration) = self
+            .recording
+            .as_ref()
+            .map(|recording| recording.duration())
+        else {
+            return;
+        };
+        if !self.active {
+            self.play();
+            self.paused = true;
+        }
+        let time = time.clamp(0.0, duration);
+        if time < self.clock {
+            self.restart = true;
+        }
+        self.clock = time;
+        self.faithful = false;
+        self.report = None;
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+        if self.active && speed != 1.0 {
+            self.faithful = false;
+        }
+    }
+}
+
+// Run condition for spawn paths that don't go through SpawnEntityEvent
+pub fn playback_inactive(playback: Res<Playback>) -> bool {
+    !playback.active
+}
+
+// The edits a recording replays, one writer per recorded action
+#[derive(SystemParam)]
+pub struct ReplayedEvents<'w> {
+    pub spawns: EventWriter<'w, SpawnEntityEvent>,
+    pub despawns: EventWriter<'w, DespawnEntityEvent>,
+    pub transforms: EventWriter<'w, TransformEditEvent>,
+    pub materials: EventWriter<'w, MaterialEditEvent>,
+    pub physics: EventWriter<'w, PhysicsEditEvent>,
+    pub dimensions: EventWriter<'w, DimensionEditEvent>,
+    pub renames: EventWriter<'w, RenameObjectEvent>,
+    pub tags: EventWriter<'w, TagObjectEvent>,
+    pub meta: EventWriter<'w, MetaEditEvent>,
+}
+
+// Runs first in the frame so the events it sends are carried out the same
+// frame, like ones sent from input
+pub fn playback_system(
+    mut playback: ResMut<Playback>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut rng: ResMut<EditorRng>,
+    mut restore_events: EventWriter<RestoreSceneEvent>,
+    ReplayedEvents {
+        mut spawns,
+        mut despawns,
+        mut transforms,
+        mut materials,
+        mut physics,
+        mut dimensions,
+        mut renames,
+        mut tags,
+        mut meta,
+    }: ReplayedEvents,
+    mut notifications: ResMut<EditorNotifications>,
+    time: Res<Time>,
+) {
+    let Some(recording) = playback.recording.clone() else {
+        return;
+    };
+    if !playback.active {
+        return;
+    }
+
+    if playback.restart {
+        playback.restart = false;
+        playback.restoring = true;
+        playback.next = 0;
+        restore_events.write(RestoreSceneEvent {
+            source: "the recording".to_string(),
+            scene: recording.scene.clone(),
+            mode: SceneLoadMode::Replace,
+        });
+        *rng = EditorRng::seeded(recording.seed);
+        return;
+    }
+    if playback.restoring {
+        playback.restoring = false;
+        if let Some(next_id) = recording.next_id {
+            game_manager.ids_mut().advance_to(next_id);
+        }
+    }
+
+    let duration = recording.duration();
+    if !playback.paused {
+        playback.clock =
+            (playback.clock + time.delta_secs_f64() * playback.speed as f64).min(duration);
+    }
+
+    // An event for an object spawned earlier this frame waits for the next
+    // one, the spawn isn't carried out yet
+    let mut spawned = false;
+    while let Some(event) = recording.events.get(playback.next) {
+        if event.time > playback.clock {
+            break;
+        }
+        let entity_of = |id: u32| game_manager.get_object_by_id(id).map(|obj| obj.entity());
+        let target = match &event.action {
+            RecordedAction::Spawn(_) => None,
+            RecordedAction::Despawn(ids) => ids.first().copied(),
+            RecordedAction::Transform { id, .. }
+            | RecordedAction::Color { id, .. }
+            | RecordedAction::Physics { id, .. }
+            | RecordedAction::Dimensions { id, .. }
+            | RecordedAction::Rename { id, .. }
+            | RecordedAction::Tag { id, .. }
+            | RecordedAction::Meta { id, .. } => Some(*id),
+        };
+        if spawned && target.is_some_and(|id| entity_of(id).is_none()) {
+            break;
+        }
+        playback.next += 1;
+
+        match &event.action {
+            RecordedAction::Spawn(spawn) => {
+                spawns.write(SpawnEntityEvent {
+                    source: SpawnSource::Playback,
+                    ..spawn.clone()
+                });
+                spawned = true;
+            }
+            RecordedAction::Despawn(ids) => {
+                despawns.write(DespawnEntityEvent {
+                    target: DespawnTarget::Ids(ids.clone()),
+                });
+            }
+            RecordedAction::Transform {
+                id,
+                translation,
+                rotation,
+                scale,
+            } => {
+                if let Some(entity) = entity_of(*id) {
+                    transforms.write(TransformEditEvent {
+                        entity,
+                        transform: Transform {
+                            translation: *translation,
+                            rotation: *rotation,
+                            scale: *scale,
+                        },
+                    });
+                }
+            }
+            RecordedAction::Color { id, color } => {
+                if let Some(entity) = entity_of(*id) {
+                    materials.write(MaterialEditEvent {
+                        entity,
+                        color: *color,
+                    });
+                }
+            }
+            RecordedAction::Physics { id, props } => {
+                if let Some(entity) = entity_of(*id) {
+                    physics.write(PhysicsEditEvent {
+                        entity,
+                        props: *props,
+                    });
+                }
+            }
+            RecordedAction::Dimensions {
+                id,
+                dimensions: dims,
+            } => {
+                if let Some(entity) = entity_of(*id) {
+                    dimensions.write(DimensionEditEvent {
+                        entity,
+                        dimensions: *dims,
+                    });
+                }
+            }
+            RecordedAction::Rename { id, name } => {
+                renames.write(RenameObjectEvent {
+                    id: *id,
+                    new_name: name.clone(),
+                });
+            }
+            RecordedAction::Tag { id, tag, add } => {
+                tags.write(TagObjectEvent {
+                    id: *id,
+                    tag: tag.clone(),
+                    add: *add,
+                });
+            }
+            RecordedAction::Meta { id, key, value } => {
+                meta.write(MetaEditEvent {
+                    id: *id,
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    if playback.clock >= duration
+        && playback.next >= recording.events.len()
+        && playback.report.is_none()
+    {
+        let report = drift_report(&recording, &game_manager, playback.faithful);
+        info!(
+            "Playback finished, {} objects compared, max drift {:.4}",
+            report.compared, report.max
+        );
+        if report.deterministic() {
+            notifications.info("Playback finished, no drift");
+        } else {
+            notifications.warn(format!("Playback finished, max drift {:.3}", report.max));
+        }
+        playback.report = Some(report);
+        playback.paused = true;
+    }
+}
+
+fn format_clock(secs: f64) -> String {
+    format!("{:02}:{:04.1}", (secs / 60.0) as u32, secs % 60.0)
+}
+
+pub fn playback_panel_system(
+    mut contexts: EguiContexts,
+    mut playback: ResMut<Playback>,
+    mut layout: ResMut<EditorLayout>,
+    mut notifications: ResMut<EditorNotifications>,
+    // Scrub bar position while it's dragged, applied on release
+    mut scrub: Local<Option<f64>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Playback",
+        egui::Window::new("Playback").default_width(320.0),
+        |ui| {
+            ui.label("File (.nvrec)");
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut playback.path)
+                        .hint_text(default_recording_dir().display().to_string())
+                        .desired_width(220.0),
+                );
+                let path = playback.path.trim().to_string();
+                if ui
+                    .add_enabled(!path.is_empty(), egui::Button::new("Load"))
+                    .clicked()
+                {
+                    match Recording::read(&PathBuf::from(&path)) {
+                        Ok(recording) => {
+                            notifications.info(format!(
+                                "Loaded recording with {} events",
+                                recording.events.len()
+                            ));
+                            playback.load(recording);
+                        }
+                        Err(err) => {
+                            warn!("Cannot load recording {}: {}", path, err);
+                            notifications.error(format!("Cannot load recording: {}", err));
+                        }
+                    }
+                }
+            });
+
+            let Some(recording) = playback.recording.clone() else {
+                ui.weak("No recording loaded");
+                return;
+            };
+            ui.label(format!(
+                "{} events over {}, {} objects at the start",
+                recording.events.len(),
+                format_clock(recording.duration()),
+                recording.scene.objects.len()
+            ));
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Play from start").clicked() {
+                    playback.play();
+                }
+                let active = playback.is_active();
+                let pause_label = if playback.paused { "Resume" } else { "Pause" };
+                if ui
+                    .add_enabled(active, egui::Button::new(pause_label))
+                    .clicked()
+                {
+                    playback.paused = !playback.paused;
+                }
+                if ui.add_enabled(active, egui::Button::new("Stop")).clicked() {
+                    playback.stop();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Speed");
+                for speed in PLAYBACK_SPEEDS {
+                    if ui
+                        .selectable_label(playback.speed == speed, format!("{}x", speed))
+                        .clicked()
+                    {
+                        playback.set_speed(speed);
+                    }
+                }
+            });
+
+            let mut position = scrub.unwrap_or(playback.clock());
+            let label = format_clock(position);
+            let response = ui.add(
+                egui::Slider::new(&mut position, 0.0..=recording.duration())
+                    .show_value(false)
+                    .text(label),
+            );
+            if response.dragged() {
+                *scrub = Some(position);
+            } else if response.changed() || response.drag_stopped() {
+                *scrub = None;
+                playback.seek(position);
+            }
+            if playback.is_active() {
+                ui.weak(format!(
+                    "{} of {}, spawning is disabled",
+                    format_clock(playback.clock()),
+                    format_clock(recording.duration())
+                ));
+            }
+
+            let Some(report) = &playback.report else {
+                return;
+            };
+            ui.separator();
+            if report.deterministic() {
+                ui.label(format!("No drift across {} objects", report.compared));
+            } else {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "Drift: max {:.4}, mean {:.4} across {} objects",
+                        report.max, report.mean, report.compared
+                    ),
+                );
+                if let Some((id, drift)) = report.worst {
+                    ui.label(format!("Furthest off: object {} by {:.4}", id, drift));
+                }
+                if !report.missing.is_empty() {
+                    ui.label(format!("{} recorded objects missing", report.missing.len()));
+                }
+                if report.extra > 0 {
+                    ui.label(format!("{} objects not in the recording", report.extra));
+                }
+            }
+            if !report.faithful {
+                ui.weak("Played at another speed or scrubbed, some drift is expected");
+            }
+        },
+    );
+}
+
+pub fn register_playback_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Play recording from start", None, |world| {
+        world.resource_mut::<Playback>().play();
+    });
+    registry.register("Stop playback", None, |world| {
+        world.resource_mut::<Playback>().stop();
+    });
+}
+
+// Playback of recorded sessions
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playback>()
+            .add_systems(Startup, register_playback_commands)
+            .add_systems(EguiContextPass, playback_panel_system.in_set(UiSet::Panels));
+    }
+}
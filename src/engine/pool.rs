@@ -0,0 +1,356 @@
+use crate::engine::lifetime::Lifetime;
+use crate::engine::objects::{
+    GameObjectId, ObjectSnapshot, ShapeDimensions, ShapeType, SharedMaterial, set_material_color,
+    wake_body,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashMap;
+
+// Parked entities per shape unless configured otherwise
+pub const DEFAULT_POOL_SIZE: usize = 64;
+
+// Where parked entities wait, well below the ground and out of view
+const POOL_PARKING_SPOT: Vec3 = Vec3::new(0.0, -1000.0, 0.0);
+
+// On a parked entity, the dimensions its mesh and collider were built for
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PooledObject {
+    pub dimensions: ShapeDimensions,
+}
+
+// Despawned objects parked for reuse by spawn_entity_system, so high churn
+// spawning doesn't build a new mesh, material and collider every time. Parked
+// entities aren't in the GameObjectManager
+#[derive(Resource, Debug, Default)]
+pub struct EntityPool {
+    pub enabled: bool,
+    sizes: HashMap<ShapeType, usize>,
+    parked: HashMap<ShapeType, Vec<Entity>>,
+}
+
+impl EntityPool {
+    pub fn size(&self, shape_type: ShapeType) -> usize {
+        self.sizes
+            .get(&shape_type)
+            .copied()
+            .unwrap_or(DEFAULT_POOL_SIZE)
+    }
+
+    // Extra entities are despawned by trim_pool_system
+    pub fn set_size(&mut self, shape_type: ShapeType, size: usize) {
+        self.sizes.insert(shape_type, size);
+    }
+
+    // Imported meshes aren't parked, a reused one could hold a different mesh
+    pub fn has_room(&self, shape_type: ShapeType) -> bool {
+        self.enabled
+            && shape_type != ShapeType::CustomMesh
+            && self.parked_count(shape_type) < self.size(shape_type)
+    }
+
+    pub fn parked_count(&self, shape_type: ShapeType) -> usize {
+        self.parked.get(&shape_type).map_or(0, Vec::len)
+    }
+
+    // A parked entity to reuse, None when pooling is off or none are waiting
+    pub fn take(&mut self, shape_type: ShapeType) -> Option<Entity> {
+        if !self.enabled {
+            return None;
+        }
+        self.parked.get_mut(&shape_type)?.pop()
+    }
+
+    // Hide the entity and keep it for the next spawn of its shape. The caller
+    // has already taken it out of the manager
+    pub fn park(
+        &mut self,
+        commands: &mut Commands,
+        entity: Entity,
+        shape_type: ShapeType,
+        dimensions: ShapeDimensions,
+    ) {
+        commands
+            .entity(entity)
+            .remove::<(GameObjectId, Lifetime, ChildOf)>()
+            .insert((
+                PooledObject { dimensions },
+                Visibility::Hidden,
+                ColliderDisabled,
+                RigidBody::Fixed,
+                Velocity::zero(),
+                Transform::from_translation(POOL_PARKING_SPOT),
+            ));
+        self.parked.entry(shape_type).or_default().push(entity);
+    }
+
+    // Entities over their shape's size, or all of them with pooling off
+    fn drain_excess(&mut self) -> Vec<Entity> {
+        let mut excess = Vec::new();
+        for (shape_type, parked) in &mut self.parked {
+            let keep = if self.enabled {
+                self.sizes
+                    .get(shape_type)
+                    .copied()
+                    .unwrap_or(DEFAULT_POOL_SIZE)
+            } else {
+                0
+            };
+            if parked.len() > keep {
+                excess.extend(parked.drain(keep..));
+            }
+        }
+        excess
+    }
+}
+
+// A parked entity's pool entry, material and whether that's shared
+pub type ParkedObject<'a> = (&'a PooledObject, &'a MeshMaterial3d<StandardMaterial>, bool);
+
+// The pool along with the parked entities it hands out
+#[derive(SystemParam)]
+pub struct PooledEntities<'w, 's> {
+    pool: ResMut<'w, EntityPool>,
+    parked: Query<
+        'w,
+        's,
+        (
+            &'static PooledObject,
+            &'static MeshMaterial3d<StandardMaterial>,
+            Has<SharedMaterial>,
+        ),
+    >,
+}
+
+impl PooledEntities<'_, '_> {
+    // A parked entity of this shape, ready for reactivate_pooled
+    pub fn take(
+        &mut self,
+        commands: &mut Commands,
+        shape_type: ShapeType,
+    ) -> Option<(Entity, ParkedObject<'_>)> {
+        let entity = self.pool.take(shape_type)?;
+        match self.parked.get(entity) {
+            Ok(parked) => Some((entity, parked)),
+            Err(_) => {
+                // Out of the pool and not an object, nothing else would ever
+                // despawn it
+                commands.entity(entity).try_despawn();
+                None
+            }
+        }
+    }
+}
+
+// Bring a parked entity back as the object in snapshot, resetting everything a
+// fresh spawn would set. Mesh and collider are only rebuilt if the size changed
+pub fn reactivate_pooled(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    entity: Entity,
+    (pooled, material, shared): ParkedObject,
+    snapshot: &ObjectSnapshot,
+) {
+    set_material_color(
+        commands,
+        materials,
+        entity,
+        material,
+        shared,
+        snapshot.color,
+    );
+    let mut entity_commands = commands.entity(entity);
+    entity_commands
+        .remove::<(PooledObject, ColliderDisabled)>()
+        .insert((
+            snapshot.transform,
+            snapshot
+                .physics
+                .rigid_body(snapshot.physics_locked, snapshot.parent.is_some()),
+            snapshot.physics.components(),
+            Velocity::zero(),
+            Visibility::Inherited,
+        ));
+    if pooled.dimensions != snapshot.dimensions {
+        entity_commands.insert((
+            Mesh3d(
+                snapshot
+                    .shape_type
+                    .create_mesh_with(meshes, &snapshot.dimensions),
+            ),
+            snapshot
+                .shape_type
+                .create_collider_with(&snapshot.dimensions),
+        ));
+    }
+    wake_body(commands, entity);
+}
+
+// Despawn parked entities the pool no longer has room for
+pub fn trim_pool_system(mut commands: Commands, mut pool: ResMut<EntityPool>) {
+    for entity in pool.drain_excess() {
+        commands.entity(entity).try_despawn();
+    }
+}
+
+// Pool toggle and per-shape sizes for the World panel
+pub fn pool_settings_ui(ui: &mut egui::Ui, pool: &mut EntityPool) {
+    ui.checkbox(&mut pool.enabled, "Reuse despawned entities")
+        .on_hover_text("Park despawned objects and reuse them for new spawns of the same shape");
+    ui.add_enabled_ui(pool.enabled, |ui| {
+        egui::Grid::new("pool_sizes").show(ui, |ui| {
+            for shape_type in ShapeType::all() {
+                ui.label(shape_type.display_name());
+                let mut size = pool.size(shape_type);
+                if ui
+                    .add(egui::DragValue::new(&mut size).range(0..=10_000))
+                    .changed()
+                {
+                    pool.set_size(shape_type, size);
+                }
+                ui.weak(format!("{} parked", pool.parked_count(shape_type)));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+// Despawned objects kept for reuse
+pub struct EntityPoolPlugin;
+
+impl Plugin for EntityPoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityPool>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::world::CommandQueue;
+
+    // Park `count` new entities of each shape
+    fn parked(pool: &mut EntityPool, world: &mut World, shapes: &[(ShapeType, usize)]) {
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        for &(shape_type, count) in shapes {
+            for _ in 0..count {
+                let entity = commands.spawn_empty().id();
+                pool.park(
+                    &mut commands,
+                    entity,
+                    shape_type,
+                    shape_type.default_dimensions(),
+                );
+            }
+        }
+        queue.apply(world);
+    }
+
+    #[test]
+    fn room_follows_the_size_of_each_shape() {
+        let mut world = World::new();
+        let mut pool = EntityPool {
+            enabled: true,
+            ..default()
+        };
+        pool.set_size(ShapeType::Cube, 2);
+        pool.set_size(ShapeType::Ball, 0);
+        parked(&mut pool, &mut world, &[(ShapeType::Cube, 1)]);
+
+        assert!(pool.has_room(ShapeType::Cube));
+        assert!(!pool.has_room(ShapeType::Ball));
+        assert!(pool.has_room(ShapeType::Cone));
+        assert_eq!(pool.size(ShapeType::Cone), DEFAULT_POOL_SIZE);
+        parked(&mut pool, &mut world, &[(ShapeType::Cube, 1)]);
+        assert!(!pool.has_room(ShapeType::Cube));
+
+        pool.enabled = false;
+        assert!(!pool.has_room(ShapeType::Cone));
+    }
+
+    #[test]
+    fn imported_meshes_are_never_parked() {
+        let mut pool = EntityPool {
+            enabled: true,
+            ..default()
+        };
+        pool.set_size(ShapeType::CustomMesh, 100);
+        assert!(!pool.has_room(ShapeType::CustomMesh));
+        assert!(pool.has_room(ShapeType::Cube));
+    }
+
+    #[test]
+    fn parked_entities_wait_hidden_out_of_the_way() {
+        let mut world = World::new();
+        let mut pool = EntityPool {
+            enabled: true,
+            ..default()
+        };
+        parked(&mut pool, &mut world, &[(ShapeType::Ball, 1)]);
+        let entity = pool.take(ShapeType::Ball).unwrap();
+
+        let parked = world.entity(entity);
+        assert_eq!(
+            parked.get::<Transform>().unwrap().translation,
+            POOL_PARKING_SPOT
+        );
+        assert_eq!(parked.get::<Visibility>(), Some(&Visibility::Hidden));
+        assert!(parked.contains::<ColliderDisabled>());
+        assert_eq!(parked.get::<Velocity>(), Some(&Velocity::zero()));
+        assert!(!parked.contains::<GameObjectId>());
+        assert_eq!(
+            parked.get::<PooledObject>().unwrap().dimensions,
+            ShapeType::Ball.default_dimensions()
+        );
+        assert_eq!(pool.take(ShapeType::Ball), None);
+    }
+
+    #[test]
+    fn nothing_is_taken_with_pooling_off() {
+        let mut world = World::new();
+        let mut pool = EntityPool {
+            enabled: true,
+            ..default()
+        };
+        parked(&mut pool, &mut world, &[(ShapeType::Cube, 1)]);
+        pool.enabled = false;
+        assert_eq!(pool.take(ShapeType::Cube), None);
+        assert_eq!(pool.parked_count(ShapeType::Cube), 1);
+    }
+
+    #[test]
+    fn excess_is_drained_per_shape() {
+        let mut world = World::new();
+        let mut pool = EntityPool {
+            enabled: true,
+            ..default()
+        };
+        parked(
+            &mut pool,
+            &mut world,
+            &[
+                (ShapeType::Cube, 5),
+                (ShapeType::Ball, 3),
+                (ShapeType::Cone, 2),
+            ],
+        );
+        pool.set_size(ShapeType::Cube, 2);
+        pool.set_size(ShapeType::Ball, 3);
+
+        assert_eq!(pool.drain_excess().len(), 3);
+        assert_eq!(pool.parked_count(ShapeType::Cube), 2);
+        assert_eq!(pool.parked_count(ShapeType::Ball), 3);
+        assert_eq!(pool.parked_count(ShapeType::Cone), 2);
+        assert!(pool.drain_excess().is_empty());
+
+        pool.enabled = false;
+        assert_eq!(pool.drain_excess().len(), 7);
+        for shape_type in ShapeType::all() {
+            assert_eq!(pool.parked_count(shape_type), 0);
+        }
+    }
+}
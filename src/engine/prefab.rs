@@ -0,0 +1,573 @@
+use crate::engine::core::UiSet;
+use crate::engine::editor::{DimensionEditEvent, MaterialEditEvent, PhysicsEditEvent};
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::layout::PanelContext;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObject, GameObjectId, GameObjectManager, ObjectSpawner,
+    PhysicsProps, ShapeDimensions, ShapeType, SpawnRules, SpawnSource, insert_object_components,
+    random_spawn_position, unique_name,
+};
+use crate::engine::prefab_file::PrefabFileDialog;
+use crate::engine::selection::Selection;
+use crate::engine::serde_util;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+const PREFABS_FILE_NAME: &str = "prefabs.ron";
+
+// Everything about an object that its instances share. Position, name and
+// hierarchy belong to each instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    pub shape_type: ShapeType,
+    pub dimensions: ShapeDimensions,
+    #[serde(with = "serde_util::color")]
+    pub color: Color,
+    pub physics: PhysicsProps,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    // Custom mesh library name, needed for ShapeType::CustomMesh
+    #[serde(default)]
+    pub custom_mesh: Option<String>,
+}
+
+impl From<&GameObject> for Prefab {
+    fn from(obj: &GameObject) -> Self {
+        Self {
+            shape_type: obj.shape_type,
+            dimensions: obj.dimensions,
+            color: obj.color,
+            physics: obj.physics,
+            tags: obj.tags.iter().cloned().collect(),
+            custom_mesh: obj.custom_mesh.clone(),
+        }
+    }
+}
+
+// Named prefabs, saved next to the editor prefs whenever they change
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrefabLibrary {
+    prefabs: BTreeMap<String, Prefab>,
+}
+
+// Location of the prefab library in the platform config directory
+pub fn prefabs_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "Navi").map(|dirs| dirs.config_dir().join(PREFABS_FILE_NAME))
+}
+
+impl PrefabLibrary {
+    // Missing or unreadable files give an empty library
+    pub fn load() -> Self {
+        let Some(path) = prefabs_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str(&contents) {
+            Ok(library) => library,
+            Err(err) => {
+                warn!(
+                    "Ignoring corrupt prefab library {}: {}",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = prefabs_path().ok_or("no config directory available")?;
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, contents).map_err(|err| err.to_string())?;
+        info!("Saved {} prefabs to {}", self.prefabs.len(), path.display());
+        Ok(())
+    }
+
+    // Store a prefab under a trimmed name, returning the name and whether an
+    // existing definition was replaced
+    pub fn insert(&mut self, name: &str, prefab: Prefab) -> Option<(String, bool)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let replaced = self.prefabs.insert(name.to_string(), prefab).is_some();
+        Some((name.to_string(), replaced))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    // Prefabs by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Prefab)> {
+        self.prefabs
+            .iter()
+            .map(|(name, prefab)| (name.as_str(), prefab))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+
+    // Add prefabs from elsewhere, asking `resolve` what to do with each one
+    // whose name is taken
+    pub fn merge(
+        &mut self,
+        incoming: impl IntoIterator<Item = (String, Prefab)>,
+        resolve: impl Fn(&str) -> PrefabConflict,
+    ) -> PrefabMerge {
+        let mut merge = PrefabMerge::default();
+        for (name, prefab) in incoming {
+            let Some(name) = Some(name.trim().to_string()).filter(|name| !name.is_empty()) else {
+                continue;
+            };
+            if !self.prefabs.contains_key(&name) {
+                self.prefabs.insert(name.clone(), prefab);
+                merge.added.push(name);
+                continue;
+            }
+            match resolve(&name) {
+                PrefabConflict::Skip => merge.skipped.push(name),
+                PrefabConflict::Overwrite => {
+                    self.prefabs.insert(name.clone(), prefab);
+                    merge.overwritten.push(name);
+                }
+                PrefabConflict::Rename => {
+                    let renamed = unique_name(&name, |candidate| self.contains(candidate));
+                    self.prefabs.insert(renamed.clone(), prefab);
+                    merge.renamed.push((name, renamed));
+                }
+            }
+        }
+        merge
+    }
+}
+
+// What happens to an incoming prefab whose name is already in the library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefabConflict {
+    #[default]
+    Skip,
+    Overwrite,
+    // Stored as "name 2" or the next free number
+    Rename,
+}
+
+impl PrefabConflict {
+    pub fn all() -> [PrefabConflict; 3] {
+        [
+            PrefabConflict::Skip,
+            PrefabConflict::Overwrite,
+            PrefabConflict::Rename,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrefabConflict::Skip => "Skip",
+            PrefabConflict::Overwrite => "Overwrite",
+            PrefabConflict::Rename => "Keep both",
+        }
+    }
+}
+
+// Names by outcome of PrefabLibrary::merge
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefabMerge {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    // Incoming name and the name it was stored under
+    pub renamed: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+}
+
+impl PrefabMerge {
+    // Names that ended up in the library, as stored
+    pub fn stored(&self) -> impl Iterator<Item = &str> {
+        self.added
+            .iter()
+            .chain(&self.overwritten)
+            .map(String::as_str)
+            .chain(self.renamed.iter().map(|(_, renamed)| renamed.as_str()))
+    }
+}
+
+// Ids of the objects spawned from a prefab
+pub fn prefab_instances(game_manager: &GameObjectManager, prefab: &str) -> Vec<u32> {
+    game_manager
+        .iter()
+        .filter(|obj| obj.prefab.as_deref() == Some(prefab))
+        .map(|obj| obj.id)
+        .collect()
+}
+
+#[derive(Event, Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnPrefabEvent {
+    #[serde(default)]
+    pub source: SpawnSource,
+    pub prefab: String,
+    #[serde(with = "serde_util::vec3")]
+    pub position: Vec3,
+}
+
+// Bring every instance of a prefab in line with its current definition
+#[derive(Event, Debug, Clone)]
+pub struct UpdatePrefabInstancesEvent {
+    pub prefab: String,
+}
+
+pub fn spawn_prefab_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut spawn_events: EventReader<SpawnPrefabEvent>,
+    library: Res<PrefabLibrary>,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    SpawnRules {
+        snap,
+        naming,
+        limits,
+        ..
+    }: SpawnRules,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
+) {
+    let mut evicting = HashSet::new();
+    for event in spawn_events.read() {
+        let Some(prefab) = library.get(&event.prefab) else {
+            notifications.error(format!("No prefab named \"{}\"", event.prefab));
+            continue;
+        };
+        match limits.make_room(&game_manager, prefab.shape_type, 1, &evicting) {
+            Ok(victims) if victims.is_empty() => {}
+            Ok(victims) => {
+                evicting.extend(victims.iter().copied());
+                despawn_events.write(DespawnEntityEvent {
+                    target: DespawnTarget::Evicted(victims),
+                });
+            }
+            Err(err) => {
+                notifications.warn(format!("Cannot spawn {}: {}", event.prefab, err));
+                continue;
+            }
+        }
+
+        let position = snap.snap_position(event.position);
+        let entity = commands.spawn_empty().id();
+        let object_id = match game_manager.add_object(
+            entity,
+            prefab.shape_type,
+            position,
+            Some(event.prefab.clone()),
+            naming.unique_names,
+            time.elapsed_secs_f64(),
+        ) {
+            Ok(id) => id,
+            Err(err) => {
+                commands.entity(entity).despawn();
+                notifications.error(format!("Cannot spawn {}: {}", event.prefab, err));
+                continue;
+            }
+        };
+        let Some(obj) = game_manager.get_object_by_id_mut(object_id) else {
+            continue;
+        };
+        obj.color = prefab.color;
+        obj.dimensions = prefab.dimensions.validated(prefab.shape_type);
+        obj.physics = prefab.physics;
+        obj.tags = prefab.tags.iter().cloned().collect();
+        obj.custom_mesh = prefab.custom_mesh.clone();
+        obj.prefab = Some(event.prefab.clone());
+        obj.source = event.source;
+        let object_id_component = GameObjectId::from(&*obj);
+
+        let Some(snapshot) =
+            game_manager.snapshot(object_id, Transform::from_translation(position))
+        else {
+            continue;
+        };
+        insert_object_components(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            entity,
+            &snapshot,
+        );
+        commands.entity(entity).insert(object_id_component);
+
+        notifications.info(format!("Spawned {} from prefab", snapshot.name));
+        history.push(EditorCommand::Spawn(snapshot), time.elapsed_secs_f64());
+    }
+}
+
+// Colour, size and physics go through the inspector's edit events, tags are
+// manager only. Locked instances and ones of another shape are left alone
+pub fn update_prefab_instances_system(
+    mut update_events: EventReader<UpdatePrefabInstancesEvent>,
+    library: Res<PrefabLibrary>,
+    mut game_manager: ResMut<GameObjectManager>,
+    mut material_events: EventWriter<MaterialEditEvent>,
+    mut physics_events: EventWriter<PhysicsEditEvent>,
+    mut dimension_events: EventWriter<DimensionEditEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in update_events.read() {
+        let Some(prefab) = library.get(&event.prefab) else {
+            continue;
+        };
+
+        let mut updated = 0;
+        let mut skipped = 0;
+        for id in prefab_instances(&game_manager, &event.prefab) {
+            let Some(obj) = game_manager.get_object_by_id_mut(id) else {
+                continue;
+            };
+            if obj.locked || obj.shape_type != prefab.shape_type {
+                skipped += 1;
+                continue;
+            }
+            obj.tags = prefab.tags.iter().cloned().collect();
+            material_events.write(MaterialEditEvent {
+                entity: obj.entity(),
+                color: prefab.color,
+            });
+            physics_events.write(PhysicsEditEvent {
+                entity: obj.entity(),
+                props: prefab.physics,
+            });
+            dimension_events.write(DimensionEditEvent {
+                entity: obj.entity(),
+                dimensions: prefab.dimensions,
+            });
+            updated += 1;
+        }
+
+        let mut message = format!("Updated {} instances of {}", updated, event.prefab);
+        if skipped > 0 {
+            message.push_str(&format!(", skipped {} locked or reshaped", skipped));
+        }
+        notifications.info(message);
+    }
+}
+
+// Panel state kept between frames
+#[derive(Default)]
+pub struct PrefabPanelState {
+    new_name: String,
+    // Prefab whose definition just changed while it has instances in the scene
+    confirm_update: Option<String>,
+}
+
+// What the prefab panel asks for: instances spawned or updated, and the
+// import and export dialog
+#[derive(SystemParam)]
+pub struct PrefabRequests<'w> {
+    pub spawn_events: EventWriter<'w, SpawnPrefabEvent>,
+    pub update_events: EventWriter<'w, UpdatePrefabInstancesEvent>,
+    pub file_dialog: ResMut<'w, PrefabFileDialog>,
+}
+
+pub fn prefab_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    mut library: ResMut<PrefabLibrary>,
+    game_manager: Res<GameObjectManager>,
+    selection: Res<Selection>,
+    PrefabRequests {
+        mut spawn_events,
+        mut update_events,
+        mut file_dialog,
+    }: PrefabRequests,
+    mut notifications: ResMut<EditorNotifications>,
+    mut state: Local<PrefabPanelState>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let selected = selection
+        .primary()
+        .and_then(|entity| game_manager.get_object_by_entity(entity));
+    let mut saved = None;
+    let mut removed = None;
+
+    layout.show(
+        ctx,
+        "Prefabs",
+        egui::Window::new("Prefabs").default_width(260.0),
+        |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.new_name)
+                        .hint_text("Prefab name")
+                        .desired_width(120.0),
+                );
+                let save = ui
+                    .add_enabled(selected.is_some(), egui::Button::new("Save selected"))
+                    .on_hover_text(
+                        "Save the selected object's shape, size, colour, physics and tags",
+                    );
+                if save.clicked()
+                    && let Some(obj) = selected
+                {
+                    let name = if state.new_name.trim().is_empty() {
+                        obj.name.clone()
+                    } else {
+                        std::mem::take(&mut state.new_name)
+                    };
+                    saved = Some((name, Prefab::from(obj)));
+                }
+            });
+
+            if let Some(name) = state.confirm_update.clone() {
+                let instances = prefab_instances(&game_manager, &name).len();
+                ui.separator();
+                ui.label(format!(
+                    "\"{}\" changed. Update its {} instances?",
+                    name, instances
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Update instances").clicked() {
+                        update_events.write(UpdatePrefabInstancesEvent {
+                            prefab: name.clone(),
+                        });
+                        state.confirm_update = None;
+                    }
+                    if ui.button("Leave them").clicked() {
+                        state.confirm_update = None;
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!library.is_empty(), egui::Button::new("Export..."))
+                    .on_hover_text("Write prefabs to a file to share them")
+                    .clicked()
+                {
+                    file_dialog.open_export();
+                }
+                if ui.button("Import...").clicked() {
+                    file_dialog.open_import();
+                }
+            });
+            ui.separator();
+
+            if library.is_empty() {
+                ui.weak("No prefabs yet, select an object and save it");
+                return;
+            }
+
+            egui::Grid::new("prefab_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, prefab) in library.iter() {
+                        let instances = prefab_instances(&game_manager, name).len();
+                        ui.label(format!("{} ({})", name, prefab.shape_type.display_name()))
+                            .on_hover_text(format!("{} instances in the scene", instances));
+                        ui.horizontal(|ui| {
+                            if ui.small_button("Spawn").clicked() {
+                                spawn_events.write(SpawnPrefabEvent {
+                                    source: SpawnSource::ToolbarButton,
+                                    prefab: name.to_string(),
+                                    position: random_spawn_position(),
+                                });
+                            }
+                            if ui
+                                .add_enabled(
+                                    selected.is_some(),
+                                    egui::Button::new("Update").small(),
+                                )
+                                .on_hover_text("Replace the definition with the selected object")
+                                .clicked()
+                                && let Some(obj) = selected
+                            {
+                                saved = Some((name.to_string(), Prefab::from(obj)));
+                            }
+                            if ui
+                                .small_button("Delete")
+                                .on_hover_text("Instances stay in the scene")
+                                .clicked()
+                            {
+                                removed = Some(name.to_string());
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        },
+    );
+
+    let mut changed = false;
+    if let Some((name, prefab)) = saved {
+        match library.insert(&name, prefab) {
+            Some((name, replaced)) => {
+                if replaced && !prefab_instances(&game_manager, &name).is_empty() {
+                    state.confirm_update = Some(name.clone());
+                }
+                notifications.info(format!("Saved prefab {}", name));
+                changed = true;
+            }
+            None => notifications.error("Prefab name can't be empty"),
+        }
+    }
+    if let Some(name) = removed {
+        library.remove(&name);
+        if state.confirm_update.as_deref() == Some(name.as_str()) {
+            state.confirm_update = None;
+        }
+        changed = true;
+    }
+    if changed && let Err(err) = library.save() {
+        warn!("Failed to save prefabs: {}", err);
+        notifications.error(format!("Failed to save prefabs: {}", err));
+    }
+}
+
+// Prefabs, loaded from the prefab library file at startup
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PrefabLibrary::load())
+            .add_event::<SpawnPrefabEvent>()
+            .add_event::<UpdatePrefabInstancesEvent>()
+            .add_systems(EguiContextPass, prefab_panel_system.in_set(UiSet::Panels));
+    }
+}
@@ -0,0 +1,677 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::custom_mesh::{CustomMeshLibrary, ImportMeshEvent, MeshColliderKind};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::palette::CommandRegistry;
+use crate::engine::prefab::{Prefab, PrefabConflict, PrefabLibrary};
+use bevy::prelude::*;
+use bevy_egui::*;
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Bumped when the layout of .nvprefabs files changes
+pub const PREFAB_FILE_VERSION: u32 = 1;
+pub const PREFAB_FILE_EXTENSION: &str = "nvprefabs";
+
+// A prefab as shared between machines. The mesh path lets the importing
+// editor load the mesh itself when it hasn't got one by that name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedPrefab {
+    pub prefab: Prefab,
+    #[serde(default)]
+    pub mesh_path: Option<PathBuf>,
+}
+
+// Contents of a .nvprefabs file, RON like the library itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabFile {
+    pub version: u32,
+    pub prefabs: BTreeMap<String, SharedPrefab>,
+}
+
+impl PrefabFile {
+    // Prefabs by name, all of them when `names` is None. The second value
+    // lists meshes whose path can't be embedded because they aren't imported
+    pub fn from_library(
+        library: &PrefabLibrary,
+        meshes: &CustomMeshLibrary,
+        names: Option<&BTreeSet<String>>,
+    ) -> (Self, Vec<String>) {
+        let mut unknown_meshes = Vec::new();
+        let prefabs = library
+            .iter()
+            .filter(|(name, _)| names.is_none_or(|names| names.contains(*name)))
+            .map(|(name, prefab)| {
+                let mesh_path = prefab.custom_mesh.as_ref().and_then(|mesh| {
+                    let path = meshes.get(mesh).map(|entry| entry.path.clone());
+                    if path.is_none() {
+                        unknown_meshes.push(mesh.clone());
+                    }
+                    path
+                });
+                (
+                    name.to_string(),
+                    SharedPrefab {
+                        prefab: prefab.clone(),
+                        mesh_path,
+                    },
+                )
+            })
+            .collect();
+        (
+            Self {
+                version: PREFAB_FILE_VERSION,
+                prefabs,
+            },
+            unknown_meshes,
+        )
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let file: Self = ron::from_str(&contents).map_err(|err| err.to_string())?;
+        if file.version > PREFAB_FILE_VERSION {
+            return Err(format!(
+                "written by a newer editor (version {}, this one reads up to {})",
+                file.version, PREFAB_FILE_VERSION
+            ));
+        }
+        Ok(file)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+}
+
+// Documents/prefabs.nvprefabs, or the working directory without one
+pub fn default_prefab_file_path() -> PathBuf {
+    let name = format!("prefabs.{}", PREFAB_FILE_EXTENSION);
+    UserDirs::new()
+        .and_then(|dirs| dirs.document_dir().map(|dir| dir.join(&name)))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+// Write prefabs to a .nvprefabs file, every one when `names` is None
+#[derive(Event, Debug, Clone)]
+pub struct ExportPrefabsEvent {
+    pub path: PathBuf,
+    pub names: Option<BTreeSet<String>>,
+}
+
+pub fn export_prefabs_system(
+    mut export_events: EventReader<ExportPrefabsEvent>,
+    library: Res<PrefabLibrary>,
+    meshes: Res<CustomMeshLibrary>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    for event in export_events.read() {
+        let (file, unknown_meshes) =
+            PrefabFile::from_library(&library, &meshes, event.names.as_ref());
+        match file.write(&event.path) {
+            Ok(()) => {
+                info!(
+                    "Exported {} prefabs to {}",
+                    file.prefabs.len(),
+                    event.path.display()
+                );
+                notifications.info_with_link(
+                    format!(
+                        "Exported {} prefabs to {}",
+                        file.prefabs.len(),
+                        event.path.display()
+                    ),
+                    &event.path,
+                );
+                if !unknown_meshes.is_empty() {
+                    notifications.warn(format!(
+                        "Meshes not imported this session, their files aren't in the export: {}",
+                        unknown_meshes.join(", ")
+                    ));
+                }
+            }
+            Err(err) => {
+                warn!("Cannot export prefabs to {}: {}", event.path.display(), err);
+                notifications.error(format!("Cannot export prefabs: {}", err));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefabFileAction {
+    #[default]
+    Export,
+    Import,
+}
+
+// A read file waiting for its name conflicts to be settled
+struct PendingImport {
+    path: PathBuf,
+    file: PrefabFile,
+    // Applied to every conflict, individual ones can differ
+    all: PrefabConflict,
+    choices: BTreeMap<String, PrefabConflict>,
+}
+
+#[derive(Resource, Default)]
+pub struct PrefabFileDialog {
+    pub open: bool,
+    pub action: PrefabFileAction,
+    pub path: String,
+    // Prefabs left out of the export
+    excluded: BTreeSet<String>,
+    pending: Option<PendingImport>,
+}
+
+impl PrefabFileDialog {
+    fn open_for(&mut self, action: PrefabFileAction) {
+        if self.path.is_empty() {
+            self.path = default_prefab_file_path().display().to_string();
+        }
+        self.action = action;
+        self.pending = None;
+        self.open = true;
+    }
+
+    pub fn open_export(&mut self) {
+        self.open_for(PrefabFileAction::Export);
+    }
+
+    pub fn open_import(&mut self) {
+        self.open_for(PrefabFileAction::Import);
+    }
+}
+
+// Bring in the meshes imported prefabs use. Ones already in the library are
+// kept, missing files are reported
+fn import_prefab_meshes<'a>(
+    prefabs: impl Iterator<Item = &'a SharedPrefab>,
+    meshes: &CustomMeshLibrary,
+    import_events: &mut EventWriter<ImportMeshEvent>,
+    notifications: &mut EditorNotifications,
+) {
+    let mut seen = BTreeSet::new();
+    for shared in prefabs {
+        let Some(mesh) = &shared.prefab.custom_mesh else {
+            continue;
+        };
+        if meshes.contains(mesh) || !seen.insert(mesh.clone()) {
+            continue;
+        }
+        match &shared.mesh_path {
+            Some(path) if path.exists() => {
+                import_events.write(ImportMeshEvent {
+                    path: path.clone(),
+                    name: Some(mesh.clone()),
+                    collider: MeshColliderKind::default(),
+                });
+            }
+            Some(path) => {
+                warn!("Prefab mesh {} not found at {}", mesh, path.display());
+                notifications.warn(format!(
+                    "Mesh \"{}\" isn't at {} on this machine, import it to use its prefabs",
+                    mesh,
+                    path.display()
+                ));
+            }
+            None => {
+                notifications.warn(format!(
+                    "Mesh \"{}\" came without a file, import it to use its prefabs",
+                    mesh
+                ));
+            }
+        }
+    }
+}
+
+pub fn prefab_file_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<PrefabFileDialog>,
+    mut library: ResMut<PrefabLibrary>,
+    meshes: Res<CustomMeshLibrary>,
+    mut export_events: EventWriter<ExportPrefabsEvent>,
+    mut import_events: EventWriter<ImportMeshEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let title = match dialog.action {
+        PrefabFileAction::Export => "Export prefab library",
+        PrefabFileAction::Import => "Import prefab library",
+    };
+    let mut imported = false;
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            let dialog = &mut *dialog;
+            if let Some(pending) = &mut dialog.pending {
+                let conflicts: Vec<&String> = pending
+                    .file
+                    .prefabs
+                    .keys()
+                    .filter(|name| library.contains(name))
+                    .collect();
+                ui.label(format!(
+                    "{} prefabs in {}, {} new",
+                    pending.file.prefabs.len(),
+                    pending.path.display(),
+                    pending.file.prefabs.len() - conflicts.len()
+                ));
+                if !conflicts.is_empty() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} names already taken", conflicts.len()));
+                        for conflict in PrefabConflict::all() {
+                            if ui
+                                .selectable_label(pending.all == conflict, conflict.label())
+                                .on_hover_text("Apply to every conflict")
+                                .clicked()
+                            {
+                                pending.all = conflict;
+                                pending.choices.clear();
+                            }
+                        }
+                    });
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("prefab_conflicts")
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for name in conflicts {
+                                        ui.label(name);
+                                        let mut choice = pending
+                                            .choices
+                                            .get(name)
+                                            .copied()
+                                            .unwrap_or(pending.all);
+                                        ui.horizontal(|ui| {
+                                            for conflict in PrefabConflict::all() {
+                                                ui.selectable_value(
+                                                    &mut choice,
+                                                    conflict,
+                                                    conflict.label(),
+                                                );
+                                            }
+                                        });
+                                        if choice != pending.all {
+                                            pending.choices.insert(name.clone(), choice);
+                                        } else {
+                                            pending.choices.remove(name);
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        imported = true;
+                    }
+                    if ui.button("Back").clicked() {
+                        dialog.pending = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        dialog.open = false;
+                    }
+                });
+                return;
+            }
+
+            if dialog.action == PrefabFileAction::Export {
+                if library.is_empty() {
+                    ui.weak("The prefab library is empty");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (name, _) in library.iter() {
+                            let mut included = !dialog.excluded.contains(name);
+                            if ui.checkbox(&mut included, name).changed() {
+                                if included {
+                                    dialog.excluded.remove(name);
+                                } else {
+                                    dialog.excluded.insert(name.to_string());
+                                }
+                            }
+                        }
+                    });
+                ui.separator();
+            }
+
+            ui.label(format!("File (.{})", PREFAB_FILE_EXTENSION));
+            ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(360.0));
+            let path = dialog.path.trim().to_string();
+            ui.horizontal(|ui| {
+                match dialog.action {
+                    PrefabFileAction::Export => {
+                        let names: BTreeSet<String> = library
+                            .iter()
+                            .map(|(name, _)| name.to_string())
+                            .filter(|name| !dialog.excluded.contains(name))
+                            .collect();
+                        let ready = !path.is_empty() && !names.is_empty();
+                        if ui
+                            .add_enabled(
+                                ready,
+                                egui::Button::new(format!("Export {} prefabs", names.len())),
+                            )
+                            .clicked()
+                        {
+                            let names = (names.len() < library.len()).then_some(names);
+                            export_events.write(ExportPrefabsEvent {
+                                path: PathBuf::from(&path),
+                                names,
+                            });
+                            dialog.open = false;
+                        }
+                    }
+                    PrefabFileAction::Import => {
+                        if ui
+                            .add_enabled(!path.is_empty(), egui::Button::new("Read"))
+                            .clicked()
+                        {
+                            let path = PathBuf::from(&path);
+                            match PrefabFile::read(&path) {
+                                Ok(file) => {
+                                    dialog.pending = Some(PendingImport {
+                                        path,
+                                        file,
+                                        all: PrefabConflict::default(),
+                                        choices: BTreeMap::new(),
+                                    });
+                                }
+                                Err(err) => {
+                                    warn!("Cannot read prefabs {}: {}", path.display(), err);
+                                    notifications.error(format!("Cannot read prefabs: {}", err));
+                                }
+                            }
+                        }
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.open = false;
+                }
+            });
+        });
+
+    if !imported {
+        return;
+    }
+    let Some(pending) = dialog.pending.take() else {
+        return;
+    };
+    dialog.open = false;
+
+    let merge = library.merge(
+        pending
+            .file
+            .prefabs
+            .iter()
+            .map(|(name, shared)| (name.clone(), shared.prefab.clone())),
+        |name| pending.choices.get(name).copied().unwrap_or(pending.all),
+    );
+    let skipped: BTreeSet<&String> = merge.skipped.iter().collect();
+    import_prefab_meshes(
+        pending
+            .file
+            .prefabs
+            .iter()
+            .filter(|(name, _)| !skipped.contains(name))
+            .map(|(_, shared)| shared),
+        &meshes,
+        &mut import_events,
+        &mut notifications,
+    );
+
+    info!(
+        "Imported prefabs from {}: {} added, {} overwritten, {} renamed, {} skipped",
+        pending.path.display(),
+        merge.added.len(),
+        merge.overwritten.len(),
+        merge.renamed.len(),
+        merge.skipped.len()
+    );
+    notifications.info(format!(
+        "Imported {} prefabs, {} skipped",
+        merge.stored().count(),
+        merge.skipped.len()
+    ));
+    if let Err(err) = library.save() {
+        warn!("Failed to save prefabs: {}", err);
+        notifications.error(format!("Failed to save prefabs: {}", err));
+    }
+}
+
+pub fn register_prefab_file_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Export prefab library...", None, |world| {
+        world.resource_mut::<PrefabFileDialog>().open_export();
+    });
+    registry.register("Import prefab library...", None, |world| {
+        world.resource_mut::<PrefabFileDialog>().open_import();
+    });
+}
+
+// Import and export of prefab files
+pub struct PrefabFilePlugin;
+
+impl Plugin for PrefabFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportPrefabsEvent>()
+            .init_resource::<PrefabFileDialog>()
+            .add_systems(Startup, register_prefab_file_commands)
+            .add_systems(
+                EguiContextPass,
+                prefab_file_dialog_system.in_set(UiSet::Popups),
+            )
+            .add_systems(Update, export_prefabs_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::{BodyKind, PhysicsProps, ShapeType};
+    use crate::engine::prefab::PrefabMerge;
+
+    fn prefab(shape_type: ShapeType, body: BodyKind) -> Prefab {
+        Prefab {
+            shape_type,
+            dimensions: shape_type.default_dimensions(),
+            color: Color::srgba(0.25, 0.5, 0.75, 1.0),
+            physics: PhysicsProps { body, ..default() },
+            tags: BTreeSet::from(["shared".to_string()]),
+            custom_mesh: None,
+        }
+    }
+
+    fn library(prefabs: &[(&str, Prefab)]) -> PrefabLibrary {
+        let mut library = PrefabLibrary::default();
+        for (name, prefab) in prefabs {
+            library.insert(name, prefab.clone()).unwrap();
+        }
+        library
+    }
+
+    // Export from one library, write and read the file, import into another
+    fn export_then_import(
+        from: &PrefabLibrary,
+        into: &mut PrefabLibrary,
+        resolve: impl Fn(&str) -> PrefabConflict,
+    ) -> PrefabMerge {
+        let path = std::env::temp_dir()
+            .join(format!(
+                "navi-prefab-test-{:?}",
+                std::thread::current().id()
+            ))
+            .join(format!("library.{}", PREFAB_FILE_EXTENSION));
+        let (file, unknown_meshes) =
+            PrefabFile::from_library(from, &CustomMeshLibrary::default(), None);
+        assert!(unknown_meshes.is_empty());
+        file.write(&path).unwrap();
+        let read = PrefabFile::read(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.version, PREFAB_FILE_VERSION);
+        into.merge(
+            read.prefabs
+                .into_iter()
+                .map(|(name, shared)| (name, shared.prefab)),
+            resolve,
+        )
+    }
+
+    fn exported() -> PrefabLibrary {
+        library(&[
+            ("Crate", prefab(ShapeType::Cube, BodyKind::Dynamic)),
+            ("Pillar", prefab(ShapeType::Cylinder, BodyKind::Fixed)),
+            ("Pillar 2", prefab(ShapeType::Cylinder, BodyKind::Kinematic)),
+        ])
+    }
+
+    // Already has a Crate and a Pillar of its own
+    fn existing() -> PrefabLibrary {
+        library(&[
+            ("Crate", prefab(ShapeType::Ball, BodyKind::Fixed)),
+            ("Pillar", prefab(ShapeType::Cone, BodyKind::Dynamic)),
+        ])
+    }
+
+    #[test]
+    fn an_empty_library_takes_everything() {
+        let from = exported();
+        let mut into = PrefabLibrary::default();
+        let merge = export_then_import(&from, &mut into, |name| panic!("{} conflicted", name));
+
+        assert_eq!(merge.added, ["Crate", "Pillar", "Pillar 2"]);
+        assert!(merge.overwritten.is_empty() && merge.renamed.is_empty());
+        assert!(merge.skipped.is_empty());
+        for (name, prefab) in from.iter() {
+            assert_eq!(into.get(name), Some(prefab), "{}", name);
+        }
+    }
+
+    #[test]
+    fn skipped_conflicts_keep_the_existing_prefab() {
+        let from = exported();
+        let mut into = existing();
+        let merge = export_then_import(&from, &mut into, |_| PrefabConflict::Skip);
+
+        assert_eq!(merge.skipped, ["Crate", "Pillar"]);
+        assert_eq!(merge.added, ["Pillar 2"]);
+        assert_eq!(into.len(), 3);
+        assert_eq!(into.get("Crate"), existing().get("Crate"));
+        assert_eq!(into.get("Pillar"), existing().get("Pillar"));
+        assert_eq!(into.get("Pillar 2"), from.get("Pillar 2"));
+    }
+
+    #[test]
+    fn overwritten_conflicts_take_the_imported_prefab() {
+        let from = exported();
+        let mut into = existing();
+        let merge = export_then_import(&from, &mut into, |_| PrefabConflict::Overwrite);
+
+        assert_eq!(merge.overwritten, ["Crate", "Pillar"]);
+        assert_eq!(merge.added, ["Pillar 2"]);
+        assert_eq!(into.len(), 3);
+        for (name, prefab) in from.iter() {
+            assert_eq!(into.get(name), Some(prefab), "{}", name);
+        }
+    }
+
+    #[test]
+    fn renamed_conflicts_keep_both() {
+        let from = exported();
+        let mut into = existing();
+        let merge = export_then_import(&from, &mut into, |_| PrefabConflict::Rename);
+
+        // The renamed Pillar takes "Pillar 2" before the incoming one arrives,
+        // which then conflicts in turn
+        assert_eq!(merge.added, Vec::<String>::new());
+        assert_eq!(
+            merge.renamed,
+            [
+                ("Crate".to_string(), "Crate 2".to_string()),
+                ("Pillar".to_string(), "Pillar 2".to_string()),
+                ("Pillar 2".to_string(), "Pillar 2 2".to_string()),
+            ]
+        );
+        assert_eq!(into.len(), 5);
+        assert_eq!(into.get("Crate"), existing().get("Crate"));
+        assert_eq!(into.get("Crate 2"), from.get("Crate"));
+        assert_eq!(into.get("Pillar"), existing().get("Pillar"));
+        assert_eq!(into.get("Pillar 2"), from.get("Pillar"));
+        assert_eq!(into.get("Pillar 2 2"), from.get("Pillar 2"));
+        let stored: Vec<&str> = merge.stored().collect();
+        assert_eq!(stored, ["Crate 2", "Pillar 2", "Pillar 2 2"]);
+    }
+
+    #[test]
+    fn conflicts_are_resolved_one_by_one() {
+        let from = exported();
+        let mut into = existing();
+        let merge = export_then_import(&from, &mut into, |name| match name {
+            "Crate" => PrefabConflict::Overwrite,
+            _ => PrefabConflict::Rename,
+        });
+
+        assert_eq!(merge.overwritten, ["Crate"]);
+        assert_eq!(merge.renamed.len(), 2);
+        assert_eq!(into.get("Crate"), from.get("Crate"));
+        assert_eq!(into.get("Pillar"), existing().get("Pillar"));
+    }
+
+    #[test]
+    fn exports_name_the_meshes_they_cannot_embed() {
+        let mut teapot = prefab(ShapeType::CustomMesh, BodyKind::Dynamic);
+        teapot.custom_mesh = Some("teapot".to_string());
+        let from = library(&[
+            ("Teapot", teapot),
+            ("Crate", prefab(ShapeType::Cube, BodyKind::Dynamic)),
+        ]);
+        let names = BTreeSet::from(["Teapot".to_string()]);
+        let (file, unknown_meshes) =
+            PrefabFile::from_library(&from, &CustomMeshLibrary::default(), Some(&names));
+
+        assert_eq!(unknown_meshes, ["teapot"]);
+        let exported: Vec<&str> = file.prefabs.keys().map(String::as_str).collect();
+        assert_eq!(exported, ["Teapot"]);
+        assert_eq!(file.prefabs["Teapot"].mesh_path, None);
+    }
+
+    #[test]
+    fn files_from_a_newer_editor_are_refused() {
+        let path = std::env::temp_dir().join(format!(
+            "navi-prefab-newer-{}.{}",
+            std::process::id(),
+            PREFAB_FILE_EXTENSION
+        ));
+        let file = PrefabFile {
+            version: PREFAB_FILE_VERSION + 1,
+            prefabs: BTreeMap::new(),
+        };
+        file.write(&path).unwrap();
+        let read = PrefabFile::read(&path);
+        fs::remove_file(&path).unwrap();
+
+        let err = read.unwrap_err();
+        assert!(err.starts_with("written by a newer editor"), "{}", err);
+    }
+}
@@ -0,0 +1,522 @@
+use crate::engine::autosave::{Autosave, AutosaveSettings, autosave_settings_ui};
+use crate::engine::bookmarks::CameraBookmarks;
+use crate::engine::camera::CameraSettings;
+use crate::engine::core::UiSet;
+use crate::engine::input::InputBindings;
+use crate::engine::layout::{EditorLayout, PanelContext};
+use crate::engine::notify::{EditorNotifications, NotificationSettings};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::SceneFileDialog;
+use crate::engine::screenshot::{ScreenshotSettings, screenshot_settings_ui};
+use crate::engine::snap::SnapSettings;
+use crate::engine::thumbnails::RecentScenes;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PREFS_FILE_NAME: &str = "editor.toml";
+// A corrupt file is renamed to this extension
+const CORRUPT_EXTENSION: &str = "toml.corrupt";
+const PREFS_SAVE_DELAY_SECS: f32 = 1.0;
+
+pub const MIN_UI_SCALE: f32 = 0.75;
+pub const MAX_UI_SCALE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UiTheme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl UiTheme {
+    pub fn egui_theme(&self) -> egui::Theme {
+        match self {
+            UiTheme::Dark => egui::Theme::Dark,
+            UiTheme::Light => egui::Theme::Light,
+        }
+    }
+}
+
+// Look of the editor UI, the scale multiplies the window's own DPI scale
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiPrefs {
+    pub scale: f32,
+    pub theme: UiTheme,
+}
+
+impl Default for UiPrefs {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            theme: UiTheme::Dark,
+        }
+    }
+}
+
+impl UiPrefs {
+    pub fn clamped(&self) -> Self {
+        let scale = if self.scale.is_finite() {
+            self.scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+        } else {
+            1.0
+        };
+        Self { scale, ..*self }
+    }
+}
+
+// Everything stored in the editor preferences file
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EditorPrefs {
+    pub layout: EditorLayout,
+    pub camera: CameraSettings,
+    pub bindings: InputBindings,
+    pub ui: UiPrefs,
+    pub notifications: NotificationSettings,
+    pub bookmarks: CameraBookmarks,
+    pub autosave: AutosaveSettings,
+    // Scene open when the editor closed, checked for a newer autosave on start
+    pub last_scene: Option<PathBuf>,
+    pub recent_scenes: RecentScenes,
+    pub screenshots: ScreenshotSettings,
+    pub snap: SnapSettings,
+}
+
+// Location of the preferences file in the platform config directory
+pub fn prefs_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "Navi").map(|dirs| dirs.config_dir().join(PREFS_FILE_NAME))
+}
+
+impl EditorPrefs {
+    // Missing files fall back to defaults. A file that can't be parsed is
+    // moved aside so the next save doesn't overwrite it, the message says
+    // where it went
+    pub fn load() -> (Self, Option<String>) {
+        match prefs_path() {
+            Some(path) => Self::load_from(&path),
+            None => (Self::default(), None),
+        }
+    }
+
+    fn load_from(path: &Path) -> (Self, Option<String>) {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return (Self::default(), None);
+        };
+
+        match Self::parse(&contents) {
+            Ok(prefs) => (prefs, None),
+            Err(err) => {
+                warn!("Ignoring corrupt editor prefs {}: {}", path.display(), err);
+                let aside = path.with_extension(CORRUPT_EXTENSION);
+                let message = match fs::rename(path, &aside) {
+                    Ok(()) => format!(
+                        "Preferences were unreadable and have been reset, the old file is {}",
+                        aside.display()
+                    ),
+                    Err(err) => {
+                        warn!("Failed to move corrupt prefs aside: {}", err);
+                        "Preferences were unreadable and have been reset".to_string()
+                    }
+                };
+                (Self::default(), Some(message))
+            }
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    fn write(contents: &str) {
+        let Some(path) = prefs_path() else {
+            warn!("No config directory available, editor prefs not saved");
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, contents));
+        match result {
+            Ok(()) => info!("Saved editor prefs to {}", path.display()),
+            Err(err) => warn!("Failed to save editor prefs to {}: {}", path.display(), err),
+        }
+    }
+
+    pub fn save(&self) {
+        match self.to_toml() {
+            Ok(contents) => Self::write(&contents),
+            Err(err) => warn!("Failed to serialize editor prefs: {}", err),
+        }
+    }
+
+    pub fn delete() {
+        if let Some(path) = prefs_path().filter(|path| path.exists())
+            && let Err(err) = fs::remove_file(&path)
+        {
+            warn!("Failed to delete editor prefs {}: {}", path.display(), err);
+        }
+    }
+}
+
+// Debounces writes of the prefs file. The resources are compared as
+// serialized text once a second, a change is written once it has held still
+// for a whole check so dragging a slider doesn't write on every step
+#[derive(Resource)]
+pub struct PrefsSaver {
+    // Contents of the file as last read or written
+    written: Option<String>,
+    // Differs from `written`, waiting for the next check
+    pending: Option<String>,
+    timer: Timer,
+}
+
+impl PrefsSaver {
+    pub fn new(prefs: &EditorPrefs) -> Self {
+        Self {
+            written: prefs.to_toml().ok(),
+            pending: None,
+            timer: Timer::from_seconds(PREFS_SAVE_DELAY_SECS, TimerMode::Repeating),
+        }
+    }
+
+    // Whether the file holds what the editor itself wrote last
+    pub fn wrote(&self, contents: &str) -> bool {
+        self.written.as_deref() == Some(contents)
+    }
+}
+
+// Every resource the prefs file is written from
+#[derive(SystemParam)]
+pub struct SavedPrefs<'w> {
+    layout: Res<'w, EditorLayout>,
+    camera: Res<'w, CameraSettings>,
+    bindings: Res<'w, InputBindings>,
+    ui: Res<'w, UiPrefs>,
+    notifications: Res<'w, EditorNotifications>,
+    bookmarks: Res<'w, CameraBookmarks>,
+    autosave_settings: Res<'w, AutosaveSettings>,
+    autosave: Res<'w, Autosave>,
+    scene_dialog: Res<'w, SceneFileDialog>,
+    recent_scenes: Res<'w, RecentScenes>,
+    screenshots: Res<'w, ScreenshotSettings>,
+    snap: Res<'w, SnapSettings>,
+}
+
+impl SavedPrefs<'_> {
+    pub fn prefs(&self) -> EditorPrefs {
+        EditorPrefs {
+            layout: self.layout.clone(),
+            camera: self.camera.clone(),
+            bindings: self.bindings.clone(),
+            ui: self.ui.clone(),
+            notifications: self.notifications.settings.clone(),
+            bookmarks: self.bookmarks.clone(),
+            autosave: self.autosave_settings.clone(),
+            last_scene: self
+                .scene_dialog
+                .current
+                .clone()
+                .or_else(|| self.autosave.last_scene.clone()),
+            recent_scenes: self.recent_scenes.clone(),
+            screenshots: self.screenshots.clone(),
+            snap: self.snap.clone(),
+        }
+    }
+}
+
+// Write prefs a moment after they change, and right away when the app is
+// closing
+pub fn save_prefs_system(
+    mut exit_events: EventReader<AppExit>,
+    time: Res<Time>,
+    mut saver: ResMut<PrefsSaver>,
+    saved: SavedPrefs,
+) {
+    let exiting = exit_events.read().next().is_some();
+    if !saver.timer.tick(time.delta()).just_finished() && !exiting {
+        return;
+    }
+
+    let prefs = saved.prefs();
+    let contents = match prefs.to_toml() {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("Failed to serialize editor prefs: {}", err);
+            return;
+        }
+    };
+
+    if saver.written.as_ref() == Some(&contents) {
+        saver.pending = None;
+    } else if exiting || saver.pending.as_ref() == Some(&contents) {
+        EditorPrefs::write(&contents);
+        saver.written = Some(contents);
+        saver.pending = None;
+    } else {
+        saver.pending = Some(contents);
+    }
+}
+
+// Put every setting back to its default. Bookmarks and the recent scenes are
+// kept, they're the user's data rather than preferences
+#[derive(Event, Debug, Clone)]
+pub struct ResetPrefsEvent;
+
+// The settings a reset puts back, notification settings aside
+#[derive(SystemParam)]
+pub struct ResetPrefs<'w> {
+    pub layout: ResMut<'w, EditorLayout>,
+    pub camera: ResMut<'w, CameraSettings>,
+    pub bindings: ResMut<'w, InputBindings>,
+    pub ui: ResMut<'w, UiPrefs>,
+    pub autosave: ResMut<'w, AutosaveSettings>,
+    pub screenshots: ResMut<'w, ScreenshotSettings>,
+    pub snap: ResMut<'w, SnapSettings>,
+}
+
+pub fn reset_prefs_system(
+    mut reset_events: EventReader<ResetPrefsEvent>,
+    mut notifications: ResMut<EditorNotifications>,
+    ResetPrefs {
+        mut layout,
+        mut camera,
+        mut bindings,
+        mut ui,
+        mut autosave,
+        mut screenshots,
+        mut snap,
+    }: ResetPrefs,
+) {
+    if reset_events.read().last().is_none() {
+        return;
+    }
+
+    layout.reset();
+    *camera = CameraSettings::default();
+    *bindings = InputBindings::default();
+    *ui = UiPrefs::default();
+    notifications.settings = NotificationSettings::default();
+    *autosave = AutosaveSettings::default();
+    *screenshots = ScreenshotSettings::default();
+    *snap = SnapSettings::default();
+    notifications.info("Preferences reset to their defaults");
+}
+
+// Push UI prefs into egui. The camera is spawned with the saved scale so the
+// first frame is already scaled, this only handles later edits and the theme
+pub fn apply_ui_prefs_system(
+    mut contexts: EguiContexts,
+    prefs: Res<UiPrefs>,
+    mut settings: Query<&mut EguiContextSettings>,
+) {
+    if !prefs.is_changed() {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    ctx.set_theme(prefs.theme.egui_theme());
+    for mut settings in &mut settings {
+        if settings.scale_factor != prefs.scale {
+            settings.scale_factor = prefs.scale;
+        }
+    }
+}
+
+pub fn preferences_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    mut prefs: ResMut<UiPrefs>,
+    mut pending_scale: Local<Option<f32>>,
+    mut notifications: ResMut<EditorNotifications>,
+    mut autosave: ResMut<AutosaveSettings>,
+    mut screenshots: ResMut<ScreenshotSettings>,
+    mut reset_events: EventWriter<ResetPrefsEvent>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Preferences",
+        egui::Window::new("Preferences").default_width(240.0),
+        |ui| {
+            // Edit a copy so the resource is only marked changed on real edits
+            let mut edited = prefs.clone();
+
+            // Rescaling mid-drag would move the slider under the cursor, so commit on release
+            let mut scale = pending_scale.unwrap_or(edited.scale);
+            let response = ui.add(
+                egui::Slider::new(&mut scale, MIN_UI_SCALE..=MAX_UI_SCALE)
+                    .step_by(0.05)
+                    .text("UI scale"),
+            );
+            if response.dragged() {
+                *pending_scale = Some(scale);
+            } else {
+                *pending_scale = None;
+                edited.scale = scale;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Theme");
+                ui.selectable_value(&mut edited.theme, UiTheme::Dark, "Dark");
+                ui.selectable_value(&mut edited.theme, UiTheme::Light, "Light");
+            });
+
+            ui.separator();
+            let mut toasts = notifications.settings.clone();
+            ui.add(
+                egui::Slider::new(&mut toasts.duration_secs, 1.0..=15.0)
+                    .suffix(" s")
+                    .text("Toast duration"),
+            );
+            ui.add(egui::Slider::new(&mut toasts.max_visible, 1..=10).text("Max toasts"));
+
+            ui.separator();
+            let mut autosave_edited = autosave.clone();
+            autosave_settings_ui(ui, &mut autosave_edited);
+
+            ui.separator();
+            let mut screenshots_edited = screenshots.clone();
+            screenshot_settings_ui(ui, &mut screenshots_edited);
+
+            ui.separator();
+            if ui
+                .button("Reset all preferences")
+                .on_hover_text("Layout, keybindings, camera, snapping and everything above")
+                .clicked()
+            {
+                reset_events.write(ResetPrefsEvent);
+            }
+            if ui.button("Reset").clicked() {
+                edited = UiPrefs::default();
+                toasts = NotificationSettings::default();
+                autosave_edited = AutosaveSettings::default();
+                screenshots_edited = ScreenshotSettings::default();
+            }
+
+            if edited != *prefs {
+                *prefs = edited;
+            }
+            if toasts != notifications.settings {
+                notifications.settings = toasts;
+            }
+            if autosave_edited != *autosave {
+                *autosave = autosave_edited;
+            }
+            if screenshots_edited != *screenshots {
+                *screenshots = screenshots_edited;
+            }
+        },
+    );
+}
+
+pub fn register_prefs_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Reset all preferences", None, |world| {
+        world.send_event(ResetPrefsEvent);
+    });
+}
+
+// Applies, resets and saves the prefs. They're loaded before the app is built,
+// so run inserts them
+pub struct PrefsPlugin;
+
+impl Plugin for PrefsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetPrefsEvent>()
+            .add_systems(Startup, register_prefs_commands)
+            .add_systems(EguiContextPass, apply_ui_prefs_system.in_set(UiSet::Prefs))
+            .add_systems(
+                EguiContextPass,
+                preferences_panel_system.in_set(UiSet::Panels),
+            )
+            .add_systems(Last, (reset_prefs_system, save_prefs_system).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::input::InputAction;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("navi-prefs-test-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn prefs_survive_a_round_trip_through_toml() {
+        let mut prefs = EditorPrefs::default();
+        prefs.bindings.bind(InputAction::all()[0], KeyCode::F9);
+        prefs.ui = UiPrefs {
+            scale: 1.5,
+            theme: UiTheme::Light,
+        };
+        prefs.autosave.copies = 5;
+        prefs.last_scene = Some(PathBuf::from("/scenes/level \"1\".ron"));
+        prefs
+            .recent_scenes
+            .push(PathBuf::from("/scenes/level.json"));
+        prefs.snap.enabled = true;
+
+        let text = prefs.to_toml().unwrap();
+        let read = EditorPrefs::parse(&text).unwrap();
+        assert_eq!(read.bindings, prefs.bindings);
+        assert_eq!(read.ui, prefs.ui);
+        assert_eq!(read.autosave, prefs.autosave);
+        assert_eq!(read.last_scene, prefs.last_scene);
+        assert_eq!(read.recent_scenes, prefs.recent_scenes);
+        assert_eq!(read.snap, prefs.snap);
+        assert_eq!(read.to_toml().unwrap(), text);
+    }
+
+    #[test]
+    fn a_corrupt_file_is_moved_aside_for_the_defaults() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join(PREFS_FILE_NAME);
+        fs::write(&path, "[ui\nscale = ").unwrap();
+
+        let (prefs, message) = EditorPrefs::load_from(&path);
+        let aside = path.with_extension(CORRUPT_EXTENSION);
+        let kept = fs::read_to_string(&aside);
+        let still_there = path.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(prefs.ui, UiPrefs::default());
+        assert_eq!(
+            message.unwrap(),
+            format!(
+                "Preferences were unreadable and have been reset, the old file is {}",
+                aside.display()
+            )
+        );
+        assert_eq!(kept.unwrap(), "[ui\nscale = ");
+        assert!(!still_there);
+    }
+
+    #[test]
+    fn a_missing_file_gives_the_defaults_quietly() {
+        let dir = temp_dir("missing");
+        let (prefs, message) = EditorPrefs::load_from(&dir.join(PREFS_FILE_NAME));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(prefs.ui, UiPrefs::default());
+        assert_eq!(message, None);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::GameObjectManager;
+use bevy::app::{MainScheduleOrder, RunFixedMainLoop};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// Samples kept, enough for several seconds at high frame rates
+const HISTORY_CAPACITY: usize = 600;
+// Window the averages, worst frame and sparkline cover
+const WINDOW_SECS: f32 = 5.0;
+const SPARKLINE_HEIGHT: f32 = 60.0;
+
+// Main schedule stages timed by the profiler, in run order
+const STAGES: [&str; 6] = [
+    "First",
+    "PreUpdate",
+    "FixedUpdate",
+    "Update",
+    "PostUpdate (physics, UI)",
+    "Last",
+];
+
+// Tiny schedules slotted between the main ones, each only records the time.
+// Mark 0 runs before First and mark N after the Nth stage
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct ProfileMark(usize);
+
+#[derive(Debug, Clone)]
+pub struct FrameSample {
+    pub frame_ms: f32,
+    // Time in each of STAGES, the rest of the frame is rendering and presenting
+    pub stage_ms: [f32; STAGES.len()],
+    pub entities: u32,
+}
+
+// Rolling per-frame timings
+#[derive(Resource, Default)]
+pub struct Profiler {
+    pub samples: VecDeque<FrameSample>,
+    marks: [Option<Instant>; STAGES.len() + 1],
+    pub paused: bool,
+}
+
+impl Profiler {
+    fn mark(&mut self, index: usize) {
+        self.marks[index] = Some(Instant::now());
+    }
+
+    fn finish_frame(&mut self, frame_ms: f32, entities: u32) {
+        self.mark(STAGES.len());
+        if self.paused {
+            return;
+        }
+        let mut stage_ms = [0.0; STAGES.len()];
+        for (i, ms) in stage_ms.iter_mut().enumerate() {
+            if let (Some(start), Some(end)) = (self.marks[i], self.marks[i + 1]) {
+                *ms = end.duration_since(start).as_secs_f32() * 1000.0;
+            }
+        }
+        if self.samples.len() == HISTORY_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(FrameSample {
+            frame_ms,
+            stage_ms,
+            entities,
+        });
+    }
+
+    // Newest samples covering the last WINDOW_SECS, oldest first
+    pub fn window(&self) -> Vec<&FrameSample> {
+        let mut total = 0.0;
+        let mut window: Vec<&FrameSample> = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|sample| {
+                total += sample.frame_ms / 1000.0;
+                total <= WINDOW_SECS
+            })
+            .collect();
+        window.reverse();
+        window
+    }
+}
+
+// Times each main schedule stage by bracketing it with marker schedules, for
+// the Profiler panel
+pub struct ProfilerPlugin;
+
+impl Plugin for ProfilerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profiler>()
+            .add_systems(EguiContextPass, profiler_panel_system.in_set(UiSet::Panels));
+
+        let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+        order.insert_before(First, ProfileMark(0));
+        order.insert_after(First, ProfileMark(1));
+        order.insert_after(PreUpdate, ProfileMark(2));
+        order.insert_after(RunFixedMainLoop, ProfileMark(3));
+        order.insert_after(Update, ProfileMark(4));
+        order.insert_after(PostUpdate, ProfileMark(5));
+        order.insert_after(Last, ProfileMark(STAGES.len()));
+
+        for index in 0..STAGES.len() {
+            app.add_systems(ProfileMark(index), move |mut profiler: ResMut<Profiler>| {
+                profiler.mark(index);
+            });
+        }
+        app.add_systems(ProfileMark(STAGES.len()), |world: &mut World| {
+            let frame_ms = world.resource::<Time<Real>>().delta_secs() * 1000.0;
+            let entities = world.entities().len();
+            world
+                .resource_mut::<Profiler>()
+                .finish_frame(frame_ms, entities);
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortColumn {
+    #[default]
+    Stage,
+    Average,
+    Worst,
+}
+
+// Line graph of frame times, scaled to the worst frame in view
+fn sparkline(ui: &mut egui::Ui, values: &[f32]) {
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), SPARKLINE_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    if values.len() < 2 {
+        return;
+    }
+
+    let max = values.iter().copied().fold(1.0_f32, f32::max);
+    // Guide lines at 60 and 30 fps when they fit
+    for (ms, color) in [
+        (1000.0 / 60.0, egui::Color32::DARK_GREEN),
+        (1000.0 / 30.0, egui::Color32::DARK_RED),
+    ] {
+        if ms < max {
+            let y = rect.bottom() - ms / max * rect.height();
+            painter.hline(rect.x_range(), y, egui::Stroke::new(1.0, color));
+        }
+    }
+
+    let step = rect.width() / (values.len() - 1) as f32;
+    let points: Vec<egui::Pos2> = values
+        .iter()
+        .enumerate()
+        .map(|(i, ms)| {
+            egui::pos2(
+                rect.left() + i as f32 * step,
+                rect.bottom() - ms / max * rect.height(),
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+    ));
+}
+
+pub fn profiler_panel_system(
+    mut contexts: EguiContexts,
+    mut profiler: ResMut<Profiler>,
+    game_manager: Res<GameObjectManager>,
+    mut sort: Local<(SortColumn, bool)>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Profiler",
+        egui::Window::new("Profiler").default_width(340.0),
+        |ui| {
+            let mut paused = profiler.paused;
+            if ui.checkbox(&mut paused, "Pause").changed() {
+                profiler.paused = paused;
+            }
+
+            let window = profiler.window();
+            let Some(latest) = window.last() else {
+                ui.label("Collecting samples...");
+                return;
+            };
+            let count = window.len() as f32;
+            let frame_times: Vec<f32> = window.iter().map(|s| s.frame_ms).collect();
+            let average = frame_times.iter().sum::<f32>() / count;
+            let worst = frame_times.iter().copied().fold(0.0, f32::max);
+
+            ui.label(format!(
+                "Frame: {:.2} ms avg, {:.2} ms worst ({:.0} fps)",
+                average,
+                worst,
+                1000.0 / average.max(f32::EPSILON)
+            ));
+            ui.label(format!(
+                "Entities: {}, objects: {}",
+                latest.entities,
+                game_manager.len()
+            ));
+            sparkline(ui, &frame_times);
+            ui.weak(format!("Last {:.0}s, {} frames", WINDOW_SECS, window.len()));
+            ui.separator();
+
+            let mut rows: Vec<(usize, &str, f32, f32)> = STAGES
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let avg = window.iter().map(|s| s.stage_ms[i]).sum::<f32>() / count;
+                    let worst = window.iter().map(|s| s.stage_ms[i]).fold(0.0, f32::max);
+                    (i, *name, avg, worst)
+                })
+                .collect();
+            // Whatever the stages don't cover: rendering, presenting and waiting
+            let stage_total: f32 = rows.iter().map(|(_, _, avg, _)| avg).sum();
+            rows.push((
+                STAGES.len(),
+                "Other (render, vsync)",
+                (average - stage_total).max(0.0),
+                0.0,
+            ));
+
+            let (column, descending) = *sort;
+            rows.sort_by(|a, b| {
+                let ordering = match column {
+                    SortColumn::Stage => a.0.cmp(&b.0),
+                    SortColumn::Average => a.2.total_cmp(&b.2),
+                    SortColumn::Worst => a.3.total_cmp(&b.3),
+                };
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+
+            egui::Grid::new("profiler_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (label, header) in [
+                        ("Stage", SortColumn::Stage),
+                        ("Avg ms", SortColumn::Average),
+                        ("Worst ms", SortColumn::Worst),
+                    ] {
+                        let arrow = match (*sort == (header, true), sort.0 == header) {
+                            (true, _) => " ⏷",
+                            (false, true) => " ⏶",
+                            _ => "",
+                        };
+                        if ui.button(format!("{}{}", label, arrow)).clicked() {
+                            // Clicking the sorted column flips the direction
+                            *sort = if sort.0 == header {
+                                (header, !sort.1)
+                            } else {
+                                (header, header != SortColumn::Stage)
+                            };
+                        }
+                    }
+                    ui.end_row();
+
+                    for (i, name, avg, worst) in rows {
+                        ui.label(name);
+                        ui.label(format!("{:.3}", avg));
+                        if i < STAGES.len() {
+                            ui.label(format!("{:.3}", worst));
+                        } else {
+                            ui.label("-");
+                        }
+                        ui.end_row();
+                    }
+                });
+        },
+    );
+}
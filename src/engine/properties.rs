@@ -0,0 +1,364 @@
+use crate::engine::core::UiSet;
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::objects::{
+    GameObject, GameObjectManager, MaterialHandles, ObjectSpawner, PhysicsProps, ShapeDimensions,
+    set_material_color, set_rigid_body, wake_body,
+};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::selection::Selection;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+
+// Which property groups a paste applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyMask {
+    pub color: bool,
+    // Restitution, friction, damping and gravity scale
+    pub surface: bool,
+    pub body: bool,
+    // Only applied between objects of the same shape
+    pub dimensions: bool,
+}
+
+impl Default for PropertyMask {
+    fn default() -> Self {
+        Self {
+            color: true,
+            surface: true,
+            body: true,
+            dimensions: true,
+        }
+    }
+}
+
+// The pasteable state of one object, kept before and after for undo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectProperties {
+    pub color: Color,
+    pub physics: PhysicsProps,
+    pub dimensions: ShapeDimensions,
+}
+
+impl ObjectProperties {
+    pub fn of(obj: &GameObject) -> Self {
+        Self {
+            color: obj.color,
+            physics: obj.physics,
+            dimensions: obj.dimensions,
+        }
+    }
+
+    // These properties with the masked groups taken from source
+    pub fn merged(&self, source: &ObjectProperties, mask: PropertyMask, same_shape: bool) -> Self {
+        let mut merged = *self;
+        if mask.color {
+            merged.color = source.color;
+        }
+        if mask.surface {
+            merged.physics = PhysicsProps {
+                body: merged.physics.body,
+                ..source.physics
+            };
+        }
+        if mask.body {
+            merged.physics.body = source.physics.body;
+        }
+        if mask.dimensions && same_shape {
+            merged.dimensions = source.dimensions;
+        }
+        merged
+    }
+}
+
+// Copy the masked properties of source_id onto every target. Locked targets and
+// the source itself are skipped, the whole paste is one undo step
+#[derive(Event, Debug, Clone)]
+pub struct ApplyPropertiesEvent {
+    pub source_id: u32,
+    pub targets: Vec<u32>,
+    pub mask: PropertyMask,
+}
+
+// Object picked with "Copy properties", and the groups the next paste applies
+#[derive(Resource, Debug, Default)]
+pub struct PropertyClipboard {
+    pub source_id: Option<u32>,
+    pub mask: PropertyMask,
+}
+
+// Put properties on an object's components and mirror them in the manager.
+// Mesh and collider are only rebuilt when the dimensions change
+pub fn apply_object_properties(
+    commands: &mut Commands,
+    game_manager: &mut GameObjectManager,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    material_handles: &MaterialHandles,
+    id: u32,
+    properties: &ObjectProperties,
+) {
+    let Some(obj) = game_manager.get_object_by_id_mut(id) else {
+        warn!("Cannot apply properties to object {}: not found", id);
+        return;
+    };
+    let entity = obj.entity();
+
+    if obj.color != properties.color
+        && let Ok((handle, shared)) = material_handles.get(entity)
+    {
+        set_material_color(
+            commands,
+            materials,
+            entity,
+            handle,
+            shared,
+            properties.color,
+        );
+    }
+    if obj.physics.body != properties.physics.body {
+        set_rigid_body(
+            commands,
+            entity,
+            properties
+                .physics
+                .rigid_body(obj.physics_locked, obj.parent.is_some()),
+        );
+    }
+    commands
+        .entity(entity)
+        .insert(properties.physics.components());
+    if obj.dimensions != properties.dimensions {
+        let dimensions = properties.dimensions.validated(obj.shape_type);
+        commands.entity(entity).insert((
+            Mesh3d(obj.shape_type.create_mesh_with(meshes, &dimensions)),
+            obj.shape_type.create_collider_with(&dimensions),
+        ));
+        obj.dimensions = dimensions;
+    }
+    obj.color = properties.color;
+    obj.physics = properties.physics;
+    wake_body(commands, entity);
+}
+
+pub fn apply_properties_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut events: EventReader<ApplyPropertiesEvent>,
+    material_handles: MaterialHandles,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+) {
+    for event in events.read() {
+        let Some(source) = game_manager.get_object_by_id(event.source_id) else {
+            notifications.warn(format!(
+                "Cannot paste properties: object {} no longer exists",
+                event.source_id
+            ));
+            continue;
+        };
+        let (source_shape, source_mesh, copied) = (
+            source.shape_type,
+            source.custom_mesh.clone(),
+            ObjectProperties::of(source),
+        );
+
+        let changes: Vec<(u32, ObjectProperties, ObjectProperties)> = event
+            .targets
+            .iter()
+            .filter(|&&id| id != event.source_id)
+            .filter_map(|&id| game_manager.get_object_by_id(id))
+            .filter(|obj| !obj.locked)
+            .map(|obj| {
+                let before = ObjectProperties::of(obj);
+                // Dimensions of an imported mesh only fit other copies of that mesh
+                let same_shape = obj.shape_type == source_shape && obj.custom_mesh == source_mesh;
+                let after = before.merged(&copied, event.mask, same_shape);
+                (obj.id, before, after)
+            })
+            .filter(|(_, before, after)| before != after)
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+
+        for (id, _, after) in &changes {
+            apply_object_properties(
+                &mut commands,
+                &mut game_manager,
+                &mut meshes,
+                &mut materials,
+                &material_handles,
+                *id,
+                after,
+            );
+        }
+        notifications.info(format!("Pasted properties onto {} objects", changes.len()));
+        history.push(
+            EditorCommand::Properties { changes },
+            time.elapsed_secs_f64(),
+        );
+    }
+}
+
+fn copy_selected_properties(
+    selection: &Selection,
+    game_manager: &GameObjectManager,
+    clipboard: &mut PropertyClipboard,
+) {
+    if let Some(obj) = selection
+        .primary()
+        .and_then(|entity| game_manager.get_object_by_entity(entity))
+    {
+        clipboard.source_id = Some(obj.id);
+        info!("Copied properties of {}", obj.name);
+    }
+}
+
+fn paste_event(
+    selection: &Selection,
+    game_manager: &GameObjectManager,
+    clipboard: &PropertyClipboard,
+) -> Option<ApplyPropertiesEvent> {
+    let source_id = clipboard.source_id?;
+    let targets = selection.ids(game_manager);
+    (!targets.is_empty()).then_some(ApplyPropertiesEvent {
+        source_id,
+        targets,
+        mask: clipboard.mask,
+    })
+}
+
+// Ctrl+Shift+C copies the primary selection's properties, Ctrl+Shift+V pastes
+// them onto the selection with the clipboard's mask
+pub fn properties_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    mut clipboard: ResMut<PropertyClipboard>,
+    mut apply_events: EventWriter<ApplyPropertiesEvent>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        copy_selected_properties(&selection, &game_manager, &mut clipboard);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyV)
+        && let Some(event) = paste_event(&selection, &game_manager, &clipboard)
+    {
+        apply_events.write(event);
+    }
+}
+
+// Shown while something is on the clipboard, picks the groups to paste
+pub fn paste_properties_window_system(
+    mut contexts: EguiContexts,
+    mut clipboard: ResMut<PropertyClipboard>,
+    selection: Res<Selection>,
+    game_manager: Res<GameObjectManager>,
+    mut apply_events: EventWriter<ApplyPropertiesEvent>,
+) {
+    let Some(source_id) = clipboard.source_id else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let Some(source) = game_manager.get_object_by_id(source_id) else {
+        clipboard.source_id = None;
+        return;
+    };
+
+    let mut open = true;
+    egui::Window::new("Paste Properties")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            ui.label(format!("From {} (ID: {})", source.name, source.id));
+            let mut mask = clipboard.mask;
+            ui.checkbox(&mut mask.color, "Material color");
+            ui.checkbox(&mut mask.surface, "Restitution, friction, damping");
+            ui.checkbox(&mut mask.body, "Body type");
+            ui.checkbox(&mut mask.dimensions, "Dimensions")
+                .on_hover_text("Skipped for targets of a different shape");
+            if mask != clipboard.mask {
+                clipboard.mask = mask;
+            }
+
+            let event = paste_event(&selection, &game_manager, &clipboard);
+            let count = event.as_ref().map_or(0, |event| event.targets.len());
+            if ui
+                .add_enabled(
+                    event.is_some(),
+                    egui::Button::new(format!("Paste onto selection ({})", count)),
+                )
+                .on_hover_text("Ctrl+Shift+V")
+                .clicked()
+                && let Some(event) = event
+            {
+                apply_events.write(event);
+            }
+        });
+    if !open {
+        clipboard.source_id = None;
+    }
+}
+
+pub fn register_properties_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register(
+        "Copy properties",
+        Some(CommandHint::Keys("Ctrl+Shift+C")),
+        |world| {
+            world.resource_scope(|world, mut clipboard: Mut<PropertyClipboard>| {
+                copy_selected_properties(
+                    world.resource::<Selection>(),
+                    world.resource::<GameObjectManager>(),
+                    &mut clipboard,
+                );
+            });
+        },
+    );
+    registry.register(
+        "Paste properties",
+        Some(CommandHint::Keys("Ctrl+Shift+V")),
+        |world| {
+            if let Some(event) = paste_event(
+                world.resource::<Selection>(),
+                world.resource::<GameObjectManager>(),
+                world.resource::<PropertyClipboard>(),
+            ) {
+                world.send_event(event);
+            }
+        },
+    );
+}
+
+// Copy and paste of properties between objects
+pub struct PropertiesPlugin;
+
+impl Plugin for PropertiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyPropertiesEvent>()
+            .init_resource::<PropertyClipboard>()
+            .add_systems(Startup, register_properties_commands)
+            .add_systems(
+                EguiContextPass,
+                paste_properties_window_system.in_set(UiSet::Popups),
+            );
+    }
+}
@@ -0,0 +1,513 @@
+use crate::engine::core::UiSet;
+use crate::engine::editor::{
+    DimensionEditEvent, MaterialEditEvent, PhysicsEditEvent, TransformEditEvent,
+};
+use crate::engine::layout::EditorLayout;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DespawnEntityEvent, EditorRng, GameObjectManager, MetaEditEvent, MetaValue, PhysicsProps,
+    RenameObjectEvent, ShapeDimensions, SpawnEntityEvent, TagObjectEvent,
+};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::{SceneCapture, SceneFile};
+use crate::engine::screenshot::timestamped_name;
+use crate::engine::serde_util;
+use bevy::diagnostic::FrameCount;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on};
+use bevy_egui::*;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+// Version of the .nvrec contents, bumped when old recordings stop reading
+pub const RECORDING_FORMAT_VERSION: u32 = 1;
+// .nvrec files are this, a u32 LE version, then the recording as MessagePack
+const RECORDING_MAGIC: &[u8; 8] = b"NVREC\0\0\0";
+const RECORDING_HEADER_LEN: usize = RECORDING_MAGIC.len() + 4;
+
+// One change made while recording. Objects are referred to by id, which
+// playback reproduces by starting from the same scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedAction {
+    Spawn(SpawnEntityEvent),
+    // Ids the despawn covered when it was sent
+    Despawn(Vec<u32>),
+    Transform {
+        id: u32,
+        #[serde(with = "serde_util::vec3")]
+        translation: Vec3,
+        #[serde(with = "serde_util::quat")]
+        rotation: Quat,
+        #[serde(with = "serde_util::vec3")]
+        scale: Vec3,
+    },
+    Color {
+        id: u32,
+        #[serde(with = "serde_util::color")]
+        color: Color,
+    },
+    Physics {
+        id: u32,
+        props: PhysicsProps,
+    },
+    Dimensions {
+        id: u32,
+        dimensions: ShapeDimensions,
+    },
+    Rename {
+        id: u32,
+        name: String,
+    },
+    Tag {
+        id: u32,
+        tag: String,
+        add: bool,
+    },
+    Meta {
+        id: u32,
+        key: String,
+        value: Option<MetaValue>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    // Frames and seconds since recording started
+    pub frame: u32,
+    pub time: f64,
+    pub action: RecordedAction,
+}
+
+// Where an object was when recording stopped
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPosition {
+    pub id: u32,
+    #[serde(with = "serde_util::vec3")]
+    pub position: Vec3,
+}
+
+// A recorded session: the scene it started from, the seed spawns drew their
+// colors from and everything that happened after
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub version: u32,
+    pub seed: u64,
+    pub scene: SceneFile,
+    // Id the first spawn got, playback hands out the same ones
+    #[serde(default)]
+    pub next_id: Option<u32>,
+    pub events: Vec<RecordedEvent>,
+    // Seconds from start to stop
+    #[serde(default)]
+    pub length: f64,
+    // For playback's determinism check
+    #[serde(default)]
+    pub final_positions: Vec<RecordedPosition>,
+}
+
+impl Recording {
+    // Seconds from start to stop, or to the last event for recordings
+    // without a length
+    pub fn duration(&self) -> f64 {
+        self.events
+            .last()
+            .map_or(self.length, |event| event.time.max(self.length))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut contents = RECORDING_MAGIC.to_vec();
+        contents.extend_from_slice(&RECORDING_FORMAT_VERSION.to_le_bytes());
+        rmp_serde::encode::write_named(&mut contents, self).map_err(|err| err.to_string())?;
+        Ok(contents)
+    }
+
+    pub fn from_bytes(contents: &[u8]) -> Result<Self, String> {
+        if contents.len() < RECORDING_HEADER_LEN || !contents.starts_with(RECORDING_MAGIC) {
+            return Err("not a Navi recording".to_string());
+        }
+        let mut version = [0; 4];
+        version.copy_from_slice(&contents[RECORDING_MAGIC.len()..RECORDING_HEADER_LEN]);
+        let version = u32::from_le_bytes(version);
+        if version > RECORDING_FORMAT_VERSION {
+            return Err(format!(
+                "recording is format version {}, it requires a newer Navi",
+                version
+            ));
+        }
+        rmp_serde::from_slice(&contents[RECORDING_HEADER_LEN..]).map_err(|err| err.to_string())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read(path).map_err(|err| err.to_string())?;
+        Self::from_bytes(&contents)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = self.to_bytes()?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+}
+
+// Where recordings go when the panel's file field is left empty
+pub fn default_recording_dir() -> PathBuf {
+    ProjectDirs::from("", "", "Navi")
+        .map(|dirs| dirs.data_dir().join("recordings"))
+        .unwrap_or_else(|| PathBuf::from("recordings"))
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingEvent {
+    Start,
+    // Stop and write the recording
+    Stop,
+    // Write the last recording again, to the current file field
+    Save,
+}
+
+struct ActiveRecording {
+    recording: Recording,
+    start_frame: u32,
+    start_time: f64,
+}
+
+#[derive(Resource, Default)]
+pub struct Recorder {
+    active: Option<ActiveRecording>,
+    // Last stopped recording, kept so it can be saved somewhere else too
+    last: Option<Arc<Recording>>,
+    // File Stop and Save write to, a timestamped one in
+    // default_recording_dir() when empty
+    pub path: String,
+    // Writes in flight
+    saves: Vec<Task<Result<PathBuf, String>>>,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.active.is_some()
+    }
+
+    // Seconds recorded so far
+    pub fn elapsed(&self, now: f64) -> f64 {
+        self.active
+            .as_ref()
+            .map_or(0.0, |active| now - active.start_time)
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.active
+            .as_ref()
+            .map_or(0, |active| active.recording.events.len())
+    }
+
+    pub fn last(&self) -> Option<&Recording> {
+        self.last.as_deref()
+    }
+
+    fn save_path(&self) -> PathBuf {
+        match self.path.trim() {
+            "" => default_recording_dir().join(timestamped_name(
+                "session",
+                "nvrec",
+                SystemTime::now(),
+            )),
+            path => PathBuf::from(path),
+        }
+    }
+
+    fn save_last(&mut self) {
+        let Some(recording) = self.last.clone() else {
+            return;
+        };
+        let path = self.save_path();
+        self.saves
+            .push(IoTaskPool::get().spawn(async move { recording.write(&path).map(|()| path) }));
+    }
+}
+
+// Recorder requests and the edits sent this frame
+#[derive(SystemParam)]
+pub struct RecorderInput<'w, 's> {
+    recording_events: EventReader<'w, 's, RecordingEvent>,
+    spawns: EventReader<'w, 's, SpawnEntityEvent>,
+    despawns: EventReader<'w, 's, DespawnEntityEvent>,
+    transforms: EventReader<'w, 's, TransformEditEvent>,
+    materials: EventReader<'w, 's, MaterialEditEvent>,
+    physics: EventReader<'w, 's, PhysicsEditEvent>,
+    dimensions: EventReader<'w, 's, DimensionEditEvent>,
+    renames: EventReader<'w, 's, RenameObjectEvent>,
+    tags: EventReader<'w, 's, TagObjectEvent>,
+    meta: EventReader<'w, 's, MetaEditEvent>,
+}
+
+impl RecorderInput<'_, '_> {
+    fn requests(&mut self) -> Vec<RecordingEvent> {
+        self.recording_events.read().copied().collect()
+    }
+
+    // Edits sent this frame, as recorded actions
+    fn actions(&mut self, game_manager: &GameObjectManager) -> Vec<RecordedAction> {
+        let RecorderInput {
+            spawns,
+            despawns,
+            transforms,
+            materials,
+            physics,
+            dimensions,
+            renames,
+            tags,
+            meta,
+            ..
+        } = self;
+        let id_of = |entity: Entity| game_manager.get_object_by_entity(entity).map(|obj| obj.id);
+        let mut actions: Vec<RecordedAction> =
+            spawns.read().cloned().map(RecordedAction::Spawn).collect();
+        actions.extend(despawns.read().filter_map(|event| {
+            event
+                .target
+                .resolve(game_manager)
+                .filter(|ids| !ids.is_empty())
+                .map(RecordedAction::Despawn)
+        }));
+        actions.extend(transforms.read().filter_map(|event| {
+            Some(RecordedAction::Transform {
+                id: id_of(event.entity)?,
+                translation: event.transform.translation,
+                rotation: event.transform.rotation,
+                scale: event.transform.scale,
+            })
+        }));
+        actions.extend(materials.read().filter_map(|event| {
+            Some(RecordedAction::Color {
+                id: id_of(event.entity)?,
+                color: event.color,
+            })
+        }));
+        actions.extend(physics.read().filter_map(|event| {
+            Some(RecordedAction::Physics {
+                id: id_of(event.entity)?,
+                props: event.props,
+            })
+        }));
+        actions.extend(dimensions.read().filter_map(|event| {
+            Some(RecordedAction::Dimensions {
+                id: id_of(event.entity)?,
+                dimensions: event.dimensions,
+            })
+        }));
+        actions.extend(renames.read().map(|event| RecordedAction::Rename {
+            id: event.id,
+            name: event.new_name.clone(),
+        }));
+        actions.extend(tags.read().map(|event| RecordedAction::Tag {
+            id: event.id,
+            tag: event.tag.clone(),
+            add: event.add,
+        }));
+        actions.extend(meta.read().map(|event| RecordedAction::Meta {
+            id: event.id,
+            key: event.key.clone(),
+            value: event.value.clone(),
+        }));
+        actions
+    }
+}
+
+// Runs before the events it records are applied, so despawns and entity
+// targets still resolve to ids. Events are read every frame either way so a
+// new recording doesn't pick up old ones
+pub fn record_session_system(
+    mut input: RecorderInput,
+    mut recorder: ResMut<Recorder>,
+    mut rng: ResMut<EditorRng>,
+    capture: SceneCapture,
+    frame: Res<FrameCount>,
+    time: Res<Time>,
+    mut notifications: ResMut<EditorNotifications>,
+) {
+    let game_manager = &capture.game_manager;
+    let requests = input.requests();
+    let actions = input.actions(game_manager);
+    let now = time.elapsed_secs_f64();
+
+    if requests.contains(&RecordingEvent::Start) && !recorder.is_recording() {
+        // A fresh seed per recording, playback reseeds with it
+        *rng = EditorRng::seeded(rand::random());
+        recorder.active = Some(ActiveRecording {
+            recording: Recording {
+                version: RECORDING_FORMAT_VERSION,
+                seed: rng.seed(),
+                scene: capture.capture(false),
+                next_id: game_manager.ids().peek(),
+                events: Vec::new(),
+                length: 0.0,
+                final_positions: Vec::new(),
+            },
+            start_frame: frame.0,
+            start_time: now,
+        });
+        info!("Recording started");
+        notifications.info("Recording started");
+    }
+
+    if let Some(active) = &mut recorder.active {
+        let recorded_frame = frame.0.wrapping_sub(active.start_frame);
+        active
+            .recording
+            .events
+            .extend(actions.into_iter().map(|action| RecordedEvent {
+                frame: recorded_frame,
+                time: now - active.start_time,
+                action,
+            }));
+    }
+
+    if requests.contains(&RecordingEvent::Stop)
+        && let Some(mut active) = recorder.active.take()
+    {
+        active.recording.length = now - active.start_time;
+        active.recording.final_positions = game_manager
+            .iter()
+            .map(|obj| RecordedPosition {
+                id: obj.id,
+                position: obj.position(),
+            })
+            .collect();
+        info!(
+            "Recording stopped after {:.1}s, {} events",
+            now - active.start_time,
+            active.recording.events.len()
+        );
+        recorder.last = Some(Arc::new(active.recording));
+        recorder.save_last();
+    } else if requests.contains(&RecordingEvent::Save) && !recorder.is_recording() {
+        recorder.save_last();
+    }
+
+    if recorder.saves.iter().all(|task| !task.is_finished()) {
+        return;
+    }
+    let (finished, running) = recorder
+        .saves
+        .drain(..)
+        .partition::<Vec<_>, _>(|task| task.is_finished());
+    recorder.saves = running;
+    for task in finished {
+        match block_on(task) {
+            Ok(path) => {
+                info!("Saved recording to {}", path.display());
+                notifications
+                    .info_with_link(format!("Recording saved to {}", path.display()), &path);
+            }
+            Err(err) => {
+                warn!("Cannot save recording: {}", err);
+                notifications.error(format!("Cannot save recording: {}", err));
+            }
+        }
+    }
+}
+
+pub fn recorder_panel_system(
+    mut contexts: EguiContexts,
+    mut recorder: ResMut<Recorder>,
+    mut recording_events: EventWriter<RecordingEvent>,
+    mut layout: ResMut<EditorLayout>,
+    time: Res<Time>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Recorder",
+        egui::Window::new("Recorder").default_width(280.0),
+        |ui| {
+            let recording = recorder.is_recording();
+            if recording {
+                let elapsed = recorder.elapsed(time.elapsed_secs_f64());
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!(
+                        "Recording {:02}:{:04.1}, {} events",
+                        (elapsed / 60.0) as u32,
+                        elapsed % 60.0,
+                        recorder.event_count()
+                    ),
+                );
+            } else if let Some(last) = recorder.last() {
+                ui.label(format!(
+                    "Last recording {:.1}s, {} events",
+                    last.duration(),
+                    last.events.len()
+                ));
+            } else {
+                ui.label("Not recording");
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!recording, egui::Button::new("Start"))
+                    .clicked()
+                {
+                    recording_events.write(RecordingEvent::Start);
+                }
+                if ui
+                    .add_enabled(recording, egui::Button::new("Stop"))
+                    .clicked()
+                {
+                    recording_events.write(RecordingEvent::Stop);
+                }
+                if ui
+                    .add_enabled(
+                        !recording && recorder.last().is_some(),
+                        egui::Button::new("Save"),
+                    )
+                    .clicked()
+                {
+                    recording_events.write(RecordingEvent::Save);
+                }
+            });
+
+            ui.label("File (.nvrec)");
+            ui.add(
+                egui::TextEdit::singleline(&mut recorder.path)
+                    .hint_text(format!(
+                        "Timestamped in {}",
+                        default_recording_dir().display()
+                    ))
+                    .desired_width(260.0),
+            );
+            ui.weak("Stop writes the recording there, Save writes the last one again");
+        },
+    );
+}
+
+pub fn register_recorder_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Start recording", None, |world| {
+        world.send_event(RecordingEvent::Start);
+    });
+    registry.register("Stop recording", None, |world| {
+        world.send_event(RecordingEvent::Stop);
+    });
+}
+
+// Session recording
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RecordingEvent>()
+            .init_resource::<Recorder>()
+            .add_systems(Startup, register_recorder_commands)
+            .add_systems(EguiContextPass, recorder_panel_system.in_set(UiSet::Panels));
+    }
+}
@@ -0,0 +1,1328 @@
+use crate::engine::autosave::Autosave;
+use crate::engine::config::NaviConfig;
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::groups::{GroupError, Groups};
+use crate::engine::history::{EditContext, EditorCommand};
+use crate::engine::joints::{JointRegistry, ObjectJoint};
+use crate::engine::migrate;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    GameObjectManager, MetaValue, ObjectRemovedEvent, ObjectSnapshot, ObjectSpawner, PhysicsProps,
+    ShapeDimensions, ShapeType, SpawnSource, restore_objects,
+};
+use crate::engine::oplog::OperationLog;
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::screenshot::CaptureScreenshotEvent;
+use crate::engine::serde_util;
+use crate::engine::templates::{NewSceneEvent, SceneTemplates};
+use crate::engine::thumbnails::thumbnail_path;
+use crate::engine::trash::reassign_taken_ids;
+use crate::engine::world::WorldSettings;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on};
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+use directories::ProjectDirs;
+use serde::de::{self, DeserializeSeed, EnumAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Bumped whenever SceneFile changes in a way older readers can't handle
+pub const SCENE_FORMAT_VERSION: u32 = 1;
+
+const DEFAULT_SCENE_FILE_NAME: &str = "scene.ron";
+
+// One object as stored in a scene file. The transform is relative to the
+// parent, like the entity's own Transform
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneObject {
+    pub id: u32,
+    pub name: String,
+    pub shape_type: SceneShape,
+    pub dimensions: ShapeDimensions,
+    #[serde(with = "serde_util::vec3")]
+    pub translation: Vec3,
+    #[serde(with = "serde_util::quat")]
+    pub rotation: Quat,
+    #[serde(with = "serde_util::vec3", default = "unit_scale")]
+    pub scale: Vec3,
+    #[serde(with = "serde_util::color")]
+    pub color: Color,
+    #[serde(default)]
+    pub physics: PhysicsProps,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub parent: Option<u32>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub physics_locked: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub collider_disabled: bool,
+    #[serde(default)]
+    pub prefab: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_mesh: Option<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, MetaValue>,
+    // Only written when saving with "Include physics state"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_state: Option<BodyState>,
+}
+
+// How a body was moving when the scene was saved
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodyState {
+    #[serde(with = "serde_util::vec3")]
+    pub linvel: Vec3,
+    #[serde(with = "serde_util::vec3")]
+    pub angvel: Vec3,
+    #[serde(default)]
+    pub sleeping: bool,
+}
+
+impl BodyState {
+    // Put the saved motion back on a restored body. While the simulation is
+    // paused rapier leaves the components alone, so they wait for unpause
+    fn apply(&self, commands: &mut Commands, entity: Entity) {
+        commands.entity(entity).insert((
+            Velocity {
+                linvel: self.linvel,
+                angvel: self.angvel,
+            },
+            Sleeping {
+                sleeping: self.sleeping,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn unit_scale() -> Vec3 {
+    Vec3::ONE
+}
+
+// Shape as read from a scene file. Names this build doesn't know are kept, so
+// the load can skip just that object and say why
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneShape {
+    Known(ShapeType),
+    Unknown(String),
+}
+
+impl Serialize for SceneShape {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            SceneShape::Known(shape_type) => shape_type.serialize(serializer),
+            SceneShape::Unknown(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+// Read as an enum whose variant can be any name, so a shape added by a newer
+// build doesn't fail the whole file
+impl<'de> Deserialize<'de> for SceneShape {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VariantName;
+
+        impl<'de> DeserializeSeed<'de> for VariantName {
+            type Value = String;
+
+            fn deserialize<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<String, D::Error> {
+                struct NameVisitor;
+
+                impl Visitor<'_> for NameVisitor {
+                    type Value = String;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("a shape type name")
+                    }
+
+                    fn visit_str<E: de::Error>(self, name: &str) -> Result<String, E> {
+                        Ok(name.to_string())
+                    }
+                }
+
+                deserializer.deserialize_identifier(NameVisitor)
+            }
+        }
+
+        struct ShapeVisitor;
+
+        impl<'de> Visitor<'de> for ShapeVisitor {
+            type Value = SceneShape;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a shape type")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<SceneShape, A::Error> {
+                let (name, variant) = data.variant_seed(VariantName)?;
+                variant.unit_variant()?;
+                Ok(ShapeType::all()
+                    .into_iter()
+                    .chain([ShapeType::CustomMesh])
+                    .find(|shape_type| format!("{:?}", shape_type) == name)
+                    .map_or(SceneShape::Unknown(name), SceneShape::Known))
+            }
+        }
+
+        deserializer.deserialize_enum("ShapeType", SHAPE_TYPE_NAMES, ShapeVisitor)
+    }
+}
+
+const SHAPE_TYPE_NAMES: &[&str] = &["Ball", "Cube", "Capsule", "Cylinder", "Cone", "CustomMesh"];
+
+impl SceneObject {
+    // The object as it's restored, or why it can't be
+    pub fn to_snapshot(&self) -> Result<ObjectSnapshot, String> {
+        let shape_type = match &self.shape_type {
+            SceneShape::Known(shape_type) => *shape_type,
+            SceneShape::Unknown(name) => {
+                return Err(format!(
+                    "{} (ID: {}): unknown shape type \"{}\"",
+                    self.name, self.id, name
+                ));
+            }
+        };
+        Ok(ObjectSnapshot {
+            id: self.id,
+            name: self.name.clone(),
+            shape_type,
+            transform: Transform {
+                translation: self.translation,
+                rotation: self.rotation,
+                scale: self.scale,
+            },
+            color: self.color,
+            physics: self.physics,
+            dimensions: self.dimensions.validated(shape_type),
+            locked: self.locked,
+            physics_locked: self.physics_locked,
+            hidden: self.hidden,
+            collider_disabled: self.collider_disabled,
+            parent: self.parent,
+            tags: self.tags.iter().cloned().collect(),
+            prefab: self.prefab.clone(),
+            custom_mesh: self.custom_mesh.clone(),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            source: SpawnSource::SceneLoad,
+        })
+    }
+}
+
+impl From<&ObjectSnapshot> for SceneObject {
+    fn from(snapshot: &ObjectSnapshot) -> Self {
+        Self {
+            id: snapshot.id,
+            name: snapshot.name.clone(),
+            shape_type: SceneShape::Known(snapshot.shape_type),
+            dimensions: snapshot.dimensions,
+            translation: snapshot.transform.translation,
+            rotation: snapshot.transform.rotation,
+            scale: snapshot.transform.scale,
+            color: snapshot.color,
+            physics: snapshot.physics,
+            tags: snapshot.tags.iter().cloned().collect(),
+            parent: snapshot.parent,
+            locked: snapshot.locked,
+            physics_locked: snapshot.physics_locked,
+            hidden: snapshot.hidden,
+            collider_disabled: snapshot.collider_disabled,
+            prefab: snapshot.prefab.clone(),
+            custom_mesh: snapshot.custom_mesh.clone(),
+            metadata: snapshot
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            body_state: None,
+        }
+    }
+}
+
+// Everything written by Save scene. Objects are in id order, so saving the
+// same scene twice gives the same file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneFile {
+    pub version: u32,
+    #[serde(default)]
+    pub world: WorldSettings,
+    pub objects: Vec<SceneObject>,
+    // By object id, restored after the objects
+    #[serde(default)]
+    pub joints: Vec<ObjectJoint>,
+}
+
+impl SceneFile {
+    pub fn capture(
+        game_manager: &GameObjectManager,
+        world: &WorldSettings,
+        transforms: &Query<&Transform>,
+    ) -> Self {
+        let objects = game_manager
+            .iter()
+            .filter_map(|obj| {
+                let transform = transforms.get(obj.entity()).copied().unwrap_or_default();
+                game_manager.snapshot(obj.id, transform)
+            })
+            .map(|snapshot| SceneObject::from(&snapshot))
+            .collect();
+        Self {
+            version: SCENE_FORMAT_VERSION,
+            world: world.clone(),
+            objects,
+            joints: Vec::new(),
+        }
+    }
+
+    // Record the velocity and sleep flag of every object that has a body
+    pub fn capture_body_states(
+        &mut self,
+        game_manager: &GameObjectManager,
+        bodies: &Query<(Option<&Velocity>, Option<&Sleeping>)>,
+    ) {
+        for object in &mut self.objects {
+            let Some((velocity, sleeping)) = game_manager
+                .get_object_by_id(object.id)
+                .and_then(|obj| bodies.get(obj.entity()).ok())
+            else {
+                continue;
+            };
+            let velocity = velocity.copied().unwrap_or_default();
+            object.body_state = Some(BodyState {
+                linvel: velocity.linvel,
+                angvel: velocity.angvel,
+                sleeping: sleeping.is_some_and(|sleeping| sleeping.sleeping),
+            });
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read(path).map_err(|err| err.to_string())?;
+        let format = SceneFormat::of(path);
+        let parsed = format.parse(&contents);
+        if parsed
+            .as_ref()
+            .is_ok_and(|scene| scene.version == SCENE_FORMAT_VERSION)
+        {
+            return parsed;
+        }
+        // Older files go through the migrations, newer ones are refused. A
+        // file without a readable version falls through to the parse error
+        if let Ok(version) = migrate::probe_version(format, &contents) {
+            migrate::check_version(version)?;
+            if version < SCENE_FORMAT_VERSION {
+                return migrate::read_migrated(format, &contents, version);
+            }
+        }
+        parsed.map_err(|err| {
+            // Say so when the file is fine in another format
+            match SceneFormat::all()
+                .into_iter()
+                .find(|other| *other != format && other.parse(&contents).is_ok())
+            {
+                Some(other) => format!(
+                    "{} (the file is {}, give it a .{} extension)",
+                    err,
+                    other.name(),
+                    other.extension()
+                ),
+                None => err,
+            }
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = SceneFormat::of(path).serialize(self)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+}
+
+// Start of every .nvscene file, followed by the container version as a
+// little-endian u32 and the scene as MessagePack. MessagePack rather than
+// bincode or postcard because those can't describe themselves: defaulted and
+// skipped fields, untagged metadata values and migrations reading the file
+// into a Value all need a format that can
+const BINARY_MAGIC: &[u8; 8] = b"NVSCENE\0";
+// Bumped when the header or encoding changes, the scene has its own version
+const BINARY_CONTAINER_VERSION: u32 = 1;
+const BINARY_HEADER_LEN: usize = BINARY_MAGIC.len() + 4;
+
+// The MessagePack part of a .nvscene file, after checking the header
+pub fn binary_payload(contents: &[u8]) -> Result<&[u8], String> {
+    if contents.len() < BINARY_HEADER_LEN || !contents.starts_with(BINARY_MAGIC) {
+        return Err("not a Navi binary scene".to_string());
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&contents[BINARY_MAGIC.len()..BINARY_HEADER_LEN]);
+    let version = u32::from_le_bytes(version);
+    if version > BINARY_CONTAINER_VERSION {
+        return Err(format!(
+            "binary scene container version {} requires a newer Navi",
+            version
+        ));
+    }
+    Ok(&contents[BINARY_HEADER_LEN..])
+}
+
+// Scene files are RON unless their extension is .json, or .nvscene for the
+// binary format meant for large scenes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneFormat {
+    Ron,
+    Json,
+    Binary,
+}
+
+impl SceneFormat {
+    pub fn all() -> [SceneFormat; 3] {
+        [SceneFormat::Ron, SceneFormat::Json, SceneFormat::Binary]
+    }
+
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => SceneFormat::Json,
+            Some(ext) if ext.eq_ignore_ascii_case("nvscene") => SceneFormat::Binary,
+            _ => SceneFormat::Ron,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SceneFormat::Ron => "RON",
+            SceneFormat::Json => "JSON",
+            SceneFormat::Binary => "a binary scene",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SceneFormat::Ron => "ron",
+            SceneFormat::Json => "json",
+            SceneFormat::Binary => "nvscene",
+        }
+    }
+
+    // The text formats as a string, binary scenes can't be read as one
+    pub fn text(contents: &[u8]) -> Result<&str, String> {
+        std::str::from_utf8(contents).map_err(|_| "the file is not text".to_string())
+    }
+
+    // Parse errors name the format and where in the file it went wrong
+    fn parse(&self, contents: &[u8]) -> Result<SceneFile, String> {
+        match self {
+            SceneFormat::Ron => ron::from_str(Self::text(contents)?).map_err(|err| {
+                format!(
+                    "invalid RON at line {}, column {}: {}",
+                    err.position.line, err.position.col, err.code
+                )
+            }),
+            SceneFormat::Json => serde_json::from_str(Self::text(contents)?).map_err(|err| {
+                // serde_json puts the position at the end of its message
+                let position = format!(" at line {} column {}", err.line(), err.column());
+                let message = err.to_string();
+                format!(
+                    "invalid JSON at line {}, column {}: {}",
+                    err.line(),
+                    err.column(),
+                    message.strip_suffix(&position).unwrap_or(&message)
+                )
+            }),
+            SceneFormat::Binary => rmp_serde::from_slice(binary_payload(contents)?)
+                .map_err(|err| format!("invalid binary scene: {}", err)),
+        }
+    }
+
+    fn serialize(&self, scene: &SceneFile) -> Result<Vec<u8>, String> {
+        match self {
+            SceneFormat::Ron => {
+                ron::ser::to_string_pretty(scene, ron::ser::PrettyConfig::default())
+                    .map(String::into_bytes)
+                    .map_err(|err| err.to_string())
+            }
+            SceneFormat::Json => serde_json::to_vec_pretty(scene).map_err(|err| err.to_string()),
+            SceneFormat::Binary => {
+                let mut contents = BINARY_MAGIC.to_vec();
+                contents.extend_from_slice(&BINARY_CONTAINER_VERSION.to_le_bytes());
+                // Named fields, so skipped and defaulted fields read back
+                rmp_serde::encode::write_named(&mut contents, scene)
+                    .map_err(|err| err.to_string())?;
+                Ok(contents)
+            }
+        }
+    }
+}
+
+// Rewrite a scene in the format of the target's extension, for `navi
+// --convert`. Returns a line for the terminal
+pub fn convert_scene_file(from: &Path, to: &Path) -> Result<String, String> {
+    let scene = SceneFile::read(from)?;
+    scene.write(to)?;
+    Ok(format!(
+        "Converted {} objects from {} to {} ({})",
+        scene.objects.len(),
+        from.display(),
+        to.display(),
+        SceneFormat::of(to).name()
+    ))
+}
+
+// Default place to save scenes, in the platform data directory
+pub fn default_scene_path() -> PathBuf {
+    ProjectDirs::from("", "", "Navi")
+        .map(|dirs| dirs.data_dir().join("scenes").join(DEFAULT_SCENE_FILE_NAME))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SCENE_FILE_NAME))
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct SaveSceneEvent {
+    pub path: PathBuf,
+    // Also store velocities and sleep flags so motion resumes on load
+    pub include_physics: bool,
+}
+
+// The live scene, everything a save or an autosave writes
+#[derive(SystemParam)]
+pub struct SceneCapture<'w, 's> {
+    pub game_manager: Res<'w, GameObjectManager>,
+    pub world: Res<'w, WorldSettings>,
+    pub joints: Res<'w, JointRegistry>,
+    pub transforms: Query<'w, 's, &'static Transform>,
+    pub bodies: Query<'w, 's, (Option<&'static Velocity>, Option<&'static Sleeping>)>,
+}
+
+impl SceneCapture<'_, '_> {
+    // Velocities and sleep flags are only stored with include_physics
+    pub fn capture(&self, include_physics: bool) -> SceneFile {
+        let mut scene = SceneFile::capture(&self.game_manager, &self.world, &self.transforms);
+        scene.joints = self.joints.saved();
+        if include_physics {
+            scene.capture_body_states(&self.game_manager, &self.bodies);
+        }
+        scene
+    }
+}
+
+// Which file the scene is saved to, and what was last saved or autosaved
+#[derive(SystemParam)]
+pub struct SaveState<'w> {
+    pub dialog: ResMut<'w, SceneFileDialog>,
+    pub autosave: ResMut<'w, Autosave>,
+}
+
+pub fn save_scene_system(
+    mut save_events: EventReader<SaveSceneEvent>,
+    capture: SceneCapture,
+    SaveState {
+        mut dialog,
+        mut autosave,
+    }: SaveState,
+    log: Res<OperationLog>,
+    mut notifications: ResMut<EditorNotifications>,
+    time: Res<Time>,
+    mut capture_events: EventWriter<CaptureScreenshotEvent>,
+) {
+    for event in save_events.read() {
+        let scene = capture.capture(event.include_physics);
+        match scene.write(&event.path) {
+            Ok(()) => {
+                info!(
+                    "Saved {} objects to {}",
+                    scene.objects.len(),
+                    event.path.display()
+                );
+                notifications.info(format!("Saved scene to {}", event.path.display()));
+                dialog.current = Some(event.path.clone());
+                autosave.mark_saved(log.revision(), time.elapsed_secs_f64());
+                capture_events.write(CaptureScreenshotEvent {
+                    path: Some(thumbnail_path(&event.path)),
+                    thumbnail: true,
+                });
+            }
+            Err(err) => {
+                warn!("Cannot save scene to {}: {}", event.path.display(), err);
+                notifications.error(format!("Cannot save scene: {}", err));
+            }
+        }
+    }
+}
+
+// Whether a load keeps the objects already in the scene
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SceneLoadMode {
+    // Everything in the scene is removed first and undo history is dropped
+    #[default]
+    Replace,
+    // Loaded objects join the scene as one undo step. Those whose id is taken
+    // get a fresh one and names already in use get a .NNN suffix
+    Additive(SceneImport),
+}
+
+// Extras for an additive load
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SceneImport {
+    // Added to every top-level object, children move with their parents
+    pub offset: Vec3,
+    // New group holding the imported objects, suffixed if the name is taken
+    pub group: Option<String>,
+}
+
+// Imported names that clash with the scene get the next free suffixes. Names
+// the file repeats itself are left alone
+fn rename_clashes(game_manager: &GameObjectManager, snapshots: &mut [ObjectSnapshot]) {
+    let mut clashes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        if game_manager.iter().any(|obj| obj.name == snapshot.name) {
+            clashes
+                .entry(snapshot.name.clone())
+                .or_default()
+                .push(index);
+        }
+    }
+    for (name, indices) in clashes {
+        let names = game_manager.unique_names(&name, indices.len());
+        for (index, name) in indices.into_iter().zip(names) {
+            snapshots[index].name = name;
+        }
+    }
+}
+
+// The group name itself if it's free, otherwise the lowest free ".NNN" suffix
+fn create_import_group(groups: &mut Groups, name: &str) -> Result<String, GroupError> {
+    match groups.create(name) {
+        Err(GroupError::AlreadyExists(_)) => {
+            let mut number = 1;
+            loop {
+                match groups.create(&format!("{}.{:03}", name.trim(), number)) {
+                    Err(GroupError::AlreadyExists(_)) => number += 1,
+                    result => return result,
+                }
+            }
+        }
+        result => result,
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct LoadSceneEvent {
+    pub path: PathBuf,
+    pub mode: SceneLoadMode,
+    // Where Ctrl+S writes after a replacing load, the loaded file when unset.
+    // Recovering an autosave points this back at the original scene
+    pub save_to: Option<PathBuf>,
+}
+
+// Outcome of a load that didn't go cleanly, shown until dismissed
+#[derive(Debug, Clone)]
+pub struct SceneLoadReport {
+    // The file or template the scene came from
+    pub source: String,
+    pub loaded: usize,
+    pub problems: Vec<String>,
+}
+
+// Put a scene already in memory in place, left untitled when it replaces the
+// current one. Playback puts a recording's starting scene back with it and
+// presets like the door are added with it
+#[derive(Event, Debug, Clone)]
+pub struct RestoreSceneEvent {
+    // Shown in messages in place of a file name
+    pub source: String,
+    pub scene: SceneFile,
+    pub mode: SceneLoadMode,
+}
+
+// A scene to put in place, read from a file, built from a template or restored
+struct SceneLoad {
+    source: String,
+    scene: Result<SceneFile, String>,
+    mode: SceneLoadMode,
+    // Where Ctrl+S writes after a replacing load, None leaves it untitled
+    current: Option<PathBuf>,
+}
+
+// Requests for a scene to put in place: a template, one already in memory or
+// a file
+#[derive(SystemParam)]
+pub struct SceneRequests<'w, 's> {
+    new_scene_events: EventReader<'w, 's, NewSceneEvent>,
+    restore_events: EventReader<'w, 's, RestoreSceneEvent>,
+    load_events: EventReader<'w, 's, LoadSceneEvent>,
+    templates: Res<'w, SceneTemplates>,
+    config: Res<'w, NaviConfig>,
+}
+
+impl SceneRequests<'_, '_> {
+    // This frame's loads, in the order they're carried out
+    fn read(&mut self) -> Vec<SceneLoad> {
+        let templated = self.new_scene_events.read().map(|event| SceneLoad {
+            source: format!("the {} template", event.template),
+            scene: self
+                .templates
+                .get(&event.template)
+                .map(|template| {
+                    // Templates that keep the default gravity get the configured one
+                    let mut scene = (template.build)();
+                    if scene.world.gravity == WorldSettings::default().gravity {
+                        scene.world.gravity = self.config.gravity;
+                    }
+                    scene
+                })
+                .ok_or_else(|| format!("there's no template named {}", event.template)),
+            mode: SceneLoadMode::Replace,
+            current: None,
+        });
+        templated
+            .chain(self.restore_events.read().map(|event| SceneLoad {
+                source: event.source.clone(),
+                scene: Ok(event.scene.clone()),
+                mode: event.mode.clone(),
+                current: None,
+            }))
+            .chain(self.load_events.read().map(|event| SceneLoad {
+                source: event.path.display().to_string(),
+                scene: SceneFile::read(&event.path),
+                mode: event.mode.clone(),
+                current: Some(event.save_to.clone().unwrap_or(event.path.clone())),
+            }))
+            .collect()
+    }
+}
+
+// What a scene brings along besides its objects
+#[derive(SystemParam)]
+pub struct SceneSettings<'w> {
+    pub world: ResMut<'w, WorldSettings>,
+    pub groups: ResMut<'w, Groups>,
+    pub joints: ResMut<'w, JointRegistry>,
+}
+
+pub fn load_scene_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        library,
+        mut game_manager,
+    }: ObjectSpawner,
+    mut requests: SceneRequests,
+    EditContext {
+        mut history,
+        mut notifications,
+        time,
+    }: EditContext,
+    SaveState {
+        mut dialog,
+        mut autosave,
+    }: SaveState,
+    SceneSettings {
+        mut world,
+        mut groups,
+        mut joints,
+    }: SceneSettings,
+    mut removed_events: EventWriter<ObjectRemovedEvent>,
+) {
+    for load in requests.read() {
+        let scene = match load.scene {
+            Ok(scene) => scene,
+            Err(err) => {
+                warn!("Cannot load scene {}: {}", load.source, err);
+                notifications.error(format!("Cannot load scene: {}", err));
+                dialog.report = Some(SceneLoadReport {
+                    source: load.source,
+                    loaded: 0,
+                    problems: vec![err],
+                });
+                continue;
+            }
+        };
+
+        let mut problems = Vec::new();
+        let mut scene_joints = scene.joints.clone();
+        let mut snapshots: Vec<ObjectSnapshot> = Vec::new();
+        let mut body_states = Vec::new();
+        for object in &scene.objects {
+            match object.to_snapshot() {
+                Ok(snapshot) => {
+                    snapshots.push(snapshot);
+                    body_states.push(object.body_state);
+                }
+                Err(problem) => problems.push(format!("Skipped {}", problem)),
+            }
+            if let Some(mesh) = object
+                .custom_mesh
+                .as_ref()
+                .filter(|mesh| !library.contains(mesh))
+            {
+                problems.push(format!(
+                    "{} (ID: {}) uses mesh \"{}\", which isn't imported. Shown as its bounding box until it is",
+                    object.name, object.id, mesh
+                ));
+            }
+        }
+
+        match &load.mode {
+            SceneLoadMode::Replace => {
+                // Despawning is recursive, so children go with their roots
+                for obj in game_manager.iter().filter(|obj| obj.parent.is_none()) {
+                    commands.entity(obj.entity()).try_despawn();
+                }
+                for obj in game_manager.clear_all() {
+                    removed_events.write(ObjectRemovedEvent::from(&obj));
+                }
+                joints.clear(&mut commands);
+                history.clear();
+                *world = scene.world.clone();
+                dialog.current = load.current.clone();
+                autosave.mark_loaded();
+            }
+            SceneLoadMode::Additive(import) => {
+                let original: Vec<u32> = snapshots.iter().map(|snapshot| snapshot.id).collect();
+                snapshots = reassign_taken_ids(&game_manager, &snapshots);
+                // Joints follow their objects to the new ids
+                let renumbered: HashMap<u32, u32> = original
+                    .into_iter()
+                    .zip(snapshots.iter().map(|snapshot| snapshot.id))
+                    .collect();
+                scene_joints.retain_mut(|joint| {
+                    match (renumbered.get(&joint.parent), renumbered.get(&joint.child)) {
+                        (Some(&parent), Some(&child)) => {
+                            joint.parent = parent;
+                            joint.child = child;
+                            true
+                        }
+                        _ => {
+                            problems.push(format!(
+                                "Skipped joint between objects {} and {}: not in the scene",
+                                joint.parent, joint.child
+                            ));
+                            false
+                        }
+                    }
+                });
+                rename_clashes(&game_manager, &mut snapshots);
+                let ids: BTreeSet<u32> = snapshots.iter().map(|snapshot| snapshot.id).collect();
+                for snapshot in &mut snapshots {
+                    if snapshot.parent.is_none_or(|parent| !ids.contains(&parent)) {
+                        snapshot.transform.translation += import.offset;
+                    }
+                }
+            }
+        }
+
+        let failed = restore_objects(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut game_manager,
+            &snapshots,
+            time.elapsed_secs_f64(),
+        );
+        for (id, err) in &failed {
+            problems.push(format!("Skipped object {}: {}", id, err));
+        }
+        game_manager.reconcile_ids();
+        for joint in scene_joints {
+            if let Err(err) = joints.attach(&mut commands, &game_manager, joint) {
+                problems.push(format!(
+                    "Skipped joint between objects {} and {}: {}",
+                    joint.parent, joint.child, err
+                ));
+            }
+        }
+        if let SceneLoadMode::Additive(import) = &load.mode {
+            let imported: Vec<ObjectSnapshot> = snapshots
+                .iter()
+                .filter(|snapshot| failed.iter().all(|(id, _)| *id != snapshot.id))
+                .cloned()
+                .collect();
+            if let Some(name) = &import.group {
+                match create_import_group(&mut groups, name) {
+                    Ok(group) => {
+                        for snapshot in &imported {
+                            let _ = groups.add_member(&game_manager, &group, snapshot.id);
+                        }
+                    }
+                    Err(err) => problems.push(format!("Imported objects not grouped: {}", err)),
+                }
+            }
+            // Undone as a whole
+            if !imported.is_empty() {
+                history.push(EditorCommand::SpawnBatch(imported), time.elapsed_secs_f64());
+            }
+        }
+        // Additive loads may renumber, but keep the order, so states line up
+        for (snapshot, state) in snapshots.iter().zip(&body_states) {
+            if let Some(state) = state
+                && let Some(obj) = game_manager.get_object_by_id(snapshot.id)
+            {
+                state.apply(&mut commands, obj.entity());
+            }
+        }
+
+        let loaded = snapshots.len() - failed.len();
+        info!("Loaded {} objects from {}", loaded, load.source);
+        if problems.is_empty() {
+            notifications.info(format!("Loaded {} objects from {}", loaded, load.source));
+        } else {
+            notifications.warn(format!(
+                "Loaded {} objects, {} problems",
+                loaded,
+                problems.len()
+            ));
+            dialog.report = Some(SceneLoadReport {
+                source: load.source,
+                loaded,
+                problems,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SceneDialogAction {
+    #[default]
+    Save,
+    Load,
+}
+
+// Path prompt for saving and loading, opened from the Scene menu, the palette,
+// Ctrl+S or Ctrl+O. The file is chosen in the platform's own dialog, this
+// window holds the options that go with it
+#[derive(Resource, Default)]
+pub struct SceneFileDialog {
+    pub open: bool,
+    pub action: SceneDialogAction,
+    pub path: String,
+    pub additive: bool,
+    // Applied to additive loads
+    pub import_offset: Vec3,
+    pub import_group: bool,
+    // Kept for later Ctrl+S saves and autosaves too
+    pub include_physics: bool,
+    // File the scene was last saved to or loaded from, Ctrl+S writes there
+    // without asking
+    pub current: Option<PathBuf>,
+    pub report: Option<SceneLoadReport>,
+    // The native file dialog while it's up. It waits on the IO pool so the
+    // editor keeps drawing, None when it's cancelled
+    picker: Option<Task<Option<PathBuf>>>,
+}
+
+impl SceneFileDialog {
+    fn open_for(&mut self, action: SceneDialogAction) {
+        self.path = self
+            .current
+            .clone()
+            .unwrap_or_else(default_scene_path)
+            .display()
+            .to_string();
+        self.action = action;
+        self.open = true;
+        self.browse();
+    }
+
+    // Choose the file in the native dialog, starting where the path points
+    pub fn browse(&mut self) {
+        if self.picker.is_some() {
+            return;
+        }
+        let start = PathBuf::from(self.path.trim());
+        let mut picker =
+            rfd::AsyncFileDialog::new().add_filter("Navi scene", &["ron", "json", "nvscene"]);
+        if let Some(dir) = start.parent().filter(|dir| dir.is_dir()) {
+            picker = picker.set_directory(dir);
+        }
+        let action = self.action;
+        self.picker = Some(IoTaskPool::get().spawn(async move {
+            let file = match action {
+                SceneDialogAction::Save => {
+                    let name = start
+                        .file_name()
+                        .map_or(DEFAULT_SCENE_FILE_NAME.into(), |name| {
+                            name.to_string_lossy().into_owned()
+                        });
+                    picker
+                        .set_title("Save scene")
+                        .set_file_name(name)
+                        .save_file()
+                        .await
+                }
+                SceneDialogAction::Load => picker.set_title("Open scene").pick_file().await,
+            };
+            file.map(|file| file.path().to_path_buf())
+        }));
+    }
+
+    pub fn picking(&self) -> bool {
+        self.picker.is_some()
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        // Dropping the task stops waiting on the native dialog
+        self.picker = None;
+    }
+
+    pub fn open_save(&mut self) {
+        self.open_for(SceneDialogAction::Save);
+    }
+
+    pub fn open_load(&mut self) {
+        self.open_for(SceneDialogAction::Load);
+    }
+
+    pub fn open_import(&mut self) {
+        self.open_for(SceneDialogAction::Load);
+        self.additive = true;
+        self.import_group = true;
+    }
+}
+
+// Ctrl+S saves to the current file, or asks for one the first time. Ctrl+O
+// asks for a scene to open
+pub fn scene_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut dialog: ResMut<SceneFileDialog>,
+    mut save_events: EventWriter<SaveSceneEvent>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyO) {
+        dialog.open_load();
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        match dialog.current.clone() {
+            Some(path) => {
+                save_events.write(SaveSceneEvent {
+                    path,
+                    include_physics: dialog.include_physics,
+                });
+            }
+            None => dialog.open_save(),
+        }
+    }
+}
+
+pub fn scene_file_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<SceneFileDialog>,
+    mut save_events: EventWriter<SaveSceneEvent>,
+    mut load_events: EventWriter<LoadSceneEvent>,
+) {
+    if !dialog.open && dialog.report.is_none() {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    if let Some(report) = &dialog.report {
+        let mut dismissed = false;
+        egui::Window::new("Scene load problems")
+            .collapsible(false)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}: loaded {} objects",
+                    report.source, report.loaded
+                ));
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for problem in &report.problems {
+                            ui.label(problem);
+                        }
+                    });
+                dismissed = ui.button("OK").clicked();
+            });
+        if dismissed {
+            dialog.report = None;
+        }
+    }
+    if !dialog.open {
+        return;
+    }
+    if let Some(task) = &dialog.picker
+        && task.is_finished()
+        && let Some(task) = dialog.picker.take()
+        && let Some(path) = block_on(task)
+    {
+        dialog.path = path.display().to_string();
+    }
+
+    let title = match dialog.action {
+        SceneDialogAction::Save => "Save scene",
+        SceneDialogAction::Load => "Open scene",
+    };
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("File (.ron, .json or .nvscene)");
+            let response = ui
+                .horizontal(|ui| {
+                    let response =
+                        ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(300.0));
+                    let picking = dialog.picking();
+                    if ui
+                        .add_enabled(!picking, egui::Button::new("Browse..."))
+                        .clicked()
+                    {
+                        dialog.browse();
+                    }
+                    response
+                })
+                .inner;
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if dialog.action == SceneDialogAction::Save {
+                ui.checkbox(&mut dialog.include_physics, "Include physics state")
+                    .on_hover_text("Velocities and sleep, so moving objects keep moving on load");
+            }
+            if dialog.action == SceneDialogAction::Load {
+                ui.checkbox(&mut dialog.additive, "Add to the current scene")
+                    .on_hover_text(
+                        "Otherwise the current scene is replaced and undo history cleared",
+                    );
+                if dialog.additive {
+                    ui.horizontal(|ui| {
+                        ui.label("Offset");
+                        ui.add(egui::DragValue::new(&mut dialog.import_offset.x).speed(0.1));
+                        ui.add(egui::DragValue::new(&mut dialog.import_offset.y).speed(0.1));
+                        ui.add(egui::DragValue::new(&mut dialog.import_offset.z).speed(0.1));
+                    });
+                    ui.checkbox(&mut dialog.import_group, "Group the imported objects")
+                        .on_hover_text("In a new group named after the file");
+                }
+            }
+            ui.horizontal(|ui| {
+                let path = dialog.path.trim().to_string();
+                let confirm = match dialog.action {
+                    SceneDialogAction::Save => "Save",
+                    SceneDialogAction::Load => "Open",
+                };
+                let ready = !path.is_empty() && !dialog.picking();
+                if (ui.add_enabled(ready, egui::Button::new(confirm)).clicked() || entered) && ready
+                {
+                    let path = PathBuf::from(path);
+                    match dialog.action {
+                        SceneDialogAction::Save => {
+                            save_events.write(SaveSceneEvent {
+                                path,
+                                include_physics: dialog.include_physics,
+                            });
+                        }
+                        SceneDialogAction::Load => {
+                            let mode = if dialog.additive {
+                                let group = dialog
+                                    .import_group
+                                    .then(|| path.file_stem())
+                                    .flatten()
+                                    .map(|stem| stem.to_string_lossy().into_owned());
+                                SceneLoadMode::Additive(SceneImport {
+                                    offset: dialog.import_offset,
+                                    group,
+                                })
+                            } else {
+                                SceneLoadMode::Replace
+                            };
+                            load_events.write(LoadSceneEvent {
+                                path,
+                                mode,
+                                save_to: None,
+                            });
+                        }
+                    }
+                    dialog.close();
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.close();
+                }
+            });
+        });
+}
+
+pub fn register_scene_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Save scene", Some(CommandHint::Keys("Ctrl+S")), |world| {
+        let dialog = world.resource::<SceneFileDialog>();
+        match dialog.current.clone() {
+            Some(path) => {
+                let include_physics = dialog.include_physics;
+                world.send_event(SaveSceneEvent {
+                    path,
+                    include_physics,
+                });
+            }
+            None => world.resource_mut::<SceneFileDialog>().open_save(),
+        }
+    });
+    registry.register("Save scene as...", None, |world| {
+        world.resource_mut::<SceneFileDialog>().open_save();
+    });
+    registry.register(
+        "Open scene...",
+        Some(CommandHint::Keys("Ctrl+O")),
+        |world| {
+            world.resource_mut::<SceneFileDialog>().open_load();
+        },
+    );
+    registry.register("Import scene (additive)...", None, |world| {
+        world.resource_mut::<SceneFileDialog>().open_import();
+    });
+}
+
+// Saving and loading scene files
+pub struct SceneFilePlugin;
+
+impl Plugin for SceneFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveSceneEvent>()
+            .add_event::<LoadSceneEvent>()
+            .add_event::<RestoreSceneEvent>()
+            .init_resource::<SceneFileDialog>()
+            .add_systems(Startup, register_scene_commands)
+            .add_systems(
+                EguiContextPass,
+                scene_file_dialog_system.in_set(UiSet::Popups),
+            )
+            .add_systems(Update, save_scene_system.in_set(EditorSet::Bookkeeping));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::fixtures::sample_scene;
+
+    fn round_trip(format: SceneFormat, scene: &SceneFile) -> SceneFile {
+        format.parse(&format.serialize(scene).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn json_loads_the_same_scene_as_ron() {
+        let scene = sample_scene();
+        let from_ron = round_trip(SceneFormat::Ron, &scene);
+        let from_json = round_trip(SceneFormat::Json, &scene);
+        assert_eq!(from_json, from_ron);
+        assert_eq!(from_ron, scene);
+        assert_eq!(round_trip(SceneFormat::Binary, &scene), scene);
+    }
+
+    #[test]
+    fn saving_twice_gives_the_same_file() {
+        let scene = sample_scene();
+        for format in SceneFormat::all() {
+            let once = format.serialize(&scene).unwrap();
+            let twice = format.serialize(&format.parse(&once).unwrap()).unwrap();
+            assert_eq!(once, twice, "{}", format.name());
+        }
+    }
+
+    // A shape from a newer build only fails its own object
+    #[test]
+    fn unknown_shapes_are_kept_by_name() {
+        let ron = String::from_utf8(SceneFormat::Ron.serialize(&sample_scene()).unwrap()).unwrap();
+        let ron = ron.replace("shape_type: CustomMesh", "shape_type: Torus");
+        let json =
+            String::from_utf8(SceneFormat::Json.serialize(&sample_scene()).unwrap()).unwrap();
+        let json = json.replace(
+            "\"shape_type\": \"CustomMesh\"",
+            "\"shape_type\": \"Torus\"",
+        );
+        for (format, contents) in [(SceneFormat::Ron, ron), (SceneFormat::Json, json)] {
+            let read = format.parse(contents.as_bytes()).unwrap();
+            assert_eq!(
+                read.objects[2].shape_type,
+                SceneShape::Unknown("Torus".to_string())
+            );
+            assert!(read.objects[2].to_snapshot().unwrap_err().contains("Torus"));
+            assert!(read.objects[0].to_snapshot().is_ok());
+        }
+    }
+
+    #[test]
+    fn parse_errors_give_the_format_and_position() {
+        let ron = SceneFormat::Ron.serialize(&sample_scene()).unwrap();
+        let err = SceneFormat::Json.parse(&ron).unwrap_err();
+        assert!(
+            err.starts_with("invalid JSON at line 1, column 1:"),
+            "{}",
+            err
+        );
+
+        let broken = b"(\n    version: 1,\n    objects: [oops],\n)";
+        let err = SceneFormat::Ron.parse(broken).unwrap_err();
+        assert!(err.starts_with("invalid RON at line 3, column"), "{}", err);
+    }
+
+    #[test]
+    fn reading_a_file_with_the_wrong_extension_names_the_right_one() {
+        let dir = std::env::temp_dir().join(format!("navi-scene-test-{}", std::process::id()));
+        let path = dir.join("saved-as-ron.json");
+        sample_scene().write(&dir.join("scene.ron")).unwrap();
+        fs::copy(dir.join("scene.ron"), &path).unwrap();
+
+        let err = SceneFile::read(&path).unwrap_err();
+        let read_back = SceneFile::read(&dir.join("scene.ron"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            err.ends_with("(the file is RON, give it a .ron extension)"),
+            "{}",
+            err
+        );
+        assert_eq!(read_back.unwrap(), sample_scene());
+    }
+
+    #[test]
+    fn foreign_and_newer_binary_files_are_rejected() {
+        let binary = SceneFormat::Binary.serialize(&sample_scene()).unwrap();
+        assert_eq!(SceneFormat::Binary.parse(&binary).unwrap(), sample_scene());
+
+        let mut foreign = binary.clone();
+        foreign[..BINARY_MAGIC.len()].copy_from_slice(b"NOTNAVI\0");
+        let err = SceneFormat::Binary.parse(&foreign).unwrap_err();
+        assert!(err.ends_with("not a Navi binary scene"), "{}", err);
+        let err = SceneFormat::Binary
+            .parse(&binary[..BINARY_HEADER_LEN - 1])
+            .unwrap_err();
+        assert!(err.ends_with("not a Navi binary scene"), "{}", err);
+
+        let mut newer = binary;
+        newer[BINARY_MAGIC.len()..BINARY_HEADER_LEN]
+            .copy_from_slice(&(BINARY_CONTAINER_VERSION + 1).to_le_bytes());
+        let err = SceneFormat::Binary.parse(&newer).unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "container version {} requires a newer Navi",
+                BINARY_CONTAINER_VERSION + 1
+            )),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::engine::core::EditorSet;
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::notify::EditorNotifications;
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::scene::save_scene_system;
+use crate::engine::thumbnails::THUMBNAIL_SIZE;
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::tasks::{IoTaskPool, Task, block_on};
+use bevy::window::PrimaryWindow;
+use bevy_egui::*;
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Saved with the editor prefs
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotSettings {
+    pub directory: PathBuf,
+    // Off hides the editor UI for the captured frame
+    pub include_ui: bool,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: default_screenshot_dir(),
+            include_ui: false,
+        }
+    }
+}
+
+// Pictures/Navi, or screenshots/ next to the editor when there's no pictures folder
+pub fn default_screenshot_dir() -> PathBuf {
+    UserDirs::new()
+        .and_then(|dirs| dirs.picture_dir().map(|dir| dir.join("Navi")))
+        .unwrap_or_else(|| PathBuf::from("screenshots"))
+}
+
+// Capture the primary window. Without a path the capture goes to the settings
+// directory under a timestamped name
+#[derive(Event, Debug, Clone, Default)]
+pub struct CaptureScreenshotEvent {
+    pub path: Option<PathBuf>,
+    // Scaled down to THUMBNAIL_SIZE without the UI, and written without a
+    // toast. Skipped when there's no window to capture
+    pub thumbnail: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct Screenshots {
+    // Set for the frame a capture without UI is taken on
+    hide_ui: bool,
+    // PNG writes in flight
+    saves: Vec<Task<Result<PathBuf, String>>>,
+}
+
+// navi-2026-10-15_14-03-22-123.png for ("navi", "png"), in UTC
+pub fn timestamped_name(stem: &str, extension: &str, now: SystemTime) -> String {
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{}-{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}.{}",
+        stem,
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        since_epoch.subsec_millis(),
+        extension
+    )
+}
+
+// Days since 1970-01-01 to a calendar date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// Runs on the IO pool. The format comes from the extension
+fn write_image(image: Image, path: &Path) -> Result<(), String> {
+    let image = image.try_into_dynamic().map_err(|err| err.to_string())?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    // Alpha holds brightness with HDR on, so it's dropped
+    image.to_rgb8().save(path).map_err(|err| err.to_string())
+}
+
+pub fn screenshot_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut capture_events: EventWriter<CaptureScreenshotEvent>,
+) {
+    if bindings.just_pressed(InputAction::Screenshot, &keyboard_input) {
+        capture_events.write_default();
+    }
+}
+
+// Crop to the thumbnail's aspect from the middle, then scale down
+fn write_thumbnail(image: Image, path: &Path) -> Result<(), String> {
+    let image = image.try_into_dynamic().map_err(|err| err.to_string())?;
+    image
+        .resize_to_fill(
+            THUMBNAIL_SIZE.x,
+            THUMBNAIL_SIZE.y,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_rgb8()
+        .save(path)
+        .map_err(|err| err.to_string())
+}
+
+// Start requested captures and report finished writes
+pub fn capture_screenshot_system(
+    mut commands: Commands,
+    mut capture_events: EventReader<CaptureScreenshotEvent>,
+    settings: Res<ScreenshotSettings>,
+    mut screenshots: ResMut<Screenshots>,
+    mut notifications: ResMut<EditorNotifications>,
+    windows: Query<(), With<PrimaryWindow>>,
+) {
+    for event in capture_events.read() {
+        if event.thumbnail {
+            let Some(path) = event.path.clone() else {
+                continue;
+            };
+            if windows.is_empty() {
+                debug!(
+                    "No window to capture, skipping thumbnail {}",
+                    path.display()
+                );
+                continue;
+            }
+            screenshots.hide_ui = true;
+            commands.spawn(Screenshot::primary_window()).observe(
+                move |trigger: Trigger<ScreenshotCaptured>| {
+                    let image = trigger.event().0.clone();
+                    let path = path.clone();
+                    IoTaskPool::get()
+                        .spawn(async move {
+                            if let Err(err) = write_thumbnail(image, &path) {
+                                warn!("Cannot save thumbnail {}: {}", path.display(), err);
+                            }
+                        })
+                        .detach();
+                },
+            );
+            continue;
+        }
+
+        let path = event.path.clone().unwrap_or_else(|| {
+            settings
+                .directory
+                .join(timestamped_name("navi", "png", SystemTime::now()))
+        });
+        if !settings.include_ui {
+            screenshots.hide_ui = true;
+        }
+        commands.spawn(Screenshot::primary_window()).observe(
+            move |trigger: Trigger<ScreenshotCaptured>, mut screenshots: ResMut<Screenshots>| {
+                let image = trigger.event().0.clone();
+                let path = path.clone();
+                let task = IoTaskPool::get()
+                    .spawn(async move { write_image(image, &path).map(|()| path) });
+                screenshots.saves.push(task);
+            },
+        );
+    }
+
+    if screenshots.saves.iter().all(|task| !task.is_finished()) {
+        return;
+    }
+    let (finished, running) = screenshots
+        .saves
+        .drain(..)
+        .partition::<Vec<_>, _>(|task| task.is_finished());
+    screenshots.saves = running;
+    for task in finished {
+        match block_on(task) {
+            Ok(path) => {
+                info!("Saved screenshot to {}", path.display());
+                notifications
+                    .info_with_link(format!("Screenshot saved to {}", path.display()), &path);
+            }
+            Err(err) => {
+                warn!("Cannot save screenshot: {}", err);
+                notifications.error(format!("Cannot save screenshot: {}", err));
+            }
+        }
+    }
+}
+
+// Drop the egui draw list on the frame being captured. Texture updates are
+// kept so the next frame still draws correctly
+pub fn hide_ui_for_screenshot_system(
+    mut screenshots: ResMut<Screenshots>,
+    mut outputs: Query<&mut EguiRenderOutput>,
+) {
+    if !screenshots.hide_ui {
+        return;
+    }
+    screenshots.hide_ui = false;
+    for mut output in &mut outputs {
+        output.paint_jobs = Arc::new(Vec::new());
+    }
+}
+
+// Screenshot section of the Preferences panel
+pub fn screenshot_settings_ui(ui: &mut egui::Ui, settings: &mut ScreenshotSettings) {
+    ui.horizontal(|ui| {
+        ui.label("Screenshots");
+        let mut directory = settings.directory.display().to_string();
+        if ui.text_edit_singleline(&mut directory).changed() {
+            settings.directory = PathBuf::from(directory);
+        }
+    });
+    ui.checkbox(&mut settings.include_ui, "Include editor UI in screenshots");
+}
+
+pub fn register_screenshot_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register(
+        "Take screenshot",
+        Some(CommandHint::Binding(InputAction::Screenshot)),
+        |world| {
+            world.send_event(CaptureScreenshotEvent::default());
+        },
+    );
+}
+
+// Viewport screenshots and the thumbnails written with each save
+pub struct ScreenshotsPlugin;
+
+impl Plugin for ScreenshotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CaptureScreenshotEvent>()
+            .init_resource::<Screenshots>()
+            .add_systems(Startup, register_screenshot_commands)
+            // Same frame as the save, so the thumbnail matches the file
+            .add_systems(
+                Update,
+                capture_screenshot_system
+                    .after(save_scene_system)
+                    .in_set(EditorSet::Bookkeeping),
+            )
+            // After egui has built this frame's draw list
+            .add_systems(
+                PostUpdate,
+                hide_ui_for_screenshot_system.after(EguiPostUpdateSet::ProcessOutput),
+            );
+    }
+}
@@ -0,0 +1,323 @@
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::labels::cursor_ray;
+use crate::engine::objects::{
+    DUPLICATE_OFFSET, DespawnEntityEvent, DespawnTarget, DuplicateObjectEvent, GameObjectManager,
+    delete_old_objects, select_recent_spawns,
+};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::toolbar::MainCamera;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::render_resource::Face;
+use bevy::window::PrimaryWindow;
+use bevy_egui::input::EguiWantsInput;
+use std::collections::HashSet;
+
+// Outline hull size relative to the object, and its colour
+const OUTLINE_SCALE: f32 = 1.06;
+const OUTLINE_COLOR: Color = Color::srgb(1.0, 0.6, 0.1);
+
+// Objects currently selected in the editor, the last one added is the primary
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub entities: Vec<Entity>,
+    // Row used as the start point for Shift-click range selection
+    pub anchor: Option<Entity>,
+}
+
+impl Selection {
+    // Replace the selection with a single entity
+    pub fn select(&mut self, entity: Entity) {
+        self.entities.clear();
+        self.entities.push(entity);
+        self.anchor = Some(entity);
+    }
+
+    pub fn add(&mut self, entity: Entity) {
+        if !self.contains(entity) {
+            self.entities.push(entity);
+        }
+    }
+
+    // Ctrl-click behaviour: add if missing, remove if present
+    pub fn toggle(&mut self, entity: Entity) {
+        if let Some(index) = self.entities.iter().position(|&e| e == entity) {
+            self.entities.remove(index);
+        } else {
+            self.entities.push(entity);
+        }
+        self.anchor = Some(entity);
+    }
+
+    // Replace the selection with a range, keeping the anchor where it was
+    pub fn select_range(&mut self, entities: impl IntoIterator<Item = Entity>) {
+        self.entities.clear();
+        for entity in entities {
+            self.add(entity);
+        }
+    }
+
+    // Every unlocked object
+    pub fn select_all(&mut self, game_manager: &GameObjectManager) {
+        self.entities = game_manager
+            .iter()
+            .filter(|obj| !obj.locked)
+            .map(|obj| obj.entity())
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.anchor = None;
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn primary(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    // Ids of every selected object still tracked by the manager
+    pub fn ids(&self, game_manager: &GameObjectManager) -> Vec<u32> {
+        self.entities
+            .iter()
+            .filter_map(|&entity| game_manager.get_object_by_entity(entity))
+            .map(|obj| obj.id)
+            .collect()
+    }
+}
+
+// How far select nearby reaches from the primary selection
+pub const NEARBY_RADIUS: f32 = 3.0;
+
+// Select every unlocked object within NEARBY_RADIUS of the primary selection,
+// which stays primary
+pub fn select_nearby_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    game_manager: Res<GameObjectManager>,
+    mut selection: ResMut<Selection>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !bindings.just_pressed(InputAction::SelectNearby, &keyboard_input)
+    {
+        return;
+    }
+    let Some(origin) = selection
+        .primary()
+        .and_then(|entity| game_manager.get_object_by_entity(entity))
+    else {
+        return;
+    };
+
+    let nearby: Vec<Entity> = game_manager
+        .objects_within_radius(origin.position(), NEARBY_RADIUS)
+        .into_iter()
+        .filter(|obj| !obj.locked && obj.id != origin.id)
+        .map(|obj| obj.entity())
+        .collect();
+    let primary = origin.entity();
+    info!(
+        "Selected {} objects within {}m of {}",
+        nearby.len(),
+        NEARBY_RADIUS,
+        origin.name
+    );
+    selection.select_range(nearby.into_iter().chain(std::iter::once(primary)));
+}
+
+// Select the object closest to where the cursor meets the y = 0 plane
+pub fn select_nearest_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    game_manager: Res<GameObjectManager>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut selection: ResMut<Selection>,
+) {
+    if egui_input.wants_keyboard_input()
+        || !bindings.just_pressed(InputAction::SelectNearestToCursor, &keyboard_input)
+    {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), cameras.single()) else {
+        return;
+    };
+    let Some(point) = cursor_ray(window, camera, camera_transform).and_then(|ray| {
+        ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))
+            .map(|distance| ray.get_point(distance))
+    }) else {
+        return;
+    };
+
+    match game_manager.nearest_object(point, None) {
+        Some(obj) if !obj.locked => selection.select(obj.entity()),
+        Some(obj) => info!("Nearest object {} is locked", obj.name),
+        None => info!("No objects to select"),
+    }
+}
+
+// Prune selected entities that the manager no longer tracks, so the selection
+// never holds stale handles after cleanup_destroyed_entities_system runs.
+// Objects that were just locked are dropped too
+pub fn prune_selection_system(
+    mut selection: ResMut<Selection>,
+    game_manager: Res<GameObjectManager>,
+) {
+    let before = selection.len();
+    selection.entities.retain(|&entity| {
+        game_manager
+            .get_object_by_entity(entity)
+            .is_some_and(|obj| !obj.locked)
+    });
+
+    if selection
+        .anchor
+        .is_some_and(|anchor| game_manager.get_object_by_entity(anchor).is_none())
+    {
+        selection.anchor = None;
+    }
+
+    let removed = before - selection.len();
+    if removed > 0 {
+        info!("Removed {} stale entities from the selection", removed);
+    }
+}
+
+// Slightly larger copy of a selected object's mesh with only its back faces
+// drawn, so it shows as a rim around the object. Spawned as a child so it
+// follows the object and goes away with it when the object is despawned
+#[derive(Component)]
+pub struct SelectionOutline;
+
+// Add outline hulls for newly selected objects, remove them from deselected
+// ones and keep their mesh in step with dimension edits
+pub fn selection_outline_system(
+    mut commands: Commands,
+    selection: Res<Selection>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+    objects: Query<&Mesh3d, Without<SelectionOutline>>,
+    mut outlines: Query<(Entity, &ChildOf, &mut Mesh3d), With<SelectionOutline>>,
+) {
+    let mut outlined = HashSet::new();
+    for (entity, child_of, mut mesh) in &mut outlines {
+        let owner = child_of.parent();
+        match objects.get(owner) {
+            Ok(owner_mesh) if selection.contains(owner) => {
+                if mesh.0 != owner_mesh.0 {
+                    mesh.0 = owner_mesh.0.clone();
+                }
+                outlined.insert(owner);
+            }
+            _ => commands.entity(entity).try_despawn(),
+        }
+    }
+
+    for &entity in &selection.entities {
+        if outlined.contains(&entity) {
+            continue;
+        }
+        let Ok(mesh) = objects.get(entity) else {
+            continue;
+        };
+        let material = material
+            .get_or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: OUTLINE_COLOR,
+                    unlit: true,
+                    cull_mode: Some(Face::Front),
+                    ..default()
+                })
+            })
+            .clone();
+        commands.spawn((
+            SelectionOutline,
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(material),
+            Transform::from_scale(Vec3::splat(OUTLINE_SCALE)),
+            NotShadowCaster,
+            ChildOf(entity),
+        ));
+    }
+}
+
+pub fn register_selection_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Select all", None, |world| {
+        world.resource_scope(|world, mut selection: Mut<Selection>| {
+            selection.select_all(world.resource::<GameObjectManager>());
+        });
+    });
+    registry.register("Clear selection", None, |world| {
+        world.resource_mut::<Selection>().clear();
+    });
+    registry.register(
+        "Select objects spawned in the last 10 seconds",
+        None,
+        select_recent_spawns,
+    );
+    registry.register(
+        "Delete objects older than the hierarchy age limit",
+        None,
+        delete_old_objects,
+    );
+    registry.register(
+        "Delete selected",
+        Some(CommandHint::Binding(InputAction::DeleteSelected)),
+        |world| {
+            let ids = world
+                .resource::<Selection>()
+                .ids(world.resource::<GameObjectManager>());
+            for id in ids {
+                world.send_event(DespawnEntityEvent {
+                    target: DespawnTarget::Id(id),
+                });
+            }
+        },
+    );
+    registry.register(
+        "Duplicate selected",
+        Some(CommandHint::Keys("Ctrl+D")),
+        |world| {
+            let game_manager = world.resource::<GameObjectManager>();
+            let ids: Vec<u32> = world
+                .resource::<Selection>()
+                .ids(game_manager)
+                .into_iter()
+                .filter(|&id| {
+                    game_manager
+                        .get_object_by_id(id)
+                        .is_some_and(|obj| !obj.locked)
+                })
+                .collect();
+            for source_id in ids {
+                world.send_event(DuplicateObjectEvent {
+                    source_id,
+                    offset: DUPLICATE_OFFSET,
+                });
+            }
+        },
+    );
+}
+
+// The selection and its outline
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_systems(Startup, register_selection_commands);
+    }
+}
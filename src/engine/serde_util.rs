@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Serialize a Vec3 as a plain [x, y, z] array
+pub mod vec3 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Vec3, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_array().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec3, D::Error> {
+        <[f32; 3]>::deserialize(deserializer).map(Vec3::from_array)
+    }
+}
+
+// Serialize a Quat as a plain [x, y, z, w] array
+pub mod quat {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Quat, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_array().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Quat, D::Error> {
+        <[f32; 4]>::deserialize(deserializer).map(|q| Quat::from_array(q).normalize())
+    }
+}
+
+// Serialize a Color as a plain sRGB [r, g, b, a] array
+pub mod color {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_srgba().to_f32_array().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        <[f32; 4]>::deserialize(deserializer).map(|c| Color::srgba(c[0], c[1], c[2], c[3]))
+    }
+}
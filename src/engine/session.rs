@@ -0,0 +1,154 @@
+use crate::engine::autosave::{Autosave, autosave_system};
+use crate::engine::config::NaviConfig;
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::oplog::OperationLog;
+use crate::engine::scene::{LoadSceneEvent, SceneFileDialog, SceneLoadMode};
+use crate::engine::templates::{DEFAULT_TEMPLATE, NewSceneEvent};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::*;
+use std::path::{Path, PathBuf};
+
+// What the editor starts with, picked on the command line
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub enum StartupScene {
+    // The default template, offering to reopen the scene open when the
+    // editor last closed
+    #[default]
+    Last,
+    // The default template, nothing offered
+    Empty,
+    // Load this scene. If it can't be read the load report says why and the
+    // scene stays empty
+    Open(PathBuf),
+}
+
+// Last session's scene waiting for a yes or no
+#[derive(Resource, Debug, Default)]
+pub struct ReopenPrompt {
+    pub scene: Option<PathBuf>,
+}
+
+pub fn open_startup_scene_system(
+    startup: Res<StartupScene>,
+    mut autosave: ResMut<Autosave>,
+    mut prompt: ResMut<ReopenPrompt>,
+    mut load_events: EventWriter<LoadSceneEvent>,
+    mut new_scene_events: EventWriter<NewSceneEvent>,
+) {
+    let default_template = NewSceneEvent {
+        template: DEFAULT_TEMPLATE.to_string(),
+    };
+    match &*startup {
+        StartupScene::Last => {
+            new_scene_events.write(default_template);
+            // A newer autosave gets the recovery window instead, which offers
+            // the saved scene too
+            if autosave.recovery.is_none() {
+                prompt.scene = autosave.last_scene.clone().filter(|scene| scene.exists());
+            }
+        }
+        StartupScene::Empty => {
+            new_scene_events.write(default_template);
+            autosave.recovery = None;
+        }
+        StartupScene::Open(path) => {
+            // Recovery only makes sense for the scene being opened
+            if autosave
+                .recovery
+                .as_ref()
+                .is_some_and(|recovery| recovery.scene != *path)
+            {
+                autosave.recovery = None;
+            }
+            if autosave.recovery.is_none() {
+                load_events.write(LoadSceneEvent {
+                    path: path.clone(),
+                    mode: SceneLoadMode::Replace,
+                    save_to: None,
+                });
+            }
+        }
+    }
+}
+
+pub fn reopen_prompt_window_system(
+    mut contexts: EguiContexts,
+    mut prompt: ResMut<ReopenPrompt>,
+    mut load_events: EventWriter<LoadSceneEvent>,
+) {
+    let Some(scene) = prompt.scene.clone() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let mut closed = false;
+    egui::Window::new("Reopen last scene")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!("Continue with {}?", scene.display()));
+            ui.horizontal(|ui| {
+                if ui.button("Reopen").clicked() {
+                    load_events.write(LoadSceneEvent {
+                        path: scene.clone(),
+                        mode: SceneLoadMode::Replace,
+                        save_to: None,
+                    });
+                    closed = true;
+                }
+                if ui.button("Start empty").clicked() {
+                    closed = true;
+                }
+            });
+        });
+    if closed {
+        prompt.scene = None;
+    }
+}
+
+// "scene.ron* - Navi", the star while there are unsaved changes. The app name
+// is the configured window title
+pub fn window_title_system(
+    config: Res<NaviConfig>,
+    dialog: Res<SceneFileDialog>,
+    autosave: Res<Autosave>,
+    log: Res<OperationLog>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let name = dialog
+        .current
+        .as_deref()
+        .and_then(Path::file_name)
+        .map_or("Untitled".into(), |name| name.to_string_lossy());
+    let dirty = if autosave.is_dirty(&log) { "*" } else { "" };
+    let title = format!("{}{} - {}", name, dirty, config.title);
+    for mut window in &mut windows {
+        if window.title != title {
+            window.title = title.clone();
+        }
+    }
+}
+
+// Opening the startup scene, the reopen prompt and the window title
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReopenPrompt>()
+            .add_systems(Startup, open_startup_scene_system)
+            .add_systems(
+                EguiContextPass,
+                reopen_prompt_window_system.in_set(UiSet::Popups),
+            )
+            .add_systems(
+                Update,
+                window_title_system
+                    .after(autosave_system)
+                    .in_set(EditorSet::Bookkeeping),
+            );
+    }
+}
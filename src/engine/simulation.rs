@@ -0,0 +1,112 @@
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_rapier3d::prelude::*;
+
+// Whether the physics pipeline is running, editing and spawning work either way
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationState {
+    #[default]
+    Playing,
+    Paused,
+}
+
+impl SimulationState {
+    pub fn is_paused(&self) -> bool {
+        *self == SimulationState::Paused
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SimulationState::Playing => "Playing",
+            SimulationState::Paused => "Paused",
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationEvent {
+    Play,
+    Pause,
+    TogglePause,
+    // Advance a single physics tick, ignored while playing
+    Step,
+}
+
+// Toggle pause and step one tick while paused, P and period by default
+pub fn simulation_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    mut simulation_events: EventWriter<SimulationEvent>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+
+    if bindings.just_pressed(InputAction::TogglePause, &keyboard_input) {
+        simulation_events.write(SimulationEvent::TogglePause);
+    }
+    if bindings.just_pressed(InputAction::StepSimulation, &keyboard_input) {
+        simulation_events.write(SimulationEvent::Step);
+    }
+}
+
+// Drive rapier's pipeline from the simulation state. A step runs the pipeline
+// for this frame only, rapier steps once in PostUpdate and the next frame
+// switches it back off
+pub fn apply_simulation_state_system(
+    mut simulation_events: EventReader<SimulationEvent>,
+    mut state: ResMut<SimulationState>,
+    mut configs: Query<&mut RapierConfiguration>,
+) {
+    let mut step = false;
+    for event in simulation_events.read() {
+        let next = match event {
+            SimulationEvent::Play => SimulationState::Playing,
+            SimulationEvent::Pause => SimulationState::Paused,
+            SimulationEvent::TogglePause if state.is_paused() => SimulationState::Playing,
+            SimulationEvent::TogglePause => SimulationState::Paused,
+            SimulationEvent::Step => {
+                step |= state.is_paused();
+                continue;
+            }
+        };
+        if *state != next {
+            *state = next;
+            info!("Simulation {}", state.label());
+        }
+    }
+
+    let active = !state.is_paused() || step;
+    for mut config in &mut configs {
+        if config.physics_pipeline_active != active {
+            config.physics_pipeline_active = active;
+        }
+    }
+}
+
+pub fn register_simulation_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register_event(
+        "Play / pause simulation",
+        Some(CommandHint::Binding(InputAction::TogglePause)),
+        SimulationEvent::TogglePause,
+    );
+    registry.register_event(
+        "Step simulation",
+        Some(CommandHint::Binding(InputAction::StepSimulation)),
+        SimulationEvent::Step,
+    );
+}
+
+// Pausing and stepping the physics simulation
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SimulationEvent>()
+            .init_resource::<SimulationState>()
+            .add_systems(Startup, register_simulation_commands);
+    }
+}
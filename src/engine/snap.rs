@@ -0,0 +1,269 @@
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use bevy::prelude::*;
+use bevy_egui::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Smallest grid size the UI will accept
+pub const MIN_GRID_SIZE: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapError {
+    InvalidGridSize(f32),
+    InvalidRotationStep(f32),
+}
+
+impl fmt::Display for SnapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapError::InvalidGridSize(size) => {
+                write!(
+                    f,
+                    "grid size must be at least {}, got {}",
+                    MIN_GRID_SIZE, size
+                )
+            }
+            SnapError::InvalidRotationStep(step) => {
+                write!(f, "rotation step must be positive, got {}", step)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapError {}
+
+// Grid snapping applied to spawning, inspector edits and gizmo drags
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapSettings {
+    pub enabled: bool,
+    grid_size: f32,
+    // Rotation step in degrees, None leaves rotation free
+    rotation_step: Option<f32>,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grid_size: 1.0,
+            rotation_step: None,
+        }
+    }
+}
+
+impl SnapSettings {
+    // Bad sizes from a hand-edited prefs file fall back to the defaults
+    pub fn clamped(&self) -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: self.enabled,
+            grid_size: if self.grid_size.is_finite() && self.grid_size >= MIN_GRID_SIZE {
+                self.grid_size
+            } else {
+                defaults.grid_size
+            },
+            rotation_step: self
+                .rotation_step
+                .filter(|step| step.is_finite() && *step > 0.0),
+        }
+    }
+
+    pub fn grid_size(&self) -> f32 {
+        self.grid_size
+    }
+
+    // Sizes below MIN_GRID_SIZE are rejected so snapping never divides by zero
+    // and a grid stays coarse enough to see
+    pub fn set_grid_size(&mut self, size: f32) -> Result<(), SnapError> {
+        if !size.is_finite() || size < MIN_GRID_SIZE {
+            return Err(SnapError::InvalidGridSize(size));
+        }
+        self.grid_size = size;
+        Ok(())
+    }
+
+    pub fn rotation_step(&self) -> Option<f32> {
+        self.rotation_step
+    }
+
+    pub fn set_rotation_step(&mut self, step: Option<f32>) -> Result<(), SnapError> {
+        if let Some(step) = step
+            && (!step.is_finite() || step <= 0.0)
+        {
+            return Err(SnapError::InvalidRotationStep(step));
+        }
+        self.rotation_step = step;
+        Ok(())
+    }
+
+    // Round to the nearest grid point, halves always go up so cells line up
+    // the same way on both sides of the origin
+    pub fn snap_position(&self, position: Vec3) -> Vec3 {
+        if !self.enabled {
+            return position;
+        }
+        (position / self.grid_size + Vec3::splat(0.5)).floor() * self.grid_size
+    }
+
+    pub fn snap_rotation(&self, rotation: Quat) -> Quat {
+        match self.rotation_step {
+            Some(step) if self.enabled => snap_rotation_to(rotation, step),
+            _ => rotation,
+        }
+    }
+}
+
+// Snap each euler angle to a multiple of step_degrees
+pub fn snap_rotation_to(rotation: Quat, step_degrees: f32) -> Quat {
+    let step = step_degrees.to_radians();
+    let (x, y, z) = rotation.to_euler(EulerRot::XYZ);
+    let snap = |angle: f32| (angle / step).round() * step;
+    Quat::from_euler(EulerRot::XYZ, snap(x), snap(y), snap(z))
+}
+
+pub fn snap_panel_system(
+    mut contexts: EguiContexts,
+    mut snap: ResMut<SnapSettings>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Snapping",
+        egui::Window::new("Snapping").default_width(220.0),
+        |ui| {
+            let mut enabled = snap.enabled;
+            if ui.checkbox(&mut enabled, "Snap to grid").changed() {
+                snap.enabled = enabled;
+            }
+
+            let mut grid_size = snap.grid_size();
+            ui.horizontal(|ui| {
+                ui.label("Grid size");
+                ui.add(
+                    egui::DragValue::new(&mut grid_size)
+                        .speed(0.05)
+                        .range(MIN_GRID_SIZE..=100.0),
+                );
+            });
+            if grid_size != snap.grid_size()
+                && let Err(err) = snap.set_grid_size(grid_size)
+            {
+                warn!("{}", err);
+            }
+
+            let mut rotation_enabled = snap.rotation_step().is_some();
+            let mut step = snap.rotation_step().unwrap_or(15.0);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut rotation_enabled, "Rotation step");
+                ui.add_enabled(
+                    rotation_enabled,
+                    egui::DragValue::new(&mut step)
+                        .speed(1.0)
+                        .range(1.0..=180.0)
+                        .suffix("°"),
+                );
+            });
+            let new_step = rotation_enabled.then_some(step);
+            if new_step != snap.rotation_step()
+                && let Err(err) = snap.set_rotation_step(new_step)
+            {
+                warn!("{}", err);
+            }
+        },
+    );
+}
+
+// The Snapping panel, the snap settings are loaded with the prefs
+pub struct SnapPlugin;
+
+impl Plugin for SnapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(EguiContextPass, snap_panel_system.in_set(UiSet::Panels));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapping(grid_size: f32) -> SnapSettings {
+        let mut snap = SnapSettings {
+            enabled: true,
+            ..default()
+        };
+        snap.set_grid_size(grid_size).unwrap();
+        snap
+    }
+
+    fn snapped_x(snap: &SnapSettings, x: f32) -> f32 {
+        snap.snap_position(Vec3::new(x, 0.0, 0.0)).x
+    }
+
+    #[test]
+    fn halves_round_up_on_both_sides_of_the_origin() {
+        let snap = snapping(1.0);
+        assert_eq!(snapped_x(&snap, -0.5), 0.0);
+        assert_eq!(snapped_x(&snap, -1.5), -1.0);
+        assert_eq!(snapped_x(&snap, 0.5), 1.0);
+        assert_eq!(snapped_x(&snap, -0.49), 0.0);
+        assert_eq!(snapped_x(&snap, -0.51), -1.0);
+
+        let snap = snapping(0.25);
+        assert_eq!(snapped_x(&snap, -0.5), -0.5);
+        assert_eq!(snapped_x(&snap, -1.5), -1.5);
+        assert_eq!(snapped_x(&snap, 0.5), 0.5);
+        assert_eq!(snapped_x(&snap, -0.49), -0.5);
+        assert_eq!(snapped_x(&snap, -0.125), 0.0);
+        assert_eq!(snapped_x(&snap, 0.125), 0.25);
+        assert_eq!(snapped_x(&snap, -0.375), -0.25);
+    }
+
+    #[test]
+    fn snapping_off_leaves_positions_alone() {
+        let mut snap = snapping(1.0);
+        snap.enabled = false;
+        let position = Vec3::new(-0.49, 1.5, 2.25);
+        assert_eq!(snap.snap_position(position), position);
+    }
+
+    #[test]
+    fn grid_sizes_below_the_minimum_are_rejected() {
+        let mut snap = snapping(0.5);
+        for size in [0.0, -1.0, f32::NAN, f32::INFINITY, MIN_GRID_SIZE / 2.0] {
+            assert!(
+                matches!(snap.set_grid_size(size), Err(SnapError::InvalidGridSize(_))),
+                "{}",
+                size
+            );
+        }
+        assert_eq!(snap.grid_size(), 0.5);
+        assert_eq!(snap.set_grid_size(MIN_GRID_SIZE), Ok(()));
+        assert_eq!(snap.grid_size(), MIN_GRID_SIZE);
+    }
+
+    #[test]
+    fn rotations_snap_to_the_nearest_step() {
+        let close = |a: Quat, b: Quat| a.angle_between(b) < 1e-3;
+        let turned = |degrees: f32| Quat::from_rotation_y(degrees.to_radians());
+        assert!(close(snap_rotation_to(turned(50.0), 15.0), turned(45.0)));
+        assert!(close(snap_rotation_to(turned(53.0), 15.0), turned(60.0)));
+        assert!(close(snap_rotation_to(turned(-80.0), 90.0), turned(-90.0)));
+
+        let mut snap = snapping(1.0);
+        assert!(close(snap.snap_rotation(turned(50.0)), turned(50.0)));
+        snap.set_rotation_step(Some(15.0)).unwrap();
+        assert!(close(snap.snap_rotation(turned(50.0)), turned(45.0)));
+        snap.enabled = false;
+        assert!(close(snap.snap_rotation(turned(50.0)), turned(50.0)));
+
+        assert!(snap.set_rotation_step(Some(0.0)).is_err());
+        assert!(snap.set_rotation_step(Some(f32::NAN)).is_err());
+        assert_eq!(snap.rotation_step(), Some(15.0));
+    }
+}
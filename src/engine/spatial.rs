@@ -0,0 +1,306 @@
+use bevy::math::I64Vec3;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// Edge length of a grid cell, roughly the size of a default object
+pub const SPATIAL_CELL_SIZE: f32 = 2.0;
+
+// Queries covering more cells than this, or than are occupied, scan every
+// entry instead
+const MAX_QUERY_CELLS: usize = 4096;
+
+// Uniform grid of object positions. Each id lives in the one cell holding its
+// position, queries visit every cell the query volume touches and then check
+// the exact positions, so points on cell boundaries are found either way
+#[derive(Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<u32>>,
+    // Where each id was last indexed
+    entries: HashMap<u32, (IVec3, Vec3)>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(SPATIAL_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+
+    // Add an id or move it to a new position
+    pub fn insert(&mut self, id: u32, position: Vec3) {
+        let cell = self.cell(position);
+        if let Some(entry) = self.entries.get_mut(&id) {
+            let old_cell = entry.0;
+            *entry = (cell, position);
+            if old_cell == cell {
+                return;
+            }
+            self.detach(id, old_cell);
+        } else {
+            self.entries.insert(id, (cell, position));
+        }
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        if let Some((cell, _)) = self.entries.remove(&id) {
+            self.detach(id, cell);
+        }
+    }
+
+    fn detach(&mut self, id: u32, cell: IVec3) {
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Whether a query from min to max is cheaper as a scan of every entry
+    fn scan_all(&self, min: Vec3, max: Vec3) -> bool {
+        let span = (self.cell(max).as_i64vec3() - self.cell(min).as_i64vec3() + I64Vec3::ONE)
+            .max(I64Vec3::ZERO);
+        let cell_count = span.x.saturating_mul(span.y).saturating_mul(span.z);
+        cell_count as usize > MAX_QUERY_CELLS.min(self.cells.len())
+    }
+
+    // Ids whose position passes the check, among those in cells from min to max
+    fn query(&self, min: Vec3, max: Vec3, hit: impl Fn(Vec3) -> bool) -> Vec<u32> {
+        let (min_cell, max_cell) = (self.cell(min), self.cell(max));
+        let mut ids: Vec<u32> = if self.scan_all(min, max) {
+            self.entries
+                .iter()
+                .filter(|(_, (_, position))| hit(*position))
+                .map(|(&id, _)| id)
+                .collect()
+        } else {
+            let mut ids = Vec::new();
+            for x in min_cell.x..=max_cell.x {
+                for y in min_cell.y..=max_cell.y {
+                    for z in min_cell.z..=max_cell.z {
+                        let Some(cell) = self.cells.get(&IVec3::new(x, y, z)) else {
+                            continue;
+                        };
+                        ids.extend(
+                            cell.iter()
+                                .copied()
+                                .filter(|id| self.entries.get(id).is_some_and(|e| hit(e.1))),
+                        );
+                    }
+                }
+            }
+            ids
+        };
+        ids.sort_unstable();
+        ids
+    }
+
+    // Ids within radius of center, in id order
+    pub fn within_radius(&self, center: Vec3, radius: f32) -> Vec<u32> {
+        if radius < 0.0 {
+            return Vec::new();
+        }
+        let extent = Vec3::splat(radius);
+        self.query(center - extent, center + extent, |position| {
+            position.distance_squared(center) <= radius * radius
+        })
+    }
+
+    // Closest accepted id to point, ties going to the lowest id. Searches a
+    // growing radius until something turns up or the whole grid is covered
+    pub fn nearest(&self, point: Vec3, accept: impl Fn(u32) -> bool) -> Option<u32> {
+        let closest = |ids: &mut dyn Iterator<Item = u32>| {
+            ids.filter(|&id| accept(id))
+                .filter_map(|id| Some((self.entries.get(&id)?.1.distance_squared(point), id)))
+                .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+                .map(|(_, id)| id)
+        };
+
+        let mut radius = self.cell_size;
+        while radius.is_finite() {
+            let extent = Vec3::splat(radius);
+            if self.scan_all(point - extent, point + extent) {
+                break;
+            }
+            // Anything found within radius beats everything outside it
+            if let Some(id) = closest(&mut self.within_radius(point, radius).into_iter()) {
+                return Some(id);
+            }
+            radius *= 2.0;
+        }
+        closest(&mut self.entries.keys().copied())
+    }
+
+    // Ids inside the box, edges included, in id order
+    pub fn in_aabb(&self, min: Vec3, max: Vec3) -> Vec<u32> {
+        let (min, max) = (min.min(max), min.max(max));
+        self.query(min, max, |position| {
+            position.cmpge(min).all() && position.cmple(max).all()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // Coordinates mixing arbitrary values with exact cell edges on both sides
+    // of zero, where floor puts -2.0 and -1.9 in different cells
+    fn coordinate(rng: &mut StdRng) -> f32 {
+        if rng.gen_bool(0.5) {
+            rng.gen_range(-4..=4) as f32 * SPATIAL_CELL_SIZE
+        } else {
+            rng.gen_range(-9.0..9.0)
+        }
+    }
+
+    fn point(rng: &mut StdRng) -> Vec3 {
+        Vec3::new(coordinate(rng), coordinate(rng), coordinate(rng))
+    }
+
+    fn brute_radius(points: &HashMap<u32, Vec3>, center: Vec3, radius: f32) -> Vec<u32> {
+        let mut ids: Vec<u32> = points
+            .iter()
+            .filter(|(_, position)| position.distance_squared(center) <= radius * radius)
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn brute_aabb(points: &HashMap<u32, Vec3>, min: Vec3, max: Vec3) -> Vec<u32> {
+        let (min, max) = (min.min(max), min.max(max));
+        let mut ids: Vec<u32> = points
+            .iter()
+            .filter(|(_, position)| position.cmpge(min).all() && position.cmple(max).all())
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    // Random inserts, moves and removals, checking both queries against a
+    // scan of the same points after every step
+    #[test]
+    fn queries_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(58);
+        let mut grid = SpatialGrid::default();
+        let mut points = HashMap::new();
+        for step in 0..2000 {
+            let id = rng.gen_range(0..200);
+            if rng.gen_bool(0.25) {
+                grid.remove(id);
+                points.remove(&id);
+            } else {
+                let position = point(&mut rng);
+                grid.insert(id, position);
+                points.insert(id, position);
+            }
+            assert_eq!(grid.len(), points.len());
+
+            let center = point(&mut rng);
+            let radius = rng.gen_range(0..4) as f32 * SPATIAL_CELL_SIZE / 2.0;
+            assert_eq!(
+                grid.within_radius(center, radius),
+                brute_radius(&points, center, radius),
+                "radius {} around {} at step {}",
+                radius,
+                center,
+                step
+            );
+
+            let (a, b) = (point(&mut rng), point(&mut rng));
+            assert_eq!(
+                grid.in_aabb(a, b),
+                brute_aabb(&points, a, b),
+                "box {} to {} at step {}",
+                a,
+                b,
+                step
+            );
+        }
+    }
+
+    #[test]
+    fn boundary_points_are_found_from_either_side() {
+        let mut grid = SpatialGrid::default();
+        let edge = -SPATIAL_CELL_SIZE;
+        grid.insert(1, Vec3::splat(edge));
+        grid.insert(2, Vec3::splat(edge - 0.001));
+        grid.insert(3, Vec3::ZERO);
+
+        assert_eq!(grid.in_aabb(Vec3::splat(edge), Vec3::ZERO), vec![1, 3]);
+        assert_eq!(
+            grid.in_aabb(Vec3::splat(-10.0), Vec3::splat(edge)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            grid.within_radius(Vec3::splat(edge - 0.5), 0.0),
+            Vec::<u32>::new()
+        );
+        assert_eq!(
+            grid.within_radius(Vec3::new(edge, edge, 0.5 + edge), 0.5),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn removed_and_moved_ids_leave_their_old_cell() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(1, Vec3::new(-0.5, 0.0, 0.0));
+        grid.insert(2, Vec3::new(-0.5, 0.0, 0.0));
+        grid.insert(1, Vec3::new(7.0, 0.0, 0.0));
+        grid.remove(2);
+        grid.remove(2);
+
+        assert_eq!(
+            grid.within_radius(Vec3::new(-0.5, 0.0, 0.0), 1.0),
+            Vec::<u32>::new()
+        );
+        assert_eq!(grid.within_radius(Vec3::new(7.0, 0.0, 0.0), 0.0), vec![1]);
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.cells.len(), 1);
+    }
+
+    // Wide queries take the scan path, they still have to agree
+    #[test]
+    fn queries_larger_than_the_grid_scan_every_entry() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(4, Vec3::new(-500.0, 3.0, 0.0));
+        grid.insert(2, Vec3::new(500.0, -3.0, 0.0));
+        assert_eq!(grid.within_radius(Vec3::ZERO, 1000.0), vec![2, 4]);
+        assert_eq!(
+            grid.in_aabb(Vec3::splat(1000.0), Vec3::splat(-1000.0)),
+            vec![2, 4]
+        );
+    }
+}
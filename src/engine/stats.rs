@@ -0,0 +1,249 @@
+use crate::engine::core::{EditorSet, UiSet};
+use crate::engine::input::{InputAction, InputBindings};
+use crate::engine::layout::EditorLayout;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectManager, ObjectStats, PlacedObjects, ShapeParams,
+    ShapeType, SpawnEntityEvent, SpawnPhysics, SpawnSource, random_spawn_position,
+};
+use crate::engine::world::WorldSettings;
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+use std::collections::HashMap;
+
+// Values shown by the stats overlay, refreshed a few times per second
+#[derive(Default, Clone)]
+pub struct StatsSnapshot {
+    pub fps: f64,
+    pub frame_time_ms: f64,
+    pub objects: ObjectStats,
+    pub now: f64,
+    pub active_bodies: usize,
+    pub sleeping_bodies: usize,
+    pub gravity: Vec3,
+}
+
+// Resource backing the stats overlay
+#[derive(Resource)]
+pub struct StatsOverlay {
+    pub refresh: Timer,
+    pub snapshot: StatsSnapshot,
+}
+
+impl Default for StatsOverlay {
+    fn default() -> Self {
+        Self {
+            refresh: Timer::from_seconds(0.5, TimerMode::Repeating),
+            snapshot: StatsSnapshot::default(),
+        }
+    }
+}
+
+// F3 toggles the overlay, the open state is saved with the editor layout
+pub fn toggle_stats_overlay_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    if bindings.just_pressed(InputAction::ToggleStats, &keyboard_input) {
+        layout.toggle("Stats");
+    }
+}
+
+// Rebuild the snapshot on a timer so numbers stay readable
+pub fn update_stats_system(
+    time: Res<Time>,
+    mut overlay: ResMut<StatsOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    objects: PlacedObjects,
+    rapier_context: ReadRapierContext,
+    world: Res<WorldSettings>,
+) {
+    if !overlay.refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut snapshot = StatsSnapshot {
+        fps: diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.smoothed())
+            .unwrap_or_default(),
+        frame_time_ms: diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+            .and_then(|frame_time| frame_time.smoothed())
+            .unwrap_or_default(),
+        objects: objects.stats(time.elapsed_secs_f64()),
+        now: time.elapsed_secs_f64(),
+        gravity: world.gravity,
+        ..default()
+    };
+
+    if let Ok(context) = rapier_context.single() {
+        for (_, body) in context.rigidbody_set.bodies.iter() {
+            if !body.is_dynamic() {
+                continue;
+            }
+            if body.is_sleeping() {
+                snapshot.sleeping_bodies += 1;
+            } else {
+                snapshot.active_bodies += 1;
+            }
+        }
+    }
+
+    overlay.snapshot = snapshot;
+}
+
+pub fn stats_overlay_ui(
+    mut contexts: EguiContexts,
+    overlay: Res<StatsOverlay>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let stats = &overlay.snapshot;
+    layout.show(
+        ctx,
+        "Stats",
+        egui::Window::new("Stats")
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+            .resizable(false)
+            .collapsible(false)
+            .title_bar(false),
+        |ui| {
+            ui.label(format!(
+                "FPS: {:.0} ({:.2} ms)",
+                stats.fps, stats.frame_time_ms
+            ));
+            let objects = &stats.objects;
+            ui.label(format!("Objects: {}", objects.total));
+            for shape in ShapeType::all() {
+                let count = objects.counts_by_type.get(&shape).copied().unwrap_or(0);
+                ui.label(format!("  {}: {}", shape.display_name(), count));
+            }
+            for source in SpawnSource::all() {
+                if let Some(count) = objects.counts_by_source.get(&source) {
+                    ui.label(format!("  {} {}: {}", source.icon(), source.label(), count));
+                }
+            }
+            ui.label(format!("Spawn rate: {:.1}/min", objects.spawn_rate * 60.0));
+            if let (Some(oldest), Some(newest)) =
+                (objects.oldest_created_at, objects.newest_created_at)
+            {
+                ui.label(format!(
+                    "Ages: {:.0}s to {:.0}s",
+                    (stats.now - newest).max(0.0),
+                    (stats.now - oldest).max(0.0)
+                ));
+            }
+            if let Some(bounds) = objects.bounds {
+                let size = Vec3::from(bounds.max - bounds.min);
+                ui.label(format!(
+                    "Extent: {:.1} x {:.1} x {:.1}",
+                    size.x, size.y, size.z
+                ));
+            }
+            ui.label(format!(
+                "Bodies: {} active, {} sleeping",
+                stats.active_bodies, stats.sleeping_bodies
+            ));
+            ui.label(format!(
+                "Gravity: {:.2}, {:.2}, {:.2} ({:.2} m/s²)",
+                stats.gravity.x,
+                stats.gravity.y,
+                stats.gravity.z,
+                stats.gravity.length()
+            ));
+            ui.small("F3 to hide");
+        },
+    );
+}
+
+// Live per-shape counts with quick spawn and remove buttons. Unlike the overlay
+// this reads the manager directly, and since the egui pass runs after
+// cleanup_destroyed_entities_system removals show up the same frame
+pub fn shape_counts_panel_system(
+    mut contexts: EguiContexts,
+    game_manager: Res<GameObjectManager>,
+    shape_params: Res<ShapeParams>,
+    spawn_physics: Res<SpawnPhysics>,
+    mut spawn_events: EventWriter<SpawnEntityEvent>,
+    mut despawn_events: EventWriter<DespawnEntityEvent>,
+    mut layout: ResMut<EditorLayout>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let counts = game_manager.counts_by_type();
+    layout.show(
+        ctx,
+        "Shape Counts",
+        egui::Window::new("Shape Counts").default_width(200.0),
+        |ui| {
+            egui::Grid::new("shape_counts_grid")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for shape in ShapeType::all() {
+                        let count = counts.get(&shape).copied().unwrap_or(0);
+                        ui.label(shape.display_name());
+                        ui.label(count.to_string());
+                        ui.horizontal(|ui| {
+                            if ui
+                                .small_button("+")
+                                .on_hover_text(format!("Spawn a {}", shape.display_name()))
+                                .clicked()
+                            {
+                                spawn_events.write(SpawnEntityEvent {
+                                    source: SpawnSource::ToolbarButton,
+                                    position: random_spawn_position(),
+                                    shape_type: shape,
+                                    custom_name: None,
+                                    params: Some(shape_params.get(shape)),
+                                    tags: Vec::new(),
+                                    metadata: HashMap::new(),
+                                    lifetime_secs: None,
+                                    custom_mesh: None,
+                                    physics: Some(spawn_physics.props),
+                                    body_type: None,
+                                });
+                            }
+
+                            let latest = game_manager.latest_of_type(shape);
+                            let remove = ui
+                                .add_enabled(latest.is_some(), egui::Button::new("x").small())
+                                .on_hover_text("Remove the newest one");
+                            if remove.clicked()
+                                && let Some(obj) = latest
+                            {
+                                despawn_events.write(DespawnEntityEvent {
+                                    target: DespawnTarget::Id(obj.id),
+                                });
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+            ui.label(format!("Total: {}", game_manager.len()));
+        },
+    );
+}
+
+// The stats overlay and the Shape Counts panel
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatsOverlay>()
+            .add_systems(
+                EguiContextPass,
+                (stats_overlay_ui, shape_counts_panel_system).in_set(UiSet::Panels),
+            )
+            .add_systems(Update, update_stats_system.in_set(EditorSet::Bookkeeping));
+    }
+}
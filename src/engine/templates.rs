@@ -0,0 +1,506 @@
+use crate::engine::autosave::UnsavedChanges;
+use crate::engine::core::UiSet;
+use crate::engine::impulse::push_direction;
+use crate::engine::joints::{JointKind, ObjectJoint, SpringParams, limits_from_degrees};
+use crate::engine::objects::{BodyKind, PhysicsProps, ShapeDimensions, ShapeType};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::scene::{
+    RestoreSceneEvent, SCENE_FORMAT_VERSION, SceneFile, SceneImport, SceneLoadMode, SceneObject,
+    SceneShape,
+};
+use crate::engine::thumbnails::RecentScenePicker;
+use crate::engine::toolbar::MainCamera;
+use crate::engine::world::WorldSettings;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Template the editor starts with when no scene is opened
+pub const DEFAULT_TEMPLATE: &str = "Demo";
+pub const DOOR_TEMPLATE: &str = "Door";
+
+// Starting point for a new scene. The scene is built on demand, a bundled
+// file can be returned from `build` by parsing it with SceneFormat::parse
+pub struct SceneTemplate {
+    pub name: String,
+    pub description: String,
+    // Where the camera is put when the template is created
+    pub camera: Transform,
+    pub build: fn() -> SceneFile,
+}
+
+// Templates offered by the New scene dialog, in registration order. Plugins
+// add their own with `register`
+#[derive(Resource)]
+pub struct SceneTemplates {
+    templates: Vec<SceneTemplate>,
+}
+
+impl Default for SceneTemplates {
+    fn default() -> Self {
+        let mut templates = Self {
+            templates: Vec::new(),
+        };
+        templates.register(SceneTemplate {
+            name: "Empty".to_string(),
+            description: "Nothing at all, not even the ground".to_string(),
+            camera: default_camera(),
+            build: empty_scene,
+        });
+        templates.register(SceneTemplate {
+            name: DEFAULT_TEMPLATE.to_string(),
+            description: "The ground and a bouncing ball".to_string(),
+            camera: default_camera(),
+            build: demo_scene,
+        });
+        templates.register(SceneTemplate {
+            name: "Stress test".to_string(),
+            description: format!("{} cubes dropped in a block", STRESS_CUBES_PER_SIDE.pow(3)),
+            camera: Transform::from_xyz(-16.0, 14.0, 24.0)
+                .looking_at(Vec3::new(0.0, 2.0, 0.0), Vec3::Y),
+            build: stress_test_scene,
+        });
+        templates.register(SceneTemplate {
+            name: "Dominoes".to_string(),
+            description: "A row of dominoes, the first one already tipping".to_string(),
+            camera: Transform::from_xyz(0.0, 3.0, 9.0)
+                .looking_at(Vec3::new(0.0, -1.5, 0.0), Vec3::Y),
+            build: dominoes_scene,
+        });
+        templates.register(SceneTemplate {
+            name: DOOR_TEMPLATE.to_string(),
+            description: "A panel hinged to a fixed post, push it to swing it".to_string(),
+            camera: Transform::from_xyz(-2.0, 1.5, 5.0)
+                .looking_at(Vec3::new(0.5, -0.7, 0.0), Vec3::Y),
+            build: door_scene,
+        });
+        templates
+    }
+}
+
+impl SceneTemplates {
+    // A template with the same name is replaced in place
+    pub fn register(&mut self, template: SceneTemplate) {
+        match self
+            .templates
+            .iter_mut()
+            .find(|existing| existing.name == template.name)
+        {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SceneTemplate> {
+        self.templates.iter().find(|template| template.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SceneTemplate> {
+        self.templates.iter()
+    }
+}
+
+// Where the camera starts, looking at the origin from above and behind
+pub fn default_camera() -> Transform {
+    Transform::from_xyz(-3.0, 3.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y)
+}
+
+// Height of the ground's top face, templates stand their objects on it
+const GROUND_TOP: f32 = -1.9;
+const STRESS_CUBES_PER_SIDE: u32 = 10;
+const DOMINO_COUNT: u32 = 24;
+const BRIDGE_PLANKS: u32 = 10;
+// How far in front of the camera presets are added
+const PRESET_SPAWN_DISTANCE: f32 = 4.0;
+
+fn template_object(id: u32, name: String, shape_type: ShapeType, translation: Vec3) -> SceneObject {
+    SceneObject {
+        id,
+        name,
+        shape_type: SceneShape::Known(shape_type),
+        dimensions: ShapeDimensions::default(),
+        translation,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+        color: Color::WHITE,
+        physics: PhysicsProps::default(),
+        tags: BTreeSet::new(),
+        parent: None,
+        locked: false,
+        physics_locked: false,
+        hidden: false,
+        collider_disabled: false,
+        prefab: None,
+        custom_mesh: None,
+        metadata: BTreeMap::new(),
+        body_state: None,
+    }
+}
+
+fn template_scene(world: WorldSettings, objects: Vec<SceneObject>) -> SceneFile {
+    SceneFile {
+        version: SCENE_FORMAT_VERSION,
+        world,
+        objects,
+        joints: Vec::new(),
+    }
+}
+
+fn empty_scene() -> SceneFile {
+    template_scene(
+        WorldSettings {
+            ground: false,
+            ..default()
+        },
+        Vec::new(),
+    )
+}
+
+fn demo_scene() -> SceneFile {
+    let mut ball = template_object(
+        1,
+        "Ball".to_string(),
+        ShapeType::Ball,
+        Vec3::new(0.0, 4.0, 0.0),
+    );
+    ball.color = Color::srgb(0.8, 0.7, 0.6);
+    template_scene(WorldSettings::default(), vec![ball])
+}
+
+fn stress_test_scene() -> SceneFile {
+    let side = STRESS_CUBES_PER_SIDE;
+    let spacing = 1.2;
+    let offset = (side - 1) as f32 * spacing / 2.0;
+    let mut objects = Vec::new();
+    for y in 0..side {
+        for z in 0..side {
+            for x in 0..side {
+                let id = objects.len() as u32 + 1;
+                let translation = Vec3::new(
+                    x as f32 * spacing - offset,
+                    GROUND_TOP + 0.5 + y as f32 * spacing,
+                    z as f32 * spacing - offset,
+                );
+                let mut cube =
+                    template_object(id, format!("Cube {}", id), ShapeType::Cube, translation);
+                cube.color = Color::hsl(id as f32 * 360.0 / (side * side) as f32, 0.6, 0.55);
+                objects.push(cube);
+            }
+        }
+    }
+    template_scene(WorldSettings::default(), objects)
+}
+
+fn dominoes_scene() -> SceneFile {
+    let half_extents = Vec3::new(0.08, 0.5, 0.25);
+    let spacing = 0.6;
+    let start = (DOMINO_COUNT - 1) as f32 * spacing / -2.0;
+    let objects = (0..DOMINO_COUNT)
+        .map(|index| {
+            let translation = Vec3::new(
+                start + index as f32 * spacing,
+                GROUND_TOP + half_extents.y,
+                0.0,
+            );
+            let mut domino = template_object(
+                index + 1,
+                format!("Domino {}", index + 1),
+                ShapeType::Cube,
+                translation,
+            );
+            domino.dimensions.half_extents = half_extents;
+            domino.color = if index % 2 == 0 {
+                Color::srgb(0.9, 0.9, 0.85)
+            } else {
+                Color::srgb(0.15, 0.15, 0.2)
+            };
+            domino.physics.restitution = 0.05;
+            domino.physics.friction = 0.6;
+            // Leaning into the next one so the chain starts on its own
+            if index == 0 {
+                domino.rotation = Quat::from_rotation_z(-0.35);
+                domino.translation.y += 0.02;
+            }
+            domino
+        })
+        .collect();
+    template_scene(WorldSettings::default(), objects)
+}
+
+fn door_scene() -> SceneFile {
+    let post_half = Vec3::new(0.1, 1.2, 0.1);
+    let panel_half = Vec3::new(0.5, 1.1, 0.05);
+    // A small gap so the panel doesn't rub against the post or the ground
+    let gap = 0.02;
+    let post_position = Vec3::new(0.0, GROUND_TOP + post_half.y, 0.0);
+    let panel_offset = Vec3::new(post_half.x + gap + panel_half.x, 0.05, 0.0);
+
+    let mut post = template_object(1, "Door post".to_string(), ShapeType::Cube, post_position);
+    post.dimensions.half_extents = post_half;
+    post.color = Color::srgb(0.35, 0.25, 0.2);
+    post.physics.body = BodyKind::Fixed;
+    let mut panel = template_object(
+        2,
+        "Door panel".to_string(),
+        ShapeType::Cube,
+        post_position + panel_offset,
+    );
+    panel.dimensions.half_extents = panel_half;
+    panel.color = Color::srgb(0.75, 0.6, 0.4);
+    panel.physics.restitution = 0.1;
+    // Settles after a push instead of swinging back and forth
+    panel.physics.angular_damping = 1.0;
+
+    let hinge = JointKind::Revolute {
+        pivot: Vec3::new(post_half.x + gap / 2.0, 0.0, 0.0),
+        axis: Vec3::Y,
+        limits: Some(limits_from_degrees([-90.0, 90.0])),
+    };
+    let mut scene = template_scene(WorldSettings::default(), Vec::new());
+    scene.joints.push(template_joint(hinge, &post, &panel));
+    scene.objects = vec![post, panel];
+    scene
+}
+
+fn rope_bridge_scene() -> SceneFile {
+    let spacing = 0.4;
+    let deck = GROUND_TOP + 2.0;
+    let end = (BRIDGE_PLANKS + 1) as f32 * spacing / 2.0;
+
+    let mut objects: Vec<SceneObject> = [("Bridge anchor L", -end), ("Bridge anchor R", end)]
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, x))| {
+            let mut anchor = template_object(
+                index as u32 + 1,
+                name.to_string(),
+                ShapeType::Cube,
+                Vec3::new(x, deck, 0.0),
+            );
+            anchor.dimensions.half_extents = Vec3::new(0.15, 0.15, 0.7);
+            anchor.color = Color::srgb(0.35, 0.25, 0.2);
+            anchor.physics.body = BodyKind::Fixed;
+            anchor
+        })
+        .collect();
+    for index in 0..BRIDGE_PLANKS {
+        let mut plank = template_object(
+            index + 3,
+            format!("Plank {}", index + 1),
+            ShapeType::Capsule,
+            Vec3::new(-end + (index + 1) as f32 * spacing, deck, 0.0),
+        );
+        plank.dimensions.radius = 0.1;
+        plank.dimensions.height = 1.2;
+        // Lying across the bridge
+        plank.rotation = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+        plank.color = Color::srgb(0.7, 0.55, 0.35);
+        plank.physics.restitution = 0.1;
+        plank.physics.linear_damping = 0.5;
+        plank.physics.angular_damping = 2.0;
+        objects.push(plank);
+    }
+
+    // Anchor, planks in order, then the other anchor. A little shorter than
+    // the spacing at rest so the bridge is taut
+    let spring = JointKind::Spring(SpringParams {
+        rest_length: spacing * 0.95,
+        stiffness: 400.0,
+        damping: 5.0,
+    });
+    let chain: Vec<&SceneObject> = std::iter::once(&objects[0])
+        .chain(&objects[2..])
+        .chain(std::iter::once(&objects[1]))
+        .collect();
+    let joints = chain
+        .windows(2)
+        .map(|link| template_joint(spring, link[0], link[1]))
+        .collect();
+    SceneFile {
+        joints,
+        ..template_scene(WorldSettings::default(), objects)
+    }
+}
+
+// Joint holding `child` where it's placed relative to `parent`
+fn template_joint(kind: JointKind, parent: &SceneObject, child: &SceneObject) -> ObjectJoint {
+    let inverse = parent.rotation.inverse();
+    ObjectJoint {
+        parent: parent.id,
+        child: child.id,
+        kind,
+        anchor: inverse * (child.translation - parent.translation),
+        rotation: inverse * child.rotation,
+    }
+}
+
+// A preset added to the current scene in front of the camera, at the ground's
+// height rather than the camera's
+fn preset_event(camera: &Transform, name: &str, scene: SceneFile) -> RestoreSceneEvent {
+    let forward = push_direction(camera);
+    let ahead = camera.translation + forward * PRESET_SPAWN_DISTANCE;
+    RestoreSceneEvent {
+        source: format!("the {} preset", name.to_lowercase()),
+        scene,
+        mode: SceneLoadMode::Additive(SceneImport {
+            offset: Vec3::new(ahead.x, 0.0, ahead.z),
+            group: Some(name.to_string()),
+        }),
+    }
+}
+
+pub fn spawn_door_event(camera: &Transform) -> RestoreSceneEvent {
+    preset_event(camera, DOOR_TEMPLATE, door_scene())
+}
+
+pub fn spawn_rope_bridge_event(camera: &Transform) -> RestoreSceneEvent {
+    preset_event(camera, "Rope bridge", rope_bridge_scene())
+}
+
+// Replace the scene with a template's. The result is untitled, Ctrl+S asks
+// where to save it
+#[derive(Event, Debug, Clone)]
+pub struct NewSceneEvent {
+    pub template: String,
+}
+
+// Put the camera where the template wants it
+pub fn place_template_camera_system(
+    mut new_scene_events: EventReader<NewSceneEvent>,
+    templates: Res<SceneTemplates>,
+    mut cameras: Query<&mut Transform, With<MainCamera>>,
+) {
+    for event in new_scene_events.read() {
+        let Some(template) = templates.get(&event.template) else {
+            continue;
+        };
+        for mut camera in &mut cameras {
+            *camera = template.camera;
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct NewSceneDialog {
+    pub open: bool,
+    pub template: String,
+}
+
+impl NewSceneDialog {
+    pub fn open_dialog(&mut self) {
+        if self.template.is_empty() {
+            self.template = DEFAULT_TEMPLATE.to_string();
+        }
+        self.open = true;
+    }
+}
+
+pub fn new_scene_dialog_system(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<NewSceneDialog>,
+    templates: Res<SceneTemplates>,
+    unsaved: UnsavedChanges,
+    mut new_scene_events: EventWriter<NewSceneEvent>,
+    mut recent: RecentScenePicker,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("New scene")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            for template in templates.iter() {
+                ui.radio_value(&mut dialog.template, template.name.clone(), &template.name);
+                ui.indent(&template.name, |ui| ui.weak(&template.description));
+            }
+            if !recent.is_empty() {
+                ui.separator();
+                ui.label("Or open a recent scene");
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        if recent.ui(ui, 96.0) {
+                            dialog.open = false;
+                        }
+                    });
+            }
+            let dirty = unsaved.is_dirty();
+            if dirty {
+                ui.separator();
+                let name = unsaved
+                    .scene()
+                    .map_or("the untitled scene".to_string(), |path| {
+                        path.display().to_string()
+                    });
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Unsaved changes to {} will be lost", name),
+                );
+            }
+            ui.horizontal(|ui| {
+                let label = if dirty {
+                    "Discard changes and create"
+                } else {
+                    "Create"
+                };
+                let ready = templates.get(&dialog.template).is_some();
+                if ui.add_enabled(ready, egui::Button::new(label)).clicked() {
+                    new_scene_events.write(NewSceneEvent {
+                        template: dialog.template.clone(),
+                    });
+                    dialog.open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    dialog.open = false;
+                }
+            });
+        });
+}
+
+pub fn register_template_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("New scene...", None, |world| {
+        world.resource_mut::<NewSceneDialog>().open_dialog();
+    });
+    registry.register("Spawn door", None, |world| {
+        let Ok(camera) = world
+            .query_filtered::<&Transform, With<MainCamera>>()
+            .single(world)
+            .copied()
+        else {
+            return;
+        };
+        world.send_event(spawn_door_event(&camera));
+    });
+    registry.register("Spawn rope bridge", None, |world| {
+        let Ok(camera) = world
+            .query_filtered::<&Transform, With<MainCamera>>()
+            .single(world)
+            .copied()
+        else {
+            return;
+        };
+        world.send_event(spawn_rope_bridge_event(&camera));
+    });
+}
+
+// Scene templates and the New scene dialog
+pub struct TemplatesPlugin;
+
+impl Plugin for TemplatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NewSceneEvent>()
+            .init_resource::<SceneTemplates>()
+            .init_resource::<NewSceneDialog>()
+            .add_systems(Startup, register_template_commands)
+            .add_systems(
+                EguiContextPass,
+                new_scene_dialog_system.in_set(UiSet::Popups),
+            );
+    }
+}
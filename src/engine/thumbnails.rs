@@ -0,0 +1,197 @@
+use crate::engine::core::EditorSet;
+use crate::engine::scene::{LoadSceneEvent, SceneFileDialog, SceneLoadMode, save_scene_system};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Size thumbnails are written at, the capture is cropped to this aspect
+pub const THUMBNAIL_SIZE: UVec2 = UVec2::new(256, 144);
+// Scenes kept in Open recent
+pub const MAX_RECENT_SCENES: usize = 8;
+
+// Thumbnail written next to a scene on every save, scene.ron gets
+// scene.ron.thumb.png so scenes of different formats don't share one
+pub fn thumbnail_path(scene: &Path) -> PathBuf {
+    let mut name = scene.file_name().unwrap_or_default().to_os_string();
+    name.push(".thumb.png");
+    scene.with_file_name(name)
+}
+
+// Scenes most recently opened or saved, newest first. Saved with the editor
+// prefs
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RecentScenes {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentScenes {
+    pub fn push(&mut self, path: PathBuf) {
+        self.paths.retain(|existing| *existing != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT_SCENES);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PathBuf> {
+        self.paths.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+// The recent scenes menu, picking one loads it in place of the current scene
+#[derive(SystemParam)]
+pub struct RecentScenePicker<'w> {
+    recent: Res<'w, RecentScenes>,
+    thumbnails: ResMut<'w, SceneThumbnails>,
+    load_events: EventWriter<'w, LoadSceneEvent>,
+}
+
+impl RecentScenePicker<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty()
+    }
+
+    // Lists the recent scenes, true once one was picked
+    pub fn ui(&mut self, ui: &mut egui::Ui, thumbnail_width: f32) -> bool {
+        let Some(path) = recent_scenes_ui(ui, &self.recent, &mut self.thumbnails, thumbnail_width)
+        else {
+            return false;
+        };
+        self.load_events.write(LoadSceneEvent {
+            path,
+            mode: SceneLoadMode::Replace,
+            save_to: None,
+        });
+        true
+    }
+}
+
+// Whatever the scene was last loaded from or saved to goes to the top
+pub fn track_recent_scenes_system(dialog: Res<SceneFileDialog>, mut recent: ResMut<RecentScenes>) {
+    let Some(current) = &dialog.current else {
+        return;
+    };
+    if recent.paths.first() != Some(current) {
+        recent.push(current.clone());
+    }
+}
+
+struct CachedThumbnail {
+    // Modification time of the file the texture was read from
+    modified: Option<SystemTime>,
+    texture: Option<egui::TextureHandle>,
+}
+
+// Thumbnail textures for the recent scene lists, read again when the file on
+// disk changes
+#[derive(Resource, Default)]
+pub struct SceneThumbnails {
+    cache: HashMap<PathBuf, CachedThumbnail>,
+}
+
+impl SceneThumbnails {
+    pub fn texture(&mut self, ctx: &egui::Context, scene: &Path) -> Option<egui::TextureHandle> {
+        let path = thumbnail_path(scene);
+        let modified = path.metadata().and_then(|meta| meta.modified()).ok();
+        if let Some(cached) = self.cache.get(&path)
+            && cached.modified == modified
+        {
+            return cached.texture.clone();
+        }
+
+        let texture = modified.and_then(|_| match image::open(&path) {
+            Ok(image) => {
+                let image = image.to_rgba8();
+                let size = [image.width() as usize, image.height() as usize];
+                let color = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                Some(ctx.load_texture(
+                    path.display().to_string(),
+                    color,
+                    egui::TextureOptions::LINEAR,
+                ))
+            }
+            Err(err) => {
+                warn!("Cannot read thumbnail {}: {}", path.display(), err);
+                None
+            }
+        });
+        self.cache.insert(
+            path,
+            CachedThumbnail {
+                modified,
+                texture: texture.clone(),
+            },
+        );
+        texture
+    }
+}
+
+// Recent scenes with their thumbnails, returns the one clicked. Scenes that
+// no longer exist are listed but can't be picked
+pub fn recent_scenes_ui(
+    ui: &mut egui::Ui,
+    recent: &RecentScenes,
+    thumbnails: &mut SceneThumbnails,
+    thumbnail_width: f32,
+) -> Option<PathBuf> {
+    let size = egui::vec2(
+        thumbnail_width,
+        thumbnail_width * THUMBNAIL_SIZE.y as f32 / THUMBNAIL_SIZE.x as f32,
+    );
+    let mut picked = None;
+    for path in recent.iter() {
+        let exists = path.exists();
+        ui.horizontal(|ui| {
+            match thumbnails.texture(ui.ctx(), path) {
+                Some(texture) => {
+                    ui.add(egui::Image::new(egui::load::SizedTexture::new(
+                        texture.id(),
+                        size,
+                    )));
+                }
+                None => {
+                    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                    ui.painter()
+                        .rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+                }
+            }
+            ui.vertical(|ui| {
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                if ui.add_enabled(exists, egui::Button::new(name)).clicked() {
+                    picked = Some(path.clone());
+                }
+                if let Some(dir) = path.parent() {
+                    ui.weak(dir.display().to_string());
+                }
+                if !exists {
+                    ui.weak("File not found");
+                }
+            });
+        });
+    }
+    picked
+}
+
+// Thumbnails shown next to the recent scenes
+pub struct ThumbnailsPlugin;
+
+impl Plugin for ThumbnailsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SceneThumbnails>().add_systems(
+            Update,
+            track_recent_scenes_system
+                .after(save_scene_system)
+                .in_set(EditorSet::Bookkeeping),
+        );
+    }
+}
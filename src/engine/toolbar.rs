@@ -0,0 +1,422 @@
+use crate::engine::bounds::BoundsOverlay;
+use crate::engine::core::UiSet;
+use crate::engine::csv_export::CsvExport;
+use crate::engine::custom_mesh::{CustomMeshLibrary, MeshImportDialog, custom_mesh_spawn};
+use crate::engine::dynamic_scene::DynamicSceneExport;
+use crate::engine::editor::ClearSceneDialog;
+use crate::engine::export::GltfExport;
+use crate::engine::gizmo::{GizmoMode, TransformGizmo};
+use crate::engine::history::{EditorHistory, HistoryEvent};
+use crate::engine::input::{InputBindings, key_name};
+use crate::engine::labels::ViewportLabels;
+use crate::engine::layout::{EditorLayout, PanelContext};
+use crate::engine::objects::{
+    BodyKind, ShapeParams, ShapeType, SpawnEntityEvent, SpawnPhysics, SpawnSource,
+};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::prefs::EditorPrefs;
+use crate::engine::scene::SceneFileDialog;
+use crate::engine::simulation::{SimulationEvent, SimulationState};
+use crate::engine::templates::NewSceneDialog;
+use crate::engine::thumbnails::RecentScenePicker;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::*;
+use std::collections::HashMap;
+
+// Titles of the windows listed in the View menu
+const EDITOR_PANELS: [&str; 24] = [
+    "Hierarchy",
+    "Inspector",
+    "Components",
+    "Arrange",
+    "Groups",
+    "Prefabs",
+    "Trash",
+    "Spawn",
+    "Snapping",
+    "World",
+    "Camera",
+    "Bookmarks",
+    "Measure",
+    "Minimap",
+    "Console",
+    "Operation Log",
+    "Recorder",
+    "Playback",
+    "Keybindings",
+    "Preferences",
+    "Stats",
+    "Profiler",
+    "Shape Counts",
+    "Sanity Check",
+];
+
+// Marker for the main viewport camera
+#[derive(Component)]
+pub struct MainCamera;
+
+// How far in front of the camera toolbar spawns appear
+const SPAWN_DISTANCE: f32 = 8.0;
+const BURST_COUNT: usize = 10;
+
+// Point in front of the camera where toolbar spawns are placed
+pub fn spawn_point_in_front(camera: &Transform) -> Vec3 {
+    camera.translation + camera.forward() * SPAWN_DISTANCE
+}
+
+// What the spawn buttons spawn and where
+#[derive(SystemParam)]
+pub struct ToolbarSpawns<'w, 's> {
+    pub camera: Query<'w, 's, &'static Transform, With<MainCamera>>,
+    pub spawn_events: EventWriter<'w, SpawnEntityEvent>,
+    pub shape_params: Res<'w, ShapeParams>,
+    pub spawn_physics: ResMut<'w, SpawnPhysics>,
+    pub library: Res<'w, CustomMeshLibrary>,
+}
+
+// Dialogs opened from the Scene menu
+#[derive(SystemParam)]
+pub struct ToolbarDialogs<'w> {
+    pub clear_dialog: ResMut<'w, ClearSceneDialog>,
+    pub scene_dialog: ResMut<'w, SceneFileDialog>,
+    pub mesh_import: ResMut<'w, MeshImportDialog>,
+    pub new_scene: ResMut<'w, NewSceneDialog>,
+    pub gltf_export: ResMut<'w, GltfExport>,
+    pub csv_export: ResMut<'w, CsvExport>,
+    pub bevy_scene_export: ResMut<'w, DynamicSceneExport>,
+}
+
+// Undo, simulation and gizmo buttons
+#[derive(SystemParam)]
+pub struct ToolbarControls<'w> {
+    pub history: Res<'w, EditorHistory>,
+    pub history_events: EventWriter<'w, HistoryEvent>,
+    pub simulation: Res<'w, SimulationState>,
+    pub simulation_events: EventWriter<'w, SimulationEvent>,
+    pub gizmo: ResMut<'w, TransformGizmo>,
+    pub bindings: Res<'w, InputBindings>,
+}
+
+// Top toolbar with one spawn button per shape, Ctrl-click spawns a burst
+pub fn toolbar_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    ToolbarSpawns {
+        camera,
+        mut spawn_events,
+        shape_params,
+        mut spawn_physics,
+        library,
+    }: ToolbarSpawns,
+    ToolbarDialogs {
+        mut clear_dialog,
+        mut scene_dialog,
+        mut mesh_import,
+        mut new_scene,
+        mut gltf_export,
+        mut csv_export,
+        mut bevy_scene_export,
+    }: ToolbarDialogs,
+    ToolbarControls {
+        history,
+        mut history_events,
+        simulation,
+        mut simulation_events,
+        mut gizmo,
+        bindings,
+    }: ToolbarControls,
+    mut recent: RecentScenePicker,
+    mut labels: ResMut<ViewportLabels>,
+    mut bounds: ResMut<BoundsOverlay>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let origin = camera
+        .single()
+        .map(spawn_point_in_front)
+        .unwrap_or(Vec3::new(0.0, 4.0, 0.0));
+
+    egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.menu_button("Scene", |ui| {
+                if ui.button("New scene...").clicked() {
+                    new_scene.open_dialog();
+                    ui.close_menu();
+                }
+                if ui.button("Open scene...").clicked() {
+                    scene_dialog.open_load();
+                    ui.close_menu();
+                }
+                ui.add_enabled_ui(!recent.is_empty(), |ui| {
+                    ui.menu_button("Open recent", |ui| {
+                        if recent.ui(ui, 128.0) {
+                            ui.close_menu();
+                        }
+                    });
+                });
+                if ui.button("Import scene (additive)...").clicked() {
+                    scene_dialog.open_import();
+                    ui.close_menu();
+                }
+                if ui.button("Save scene...").clicked() {
+                    scene_dialog.open_save();
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(!gltf_export.running(), egui::Button::new("Export glTF..."))
+                    .clicked()
+                {
+                    gltf_export.open_dialog();
+                    ui.close_menu();
+                }
+                if ui.button("Export CSV...").clicked() {
+                    csv_export.open_dialog();
+                    ui.close_menu();
+                }
+                if ui.button("Export Bevy scene...").clicked() {
+                    bevy_scene_export.open_dialog();
+                    ui.close_menu();
+                }
+                if ui.button("Import mesh...").clicked() {
+                    mesh_import.open = true;
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Clear scene...").clicked() {
+                    clear_dialog.open = true;
+                    ui.close_menu();
+                }
+            });
+            view_menu(ui, &mut layout, &mut labels, &mut bounds);
+            ui.separator();
+
+            let undo = ui
+                .add_enabled(history.can_undo(), egui::Button::new("⟲ Undo"))
+                .on_hover_text(history.undo_description().unwrap_or_default());
+            if undo.clicked() {
+                history_events.write(HistoryEvent::Undo);
+            }
+            let redo = ui
+                .add_enabled(history.can_redo(), egui::Button::new("⟳ Redo"))
+                .on_hover_text(history.redo_description().unwrap_or_default());
+            if redo.clicked() {
+                history_events.write(HistoryEvent::Redo);
+            }
+            ui.separator();
+
+            simulation_controls(ui, *simulation, &mut simulation_events);
+            ui.separator();
+
+            for mode in GizmoMode::all() {
+                let label = ui
+                    .selectable_label(gizmo.mode == mode, mode.label())
+                    .on_hover_text(key_name(bindings.key(mode.action())));
+                if label.clicked() && !gizmo.is_dragging() {
+                    gizmo.mode = mode;
+                }
+            }
+            ui.separator();
+
+            ui.label("Spawn:");
+            let mut body = spawn_physics.props.body;
+            egui::ComboBox::from_id_salt("spawn_body")
+                .selected_text(body.label())
+                .width(90.0)
+                .show_ui(ui, |ui| {
+                    for kind in BodyKind::all() {
+                        ui.selectable_value(&mut body, kind, kind.label());
+                    }
+                })
+                .response
+                .on_hover_text("Body type of new objects");
+            if body != spawn_physics.props.body {
+                spawn_physics.props.body = body;
+            }
+            for shape in ShapeType::all() {
+                let response = shape_button(ui, shape).on_hover_text(format!(
+                    "Spawn a {} (Ctrl-click for 10)",
+                    shape.display_name()
+                ));
+                if !response.clicked() {
+                    continue;
+                }
+
+                let count = if ui.input(|i| i.modifiers.ctrl) {
+                    BURST_COUNT
+                } else {
+                    1
+                };
+                for i in 0..count {
+                    let offset = if i == 0 {
+                        Vec3::ZERO
+                    } else {
+                        Vec3::new(
+                            rand::random::<f32>() - 0.5,
+                            rand::random::<f32>(),
+                            rand::random::<f32>() - 0.5,
+                        ) * 2.0
+                    };
+                    spawn_events.write(SpawnEntityEvent {
+                        source: SpawnSource::ToolbarButton,
+                        position: origin + offset,
+                        shape_type: shape,
+                        custom_name: None,
+                        params: Some(shape_params.get(shape)),
+                        tags: Vec::new(),
+                        metadata: HashMap::new(),
+                        lifetime_secs: None,
+                        custom_mesh: None,
+                        physics: Some(spawn_physics.props),
+                        body_type: None,
+                    });
+                }
+            }
+            // Imported meshes follow the primitives once they've loaded
+            for name in library.ready() {
+                if ui
+                    .button(name.as_str())
+                    .on_hover_text(format!("Spawn imported mesh {}", name))
+                    .clicked()
+                {
+                    spawn_events.write(custom_mesh_spawn(name, origin));
+                }
+            }
+        });
+    });
+}
+
+// Play/pause toggle, single step while paused and the current state
+fn simulation_controls(
+    ui: &mut egui::Ui,
+    simulation: SimulationState,
+    simulation_events: &mut EventWriter<SimulationEvent>,
+) {
+    let (text, hover) = if simulation.is_paused() {
+        ("▶ Play", "Resume physics (P)")
+    } else {
+        ("⏸ Pause", "Freeze physics (P)")
+    };
+    if ui.button(text).on_hover_text(hover).clicked() {
+        simulation_events.write(SimulationEvent::TogglePause);
+    }
+    let step = ui
+        .add_enabled(simulation.is_paused(), egui::Button::new("⏭ Step"))
+        .on_hover_text("Advance one physics tick (.)");
+    if step.clicked() {
+        simulation_events.write(SimulationEvent::Step);
+    }
+
+    let color = if simulation.is_paused() {
+        egui::Color32::YELLOW
+    } else {
+        egui::Color32::LIGHT_GREEN
+    };
+    ui.colored_label(color, simulation.label());
+}
+
+// Menu for reopening closed panels and resetting the layout
+fn view_menu(
+    ui: &mut egui::Ui,
+    layout: &mut EditorLayout,
+    labels: &mut ViewportLabels,
+    bounds: &mut BoundsOverlay,
+) {
+    ui.menu_button("View", |ui| {
+        for title in EDITOR_PANELS {
+            let mut open = layout.is_open(title);
+            if ui.checkbox(&mut open, title).changed() {
+                layout.set_open(title, open);
+            }
+        }
+        ui.separator();
+        ui.checkbox(&mut labels.show_names, "Object labels");
+        ui.checkbox(&mut labels.show_tooltips, "Hover tooltips");
+        ui.checkbox(&mut bounds.show_selected, "Selection bounds");
+        ui.checkbox(&mut bounds.show_shape_type, "Bounds of spawn shape")
+            .on_hover_text("Bounding boxes of every object of the shape picked for spawning");
+        ui.separator();
+        if ui.button("Reset layout").clicked() {
+            EditorPrefs::delete();
+            layout.reset();
+            ui.close_menu();
+        }
+    });
+}
+
+// Button with a small dot in the shape's swatch colour
+fn shape_button(ui: &mut egui::Ui, shape: ShapeType) -> egui::Response {
+    let [r, g, b, _] = shape.swatch_color().to_srgba().to_u8_array();
+    let mut job = egui::text::LayoutJob::default();
+    job.append(
+        "● ",
+        0.0,
+        egui::TextFormat {
+            color: egui::Color32::from_rgb(r, g, b),
+            ..default()
+        },
+    );
+    job.append(
+        shape.display_name(),
+        0.0,
+        egui::TextFormat {
+            color: ui.visuals().text_color(),
+            ..default()
+        },
+    );
+    ui.button(job)
+}
+
+pub fn register_toolbar_commands(mut registry: ResMut<CommandRegistry>) {
+    for shape in ShapeType::all() {
+        registry.register(
+            format!("Spawn {}", shape.display_name()),
+            None,
+            move |world| {
+                let origin = world
+                    .query_filtered::<&Transform, With<MainCamera>>()
+                    .single(world)
+                    .map(spawn_point_in_front)
+                    .unwrap_or(Vec3::new(0.0, 4.0, 0.0));
+                let params = world.resource::<ShapeParams>().get(shape);
+                let physics = world.resource::<SpawnPhysics>().props;
+                world.send_event(SpawnEntityEvent {
+                    source: SpawnSource::ToolbarButton,
+                    position: origin,
+                    shape_type: shape,
+                    custom_name: None,
+                    params: Some(params),
+                    tags: Vec::new(),
+                    metadata: HashMap::new(),
+                    lifetime_secs: None,
+                    custom_mesh: None,
+                    physics: Some(physics),
+                    body_type: None,
+                });
+            },
+        );
+    }
+
+    for title in EDITOR_PANELS {
+        registry.register(format!("Toggle {} panel", title), None, move |world| {
+            world.resource_mut::<EditorLayout>().toggle(title);
+        });
+    }
+    registry.register("Reset layout", None, |world| {
+        EditorPrefs::delete();
+        world.resource_mut::<EditorLayout>().reset();
+    });
+}
+
+// The toolbar along the top of the window
+pub struct ToolbarPlugin;
+
+impl Plugin for ToolbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, register_toolbar_commands)
+            .add_systems(EguiContextPass, toolbar_system.in_set(UiSet::Toolbar));
+    }
+}
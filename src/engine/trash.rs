@@ -0,0 +1,400 @@
+use crate::engine::core::UiSet;
+use crate::engine::layout::EditorLayout;
+use crate::engine::notify::EditorNotifications;
+use crate::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectManager, ObjectSnapshot, ObjectSpawner,
+    restore_objects,
+};
+use crate::engine::palette::CommandRegistry;
+use crate::engine::selection::Selection;
+use bevy::prelude::*;
+use bevy_egui::EguiContextPass;
+use bevy_egui::{EguiContexts, egui};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Entries kept before the oldest is deleted for good
+pub const TRASH_CAPACITY: usize = 32;
+
+// A trashed object and the children that went with it, parent first
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    // Stable handle for the panel, indices shift as entries come and go
+    pub key: u64,
+    pub snapshots: Vec<ObjectSnapshot>,
+    pub trashed_at: f64,
+}
+
+impl TrashEntry {
+    pub fn name(&self) -> &str {
+        self.snapshots.first().map_or("", |snapshot| &snapshot.name)
+    }
+}
+
+// Objects moved to the trash, newest last. Full entries push out the oldest
+#[derive(Resource, Debug)]
+pub struct Trash {
+    entries: VecDeque<TrashEntry>,
+    capacity: usize,
+    next_key: u64,
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Self::with_capacity(TRASH_CAPACITY)
+    }
+}
+
+impl Trash {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_key: 0,
+        }
+    }
+
+    // Add an entry, returning the one evicted to make room
+    pub fn push(&mut self, snapshots: Vec<ObjectSnapshot>, timestamp: f64) -> Option<TrashEntry> {
+        if snapshots.is_empty() {
+            return None;
+        }
+        let evicted = if self.entries.len() >= self.capacity {
+            self.entries.pop_front()
+        } else {
+            None
+        };
+        self.entries.push_back(TrashEntry {
+            key: self.next_key,
+            snapshots,
+            trashed_at: timestamp,
+        });
+        self.next_key += 1;
+        evicted
+    }
+
+    pub fn take(&mut self, key: u64) -> Option<TrashEntry> {
+        let index = self.entries.iter().position(|entry| entry.key == key)?;
+        self.entries.remove(index)
+    }
+
+    // Drop everything, returns how many entries there were
+    pub fn empty(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        count
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TrashEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+// Give snapshots whose id has been taken since they were trashed a fresh one,
+// and point children at their parent's new id. Also used for additive scene loads
+pub fn reassign_taken_ids(
+    game_manager: &GameObjectManager,
+    snapshots: &[ObjectSnapshot],
+) -> Vec<ObjectSnapshot> {
+    let mut used: HashSet<u32> = snapshots
+        .iter()
+        .map(|snapshot| snapshot.id)
+        .filter(|&id| game_manager.get_object_by_id(id).is_none())
+        .collect();
+    let mut next = game_manager.ids().peek().unwrap_or_default();
+    let mut new_ids = HashMap::new();
+    for snapshot in snapshots {
+        if game_manager.get_object_by_id(snapshot.id).is_none() {
+            continue;
+        }
+        while game_manager.ids().is_live(next) || used.contains(&next) {
+            next = next.wrapping_add(1);
+        }
+        used.insert(next);
+        new_ids.insert(snapshot.id, next);
+    }
+
+    snapshots
+        .iter()
+        .cloned()
+        .map(|mut snapshot| {
+            if let Some(&id) = new_ids.get(&snapshot.id) {
+                snapshot.id = id;
+            }
+            if let Some(&parent) = snapshot.parent.as_ref().and_then(|id| new_ids.get(id)) {
+                snapshot.parent = Some(parent);
+            }
+            snapshot
+        })
+        .collect()
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashEvent {
+    Restore(u64),
+    DeleteForever(u64),
+    Empty,
+}
+
+// Bring entries back or drop them. Restoring keeps the original ids unless
+// something else has taken them
+pub fn apply_trash_events_system(
+    ObjectSpawner {
+        mut commands,
+        mut meshes,
+        mut materials,
+        mut game_manager,
+        ..
+    }: ObjectSpawner,
+    mut trash_events: EventReader<TrashEvent>,
+    mut trash: ResMut<Trash>,
+    mut notifications: ResMut<EditorNotifications>,
+    time: Res<Time>,
+) {
+    for event in trash_events.read() {
+        match *event {
+            TrashEvent::Restore(key) => {
+                let Some(entry) = trash.take(key) else {
+                    continue;
+                };
+                let snapshots = reassign_taken_ids(&game_manager, &entry.snapshots);
+                restore_objects(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &mut game_manager,
+                    &snapshots,
+                    time.elapsed_secs_f64(),
+                );
+                let root = &snapshots[0];
+                if root.id == entry.snapshots[0].id {
+                    notifications.info(format!("Restored {}", root.name));
+                } else {
+                    notifications.info(format!(
+                        "Restored {} as ID {}, its old ID is in use",
+                        root.name, root.id
+                    ));
+                }
+            }
+            TrashEvent::DeleteForever(key) => {
+                if let Some(entry) = trash.take(key) {
+                    info!("Deleted {} from the trash", entry.name());
+                }
+            }
+            TrashEvent::Empty => {
+                let count = trash.empty();
+                if count > 0 {
+                    notifications.info(format!("Emptied the trash ({} entries)", count));
+                }
+            }
+        }
+    }
+}
+
+pub fn trash_panel_system(
+    mut contexts: EguiContexts,
+    trash: Res<Trash>,
+    mut trash_events: EventWriter<TrashEvent>,
+    mut layout: ResMut<EditorLayout>,
+    time: Res<Time>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "Trash",
+        egui::Window::new("Trash").default_width(280.0),
+        |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} / {} entries", trash.len(), trash.capacity()));
+                if ui
+                    .add_enabled(!trash.is_empty(), egui::Button::new("Empty trash"))
+                    .clicked()
+                {
+                    trash_events.write(TrashEvent::Empty);
+                }
+            });
+            ui.separator();
+
+            if trash.is_empty() {
+                ui.weak("The trash is empty");
+                return;
+            }
+
+            let now = time.elapsed_secs_f64();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // Newest first
+                for entry in trash.iter().rev() {
+                    let Some(root) = entry.snapshots.first() else {
+                        continue;
+                    };
+                    ui.horizontal(|ui| {
+                        let children = match entry.snapshots.len() {
+                            1 => String::new(),
+                            n => format!(" +{} children", n - 1),
+                        };
+                        ui.label(format!(
+                            "{} ({}){}",
+                            root.name,
+                            root.shape_type.display_name(),
+                            children
+                        ))
+                        .on_hover_text(format!(
+                            "ID {}, trashed {:.0}s ago",
+                            root.id,
+                            now - entry.trashed_at
+                        ));
+                        if ui.small_button("Restore").clicked() {
+                            trash_events.write(TrashEvent::Restore(entry.key));
+                        }
+                        if ui.small_button("Delete forever").clicked() {
+                            trash_events.write(TrashEvent::DeleteForever(entry.key));
+                        }
+                    });
+                }
+            });
+        },
+    );
+}
+
+pub fn register_trash_commands(mut registry: ResMut<CommandRegistry>) {
+    registry.register("Move selected to trash", None, |world| {
+        let ids = world
+            .resource::<Selection>()
+            .ids(world.resource::<GameObjectManager>());
+        if !ids.is_empty() {
+            world.send_event(DespawnEntityEvent {
+                target: DespawnTarget::Trash(ids),
+            });
+        }
+    });
+    registry.register("Empty trash", None, |world| {
+        world.send_event(TrashEvent::Empty);
+    });
+}
+
+// Deleted objects kept so they can be restored
+pub struct TrashPlugin;
+
+impl Plugin for TrashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TrashEvent>()
+            .init_resource::<Trash>()
+            .add_systems(Startup, register_trash_commands)
+            .add_systems(EguiContextPass, trash_panel_system.in_set(UiSet::Panels));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::objects::ShapeType;
+
+    // Snapshots of freshly registered objects, ids from zero
+    fn snapshots(count: u32) -> (GameObjectManager, Vec<ObjectSnapshot>) {
+        let mut game_manager = GameObjectManager::default();
+        let snapshots = (0..count)
+            .map(|index| {
+                let id = game_manager
+                    .add_object(
+                        Entity::from_raw(index + 1),
+                        ShapeType::Cube,
+                        Vec3::ZERO,
+                        Some(format!("object {}", index)),
+                        false,
+                        0.0,
+                    )
+                    .unwrap();
+                game_manager.snapshot(id, Transform::default()).unwrap()
+            })
+            .collect();
+        (game_manager, snapshots)
+    }
+
+    fn names(trash: &Trash) -> Vec<&str> {
+        trash.iter().map(TrashEntry::name).collect()
+    }
+
+    #[test]
+    fn full_trash_evicts_the_oldest_entry() {
+        let (_, snapshots) = snapshots(4);
+        let mut trash = Trash::with_capacity(3);
+        for (index, snapshot) in snapshots.iter().take(3).enumerate() {
+            assert!(trash.push(vec![snapshot.clone()], index as f64).is_none());
+        }
+
+        let evicted = trash.push(vec![snapshots[3].clone()], 3.0).unwrap();
+        assert_eq!(evicted.name(), "object 0");
+        assert_eq!(trash.len(), 3);
+        assert_eq!(names(&trash), ["object 1", "object 2", "object 3"]);
+    }
+
+    #[test]
+    fn keys_stay_unique_after_eviction() {
+        let (_, snapshots) = snapshots(3);
+        let mut trash = Trash::with_capacity(2);
+        for snapshot in &snapshots {
+            trash.push(vec![snapshot.clone()], 0.0);
+        }
+        let keys: Vec<u64> = trash.iter().map(|entry| entry.key).collect();
+        assert_eq!(keys, [1, 2]);
+        assert!(trash.take(0).is_none());
+        assert_eq!(trash.take(2).unwrap().name(), "object 2");
+        assert_eq!(names(&trash), ["object 1"]);
+    }
+
+    #[test]
+    fn emptying_drops_every_entry() {
+        let (_, snapshots) = snapshots(3);
+        let mut trash = Trash::default();
+        // A parent and its child go in as one entry
+        trash.push(snapshots[..2].to_vec(), 0.0);
+        trash.push(vec![snapshots[2].clone()], 1.0);
+        assert!(trash.push(Vec::new(), 2.0).is_none());
+        assert_eq!(trash.len(), 2);
+
+        assert_eq!(trash.empty(), 2);
+        assert!(trash.is_empty());
+        assert_eq!(trash.empty(), 0);
+        // Still usable afterwards
+        trash.push(vec![snapshots[0].clone()], 3.0);
+        assert_eq!(names(&trash), ["object 0"]);
+    }
+
+    #[test]
+    fn zero_capacity_still_holds_one_entry() {
+        let (_, snapshots) = snapshots(2);
+        let mut trash = Trash::with_capacity(0);
+        assert_eq!(trash.capacity(), 1);
+        trash.push(vec![snapshots[0].clone()], 0.0);
+        assert!(trash.push(vec![snapshots[1].clone()], 1.0).is_some());
+        assert_eq!(names(&trash), ["object 1"]);
+    }
+
+    #[test]
+    fn restored_ids_only_change_when_taken() {
+        let (mut game_manager, snapshots) = snapshots(3);
+        // Object 1 was trashed, object 0 still exists
+        game_manager.remove_object(Entity::from_raw(2));
+        let mut trashed = vec![snapshots[0].clone(), snapshots[1].clone()];
+        trashed[1].parent = Some(snapshots[0].id);
+
+        let restored = reassign_taken_ids(&game_manager, &trashed);
+        let ids: Vec<u32> = restored.iter().map(|snapshot| snapshot.id).collect();
+        assert_eq!(ids, [3, 1]);
+        assert_eq!(restored[1].parent, Some(3));
+    }
+}
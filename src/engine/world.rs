@@ -0,0 +1,318 @@
+use crate::engine::config::{CONFIG_FILE_NAME, NaviConfig};
+use crate::engine::core::UiSet;
+use crate::engine::core::{GROUND_COLOR, GROUND_POSITION, GROUND_SIZE};
+use crate::engine::input::{InputAction, InputBindings, key_name};
+use crate::engine::layout::PanelContext;
+use crate::engine::objects::{EvictionPolicy, GameObjectManager, SpawnLimits};
+use crate::engine::palette::{CommandHint, CommandRegistry};
+use crate::engine::pool::{EntityPool, pool_settings_ui};
+use crate::engine::serde_util;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const EARTH_GRAVITY: Vec3 = Vec3::new(0.0, -9.81, 0.0);
+pub const MOON_GRAVITY: Vec3 = Vec3::new(0.0, -1.62, 0.0);
+pub const MARS_GRAVITY: Vec3 = Vec3::new(0.0, -3.71, 0.0);
+
+// Gravity buttons in the World panel and the palette
+pub const GRAVITY_PRESETS: [(&str, Vec3); 4] = [
+    ("Earth", EARTH_GRAVITY),
+    ("Moon", MOON_GRAVITY),
+    ("Mars", MARS_GRAVITY),
+    ("Zero-G", Vec3::ZERO),
+];
+
+// Physics world parameters, kept serializable so scenes can store them
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldSettings {
+    #[serde(with = "serde_util::vec3")]
+    pub gravity: Vec3,
+    // The static ground slab, on unless a scene turns it off
+    pub ground: bool,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            gravity: EARTH_GRAVITY,
+            ground: true,
+        }
+    }
+}
+
+// Change gravity from code or the palette, non-finite values are ignored
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SetGravityEvent {
+    pub gravity: Vec3,
+}
+
+pub fn set_gravity_system(
+    mut gravity_events: EventReader<SetGravityEvent>,
+    mut settings: ResMut<WorldSettings>,
+) {
+    for event in gravity_events.read() {
+        if !event.gravity.is_finite() {
+            warn!("Ignoring gravity {:?}", event.gravity);
+            continue;
+        }
+        if event.gravity != settings.gravity {
+            settings.gravity = event.gravity;
+        }
+    }
+}
+
+// Turn gravity upside down, all three axes so sideways gravity flips too
+pub fn flip_gravity_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    bindings: Res<InputBindings>,
+    settings: Res<WorldSettings>,
+    mut gravity_events: EventWriter<SetGravityEvent>,
+) {
+    if !egui_input.wants_keyboard_input()
+        && bindings.just_pressed(InputAction::FlipGravity, &keyboard_input)
+    {
+        gravity_events.write(SetGravityEvent {
+            gravity: -settings.gravity,
+        });
+    }
+}
+
+// The ground every scene stands on unless it turns it off, not tracked by the manager
+#[derive(Component)]
+pub struct Ground;
+
+fn spawn_ground(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    commands.spawn((
+        Ground,
+        Name::new("Ground"),
+        Mesh3d(meshes.add(Cuboid::from_size(GROUND_SIZE))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: GROUND_COLOR,
+            ..default()
+        })),
+        Transform::from_translation(GROUND_POSITION),
+        Collider::cuboid(
+            GROUND_SIZE.x / 2.0,
+            GROUND_SIZE.y / 2.0,
+            GROUND_SIZE.z / 2.0,
+        ),
+        RigidBody::Fixed,
+    ));
+}
+
+// Push changed settings into rapier and wake every body so resting objects react
+pub fn apply_world_settings_system(
+    mut commands: Commands,
+    settings: Res<WorldSettings>,
+    mut configs: Query<&mut RapierConfiguration>,
+    mut rapier_context: WriteRapierContext,
+    grounds: Query<Entity, With<Ground>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if settings.ground && grounds.is_empty() {
+        spawn_ground(&mut commands, &mut meshes, &mut materials);
+    } else if !settings.ground {
+        for entity in &grounds {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for mut config in &mut configs {
+        config.gravity = settings.gravity;
+    }
+
+    if let Ok(mut context) = rapier_context.single_mut() {
+        for (_, body) in context.rigidbody_set.bodies.iter_mut() {
+            body.wake_up(true);
+        }
+    }
+    info!("Gravity set to {:?}", settings.gravity);
+}
+
+pub fn world_panel_system(
+    PanelContext {
+        mut contexts,
+        mut layout,
+    }: PanelContext,
+    mut settings: ResMut<WorldSettings>,
+    mut limits: ResMut<SpawnLimits>,
+    mut pool: ResMut<EntityPool>,
+    game_manager: Res<GameObjectManager>,
+    config: Res<NaviConfig>,
+    bindings: Res<InputBindings>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    layout.show(
+        ctx,
+        "World",
+        egui::Window::new("World").default_width(240.0),
+        |ui| {
+            // Edit a copy so the resource is only marked changed on real edits
+            let mut gravity = settings.gravity;
+            ui.horizontal(|ui| {
+                ui.label("Gravity");
+                ui.add(
+                    egui::DragValue::new(&mut gravity.x)
+                        .speed(0.05)
+                        .prefix("x: "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut gravity.y)
+                        .speed(0.05)
+                        .prefix("y: "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut gravity.z)
+                        .speed(0.05)
+                        .prefix("z: "),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                for (name, preset) in GRAVITY_PRESETS {
+                    if ui
+                        .selectable_label(gravity == preset, name)
+                        .on_hover_text(format!("{} m/s²", preset.length()))
+                        .clicked()
+                    {
+                        gravity = preset;
+                    }
+                }
+            });
+            if ui
+                .button("Flip")
+                .on_hover_text(key_name(bindings.key(InputAction::FlipGravity)))
+                .clicked()
+            {
+                gravity = -gravity;
+            }
+
+            let reset = ui.button("Reset");
+            let reset = if config.is_from_file("physics.gravity") {
+                reset.on_hover_text(format!("Default gravity from {}", CONFIG_FILE_NAME))
+            } else {
+                reset
+            };
+            if reset.clicked() {
+                gravity = config.gravity;
+            }
+
+            if gravity != settings.gravity {
+                settings.gravity = gravity;
+            }
+
+            let mut ground = settings.ground;
+            ui.checkbox(&mut ground, "Ground");
+            if ground != settings.ground {
+                settings.ground = ground;
+            }
+
+            ui.separator();
+            let mut capped = limits.max_objects.is_some();
+            let mut max = limits.max_objects.unwrap_or(500);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut capped, "Limit objects");
+                ui.add_enabled(capped, egui::DragValue::new(&mut max).range(1..=100_000));
+                ui.weak(format!("{} now", game_manager.len()));
+            });
+            let max_objects = capped.then_some(max);
+            if max_objects != limits.max_objects {
+                limits.max_objects = max_objects;
+            }
+            ui.add_enabled_ui(capped, |ui| {
+                egui::ComboBox::from_label("When full")
+                    .selected_text(limits.policy.label())
+                    .show_ui(ui, |ui| {
+                        for policy in EvictionPolicy::all() {
+                            ui.selectable_value(&mut limits.policy, policy, policy.label());
+                        }
+                    });
+            });
+
+            ui.separator();
+            pool_settings_ui(ui, &mut pool);
+
+            ui.separator();
+            let source = config.path.as_ref().map_or_else(
+                || format!("Engine config (no {})", CONFIG_FILE_NAME),
+                |path| format!("Engine config ({})", path.display()),
+            );
+            ui.collapsing(source, |ui| {
+                egui::Grid::new("engine_config")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (key, value) in config.entries() {
+                            ui.label(key);
+                            ui.label(value);
+                            if config.is_from_file(key) {
+                                ui.label(CONFIG_FILE_NAME);
+                            } else {
+                                ui.weak("default");
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if !config.warnings.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} settings ignored, see the console",
+                            config.warnings.len()
+                        ),
+                    );
+                }
+                ui.weak("Read on start, restart to apply changes");
+            });
+        },
+    );
+}
+
+pub fn register_world_commands(mut registry: ResMut<CommandRegistry>) {
+    for (name, gravity) in GRAVITY_PRESETS {
+        registry.register(format!("Gravity: {}", name), None, move |world| {
+            world.send_event(SetGravityEvent { gravity });
+        });
+    }
+    registry.register(
+        "Flip gravity",
+        Some(CommandHint::Binding(InputAction::FlipGravity)),
+        |world| {
+            let gravity = -world.resource::<WorldSettings>().gravity;
+            world.send_event(SetGravityEvent { gravity });
+        },
+    );
+    registry.register("Toggle debug render", None, |world| {
+        if let Some(mut debug) = world.get_resource_mut::<DebugRenderContext>() {
+            debug.enabled = !debug.enabled;
+        }
+    });
+}
+
+// Gravity and the ground. The world settings start from navi.toml
+pub struct WorldPlugin;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SetGravityEvent>()
+            .add_systems(Startup, register_world_commands)
+            .add_systems(EguiContextPass, world_panel_system.in_set(UiSet::Panels));
+    }
+}
@@ -1,5 +1,62 @@
-mod engine;
+use clap::Parser;
+use navi::engine::session::StartupScene;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-fn main() {
-    engine::core::run();
+#[derive(Parser)]
+#[command(name = "navi", version, about = "A 3D scene editor")]
+struct Cli {
+    #[arg(help = "Scene to open (.ron, .json or .nvscene)")]
+    scene: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with = "scene",
+        help = "Start with an empty scene instead of offering the last one"
+    )]
+    new: bool,
+    // Scene file tools, these run without opening the editor
+    #[arg(
+        long,
+        value_name = "SCENE",
+        conflicts_with_all = ["scene", "new", "convert"],
+        help = "Upgrade a scene file to the current format version in place"
+    )]
+    migrate: Option<PathBuf>,
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["FROM", "TO"],
+        conflicts_with_all = ["scene", "new"],
+        help = "Rewrite a scene in the format of TO's extension, .ron, .json or .nvscene"
+    )]
+    convert: Option<Vec<PathBuf>>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = if let Some(file) = &cli.migrate {
+        navi::engine::migrate::migrate_file(file)
+            .map_err(|err| format!("Cannot migrate {}: {}", file.display(), err))
+    } else if let Some([from, to]) = cli.convert.as_deref() {
+        navi::engine::scene::convert_scene_file(from, to)
+            .map_err(|err| format!("Cannot convert {}: {}", from.display(), err))
+    } else {
+        let startup = match cli.scene {
+            Some(path) => StartupScene::Open(path),
+            None if cli.new => StartupScene::Empty,
+            None => StartupScene::Last,
+        };
+        navi::engine::core::run(startup);
+        return ExitCode::SUCCESS;
+    };
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
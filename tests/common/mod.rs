@@ -0,0 +1,119 @@
+// An editor app without a window, renderer or egui: the object model, the
+// spawn, despawn and scene systems in the order core.rs runs them, and
+// counters for the object lifecycle events
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use navi::engine::autosave::Autosave;
+use navi::engine::batch::{BatchSpawnEvent, batch_spawn_system};
+use navi::engine::config::NaviConfig;
+use navi::engine::custom_mesh::CustomMeshLibrary;
+use navi::engine::groups::Groups;
+use navi::engine::history::EditorHistory;
+use navi::engine::joints::JointRegistry;
+use navi::engine::notify::EditorNotifications;
+use navi::engine::objects::{
+    DespawnEntityEvent, EditorRng, GameObjectManager, HierarchySettings, NamingSettings,
+    ObjectRemovedEvent, ObjectSpawnedEvent, SpawnEntityEvent, SpawnLimits,
+    announce_spawned_objects_system, cleanup_destroyed_entities_system, despawn_entity_system,
+    spawn_entity_system, update_object_positions_system,
+};
+use navi::engine::playback::Playback;
+use navi::engine::pool::EntityPool;
+use navi::engine::scene::{LoadSceneEvent, RestoreSceneEvent, SceneFileDialog, load_scene_system};
+use navi::engine::snap::SnapSettings;
+use navi::engine::templates::{NewSceneEvent, SceneTemplates};
+use navi::engine::trash::Trash;
+use navi::engine::world::WorldSettings;
+use std::collections::HashMap;
+
+// Lifecycle events seen so far, per object id
+#[derive(Resource, Default, Debug)]
+pub struct EventCounts {
+    pub spawned: HashMap<u32, usize>,
+    pub removed: HashMap<u32, usize>,
+}
+
+impl EventCounts {
+    pub fn total_spawned(&self) -> usize {
+        self.spawned.values().sum()
+    }
+
+    pub fn total_removed(&self) -> usize {
+        self.removed.values().sum()
+    }
+}
+
+fn count_events_system(
+    mut spawned: EventReader<ObjectSpawnedEvent>,
+    mut removed: EventReader<ObjectRemovedEvent>,
+    mut counts: ResMut<EventCounts>,
+) {
+    for event in spawned.read() {
+        *counts.spawned.entry(event.id).or_default() += 1;
+    }
+    for event in removed.read() {
+        *counts.removed.entry(event.id).or_default() += 1;
+    }
+}
+
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .add_event::<SpawnEntityEvent>()
+        .add_event::<BatchSpawnEvent>()
+        .add_event::<DespawnEntityEvent>()
+        .add_event::<ObjectSpawnedEvent>()
+        .add_event::<ObjectRemovedEvent>()
+        .add_event::<LoadSceneEvent>()
+        .add_event::<RestoreSceneEvent>()
+        .add_event::<NewSceneEvent>()
+        .init_resource::<GameObjectManager>()
+        .init_resource::<EditorHistory>()
+        .init_resource::<EditorNotifications>()
+        .init_resource::<SnapSettings>()
+        .init_resource::<NamingSettings>()
+        .init_resource::<SpawnLimits>()
+        .init_resource::<EntityPool>()
+        .init_resource::<CustomMeshLibrary>()
+        .init_resource::<EditorRng>()
+        .init_resource::<Playback>()
+        .init_resource::<HierarchySettings>()
+        .init_resource::<Trash>()
+        .init_resource::<SceneFileDialog>()
+        .init_resource::<WorldSettings>()
+        .init_resource::<Groups>()
+        .init_resource::<Autosave>()
+        .init_resource::<SceneTemplates>()
+        .init_resource::<NaviConfig>()
+        .init_resource::<JointRegistry>()
+        .init_resource::<EventCounts>()
+        .add_systems(
+            Update,
+            (
+                spawn_entity_system,
+                batch_spawn_system,
+                load_scene_system,
+                despawn_entity_system,
+                cleanup_destroyed_entities_system,
+                announce_spawned_objects_system,
+                update_object_positions_system,
+                count_events_system,
+            )
+                .chain(),
+        );
+    app
+}
+
+pub fn spawn_event(
+    shape_type: navi::engine::objects::ShapeType,
+    position: Vec3,
+) -> SpawnEntityEvent {
+    SpawnEntityEvent {
+        position,
+        shape_type,
+        ..default()
+    }
+}
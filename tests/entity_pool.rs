@@ -0,0 +1,116 @@
+// Pooled entities come back as new objects with nothing left over from the
+// object they were
+mod common;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use common::{headless_app, spawn_event};
+use navi::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectId, GameObjectManager, ShapeDimensions, ShapeType,
+    SpawnEntityEvent,
+};
+use navi::engine::pool::{EntityPool, PooledObject};
+
+fn pooled_app() -> App {
+    let mut app = headless_app();
+    app.world_mut().resource_mut::<EntityPool>().enabled = true;
+    app
+}
+
+fn spawn(app: &mut App, name: &str, position: Vec3, params: Option<ShapeDimensions>) -> Entity {
+    app.world_mut().send_event(SpawnEntityEvent {
+        custom_name: Some(name.to_string()),
+        params,
+        ..spawn_event(ShapeType::Cube, position)
+    });
+    app.update();
+    let game_manager = app.world().resource::<GameObjectManager>();
+    game_manager
+        .iter()
+        .find(|obj| obj.name == name)
+        .map(|obj| obj.entity())
+        .unwrap()
+}
+
+fn despawn(app: &mut App, entity: Entity) {
+    let id = app.world().get::<GameObjectId>(entity).unwrap().id;
+    app.world_mut().send_event(DespawnEntityEvent {
+        target: DespawnTarget::Id(id),
+    });
+    app.update();
+}
+
+fn parked_cubes(app: &App) -> usize {
+    app.world()
+        .resource::<EntityPool>()
+        .parked_count(ShapeType::Cube)
+}
+
+#[test]
+fn reused_entities_are_fully_reset() {
+    let mut app = pooled_app();
+    let first = spawn(&mut app, "first", Vec3::ZERO, None);
+    // Leave marks a fresh spawn wouldn't have
+    app.world_mut().entity_mut(first).insert((
+        Velocity {
+            linvel: Vec3::new(3.0, -2.0, 1.0),
+            angvel: Vec3::Y * 4.0,
+        },
+        Transform::from_xyz(8.0, 9.0, 10.0).with_rotation(Quat::from_rotation_x(1.0)),
+    ));
+    despawn(&mut app, first);
+    assert_eq!(parked_cubes(&app), 1);
+    assert!(app.world().resource::<GameObjectManager>().is_empty());
+    assert!(app.world().entity(first).contains::<PooledObject>());
+
+    let second = spawn(&mut app, "second", Vec3::new(3.0, 1.0, -2.0), None);
+    assert_eq!(second, first);
+    assert_eq!(parked_cubes(&app), 0);
+
+    let world = app.world();
+    let game_manager = world.resource::<GameObjectManager>();
+    let obj = game_manager.get_object_by_entity(second).unwrap();
+    let reused = world.entity(second);
+    assert_eq!(reused.get::<GameObjectId>().unwrap().name, "second");
+    assert_eq!(reused.get::<GameObjectId>().unwrap().id, obj.id);
+    assert_eq!(reused.get::<Velocity>(), Some(&Velocity::zero()));
+    assert_eq!(
+        reused.get::<Transform>(),
+        Some(&Transform::from_xyz(3.0, 1.0, -2.0))
+    );
+    assert_eq!(reused.get::<Visibility>(), Some(&Visibility::Inherited));
+    assert!(!reused.contains::<PooledObject>());
+    assert!(!reused.contains::<ColliderDisabled>());
+    let material = reused.get::<MeshMaterial3d<StandardMaterial>>().unwrap();
+    let materials = world.resource::<Assets<StandardMaterial>>();
+    assert_eq!(materials.get(&material.0).unwrap().base_color, obj.color);
+}
+
+#[test]
+fn a_new_size_rebuilds_the_mesh() {
+    let mut app = pooled_app();
+    let first = spawn(&mut app, "first", Vec3::ZERO, None);
+    let mesh = app.world().get::<Mesh3d>(first).unwrap().clone();
+    despawn(&mut app, first);
+
+    let bigger = ShapeDimensions {
+        half_extents: Vec3::splat(2.0),
+        ..ShapeType::Cube.default_dimensions()
+    };
+    let second = spawn(&mut app, "second", Vec3::ZERO, Some(bigger));
+    assert_eq!(second, first);
+    assert_ne!(app.world().get::<Mesh3d>(second).unwrap(), &mesh);
+}
+
+#[test]
+fn an_unusable_parked_entity_is_despawned_not_lost() {
+    let mut app = pooled_app();
+    let first = spawn(&mut app, "first", Vec3::ZERO, None);
+    despawn(&mut app, first);
+    app.world_mut().entity_mut(first).remove::<PooledObject>();
+
+    let second = spawn(&mut app, "second", Vec3::ZERO, None);
+    assert_ne!(second, first);
+    assert!(app.world().get_entity(first).is_err());
+    assert_eq!(parked_cubes(&app), 0);
+}
@@ -0,0 +1,145 @@
+// ObjectSpawnedEvent and ObjectRemovedEvent fire exactly once per object,
+// whichever way it came or went
+mod common;
+
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use common::{EventCounts, headless_app, spawn_event};
+use navi::engine::batch::{BatchSpawnEvent, SpawnLayout, SpawnRegion};
+use navi::engine::objects::{
+    DespawnEntityEvent, DespawnTarget, GameObjectManager, ShapeType, SpawnSource,
+};
+use navi::engine::scene::{RestoreSceneEvent, SceneFile, SceneLoadMode};
+use navi::engine::world::WorldSettings;
+
+fn counts(app: &App) -> &EventCounts {
+    app.world().resource::<EventCounts>()
+}
+
+fn ids(app: &App) -> Vec<u32> {
+    app.world()
+        .resource::<GameObjectManager>()
+        .iter()
+        .map(|obj| obj.id)
+        .collect()
+}
+
+fn assert_once_each(map: &std::collections::HashMap<u32, usize>) {
+    for (id, count) in map {
+        assert_eq!(*count, 1, "object {} got {} events", id, count);
+    }
+}
+
+fn batch(count: usize) -> BatchSpawnEvent {
+    BatchSpawnEvent {
+        source: SpawnSource::Script,
+        shape_type: ShapeType::Ball,
+        count,
+        region: SpawnRegion::Box {
+            center: Vec3::ZERO,
+            half_extents: Vec3::splat(5.0),
+        },
+        base_name: None,
+        layout: SpawnLayout::Grid,
+        jitter: 0.0,
+        params: None,
+    }
+}
+
+#[test]
+fn single_spawns_and_despawns() {
+    let mut app = headless_app();
+    for x in 0..3 {
+        app.world_mut()
+            .send_event(spawn_event(ShapeType::Cube, Vec3::X * x as f32));
+    }
+    app.update();
+    assert_eq!(counts(&app).total_spawned(), 3);
+    assert_once_each(&counts(&app).spawned);
+
+    let first = ids(&app)[0];
+    app.world_mut().send_event(DespawnEntityEvent {
+        target: DespawnTarget::Id(first),
+    });
+    app.update();
+    // And nothing more turns up a frame later, once the entity is gone
+    app.update();
+    assert_eq!(counts(&app).total_removed(), 1);
+    assert_eq!(counts(&app).removed.get(&first), Some(&1));
+    assert_eq!(counts(&app).total_spawned(), 3);
+}
+
+#[test]
+fn batch_spawn_fires_once_per_object() {
+    let mut app = headless_app();
+    app.world_mut().send_event(batch(50));
+    app.update();
+    app.update();
+    assert_eq!(counts(&app).total_spawned(), 50);
+    assert_eq!(counts(&app).spawned.len(), 50);
+    assert_once_each(&counts(&app).spawned);
+
+    app.world_mut().send_event(DespawnEntityEvent {
+        target: DespawnTarget::All,
+    });
+    app.update();
+    app.update();
+    assert_eq!(counts(&app).total_removed(), 50);
+    assert_once_each(&counts(&app).removed);
+}
+
+#[test]
+fn entities_despawned_outside_the_editor_are_reported() {
+    let mut app = headless_app();
+    app.world_mut()
+        .send_event(spawn_event(ShapeType::Cylinder, Vec3::ZERO));
+    app.update();
+    let entity = app
+        .world()
+        .resource::<GameObjectManager>()
+        .iter()
+        .next()
+        .unwrap()
+        .entity();
+    app.world_mut().entity_mut(entity).despawn();
+    app.update();
+    app.update();
+    assert_eq!(counts(&app).total_removed(), 1);
+    assert!(app.world().resource::<GameObjectManager>().is_empty());
+}
+
+#[test]
+fn scene_loads_report_replaced_and_loaded_objects() {
+    let mut app = headless_app();
+    app.world_mut().send_event(batch(5));
+    app.update();
+    let before = ids(&app);
+
+    // The same objects saved and loaded back over themselves
+    let scene = {
+        let mut state: SystemState<(
+            Res<GameObjectManager>,
+            Res<WorldSettings>,
+            Query<&Transform>,
+        )> = SystemState::new(app.world_mut());
+        let (game_manager, world, transforms) = state.get(app.world());
+        SceneFile::capture(&game_manager, &world, &transforms)
+    };
+    app.world_mut().send_event(RestoreSceneEvent {
+        source: "the test".to_string(),
+        scene: scene.clone(),
+        mode: SceneLoadMode::Replace,
+    });
+    app.update();
+    app.update();
+
+    let counts = counts(&app);
+    for id in &before {
+        assert_eq!(counts.removed.get(id), Some(&1), "object {}", id);
+        // Spawned by the batch, then again by the load
+        assert_eq!(counts.spawned.get(id), Some(&2), "object {}", id);
+    }
+    assert_eq!(counts.total_removed(), 5);
+    assert_eq!(counts.total_spawned(), 10);
+    assert_eq!(ids(&app), before);
+}
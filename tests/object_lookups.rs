@@ -0,0 +1,78 @@
+// GameObjectManager lookups by id and entity at a scene size where a scan per
+// lookup would show, no app needed
+use bevy::prelude::*;
+use navi::engine::objects::{GameObjectManager, ShapeType};
+use std::time::{Duration, Instant};
+
+const OBJECTS: u32 = 10_000;
+// Every lookup below, twice over. A linear scan per lookup makes this 10^8
+// comparisons, which takes seconds even optimized
+const LOOKUP_BUDGET: Duration = if cfg!(debug_assertions) {
+    Duration::from_millis(250)
+} else {
+    Duration::from_millis(20)
+};
+
+fn entity(index: u32) -> Entity {
+    Entity::from_raw(index + 1)
+}
+
+fn manager() -> GameObjectManager {
+    let mut game_manager = GameObjectManager::default();
+    let ids = game_manager.add_objects(
+        ShapeType::Cube,
+        (0..OBJECTS).map(|index| (entity(index), Vec3::X * index as f32, None)),
+        0.0,
+    );
+    assert_eq!(ids.len(), OBJECTS as usize);
+    game_manager
+}
+
+#[test]
+fn lookups_stay_fast_with_ten_thousand_objects() {
+    let game_manager = manager();
+
+    let start = Instant::now();
+    for index in 0..OBJECTS {
+        let by_entity = game_manager.get_object_by_entity(entity(index)).unwrap();
+        let by_id = game_manager.get_object_by_id(by_entity.id).unwrap();
+        assert_eq!(by_id.entity(), entity(index));
+    }
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < LOOKUP_BUDGET,
+        "{} lookups took {:?}",
+        OBJECTS * 2,
+        elapsed
+    );
+}
+
+#[test]
+fn removals_stay_fast_and_keep_the_index_right() {
+    let mut game_manager = manager();
+
+    let start = Instant::now();
+    for index in (0..OBJECTS).step_by(2) {
+        assert!(game_manager.remove_object(entity(index)).is_some());
+    }
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < LOOKUP_BUDGET,
+        "{} removals took {:?}",
+        OBJECTS / 2,
+        elapsed
+    );
+
+    assert_eq!(game_manager.len(), (OBJECTS / 2) as usize);
+    for index in 0..OBJECTS {
+        let found = game_manager.get_object_by_entity(entity(index));
+        assert_eq!(found.is_some(), index % 2 == 1, "entity {}", index);
+        if let Some(obj) = found {
+            assert_eq!(
+                game_manager.get_object_by_id(obj.id).unwrap().entity(),
+                entity(index)
+            );
+        }
+    }
+    assert!(game_manager.remove_object(entity(0)).is_none());
+}
@@ -0,0 +1,155 @@
+// The serialized object model, frozen in tests/data/object_model.json. A
+// failure here means saved scenes, prefabs or clipboard data would change
+// shape, so either undo the change or bump the scene format with a migration
+// and update the file
+use bevy::prelude::*;
+use navi::engine::objects::{
+    BodyKind, GameObject, GameObjectManager, MetaValue, PhysicsProps, ShapeDimensions, ShapeParams,
+    ShapeType, SpawnEntityEvent, SpawnSource,
+};
+use navi::engine::world::WorldSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const FROZEN: &str = include_str!("data/object_model.json");
+
+// One of each serialized type. Values are exact in f32 so the JSON numbers
+// compare equal whichever way they were produced
+#[derive(Serialize, Deserialize)]
+struct Sample {
+    shape_types: Vec<ShapeType>,
+    dimensions: ShapeDimensions,
+    params: ShapeParams,
+    physics: PhysicsProps,
+    world: WorldSettings,
+    object: GameObject,
+    spawn: SpawnEntityEvent,
+}
+
+fn sample() -> Sample {
+    let dimensions = ShapeDimensions {
+        radius: 0.25,
+        half_extents: Vec3::new(0.5, 1.0, 1.5),
+        height: 2.0,
+    };
+    let cube = ShapeDimensions {
+        half_extents: Vec3::new(2.0, 0.5, 0.25),
+        ..default()
+    };
+    let physics = PhysicsProps {
+        body: BodyKind::Kinematic,
+        restitution: 0.25,
+        friction: 0.5,
+        density: 2.0,
+        linear_damping: 0.125,
+        angular_damping: 0.0625,
+        gravity_scale: 1.5,
+    };
+
+    let mut game_manager = GameObjectManager::default();
+    let id = game_manager
+        .add_object(
+            Entity::from_raw(1),
+            ShapeType::Cube,
+            Vec3::new(1.0, 2.5, -3.0),
+            Some("Crate".to_string()),
+            false,
+            12.5,
+        )
+        .unwrap();
+    game_manager.add_tag(id, "Heavy").unwrap();
+    for (key, value) in [
+        ("flag", MetaValue::Bool(true)),
+        ("score", MetaValue::Number(3.5)),
+        ("note", MetaValue::String("true".to_string())),
+    ] {
+        game_manager.set_meta(id, key, value).unwrap();
+    }
+    let obj = game_manager.get_object_by_id_mut(id).unwrap();
+    obj.linvel = Vec3::X;
+    obj.angvel = Vec3::Y * 0.5;
+    obj.color = Color::srgba(1.0, 0.5, 0.25, 1.0);
+    obj.physics = PhysicsProps {
+        body: BodyKind::Dynamic,
+        ..physics
+    };
+    obj.dimensions = cube;
+    obj.locked = true;
+    obj.prefab = Some("crate".to_string());
+    obj.source = SpawnSource::Keyboard;
+    let object = obj.clone();
+
+    Sample {
+        shape_types: vec![
+            ShapeType::Ball,
+            ShapeType::Cube,
+            ShapeType::Capsule,
+            ShapeType::Cylinder,
+            ShapeType::Cone,
+            ShapeType::CustomMesh,
+        ],
+        dimensions,
+        params: ShapeParams {
+            dimensions: HashMap::from([(ShapeType::Cube, cube)]),
+        },
+        physics,
+        world: WorldSettings {
+            gravity: Vec3::new(0.0, -4.0, 0.0),
+            ground: false,
+        },
+        object,
+        spawn: SpawnEntityEvent {
+            source: SpawnSource::Network,
+            position: Vec3::Y * 5.0,
+            shape_type: ShapeType::Capsule,
+            custom_name: Some("pill".to_string()),
+            params: Some(dimensions),
+            tags: vec!["falling".to_string()],
+            metadata: HashMap::from([("score".to_string(), MetaValue::Number(3.5))]),
+            lifetime_secs: Some(2.5),
+            custom_mesh: None,
+            physics: None,
+            body_type: Some(BodyKind::Fixed),
+        },
+    }
+}
+
+fn frozen() -> Value {
+    serde_json::from_str(FROZEN).unwrap()
+}
+
+#[test]
+fn sample_serializes_to_the_frozen_file() {
+    assert_eq!(serde_json::to_value(sample()).unwrap(), frozen());
+}
+
+#[test]
+fn frozen_file_reads_back_unchanged() {
+    let read: Sample = serde_json::from_str(FROZEN).unwrap();
+    assert_eq!(read.object.entity(), Entity::PLACEHOLDER);
+    assert_eq!(read.object.position(), Vec3::new(1.0, 2.5, -3.0));
+    assert_eq!(
+        read.object.metadata.get("note"),
+        Some(&MetaValue::String("true".to_string()))
+    );
+    assert_eq!(serde_json::to_value(read).unwrap(), frozen());
+}
+
+#[test]
+fn every_type_round_trips_through_ron() {
+    let text = ron::to_string(&sample()).unwrap();
+    let read: Sample = ron::from_str(&text).unwrap();
+    assert_eq!(serde_json::to_value(read).unwrap(), frozen());
+}
+
+#[test]
+fn missing_fields_fall_back_to_defaults() {
+    let spawn: SpawnEntityEvent = serde_json::from_str(r#"{ "shape_type": "Cone" }"#).unwrap();
+    assert_eq!(spawn.shape_type, ShapeType::Cone);
+    assert_eq!(spawn.source, SpawnSource::Script);
+    let physics: PhysicsProps = serde_json::from_str("{}").unwrap();
+    assert_eq!(physics, PhysicsProps::default());
+    let world: WorldSettings = serde_json::from_str("{}").unwrap();
+    assert_eq!(world, WorldSettings::default());
+}
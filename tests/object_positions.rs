@@ -0,0 +1,254 @@
+// update_object_positions_system keeping the manager and its spatial index in
+// step with moving transforms, run headless without a window or renderer
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use navi::engine::editor::{TransformEditEvent, apply_transform_edits_system};
+use navi::engine::history::EditorHistory;
+use navi::engine::objects::{
+    GameObjectId, GameObjectManager, ShapeType, update_object_positions_system,
+};
+use navi::engine::snap::SnapSettings;
+use std::f32::consts::FRAC_PI_2;
+use std::time::{Duration, Instant};
+
+const MOVING_OBJECTS: usize = 10_000;
+// One frame at 60 Hz when optimized. Unoptimized test builds get more room,
+// the point there is catching a return to a scan per changed entity, which
+// takes seconds at this size
+const FRAME_BUDGET: Duration = if cfg!(debug_assertions) {
+    Duration::from_millis(250)
+} else {
+    Duration::from_micros(16_667)
+};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<GameObjectManager>()
+        .add_systems(Update, update_object_positions_system);
+    app
+}
+
+// Spawn objects on a line along x, registered like spawn_entity_system does
+fn spawn_objects(app: &mut App, count: usize) -> Vec<Entity> {
+    let world = app.world_mut();
+    let entities: Vec<Entity> = (0..count)
+        .map(|index| {
+            world
+                .spawn(Transform::from_xyz(index as f32, 0.0, 0.0))
+                .id()
+        })
+        .collect();
+    let mut game_manager = world.resource_mut::<GameObjectManager>();
+    let ids = game_manager.add_objects(
+        ShapeType::Cube,
+        entities
+            .iter()
+            .enumerate()
+            .map(|(index, &entity)| (entity, Vec3::new(index as f32, 0.0, 0.0), None)),
+        0.0,
+    );
+    assert_eq!(ids.len(), count);
+    let objects: Vec<(Entity, GameObjectId)> = entities
+        .iter()
+        .map(|&entity| {
+            let obj = game_manager.get_object_by_entity(entity).unwrap();
+            (entity, GameObjectId::from(obj))
+        })
+        .collect();
+    for (entity, id) in objects {
+        world.entity_mut(entity).insert(id);
+    }
+    entities
+}
+
+fn move_all(app: &mut App, entities: &[Entity], offset: Vec3) {
+    let world = app.world_mut();
+    for &entity in entities {
+        world.get_mut::<Transform>(entity).unwrap().translation += offset;
+    }
+}
+
+#[test]
+fn ten_thousand_moving_objects_fit_a_frame() {
+    let mut app = app();
+    let entities = spawn_objects(&mut app, MOVING_OBJECTS);
+    // The first update sees every transform as changed
+    app.update();
+
+    move_all(&mut app, &entities, Vec3::new(0.0, 5.0, 0.0));
+    let start = Instant::now();
+    app.update();
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < FRAME_BUDGET,
+        "moving {} objects took {:?}",
+        MOVING_OBJECTS,
+        elapsed
+    );
+
+    let game_manager = app.world().resource::<GameObjectManager>();
+    assert!(
+        game_manager
+            .iter()
+            .all(|obj| (obj.position().y - 5.0).abs() < 1e-5)
+    );
+    // Everything left y = 0 and the index knows it
+    assert!(
+        game_manager
+            .objects_in_aabb(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1e5, 1.0, 1.0))
+            .is_empty()
+    );
+    assert_eq!(
+        game_manager
+            .objects_in_aabb(Vec3::new(-1.0, 4.0, -1.0), Vec3::new(1e5, 6.0, 1.0))
+            .len(),
+        MOVING_OBJECTS
+    );
+}
+
+#[test]
+fn set_position_moves_the_index() {
+    let mut app = app();
+    let entities = spawn_objects(&mut app, 2);
+    let mut game_manager = app.world_mut().resource_mut::<GameObjectManager>();
+    game_manager.set_position(entities[0], Vec3::new(-30.0, 0.0, 0.0));
+    assert!(
+        game_manager
+            .objects_within_radius(Vec3::ZERO, 0.5)
+            .is_empty()
+    );
+    assert_eq!(
+        game_manager
+            .objects_within_radius(Vec3::new(-30.0, 0.0, 0.0), 0.5)
+            .len(),
+        1
+    );
+}
+
+fn ids_within(game_manager: &GameObjectManager, center: Vec3) -> Vec<u32> {
+    game_manager
+        .objects_within_radius(center, 0.5)
+        .iter()
+        .map(|obj| obj.id)
+        .collect()
+}
+
+// A child's transform is relative to its parent, the index holds where it
+// really is, and follows when only the parent moves
+#[test]
+fn children_are_indexed_in_world_space() {
+    let mut app = app();
+    let entities = spawn_objects(&mut app, 2);
+    let (parent, child) = (entities[0], entities[1]);
+    let world = app.world_mut();
+    *world.get_mut::<Transform>(parent).unwrap() =
+        Transform::from_xyz(10.0, 0.0, 0.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2));
+    world
+        .entity_mut(child)
+        .insert((Transform::from_xyz(2.0, 1.0, 0.0), ChildOf(parent)));
+    let mut game_manager = world.resource_mut::<GameObjectManager>();
+    let ids: Vec<u32> = entities
+        .iter()
+        .map(|&entity| game_manager.get_object_by_entity(entity).unwrap().id)
+        .collect();
+    game_manager.set_parent(ids[1], ids[0]).unwrap();
+    app.update();
+
+    // Turned a quarter around y, the parent's +x is world -z
+    let expected = Vec3::new(10.0, 1.0, -2.0);
+    let game_manager = app.world().resource::<GameObjectManager>();
+    let obj = game_manager.get_object_by_entity(child).unwrap();
+    assert!(
+        obj.position().distance(expected) < 1e-4,
+        "{}",
+        obj.position()
+    );
+    assert_eq!(ids_within(game_manager, expected), [ids[1]]);
+    assert!(
+        game_manager
+            .objects_within_radius(Vec3::new(2.0, 1.0, 0.0), 0.5)
+            .is_empty()
+    );
+
+    app.world_mut()
+        .get_mut::<Transform>(parent)
+        .unwrap()
+        .translation = Vec3::new(-10.0, 0.0, 0.0);
+    app.update();
+    let moved = Vec3::new(-10.0, 1.0, -2.0);
+    let game_manager = app.world().resource::<GameObjectManager>();
+    assert_eq!(ids_within(game_manager, moved), [ids[1]]);
+    assert!(
+        game_manager
+            .objects_in_aabb(expected - Vec3::ONE, expected + Vec3::ONE)
+            .is_empty()
+    );
+}
+
+// Ids the index finds within the radius against a scan of every position
+fn assert_index_matches_scan(game_manager: &GameObjectManager, center: Vec3, radius: f32) {
+    let mut indexed: Vec<u32> = game_manager
+        .objects_within_radius(center, radius)
+        .iter()
+        .map(|obj| obj.id)
+        .collect();
+    indexed.sort_unstable();
+    let scanned: Vec<u32> = game_manager
+        .iter()
+        .filter(|obj| obj.position().distance(center) <= radius)
+        .map(|obj| obj.id)
+        .collect();
+    assert_eq!(indexed, scanned, "radius {} around {}", radius, center);
+}
+
+// An inspector edit on a child sets its local transform, the manager and
+// its index still end up holding the world position
+#[test]
+fn inspector_edits_on_children_are_indexed_in_world_space() {
+    let mut app = app();
+    app.add_event::<TransformEditEvent>()
+        .init_resource::<EditorHistory>()
+        .init_resource::<SnapSettings>();
+    let entities = spawn_objects(&mut app, 3);
+    let (parent, child) = (entities[0], entities[1]);
+    let world = app.world_mut();
+    world.get_mut::<Transform>(parent).unwrap().translation = Vec3::new(10.0, 0.0, 0.0);
+    world
+        .entity_mut(child)
+        .insert((Transform::from_xyz(2.0, 1.0, 0.0), ChildOf(parent)));
+    let mut game_manager = world.resource_mut::<GameObjectManager>();
+    let parent_id = game_manager.get_object_by_entity(parent).unwrap().id;
+    let child_id = game_manager.get_object_by_entity(child).unwrap().id;
+    game_manager.set_parent(child_id, parent_id).unwrap();
+    app.update();
+
+    let local = Vec3::new(0.0, 3.0, 0.0);
+    let world = app.world_mut();
+    world.send_event(TransformEditEvent {
+        entity: child,
+        transform: Transform::from_translation(local),
+    });
+    // Run alone, the edit must not put the child at its local translation
+    world.run_system_once(apply_transform_edits_system).unwrap();
+    let game_manager = world.resource::<GameObjectManager>();
+    assert!(ids_within(game_manager, local).is_empty());
+    world
+        .run_system_once(update_object_positions_system)
+        .unwrap();
+
+    let expected = Vec3::new(10.0, 3.0, 0.0);
+    let game_manager = app.world().resource::<GameObjectManager>();
+    let obj = game_manager.get_object_by_entity(child).unwrap();
+    assert!(
+        obj.position().distance(expected) < 1e-4,
+        "{}",
+        obj.position()
+    );
+    assert_eq!(ids_within(game_manager, expected), [child_id]);
+    for center in [expected, Vec3::new(0.0, 3.0, 0.0), Vec3::ZERO] {
+        for radius in [0.5, 2.0, 12.0] {
+            assert_index_matches_scan(game_manager, center, radius);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Scene files of every format version the editor has written, kept in
+// tests/data/scenes as v<version>.ron. Each must keep loading as the format
+// moves on, and files from a later editor are refused untouched
+use bevy::prelude::*;
+use navi::engine::joints::JointKind;
+use navi::engine::migrate::{migrate_file, read_migrated};
+use navi::engine::objects::{BodyKind, MetaValue, ShapeType};
+use navi::engine::scene::{SCENE_FORMAT_VERSION, SceneFile, SceneFormat, SceneShape};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixture(version: u32) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/scenes")
+        .join(format!("v{}.ron", version))
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("navi-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// What every fixture holds, a door on a hinge with a weight on a spring
+fn assert_door_scene(scene: &SceneFile, version: u32) {
+    assert_eq!(scene.version, SCENE_FORMAT_VERSION, "v{}", version);
+    assert_eq!(scene.world.gravity, Vec3::new(0.0, -3.75, 0.0));
+    assert!(!scene.world.ground);
+
+    let ids: Vec<u32> = scene.objects.iter().map(|object| object.id).collect();
+    assert_eq!(ids, [0, 1, 4], "v{}", version);
+    let [frame, door, weight] = &scene.objects[..] else {
+        unreachable!()
+    };
+    assert_eq!(frame.physics.body, BodyKind::Fixed);
+    assert!(frame.locked);
+    assert_eq!(
+        frame.metadata.get("label"),
+        Some(&MetaValue::String("Front door".to_string()))
+    );
+    assert_eq!(door.parent, Some(0));
+    assert_eq!(door.prefab.as_deref(), Some("Door"));
+    assert_eq!(door.body_state.unwrap().angvel, Vec3::new(0.0, 1.5, 0.0));
+    assert_eq!(weight.shape_type, SceneShape::Known(ShapeType::Ball));
+    assert_eq!(weight.body_state, None);
+
+    let kinds: Vec<JointKind> = scene.joints.iter().map(|joint| joint.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            JointKind::Revolute {
+                pivot: Vec3::new(0.25, 0.0, 0.0),
+                axis: Vec3::Y,
+                limits: Some([0.0, 1.5]),
+            },
+            JointKind::Spring(default()),
+            JointKind::Fixed,
+        ],
+        "v{}",
+        version
+    );
+}
+
+#[test]
+fn every_format_version_still_loads() {
+    for version in 1..=SCENE_FORMAT_VERSION {
+        let path = fixture(version);
+        let scene =
+            SceneFile::read(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        assert_door_scene(&scene, version);
+    }
+}
+
+// Once a migration exists the fixtures are read as values first, hinges and
+// springs included
+#[test]
+fn every_format_version_reads_through_the_migration_path() {
+    for version in 1..=SCENE_FORMAT_VERSION {
+        let contents = fs::read(fixture(version)).unwrap();
+        let scene = read_migrated(SceneFormat::Ron, &contents, version)
+            .unwrap_or_else(|err| panic!("v{}: {}", version, err));
+        assert_door_scene(&scene, version);
+        assert_eq!(scene, SceneFile::read(&fixture(version)).unwrap());
+    }
+}
+
+#[test]
+fn migrating_the_current_version_leaves_the_file_alone() {
+    let dir = scratch_dir("migrate-current");
+    let path = dir.join("door.ron");
+    fs::copy(fixture(SCENE_FORMAT_VERSION), &path).unwrap();
+
+    let message = migrate_file(&path);
+    let unchanged = fs::read(&path).unwrap() == fs::read(fixture(SCENE_FORMAT_VERSION)).unwrap();
+    let backup = dir.join("door.ron.bak").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let expected = format!("is already format version {}", SCENE_FORMAT_VERSION);
+    assert!(message.unwrap().ends_with(&expected));
+    assert!(unchanged);
+    assert!(!backup);
+}
+
+#[test]
+fn files_from_a_newer_editor_are_refused_untouched() {
+    let newer = SCENE_FORMAT_VERSION + 1;
+    let source = fs::read_to_string(fixture(SCENE_FORMAT_VERSION)).unwrap();
+    let source = source.replacen(
+        &format!("version: {},", SCENE_FORMAT_VERSION),
+        &format!("version: {},\n    hinge_motors: [],", newer),
+        1,
+    );
+    let dir = scratch_dir("migrate-newer");
+    let path = dir.join("door.ron");
+    fs::write(&path, &source).unwrap();
+
+    let read = SceneFile::read(&path);
+    let migrated = migrate_file(&path);
+    let unchanged = fs::read_to_string(&path).unwrap() == source;
+    let backup = dir.join("door.ron.bak").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let expected = format!(
+        "scene is format version {}, it requires a newer Navi",
+        newer
+    );
+    assert!(read.unwrap_err().starts_with(&expected));
+    assert!(migrated.unwrap_err().starts_with(&expected));
+    assert!(unchanged);
+    assert!(!backup);
+}
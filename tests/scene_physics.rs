@@ -0,0 +1,179 @@
+// Scenes saved with their physics state pick the simulation up where it was:
+// saving mid-flight, loading and stepping on ends up where an uninterrupted
+// run does
+mod common;
+
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use common::headless_app;
+use navi::engine::objects::{GameObjectManager, ShapeType};
+use navi::engine::scene::{
+    BodyState, RestoreSceneEvent, SCENE_FORMAT_VERSION, SceneFile, SceneLoadMode, SceneObject,
+    SceneShape,
+};
+use navi::engine::world::WorldSettings;
+use std::collections::BTreeMap;
+
+const STEPS_BEFORE_SAVE: usize = 20;
+const STEPS_AFTER_SAVE: usize = 30;
+// A fixed step per update, so both runs simulate the same time
+const STEP: f32 = 1.0 / 60.0;
+
+fn physics_app() -> App {
+    let mut app = headless_app();
+    // Rapier's collider systems want the scene spawner even with no glTF
+    app.add_plugins((
+        TransformPlugin,
+        bevy::scene::ScenePlugin,
+        RapierPhysicsPlugin::<NoUserData>::default(),
+    ))
+    .insert_resource(TimestepMode::Fixed {
+        dt: STEP,
+        substeps: 1,
+    });
+    app
+}
+
+// Balls far enough apart never to touch, thrown in different directions
+fn thrown_balls() -> SceneFile {
+    let objects = (0..4)
+        .map(|id| {
+            let spin = id as f32;
+            SceneObject {
+                id,
+                name: format!("ball {}", id),
+                shape_type: SceneShape::Known(ShapeType::Ball),
+                dimensions: ShapeType::Ball.default_dimensions(),
+                translation: Vec3::new(id as f32 * 20.0, 10.0, 0.0),
+                rotation: Quat::IDENTITY,
+                scale: Vec3::ONE,
+                color: Color::WHITE,
+                physics: default(),
+                tags: default(),
+                parent: None,
+                locked: false,
+                physics_locked: false,
+                hidden: false,
+                collider_disabled: false,
+                prefab: None,
+                custom_mesh: None,
+                metadata: BTreeMap::new(),
+                body_state: Some(BodyState {
+                    linvel: Vec3::new(2.0 - spin, 6.0, spin * 1.5),
+                    angvel: Vec3::new(0.0, spin, 1.0),
+                    sleeping: false,
+                }),
+            }
+        })
+        .collect();
+    SceneFile {
+        version: SCENE_FORMAT_VERSION,
+        world: WorldSettings::default(),
+        objects,
+        joints: Vec::new(),
+    }
+}
+
+fn load(app: &mut App, scene: SceneFile) {
+    app.world_mut().send_event(RestoreSceneEvent {
+        source: "the test".to_string(),
+        scene,
+        mode: SceneLoadMode::Replace,
+    });
+    app.update();
+}
+
+fn step(app: &mut App, steps: usize) {
+    for _ in 0..steps {
+        app.update();
+    }
+}
+
+type SaveParams = (
+    Res<'static, GameObjectManager>,
+    Res<'static, WorldSettings>,
+    Query<'static, 'static, &'static Transform>,
+    Query<'static, 'static, (Option<&'static Velocity>, Option<&'static Sleeping>)>,
+);
+
+// What Save scene writes with "Include physics state" ticked
+fn save(app: &mut App) -> SceneFile {
+    let mut state: SystemState<SaveParams> = SystemState::new(app.world_mut());
+    let (game_manager, world, transforms, bodies) = state.get(app.world());
+    let mut scene = SceneFile::capture(&game_manager, &world, &transforms);
+    scene.capture_body_states(&game_manager, &bodies);
+    scene
+}
+
+fn positions(app: &mut App) -> Vec<(u32, Vec3)> {
+    let entities: Vec<(u32, Entity)> = app
+        .world()
+        .resource::<GameObjectManager>()
+        .iter()
+        .map(|obj| (obj.id, obj.entity()))
+        .collect();
+    entities
+        .into_iter()
+        .map(|(id, entity)| {
+            let transform = app.world().get::<Transform>(entity).unwrap();
+            (id, transform.translation)
+        })
+        .collect()
+}
+
+fn max_drift(a: &[(u32, Vec3)], b: &[(u32, Vec3)]) -> f32 {
+    assert_eq!(
+        a.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+        b.iter().map(|(id, _)| id).collect::<Vec<_>>()
+    );
+    a.iter()
+        .zip(b)
+        .map(|((_, a), (_, b))| a.distance(*b))
+        .fold(0.0, f32::max)
+}
+
+// The run that never stops, and the scene saved partway through it
+fn uninterrupted() -> (SceneFile, Vec<(u32, Vec3)>) {
+    let mut app = physics_app();
+    load(&mut app, thrown_balls());
+    step(&mut app, STEPS_BEFORE_SAVE);
+    let saved = save(&mut app);
+    step(&mut app, STEPS_AFTER_SAVE);
+    (saved, positions(&mut app))
+}
+
+#[test]
+fn saved_motion_resumes_on_load() {
+    let (saved, expected) = uninterrupted();
+    assert!(saved.objects.iter().all(|object| {
+        object
+            .body_state
+            .is_some_and(|state| state.linvel.length() > 0.1)
+    }));
+
+    // Through the file format too, the state has to survive being written
+    let text = ron::ser::to_string(&saved).unwrap();
+    let saved: SceneFile = ron::from_str(&text).unwrap();
+
+    let mut app = physics_app();
+    load(&mut app, saved);
+    step(&mut app, STEPS_AFTER_SAVE - 1);
+    let drift = max_drift(&positions(&mut app), &expected);
+    assert!(drift < 1e-3, "resumed run drifted {} m", drift);
+}
+
+#[test]
+fn without_physics_state_the_bodies_start_from_rest() {
+    let (mut saved, expected) = uninterrupted();
+    for object in &mut saved.objects {
+        object.body_state = None;
+    }
+
+    let mut app = physics_app();
+    load(&mut app, saved);
+    step(&mut app, STEPS_AFTER_SAVE - 1);
+    // Half a second of lost sideways speed adds up to well over a meter
+    let drift = max_drift(&positions(&mut app), &expected);
+    assert!(drift > 0.5, "only drifted {} m", drift);
+}